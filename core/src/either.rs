@@ -18,7 +18,7 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::muxing::StreamMuxerEvent;
+use crate::muxing::{MuxerCounters, StreamMuxerEvent, StreamPriority};
 use crate::{
     muxing::StreamMuxer,
     transport::{ListenerId, Transport, TransportError, TransportEvent},
@@ -27,7 +27,7 @@ use crate::{
 use either::Either;
 use futures::prelude::*;
 use pin_project::pin_project;
-use std::{pin::Pin, task::Context, task::Poll};
+use std::{pin::Pin, task::Context, task::Poll, time::Duration};
 
 impl<A, B> StreamMuxer for future::Either<A, B>
 where
@@ -69,6 +69,23 @@ where
         }
     }
 
+    fn poll_outbound_with_priority(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        priority: StreamPriority,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        match self.as_pin_mut() {
+            future::Either::Left(inner) => inner
+                .poll_outbound_with_priority(cx, priority)
+                .map_ok(future::Either::Left)
+                .map_err(Either::Left),
+            future::Either::Right(inner) => inner
+                .poll_outbound_with_priority(cx, priority)
+                .map_ok(future::Either::Right)
+                .map_err(Either::Right),
+        }
+    }
+
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         match self.as_pin_mut() {
             future::Either::Left(inner) => inner.poll_close(cx).map_err(Either::Left),
@@ -85,6 +102,20 @@ where
             future::Either::Right(inner) => inner.poll(cx).map_err(Either::Right),
         }
     }
+
+    fn preferred_write_size(&self) -> Option<usize> {
+        match self {
+            future::Either::Left(inner) => inner.preferred_write_size(),
+            future::Either::Right(inner) => inner.preferred_write_size(),
+        }
+    }
+
+    fn counters(&self) -> Option<MuxerCounters> {
+        match self {
+            future::Either::Left(inner) => inner.counters(),
+            future::Either::Right(inner) => inner.counters(),
+        }
+    }
 }
 
 /// Implements `Future` and dispatches all method calls to either `First` or `Second`.
@@ -154,6 +185,13 @@ where
         }
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        match self {
+            Either::Left(t) => t.remove_listener_graceful(id, timeout),
+            Either::Right(t) => t.remove_listener_graceful(id, timeout),
+        }
+    }
+
     fn listen_on(
         &mut self,
         id: ListenerId,
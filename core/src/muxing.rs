@@ -57,8 +57,10 @@ use std::pin::Pin;
 
 pub use self::boxed::StreamMuxerBox;
 pub use self::boxed::SubstreamBox;
+pub use self::stats::{MuxerCounters, StreamCounters, StreamMuxerStats, StreamStats};
 
 mod boxed;
+mod stats;
 
 /// Provides multiplexing for a connection by allowing users to open substreams.
 ///
@@ -88,6 +90,20 @@ pub trait StreamMuxer {
         cx: &mut Context<'_>,
     ) -> Poll<Result<Self::Substream, Self::Error>>;
 
+    /// Poll for a new, outbound substream whose data is sent with the given priority.
+    ///
+    /// Implementations may send the data of substreams with a higher priority ahead of the data
+    /// of other substreams of the connection. The default implementation ignores the hint and
+    /// calls [`StreamMuxer::poll_outbound`], which is equivalent to [`StreamPriority::Normal`].
+    fn poll_outbound_with_priority(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        priority: StreamPriority,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let _ = priority;
+        self.poll_outbound(cx)
+    }
+
     /// Poll to close this [`StreamMuxer`].
     ///
     /// After this has returned `Poll::Ready(Ok(()))`, the muxer has become useless and may be safely
@@ -109,6 +125,39 @@ pub trait StreamMuxer {
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<Result<StreamMuxerEvent, Self::Error>>;
+
+    /// The advisory size in bytes of a single write to a substream that avoids fragmentation on
+    /// the underlying connection, e.g. the maximum payload of a QUIC datagram.
+    ///
+    /// Protocols may use this to size their frames. Returns `None` if unknown, the default.
+    fn preferred_write_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// The counters of this muxer, e.g. the number of open streams and the bytes transferred
+    /// over each of them.
+    ///
+    /// Observers may keep the returned handle and take snapshots of the statistics via
+    /// [`MuxerCounters::stats`] while the muxer is in use. Returns `None` if the implementation
+    /// does not keep any counters, the default.
+    fn counters(&self) -> Option<MuxerCounters> {
+        None
+    }
+}
+
+/// A hint how urgently the data of a substream is to be sent, compared to the other substreams
+/// of its connection.
+///
+/// See [`StreamMuxer::poll_outbound_with_priority`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StreamPriority {
+    /// Bulk data that may be delayed in favour of the data of other substreams.
+    Low,
+    /// The priority of substreams opened without a hint.
+    #[default]
+    Normal,
+    /// Urgent data, e.g. of control protocols like ping, to be sent ahead of bulk data.
+    High,
 }
 
 /// An event produced by a [`StreamMuxer`].
@@ -142,6 +191,18 @@ pub trait StreamMuxerExt: StreamMuxer + Sized {
         Pin::new(self).poll_outbound(cx)
     }
 
+    /// Convenience function for calling [`StreamMuxer::poll_outbound_with_priority`] for [`StreamMuxer`]s that are `Unpin`.
+    fn poll_outbound_with_priority_unpin(
+        &mut self,
+        cx: &mut Context<'_>,
+        priority: StreamPriority,
+    ) -> Poll<Result<Self::Substream, Self::Error>>
+    where
+        Self: Unpin,
+    {
+        Pin::new(self).poll_outbound_with_priority(cx, priority)
+    }
+
     /// Convenience function for calling [`StreamMuxer::poll`] for [`StreamMuxer`]s that are `Unpin`.
     fn poll_unpin(&mut self, cx: &mut Context<'_>) -> Poll<Result<StreamMuxerEvent, Self::Error>>
     where
@@ -1,4 +1,4 @@
-use crate::muxing::{StreamMuxer, StreamMuxerEvent};
+use crate::muxing::{MuxerCounters, StreamMuxer, StreamMuxerEvent, StreamPriority};
 use futures::{AsyncRead, AsyncWrite};
 use pin_project::pin_project;
 use std::error::Error;
@@ -65,6 +65,18 @@ where
             .map_err(into_io_error)
     }
 
+    fn poll_outbound_with_priority(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        priority: StreamPriority,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        self.project()
+            .inner
+            .poll_outbound_with_priority(cx, priority)
+            .map_ok(SubstreamBox::new)
+            .map_err(into_io_error)
+    }
+
     #[inline]
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         self.project().inner.poll_close(cx).map_err(into_io_error)
@@ -76,6 +88,14 @@ where
     ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
         self.project().inner.poll(cx).map_err(into_io_error)
     }
+
+    fn preferred_write_size(&self) -> Option<usize> {
+        self.inner.preferred_write_size()
+    }
+
+    fn counters(&self) -> Option<MuxerCounters> {
+        self.inner.counters()
+    }
 }
 
 fn into_io_error<E>(err: E) -> io::Error
@@ -125,6 +145,14 @@ impl StreamMuxer for StreamMuxerBox {
         self.project().poll_outbound(cx)
     }
 
+    fn poll_outbound_with_priority(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        priority: StreamPriority,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        self.project().poll_outbound_with_priority(cx, priority)
+    }
+
     #[inline]
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         self.project().poll_close(cx)
@@ -136,6 +164,14 @@ impl StreamMuxer for StreamMuxerBox {
     ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
         self.project().poll(cx)
     }
+
+    fn preferred_write_size(&self) -> Option<usize> {
+        self.inner.preferred_write_size()
+    }
+
+    fn counters(&self) -> Option<MuxerCounters> {
+        self.inner.counters()
+    }
 }
 
 impl SubstreamBox {
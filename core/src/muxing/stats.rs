@@ -0,0 +1,203 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Counters that [`StreamMuxer`](super::StreamMuxer) implementations update to report the health
+//! of their connection, see [`StreamMuxer::counters`](super::StreamMuxer::counters).
+
+use parking_lot::Mutex;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Weak,
+};
+
+/// A snapshot of the statistics of a stream muxer, see [`MuxerCounters::stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamMuxerStats {
+    streams: Vec<StreamStats>,
+    stream_open_failures: u64,
+    window_stalls: u64,
+}
+
+impl StreamMuxerStats {
+    /// The number of currently open streams.
+    pub fn open_streams(&self) -> usize {
+        self.streams.len()
+    }
+
+    /// The statistics of each currently open stream, in the order they were opened.
+    pub fn streams(&self) -> &[StreamStats] {
+        &self.streams
+    }
+
+    /// The number of outbound streams that could not be opened.
+    pub fn stream_open_failures(&self) -> u64 {
+        self.stream_open_failures
+    }
+
+    /// The number of times a write to a stream had to wait for the remote to grant more
+    /// flow-control credit, e.g. because the send window of the stream was exhausted.
+    pub fn window_stalls(&self) -> u64 {
+        self.window_stalls
+    }
+}
+
+/// A snapshot of the statistics of a single stream, see [`StreamMuxerStats::streams`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamStats {
+    bytes_inbound: u64,
+    bytes_outbound: u64,
+}
+
+impl StreamStats {
+    /// The number of bytes read from the stream.
+    pub fn bytes_inbound(&self) -> u64 {
+        self.bytes_inbound
+    }
+
+    /// The number of bytes written to the stream.
+    pub fn bytes_outbound(&self) -> u64 {
+        self.bytes_outbound
+    }
+}
+
+/// The counters of a stream muxer, shared between the muxer and its observers.
+///
+/// Cloning it returns a handle to the same counters.
+#[derive(Debug, Clone, Default)]
+pub struct MuxerCounters {
+    inner: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    streams: Mutex<Vec<Weak<StreamShared>>>,
+    stream_open_failures: AtomicU64,
+    window_stalls: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct StreamShared {
+    bytes_inbound: AtomicU64,
+    bytes_outbound: AtomicU64,
+}
+
+impl MuxerCounters {
+    /// Returns the counters of a newly opened stream.
+    ///
+    /// The stream counts as open until the returned [`StreamCounters`] are dropped.
+    pub fn new_stream(&self) -> StreamCounters {
+        let stream = Arc::new(StreamShared::default());
+
+        let mut streams = self.inner.streams.lock();
+        streams.retain(|s| s.strong_count() > 0);
+        streams.push(Arc::downgrade(&stream));
+
+        StreamCounters {
+            stream,
+            muxer: self.inner.clone(),
+        }
+    }
+
+    /// Records that an outbound stream could not be opened.
+    pub fn record_open_failure(&self) {
+        self.inner
+            .stream_open_failures
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns a snapshot of the current statistics.
+    pub fn stats(&self) -> StreamMuxerStats {
+        let streams = self
+            .inner
+            .streams
+            .lock()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .map(|s| StreamStats {
+                bytes_inbound: s.bytes_inbound.load(Ordering::Relaxed),
+                bytes_outbound: s.bytes_outbound.load(Ordering::Relaxed),
+            })
+            .collect();
+
+        StreamMuxerStats {
+            streams,
+            stream_open_failures: self.inner.stream_open_failures.load(Ordering::Relaxed),
+            window_stalls: self.inner.window_stalls.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// The counters of a single stream, see [`MuxerCounters::new_stream`].
+#[derive(Debug)]
+pub struct StreamCounters {
+    stream: Arc<StreamShared>,
+    muxer: Arc<Shared>,
+}
+
+impl StreamCounters {
+    /// Records bytes read from the stream.
+    pub fn record_inbound(&self, num_bytes: usize) {
+        self.stream
+            .bytes_inbound
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records bytes written to the stream.
+    pub fn record_outbound(&self, num_bytes: usize) {
+        self.stream
+            .bytes_outbound
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+
+    /// Records that a write to the stream is waiting for flow-control credit.
+    pub fn record_window_stall(&self) {
+        self.muxer.window_stalls.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn streams_count_as_open_until_dropped() {
+        let counters = MuxerCounters::default();
+
+        let first = counters.new_stream();
+        let second = counters.new_stream();
+        first.record_inbound(10);
+        second.record_outbound(20);
+        second.record_window_stall();
+        counters.record_open_failure();
+
+        let stats = counters.stats();
+        assert_eq!(stats.open_streams(), 2);
+        assert_eq!(stats.streams()[0].bytes_inbound(), 10);
+        assert_eq!(stats.streams()[1].bytes_outbound(), 20);
+        assert_eq!(stats.window_stalls(), 1);
+        assert_eq!(stats.stream_open_failures(), 1);
+
+        drop(first);
+        let stats = counters.stats();
+        assert_eq!(stats.open_streams(), 1);
+        assert_eq!(stats.streams()[0].bytes_outbound(), 20);
+        assert_eq!(stats.window_stalls(), 1);
+    }
+}
@@ -33,15 +33,19 @@ use std::{
     pin::Pin,
     sync::atomic::{AtomicUsize, Ordering},
     task::{Context, Poll},
+    time::Duration,
 };
 
 pub mod and_then;
 pub mod choice;
 pub mod dummy;
 pub mod global_only;
+pub mod http_connect;
 pub mod map;
 pub mod map_err;
 pub mod memory;
+pub mod socks5;
+pub mod throttle;
 pub mod timeout;
 pub mod upgrade;
 
@@ -125,6 +129,23 @@ pub trait Transport {
     /// otherwise.
     fn remove_listener(&mut self, id: ListenerId) -> bool;
 
+    /// Remove a listener gracefully.
+    ///
+    /// Like [`Transport::remove_listener`], the listener stops accepting new connections
+    /// immediately and reports a final [`TransportEvent::ListenerClosed`]. In contrast,
+    /// connections that depend on resources of the listener, e.g. ones sharing its socket,
+    /// are given up to `timeout` to complete instead of being aborted.
+    ///
+    /// Transports that have nothing to drain can rely on the default implementation,
+    /// which falls back to [`Transport::remove_listener`].
+    ///
+    /// Return `true` if there was a listener with this Id, `false`
+    /// otherwise.
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        let _ = timeout;
+        self.remove_listener(id)
+    }
+
     /// Dials the given [`Multiaddr`], returning a future for a pending outbound connection.
     ///
     /// If [`TransportError::MultiaddrNotSupported`] is returned, it may be desirable to
@@ -235,6 +256,27 @@ pub trait Transport {
         and_then::AndThen::new(self, f)
     }
 
+    /// Limits the total ingress and egress bandwidth of the connections created
+    /// by the transport, in bytes per second.
+    ///
+    /// The limits are shared by all connections, see
+    /// [`throttle::Throttle::with_connection_limits`] to also limit single connections.
+    /// A rate of `u64::MAX` disables the limit in that direction.
+    ///
+    /// Apply it to the raw transport, before any protocol upgrades, to account for
+    /// the bytes on the wire.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a rate is zero.
+    fn throttle(self, ingress: u64, egress: u64) -> throttle::Throttle<Self>
+    where
+        Self: Sized,
+        Self::Output: AsyncRead + AsyncWrite,
+    {
+        throttle::Throttle::new(self, ingress, egress)
+    }
+
     /// Begins a series of protocol upgrades via an [`upgrade::Builder`].
     fn upgrade(self, version: upgrade::Version) -> upgrade::Builder<Self>
     where
@@ -566,3 +608,16 @@ where
         }
     }
 }
+
+/// What a transport does when the port requested in [`Transport::listen_on`] is already in use.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum PortConflictPolicy {
+    /// Fail the listener, returning the `AddrInUse` error from [`Transport::listen_on`].
+    #[default]
+    Fail,
+    /// Retry with a port assigned by the operating system.
+    ///
+    /// The listener reports the address it is actually bound to through the usual
+    /// [`TransportEvent::NewAddress`] events, like any other listener.
+    UseEphemeral,
+}
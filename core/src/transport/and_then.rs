@@ -25,7 +25,7 @@ use crate::{
 use either::Either;
 use futures::prelude::*;
 use multiaddr::Multiaddr;
-use std::{error, marker::PhantomPinned, pin::Pin, task::Context, task::Poll};
+use std::{error, marker::PhantomPinned, pin::Pin, task::Context, task::Poll, time::Duration};
 
 /// See the [`Transport::and_then`] method.
 #[pin_project::pin_project]
@@ -68,6 +68,10 @@ where
         self.transport.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.transport.remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         let dialed_fut = self
             .transport
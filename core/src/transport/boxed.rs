@@ -26,6 +26,7 @@ use std::{
     fmt, io,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Creates a new [`Boxed`] transport from the given transport.
@@ -58,6 +59,7 @@ trait Abstract<O> {
         addr: Multiaddr,
     ) -> Result<(), TransportError<io::Error>>;
     fn remove_listener(&mut self, id: ListenerId) -> bool;
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool;
     fn dial(&mut self, addr: Multiaddr) -> Result<Dial<O>, TransportError<io::Error>>;
     fn dial_as_listener(&mut self, addr: Multiaddr) -> Result<Dial<O>, TransportError<io::Error>>;
     fn address_translation(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr>;
@@ -86,6 +88,10 @@ where
         Transport::remove_listener(self, id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        Transport::remove_listener_graceful(self, id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Dial<O>, TransportError<io::Error>> {
         let fut = Transport::dial(self, addr)
             .map(|r| r.map_err(box_err))
@@ -143,6 +149,10 @@ impl<O> Transport for Boxed<O> {
         self.inner.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.inner.remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         self.inner.dial(addr)
     }
@@ -23,7 +23,7 @@ use crate::transport::{ListenerId, Transport, TransportError, TransportEvent};
 use either::Either;
 use futures::future;
 use multiaddr::Multiaddr;
-use std::{pin::Pin, task::Context, task::Poll};
+use std::{pin::Pin, task::Context, task::Poll, time::Duration};
 
 /// Struct returned by `or_transport()`.
 #[derive(Debug, Copy, Clone)]
@@ -92,6 +92,10 @@ where
         self.0.remove_listener(id) || self.1.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.0.remove_listener_graceful(id, timeout) || self.1.remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         tracing::trace!(
             address=%addr,
@@ -25,6 +25,7 @@ use crate::{
 use std::{
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// Dropping all dial requests to non-global IP addresses.
@@ -287,6 +288,10 @@ impl<T: crate::Transport + Unpin> crate::Transport for Transport<T> {
         self.inner.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.inner.remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         match addr.iter().next() {
             Some(Protocol::Ip4(a)) => {
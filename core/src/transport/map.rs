@@ -24,7 +24,7 @@ use crate::{
 };
 use futures::prelude::*;
 use multiaddr::Multiaddr;
-use std::{pin::Pin, task::Context, task::Poll};
+use std::{pin::Pin, task::Context, task::Poll, time::Duration};
 
 use super::ListenerId;
 
@@ -73,6 +73,10 @@ where
         self.transport.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.transport.remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         let future = self.transport.dial(addr.clone())?;
         let p = ConnectedPoint::Dialer {
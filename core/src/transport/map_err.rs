@@ -21,7 +21,7 @@
 use crate::transport::{ListenerId, Transport, TransportError, TransportEvent};
 use futures::prelude::*;
 use multiaddr::Multiaddr;
-use std::{error, pin::Pin, task::Context, task::Poll};
+use std::{error, pin::Pin, task::Context, task::Poll, time::Duration};
 
 /// See `Transport::map_err`.
 #[derive(Debug, Copy, Clone)]
@@ -65,6 +65,10 @@ where
         self.transport.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.transport.remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         let map = self.map.clone();
         match self.transport.dial(addr) {
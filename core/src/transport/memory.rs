@@ -21,28 +21,35 @@
 use crate::transport::{ListenerId, Transport, TransportError, TransportEvent};
 use fnv::FnvHashMap;
 use futures::{channel::mpsc, future::Ready, prelude::*, task::Context, task::Poll};
+use futures_timer::Delay;
+use instant::Instant;
 use multiaddr::{Multiaddr, Protocol};
 use once_cell::sync::Lazy;
 use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rw_stream_sink::RwStreamSink;
 use std::{
     collections::{hash_map::Entry, VecDeque},
     error, fmt, io,
     num::NonZeroU64,
     pin::Pin,
+    time::Duration,
 };
 
+/// Lower bound of the delay of a dropped chunk, see [`LinkConditions::with_loss`].
+const MIN_RETRANSMISSION_TIMEOUT: Duration = Duration::from_millis(10);
+
 static HUB: Lazy<Hub> = Lazy::new(|| Hub(Mutex::new(FnvHashMap::default())));
 
 struct Hub(Mutex<FnvHashMap<NonZeroU64, ChannelSender>>);
 
-/// A [`mpsc::Sender`] enabling a [`DialFuture`] to send a [`Channel`] and the
+/// A [`mpsc::Sender`] enabling a [`DialFuture`] to send a [`Chan`] and the
 /// port of the dialer to a [`Listener`].
-type ChannelSender = mpsc::Sender<(Channel<Vec<u8>>, NonZeroU64)>;
+type ChannelSender = mpsc::Sender<(Chan<Vec<u8>>, NonZeroU64)>;
 
-/// A [`mpsc::Receiver`] enabling a [`Listener`] to receive a [`Channel`] and
+/// A [`mpsc::Receiver`] enabling a [`Listener`] to receive a [`Chan`] and
 /// the port of the dialer from a [`DialFuture`].
-type ChannelReceiver = mpsc::Receiver<(Channel<Vec<u8>>, NonZeroU64)>;
+type ChannelReceiver = mpsc::Receiver<(Chan<Vec<u8>>, NonZeroU64)>;
 
 impl Hub {
     /// Registers the given port on the hub.
@@ -87,12 +94,151 @@ impl Hub {
 #[derive(Default)]
 pub struct MemoryTransport {
     listeners: VecDeque<Pin<Box<Listener>>>,
+    /// Conditions applied to the data sent on connections of this transport.
+    link_conditions: Option<LinkConditions>,
 }
 
 impl MemoryTransport {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// Simulates the given network conditions on the data sent on connections of this
+    /// transport, both dialed and accepted ones.
+    ///
+    /// The conditions of a link are thus set by the transports of both of its ends, one for
+    /// each direction.
+    pub fn with_link_conditions(mut self, conditions: LinkConditions) -> Self {
+        self.link_conditions = Some(conditions);
+        self
+    }
+}
+
+/// Network conditions simulated by a [`MemoryTransport`], see
+/// [`MemoryTransport::with_link_conditions`].
+///
+/// Every chunk written to a connection is delayed by the latency, a random jitter and the
+/// time needed to send it with the bandwidth. Chunks are never reordered.
+#[derive(Debug, Clone, Default)]
+pub struct LinkConditions {
+    latency: Duration,
+    jitter: Duration,
+    bandwidth: Option<u64>,
+    loss: f64,
+    seed: Option<u64>,
+}
+
+impl LinkConditions {
+    /// Conditions of an ideal link, delivering data without any delay.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delays every chunk by `latency`.
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Varies the latency of every chunk by up to `jitter` in either direction.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Limits the bandwidth to `bytes_per_second`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bytes_per_second` is zero.
+    pub fn with_bandwidth(mut self, bytes_per_second: u64) -> Self {
+        assert!(bytes_per_second > 0, "bandwidth must be greater than zero");
+        self.bandwidth = Some(bytes_per_second);
+        self
+    }
+
+    /// Drops every chunk with the given `probability`.
+    ///
+    /// Connections of the memory transport are reliable, thus a dropped chunk is retransmitted
+    /// after twice the latency, but at least 10ms, and may be dropped again. As chunks are
+    /// never reordered, all chunks after it are delayed as well.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `probability` is not within `0.0..1.0`.
+    pub fn with_loss(mut self, probability: f64) -> Self {
+        assert!(
+            (0.0..1.0).contains(&probability),
+            "loss probability must be within 0.0..1.0"
+        );
+        self.loss = probability;
+        self
+    }
+
+    /// Seeds the random number generator used for the jitter and loss of each connection,
+    /// making them reproducible.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    fn shaper<T: AsRef<[u8]>>(&self) -> Shaper<T> {
+        Shaper {
+            rng: match self.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            conditions: self.clone(),
+            next_departure: Instant::now(),
+            last_arrival: Instant::now(),
+            chunk_len: |chunk| chunk.as_ref().len(),
+        }
+    }
+}
+
+/// Computes the arrival time of the chunks sent on a [`Chan`] according to
+/// [`LinkConditions`].
+struct Shaper<T> {
+    conditions: LinkConditions,
+    rng: StdRng,
+    /// When the link is free to send the next chunk.
+    next_departure: Instant,
+    /// Arrival of the last chunk, which the next one must not overtake.
+    last_arrival: Instant,
+    /// Length of a chunk in bytes.
+    chunk_len: fn(&T) -> usize,
+}
+
+impl<T> Shaper<T> {
+    /// Computes the arrival time of a chunk of `len` bytes sent at `now`.
+    fn arrival(&mut self, len: usize, now: Instant) -> Instant {
+        let LinkConditions {
+            latency,
+            jitter,
+            bandwidth,
+            loss,
+            seed: _,
+        } = self.conditions;
+
+        let mut departure = self.next_departure.max(now);
+        if let Some(bandwidth) = bandwidth {
+            departure += Duration::from_secs_f64(len as f64 / bandwidth as f64);
+        }
+        self.next_departure = departure;
+
+        let mut delay = latency;
+        if !jitter.is_zero() {
+            let offset = self.rng.gen_range(Duration::ZERO..=jitter * 2);
+            delay = (delay + offset).saturating_sub(jitter);
+        }
+        let retransmission_timeout = (latency * 2).max(MIN_RETRANSMISSION_TIMEOUT);
+        while loss > 0.0 && self.rng.gen_bool(loss) {
+            delay += retransmission_timeout;
+        }
+
+        self.last_arrival = (departure + delay).max(self.last_arrival);
+        self.last_arrival
+    }
 }
 
 /// Connection to a `MemoryTransport` currently being opened.
@@ -105,12 +251,12 @@ pub struct DialFuture {
     /// is not at the same time a listen address (analogous to TCP).
     dial_port: NonZeroU64,
     sender: ChannelSender,
-    channel_to_send: Option<Channel<Vec<u8>>>,
+    channel_to_send: Option<Chan<Vec<u8>>>,
     channel_to_return: Option<Channel<Vec<u8>>>,
 }
 
 impl DialFuture {
-    fn new(port: NonZeroU64) -> Option<Self> {
+    fn new(port: NonZeroU64, link_conditions: Option<&LinkConditions>) -> Option<Self> {
         let sender = HUB.get(&port)?;
 
         let (_dial_port_channel, dial_port) = HUB
@@ -122,15 +268,19 @@ impl DialFuture {
         Some(DialFuture {
             dial_port,
             sender,
-            channel_to_send: Some(RwStreamSink::new(Chan {
+            channel_to_send: Some(Chan {
                 incoming: a_rx,
                 outgoing: b_tx,
                 dial_port: None,
-            })),
+                shaper: None,
+                delayed: None,
+            }),
             channel_to_return: Some(RwStreamSink::new(Chan {
                 incoming: b_rx,
                 outgoing: a_tx,
                 dial_port: Some(dial_port),
+                shaper: link_conditions.map(LinkConditions::shaper),
+                delayed: None,
             })),
         })
     }
@@ -219,7 +369,8 @@ impl Transport for MemoryTransport {
             return Err(TransportError::MultiaddrNotSupported(addr));
         };
 
-        DialFuture::new(port).ok_or(TransportError::Other(MemoryTransportError::Unreachable))
+        DialFuture::new(port, self.link_conditions.as_ref())
+            .ok_or(TransportError::Other(MemoryTransportError::Unreachable))
     }
 
     fn dial_as_listener(
@@ -255,9 +406,12 @@ impl Transport for MemoryTransport {
 
             let event = match Stream::poll_next(Pin::new(&mut listener.receiver), cx) {
                 Poll::Pending => None,
-                Poll::Ready(Some((channel, dial_port))) => Some(TransportEvent::Incoming {
+                Poll::Ready(Some((mut channel, dial_port))) => Some(TransportEvent::Incoming {
                     listener_id: listener.id,
-                    upgrade: future::ready(Ok(channel)),
+                    upgrade: future::ready(Ok({
+                        channel.shaper = self.link_conditions.as_ref().map(LinkConditions::shaper);
+                        RwStreamSink::new(channel)
+                    })),
                     local_addr: listener.addr.clone(),
                     send_back_addr: Protocol::Memory(dial_port.get()).into(),
                 }),
@@ -338,8 +492,9 @@ pub type Channel<T> = RwStreamSink<Chan<T>>;
 ///
 /// Implements `Sink` and `Stream`.
 pub struct Chan<T = Vec<u8>> {
-    incoming: mpsc::Receiver<T>,
-    outgoing: mpsc::Sender<T>,
+    /// Chunks sent by the remote with their arrival time, if delayed.
+    incoming: mpsc::Receiver<(T, Option<Instant>)>,
+    outgoing: mpsc::Sender<(T, Option<Instant>)>,
 
     // Needed in [`Drop`] implementation of [`Chan`] to unregister the dialing
     // port with the global [`HUB`]. Is [`Some`] when [`Chan`] of dialer and
@@ -348,6 +503,11 @@ pub struct Chan<T = Vec<u8>> {
     // Note: Listening port is unregistered in [`Drop`] implementation of
     // [`Listener`].
     dial_port: Option<NonZeroU64>,
+
+    /// Delays the chunks sent on this channel, if link conditions are simulated.
+    shaper: Option<Shaper<T>>,
+    /// Received chunk waiting for its arrival time.
+    delayed: Option<(T, Delay)>,
 }
 
 impl<T> Unpin for Chan<T> {}
@@ -356,10 +516,26 @@ impl<T> Stream for Chan<T> {
     type Item = Result<T, io::Error>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        match Stream::poll_next(Pin::new(&mut self.incoming), cx) {
-            Poll::Pending => Poll::Pending,
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Ready(Some(v)) => Poll::Ready(Some(Ok(v))),
+        loop {
+            if let Some((_, delay)) = self.delayed.as_mut() {
+                futures::ready!(delay.poll_unpin(cx));
+                let (v, _) = self.delayed.take().expect("checked above");
+                return Poll::Ready(Some(Ok(v)));
+            }
+
+            match Stream::poll_next(Pin::new(&mut self.incoming), cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Ready(Some((v, arrival))) => {
+                    let now = Instant::now();
+                    match arrival {
+                        Some(arrival) if arrival > now => {
+                            self.delayed = Some((v, Delay::new(arrival - now)));
+                        }
+                        _ => return Poll::Ready(Some(Ok(v))),
+                    }
+                }
+            }
         }
     }
 }
@@ -374,8 +550,12 @@ impl<T> Sink<T> for Chan<T> {
     }
 
     fn start_send(mut self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        let arrival = self.shaper.as_mut().map(|shaper| {
+            let len = (shaper.chunk_len)(&item);
+            shaper.arrival(len, Instant::now())
+        });
         self.outgoing
-            .start_send(item)
+            .start_send((item, arrival))
             .map_err(|_| io::ErrorKind::BrokenPipe.into())
     }
 
@@ -574,6 +754,98 @@ mod tests {
         futures::executor::block_on(futures::future::join(listener, dialer));
     }
 
+    #[test]
+    fn link_conditions_delay_data() {
+        let msg = [0; 100];
+
+        let rand_port = rand::random::<u64>().saturating_add(1);
+        let t1_addr: Multiaddr = format!("/memory/{rand_port}").parse().unwrap();
+
+        let mut t1 = MemoryTransport::default().boxed();
+        let mut t2 = MemoryTransport::default()
+            .with_link_conditions(LinkConditions::new().with_latency(Duration::from_secs(60)));
+
+        futures::executor::block_on(async move {
+            t1.listen_on(ListenerId::next(), t1_addr.clone()).unwrap();
+            let mut dialer = t2.dial(t1_addr).unwrap().await.unwrap();
+            let upgrade = loop {
+                let event = t1.select_next_some().await;
+                if let Some(upgrade) = event.into_incoming() {
+                    break upgrade;
+                }
+            };
+            let mut listener = upgrade.0.await.unwrap();
+
+            dialer.write_all(&msg).await.unwrap();
+
+            let mut buf = [1; 100];
+            let mut read = listener.read_exact(&mut buf);
+            assert!(futures::poll!(&mut read).is_pending());
+        });
+    }
+
+    #[test]
+    fn link_conditions_without_delay_deliver_data_immediately() {
+        let msg = [0; 100];
+
+        let rand_port = rand::random::<u64>().saturating_add(1);
+        let t1_addr: Multiaddr = format!("/memory/{rand_port}").parse().unwrap();
+
+        let mut t1 = MemoryTransport::default().boxed();
+        let mut t2 = MemoryTransport::default().with_link_conditions(LinkConditions::new());
+
+        futures::executor::block_on(async move {
+            t1.listen_on(ListenerId::next(), t1_addr.clone()).unwrap();
+            let mut dialer = t2.dial(t1_addr).unwrap().await.unwrap();
+            let upgrade = loop {
+                let event = t1.select_next_some().await;
+                if let Some(upgrade) = event.into_incoming() {
+                    break upgrade;
+                }
+            };
+            let mut listener = upgrade.0.await.unwrap();
+
+            dialer.write_all(&msg).await.unwrap();
+
+            let mut buf = [1; 100];
+            let mut read = listener.read_exact(&mut buf);
+            assert!(futures::poll!(&mut read).is_ready());
+            assert_eq!(buf, msg);
+        });
+    }
+
+    #[test]
+    fn link_conditions_add_latency_and_transmission_time() {
+        let mut shaper = LinkConditions::new()
+            .with_latency(Duration::from_millis(50))
+            .with_bandwidth(1000)
+            .shaper::<Vec<u8>>();
+        let now = Instant::now();
+
+        // 100 bytes take 100ms to be sent at 1000 bytes per second.
+        assert_eq!(shaper.arrival(100, now), now + Duration::from_millis(150));
+        // The next chunk has to wait for the previous one to be sent.
+        assert_eq!(shaper.arrival(100, now), now + Duration::from_millis(250));
+    }
+
+    #[test]
+    fn link_conditions_never_reorder_chunks() {
+        let mut shaper = LinkConditions::new()
+            .with_latency(Duration::from_millis(10))
+            .with_jitter(Duration::from_millis(10))
+            .with_loss(0.5)
+            .with_seed(1)
+            .shaper::<Vec<u8>>();
+        let now = Instant::now();
+
+        let mut last_arrival = now;
+        for _ in 0..100 {
+            let arrival = shaper.arrival(10, now);
+            assert!(arrival >= last_arrival);
+            last_arrival = arrival;
+        }
+    }
+
     #[test]
     fn dialer_address_unequal_to_listener_address() {
         let listener_addr: Multiaddr =
@@ -20,7 +20,7 @@
 
 use crate::transport::{ListenerId, Transport, TransportError, TransportEvent};
 use multiaddr::Multiaddr;
-use std::{pin::Pin, task::Context, task::Poll};
+use std::{pin::Pin, task::Context, task::Poll, time::Duration};
 
 /// Transport that is possibly disabled.
 ///
@@ -80,6 +80,14 @@ where
         }
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        if let Some(inner) = self.0.as_mut() {
+            inner.remove_listener_graceful(id, timeout)
+        } else {
+            false
+        }
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         if let Some(inner) = self.0.as_mut() {
             inner.dial(addr)
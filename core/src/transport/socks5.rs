@@ -0,0 +1,466 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Dialing through a SOCKS5 proxy.
+//!
+//! [`Socks5Transport`] wraps a transport that can reach the proxy, e.g. TCP, and tunnels all
+//! outgoing connections through the proxy as described in RFC 1928, optionally authenticating
+//! with a username and password (RFC 1929). `/dns`, `/dns4` and `/dns6` addresses are resolved
+//! by the proxy, so the transport must not be wrapped in a DNS transport when the local resolver
+//! is to be bypassed, e.g. when using Tor.
+//!
+//! **Note**: Listening is delegated to the inner transport and not proxied.
+
+use crate::{
+    multiaddr::Protocol,
+    transport::{ListenerId, TransportError, TransportEvent},
+    Multiaddr, Transport,
+};
+use futures::{future::BoxFuture, prelude::*};
+use std::{
+    error, fmt, io,
+    net::{Ipv4Addr, Ipv6Addr},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+const VERSION: u8 = 0x05;
+const METHOD_NO_AUTH: u8 = 0x00;
+const METHOD_USERNAME_PASSWORD: u8 = 0x02;
+const METHOD_NONE_ACCEPTABLE: u8 = 0xff;
+const AUTH_VERSION: u8 = 0x01;
+const COMMAND_CONNECT: u8 = 0x01;
+const ADDRESS_IPV4: u8 = 0x01;
+const ADDRESS_DOMAIN: u8 = 0x03;
+const ADDRESS_IPV6: u8 = 0x04;
+
+/// Configuration of a [`Socks5Transport`].
+#[derive(Debug, Clone)]
+pub struct Config {
+    proxy: Multiaddr,
+    auth: Option<(String, String)>,
+}
+
+impl Config {
+    /// Dials through the proxy at the given address of the inner transport.
+    pub fn new(proxy: Multiaddr) -> Self {
+        Self { proxy, auth: None }
+    }
+
+    /// Authenticates with the proxy using a username and password.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the username or password is longer than 255 bytes.
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        let (username, password) = (username.into(), password.into());
+        assert!(
+            username.len() <= 255 && password.len() <= 255,
+            "SOCKS5 credentials must not exceed 255 bytes"
+        );
+        self.auth = Some((username, password));
+        self
+    }
+}
+
+/// A [`Transport`] that dials through a SOCKS5 proxy reached via the inner transport.
+#[derive(Debug, Clone)]
+#[pin_project::pin_project]
+pub struct Socks5Transport<T> {
+    #[pin]
+    inner: T,
+    config: Config,
+}
+
+impl<T> Socks5Transport<T> {
+    /// Wraps around a `Transport` to dial all connections through the configured proxy.
+    pub fn new(inner: T, config: Config) -> Self {
+        Socks5Transport { inner, config }
+    }
+}
+
+impl<T> Transport for Socks5Transport<T>
+where
+    T: Transport,
+    T::Output: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    T::Dial: Send + 'static,
+    T::Error: Send + 'static,
+{
+    type Output = T::Output;
+    type Error = Socks5Error<T::Error>;
+    type ListenerUpgrade = future::MapErr<T::ListenerUpgrade, fn(T::Error) -> Self::Error>;
+    type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+    fn listen_on(
+        &mut self,
+        id: ListenerId,
+        addr: Multiaddr,
+    ) -> Result<(), TransportError<Self::Error>> {
+        self.inner
+            .listen_on(id, addr)
+            .map_err(|err| err.map(Socks5Error::Transport))
+    }
+
+    fn remove_listener(&mut self, id: ListenerId) -> bool {
+        self.inner.remove_listener(id)
+    }
+
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.inner.remove_listener_graceful(id, timeout)
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let Some(target) = Target::from_multiaddr(&addr) else {
+            return Err(TransportError::MultiaddrNotSupported(addr));
+        };
+        let dial = self
+            .inner
+            .dial(self.config.proxy.clone())
+            .map_err(|err| err.map(Socks5Error::Transport))?;
+        let auth = self.config.auth.clone();
+
+        Ok(async move {
+            let mut stream = dial.await.map_err(Socks5Error::Transport)?;
+            handshake(&mut stream, &target, auth.as_ref()).await?;
+            Ok(stream)
+        }
+        .boxed())
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        // The proxy makes a regular outgoing connection either way.
+        self.dial(addr)
+    }
+
+    fn address_translation(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.address_translation(server, observed)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        self.project().inner.poll(cx).map(|event| {
+            event
+                .map_upgrade(|upgrade| upgrade.map_err(Socks5Error::Transport as fn(_) -> _))
+                .map_err(Socks5Error::Transport)
+        })
+    }
+}
+
+/// The host and port to connect to through the proxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Target {
+    Ipv4(Ipv4Addr, u16),
+    Ipv6(Ipv6Addr, u16),
+    Domain(String, u16),
+}
+
+impl Target {
+    /// Parses addresses of the form `/<ip4|ip6|dns|dns4|dns6>/<host>/tcp/<port>[/p2p/<peer>]`.
+    pub(crate) fn from_multiaddr(addr: &Multiaddr) -> Option<Self> {
+        let mut iter = addr.iter();
+        let host = iter.next()?;
+        let Protocol::Tcp(port) = iter.next()? else {
+            return None;
+        };
+        match iter.next() {
+            None | Some(Protocol::P2p(_)) => {}
+            Some(_) => return None,
+        }
+
+        match host {
+            Protocol::Ip4(ip) => Some(Target::Ipv4(ip, port)),
+            Protocol::Ip6(ip) => Some(Target::Ipv6(ip, port)),
+            Protocol::Dns(name) | Protocol::Dns4(name) | Protocol::Dns6(name)
+                if name.len() <= 255 =>
+            {
+                Some(Target::Domain(name.into_owned(), port))
+            }
+            _ => None,
+        }
+    }
+
+    /// Encodes the target as the address and port of a SOCKS5 request.
+    fn encode(&self, buf: &mut Vec<u8>) {
+        let port = match self {
+            Target::Ipv4(ip, port) => {
+                buf.push(ADDRESS_IPV4);
+                buf.extend_from_slice(&ip.octets());
+                port
+            }
+            Target::Ipv6(ip, port) => {
+                buf.push(ADDRESS_IPV6);
+                buf.extend_from_slice(&ip.octets());
+                port
+            }
+            Target::Domain(name, port) => {
+                buf.push(ADDRESS_DOMAIN);
+                buf.push(name.len() as u8);
+                buf.extend_from_slice(name.as_bytes());
+                port
+            }
+        };
+        buf.extend_from_slice(&port.to_be_bytes());
+    }
+}
+
+/// Formats the target as `host:port`, enclosing IPv6 addresses in brackets.
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Target::Ipv4(ip, port) => write!(f, "{ip}:{port}"),
+            Target::Ipv6(ip, port) => write!(f, "[{ip}]:{port}"),
+            Target::Domain(name, port) => write!(f, "{name}:{port}"),
+        }
+    }
+}
+
+/// Performs the SOCKS5 handshake on a connection to the proxy, asking it to connect to `target`.
+async fn handshake<S, TErr>(
+    stream: &mut S,
+    target: &Target,
+    auth: Option<&(String, String)>,
+) -> Result<(), Socks5Error<TErr>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // Method negotiation.
+    let greeting: &[u8] = if auth.is_some() {
+        &[VERSION, 2, METHOD_NO_AUTH, METHOD_USERNAME_PASSWORD]
+    } else {
+        &[VERSION, 1, METHOD_NO_AUTH]
+    };
+    stream.write_all(greeting).await?;
+    stream.flush().await?;
+
+    let mut reply = [0; 2];
+    stream.read_exact(&mut reply).await?;
+    match (reply, auth) {
+        ([VERSION, METHOD_NO_AUTH], _) => {}
+        ([VERSION, METHOD_USERNAME_PASSWORD], Some((username, password))) => {
+            let mut request = vec![AUTH_VERSION, username.len() as u8];
+            request.extend_from_slice(username.as_bytes());
+            request.push(password.len() as u8);
+            request.extend_from_slice(password.as_bytes());
+            stream.write_all(&request).await?;
+            stream.flush().await?;
+
+            let mut reply = [0; 2];
+            stream.read_exact(&mut reply).await?;
+            if reply != [AUTH_VERSION, 0x00] {
+                return Err(Socks5Error::AuthenticationFailed);
+            }
+        }
+        ([VERSION, METHOD_NONE_ACCEPTABLE], _) => return Err(Socks5Error::NoAcceptableAuthMethod),
+        _ => return Err(Socks5Error::InvalidReply),
+    }
+
+    // Connect request.
+    let mut request = vec![VERSION, COMMAND_CONNECT, 0x00];
+    target.encode(&mut request);
+    stream.write_all(&request).await?;
+    stream.flush().await?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await?;
+    if reply[0] != VERSION {
+        return Err(Socks5Error::InvalidReply);
+    }
+    if reply[1] != 0x00 {
+        return Err(Socks5Error::ConnectFailed(reply[1]));
+    }
+
+    // Skip the address the proxy bound for the connection.
+    let address_len = match reply[3] {
+        ADDRESS_IPV4 => 4,
+        ADDRESS_IPV6 => 16,
+        ADDRESS_DOMAIN => {
+            let mut len = [0; 1];
+            stream.read_exact(&mut len).await?;
+            len[0] as usize
+        }
+        _ => return Err(Socks5Error::InvalidReply),
+    };
+    let mut bound_address = vec![0; address_len + 2];
+    stream.read_exact(&mut bound_address).await?;
+
+    Ok(())
+}
+
+/// Error that can be produced by the [`Socks5Transport`].
+#[derive(Debug)]
+pub enum Socks5Error<TErr> {
+    /// An error of the inner transport while connecting to the proxy.
+    Transport(TErr),
+    /// An I/O error during the handshake with the proxy.
+    Io(io::Error),
+    /// The proxy sent an unexpected reply.
+    InvalidReply,
+    /// The proxy accepts none of the offered authentication methods.
+    NoAcceptableAuthMethod,
+    /// The proxy rejected the username and password.
+    AuthenticationFailed,
+    /// The proxy failed to connect to the target, with the reply code of RFC 1928.
+    ConnectFailed(u8),
+}
+
+impl<TErr> From<io::Error> for Socks5Error<TErr> {
+    fn from(err: io::Error) -> Self {
+        Socks5Error::Io(err)
+    }
+}
+
+impl<TErr> fmt::Display for Socks5Error<TErr>
+where
+    TErr: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Socks5Error::Transport(err) => write!(f, "{err}"),
+            Socks5Error::Io(err) => write!(f, "SOCKS5 handshake failed: {err}"),
+            Socks5Error::InvalidReply => write!(f, "Invalid reply from SOCKS5 proxy"),
+            Socks5Error::NoAcceptableAuthMethod => {
+                write!(
+                    f,
+                    "SOCKS5 proxy accepts none of the offered authentication methods"
+                )
+            }
+            Socks5Error::AuthenticationFailed => write!(f, "SOCKS5 authentication failed"),
+            Socks5Error::ConnectFailed(code) => {
+                let reason = match code {
+                    0x01 => "general failure",
+                    0x02 => "connection not allowed by ruleset",
+                    0x03 => "network unreachable",
+                    0x04 => "host unreachable",
+                    0x05 => "connection refused",
+                    0x06 => "TTL expired",
+                    0x07 => "command not supported",
+                    0x08 => "address type not supported",
+                    _ => "unknown error",
+                };
+                write!(f, "SOCKS5 proxy failed to connect: {reason} ({code:#04x})")
+            }
+        }
+    }
+}
+
+impl<TErr> error::Error for Socks5Error<TErr>
+where
+    TErr: error::Error + 'static,
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Socks5Error::Transport(err) => Some(err),
+            Socks5Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::MemoryTransport;
+
+    #[test]
+    fn parses_targets() {
+        let target = |addr: &str| Target::from_multiaddr(&addr.parse().unwrap());
+
+        assert_eq!(
+            target("/ip4/1.2.3.4/tcp/4001"),
+            Some(Target::Ipv4(Ipv4Addr::new(1, 2, 3, 4), 4001))
+        );
+        assert_eq!(
+            target(
+                "/dns4/example.com/tcp/80/p2p/12D3KooWR7EfNv5SLtgjMRjUwR8AvNu3hP4fLrtSa9fmHHXKYWNG"
+            ),
+            Some(Target::Domain("example.com".to_owned(), 80))
+        );
+        assert_eq!(target("/ip4/1.2.3.4/udp/4001/quic-v1"), None);
+        assert_eq!(target("/ip4/1.2.3.4/tcp/4001/ws"), None);
+    }
+
+    #[test]
+    fn dial_through_proxy() {
+        let proxy_addr: Multiaddr =
+            Protocol::Memory(rand::random::<u64>().saturating_add(1)).into();
+
+        let mut proxy = MemoryTransport::default().boxed();
+        proxy
+            .listen_on(ListenerId::next(), proxy_addr.clone())
+            .unwrap();
+        let proxy = async move {
+            let upgrade = loop {
+                if let Some(upgrade) = proxy.select_next_some().await.into_incoming() {
+                    break upgrade;
+                }
+            };
+            let mut socket = upgrade.0.await.unwrap();
+
+            let mut greeting = [0; 4];
+            socket.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(
+                greeting,
+                [VERSION, 2, METHOD_NO_AUTH, METHOD_USERNAME_PASSWORD]
+            );
+            socket
+                .write_all(&[VERSION, METHOD_USERNAME_PASSWORD])
+                .await
+                .unwrap();
+
+            let mut auth = [0; 11];
+            socket.read_exact(&mut auth).await.unwrap();
+            assert_eq!(&auth, b"\x01\x04user\x04pass");
+            socket.write_all(&[AUTH_VERSION, 0x00]).await.unwrap();
+
+            let mut request = [0; 18];
+            socket.read_exact(&mut request).await.unwrap();
+            assert_eq!(&request, b"\x05\x01\x00\x03\x0bexample.com\x0f\xa1");
+            socket
+                .write_all(&[VERSION, 0x00, 0x00, ADDRESS_IPV4, 0, 0, 0, 0, 0, 0])
+                .await
+                .unwrap();
+
+            socket.write_all(b"hello").await.unwrap();
+        };
+
+        let mut transport = Socks5Transport::new(
+            MemoryTransport::default(),
+            Config::new(proxy_addr).with_auth("user", "pass"),
+        );
+        let dialer = async move {
+            let mut socket = transport
+                .dial("/dns/example.com/tcp/4001".parse().unwrap())
+                .unwrap()
+                .await
+                .unwrap();
+            let mut buf = [0; 5];
+            socket.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hello");
+        };
+
+        futures::executor::block_on(future::join(proxy, dialer));
+    }
+}
@@ -0,0 +1,458 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Transports limiting the bandwidth of their connections.
+//!
+//! See [`Transport::throttle`](crate::Transport::throttle).
+//!
+//! Reads and writes are rate limited with token buckets, holding up to one second
+//! worth of traffic, shared by all connections of the transport and, if configured
+//! via [`Throttle::with_connection_limits`], kept per connection.
+
+use crate::{
+    transport::{ListenerId, Transport, TransportError, TransportEvent},
+    Multiaddr,
+};
+use futures::{prelude::*, ready};
+use futures_timer::Delay;
+use instant::Instant;
+use parking_lot::Mutex;
+use std::{
+    io,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// See [`Transport::throttle`](crate::Transport::throttle).
+#[derive(Debug, Clone)]
+#[pin_project::pin_project]
+pub struct Throttle<T> {
+    #[pin]
+    inner: T,
+    global: Limits,
+    connection: Option<(u64, u64)>,
+}
+
+impl<T> Throttle<T> {
+    pub(crate) fn new(inner: T, ingress: u64, egress: u64) -> Self {
+        Throttle {
+            inner,
+            global: Limits {
+                ingress: TokenBucket::shared(ingress),
+                egress: TokenBucket::shared(egress),
+            },
+            connection: None,
+        }
+    }
+
+    /// Additionally limits the ingress and egress bandwidth of every single connection,
+    /// in bytes per second.
+    ///
+    /// A rate of `u64::MAX` disables the limit in that direction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a rate is zero.
+    pub fn with_connection_limits(mut self, ingress: u64, egress: u64) -> Self {
+        assert!(ingress > 0 && egress > 0, "rates must be greater than zero");
+        self.connection = Some((ingress, egress));
+        self
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T> Transport for Throttle<T>
+where
+    T: Transport,
+    T::Output: AsyncRead + AsyncWrite,
+{
+    type Output = Throttled<T::Output>;
+    type Error = T::Error;
+    type ListenerUpgrade = ThrottleFuture<T::ListenerUpgrade>;
+    type Dial = ThrottleFuture<T::Dial>;
+
+    fn listen_on(
+        &mut self,
+        id: ListenerId,
+        addr: Multiaddr,
+    ) -> Result<(), TransportError<Self::Error>> {
+        self.inner.listen_on(id, addr)
+    }
+
+    fn remove_listener(&mut self, id: ListenerId) -> bool {
+        self.inner.remove_listener(id)
+    }
+
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.inner.remove_listener_graceful(id, timeout)
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let dial = self.inner.dial(addr)?;
+        Ok(ThrottleFuture {
+            inner: dial,
+            limiters: Some(self.global.limiters(self.connection)),
+        })
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let dial = self.inner.dial_as_listener(addr)?;
+        Ok(ThrottleFuture {
+            inner: dial,
+            limiters: Some(self.global.limiters(self.connection)),
+        })
+    }
+
+    fn address_translation(&self, server: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.address_translation(server, observed)
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        let this = self.project();
+        let (global, connection) = (this.global, *this.connection);
+        this.inner.poll(cx).map(|event| {
+            event.map_upgrade(|upgrade| ThrottleFuture {
+                inner: upgrade,
+                limiters: Some(global.limiters(connection)),
+            })
+        })
+    }
+}
+
+/// Wraps the connections produced by a future in [`Throttled`].
+#[pin_project::pin_project]
+#[must_use = "futures do nothing unless polled"]
+pub struct ThrottleFuture<F> {
+    #[pin]
+    inner: F,
+    limiters: Option<(Limiter, Limiter)>,
+}
+
+impl<F, O, E> Future for ThrottleFuture<F>
+where
+    F: Future<Output = Result<O, E>>,
+{
+    type Output = Result<Throttled<O>, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let inner = ready!(this.inner.poll(cx))?;
+        let (ingress, egress) = this
+            .limiters
+            .take()
+            .expect("ThrottleFuture must not be polled after completion");
+        Poll::Ready(Ok(Throttled {
+            inner,
+            ingress,
+            egress,
+        }))
+    }
+}
+
+/// A connection whose reads and writes are rate limited.
+#[derive(Debug)]
+#[pin_project::pin_project]
+pub struct Throttled<S> {
+    #[pin]
+    inner: S,
+    ingress: Limiter,
+    egress: Limiter,
+}
+
+impl<S> Throttled<S> {
+    /// Returns the wrapped connection.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: AsyncRead> AsyncRead for Throttled<S> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if buf.is_empty() {
+            return this.inner.poll_read(cx, buf);
+        }
+        let allowance = ready!(this.ingress.poll_allowance(cx, buf.len()));
+        let read = ready!(this.inner.poll_read(cx, &mut buf[..allowance]))?;
+        this.ingress.consume(read);
+        Poll::Ready(Ok(read))
+    }
+}
+
+impl<S: AsyncWrite> AsyncWrite for Throttled<S> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.project();
+        if buf.is_empty() {
+            return this.inner.poll_write(cx, buf);
+        }
+        let allowance = ready!(this.egress.poll_allowance(cx, buf.len()));
+        let written = ready!(this.inner.poll_write(cx, &buf[..allowance]))?;
+        this.egress.consume(written);
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_close(cx)
+    }
+}
+
+/// The token buckets shared by all connections of a [`Throttle`].
+#[derive(Debug, Clone)]
+struct Limits {
+    ingress: Option<Arc<Mutex<TokenBucket>>>,
+    egress: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+impl Limits {
+    /// Creates the ingress and egress limiters of a new connection.
+    fn limiters(&self, connection: Option<(u64, u64)>) -> (Limiter, Limiter) {
+        let (ingress, egress) = connection.unwrap_or((u64::MAX, u64::MAX));
+        (
+            Limiter::new(self.ingress.clone(), TokenBucket::new(ingress)),
+            Limiter::new(self.egress.clone(), TokenBucket::new(egress)),
+        )
+    }
+}
+
+/// Limits one direction of a connection by the shared and its own token bucket.
+#[derive(Debug)]
+struct Limiter {
+    global: Option<Arc<Mutex<TokenBucket>>>,
+    connection: Option<TokenBucket>,
+    delay: Option<Delay>,
+}
+
+impl Limiter {
+    fn new(global: Option<Arc<Mutex<TokenBucket>>>, connection: Option<TokenBucket>) -> Self {
+        Limiter {
+            global,
+            connection,
+            delay: None,
+        }
+    }
+
+    /// Returns the number of bytes, up to `max`, that may be transferred now, or
+    /// schedules a wake-up once enough tokens are available.
+    fn poll_allowance(&mut self, cx: &mut Context<'_>, max: usize) -> Poll<usize> {
+        loop {
+            if let Some(delay) = self.delay.as_mut() {
+                ready!(delay.poll_unpin(cx));
+                self.delay = None;
+            }
+
+            match self.allowance(Instant::now(), max) {
+                Ok(allowance) => return Poll::Ready(allowance),
+                Err(wait) => self.delay = Some(Delay::new(wait)),
+            }
+        }
+    }
+
+    /// Returns the number of bytes, up to `max`, that may be transferred at `now`, or the
+    /// time to wait until enough tokens are available.
+    fn allowance(&mut self, now: Instant, max: usize) -> Result<usize, Duration> {
+        let mut allowance = max;
+        let mut wait = Duration::ZERO;
+        let mut global = self.global.as_ref().map(|bucket| bucket.lock());
+        for bucket in self.connection.iter_mut().chain(global.as_deref_mut()) {
+            bucket.refill(now);
+            allowance = allowance.min(bucket.available(max));
+            wait = wait.max(bucket.wait_time(max));
+        }
+        if allowance > 0 {
+            return Ok(allowance);
+        }
+        Err(wait)
+    }
+
+    /// Takes the tokens for `n` transferred bytes.
+    fn consume(&mut self, n: usize) {
+        if let Some(bucket) = self.connection.as_mut() {
+            bucket.consume(n);
+        }
+        if let Some(bucket) = self.global.as_ref() {
+            bucket.lock().consume(n);
+        }
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    /// The refill rate in bytes per second, which is also the capacity of the bucket.
+    rate: f64,
+    /// The available tokens, which are negative after concurrent transfers overdrew them.
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    /// Creates a full bucket, or `None` for a rate of `u64::MAX`.
+    fn new(rate: u64) -> Option<Self> {
+        assert!(rate > 0, "rates must be greater than zero");
+        (rate != u64::MAX).then(|| TokenBucket {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last_refill: Instant::now(),
+        })
+    }
+
+    fn shared(rate: u64) -> Option<Arc<Mutex<Self>>> {
+        Self::new(rate).map(|bucket| Arc::new(Mutex::new(bucket)))
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.rate);
+        self.last_refill = now;
+    }
+
+    /// The number of tokens worth granting at once, to avoid waking up for every
+    /// single byte on saturated connections.
+    fn min_grant(&self, max: usize) -> f64 {
+        (self.rate / 100.0).max(1.0).min(max as f64)
+    }
+
+    fn available(&self, max: usize) -> usize {
+        if self.tokens < self.min_grant(max) {
+            return 0;
+        }
+        (self.tokens as usize).min(max)
+    }
+
+    fn wait_time(&self, max: usize) -> Duration {
+        let missing = self.min_grant(max) - self.tokens;
+        if missing <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(missing / self.rate)
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.tokens -= n as f64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_egress() {
+        let (_, mut egress) = Throttle::new((), u64::MAX, 100_000).global.limiters(None);
+        let now = Instant::now();
+
+        // The first 100_000 bytes are covered by the initial burst.
+        assert_eq!(egress.allowance(now, 150_000), Ok(100_000));
+        egress.consume(100_000);
+        assert!(egress.allowance(now, 50_000).is_err());
+
+        // Tokens are refilled at the rate of the limit.
+        assert_eq!(
+            egress.allowance(now + Duration::from_millis(250), 50_000),
+            Ok(25_000)
+        );
+        assert_eq!(
+            egress.allowance(now + Duration::from_millis(500), 50_000),
+            Ok(50_000)
+        );
+    }
+
+    #[test]
+    fn limits_ingress() {
+        let (mut ingress, _) = Throttle::new((), 100_000, u64::MAX).global.limiters(None);
+        let now = Instant::now();
+
+        assert_eq!(ingress.allowance(now, 150_000), Ok(100_000));
+        ingress.consume(100_000);
+
+        let wait = ingress.allowance(now, 50_000).unwrap_err();
+        assert!(wait > Duration::ZERO);
+        assert!(ingress.allowance(now + wait, 50_000).is_ok());
+    }
+
+    #[test]
+    fn bucket_holds_up_to_one_second_of_traffic() {
+        let (_, mut egress) = Throttle::new((), u64::MAX, 100_000).global.limiters(None);
+        let now = Instant::now();
+
+        egress.consume(100_000);
+        assert_eq!(
+            egress.allowance(now + Duration::from_secs(10), 150_000),
+            Ok(100_000)
+        );
+    }
+
+    #[test]
+    fn shares_global_limit() {
+        let throttle =
+            Throttle::new((), u64::MAX, 100_000).with_connection_limits(u64::MAX, 75_000);
+        let connection = throttle.connection;
+        let (_, mut first) = throttle.global.limiters(connection);
+        let (_, mut second) = throttle.global.limiters(connection);
+        let now = Instant::now();
+
+        assert_eq!(first.allowance(now, 100_000), Ok(75_000));
+        first.consume(75_000);
+
+        // The second connection is limited by what the first one left in the shared bucket.
+        assert_eq!(second.allowance(now, 100_000), Ok(25_000));
+    }
+
+    #[async_std::test]
+    async fn writes_through_limiter() {
+        let (ingress, egress) = Throttle::new((), u64::MAX, 100_000).global.limiters(None);
+        let mut connection = Throttled {
+            inner: futures::io::Cursor::new(Vec::new()),
+            ingress,
+            egress,
+        };
+
+        let written = connection.write(&[0; 150_000]).await.unwrap();
+
+        assert_eq!(written, 100_000);
+        assert_eq!(connection.into_inner().into_inner().len(), 100_000);
+    }
+}
@@ -343,6 +343,10 @@ where
         self.0.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.0.remove_listener_graceful(id, timeout)
+    }
+
     fn dial_as_listener(
         &mut self,
         addr: Multiaddr,
@@ -419,6 +423,10 @@ where
         self.inner.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.inner.remove_listener_graceful(id, timeout)
+    }
+
     fn dial_as_listener(
         &mut self,
         addr: Multiaddr,
@@ -117,6 +117,28 @@ impl SecretKey {
             .map(SecretKey)
     }
 
+    /// Encode the secret key as DER-encoded PKCS#8 PrivateKeyInfo.
+    #[cfg(feature = "pkcs8")]
+    pub(crate) fn encode_pkcs8_der(&self) -> Vec<u8> {
+        use p256::pkcs8::EncodePrivateKey;
+
+        self.0
+            .to_pkcs8_der()
+            .expect("Encoding to pkcs#8 format to succeed")
+            .as_bytes()
+            .to_vec()
+    }
+
+    /// Decode a secret key from DER-encoded PKCS#8 PrivateKeyInfo.
+    #[cfg(feature = "pkcs8")]
+    pub(crate) fn try_decode_pkcs8_der(der: &[u8]) -> Result<SecretKey, DecodingError> {
+        use p256::pkcs8::DecodePrivateKey;
+
+        SigningKey::from_pkcs8_der(der)
+            .map_err(|err| DecodingError::failed_to_parse("ecdsa p256 PKCS#8 secret key", err))
+            .map(SecretKey)
+    }
+
     /// Encode the secret key into DER-encoded byte buffer.
     pub(crate) fn encode_der(&self) -> Vec<u8> {
         self.0
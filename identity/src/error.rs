@@ -77,10 +77,15 @@ impl DecodingError {
         }
     }
 
-    #[cfg(all(feature = "rsa", not(target_arch = "wasm32")))]
-    pub(crate) fn encoding_unsupported(key_type: &'static str) -> Self {
+    #[cfg(any(
+        feature = "ecdsa",
+        feature = "secp256k1",
+        feature = "ed25519",
+        feature = "rsa"
+    ))]
+    pub(crate) fn encoding_unsupported(key_type: impl fmt::Display, format: &'static str) -> Self {
         Self {
-            msg: format!("encoding {key_type} key to Protobuf is unsupported"),
+            msg: format!("encoding {key_type} key to {format} is unsupported"),
             source: None,
         }
     }
@@ -107,16 +112,18 @@ pub struct SigningError {
 
 /// An error during encoding of key material.
 impl SigningError {
-    #[cfg(all(feature = "rsa", not(target_arch = "wasm32")))]
-    pub(crate) fn new<S: ToString>(msg: S) -> Self {
+    /// Creates a new signing error with the given message.
+    ///
+    /// Intended for implementors of [`Signer`](crate::Signer).
+    pub fn new<S: ToString>(msg: S) -> Self {
         Self {
             msg: msg.to_string(),
             source: None,
         }
     }
 
-    #[cfg(all(feature = "rsa", not(target_arch = "wasm32")))]
-    pub(crate) fn source(self, source: impl Error + Send + Sync + 'static) -> Self {
+    /// Attaches the underlying cause of this error.
+    pub fn source(self, source: impl Error + Send + Sync + 'static) -> Self {
         Self {
             source: Some(Box::new(source)),
             ..self
@@ -0,0 +1,43 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Unkeyed hashing for collections keyed by [`PeerId`]s.
+//!
+//! Peer IDs are chosen by remote peers, who can grind them to provoke hash collisions in
+//! collections using an unkeyed hash function. Collections keyed by peer IDs of remote peers
+//! should therefore use the randomly keyed SipHash of the standard library's [`HashMap`] and
+//! [`HashSet`].
+//!
+//! For non-adversarial contexts, the faster but unkeyed [`FxPeerIdHashMap`] and
+//! [`FxPeerIdHashSet`] are available.
+
+use crate::PeerId;
+use std::collections::{HashMap, HashSet};
+
+/// An unkeyed FxHash [`BuildHasher`](std::hash::BuildHasher).
+///
+/// Only use this if the hashed keys are not controlled by remote peers.
+pub type FxBuildHasher = std::hash::BuildHasherDefault<rustc_hash::FxHasher>;
+
+/// A [`HashMap`] keyed by [`PeerId`]s using [`FxBuildHasher`].
+pub type FxPeerIdHashMap<V> = HashMap<PeerId, V, FxBuildHasher>;
+
+/// A [`HashSet`] of [`PeerId`]s using [`FxBuildHasher`].
+pub type FxPeerIdHashSet = HashSet<PeerId, FxBuildHasher>;
@@ -0,0 +1,314 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Hierarchical deterministic derivation of identity keys.
+//!
+//! Keys are derived from a [`Seed`] along a [`DerivationPath`] as specified by
+//! [SLIP-0010](https://github.com/satoshilabs/slips/blob/master/slip-0010.md), which is
+//! compatible with BIP32 for Secp256k1. This allows recreating the identities of a whole fleet of
+//! nodes from a single backed-up secret, e.g. by deriving the key of the `i`-th node at
+//! `m/44'/0'/i'`.
+//!
+//! Ed25519 only supports hardened derivation, so all path components are hardened for either key
+//! type.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use zeroize::Zeroizing;
+
+use crate::Keypair;
+
+/// The smallest seed length in bytes accepted by BIP32.
+const MIN_SEED_LEN: usize = 16;
+/// The largest seed length in bytes accepted by BIP32.
+const MAX_SEED_LEN: usize = 64;
+const HARDENED: u32 = 1 << 31;
+/// The number of PBKDF2 rounds used by BIP39.
+const PHRASE_ROUNDS: u32 = 2048;
+
+/// The secret from which keys are derived.
+#[derive(Clone)]
+pub struct Seed(Zeroizing<Vec<u8>>);
+
+impl Seed {
+    /// Creates a seed from raw bytes, which must be between 16 and 64 bytes long.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Seed, DerivationError> {
+        if !(MIN_SEED_LEN..=MAX_SEED_LEN).contains(&bytes.len()) {
+            return Err(DerivationError::InvalidSeedLength(bytes.len()));
+        }
+
+        Ok(Seed(Zeroizing::new(bytes.to_vec())))
+    }
+
+    /// Creates a seed from a mnemonic `phrase` and an optional `passphrase` as specified by
+    /// [BIP39](https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki).
+    ///
+    /// The phrase is not checked against a word list and must already be in Unicode NFKD form,
+    /// which holds for phrases of the English word list.
+    pub fn from_phrase(phrase: &str, passphrase: &str) -> Seed {
+        let salt = Zeroizing::new(format!("mnemonic{passphrase}"));
+        let mut seed = Zeroizing::new(vec![0u8; MAX_SEED_LEN]);
+        pbkdf2::pbkdf2_hmac::<Sha512>(phrase.as_bytes(), salt.as_bytes(), PHRASE_ROUNDS, &mut seed);
+
+        Seed(seed)
+    }
+
+    /// Derives the Ed25519 keypair at `path`.
+    #[cfg(feature = "ed25519")]
+    pub fn derive_ed25519(&self, path: &DerivationPath) -> Keypair {
+        let (mut key, mut chain_code) = split(hmac(b"ed25519 seed", &[&self.0[..]]));
+        for index in &path.0 {
+            (key, chain_code) = split(hmac(
+                &chain_code[..],
+                &[&[0], &*key, &(index | HARDENED).to_be_bytes()],
+            ));
+        }
+
+        let secret = crate::ed25519::SecretKey::try_from_bytes(&mut *key)
+            .expect("32 bytes to be a valid Ed25519 secret key");
+        crate::ed25519::Keypair::from(secret).into()
+    }
+
+    /// Derives the Secp256k1 keypair at `path`.
+    #[cfg(feature = "secp256k1")]
+    pub fn derive_secp256k1(&self, path: &DerivationPath) -> Keypair {
+        let mut i = hmac(b"Bitcoin seed", &[&self.0[..]]);
+        let (mut secret, mut chain_code) = loop {
+            let (key, chain_code) = split(i.clone());
+            match libsecp256k1::SecretKey::parse(&key) {
+                Ok(secret) => break (secret, chain_code),
+                Err(_) => i = hmac(b"Bitcoin seed", &[&*i]),
+            }
+        };
+
+        for index in &path.0 {
+            let index = (index | HARDENED).to_be_bytes();
+            let mut i = hmac(&chain_code[..], &[&[0], &secret.serialize(), &index]);
+            (secret, chain_code) = loop {
+                let (tweak, child_chain_code) = split(i);
+                if let Ok(tweak) = libsecp256k1::SecretKey::parse(&tweak) {
+                    let mut child = secret;
+                    if child.tweak_add_assign(&tweak).is_ok() {
+                        break (child, child_chain_code);
+                    }
+                }
+                i = hmac(&chain_code[..], &[&[1], &*child_chain_code, &index]);
+            };
+        }
+
+        let secret = crate::secp256k1::SecretKey::try_from_bytes(secret.serialize())
+            .expect("a valid secret key to be parsed again");
+        crate::secp256k1::Keypair::from(secret).into()
+    }
+}
+
+impl fmt::Debug for Seed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Seed")
+    }
+}
+
+/// A path of hardened child indices, e.g. `m/44'/0'/1'`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// Creates a path of the given hardened child indices, which must be smaller than `2^31`.
+    pub fn new(indices: impl IntoIterator<Item = u32>) -> Result<DerivationPath, DerivationError> {
+        let indices = indices.into_iter().collect::<Vec<_>>();
+        if indices.iter().any(|index| *index >= HARDENED) {
+            return Err(DerivationError::InvalidPath("index out of range"));
+        }
+
+        Ok(DerivationPath(indices))
+    }
+
+    /// Returns the path extended by the hardened child `index`.
+    pub fn child(&self, index: u32) -> Result<DerivationPath, DerivationError> {
+        DerivationPath::new(self.0.iter().copied().chain(Some(index)))
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = DerivationError;
+
+    /// Parses a path of the form `m/44'/0'/1'`. Every component must be hardened, marked by a
+    /// trailing `'` or `h`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut components = s.split('/');
+        if components.next() != Some("m") {
+            return Err(DerivationError::InvalidPath("path must start with `m`"));
+        }
+
+        let indices = components
+            .map(|component| {
+                let index = component
+                    .strip_suffix(|c| c == '\'' || c == 'h')
+                    .ok_or(DerivationError::InvalidPath("component must be hardened"))?;
+                index
+                    .parse::<u32>()
+                    .map_err(|_| DerivationError::InvalidPath("component must be an integer"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        DerivationPath::new(indices)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for index in &self.0 {
+            write!(f, "/{index}'")?;
+        }
+        Ok(())
+    }
+}
+
+fn hmac(key: &[u8], data: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC to accept keys of any length");
+    for data in data {
+        mac.update(data);
+    }
+
+    let mut out = Zeroizing::new([0u8; 64]);
+    out.copy_from_slice(&mac.finalize().into_bytes());
+    out
+}
+
+/// Splits the output of [`hmac`] into the key and the chain code.
+fn split(i: Zeroizing<[u8; 64]>) -> (Zeroizing<[u8; 32]>, Zeroizing<[u8; 32]>) {
+    let (key, chain_code) = i.split_at(32);
+
+    (
+        Zeroizing::new(key.try_into().expect("slice to have length 32")),
+        Zeroizing::new(chain_code.try_into().expect("slice to have length 32")),
+    )
+}
+
+/// An error while creating a [`Seed`] or [`DerivationPath`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum DerivationError {
+    /// The seed is shorter than 16 or longer than 64 bytes.
+    InvalidSeedLength(usize),
+    /// The derivation path is malformed or contains an index that is out of range.
+    InvalidPath(&'static str),
+}
+
+impl fmt::Display for DerivationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationError::InvalidSeedLength(len) => {
+                write!(f, "Invalid seed length: {len} bytes")
+            }
+            DerivationError::InvalidPath(msg) => write!(f, "Invalid derivation path: {msg}"),
+        }
+    }
+}
+
+impl Error for DerivationError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Test vector 1 of SLIP-0010 and BIP32.
+    const SEED: [u8; 16] = hex_literal::hex!("000102030405060708090a0b0c0d0e0f");
+
+    #[test]
+    #[cfg(feature = "ed25519")]
+    fn derive_ed25519() {
+        let seed = Seed::from_bytes(&SEED).unwrap();
+        let secret = |path: &str| {
+            let keypair = seed.derive_ed25519(&path.parse().unwrap());
+            keypair
+                .try_into_ed25519()
+                .unwrap()
+                .secret()
+                .as_ref()
+                .to_vec()
+        };
+
+        assert_eq!(
+            secret("m"),
+            hex_literal::hex!("2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7")
+        );
+        assert_eq!(
+            secret("m/0'"),
+            hex_literal::hex!("68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3")
+        );
+        assert_eq!(
+            secret("m/0'/1h"),
+            hex_literal::hex!("b1d0bad404bf35da785a64ca1ac54b2617211d2777696fbffaf208f746ae84f2")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "secp256k1")]
+    fn derive_secp256k1() {
+        let seed = Seed::from_bytes(&SEED).unwrap();
+        let secret = |path: &str| {
+            let keypair = seed.derive_secp256k1(&path.parse().unwrap());
+            keypair.try_into_secp256k1().unwrap().secret().to_bytes()
+        };
+
+        assert_eq!(
+            secret("m"),
+            hex_literal::hex!("e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35")
+        );
+        assert_eq!(
+            secret("m/0'"),
+            hex_literal::hex!("edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea")
+        );
+        assert_eq!(
+            secret("m/0'/1'"),
+            hex_literal::hex!("e6e5947c871f44d5516b5199144218fb6f6bea55628b45e48563164b83457557")
+        );
+    }
+
+    #[test]
+    fn seed_from_phrase() {
+        // Test vector of BIP39.
+        let seed = Seed::from_phrase(
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+            "TREZOR",
+        );
+
+        assert_eq!(
+            &seed.0[..],
+            hex_literal::hex!("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04")
+        );
+    }
+
+    #[test]
+    fn parse_derivation_path() {
+        let path = "m/44'/0'/7h".parse::<DerivationPath>().unwrap();
+
+        assert_eq!(path, DerivationPath::new([44, 0, 7]).unwrap());
+        assert_eq!(path.to_string(), "m/44'/0'/7'");
+        assert!("m/44'/0".parse::<DerivationPath>().is_err());
+        assert!("44'".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648'".parse::<DerivationPath>().is_err());
+    }
+}
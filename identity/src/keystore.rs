@@ -0,0 +1,509 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Password-protected storage of a [`Keypair`] at rest.
+//!
+//! A keystore holds the protobuf encoding of a keypair, encrypted with ChaCha20-Poly1305 under a
+//! key derived from a password with Argon2id. The layout is:
+//!
+//! ```text
+//! magic (8) | version (1) | m_cost (4) | t_cost (4) | p_cost (4) | salt (16) | nonce (12) | ciphertext
+//! ```
+//!
+//! All integers are little-endian. Everything preceding the ciphertext is authenticated as
+//! associated data, so tampering with the KDF parameters is detected on decryption.
+//!
+//! For interoperability with the keystore of go-libp2p based nodes such as Kubo, which stores the
+//! unencrypted protobuf encoding of each key in a file named after [`go_key_file_name`],
+//! see [`load_unencrypted`] and [`store_unencrypted`].
+
+use crate::error::DecodingError;
+use crate::Keypair;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use std::error::Error;
+use std::path::Path;
+use std::{fmt, fs, io};
+use zeroize::Zeroizing;
+
+const MAGIC: &[u8; 8] = b"libp2pks";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 3 * 4 + SALT_LEN + NONCE_LEN;
+
+/// Cost parameters of the Argon2id key derivation.
+///
+/// Since the parameters of a keystore are read from the file before it can be authenticated,
+/// they are bounded by [`KdfParams::MAX_M_COST`], [`KdfParams::MAX_T_COST`] and
+/// [`KdfParams::MAX_P_COST`]. Larger parameters are rejected instead of running an arbitrarily
+/// expensive key derivation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    /// Memory size in KiB.
+    pub m_cost: u32,
+    /// Number of iterations.
+    pub t_cost: u32,
+    /// Degree of parallelism.
+    pub p_cost: u32,
+}
+
+impl KdfParams {
+    /// The maximum memory size in KiB, i.e. 1 GiB.
+    pub const MAX_M_COST: u32 = 1 << 20;
+    /// The maximum number of iterations.
+    pub const MAX_T_COST: u32 = 16;
+    /// The maximum degree of parallelism.
+    pub const MAX_P_COST: u32 = 16;
+}
+
+impl Default for KdfParams {
+    /// The parameters recommended by OWASP for Argon2id, i.e. 19 MiB of memory and 2 iterations.
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+/// Encrypts `keypair` with a key derived from `password` using the default [`KdfParams`].
+pub fn encrypt(keypair: &Keypair, password: &[u8]) -> Result<Vec<u8>, KeystoreError> {
+    encrypt_with_params(keypair, password, KdfParams::default())
+}
+
+/// Encrypts `keypair` with a key derived from `password` using the given [`KdfParams`].
+pub fn encrypt_with_params(
+    keypair: &Keypair,
+    password: &[u8],
+    params: KdfParams,
+) -> Result<Vec<u8>, KeystoreError> {
+    let plaintext = Zeroizing::new(keypair.to_protobuf_encoding()?);
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.extend_from_slice(&params.m_cost.to_le_bytes());
+    out.extend_from_slice(&params.t_cost.to_le_bytes());
+    out.extend_from_slice(&params.p_cost.to_le_bytes());
+    out.extend_from_slice(&salt);
+    out.extend_from_slice(&nonce);
+
+    let key = derive_key(password, &salt, params)?;
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(&*key))
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &out,
+            },
+        )
+        .map_err(|_| KeystoreError::Encryption)?;
+    out.extend_from_slice(&ciphertext);
+
+    Ok(out)
+}
+
+/// Decrypts a keystore produced by [`encrypt`] with the given `password`.
+pub fn decrypt(keystore: &[u8], password: &[u8]) -> Result<Keypair, KeystoreError> {
+    let (params, header, salt, nonce, ciphertext) = split(keystore)?;
+
+    let key = derive_key(password, salt, params)?;
+    let plaintext = Zeroizing::new(
+        ChaCha20Poly1305::new(Key::from_slice(&*key))
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| KeystoreError::Decryption)?,
+    );
+
+    Ok(Keypair::from_protobuf_encoding(&plaintext)?)
+}
+
+/// Encrypts `keypair` and writes the keystore to `path`.
+///
+/// The file is replaced atomically. On unix, it is created with permissions restricted to the
+/// owner.
+pub fn store(
+    path: impl AsRef<Path>,
+    keypair: &Keypair,
+    password: &[u8],
+) -> Result<(), KeystoreError> {
+    write_atomic(path.as_ref(), &encrypt(keypair, password)?)
+}
+
+/// Reads the keystore at `path` and decrypts it with `password`.
+pub fn load(path: impl AsRef<Path>, password: &[u8]) -> Result<Keypair, KeystoreError> {
+    decrypt(&fs::read(path)?, password)
+}
+
+/// Re-encrypts the keystore at `path` under `new_password`, keeping its [`KdfParams`].
+///
+/// A fresh salt and nonce are used and the file is replaced atomically, so the keystore is never
+/// left in a partially written state.
+pub fn rotate(
+    path: impl AsRef<Path>,
+    old_password: &[u8],
+    new_password: &[u8],
+) -> Result<(), KeystoreError> {
+    let path = path.as_ref();
+    let keystore = fs::read(path)?;
+    let (params, ..) = split(&keystore)?;
+    let keypair = decrypt(&keystore, old_password)?;
+
+    write_atomic(path, &encrypt_with_params(&keypair, new_password, params)?)
+}
+
+/// Returns the file name under which the go-libp2p keystore of Kubo stores the key `name`.
+///
+/// That is `key_` followed by the lowercase, unpadded base32 encoding of `name`.
+pub fn go_key_file_name(name: &str) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+    let mut out = String::from("key_");
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for byte in name.bytes() {
+        buffer = (buffer << 8) | u16::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[usize::from((buffer >> bits) & 0x1f)] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[usize::from((buffer << (5 - bits)) & 0x1f)] as char);
+    }
+
+    out
+}
+
+/// Reads an unencrypted, protobuf encoded keypair as written by the keystore of go-libp2p based
+/// nodes, e.g. to migrate it into an encrypted keystore with [`store`].
+pub fn load_unencrypted(path: impl AsRef<Path>) -> Result<Keypair, KeystoreError> {
+    let bytes = Zeroizing::new(fs::read(path)?);
+
+    Ok(Keypair::from_protobuf_encoding(&bytes)?)
+}
+
+/// Writes `keypair` unencrypted and protobuf encoded to `path`, in the format read by the
+/// keystore of go-libp2p based nodes.
+///
+/// Like go-libp2p, this fails if `path` already exists and, on unix, makes the file read-only
+/// for the owner.
+pub fn store_unencrypted(path: impl AsRef<Path>, keypair: &Keypair) -> Result<(), KeystoreError> {
+    let bytes = Zeroizing::new(keypair.to_protobuf_encoding()?);
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o400);
+    }
+
+    io::Write::write_all(&mut options.open(path)?, &bytes)?;
+
+    Ok(())
+}
+
+/// Splits a keystore into its KDF parameters, the authenticated header, salt, nonce and
+/// ciphertext.
+#[allow(clippy::type_complexity)]
+fn split(keystore: &[u8]) -> Result<(KdfParams, &[u8], &[u8], &[u8], &[u8]), KeystoreError> {
+    if keystore.len() < HEADER_LEN {
+        return Err(KeystoreError::Malformed("keystore is truncated"));
+    }
+    let (header, ciphertext) = keystore.split_at(HEADER_LEN);
+
+    let (magic, rest) = header.split_at(MAGIC.len());
+    if magic != MAGIC {
+        return Err(KeystoreError::Malformed("invalid magic bytes"));
+    }
+    let (version, rest) = rest.split_at(1);
+    if version[0] != VERSION {
+        return Err(KeystoreError::UnsupportedVersion(version[0]));
+    }
+    let (m_cost, rest) = rest.split_at(4);
+    let (t_cost, rest) = rest.split_at(4);
+    let (p_cost, rest) = rest.split_at(4);
+    let (salt, nonce) = rest.split_at(SALT_LEN);
+
+    let params = KdfParams {
+        m_cost: u32::from_le_bytes(m_cost.try_into().expect("slice to have length 4")),
+        t_cost: u32::from_le_bytes(t_cost.try_into().expect("slice to have length 4")),
+        p_cost: u32::from_le_bytes(p_cost.try_into().expect("slice to have length 4")),
+    };
+
+    Ok((params, header, salt, nonce, ciphertext))
+}
+
+/// Writes `contents` to a temporary file next to `path` and renames it over `path`.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), KeystoreError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp)?;
+    io::Write::write_all(&mut file, contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+fn derive_key(
+    password: &[u8],
+    salt: &[u8],
+    params: KdfParams,
+) -> Result<Zeroizing<[u8; 32]>, KeystoreError> {
+    if params.m_cost > KdfParams::MAX_M_COST {
+        return Err(KeystoreError::Kdf(format!(
+            "memory size of {} KiB exceeds the maximum of {} KiB",
+            params.m_cost,
+            KdfParams::MAX_M_COST
+        )));
+    }
+    if params.t_cost > KdfParams::MAX_T_COST {
+        return Err(KeystoreError::Kdf(format!(
+            "{} iterations exceed the maximum of {}",
+            params.t_cost,
+            KdfParams::MAX_T_COST
+        )));
+    }
+    if params.p_cost > KdfParams::MAX_P_COST {
+        return Err(KeystoreError::Kdf(format!(
+            "parallelism of {} exceeds the maximum of {}",
+            params.p_cost,
+            KdfParams::MAX_P_COST
+        )));
+    }
+
+    let params = Params::new(params.m_cost, params.t_cost, params.p_cost, Some(32))
+        .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password, salt, &mut *key)
+        .map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+
+    Ok(key)
+}
+
+/// An error while storing or loading a keystore.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum KeystoreError {
+    /// Reading or writing the keystore file failed.
+    Io(io::Error),
+    /// The keystore is not in the expected format.
+    Malformed(&'static str),
+    /// The keystore was written with an unsupported format version.
+    UnsupportedVersion(u8),
+    /// The key derivation failed, e.g. because of invalid [`KdfParams`].
+    Kdf(String),
+    /// Encrypting the keypair failed.
+    Encryption,
+    /// Decrypting the keystore failed, either because the password is wrong or the keystore has
+    /// been tampered with.
+    Decryption,
+    /// The keypair could not be encoded or decoded.
+    Decoding(DecodingError),
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeystoreError::Io(_) => write!(f, "Keystore I/O error"),
+            KeystoreError::Malformed(msg) => write!(f, "Malformed keystore: {msg}"),
+            KeystoreError::UnsupportedVersion(v) => {
+                write!(f, "Unsupported keystore version: {v}")
+            }
+            KeystoreError::Kdf(msg) => write!(f, "Keystore key derivation failed: {msg}"),
+            KeystoreError::Encryption => write!(f, "Keystore encryption failed"),
+            KeystoreError::Decryption => {
+                write!(
+                    f,
+                    "Keystore decryption failed: wrong password or corrupted keystore"
+                )
+            }
+            KeystoreError::Decoding(_) => write!(f, "Failed to encode or decode keypair"),
+        }
+    }
+}
+
+impl Error for KeystoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            KeystoreError::Io(e) => Some(e),
+            KeystoreError::Decoding(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for KeystoreError {
+    fn from(e: io::Error) -> Self {
+        KeystoreError::Io(e)
+    }
+}
+
+impl From<DecodingError> for KeystoreError {
+    fn from(e: DecodingError) -> Self {
+        KeystoreError::Decoding(e)
+    }
+}
+
+#[cfg(all(test, feature = "ed25519"))]
+mod tests {
+    use super::*;
+
+    /// Cheap parameters to keep the tests fast.
+    const TEST_PARAMS: KdfParams = KdfParams {
+        m_cost: 8,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn roundtrip() {
+        let keypair = Keypair::generate_ed25519();
+        let keystore = encrypt_with_params(&keypair, b"password", TEST_PARAMS).unwrap();
+
+        let decrypted = decrypt(&keystore, b"password").unwrap();
+
+        assert_eq!(keypair.public(), decrypted.public());
+    }
+
+    #[test]
+    fn wrong_password_fails() {
+        let keypair = Keypair::generate_ed25519();
+        let keystore = encrypt_with_params(&keypair, b"password", TEST_PARAMS).unwrap();
+
+        assert!(matches!(
+            decrypt(&keystore, b"wrong"),
+            Err(KeystoreError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn tampered_header_fails() {
+        let keypair = Keypair::generate_ed25519();
+        let mut keystore = encrypt_with_params(&keypair, b"password", TEST_PARAMS).unwrap();
+        // Flip a bit in the salt.
+        keystore[MAGIC.len() + 1 + 12] ^= 1;
+
+        assert!(matches!(
+            decrypt(&keystore, b"password"),
+            Err(KeystoreError::Decryption)
+        ));
+    }
+
+    #[test]
+    fn excessive_kdf_params_are_rejected() {
+        let keypair = Keypair::generate_ed25519();
+        let keystore = encrypt_with_params(&keypair, b"password", TEST_PARAMS).unwrap();
+
+        for offset in [0, 4, 8] {
+            let mut keystore = keystore.clone();
+            let at = MAGIC.len() + 1 + offset;
+            keystore[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+            assert!(matches!(
+                decrypt(&keystore, b"password"),
+                Err(KeystoreError::Kdf(_))
+            ));
+        }
+
+        let params = KdfParams {
+            m_cost: KdfParams::MAX_M_COST + 1,
+            ..TEST_PARAMS
+        };
+        assert!(matches!(
+            encrypt_with_params(&keypair, b"password", params),
+            Err(KeystoreError::Kdf(_))
+        ));
+    }
+
+    #[test]
+    fn rotate_changes_password() {
+        let path = std::env::temp_dir().join(format!("libp2p-keystore-{}", std::process::id()));
+        let keypair = Keypair::generate_ed25519();
+        let keystore = encrypt_with_params(&keypair, b"old", TEST_PARAMS).unwrap();
+        fs::write(&path, keystore).unwrap();
+
+        rotate(&path, b"old", b"new").unwrap();
+
+        assert!(matches!(
+            load(&path, b"old"),
+            Err(KeystoreError::Decryption)
+        ));
+        assert_eq!(load(&path, b"new").unwrap().public(), keypair.public());
+        let (params, ..) = split(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(params, TEST_PARAMS);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn go_key_file_names() {
+        assert_eq!(go_key_file_name("self"), "key_onswyzq");
+        assert_eq!(go_key_file_name("my-key"), "key_nv4s223fpe");
+    }
+
+    #[test]
+    fn unencrypted_roundtrip() {
+        let path = std::env::temp_dir().join(format!("libp2p-go-key-{}", std::process::id()));
+        let keypair = Keypair::generate_ed25519();
+
+        store_unencrypted(&path, &keypair).unwrap();
+
+        assert_eq!(
+            fs::read(&path).unwrap(),
+            keypair.to_protobuf_encoding().unwrap()
+        );
+        assert_eq!(load_unencrypted(&path).unwrap().public(), keypair.public());
+        assert!(matches!(
+            store_unencrypted(&path, &keypair),
+            Err(KeystoreError::Io(_))
+        ));
+
+        fs::remove_file(&path).unwrap();
+    }
+}
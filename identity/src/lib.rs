@@ -59,9 +59,44 @@ pub mod rsa;
 pub mod secp256k1;
 
 mod error;
+#[cfg(feature = "fxhash")]
+pub mod hash;
+#[cfg(all(feature = "hd", any(feature = "ed25519", feature = "secp256k1")))]
+pub mod hd;
 mod keypair;
+#[cfg(feature = "keystore")]
+pub mod keystore;
 #[cfg(feature = "peerid")]
 mod peer_id;
+#[cfg(all(
+    feature = "pkcs8",
+    any(
+        feature = "ecdsa",
+        feature = "secp256k1",
+        feature = "ed25519",
+        feature = "rsa"
+    )
+))]
+mod pem;
+#[cfg(all(
+    feature = "pkcs8",
+    any(
+        feature = "ecdsa",
+        feature = "secp256k1",
+        feature = "ed25519",
+        feature = "rsa"
+    )
+))]
+mod pkcs8;
+#[cfg(any(
+    feature = "ecdsa",
+    feature = "secp256k1",
+    feature = "ed25519",
+    feature = "rsa"
+))]
+mod signer;
+#[cfg(all(feature = "webcrypto", target_arch = "wasm32"))]
+pub mod webcrypto;
 
 #[cfg(any(
     feature = "ecdsa",
@@ -112,6 +147,38 @@ pub use error::{DecodingError, OtherVariantError, SigningError};
 pub use keypair::{Keypair, PublicKey};
 #[cfg(feature = "peerid")]
 pub use peer_id::{ParseError, PeerId};
+#[cfg(any(
+    feature = "ecdsa",
+    feature = "secp256k1",
+    feature = "ed25519",
+    feature = "rsa"
+))]
+pub use signer::Signer;
+
+/// Compares two byte slices in constant time with respect to their content.
+///
+/// The length of the slices is not considered secret.
+pub(crate) fn ct_eq_bytes(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    std::hint::black_box(diff) == 0
+}
+
+/// Compares two byte slices of equal length lexicographically in constant time with respect to
+/// their content.
+#[cfg(feature = "peerid")]
+pub(crate) fn ct_cmp_bytes(a: &[u8], b: &[u8]) -> std::cmp::Ordering {
+    debug_assert_eq!(a.len(), b.len());
+    let mut result = 0i16;
+    for (x, y) in a.iter().zip(b) {
+        // All ones while no earlier byte differed, zero afterwards.
+        let undecided = -(((result | -result) >> 15) + 1);
+        result |= (i16::from(*x) - i16::from(*y)) & undecided;
+    }
+    std::hint::black_box(result).cmp(&0)
+}
 
 /// The type of key a `KeyPair` is holding.
 #[derive(Debug, PartialEq, Eq)]
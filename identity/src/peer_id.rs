@@ -21,7 +21,7 @@
 #[cfg(feature = "rand")]
 use rand::Rng;
 use sha2::Digest as _;
-use std::{fmt, str::FromStr};
+use std::{cmp::Ordering, fmt, str::FromStr};
 use thiserror::Error;
 
 /// Local type-alias for multihash.
@@ -45,11 +45,39 @@ const MULTIHASH_SHA256_CODE: u64 = 0x12;
 ///
 /// The data is a CIDv0 compatible multihash of the protobuf encoded public key of the peer
 /// as specified in [specs/peer-ids](https://github.com/libp2p/specs/blob/master/peer-ids/peer-ids.md).
-#[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+///
+/// Equality and ordering are checked in constant time with respect to the digest, see
+/// [`PeerId::ct_eq`]. Collections keyed by peer IDs controlled by remote peers should use a
+/// keyed hash function, such as the default one of [`std::collections::HashMap`].
+#[allow(clippy::derived_hash_with_manual_eq)]
+#[derive(Clone, Copy, Eq, Hash)]
 pub struct PeerId {
     multihash: Multihash,
 }
 
+impl PartialEq for PeerId {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
+impl Ord for PeerId {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Same order as `Multihash`: by code, digest size and digest.
+        self.multihash
+            .code()
+            .cmp(&other.multihash.code())
+            .then(self.multihash.size().cmp(&other.multihash.size()))
+            .then_with(|| crate::ct_cmp_bytes(self.multihash.digest(), other.multihash.digest()))
+    }
+}
+
+impl PartialOrd for PeerId {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl fmt::Debug for PeerId {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_tuple("PeerId").field(&self.to_base58()).finish()
@@ -110,6 +138,13 @@ impl PeerId {
         }
     }
 
+    /// Compares two peer IDs in constant time with respect to their digest.
+    pub fn ct_eq(&self, other: &PeerId) -> bool {
+        let same_code = self.multihash.code() == other.multihash.code();
+        let same_digest = crate::ct_eq_bytes(self.multihash.digest(), other.multihash.digest());
+        same_code & same_digest
+    }
+
     /// Returns a raw bytes representation of this `PeerId`.
     pub fn to_bytes(self) -> Vec<u8> {
         self.multihash.to_bytes()
@@ -264,6 +299,27 @@ mod tests {
         assert_eq!(peer_id, second);
     }
 
+    #[test]
+    #[cfg(feature = "rand")]
+    fn ct_eq_matches_byte_equality() {
+        let peer_id = PeerId::random();
+        let other = PeerId::random();
+        assert!(peer_id.ct_eq(&PeerId::from_bytes(&peer_id.to_bytes()).unwrap()));
+        assert!(!peer_id.ct_eq(&other));
+        assert_ne!(peer_id, other);
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn ord_matches_multihash_ord() {
+        for _ in 0..100 {
+            let peer_id = PeerId::random();
+            let other = PeerId::random();
+            assert_eq!(peer_id.cmp(&other), peer_id.multihash.cmp(&other.multihash));
+            assert_eq!(peer_id.cmp(&peer_id), Ordering::Equal);
+        }
+    }
+
     #[test]
     #[cfg(feature = "rand")]
     fn random_peer_id_is_valid() {
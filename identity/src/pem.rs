@@ -0,0 +1,61 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! PEM armor as defined in [RFC7468](https://tools.ietf.org/html/rfc7468).
+
+use crate::error::DecodingError;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::convert::Infallible;
+use zeroize::Zeroizing;
+
+/// Encodes `der` as base64 between the boundaries of `label`, wrapping lines after 64
+/// characters.
+pub(crate) fn encode(label: &str, der: &[u8]) -> String {
+    let base64 = Zeroizing::new(STANDARD.encode(der));
+
+    let mut pem = format!("-----BEGIN {label}-----\n");
+    for line in base64.as_bytes().chunks(64) {
+        pem.push_str(std::str::from_utf8(line).expect("base64 to be ASCII"));
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {label}-----\n"));
+
+    pem
+}
+
+/// Decodes the base64 between the boundaries of `label`.
+pub(crate) fn decode(label: &str, pem: &str) -> Result<Zeroizing<Vec<u8>>, DecodingError> {
+    let base64 = pem
+        .trim()
+        .strip_prefix(&format!("-----BEGIN {label}-----"))
+        .and_then(|pem| pem.strip_suffix(&format!("-----END {label}-----")))
+        .ok_or_else(|| DecodingError::failed_to_parse::<Infallible, _>("PEM boundaries", None))?;
+    let base64 = Zeroizing::new(
+        base64
+            .chars()
+            .filter(|c| !c.is_ascii_whitespace())
+            .collect::<String>(),
+    );
+
+    STANDARD
+        .decode(base64.as_bytes())
+        .map(Zeroizing::new)
+        .map_err(|e| DecodingError::failed_to_parse("PEM base64", e))
+}
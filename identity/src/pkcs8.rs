@@ -0,0 +1,156 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Encoding of private keys as PKCS#8 PrivateKeyInfo as defined in
+//! [RFC5208](https://tools.ietf.org/html/rfc5208#section-5).
+
+use crate::error::DecodingError;
+use std::convert::Infallible;
+#[cfg(any(feature = "ed25519", feature = "secp256k1"))]
+use zeroize::Zeroizing;
+
+/// The PEM label of a PKCS#8 PrivateKeyInfo.
+pub(crate) const PEM_LABEL: &str = "PRIVATE KEY";
+
+/// The AlgorithmIdentifier of Ed25519 as defined in
+/// [RFC8410](https://tools.ietf.org/html/rfc8410#section-3).
+#[cfg(feature = "ed25519")]
+pub(crate) const ED25519_ALGORITHM: &[u8] = &[0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70];
+
+/// The AlgorithmIdentifier of `id-ecPublicKey` on the `secp256k1` curve.
+#[cfg(feature = "secp256k1")]
+pub(crate) const SECP256K1_ALGORITHM: &[u8] = &[
+    0x30, 0x10, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x05, 0x2b, 0x81, 0x04,
+    0x00, 0x0a,
+];
+
+/// The AlgorithmIdentifier of `id-ecPublicKey` on the `secp256r1` curve.
+#[cfg(feature = "ecdsa")]
+pub(crate) const P256_ALGORITHM: &[u8] = &[
+    0x30, 0x13, 0x06, 0x07, 0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01, 0x06, 0x08, 0x2a, 0x86, 0x48,
+    0xce, 0x3d, 0x03, 0x01, 0x07,
+];
+
+/// The AlgorithmIdentifier of `rsaEncryption`.
+#[cfg(all(feature = "rsa", not(target_arch = "wasm32")))]
+pub(crate) const RSA_ALGORITHM: &[u8] = &[
+    0x30, 0x0d, 0x06, 0x09, 0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01, 0x05, 0x00,
+];
+
+const INTEGER: u8 = 0x02;
+const OCTET_STRING: u8 = 0x04;
+const SEQUENCE: u8 = 0x30;
+
+/// Splits a PrivateKeyInfo into the encoded AlgorithmIdentifier and the contents of the
+/// privateKey octet string.
+pub(crate) fn split_private_key_info(der: &[u8]) -> Result<(&[u8], &[u8]), DecodingError> {
+    let split = || {
+        let (info, rest) = read(der, SEQUENCE)?;
+        if !rest.is_empty() {
+            return None;
+        }
+        let (_version, info) = read(info, INTEGER)?;
+        let (header, len) = header(info, SEQUENCE)?;
+        let (algorithm, info) = info.split_at(header + len);
+        let (private_key, _) = read(info, OCTET_STRING)?;
+
+        Some((algorithm, private_key))
+    };
+
+    split().ok_or_else(|| {
+        DecodingError::failed_to_parse::<Infallible, _>("PKCS#8 PrivateKeyInfo", None)
+    })
+}
+
+/// Reads the contents of the CurvePrivateKey octet string of an Ed25519 private key.
+#[cfg(feature = "ed25519")]
+pub(crate) fn read_ed25519(private_key: &[u8]) -> Result<&[u8], DecodingError> {
+    match read(private_key, OCTET_STRING) {
+        Some((secret, [])) => Ok(secret),
+        _ => Err(DecodingError::failed_to_parse::<Infallible, _>(
+            "Ed25519 CurvePrivateKey",
+            None,
+        )),
+    }
+}
+
+/// Encodes an Ed25519 secret key as a PrivateKeyInfo.
+#[cfg(feature = "ed25519")]
+pub(crate) fn encode_ed25519(secret: &[u8]) -> Vec<u8> {
+    let mut private_key = Zeroizing::new(Vec::new());
+    write(&mut private_key, OCTET_STRING, secret);
+
+    encode_private_key_info(ED25519_ALGORITHM, &private_key)
+}
+
+/// Encodes a Secp256k1 secret key as a PrivateKeyInfo, holding an ECPrivateKey as defined in
+/// [RFC5915](https://tools.ietf.org/html/rfc5915) without the optional fields.
+#[cfg(feature = "secp256k1")]
+pub(crate) fn encode_secp256k1(secret: &[u8]) -> Vec<u8> {
+    let mut ec_private_key = Zeroizing::new(vec![INTEGER, 0x01, 0x01]);
+    write(&mut ec_private_key, OCTET_STRING, secret);
+    let mut private_key = Zeroizing::new(Vec::new());
+    write(&mut private_key, SEQUENCE, &ec_private_key);
+
+    encode_private_key_info(SECP256K1_ALGORITHM, &private_key)
+}
+
+#[cfg(any(feature = "ed25519", feature = "secp256k1"))]
+fn encode_private_key_info(algorithm: &[u8], private_key: &[u8]) -> Vec<u8> {
+    let mut info = Zeroizing::new(vec![INTEGER, 0x01, 0x00]);
+    info.extend_from_slice(algorithm);
+    write(&mut info, OCTET_STRING, private_key);
+
+    let mut der = Vec::new();
+    write(&mut der, SEQUENCE, &info);
+    der
+}
+
+/// Reads a TLV with the given tag, returning its contents and the remaining input.
+fn read(input: &[u8], tag: u8) -> Option<(&[u8], &[u8])> {
+    let (header, len) = header(input, tag)?;
+    let (tlv, rest) = input.split_at(header + len);
+
+    Some((&tlv[header..], rest))
+}
+
+/// Returns the length of the header and of the contents of the TLV with the given tag at the
+/// start of `input`.
+fn header(input: &[u8], tag: u8) -> Option<(usize, usize)> {
+    let (header, len) = match input {
+        [t, len @ 0..=0x7f, ..] if *t == tag => (2, usize::from(*len)),
+        [t, 0x81, len, ..] if *t == tag => (3, usize::from(*len)),
+        [t, 0x82, hi, lo, ..] if *t == tag => (4, usize::from(u16::from_be_bytes([*hi, *lo]))),
+        _ => return None,
+    };
+
+    (header + len <= input.len()).then_some((header, len))
+}
+
+#[cfg(any(feature = "ed25519", feature = "secp256k1"))]
+fn write(out: &mut Vec<u8>, tag: u8, contents: &[u8]) {
+    out.push(tag);
+    match contents.len() {
+        len @ 0..=0x7f => out.push(len as u8),
+        len @ 0x80..=0xff => out.extend_from_slice(&[0x81, len as u8]),
+        len => out.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+    }
+    out.extend_from_slice(contents);
+}
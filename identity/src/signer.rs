@@ -0,0 +1,43 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::error::SigningError;
+use crate::PublicKey;
+use std::fmt::Debug;
+
+/// Signs messages with a private key held outside of the process, e.g. by a hardware security
+/// module, a TPM or a PKCS#11 token, see [`Keypair::from_signer`](crate::Keypair::from_signer).
+///
+/// The resulting [`Keypair`](crate::Keypair) can be used like any other, e.g. to derive the
+/// [`PeerId`](crate::PeerId) or to authenticate Noise and TLS handshakes, but its private key
+/// can not be encoded.
+pub trait Signer: Debug + Send + Sync + 'static {
+    /// The public key corresponding to the private key.
+    ///
+    /// Queried once when the [`Keypair`](crate::Keypair) is created.
+    fn public(&self) -> PublicKey;
+
+    /// Signs a message, producing a signature in the same format as
+    /// [`Keypair::sign`](crate::Keypair::sign) for a keypair of the type of [`Signer::public`].
+    ///
+    /// That is the 64 byte signature for Ed25519, a DER-encoded signature of the SHA-256 hash of
+    /// `msg` for ECDSA and Secp256k1, and a PKCS#1 v1.5 signature with SHA-256 for RSA.
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, SigningError>;
+}
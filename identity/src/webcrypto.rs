@@ -0,0 +1,234 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Key operations delegated to the browser's [SubtleCrypto](https://developer.mozilla.org/en-US/docs/Web/API/SubtleCrypto).
+//!
+//! The private key of a [`Keypair`] is a [`CryptoKey`] handle. A key generated as non-extractable
+//! never becomes accessible to JavaScript, but the handle can still be persisted, e.g. in
+//! IndexedDB, and later be restored via [`Keypair::from_crypto_keys`].
+//!
+//! Since SubtleCrypto is asynchronous, so is [`Keypair::sign`]. A [`Keypair`] can thus not be
+//! converted into a [`crate::Keypair`], whereas its [`PublicKey`] is an ordinary one.
+
+use crate::error::DecodingError;
+use crate::{ecdsa, ed25519, PublicKey};
+use js_sys::{Array, Object, Reflect, Uint8Array};
+use std::error::Error;
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{Crypto, CryptoKey, SubtleCrypto};
+
+/// The signature algorithm of a [`Keypair`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    /// Ed25519, supported by recent browsers only.
+    Ed25519,
+    /// ECDSA on the P-256 curve with SHA-256.
+    EcdsaP256,
+}
+
+impl Algorithm {
+    /// The algorithm parameters for generating and importing keys.
+    fn key_params(&self) -> Result<Object, WebCryptoError> {
+        match self {
+            Algorithm::Ed25519 => params(&[("name", "Ed25519")]),
+            Algorithm::EcdsaP256 => params(&[("name", "ECDSA"), ("namedCurve", "P-256")]),
+        }
+    }
+
+    /// The algorithm parameters for signing.
+    fn sign_params(&self) -> Result<Object, WebCryptoError> {
+        match self {
+            Algorithm::Ed25519 => params(&[("name", "Ed25519")]),
+            Algorithm::EcdsaP256 => params(&[("name", "ECDSA"), ("hash", "SHA-256")]),
+        }
+    }
+}
+
+/// A keypair whose private key is held by the browser's SubtleCrypto.
+#[derive(Clone)]
+pub struct Keypair {
+    algorithm: Algorithm,
+    private_key: CryptoKey,
+    public: PublicKey,
+}
+
+impl Keypair {
+    /// Generates a new keypair for the given algorithm.
+    ///
+    /// Unless `extractable` is set, the private key can not be exported from SubtleCrypto.
+    pub async fn generate(algorithm: Algorithm, extractable: bool) -> Result<Self, WebCryptoError> {
+        let usages = Array::of2(&JsValue::from("sign"), &JsValue::from("verify"));
+        let pair = resolve(subtle()?.generate_key_with_object(
+            &algorithm.key_params()?,
+            extractable,
+            &usages,
+        ))
+        .await?;
+
+        let private_key = crypto_key(&pair, "privateKey")?;
+        let public_key = crypto_key(&pair, "publicKey")?;
+        Self::from_crypto_keys(algorithm, private_key, &public_key).await
+    }
+
+    /// Creates a keypair from previously generated or imported [`CryptoKey`]s, e.g. after loading
+    /// them from IndexedDB.
+    ///
+    /// The public key needs to be extractable, the private key does not.
+    pub async fn from_crypto_keys(
+        algorithm: Algorithm,
+        private_key: CryptoKey,
+        public_key: &CryptoKey,
+    ) -> Result<Self, WebCryptoError> {
+        let raw = resolve(subtle()?.export_key("raw", public_key)).await?;
+        let raw = Uint8Array::new(&raw).to_vec();
+        let public = match algorithm {
+            Algorithm::Ed25519 => ed25519::PublicKey::try_from_bytes(&raw)?.into(),
+            Algorithm::EcdsaP256 => ecdsa::PublicKey::try_from_bytes(&raw)?.into(),
+        };
+
+        Ok(Self {
+            algorithm,
+            private_key,
+            public,
+        })
+    }
+
+    /// Signs a message, producing a signature in the same format as [`crate::Keypair::sign`].
+    pub async fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, WebCryptoError> {
+        let signature = resolve(subtle()?.sign_with_object_and_buffer_source(
+            &self.algorithm.sign_params()?,
+            &self.private_key,
+            &Uint8Array::from(msg),
+        ))
+        .await?;
+        let signature = Uint8Array::new(&signature).to_vec();
+
+        match self.algorithm {
+            Algorithm::Ed25519 => Ok(signature),
+            // SubtleCrypto produces the fixed-size `r || s` encoding, libp2p expects DER.
+            Algorithm::EcdsaP256 => p256::ecdsa::Signature::from_slice(&signature)
+                .map(|s| s.to_der().as_bytes().to_vec())
+                .map_err(|_| WebCryptoError::Malformed("ECDSA signature")),
+        }
+    }
+
+    /// Returns the signature algorithm of this keypair.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Returns the public key of this keypair.
+    pub fn public(&self) -> &PublicKey {
+        &self.public
+    }
+
+    /// Returns the handle to the private key, e.g. to persist it in IndexedDB.
+    pub fn private_key(&self) -> &CryptoKey {
+        &self.private_key
+    }
+}
+
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keypair")
+            .field("algorithm", &self.algorithm)
+            .field("public", &self.public)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Returns the SubtleCrypto of the current window or worker.
+fn subtle() -> Result<SubtleCrypto, WebCryptoError> {
+    let crypto = Reflect::get(&js_sys::global(), &"crypto".into())?;
+    if crypto.is_undefined() {
+        return Err(WebCryptoError::Unavailable);
+    }
+    Ok(crypto.unchecked_into::<Crypto>().subtle())
+}
+
+async fn resolve(promise: Result<js_sys::Promise, JsValue>) -> Result<JsValue, WebCryptoError> {
+    Ok(JsFuture::from(promise?).await?)
+}
+
+fn params(entries: &[(&str, &str)]) -> Result<Object, WebCryptoError> {
+    let params = Object::new();
+    for (key, value) in entries {
+        Reflect::set(&params, &(*key).into(), &(*value).into())?;
+    }
+    Ok(params)
+}
+
+fn crypto_key(pair: &JsValue, name: &'static str) -> Result<CryptoKey, WebCryptoError> {
+    Reflect::get(pair, &name.into())?
+        .dyn_into()
+        .map_err(|_| WebCryptoError::Malformed(name))
+}
+
+/// An error during a SubtleCrypto operation.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WebCryptoError {
+    /// SubtleCrypto is not available in the current context, e.g. outside of a secure context.
+    Unavailable,
+    /// A SubtleCrypto operation failed.
+    Js(String),
+    /// SubtleCrypto returned an unexpected value.
+    Malformed(&'static str),
+    /// The exported public key could not be decoded.
+    Decoding(DecodingError),
+}
+
+impl fmt::Display for WebCryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebCryptoError::Unavailable => write!(f, "SubtleCrypto is not available"),
+            WebCryptoError::Js(msg) => write!(f, "SubtleCrypto operation failed: {msg}"),
+            WebCryptoError::Malformed(what) => write!(f, "Unexpected {what} from SubtleCrypto"),
+            WebCryptoError::Decoding(_) => write!(f, "Failed to decode public key"),
+        }
+    }
+}
+
+impl Error for WebCryptoError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            WebCryptoError::Decoding(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<JsValue> for WebCryptoError {
+    fn from(value: JsValue) -> Self {
+        let msg = value
+            .dyn_ref::<js_sys::Error>()
+            .and_then(|e| e.to_string().as_string())
+            .unwrap_or_else(|| format!("{value:?}"));
+        WebCryptoError::Js(msg)
+    }
+}
+
+impl From<DecodingError> for WebCryptoError {
+    fn from(e: DecodingError) -> Self {
+        WebCryptoError::Decoding(e)
+    }
+}
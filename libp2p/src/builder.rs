@@ -2,7 +2,7 @@ use std::marker::PhantomData;
 
 mod phase;
 mod select_muxer;
-mod select_security;
+pub(crate) mod select_security;
 
 /// Build a [`Swarm`](libp2p_swarm::Swarm) by combining an identity, a set of
 /// [`Transport`](libp2p_core::Transport)s and a
@@ -145,6 +145,9 @@ mod builder;
 mod transport_ext;
 
 pub mod bandwidth;
+#[cfg(all(feature = "serde", feature = "tokio"))]
+#[cfg(not(target_arch = "wasm32"))]
+pub mod transport_config;
 
 #[cfg(doc)]
 pub mod tutorials;
@@ -0,0 +1,446 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Assembling a transport at runtime from a [`TransportConfig`], e.g. read from a config file.
+//!
+//! Unlike the [`SwarmBuilder`](crate::SwarmBuilder), which composes the transport at compile
+//! time, the parts of the stack are selected when calling [`TransportConfig::build`].
+//! Parts that are not compiled in, because the corresponding feature of this crate is disabled,
+//! are reported as [`Error::Unsupported`].
+//!
+//! ``` rust
+//! # use libp2p::transport_config::{Security, TransportConfig};
+//! # fn build() -> Result<(), libp2p::transport_config::Error> {
+//! let config = TransportConfig {
+//!     quic: false,
+//!     security: vec![Security::Tls, Security::Noise],
+//!     ..Default::default()
+//! };
+//! let transport = config
+//!     .build(&libp2p::identity::Keypair::generate_ed25519())?
+//!     .transport;
+//! # Ok(())
+//! # }
+//! ```
+
+use futures::{AsyncRead, AsyncWrite};
+use libp2p_core::muxing::StreamMuxerBox;
+use libp2p_core::transport::{Boxed, Transport};
+#[cfg(feature = "yamux")]
+use libp2p_core::{
+    upgrade::{InboundConnectionUpgrade, OutboundConnectionUpgrade, UpgradeInfo},
+    Negotiated,
+};
+use libp2p_identity::{Keypair, PeerId};
+use serde::{Deserialize, Serialize};
+use std::error::Error as StdError;
+
+/// The transport stack of a node, see [`TransportConfig::build`].
+///
+/// Missing fields take the value of [`TransportConfig::default`], i.e. TCP and QUIC secured by
+/// Noise and multiplexed with Yamux.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TransportConfig {
+    /// Whether to listen and dial via TCP.
+    pub tcp: bool,
+    /// Whether to listen and dial via QUIC.
+    pub quic: bool,
+    /// Whether to listen and dial via websockets on top of TCP.
+    pub websocket: bool,
+    /// Whether to resolve `/dns` addresses before dialing.
+    pub dns: bool,
+    /// Whether to listen and dial via relays, see [`BuiltTransport::relay_behaviour`].
+    pub relay: bool,
+    /// Security protocols for TCP, websocket and relayed connections, by descending priority.
+    pub security: Vec<Security>,
+    /// Pre-shared key of a private network in the format of a `swarm.key` file.
+    ///
+    /// Applies to TCP and websocket connections. Not supported together with QUIC.
+    pub pnet: Option<String>,
+}
+
+impl Default for TransportConfig {
+    fn default() -> Self {
+        Self {
+            tcp: true,
+            quic: true,
+            websocket: false,
+            dns: false,
+            relay: false,
+            security: vec![Security::Noise],
+            pnet: None,
+        }
+    }
+}
+
+/// Security protocol of a [`TransportConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Security {
+    /// The Noise XX handshake, requires the `noise` feature.
+    Noise,
+    /// TLS 1.3, requires the `tls` feature.
+    Tls,
+}
+
+impl Security {
+    fn name(&self) -> &'static str {
+        match self {
+            Security::Noise => "noise",
+            Security::Tls => "tls",
+        }
+    }
+
+    fn is_supported(&self) -> bool {
+        match self {
+            Security::Noise => cfg!(feature = "noise"),
+            Security::Tls => cfg!(feature = "tls"),
+        }
+    }
+}
+
+/// Transport assembled by [`TransportConfig::build`].
+pub struct BuiltTransport {
+    /// The transport to pass to [`Swarm::new`](libp2p_swarm::Swarm::new).
+    pub transport: Boxed<(PeerId, StreamMuxerBox)>,
+    /// The relay client behaviour, which has to be part of the `NetworkBehaviour` if
+    /// [`TransportConfig::relay`] is enabled.
+    #[cfg(feature = "relay")]
+    pub relay_behaviour: Option<libp2p_relay::client::Behaviour>,
+}
+
+/// Error when building a transport from a [`TransportConfig`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("No transport is enabled")]
+    NoTransport,
+    #[error("`{0}` is not supported by this build of libp2p")]
+    Unsupported(&'static str),
+    #[error("At least one security protocol has to be configured")]
+    MissingSecurity,
+    #[error("Security protocol {0:?} is configured more than once")]
+    DuplicateSecurity(Security),
+    #[error("A private network can not be used with QUIC")]
+    PnetWithQuic,
+    #[error("Failed to set up {0}")]
+    Setup(
+        &'static str,
+        #[source] Box<dyn StdError + Send + Sync + 'static>,
+    ),
+}
+
+impl TransportConfig {
+    /// Assembles the configured transport stack, using `keypair` for the security protocols.
+    pub fn build(&self, keypair: &Keypair) -> Result<BuiltTransport, Error> {
+        if !(self.tcp || self.quic || self.websocket || self.relay) {
+            return Err(Error::NoTransport);
+        }
+        if self.pnet.is_some() && self.quic {
+            return Err(Error::PnetWithQuic);
+        }
+
+        let mut transport = None;
+
+        if let Some(stream_transport) = self.stream_transport()? {
+            let stream_transport = self.with_pnet(stream_transport)?;
+            transport = Some(or(transport, self.authenticate(stream_transport, keypair)?));
+        }
+        if self.quic {
+            transport = Some(or(transport, quic(keypair)?));
+        }
+        #[cfg(feature = "relay")]
+        let mut relay_behaviour = None;
+        if self.relay {
+            #[cfg(feature = "relay")]
+            {
+                let (relay_transport, behaviour) =
+                    libp2p_relay::client::new(keypair.public().to_peer_id());
+                let relay_transport = relay_transport
+                    .map(|connection, _| Box::new(connection) as BoxedStream)
+                    .boxed();
+                transport = Some(or(transport, self.authenticate(relay_transport, keypair)?));
+                relay_behaviour = Some(behaviour);
+            }
+            #[cfg(not(feature = "relay"))]
+            return Err(Error::Unsupported("relay"));
+        }
+
+        let transport = transport.expect("at least one transport to be enabled");
+        let transport = if self.dns { dns(transport)? } else { transport };
+
+        Ok(BuiltTransport {
+            transport,
+            #[cfg(feature = "relay")]
+            relay_behaviour,
+        })
+    }
+
+    /// Returns the TCP based transports, not yet upgraded.
+    #[cfg(feature = "tcp")]
+    fn stream_transport(&self) -> Result<Option<Boxed<BoxedStream>>, Error> {
+        let tcp = || {
+            libp2p_tcp::tokio::Transport::new(libp2p_tcp::Config::default())
+                .map(|stream, _| Box::new(stream) as BoxedStream)
+                .boxed()
+        };
+
+        let mut transport = self.tcp.then(tcp);
+        if self.websocket {
+            #[cfg(feature = "websocket")]
+            {
+                let websocket = libp2p_websocket::WsConfig::new(tcp())
+                    .map(|stream, _| Box::new(stream) as BoxedStream)
+                    .boxed();
+                transport = Some(or(transport, websocket));
+            }
+            #[cfg(not(feature = "websocket"))]
+            return Err(Error::Unsupported("websocket"));
+        }
+
+        Ok(transport)
+    }
+
+    #[cfg(not(feature = "tcp"))]
+    fn stream_transport(&self) -> Result<Option<Boxed<BoxedStream>>, Error> {
+        if self.tcp || self.websocket {
+            return Err(Error::Unsupported("tcp"));
+        }
+        Ok(None)
+    }
+
+    #[cfg(feature = "pnet")]
+    fn with_pnet(&self, transport: Boxed<BoxedStream>) -> Result<Boxed<BoxedStream>, Error> {
+        let Some(psk) = self.pnet.as_ref() else {
+            return Ok(transport);
+        };
+        let psk: libp2p_pnet::PreSharedKey =
+            psk.parse().map_err(|e| Error::Setup("pnet", Box::new(e)))?;
+
+        Ok(transport
+            .and_then(move |socket, _| libp2p_pnet::PnetConfig::new(psk).handshake(socket))
+            .map(|stream, _| Box::new(stream) as BoxedStream)
+            .boxed())
+    }
+
+    #[cfg(not(feature = "pnet"))]
+    fn with_pnet(&self, transport: Boxed<BoxedStream>) -> Result<Boxed<BoxedStream>, Error> {
+        if self.pnet.is_some() {
+            return Err(Error::Unsupported("pnet"));
+        }
+        Ok(transport)
+    }
+
+    /// Applies the configured security protocols and Yamux.
+    #[cfg(feature = "yamux")]
+    fn authenticate(
+        &self,
+        transport: Boxed<BoxedStream>,
+        keypair: &Keypair,
+    ) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Error> {
+        let muxer = libp2p_yamux::Config::default();
+
+        for (i, security) in self.security.iter().enumerate() {
+            if self.security[..i].contains(security) {
+                return Err(Error::DuplicateSecurity(*security));
+            }
+        }
+
+        match self.security.as_slice() {
+            [] => Err(Error::MissingSecurity),
+            #[cfg(feature = "noise")]
+            [Security::Noise] => Ok(upgrade(transport, noise(keypair)?, muxer)),
+            #[cfg(feature = "tls")]
+            [Security::Tls] => Ok(upgrade(transport, tls(keypair)?, muxer)),
+            #[cfg(all(feature = "noise", feature = "tls"))]
+            [Security::Noise, Security::Tls] => Ok(upgrade(
+                transport,
+                crate::builder::select_security::SelectSecurityUpgrade::new(
+                    noise(keypair)?,
+                    tls(keypair)?,
+                ),
+                muxer,
+            )),
+            #[cfg(all(feature = "noise", feature = "tls"))]
+            [Security::Tls, Security::Noise] => Ok(upgrade(
+                transport,
+                crate::builder::select_security::SelectSecurityUpgrade::new(
+                    tls(keypair)?,
+                    noise(keypair)?,
+                ),
+                muxer,
+            )),
+            security => Err(Error::Unsupported(
+                security
+                    .iter()
+                    .find(|s| !s.is_supported())
+                    .map_or("security protocol", Security::name),
+            )),
+        }
+    }
+
+    #[cfg(not(feature = "yamux"))]
+    fn authenticate(
+        &self,
+        _: Boxed<BoxedStream>,
+        _: &Keypair,
+    ) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Error> {
+        Err(Error::Unsupported("yamux"))
+    }
+}
+
+/// Stream of a transport before the security and multiplexer upgrades.
+trait AsyncReadWrite: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> AsyncReadWrite for T {}
+
+type BoxedStream = Box<dyn AsyncReadWrite>;
+
+/// Combines `b` with the optional transport `a`, preferring `b`.
+fn or<O: 'static>(a: Option<Boxed<O>>, b: Boxed<O>) -> Boxed<O> {
+    match a {
+        None => b,
+        Some(a) => b
+            .or_transport(a)
+            .map(|either, _| either.into_inner())
+            .boxed(),
+    }
+}
+
+#[cfg(feature = "yamux")]
+fn upgrade<Sec, SecStream, SecError>(
+    transport: Boxed<BoxedStream>,
+    security: Sec,
+    muxer: libp2p_yamux::Config,
+) -> Boxed<(PeerId, StreamMuxerBox)>
+where
+    Sec: InboundConnectionUpgrade<
+            Negotiated<BoxedStream>,
+            Output = (PeerId, SecStream),
+            Error = SecError,
+        > + OutboundConnectionUpgrade<
+            Negotiated<BoxedStream>,
+            Output = (PeerId, SecStream),
+            Error = SecError,
+        > + Clone
+        + Send
+        + 'static,
+    <Sec as InboundConnectionUpgrade<Negotiated<BoxedStream>>>::Future: Send,
+    <Sec as OutboundConnectionUpgrade<Negotiated<BoxedStream>>>::Future: Send,
+    <<Sec as UpgradeInfo>::InfoIter as IntoIterator>::IntoIter: Send,
+    <Sec as UpgradeInfo>::Info: Send,
+    SecStream: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    SecError: StdError + Send + Sync + 'static,
+{
+    transport
+        .upgrade(libp2p_core::upgrade::Version::V1Lazy)
+        .authenticate(security)
+        .multiplex(muxer)
+        .map(|(peer, muxer), _| (peer, StreamMuxerBox::new(muxer)))
+        .boxed()
+}
+
+#[cfg(feature = "noise")]
+fn noise(keypair: &Keypair) -> Result<libp2p_noise::Config, Error> {
+    libp2p_noise::Config::new(keypair).map_err(|e| Error::Setup("noise", Box::new(e)))
+}
+
+#[cfg(feature = "tls")]
+fn tls(keypair: &Keypair) -> Result<libp2p_tls::Config, Error> {
+    libp2p_tls::Config::new(keypair).map_err(|e| Error::Setup("tls", Box::new(e)))
+}
+
+#[cfg(feature = "quic")]
+fn quic(keypair: &Keypair) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Error> {
+    Ok(
+        libp2p_quic::tokio::Transport::new(libp2p_quic::Config::new(keypair))
+            .map(|(peer, connection), _| (peer, StreamMuxerBox::new(connection)))
+            .boxed(),
+    )
+}
+
+#[cfg(not(feature = "quic"))]
+fn quic(_: &Keypair) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Error> {
+    Err(Error::Unsupported("quic"))
+}
+
+#[cfg(feature = "dns")]
+fn dns(
+    transport: Boxed<(PeerId, StreamMuxerBox)>,
+) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Error> {
+    Ok(libp2p_dns::tokio::Transport::system(transport)
+        .map_err(|e| Error::Setup("dns", Box::new(e)))?
+        .boxed())
+}
+
+#[cfg(not(feature = "dns"))]
+fn dns(_: Boxed<(PeerId, StreamMuxerBox)>) -> Result<Boxed<(PeerId, StreamMuxerBox)>, Error> {
+    Err(Error::Unsupported("dns"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(all(
+        feature = "tcp",
+        feature = "quic",
+        feature = "noise",
+        feature = "yamux"
+    ))]
+    fn default_config_builds() {
+        let keypair = Keypair::generate_ed25519();
+        assert!(TransportConfig::default().build(&keypair).is_ok());
+    }
+
+    #[test]
+    fn invalid_configs_are_rejected() {
+        let keypair = Keypair::generate_ed25519();
+
+        let config = TransportConfig {
+            tcp: false,
+            quic: false,
+            ..Default::default()
+        };
+        assert!(matches!(config.build(&keypair), Err(Error::NoTransport)));
+
+        let config = TransportConfig {
+            pnet: Some(String::new()),
+            ..Default::default()
+        };
+        assert!(matches!(config.build(&keypair), Err(Error::PnetWithQuic)));
+    }
+
+    #[test]
+    #[cfg(all(feature = "tcp", feature = "noise", feature = "yamux"))]
+    fn duplicate_security_is_rejected() {
+        let keypair = Keypair::generate_ed25519();
+        let config = TransportConfig {
+            quic: false,
+            security: vec![Security::Noise, Security::Noise],
+            ..Default::default()
+        };
+        assert!(matches!(
+            config.build(&keypair),
+            Err(Error::DuplicateSecurity(Security::Noise))
+        ));
+    }
+}
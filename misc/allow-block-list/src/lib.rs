@@ -60,17 +60,23 @@
 //! };
 //! # }
 //! ```
+//!
+//! Peers can also be blocked for a limited time via [`Behaviour::block_peer_for`], after which
+//! they are unblocked automatically and an [`Event::BlockExpired`] is reported.
 
+use futures::FutureExt;
+use futures_timer::Delay;
+use instant::Instant;
 use libp2p_core::{Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
 use libp2p_swarm::{
     dummy, CloseConnection, ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, THandler,
     THandlerInEvent, THandlerOutEvent, ToSwarm,
 };
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 use std::task::{Context, Poll, Waker};
-use void::Void;
+use std::time::Duration;
 
 /// A [`NetworkBehaviour`] that can act as an allow or block list.
 #[derive(Default, Debug)]
@@ -90,6 +96,19 @@ pub struct AllowedPeers {
 #[derive(Default)]
 pub struct BlockedPeers {
     peers: HashSet<PeerId>,
+    /// When the peers blocked via [`Behaviour::block_peer_for`] are unblocked again.
+    expiries: HashMap<PeerId, Instant>,
+    /// Timer for the soonest entry of `expiries`.
+    timer: Option<Delay>,
+}
+
+/// Event emitted by the [`Behaviour`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// A peer blocked via [`Behaviour::block_peer_for`] has been unblocked because the period
+    /// elapsed.
+    BlockExpired { peer_id: PeerId },
 }
 
 impl Behaviour<AllowedPeers> {
@@ -119,6 +138,22 @@ impl Behaviour<BlockedPeers> {
     /// All active connections to this peer will be closed immediately.
     pub fn block_peer(&mut self, peer: PeerId) {
         self.state.peers.insert(peer);
+        self.state.expiries.remove(&peer);
+        self.close_connections.push_back(peer);
+        if let Some(waker) = self.waker.take() {
+            waker.wake()
+        }
+    }
+
+    /// Block connections to a given peer for the given duration.
+    ///
+    /// All active connections to this peer will be closed immediately. Once the duration
+    /// elapsed, the peer is unblocked and [`Event::BlockExpired`] is emitted, unless it has been
+    /// blocked or unblocked again in the meantime.
+    pub fn block_peer_for(&mut self, peer: PeerId, duration: Duration) {
+        self.state.peers.insert(peer);
+        self.state.expiries.insert(peer, Instant::now() + duration);
+        self.state.timer = None;
         self.close_connections.push_back(peer);
         if let Some(waker) = self.waker.take() {
             waker.wake()
@@ -128,6 +163,7 @@ impl Behaviour<BlockedPeers> {
     /// Unblock connections to a given peer.
     pub fn unblock_peer(&mut self, peer: PeerId) {
         self.state.peers.remove(&peer);
+        self.state.expiries.remove(&peer);
         if let Some(waker) = self.waker.take() {
             waker.wake()
         }
@@ -164,6 +200,11 @@ impl std::error::Error for Blocked {}
 
 trait Enforce: 'static {
     fn enforce(&self, peer: &PeerId) -> Result<(), ConnectionDenied>;
+
+    /// Polls for the next peer whose time-limited entry expired.
+    fn poll_expired(&mut self, _cx: &mut Context<'_>) -> Poll<PeerId> {
+        Poll::Pending
+    }
 }
 
 impl Enforce for AllowedPeers {
@@ -184,6 +225,26 @@ impl Enforce for BlockedPeers {
 
         Ok(())
     }
+
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<PeerId> {
+        loop {
+            let Some((&peer, &deadline)) = self.expiries.iter().min_by_key(|(_, d)| **d) else {
+                self.timer = None;
+                return Poll::Pending;
+            };
+
+            let now = Instant::now();
+            if deadline <= now {
+                self.expiries.remove(&peer);
+                self.peers.remove(&peer);
+                return Poll::Ready(peer);
+            }
+
+            let timer = self.timer.get_or_insert_with(|| Delay::new(deadline - now));
+            futures::ready!(timer.poll_unpin(cx));
+            self.timer = None;
+        }
+    }
 }
 
 impl<S> NetworkBehaviour for Behaviour<S>
@@ -191,7 +252,7 @@ where
     S: Enforce,
 {
     type ConnectionHandler = dummy::ConnectionHandler;
-    type ToSwarm = Void;
+    type ToSwarm = Event;
 
     fn handle_established_inbound_connection(
         &mut self,
@@ -253,6 +314,10 @@ where
             });
         }
 
+        if let Poll::Ready(peer_id) = self.state.poll_expired(cx) {
+            return Poll::Ready(ToSwarm::GenerateEvent(Event::BlockExpired { peer_id }));
+        }
+
         self.waker = Some(cx.waker().clone());
         Poll::Pending
     }
@@ -292,6 +357,45 @@ mod tests {
         dial(&mut dialer, &listener).unwrap();
     }
 
+    #[async_std::test]
+    async fn can_dial_peer_after_block_expired() {
+        let mut dialer = Swarm::new_ephemeral(|_| Behaviour::<BlockedPeers>::default());
+        let mut listener = Swarm::new_ephemeral(|_| Behaviour::<BlockedPeers>::default());
+        listener.listen().with_memory_addr_external().await;
+        let listener_id = *listener.local_peer_id();
+
+        dialer
+            .behaviour_mut()
+            .block_peer_for(listener_id, Duration::from_millis(100));
+
+        let DialError::Denied { cause } = dial(&mut dialer, &listener).unwrap_err() else {
+            panic!("unexpected dial error")
+        };
+        assert!(cause.downcast::<Blocked>().is_ok());
+
+        let expired = dialer
+            .wait(|e| match e {
+                SwarmEvent::Behaviour(Event::BlockExpired { peer_id }) => Some(peer_id),
+                _ => None,
+            })
+            .await;
+        assert_eq!(expired, listener_id);
+
+        dial(&mut dialer, &listener).unwrap();
+    }
+
+    #[test]
+    fn block_peer_overrides_block_peer_for() {
+        let mut behaviour = Behaviour::<BlockedPeers>::default();
+        let peer = PeerId::random();
+
+        behaviour.block_peer_for(peer, Duration::ZERO);
+        behaviour.block_peer(peer);
+
+        assert!(behaviour.state.expiries.is_empty());
+        assert!(behaviour.state.enforce(&peer).is_err());
+    }
+
     #[async_std::test]
     async fn blocked_peer_cannot_dial_us() {
         let mut dialer = Swarm::new_ephemeral(|_| Behaviour::<BlockedPeers>::default());
@@ -18,7 +18,7 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use libp2p_core::{ConnectedPoint, Endpoint, Multiaddr};
+use libp2p_core::{multiaddr::Protocol, ConnectedPoint, Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
 use libp2p_swarm::{
     behaviour::{ConnectionEstablished, DialFailure, ListenFailure},
@@ -27,8 +27,19 @@ use libp2p_swarm::{
 };
 use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::net::IpAddr;
+use std::num::NonZeroU32;
 use std::task::{Context, Poll};
+use std::time::Duration;
 use void::Void;
+use web_time::Instant;
+
+mod rate_limit;
+mod subnet;
+
+use rate_limit::{InboundRateLimiter, RateExceeded};
+
+pub use subnet::{InvalidSubnet, IpSubnet};
 
 /// A [`NetworkBehaviour`] that enforces a set of [`ConnectionLimits`].
 ///
@@ -42,6 +53,20 @@ use void::Void;
 ///
 /// If you employ multiple [`NetworkBehaviour`]s that manage connections, it may also be a different error.
 ///
+/// Slots can be reserved for relay connections via [`ConnectionLimits::with_reserved_relay_slots`],
+/// so that a node behind a NAT stays reachable even when its limits are otherwise saturated.
+/// Connections over relayed circuits, as well as connections to peers tagged via
+/// [`Behaviour::add_relay_server`], are considered relay connections.
+///
+/// Incoming connections can also be limited per IP subnet of the remote, or denied for entire
+/// subnets, see [`ConnectionLimits::with_max_pending_and_established_per_subnet`] and
+/// [`ConnectionLimits::with_denied_subnet`]. Connections whose remote address does not start
+/// with an IP address, e.g. relayed circuits, are exempt.
+///
+/// To survive connection floods, the rate of incoming connection attempts can be limited as well,
+/// see [`ConnectionLimits::with_inbound_connection_rate`] and
+/// [`ConnectionLimits::with_inbound_connection_rate_per_ip`].
+///
 /// # Example
 ///
 /// ```rust
@@ -66,6 +91,10 @@ pub struct Behaviour {
     established_inbound_connections: HashSet<ConnectionId>,
     established_outbound_connections: HashSet<ConnectionId>,
     established_per_peer: HashMap<PeerId, HashSet<ConnectionId>>,
+    relay_servers: HashSet<PeerId>,
+    /// The remote IP addresses of pending and established incoming connections.
+    inbound_ips: HashMap<ConnectionId, IpAddr>,
+    inbound_rate_limiter: InboundRateLimiter,
 }
 
 impl Behaviour {
@@ -77,6 +106,9 @@ impl Behaviour {
             established_inbound_connections: Default::default(),
             established_outbound_connections: Default::default(),
             established_per_peer: Default::default(),
+            relay_servers: Default::default(),
+            inbound_ips: Default::default(),
+            inbound_rate_limiter: Default::default(),
         }
     }
 
@@ -85,6 +117,63 @@ impl Behaviour {
     pub fn limits_mut(&mut self) -> &mut ConnectionLimits {
         &mut self.limits
     }
+
+    /// Tags a peer as relay server, allowing connections to it to use the reserved relay slots.
+    ///
+    /// Returns `false` if the peer was already tagged.
+    pub fn add_relay_server(&mut self, peer: PeerId) -> bool {
+        self.relay_servers.insert(peer)
+    }
+
+    /// Removes the relay server tag from a peer.
+    ///
+    /// Returns `false` if the peer was not tagged.
+    pub fn remove_relay_server(&mut self, peer: &PeerId) -> bool {
+        self.relay_servers.remove(peer)
+    }
+
+    /// Whether a connection to `peer` over `addr` may use the reserved relay slots.
+    fn is_relay_connection(&self, peer: &PeerId, addr: &Multiaddr) -> bool {
+        self.relay_servers.contains(peer) || addr.iter().any(|p| p == Protocol::P2pCircuit)
+    }
+
+    /// Checks the subnet limits for an incoming connection from `ip`.
+    fn check_subnet(&self, ip: &IpAddr) -> Result<(), ConnectionDenied> {
+        if let Some(subnet) = self.limits.denied_subnets.iter().find(|s| s.contains(ip)) {
+            return Err(ConnectionDenied::new(DeniedSubnet { subnet: *subnet }));
+        }
+
+        let (prefix_len, Some(limit)) = (match ip {
+            IpAddr::V4(_) => self.limits.max_per_ipv4_subnet,
+            IpAddr::V6(_) => self.limits.max_per_ipv6_subnet,
+        }) else {
+            return Ok(());
+        };
+        let Some(subnet) = IpSubnet::new(*ip, prefix_len) else {
+            return Ok(());
+        };
+        check_limit(
+            Some(limit),
+            self.inbound_ips
+                .values()
+                .filter(|ip| subnet.contains(ip))
+                .count(),
+            Kind::PerSubnet,
+        )
+    }
+}
+
+/// Returns the IP address of a remote address unless the connection is relayed.
+fn remote_ip(addr: &Multiaddr) -> Option<IpAddr> {
+    if addr.iter().any(|p| p == Protocol::P2pCircuit) {
+        return None;
+    }
+
+    match addr.iter().next()? {
+        Protocol::Ip4(ip) => Some(ip.into()),
+        Protocol::Ip6(ip) => Some(ip.into()),
+        _ => None,
+    }
 }
 
 fn check_limit(limit: Option<u32>, current: usize, kind: Kind) -> Result<(), ConnectionDenied> {
@@ -129,6 +218,9 @@ enum Kind {
     EstablishedOutgoing,
     EstablishedPerPeer,
     EstablishedTotal,
+    PerSubnet,
+    IncomingRate,
+    IncomingRatePerIp,
 }
 
 impl fmt::Display for Kind {
@@ -140,12 +232,41 @@ impl fmt::Display for Kind {
             Kind::EstablishedOutgoing => write!(f, "established outgoing connections"),
             Kind::EstablishedPerPeer => write!(f, "established connections per peer"),
             Kind::EstablishedTotal => write!(f, "established connections"),
+            Kind::PerSubnet => write!(f, "incoming connections per subnet"),
+            Kind::IncomingRate => write!(f, "incoming connection attempts per interval"),
+            Kind::IncomingRatePerIp => {
+                write!(
+                    f,
+                    "incoming connection attempts per IP address and interval"
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for Exceeded {}
 
+/// A connection was denied because the remote's IP address is in a denied subnet, see
+/// [`ConnectionLimits::with_denied_subnet`].
+#[derive(Debug, Clone, Copy)]
+pub struct DeniedSubnet {
+    subnet: IpSubnet,
+}
+
+impl DeniedSubnet {
+    pub fn subnet(&self) -> IpSubnet {
+        self.subnet
+    }
+}
+
+impl fmt::Display for DeniedSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "connections from {} are denied", self.subnet)
+    }
+}
+
+impl std::error::Error for DeniedSubnet {}
+
 /// The configurable connection limits.
 #[derive(Debug, Clone, Default)]
 pub struct ConnectionLimits {
@@ -155,6 +276,12 @@ pub struct ConnectionLimits {
     max_established_outgoing: Option<u32>,
     max_established_per_peer: Option<u32>,
     max_established_total: Option<u32>,
+    reserved_relay_slots: u32,
+    max_per_ipv4_subnet: (u8, Option<u32>),
+    max_per_ipv6_subnet: (u8, Option<u32>),
+    denied_subnets: Vec<IpSubnet>,
+    inbound_connection_rate: Option<(NonZeroU32, Duration)>,
+    inbound_connection_rate_per_ip: Option<(NonZeroU32, Duration)>,
 }
 
 impl ConnectionLimits {
@@ -199,6 +326,80 @@ impl ConnectionLimits {
         self.max_established_per_peer = limit;
         self
     }
+
+    /// Configures the number of established connection slots that are reserved for relay
+    /// connections, i.e. connections to tagged relay servers and relayed circuits.
+    ///
+    /// The incoming, outgoing and total established connection limits are reduced by this number
+    /// for all other connections.
+    pub fn with_reserved_relay_slots(mut self, slots: u32) -> Self {
+        self.reserved_relay_slots = slots;
+        self
+    }
+
+    /// Configures the maximum number of concurrent pending and established incoming connections
+    /// from the same subnet, given by the length of its prefix for IPv4 and IPv6 respectively.
+    ///
+    /// E.g. `with_max_pending_and_established_per_subnet(24, 64, Some(5))` allows at most 5
+    /// incoming connections per IPv4 `/24` and per IPv6 `/64`. A prefix length exceeding the
+    /// number of bits of the address disables the limit for that address family.
+    pub fn with_max_pending_and_established_per_subnet(
+        mut self,
+        ipv4_prefix_len: u8,
+        ipv6_prefix_len: u8,
+        limit: Option<u32>,
+    ) -> Self {
+        self.max_per_ipv4_subnet = (ipv4_prefix_len, limit);
+        self.max_per_ipv6_subnet = (ipv6_prefix_len, limit);
+        self
+    }
+
+    /// Denies all incoming connections from the given subnet.
+    pub fn with_denied_subnet(mut self, subnet: IpSubnet) -> Self {
+        self.denied_subnets.push(subnet);
+        self
+    }
+
+    /// Configures the maximum rate of new incoming connection attempts.
+    ///
+    /// Allows bursts of up to `limit` attempts, refilled at a rate of `limit` per `interval`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn with_inbound_connection_rate(mut self, limit: NonZeroU32, interval: Duration) -> Self {
+        assert!(!interval.is_zero());
+        self.inbound_connection_rate = Some((limit, interval));
+        self
+    }
+
+    /// Configures the maximum rate of new incoming connection attempts from the same IP address,
+    /// like [`ConnectionLimits::with_inbound_connection_rate`].
+    ///
+    /// Attempts denied because of this limit do not count towards the global rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn with_inbound_connection_rate_per_ip(
+        mut self,
+        limit: NonZeroU32,
+        interval: Duration,
+    ) -> Self {
+        assert!(!interval.is_zero());
+        self.inbound_connection_rate_per_ip = Some((limit, interval));
+        self
+    }
+
+    /// Returns the limit that applies to a connection, taking the reserved relay slots into
+    /// account.
+    fn effective(&self, limit: Option<u32>, is_relay: bool) -> Option<u32> {
+        if is_relay {
+            return limit;
+        }
+
+        limit.map(|limit| limit.saturating_sub(self.reserved_relay_slots))
+    }
 }
 
 impl NetworkBehaviour for Behaviour {
@@ -209,7 +410,7 @@ impl NetworkBehaviour for Behaviour {
         &mut self,
         connection_id: ConnectionId,
         _: &Multiaddr,
-        _: &Multiaddr,
+        remote_addr: &Multiaddr,
     ) -> Result<(), ConnectionDenied> {
         check_limit(
             self.limits.max_pending_incoming,
@@ -217,7 +418,33 @@ impl NetworkBehaviour for Behaviour {
             Kind::PendingIncoming,
         )?;
 
+        let remote_ip = remote_ip(remote_addr);
+        if let Some(ip) = &remote_ip {
+            self.check_subnet(ip)?;
+        }
+
+        self.inbound_rate_limiter
+            .try_next(
+                self.limits.inbound_connection_rate,
+                self.limits.inbound_connection_rate_per_ip,
+                remote_ip,
+                Instant::now(),
+            )
+            .map_err(|exceeded| {
+                let (limit, kind) = match exceeded {
+                    RateExceeded::Global(limit) => (limit, Kind::IncomingRate),
+                    RateExceeded::PerIp(limit) => (limit, Kind::IncomingRatePerIp),
+                };
+                ConnectionDenied::new(Exceeded {
+                    limit: limit.get(),
+                    kind,
+                })
+            })?;
+
         self.pending_inbound_connections.insert(connection_id);
+        if let Some(ip) = remote_ip {
+            self.inbound_ips.insert(connection_id, ip);
+        }
 
         Ok(())
     }
@@ -227,12 +454,15 @@ impl NetworkBehaviour for Behaviour {
         connection_id: ConnectionId,
         peer: PeerId,
         _: &Multiaddr,
-        _: &Multiaddr,
+        remote_addr: &Multiaddr,
     ) -> Result<THandler<Self>, ConnectionDenied> {
         self.pending_inbound_connections.remove(&connection_id);
 
+        let is_relay = self.is_relay_connection(&peer, remote_addr);
+
         check_limit(
-            self.limits.max_established_incoming,
+            self.limits
+                .effective(self.limits.max_established_incoming, is_relay),
             self.established_inbound_connections.len(),
             Kind::EstablishedIncoming,
         )?;
@@ -245,7 +475,8 @@ impl NetworkBehaviour for Behaviour {
             Kind::EstablishedPerPeer,
         )?;
         check_limit(
-            self.limits.max_established_total,
+            self.limits
+                .effective(self.limits.max_established_total, is_relay),
             self.established_inbound_connections.len()
                 + self.established_outbound_connections.len(),
             Kind::EstablishedTotal,
@@ -276,13 +507,16 @@ impl NetworkBehaviour for Behaviour {
         &mut self,
         connection_id: ConnectionId,
         peer: PeerId,
-        _: &Multiaddr,
+        addr: &Multiaddr,
         _: Endpoint,
     ) -> Result<THandler<Self>, ConnectionDenied> {
         self.pending_outbound_connections.remove(&connection_id);
 
+        let is_relay = self.is_relay_connection(&peer, addr);
+
         check_limit(
-            self.limits.max_established_outgoing,
+            self.limits
+                .effective(self.limits.max_established_outgoing, is_relay),
             self.established_outbound_connections.len(),
             Kind::EstablishedOutgoing,
         )?;
@@ -295,7 +529,8 @@ impl NetworkBehaviour for Behaviour {
             Kind::EstablishedPerPeer,
         )?;
         check_limit(
-            self.limits.max_established_total,
+            self.limits
+                .effective(self.limits.max_established_total, is_relay),
             self.established_inbound_connections.len()
                 + self.established_outbound_connections.len(),
             Kind::EstablishedTotal,
@@ -313,6 +548,7 @@ impl NetworkBehaviour for Behaviour {
             }) => {
                 self.established_inbound_connections.remove(&connection_id);
                 self.established_outbound_connections.remove(&connection_id);
+                self.inbound_ips.remove(&connection_id);
                 self.established_per_peer
                     .entry(peer_id)
                     .or_default()
@@ -343,6 +579,7 @@ impl NetworkBehaviour for Behaviour {
             }
             FromSwarm::ListenFailure(ListenFailure { connection_id, .. }) => {
                 self.pending_inbound_connections.remove(&connection_id);
+                self.inbound_ips.remove(&connection_id);
             }
             _ => {}
         }
@@ -477,6 +714,125 @@ mod tests {
         quickcheck(prop as fn(_));
     }
 
+    #[test]
+    fn reserved_relay_slots() {
+        let limits = ConnectionLimits::default()
+            .with_max_established_incoming(Some(2))
+            .with_reserved_relay_slots(1);
+        let mut swarm1 = Swarm::new_ephemeral(|_| Behaviour::new(limits.clone()));
+        let mut swarm2 = Swarm::new_ephemeral(|_| Behaviour::new(ConnectionLimits::default()));
+        let mut relay = Swarm::new_ephemeral(|_| Behaviour::new(ConnectionLimits::default()));
+
+        swarm1
+            .behaviour_mut()
+            .limits
+            .add_relay_server(*relay.local_peer_id());
+
+        async_std::task::block_on(async {
+            let (listen_addr, _) = swarm1.listen().with_memory_addr_external().await;
+
+            // Regular connections may only use the non-reserved slot.
+            swarm2.connect(&mut swarm1).await;
+            // The relay server may use the reserved slot.
+            relay.connect(&mut swarm1).await;
+            async_std::task::spawn(relay.loop_on_next());
+
+            swarm2.dial(listen_addr).unwrap();
+            async_std::task::spawn(swarm2.loop_on_next());
+
+            let cause = swarm1
+                .wait(|event| match event {
+                    SwarmEvent::IncomingConnectionError {
+                        error: ListenError::Denied { cause },
+                        ..
+                    } => Some(cause),
+                    _ => None,
+                })
+                .await;
+
+            assert_eq!(cause.downcast::<Exceeded>().unwrap().limit, 1);
+        });
+    }
+
+    #[test]
+    fn subnet_limits() {
+        let limits = ConnectionLimits::default()
+            .with_max_pending_and_established_per_subnet(24, 64, Some(2))
+            .with_denied_subnet("198.51.100.0/24".parse().unwrap());
+        let mut behaviour = super::Behaviour::new(limits);
+        let local: Multiaddr = "/ip4/127.0.0.1/tcp/4001".parse().unwrap();
+        let mut next_id = 0;
+        let mut connect = |behaviour: &mut super::Behaviour, remote: &str| {
+            next_id += 1;
+            let id = ConnectionId::new_unchecked(next_id);
+            behaviour
+                .handle_pending_inbound_connection(id, &local, &remote.parse().unwrap())
+                .map(|()| id)
+        };
+
+        connect(&mut behaviour, "/ip4/192.0.2.1/tcp/1").unwrap();
+        let second = connect(&mut behaviour, "/ip4/192.0.2.2/tcp/1").unwrap();
+        let cause = connect(&mut behaviour, "/ip4/192.0.2.3/tcp/1").unwrap_err();
+        assert_eq!(cause.downcast::<Exceeded>().unwrap().limit(), 2);
+
+        // Other subnets and relayed connections are not affected.
+        connect(&mut behaviour, "/ip4/192.0.3.1/tcp/1").unwrap();
+        connect(
+            &mut behaviour,
+            &format!("/ip4/192.0.2.4/tcp/1/p2p/{}/p2p-circuit", PeerId::random()),
+        )
+        .unwrap();
+
+        // A closed slot can be used again.
+        behaviour.on_swarm_event(FromSwarm::ListenFailure(ListenFailure {
+            local_addr: &local,
+            send_back_addr: &local,
+            error: &ListenError::Aborted,
+            connection_id: second,
+        }));
+        connect(&mut behaviour, "/ip4/192.0.2.3/tcp/1").unwrap();
+
+        let cause = connect(&mut behaviour, "/ip4/198.51.100.7/tcp/1").unwrap_err();
+        assert_eq!(
+            cause
+                .downcast::<DeniedSubnet>()
+                .unwrap()
+                .subnet()
+                .to_string(),
+            "198.51.100.0/24"
+        );
+    }
+
+    #[test]
+    fn inbound_connection_rate() {
+        let limits = ConnectionLimits::default()
+            .with_inbound_connection_rate(NonZeroU32::new(3).unwrap(), Duration::from_secs(60))
+            .with_inbound_connection_rate_per_ip(
+                NonZeroU32::new(2).unwrap(),
+                Duration::from_secs(60),
+            );
+        let mut behaviour = super::Behaviour::new(limits);
+        let local: Multiaddr = "/ip4/127.0.0.1/tcp/4001".parse().unwrap();
+        let mut next_id = 0;
+        let mut connect = |behaviour: &mut super::Behaviour, remote: &str| {
+            next_id += 1;
+            behaviour.handle_pending_inbound_connection(
+                ConnectionId::new_unchecked(next_id),
+                &local,
+                &remote.parse().unwrap(),
+            )
+        };
+
+        connect(&mut behaviour, "/ip4/192.0.2.1/tcp/1").unwrap();
+        connect(&mut behaviour, "/ip4/192.0.2.1/tcp/2").unwrap();
+        let cause = connect(&mut behaviour, "/ip4/192.0.2.1/tcp/3").unwrap_err();
+        assert_eq!(cause.downcast::<Exceeded>().unwrap().limit(), 2);
+
+        connect(&mut behaviour, "/ip4/192.0.2.2/tcp/1").unwrap();
+        let cause = connect(&mut behaviour, "/ip4/192.0.2.3/tcp/1").unwrap_err();
+        assert_eq!(cause.downcast::<Exceeded>().unwrap().limit(), 3);
+    }
+
     /// Another sibling [`NetworkBehaviour`] implementation might deny established connections in
     /// [`handle_established_outbound_connection`] or [`handle_established_inbound_connection`].
     /// [`Behaviour`] must not increase the established counters in
@@ -0,0 +1,214 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::num::NonZeroU32;
+use std::time::Duration;
+use web_time::Instant;
+
+/// The maximum number of per-IP buckets.
+///
+/// Once reached, full buckets are dropped first, then the ones refilled longest ago.
+const MAX_BUCKETS: usize = 1024;
+
+/// A rate of `limit` events per `interval`, allowing bursts of up to `limit` events.
+pub(crate) type Rate = (NonZeroU32, Duration);
+
+/// Rate limiter using the [Token Bucket] algorithm.
+///
+/// [Token Bucket]: https://en.wikipedia.org/wiki/Token_bucket
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: u32,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new((limit, _): Rate, now: Instant) -> Self {
+        Self {
+            tokens: limit.get(),
+            last_refill: now,
+        }
+    }
+
+    /// Adds the tokens accrued since the last refill, one every `interval / limit`.
+    fn refill(&mut self, (limit, interval): Rate, now: Instant) {
+        let refill_interval = interval / limit.get();
+        let elapsed = now.saturating_duration_since(self.last_refill);
+        let accrued = match refill_interval.as_nanos() {
+            0 => u128::from(limit.get()),
+            nanos => elapsed.as_nanos() / nanos,
+        };
+
+        if self.tokens as u128 + accrued >= u128::from(limit.get()) {
+            self.tokens = limit.get();
+            self.last_refill = now;
+        } else {
+            self.tokens += accrued as u32;
+            self.last_refill += refill_interval * accrued as u32;
+        }
+    }
+
+    fn try_take(&mut self, rate: Rate, now: Instant) -> bool {
+        self.refill(rate, now);
+        match self.tokens.checked_sub(1) {
+            Some(tokens) => {
+                self.tokens = tokens;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Rate limits incoming connection attempts, globally and per remote IP address.
+#[derive(Debug, Default)]
+pub(crate) struct InboundRateLimiter {
+    global: Option<(Rate, TokenBucket)>,
+    per_ip: Option<(Rate, HashMap<IpAddr, TokenBucket>)>,
+}
+
+/// An incoming connection attempt exceeded a rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RateExceeded {
+    Global(NonZeroU32),
+    PerIp(NonZeroU32),
+}
+
+impl InboundRateLimiter {
+    /// Takes a token for an incoming connection attempt from `ip` if neither the `global` nor the
+    /// `per_ip` rate is exceeded.
+    ///
+    /// The buckets are reset when a rate differs from the one they were created with.
+    pub(crate) fn try_next(
+        &mut self,
+        global: Option<Rate>,
+        per_ip: Option<Rate>,
+        ip: Option<IpAddr>,
+        now: Instant,
+    ) -> Result<(), RateExceeded> {
+        if self.global.as_ref().map(|(rate, _)| *rate) != global {
+            self.global = global.map(|rate| (rate, TokenBucket::new(rate, now)));
+        }
+        if self.per_ip.as_ref().map(|(rate, _)| *rate) != per_ip {
+            self.per_ip = per_ip.map(|rate| (rate, HashMap::new()));
+        }
+
+        // Check the per-IP bucket first, so that a single flooding IP does not drain the
+        // global bucket.
+        if let (Some((rate, buckets)), Some(ip)) = (self.per_ip.as_mut(), ip) {
+            if buckets.len() >= MAX_BUCKETS && !buckets.contains_key(&ip) {
+                buckets.retain(|_, bucket| {
+                    bucket.refill(*rate, now);
+                    bucket.tokens < rate.0.get()
+                });
+                if buckets.len() >= MAX_BUCKETS {
+                    let oldest = buckets
+                        .iter()
+                        .min_by_key(|(_, bucket)| bucket.last_refill)
+                        .map(|(ip, _)| *ip);
+                    if let Some(oldest) = oldest {
+                        buckets.remove(&oldest);
+                    }
+                }
+            }
+            let bucket = buckets
+                .entry(ip)
+                .or_insert_with(|| TokenBucket::new(*rate, now));
+            if !bucket.try_take(*rate, now) {
+                return Err(RateExceeded::PerIp(rate.0));
+            }
+        }
+
+        if let Some((rate, bucket)) = self.global.as_mut() {
+            if !bucket.try_take(*rate, now) {
+                return Err(RateExceeded::Global(rate.0));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_bucket_refills_over_interval() {
+        let rate = (NonZeroU32::new(2).unwrap(), Duration::from_secs(10));
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(rate, now);
+
+        assert!(bucket.try_take(rate, now));
+        assert!(bucket.try_take(rate, now));
+        assert!(!bucket.try_take(rate, now));
+
+        // One token is added every 5 seconds.
+        assert!(!bucket.try_take(rate, now + Duration::from_secs(4)));
+        assert!(bucket.try_take(rate, now + Duration::from_secs(5)));
+        assert!(!bucket.try_take(rate, now + Duration::from_secs(9)));
+
+        // The bucket holds at most `limit` tokens.
+        let later = now + Duration::from_secs(60);
+        assert!(bucket.try_take(rate, later));
+        assert!(bucket.try_take(rate, later));
+        assert!(!bucket.try_take(rate, later));
+    }
+
+    #[test]
+    fn per_ip_rate_is_checked_before_global_rate() {
+        let global = (NonZeroU32::new(3).unwrap(), Duration::from_secs(60));
+        let per_ip = (NonZeroU32::new(1).unwrap(), Duration::from_secs(60));
+        let ip_a = "192.0.2.1".parse().unwrap();
+        let ip_b = "192.0.2.2".parse().unwrap();
+        let now = Instant::now();
+        let mut limiter = InboundRateLimiter::default();
+        let mut try_next = |ip| limiter.try_next(Some(global), Some(per_ip), ip, now);
+
+        assert_eq!(try_next(Some(ip_a)), Ok(()));
+        assert_eq!(try_next(Some(ip_a)), Err(RateExceeded::PerIp(per_ip.0)));
+        assert_eq!(try_next(Some(ip_b)), Ok(()));
+        assert_eq!(try_next(None), Ok(()));
+        assert_eq!(try_next(None), Err(RateExceeded::Global(global.0)));
+    }
+
+    #[test]
+    fn per_ip_buckets_are_bounded() {
+        let per_ip = (NonZeroU32::new(1).unwrap(), Duration::from_secs(60));
+        let now = Instant::now();
+        let mut limiter = InboundRateLimiter::default();
+        let ips = (0..=MAX_BUCKETS as u32)
+            .map(|i| IpAddr::from(i.to_be_bytes()))
+            .collect::<Vec<_>>();
+
+        for (i, ip) in ips.iter().enumerate() {
+            let now = now + Duration::from_millis(i as u64);
+            assert_eq!(limiter.try_next(None, Some(per_ip), Some(*ip), now), Ok(()));
+        }
+
+        // None of the buckets is full, so the one refilled longest ago is dropped.
+        let (_, buckets) = limiter.per_ip.as_ref().unwrap();
+        assert_eq!(buckets.len(), MAX_BUCKETS);
+        assert!(!buckets.contains_key(&ips[0]));
+        assert!(buckets.contains_key(&ips[MAX_BUCKETS]));
+    }
+}
@@ -0,0 +1,126 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// An IP address prefix in CIDR notation, e.g. `192.0.2.0/24`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IpSubnet {
+    addr: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpSubnet {
+    /// Creates the subnet of the given length containing `addr`.
+    ///
+    /// Returns `None` if `prefix_len` exceeds the number of bits of the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        Some(Self {
+            addr: mask(addr, prefix_len)?,
+            prefix_len,
+        })
+    }
+
+    /// Returns the first address of the subnet.
+    pub fn addr(&self) -> IpAddr {
+        self.addr
+    }
+
+    /// Returns the length of the prefix in bits.
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    /// Whether the subnet contains the given address.
+    pub fn contains(&self, addr: &IpAddr) -> bool {
+        mask(*addr, self.prefix_len) == Some(self.addr)
+    }
+}
+
+/// Clears all but the first `prefix_len` bits of the address.
+fn mask(addr: IpAddr, prefix_len: u8) -> Option<IpAddr> {
+    match addr {
+        IpAddr::V4(addr) if prefix_len <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            Some(IpAddr::V4((u32::from(addr) & mask).into()))
+        }
+        IpAddr::V6(addr) if prefix_len <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            Some(IpAddr::V6((u128::from(addr) & mask).into()))
+        }
+        _ => None,
+    }
+}
+
+impl fmt::Display for IpSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix_len)
+    }
+}
+
+impl FromStr for IpSubnet {
+    type Err = InvalidSubnet;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix_len) = s.split_once('/').ok_or(InvalidSubnet)?;
+        let addr = addr.parse().map_err(|_| InvalidSubnet)?;
+        let prefix_len = prefix_len.parse().map_err(|_| InvalidSubnet)?;
+
+        Self::new(addr, prefix_len).ok_or(InvalidSubnet)
+    }
+}
+
+/// A string could not be parsed as [`IpSubnet`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSubnet;
+
+impl fmt::Display for InvalidSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid subnet, expected `<address>/<prefix length>`")
+    }
+}
+
+impl std::error::Error for InvalidSubnet {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_contains() {
+        let subnet: IpSubnet = "192.0.2.17/24".parse().unwrap();
+        assert_eq!(subnet.to_string(), "192.0.2.0/24");
+        assert!(subnet.contains(&"192.0.2.255".parse().unwrap()));
+        assert!(!subnet.contains(&"192.0.3.1".parse().unwrap()));
+        assert!(!subnet.contains(&"::ffff:192.0.2.1".parse().unwrap()));
+
+        let subnet: IpSubnet = "2001:db8::/32".parse().unwrap();
+        assert!(subnet.contains(&"2001:db8:1::1".parse().unwrap()));
+        assert!(!subnet.contains(&"2001:db9::1".parse().unwrap()));
+
+        let any: IpSubnet = "0.0.0.0/0".parse().unwrap();
+        assert!(any.contains(&"203.0.113.1".parse().unwrap()));
+
+        assert_eq!("192.0.2.0/33".parse::<IpSubnet>(), Err(InvalidSubnet));
+        assert_eq!("192.0.2.0".parse::<IpSubnet>(), Err(InvalidSubnet));
+    }
+}
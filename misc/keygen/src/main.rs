@@ -1,5 +1,6 @@
 use base64::prelude::*;
 use std::error::Error;
+use std::io::BufRead;
 use std::path::PathBuf;
 use std::str::{self, FromStr};
 use std::sync::mpsc;
@@ -19,6 +20,13 @@ struct Args {
     #[clap(long, global = true)]
     json: bool,
 
+    /// Additionally write the keypair to an encrypted keystore file at the given path.
+    ///
+    /// The password is read from the `KEYGEN_PASSWORD` environment variable if set, or from
+    /// the first line of stdin otherwise.
+    #[clap(long, global = true)]
+    keystore: Option<PathBuf>,
+
     #[clap(subcommand)]
     cmd: Command,
 }
@@ -31,6 +39,12 @@ enum Command {
         #[clap(value_parser)]
         config: PathBuf,
     },
+    /// Read from an encrypted keystore file
+    Keystore {
+        /// Path to the keystore file
+        #[clap(value_parser)]
+        path: PathBuf,
+    },
     /// Generate random
     Rand {
         /// The keypair prefix
@@ -68,6 +82,13 @@ fn main() -> Result<(), Box<dyn Error>> {
             (peer_id, keypair)
         }
 
+        // Decrypt a keypair from a password protected keystore file
+        Command::Keystore { path } => {
+            let keypair = identity::keystore::load(path, read_password()?.as_bytes())?;
+
+            (keypair.public().into(), keypair)
+        }
+
         // Generate a random keypair, optionally with a prefix
         Command::Rand { prefix } => {
             if let Some(prefix) = prefix {
@@ -111,6 +132,10 @@ fn main() -> Result<(), Box<dyn Error>> {
         }
     };
 
+    if let Some(path) = args.keystore {
+        identity::keystore::store(path, &local_keypair, read_password()?.as_bytes())?;
+    }
+
     if args.json {
         let config = config::Config::from_key_material(local_peer_id, &local_keypair)?;
         println!("{}", serde_json::to_string(&config)?);
@@ -124,3 +149,16 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+fn read_password() -> Result<Zeroizing<String>, Box<dyn Error>> {
+    if let Ok(password) = std::env::var("KEYGEN_PASSWORD") {
+        return Ok(Zeroizing::new(password));
+    }
+
+    let mut password = Zeroizing::new(String::new());
+    std::io::stdin().lock().read_line(&mut password)?;
+    let len = password.trim_end_matches(['\r', '\n']).len();
+    password.truncate(len);
+
+    Ok(password)
+}
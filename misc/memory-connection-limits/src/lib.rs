@@ -18,16 +18,19 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use futures::FutureExt;
+use futures_timer::Delay;
 use libp2p_core::{Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
 use libp2p_swarm::{
     dummy, ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, THandler, THandlerInEvent,
     THandlerOutEvent, ToSwarm,
 };
-use void::Void;
 
 use std::{
+    collections::{HashMap, VecDeque},
     fmt,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
     time::{Duration, Instant},
 };
@@ -44,6 +47,11 @@ use std::{
 ///
 /// If you employ multiple [`NetworkBehaviour`]s that manage connections, it may also be a different error.
 ///
+/// With [Behaviour::with_warning_thresholds], an [`Event::ThresholdReached`] is emitted once the memory
+/// usage reaches a fraction of the limit, before connections are denied. Protocols and behaviours can
+/// report the memory they use through the [`MemoryAttribution`] returned by [Behaviour::attribution],
+/// which is included in the events.
+///
 /// [Behaviour::with_max_bytes] and [Behaviour::with_max_percentage] are mutually exclusive.
 /// If you need to employ both of them, compose two instances of [Behaviour] into your custom behaviour.
 ///
@@ -65,6 +73,14 @@ pub struct Behaviour {
     max_allowed_bytes: usize,
     process_physical_memory_bytes: usize,
     last_refreshed: Instant,
+    /// The fractions of `max_allowed_bytes` to emit an [`Event`] at, in ascending order.
+    warning_thresholds: Vec<f64>,
+    /// The number of `warning_thresholds` currently reached.
+    thresholds_reached: usize,
+    /// Timer for refreshing the memory stats while `warning_thresholds` are configured.
+    check_timer: Delay,
+    attribution: MemoryAttribution,
+    events: VecDeque<Event>,
 }
 
 /// The maximum duration for which the retrieved memory-stats of the process are allowed to be stale.
@@ -72,6 +88,10 @@ pub struct Behaviour {
 /// Once exceeded, we will retrieve new stats.
 const MAX_STALE_DURATION: Duration = Duration::from_millis(100);
 
+/// The interval at which the memory usage is checked against the warning thresholds in the absence
+/// of new connections.
+const THRESHOLD_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 impl Behaviour {
     /// Sets the process memory usage threshold in absolute bytes.
     ///
@@ -83,6 +103,11 @@ impl Behaviour {
                 .map(|s| s.physical_mem)
                 .unwrap_or_default(),
             last_refreshed: Instant::now(),
+            warning_thresholds: Vec::new(),
+            thresholds_reached: 0,
+            check_timer: Delay::new(THRESHOLD_CHECK_INTERVAL),
+            attribution: MemoryAttribution::default(),
+            events: VecDeque::new(),
         }
     }
 
@@ -98,13 +123,27 @@ impl Behaviour {
         Self::with_max_bytes((system_memory_bytes as f64 * percentage).round() as usize)
     }
 
+    /// Emits an [`Event::ThresholdReached`] when the memory usage reaches one of the given fractions
+    /// of the limit, e.g. `0.8`, and an [`Event::ThresholdCleared`] once it dropped below it again.
+    pub fn with_warning_thresholds(mut self, thresholds: impl IntoIterator<Item = f64>) -> Self {
+        self.warning_thresholds = thresholds.into_iter().collect();
+        self.warning_thresholds.sort_by(f64::total_cmp);
+        self
+    }
+
     /// Gets the process memory usage threshold in bytes.
     pub fn max_allowed_bytes(&self) -> usize {
         self.max_allowed_bytes
     }
 
+    /// Gets a handle for protocols and behaviours to report the memory they use.
+    pub fn attribution(&self) -> MemoryAttribution {
+        self.attribution.clone()
+    }
+
     fn check_limit(&mut self) -> Result<(), ConnectionDenied> {
         self.refresh_memory_stats_if_needed();
+        self.check_warning_thresholds();
 
         if self.process_physical_memory_bytes > self.max_allowed_bytes {
             return Err(ConnectionDenied::new(MemoryUsageLimitExceeded {
@@ -132,11 +171,121 @@ impl Behaviour {
         self.last_refreshed = now;
         self.process_physical_memory_bytes = stats.physical_mem;
     }
+
+    fn check_warning_thresholds(&mut self) {
+        let usage = self.process_physical_memory_bytes as f64 / self.max_allowed_bytes as f64;
+        let reached = self
+            .warning_thresholds
+            .iter()
+            .take_while(|threshold| usage >= **threshold)
+            .count();
+
+        while self.thresholds_reached < reached {
+            let threshold = self.warning_thresholds[self.thresholds_reached];
+            self.thresholds_reached += 1;
+            tracing::warn!(
+                %threshold,
+                bytes=%self.process_physical_memory_bytes,
+                "Process memory usage reached warning threshold"
+            );
+            self.events
+                .push_back(Event::ThresholdReached(self.usage(threshold)));
+        }
+        while self.thresholds_reached > reached {
+            self.thresholds_reached -= 1;
+            let threshold = self.warning_thresholds[self.thresholds_reached];
+            self.events
+                .push_back(Event::ThresholdCleared(self.usage(threshold)));
+        }
+    }
+
+    fn usage(&self, threshold: f64) -> MemoryUsage {
+        MemoryUsage {
+            threshold,
+            process_physical_memory_bytes: self.process_physical_memory_bytes,
+            max_allowed_bytes: self.max_allowed_bytes,
+            attributed_bytes: self.attribution.snapshot(),
+        }
+    }
+}
+
+/// Event emitted by the [`Behaviour`] when the memory usage crosses a warning threshold, see
+/// [`Behaviour::with_warning_thresholds`].
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The memory usage reached the threshold.
+    ThresholdReached(MemoryUsage),
+    /// The memory usage dropped below a previously reached threshold.
+    ThresholdCleared(MemoryUsage),
+}
+
+/// The memory usage of the process at the time a threshold was crossed.
+#[derive(Debug, Clone)]
+pub struct MemoryUsage {
+    threshold: f64,
+    process_physical_memory_bytes: usize,
+    max_allowed_bytes: usize,
+    attributed_bytes: Vec<(String, usize)>,
+}
+
+impl MemoryUsage {
+    /// The crossed threshold as a fraction of [`MemoryUsage::max_allowed_bytes`].
+    pub fn threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    pub fn process_physical_memory_bytes(&self) -> usize {
+        self.process_physical_memory_bytes
+    }
+
+    pub fn max_allowed_bytes(&self) -> usize {
+        self.max_allowed_bytes
+    }
+
+    /// The memory reported through the [`MemoryAttribution`], largest first.
+    pub fn attributed_bytes(&self) -> &[(String, usize)] {
+        &self.attributed_bytes
+    }
+}
+
+/// A shared record of the memory used by protocols or behaviours, obtained through
+/// [`Behaviour::attribution`].
+///
+/// The process memory usage can not be attributed automatically, protocols and behaviours that
+/// buffer data are expected to report their usage, e.g. the size of their queues.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryAttribution {
+    bytes: Arc<Mutex<HashMap<String, usize>>>,
+}
+
+impl MemoryAttribution {
+    /// Sets the number of bytes used by `owner`, e.g. a protocol name.
+    pub fn set(&self, owner: impl Into<String>, bytes: usize) {
+        self.bytes.lock().unwrap().insert(owner.into(), bytes);
+    }
+
+    /// Removes the bytes used by `owner`.
+    pub fn remove(&self, owner: &str) {
+        self.bytes.lock().unwrap().remove(owner);
+    }
+
+    /// Returns the bytes used per owner, largest first.
+    pub fn snapshot(&self) -> Vec<(String, usize)> {
+        let mut bytes = self
+            .bytes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(owner, bytes)| (owner.clone(), *bytes))
+            .collect::<Vec<_>>();
+        bytes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        bytes
+    }
 }
 
 impl NetworkBehaviour for Behaviour {
     type ConnectionHandler = dummy::ConnectionHandler;
-    type ToSwarm = Void;
+    type ToSwarm = Event;
 
     fn handle_pending_inbound_connection(
         &mut self,
@@ -189,7 +338,22 @@ impl NetworkBehaviour for Behaviour {
         void::unreachable(event)
     }
 
-    fn poll(&mut self, _: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        if !self.warning_thresholds.is_empty() {
+            while self.check_timer.poll_unpin(cx).is_ready() {
+                self.check_timer.reset(THRESHOLD_CHECK_INTERVAL);
+                self.refresh_memory_stats_if_needed();
+                self.check_warning_thresholds();
+            }
+        }
+
+        if let Some(event) = self.events.pop_front() {
+            return Poll::Ready(ToSwarm::GenerateEvent(event));
+        }
+
         Poll::Pending
     }
 }
@@ -0,0 +1,149 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+mod util;
+
+use libp2p_core::Multiaddr;
+use libp2p_identity::PeerId;
+use libp2p_memory_connection_limits::*;
+use std::time::Duration;
+use util::*;
+
+use libp2p_swarm::{
+    dial_opts::{DialOpts, PeerCondition},
+    Swarm,
+};
+use libp2p_swarm_test::SwarmExt;
+
+#[async_std::test]
+async fn warning_thresholds() {
+    const CONNECTION_LIMIT: usize = 20;
+    const MEMORY_CONSUMER: &str = "mem_consumer";
+
+    let mut network = Swarm::new_ephemeral(|_| TestBehaviour {
+        connection_limits: Behaviour::with_max_bytes(usize::MAX),
+        mem_consumer: ConsumeMemoryBehaviour1MBPending0Established::default(),
+    });
+
+    let addr: Multiaddr = "/memory/1234".parse().unwrap();
+    let target = PeerId::random();
+    let dial = |network: &mut Swarm<TestBehaviour>| {
+        network.dial(
+            DialOpts::peer_id(target)
+                // Always dial, even if already dialing or connected.
+                .condition(PeerCondition::Always)
+                .addresses(vec![addr.clone()])
+                .build(),
+        )
+    };
+
+    // Exercise `dial` function to get more stable memory stats later
+    dial(&mut network).expect("Unexpected connection limit.");
+
+    // Adds current mem usage to the limit and warns halfway to the limit.
+    let current_mem = memory_stats::memory_stats().unwrap().physical_mem;
+    let max_allowed_bytes = current_mem + CONNECTION_LIMIT * 1024 * 1024;
+    let threshold =
+        (current_mem + CONNECTION_LIMIT / 2 * 1024 * 1024) as f64 / max_allowed_bytes as f64;
+    network.behaviour_mut().connection_limits =
+        Behaviour::with_max_bytes(max_allowed_bytes).with_warning_thresholds([threshold]);
+    let attribution = network.behaviour().connection_limits.attribution();
+
+    for i in 0..CONNECTION_LIMIT * 3 / 4 {
+        dial(&mut network).expect("Unexpected connection limit.");
+        attribution.set(MEMORY_CONSUMER, (i + 1) * 1024 * 1024);
+    }
+
+    std::thread::sleep(Duration::from_millis(100)); // Memory stats are only updated every 100ms internally, ensure they are up-to-date when we check the thresholds.
+
+    // Crossing the threshold does not deny connections.
+    dial(&mut network).expect("Unexpected connection limit.");
+
+    match network.next_behaviour_event().await {
+        TestBehaviourEvent::ConnectionLimits(Event::ThresholdReached(usage)) => {
+            assert_eq!(usage.threshold(), threshold);
+            assert_eq!(usage.max_allowed_bytes(), max_allowed_bytes);
+            assert!(
+                usage.process_physical_memory_bytes() as f64
+                    >= threshold * max_allowed_bytes as f64
+            );
+            assert!(usage.process_physical_memory_bytes() < max_allowed_bytes);
+            assert_eq!(
+                usage.attributed_bytes(),
+                [(
+                    MEMORY_CONSUMER.to_owned(),
+                    CONNECTION_LIMIT * 3 / 4 * 1024 * 1024
+                )]
+            );
+        }
+        e => panic!("Unexpected event: {e:?}"),
+    }
+
+    // Release the memory.
+    network.behaviour_mut().mem_consumer = Default::default();
+    attribution.remove(MEMORY_CONSUMER);
+
+    std::thread::sleep(Duration::from_millis(100));
+
+    dial(&mut network).expect("Unexpected connection limit.");
+
+    match network.next_behaviour_event().await {
+        TestBehaviourEvent::ConnectionLimits(Event::ThresholdCleared(usage)) => {
+            assert_eq!(usage.threshold(), threshold);
+            assert!(
+                (usage.process_physical_memory_bytes() as f64)
+                    < threshold * max_allowed_bytes as f64
+            );
+            assert!(usage.attributed_bytes().is_empty());
+        }
+        e => panic!("Unexpected event: {e:?}"),
+    }
+}
+
+#[test]
+fn attribution_is_sorted_by_size() {
+    let behaviour = Behaviour::with_max_bytes(usize::MAX);
+    let attribution = behaviour.attribution();
+
+    attribution.set("gossipsub", 1024);
+    attribution.set("kad", 4096);
+    attribution.set("identify", 1024);
+    // Handles share the same record.
+    behaviour.attribution().set("gossipsub", 2048);
+
+    assert_eq!(
+        attribution.snapshot(),
+        [
+            ("kad".to_owned(), 4096),
+            ("gossipsub".to_owned(), 2048),
+            ("identify".to_owned(), 1024),
+        ]
+    );
+
+    attribution.remove("kad");
+
+    assert_eq!(
+        behaviour.attribution().snapshot(),
+        [
+            ("gossipsub".to_owned(), 2048),
+            ("identify".to_owned(), 1024)
+        ]
+    );
+}
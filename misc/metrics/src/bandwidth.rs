@@ -6,21 +6,25 @@ use futures::{
     ready,
 };
 use libp2p_core::{
-    muxing::{StreamMuxer, StreamMuxerEvent},
+    muxing::{MuxerCounters, StreamMuxer, StreamMuxerEvent, StreamPriority},
     transport::{ListenerId, TransportError, TransportEvent},
     Multiaddr,
 };
 use libp2p_identity::PeerId;
 use prometheus_client::{
-    encoding::{EncodeLabelSet, EncodeLabelValue},
-    metrics::{counter::Counter, family::Family},
+    collector::Collector,
+    encoding::{DescriptorEncoder, EncodeLabelSet, EncodeLabelValue, EncodeMetric},
+    metrics::{counter::Counter, family::Family, gauge::ConstGauge, MetricType},
     registry::{Registry, Unit},
 };
 use std::{
+    collections::HashMap,
     convert::TryFrom as _,
     io,
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
+    time::Duration,
 };
 
 #[derive(Debug, Clone)]
@@ -29,21 +33,27 @@ pub struct Transport<T> {
     #[pin]
     transport: T,
     metrics: Family<Labels, Counter>,
+    muxers: Muxers,
 }
 
 impl<T> Transport<T> {
     pub fn new(transport: T, registry: &mut Registry) -> Self {
         let metrics = Family::<Labels, Counter>::default();
-        registry
-            .sub_registry_with_prefix("libp2p")
-            .register_with_unit(
-                "bandwidth",
-                "Bandwidth usage by direction and transport protocols",
-                Unit::Bytes,
-                metrics.clone(),
-            );
-
-        Transport { transport, metrics }
+        let muxers = Muxers::default();
+        let sub_registry = registry.sub_registry_with_prefix("libp2p");
+        sub_registry.register_with_unit(
+            "bandwidth",
+            "Bandwidth usage by direction and transport protocols",
+            Unit::Bytes,
+            metrics.clone(),
+        );
+        sub_registry.register_collector(Box::new(muxers.clone()));
+
+        Transport {
+            transport,
+            metrics,
+            muxers,
+        }
     }
 }
 
@@ -84,13 +94,22 @@ where
         self.transport.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.transport.remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         let metrics = ConnectionMetrics::from_family_and_addr(&self.metrics, &addr);
+        let muxers = self.muxers.clone();
+        let protocols = protocol_stack::as_string(&addr);
         Ok(self
             .transport
             .dial(addr.clone())?
-            .map_ok(Box::new(|(peer_id, stream_muxer)| {
-                (peer_id, Muxer::new(stream_muxer, metrics))
+            .map_ok(Box::new(move |(peer_id, stream_muxer)| {
+                (
+                    peer_id,
+                    Muxer::new(stream_muxer, metrics, &muxers, protocols),
+                )
             })))
     }
 
@@ -99,11 +118,16 @@ where
         addr: Multiaddr,
     ) -> Result<Self::Dial, TransportError<Self::Error>> {
         let metrics = ConnectionMetrics::from_family_and_addr(&self.metrics, &addr);
+        let muxers = self.muxers.clone();
+        let protocols = protocol_stack::as_string(&addr);
         Ok(self
             .transport
             .dial_as_listener(addr.clone())?
-            .map_ok(Box::new(|(peer_id, stream_muxer)| {
-                (peer_id, Muxer::new(stream_muxer, metrics))
+            .map_ok(Box::new(move |(peer_id, stream_muxer)| {
+                (
+                    peer_id,
+                    Muxer::new(stream_muxer, metrics, &muxers, protocols),
+                )
             })))
     }
 
@@ -125,10 +149,15 @@ where
             }) => {
                 let metrics =
                     ConnectionMetrics::from_family_and_addr(this.metrics, &send_back_addr);
+                let muxers = this.muxers.clone();
+                let protocols = protocol_stack::as_string(&send_back_addr);
                 Poll::Ready(TransportEvent::Incoming {
                     listener_id,
-                    upgrade: upgrade.map_ok(Box::new(|(peer_id, stream_muxer)| {
-                        (peer_id, Muxer::new(stream_muxer, metrics))
+                    upgrade: upgrade.map_ok(Box::new(move |(peer_id, stream_muxer)| {
+                        (
+                            peer_id,
+                            Muxer::new(stream_muxer, metrics, &muxers, protocols),
+                        )
                     })),
                     local_addr,
                     send_back_addr,
@@ -173,6 +202,88 @@ impl ConnectionMetrics {
     }
 }
 
+/// The counters of the stream muxers of all established connections, with the transport
+/// protocols of their connection, see [`StreamMuxer::counters`].
+#[derive(Debug, Clone, Default)]
+struct Muxers(Arc<Mutex<MuxersInner>>);
+
+#[derive(Debug, Default)]
+struct MuxersInner {
+    next_id: u64,
+    counters: HashMap<u64, (String, MuxerCounters)>,
+}
+
+impl Muxers {
+    fn register(&self, protocols: String, counters: MuxerCounters) -> Registration {
+        let mut inner = self.0.lock().unwrap();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        inner.counters.insert(id, (protocols, counters));
+
+        Registration {
+            id,
+            muxers: self.clone(),
+        }
+    }
+}
+
+impl Collector for Muxers {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+        let mut open_streams: HashMap<String, i64> = Default::default();
+        let mut stream_open_failures: HashMap<String, i64> = Default::default();
+        let mut window_stalls: HashMap<String, i64> = Default::default();
+
+        for (protocols, counters) in self.0.lock().unwrap().counters.values() {
+            let stats = counters.stats();
+            *open_streams.entry(protocols.clone()).or_default() += stats.open_streams() as i64;
+            *stream_open_failures.entry(protocols.clone()).or_default() +=
+                stats.stream_open_failures() as i64;
+            *window_stalls.entry(protocols.clone()).or_default() += stats.window_stalls() as i64;
+        }
+
+        for (name, help, counts) in [
+            (
+                "muxer_open_streams",
+                "Number of open streams by transport protocols",
+                open_streams,
+            ),
+            (
+                "muxer_stream_open_failures",
+                "Number of outbound streams that could not be opened on established connections, by transport protocols",
+                stream_open_failures,
+            ),
+            (
+                "muxer_window_stalls",
+                "Number of writes waiting for flow-control credit on established connections, by transport protocols",
+                window_stalls,
+            ),
+        ] {
+            let mut family_encoder =
+                encoder.encode_descriptor(name, help, None, MetricType::Gauge)?;
+            for (protocols, count) in counts {
+                let labels = [("protocols", protocols)];
+                let metric_encoder = family_encoder.encode_family(&labels)?;
+                ConstGauge::new(count).encode(metric_encoder)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Removes the counters of a stream muxer from [`Muxers`] once the muxer is dropped.
+#[derive(Debug)]
+struct Registration {
+    id: u64,
+    muxers: Muxers,
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.muxers.0.lock().unwrap().counters.remove(&self.id);
+    }
+}
+
 /// Wraps around a [`StreamMuxer`] and counts the number of bytes that go through all the opened
 /// streams.
 #[derive(Clone)]
@@ -181,12 +292,22 @@ pub struct Muxer<SMInner> {
     #[pin]
     inner: SMInner,
     metrics: ConnectionMetrics,
+    /// Keeps the counters of the inner stream muxer registered while the muxer is alive.
+    _registration: Option<Arc<Registration>>,
 }
 
-impl<SMInner> Muxer<SMInner> {
+impl<SMInner: StreamMuxer> Muxer<SMInner> {
     /// Creates a new [`Muxer`] wrapping around the provided stream muxer.
-    fn new(inner: SMInner, metrics: ConnectionMetrics) -> Self {
-        Self { inner, metrics }
+    fn new(inner: SMInner, metrics: ConnectionMetrics, muxers: &Muxers, protocols: String) -> Self {
+        let registration = inner
+            .counters()
+            .map(|counters| Arc::new(muxers.register(protocols, counters)));
+
+        Self {
+            inner,
+            metrics,
+            _registration: registration,
+        }
     }
 }
 
@@ -231,10 +352,32 @@ where
         Poll::Ready(Ok(logged))
     }
 
+    fn poll_outbound_with_priority(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        priority: StreamPriority,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        let this = self.project();
+        let inner = ready!(this.inner.poll_outbound_with_priority(cx, priority)?);
+        let logged = InstrumentedStream {
+            inner,
+            metrics: this.metrics.clone(),
+        };
+        Poll::Ready(Ok(logged))
+    }
+
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
         let this = self.project();
         this.inner.poll_close(cx)
     }
+
+    fn preferred_write_size(&self) -> Option<usize> {
+        self.inner.preferred_write_size()
+    }
+
+    fn counters(&self) -> Option<MuxerCounters> {
+        self.inner.counters()
+    }
 }
 
 /// Wraps around an [`AsyncRead`] + [`AsyncWrite`] and logs the bandwidth that goes through it.
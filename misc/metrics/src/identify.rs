@@ -47,6 +47,17 @@ const ALLOWED_PROTOCOLS: &[StreamProtocol] = &[
     libp2p_relay::HOP_PROTOCOL_NAME,
 ];
 
+/// Major capabilities of remotes, keyed by the protocol announcing them.
+///
+/// Protocol names are matched directly, so that peers are classified regardless of the features
+/// enabled on this crate. Only the highest gossipsub version of a peer is counted.
+const CAPABILITIES: &[(&str, &str)] = &[
+    ("/ipfs/kad/1.0.0", "kad_server"),
+    ("/libp2p/circuit/relay/0.2.0/hop", "relay_hop"),
+    ("/meshsub/1.1.0", "gossipsub_v1.1"),
+    ("/meshsub/1.0.0", "gossipsub_v1.0"),
+];
+
 pub(crate) struct Metrics {
     peers: Peers,
     error: Counter,
@@ -156,6 +167,7 @@ impl Collector for Peers {
         let mut count_by_protocols: HashMap<String, i64> = Default::default();
         let mut count_by_listen_addresses: HashMap<String, i64> = Default::default();
         let mut count_by_observed_addresses: HashMap<String, i64> = Default::default();
+        let mut count_by_capability: HashMap<&'static str, i64> = Default::default();
 
         for (_, peer_info) in self.0.lock().unwrap().iter() {
             {
@@ -194,6 +206,20 @@ impl Collector for Peers {
                 }
             }
 
+            {
+                let mut supports_gossipsub = false;
+                for (protocol, capability) in CAPABILITIES {
+                    let is_gossipsub = capability.starts_with("gossipsub");
+                    if is_gossipsub && supports_gossipsub {
+                        continue;
+                    }
+                    if peer_info.protocols.iter().any(|p| p.as_ref() == *protocol) {
+                        supports_gossipsub |= is_gossipsub;
+                        *count_by_capability.entry(*capability).or_default() += 1;
+                    }
+                }
+            }
+
             {
                 let count = count_by_observed_addresses
                     .entry(protocol_stack::as_string(&peer_info.observed_addr))
@@ -217,6 +243,20 @@ impl Collector for Peers {
             }
         }
 
+        {
+            let mut family_encoder = encoder.encode_descriptor(
+                "remote_capabilities",
+                "Number of connected nodes acting as kad server, as relay or supporting a specific gossipsub version",
+                None,
+                MetricType::Gauge,
+            )?;
+            for (capability, count) in count_by_capability.into_iter() {
+                let labels = [("capability", capability)];
+                let metric_encoder = family_encoder.encode_family(&labels)?;
+                ConstGauge::new(count).encode(metric_encoder)?;
+            }
+        }
+
         {
             let mut family_encoder = encoder.encode_descriptor(
                 "remote_listen_addresses",
@@ -42,6 +42,8 @@ mod protocol_stack;
 #[cfg(feature = "relay")]
 mod relay;
 mod swarm;
+#[cfg(feature = "webrtc")]
+mod webrtc;
 
 pub use bandwidth::Transport as BandwidthTransport;
 pub use prometheus_client::registry::Registry;
@@ -61,6 +63,8 @@ pub struct Metrics {
     #[cfg(feature = "relay")]
     relay: relay::Metrics,
     swarm: swarm::Metrics,
+    #[cfg(feature = "webrtc")]
+    webrtc: webrtc::Metrics,
 }
 
 impl Metrics {
@@ -88,6 +92,8 @@ impl Metrics {
             #[cfg(feature = "relay")]
             relay: relay::Metrics::new(sub_registry),
             swarm: swarm::Metrics::new(sub_registry),
+            #[cfg(feature = "webrtc")]
+            webrtc: webrtc::Metrics::new(sub_registry),
         }
     }
 }
@@ -140,6 +146,15 @@ impl Recorder<libp2p_relay::Event> for Metrics {
     }
 }
 
+/// Record a snapshot of a WebRTC connection's statistics, e.g. as obtained by periodically polling
+/// `libp2p_webrtc::tokio::StatsHandle::stats`.
+#[cfg(feature = "webrtc")]
+impl Recorder<libp2p_webrtc_utils::stats::ConnectionStats> for Metrics {
+    fn record(&self, stats: &libp2p_webrtc_utils::stats::ConnectionStats) {
+        self.webrtc.record(stats)
+    }
+}
+
 impl<TBvEv> Recorder<libp2p_swarm::SwarmEvent<TBvEv>> for Metrics {
     fn record(&self, event: &libp2p_swarm::SwarmEvent<TBvEv>) {
         self.swarm.record(event);
@@ -25,6 +25,7 @@ use prometheus_client::registry::Registry;
 
 pub(crate) struct Metrics {
     events: Family<EventLabels, Counter>,
+    circuit_timeouts: Family<CircuitTimeoutLabels, Counter>,
 }
 
 impl Metrics {
@@ -38,10 +39,31 @@ impl Metrics {
             events.clone(),
         );
 
-        Self { events }
+        let circuit_timeouts = Family::default();
+        sub_registry.register(
+            "circuit_timeouts",
+            "Circuits closed by the relay because they were idle or stalled",
+            circuit_timeouts.clone(),
+        );
+
+        Self {
+            events,
+            circuit_timeouts,
+        }
     }
 }
 
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct CircuitTimeoutLabels {
+    reason: CircuitTimeout,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelValue)]
+enum CircuitTimeout {
+    Idle,
+    Stalled,
+}
+
 #[derive(Debug, Clone, Hash, PartialEq, Eq, EncodeLabelSet)]
 struct EventLabels {
     event: EventType,
@@ -98,5 +120,19 @@ impl super::Recorder<libp2p_relay::Event> for Metrics {
                 event: event.into(),
             })
             .inc();
+
+        if let libp2p_relay::Event::CircuitClosed {
+            error: Some(error), ..
+        } = event
+        {
+            let reason = match error.get_ref().and_then(|e| e.downcast_ref()) {
+                Some(libp2p_relay::CircuitTimeout::Idle) => CircuitTimeout::Idle,
+                Some(libp2p_relay::CircuitTimeout::Stalled) => CircuitTimeout::Stalled,
+                None => return,
+            };
+            self.circuit_timeouts
+                .get_or_create(&CircuitTimeoutLabels { reason })
+                .inc();
+        }
     }
 }
@@ -18,16 +18,22 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use crate::protocol_stack;
 use instant::Instant;
 use libp2p_swarm::{ConnectionId, DialError, SwarmEvent};
-use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
+use prometheus_client::collector::Collector;
+use prometheus_client::encoding::{
+    DescriptorEncoder, EncodeLabelSet, EncodeLabelValue, EncodeMetric,
+};
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::ConstGauge;
 use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::metrics::MetricType;
 use prometheus_client::registry::{Registry, Unit};
 
 pub(crate) struct Metrics {
@@ -52,6 +58,7 @@ pub(crate) struct Metrics {
     outgoing_connection_error: Family<OutgoingConnectionErrorLabels, Counter>,
 
     connections: Arc<Mutex<HashMap<ConnectionId, Instant>>>,
+    churn: Churn,
 }
 
 impl Metrics {
@@ -165,6 +172,9 @@ impl Metrics {
             connections_duration.clone(),
         );
 
+        let churn = Churn::default();
+        sub_registry.register_collector(Box::new(churn.clone()));
+
         Self {
             connections_incoming,
             connections_incoming_error,
@@ -181,6 +191,7 @@ impl Metrics {
             connections_establishment_duration,
             connections_duration,
             connections: Default::default(),
+            churn,
         }
     }
 }
@@ -207,6 +218,7 @@ impl<TBvEv> super::Recorder<SwarmEvent<TBvEv>> for Metrics {
                     .lock()
                     .expect("lock not to be poisoned")
                     .insert(*connection_id, Instant::now());
+                self.churn.record_opened();
             }
             SwarmEvent::ConnectionClosed {
                 endpoint,
@@ -230,6 +242,7 @@ impl<TBvEv> super::Recorder<SwarmEvent<TBvEv>> for Metrics {
                         .elapsed()
                         .as_secs_f64(),
                 );
+                self.churn.record_closed();
             }
             SwarmEvent::IncomingConnection { send_back_addr, .. } => {
                 self.connections_incoming
@@ -446,3 +459,70 @@ impl From<&libp2p_swarm::ListenError> for IncomingConnectionError {
         }
     }
 }
+
+/// Interval over which [`Churn`] counts opened and closed connections.
+const CHURN_WINDOW: Duration = Duration::from_secs(60);
+
+/// Connections opened and closed within the last [`CHURN_WINDOW`].
+#[derive(Default, Debug, Clone)]
+struct Churn(Arc<Mutex<ChurnWindow>>);
+
+#[derive(Default, Debug)]
+struct ChurnWindow {
+    opened: VecDeque<Instant>,
+    closed: VecDeque<Instant>,
+}
+
+impl ChurnWindow {
+    fn prune(&mut self, now: Instant) {
+        for events in [&mut self.opened, &mut self.closed] {
+            while events
+                .front()
+                .is_some_and(|at| now.duration_since(*at) >= CHURN_WINDOW)
+            {
+                events.pop_front();
+            }
+        }
+    }
+}
+
+impl Churn {
+    fn record_opened(&self) {
+        let now = Instant::now();
+        let mut window = self.0.lock().expect("lock not to be poisoned");
+        window.prune(now);
+        window.opened.push_back(now);
+    }
+
+    fn record_closed(&self) {
+        let now = Instant::now();
+        let mut window = self.0.lock().expect("lock not to be poisoned");
+        window.prune(now);
+        window.closed.push_back(now);
+    }
+}
+
+impl Collector for Churn {
+    fn encode(&self, mut encoder: DescriptorEncoder) -> Result<(), std::fmt::Error> {
+        let mut window = self.0.lock().expect("lock not to be poisoned");
+        window.prune(Instant::now());
+
+        let metric_encoder = encoder.encode_descriptor(
+            "connections_opened_per_minute",
+            "Number of connections established within the last minute",
+            None,
+            MetricType::Gauge,
+        )?;
+        ConstGauge::new(window.opened.len() as i64).encode(metric_encoder)?;
+
+        let metric_encoder = encoder.encode_descriptor(
+            "connections_closed_per_minute",
+            "Number of connections closed within the last minute",
+            None,
+            MetricType::Gauge,
+        )?;
+        ConstGauge::new(window.closed.len() as i64).encode(metric_encoder)?;
+
+        Ok(())
+    }
+}
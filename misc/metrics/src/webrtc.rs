@@ -0,0 +1,117 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_webrtc_utils::stats::{CandidateKind, ConnectionStats};
+use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::{Registry, Unit};
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug)]
+struct BandwidthLabels {
+    direction: Direction,
+    local_candidate: CandidateType,
+    remote_candidate: CandidateType,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelValue, Debug)]
+enum Direction {
+    Inbound,
+    Outbound,
+}
+
+#[derive(Clone, Hash, PartialEq, Eq, EncodeLabelValue, Debug)]
+enum CandidateType {
+    Host,
+    ServerReflexive,
+    PeerReflexive,
+    Relay,
+    Unknown,
+}
+
+impl From<CandidateKind> for CandidateType {
+    fn from(kind: CandidateKind) -> Self {
+        match kind {
+            CandidateKind::Host => CandidateType::Host,
+            CandidateKind::ServerReflexive => CandidateType::ServerReflexive,
+            CandidateKind::PeerReflexive => CandidateType::PeerReflexive,
+            CandidateKind::Relay => CandidateType::Relay,
+            CandidateKind::Unknown => CandidateType::Unknown,
+        }
+    }
+}
+
+pub(crate) struct Metrics {
+    rtt: Histogram,
+    bandwidth: Family<BandwidthLabels, Counter>,
+}
+
+impl Metrics {
+    pub(crate) fn new(registry: &mut Registry) -> Self {
+        let sub_registry = registry.sub_registry_with_prefix("webrtc");
+
+        let rtt = Histogram::new(exponential_buckets(0.001, 2.0, 12));
+        sub_registry.register_with_unit(
+            "rtt",
+            "Round-trip time of the selected ICE candidate pair",
+            Unit::Seconds,
+            rtt.clone(),
+        );
+
+        let bandwidth = Family::default();
+        sub_registry.register_with_unit(
+            "bandwidth",
+            "Bandwidth usage by direction and type of the selected ICE candidate pair",
+            Unit::Bytes,
+            bandwidth.clone(),
+        );
+
+        Self { rtt, bandwidth }
+    }
+}
+
+impl super::Recorder<ConnectionStats> for Metrics {
+    fn record(&self, stats: &ConnectionStats) {
+        if let Some(rtt) = stats.rtt {
+            self.rtt.observe(rtt.as_secs_f64());
+        }
+
+        let (local_candidate, remote_candidate) = match &stats.selected_candidate_pair {
+            Some(pair) => (pair.local.kind.into(), pair.remote.kind.into()),
+            None => (CandidateType::Unknown, CandidateType::Unknown),
+        };
+
+        self.bandwidth
+            .get_or_create(&BandwidthLabels {
+                direction: Direction::Outbound,
+                local_candidate: local_candidate.clone(),
+                remote_candidate: remote_candidate.clone(),
+            })
+            .inc_by(stats.new_bytes_sent);
+        self.bandwidth
+            .get_or_create(&BandwidthLabels {
+                direction: Direction::Inbound,
+                local_candidate,
+                remote_candidate,
+            })
+            .inc_by(stats.new_bytes_received);
+    }
+}
@@ -338,6 +338,56 @@ where
     }
 }
 
+/// Allows `tokio` users to read from a [`Negotiated`] stream without wrapping it in a
+/// compatibility layer.
+#[cfg(feature = "tokio-io")]
+impl<TInner> tokio::io::AsyncRead for Negotiated<TInner>
+where
+    TInner: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<Result<(), io::Error>> {
+        let n = ready!(AsyncRead::poll_read(self, cx, buf.initialize_unfilled()))?;
+        buf.advance(n);
+        Poll::Ready(Ok(()))
+    }
+}
+
+/// Allows `tokio` users to write to a [`Negotiated`] stream without wrapping it in a
+/// compatibility layer.
+#[cfg(feature = "tokio-io")]
+impl<TInner> tokio::io::AsyncWrite for Negotiated<TInner>
+where
+    TInner: AsyncWrite + AsyncRead + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, io::Error>> {
+        AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), io::Error>> {
+        AsyncWrite::poll_close(self, cx)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<Result<usize, io::Error>> {
+        AsyncWrite::poll_write_vectored(self, cx, bufs)
+    }
+}
+
 /// Error that can happen when negotiating a protocol with the remote.
 #[derive(Debug)]
 pub enum NegotiationError {
@@ -24,6 +24,9 @@
 //!
 //! Each call to [`AsyncWrite::poll_write`] will send one packet to the sink.
 //! Calls to [`AsyncRead::poll_read`] will read from the stream's incoming packets.
+//!
+//! With the `tokio-io` feature, [`RwStreamSink`] also implements the `tokio` I/O traits, which
+//! copy directly into the caller's `ReadBuf` without a compatibility layer.
 
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
@@ -87,6 +90,43 @@ where
     }
 }
 
+#[cfg(feature = "tokio-io")]
+impl<S> tokio::io::AsyncRead for RwStreamSink<S>
+where
+    S: TryStream<Error = io::Error>,
+    <S as TryStream>::Ok: AsRef<[u8]>,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+
+        // Grab the item to copy from.
+        let item_to_copy = loop {
+            if let Some(ref mut i) = this.current_item {
+                if i.position() < i.get_ref().as_ref().len() as u64 {
+                    break i;
+                }
+            }
+            *this.current_item = Some(match ready!(this.inner.as_mut().try_poll_next(cx)) {
+                Some(Ok(i)) => std::io::Cursor::new(i),
+                Some(Err(e)) => return Poll::Ready(Err(e)),
+                None => return Poll::Ready(Ok(())), // EOF
+            });
+        };
+
+        // Copy it!
+        let position = item_to_copy.position() as usize;
+        let remaining = &item_to_copy.get_ref().as_ref()[position..];
+        let n = remaining.len().min(buf.remaining());
+        buf.put_slice(&remaining[..n]);
+        item_to_copy.set_position((position + n) as u64);
+        Poll::Ready(Ok(()))
+    }
+}
+
 impl<S> AsyncWrite for RwStreamSink<S>
 where
     S: TryStream + Sink<<S as TryStream>::Ok, Error = io::Error>,
@@ -113,6 +153,25 @@ where
     }
 }
 
+#[cfg(feature = "tokio-io")]
+impl<S> tokio::io::AsyncWrite for RwStreamSink<S>
+where
+    S: TryStream + Sink<<S as TryStream>::Ok, Error = io::Error>,
+    <S as TryStream>::Ok: for<'r> From<&'r [u8]>,
+{
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context, buf: &[u8]) -> Poll<io::Result<usize>> {
+        AsyncWrite::poll_write(self, cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_flush(self, cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
+        AsyncWrite::poll_close(self, cx)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::RwStreamSink;
@@ -216,4 +275,29 @@ mod tests {
             assert_eq!(0, rws.read(&mut buf).await.unwrap());
         })
     }
+
+    #[cfg(feature = "tokio-io")]
+    #[test]
+    fn tokio_io() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (tx1, mut rx1) = mpsc::channel::<Vec<u8>>(10);
+        let data: Vec<&[u8]> = vec![b"hell", b"", b"o world"];
+        let sink = tx1.sink_map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e));
+        let mut rws = RwStreamSink::new(Wrapper(stream::iter(data).map(|d| Ok(d.to_vec())), sink));
+        let mut buf = [0; 3];
+        task::block_on(async move {
+            assert_eq!(3, AsyncReadExt::read(&mut rws, &mut buf).await.unwrap());
+            assert_eq!(b"hel", &buf[..3]);
+            let mut rest = Vec::new();
+            AsyncReadExt::read_to_end(&mut rws, &mut rest)
+                .await
+                .unwrap();
+            assert_eq!(rest, b"lo world");
+
+            AsyncWriteExt::write_all(&mut rws, b"foo").await.unwrap();
+            AsyncWriteExt::flush(&mut rws).await.unwrap();
+            assert_eq!(rx1.next().await.unwrap(), b"foo");
+        })
+    }
 }
@@ -7,6 +7,7 @@ mod proto {
 mod fingerprint;
 pub mod noise;
 pub mod sdp;
+pub mod stats;
 mod stream;
 mod transport;
 
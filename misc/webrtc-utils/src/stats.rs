@@ -0,0 +1,79 @@
+//! Transport-level statistics of a WebRTC connection.
+//!
+//! The types in this module are shared between the native and the browser WebRTC transports so
+//! that a single metrics adapter can consume the statistics of either.
+
+use std::time::Duration;
+
+/// A snapshot of the statistics of a single WebRTC connection.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionStats {
+    /// The ICE candidate pair currently selected for the connection, if any.
+    pub selected_candidate_pair: Option<CandidatePair>,
+    /// The most recently measured round-trip time of the selected candidate pair.
+    pub rtt: Option<Duration>,
+    /// Bytes sent on the connection since the previous snapshot was taken.
+    pub new_bytes_sent: u64,
+    /// Bytes received on the connection since the previous snapshot was taken.
+    pub new_bytes_received: u64,
+    /// Per data-channel counters.
+    pub data_channels: Vec<DataChannelStats>,
+}
+
+/// The ICE candidate pair over which a connection's traffic flows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidatePair {
+    /// The local candidate.
+    pub local: Candidate,
+    /// The remote candidate.
+    pub remote: Candidate,
+}
+
+/// A single ICE candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// The IP address of the candidate.
+    pub address: String,
+    /// The port of the candidate.
+    pub port: u16,
+    /// The type of the candidate.
+    pub kind: CandidateKind,
+}
+
+/// The type of an ICE candidate, see <https://www.rfc-editor.org/rfc/rfc8445#section-5.1.1>.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CandidateKind {
+    /// A candidate obtained from a local interface.
+    Host,
+    /// A server reflexive candidate, obtained through STUN.
+    ServerReflexive,
+    /// A peer reflexive candidate, learned during connectivity checks.
+    PeerReflexive,
+    /// A relayed candidate, obtained through TURN.
+    Relay,
+    /// The type of the candidate is unknown.
+    Unknown,
+}
+
+/// Cumulative counters of a single data channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataChannelStats {
+    /// The SCTP stream identifier of the data channel.
+    pub id: u16,
+    /// Total number of bytes sent on the data channel.
+    pub bytes_sent: u64,
+    /// Total number of bytes received on the data channel.
+    pub bytes_received: u64,
+}
+
+impl ConnectionStats {
+    /// Total number of bytes sent across all data channels in this snapshot.
+    pub fn total_bytes_sent(&self) -> u64 {
+        self.data_channels.iter().map(|c| c.bytes_sent).sum()
+    }
+
+    /// Total number of bytes received across all data channels in this snapshot.
+    pub fn total_bytes_received(&self) -> u64 {
+        self.data_channels.iter().map(|c| c.bytes_received).sum()
+    }
+}
@@ -3,6 +3,9 @@ use libp2p_core::{multiaddr::Protocol, Multiaddr};
 use std::net::{IpAddr, SocketAddr};
 
 /// Parse the given [`Multiaddr`] into a [`SocketAddr`] and a [`Fingerprint`] for dialing.
+///
+/// If the address carries several certhashes, e.g. ahead of a certificate rotation, the first
+/// one is used.
 pub fn parse_webrtc_dial_addr(addr: &Multiaddr) -> Option<(SocketAddr, Fingerprint)> {
     let mut iter = addr.iter();
 
@@ -25,7 +28,12 @@ pub fn parse_webrtc_dial_addr(addr: &Multiaddr) -> Option<(SocketAddr, Fingerpri
         _ => return None,
     };
 
-    match iter.next() {
+    let mut next = iter.next();
+    while let Some(Protocol::Certhash(_)) = next {
+        next = iter.next();
+    }
+
+    match next {
         Some(Protocol::P2p(_)) => {}
         // peer ID is optional
         None => {}
@@ -79,6 +87,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn first_of_multiple_certhashes_is_used() {
+        let addr = "/ip4/127.0.0.1/udp/39901/webrtc-direct/certhash/uEiDikp5KVUgkLta1EjUN-IKbHk-dUBg8VzKgf5nXxLK46w/certhash/uEiABAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQ/p2p/12D3KooWNpDk9w6WrEEcdsEH1y47W71S36yFjw4sd3j7omzgCSMS"
+            .parse()
+            .unwrap();
+
+        let maybe_parsed = parse_webrtc_dial_addr(&addr);
+
+        assert_eq!(
+            maybe_parsed,
+            Some((
+                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 39901),
+                Fingerprint::raw(hex_literal::hex!(
+                    "e2929e4a5548242ed6b512350df8829b1e4f9d50183c5732a07f99d7c4b2b8eb"
+                ))
+            ))
+        );
+    }
+
     #[test]
     fn parse_ipv6() {
         let addr =
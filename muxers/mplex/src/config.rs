@@ -19,6 +19,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use crate::codec::MAX_FRAME_SIZE;
+use crate::MplexMetrics;
 use std::cmp;
 
 pub(crate) const DEFAULT_MPLEX_PROTOCOL_NAME: &str = "/mplex/6.7.0";
@@ -30,6 +31,10 @@ pub struct MplexConfig {
     pub(crate) max_substreams: usize,
     /// Maximum number of frames buffered per substream.
     pub(crate) max_buffer_len: usize,
+    /// Maximum number of bytes buffered per substream.
+    pub(crate) max_buffer_bytes: usize,
+    /// Maximum number of bytes buffered across all substreams of a connection.
+    pub(crate) max_connection_buffer_bytes: usize,
     /// Behaviour when the buffer size limit is reached for a substream.
     pub(crate) max_buffer_behaviour: MaxBufferBehaviour,
     /// When sending data, split it into frames whose maximum size is this value
@@ -37,6 +42,8 @@ pub struct MplexConfig {
     pub(crate) split_send_size: usize,
     /// Protocol name, defaults to b"/mplex/6.7.0"
     pub(crate) protocol_name: &'static str,
+    /// Buffer metrics shared by all connections using this configuration.
+    pub(crate) metrics: MplexMetrics,
 }
 
 impl MplexConfig {
@@ -72,6 +79,32 @@ impl MplexConfig {
         self
     }
 
+    /// Sets the maximum number of bytes buffered per substream.
+    ///
+    /// A substream whose buffer exceeds either this limit or the maximum
+    /// number of frames is treated according to the [`MaxBufferBehaviour`].
+    pub fn set_max_buffer_bytes(&mut self, max: usize) -> &mut Self {
+        self.max_buffer_bytes = max;
+        self
+    }
+
+    /// Sets the maximum number of bytes buffered across all substreams
+    /// of a connection.
+    ///
+    /// When the limit is exceeded, no new frames are read from the
+    /// connection until enough buffered data has been read, regardless
+    /// of the [`MaxBufferBehaviour`]. In the meantime, tasks reading from
+    /// substreams with buffered data are woken in a round-robin fashion.
+    pub fn set_max_connection_buffer_bytes(&mut self, max: usize) -> &mut Self {
+        self.max_connection_buffer_bytes = max;
+        self
+    }
+
+    /// Returns the buffer metrics of all connections using this configuration.
+    pub fn metrics(&self) -> MplexMetrics {
+        self.metrics.clone()
+    }
+
     /// Sets the behaviour when the maximum buffer size is reached
     /// for a substream.
     ///
@@ -118,6 +151,8 @@ pub enum MaxBufferBehaviour {
     /// No new message can be read from the underlying connection from any
     /// substream as long as the buffer for a single substream is full,
     /// i.e. application code is expected to read from the full buffer.
+    /// If the buffers of several substreams are full, their readers are
+    /// woken in a round-robin fashion.
     ///
     /// > **Note**: To avoid blocking without making progress, application
     /// > tasks should ensure that, when woken, always try to read (i.e.
@@ -134,9 +169,12 @@ impl Default for MplexConfig {
         MplexConfig {
             max_substreams: 128,
             max_buffer_len: 32,
+            max_buffer_bytes: 4 * 1024 * 1024,
+            max_connection_buffer_bytes: 16 * 1024 * 1024,
             max_buffer_behaviour: MaxBufferBehaviour::Block,
             split_send_size: 8 * 1024,
             protocol_name: DEFAULT_MPLEX_PROTOCOL_NAME,
+            metrics: MplexMetrics::default(),
         }
     }
 }
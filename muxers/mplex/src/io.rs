@@ -70,10 +70,16 @@ pub(crate) struct Multiplexed<C> {
     /// Whether a flush is pending due to one or more new outbound
     /// `Open` frames, before reading frames can proceed.
     pending_flush_open: IntSet<LocalStreamId>,
-    /// The stream that currently blocks reading for all streams
-    /// due to a full buffer, if any. Only applicable for use
-    /// with [`MaxBufferBehaviour::Block`].
-    blocking_stream: Option<LocalStreamId>,
+    /// The streams that currently block reading for all streams,
+    /// in the order in which their readers are woken to drain them.
+    ///
+    /// A stream blocks reading if its buffer is full and
+    /// [`MaxBufferBehaviour::Block`] is used, or if it has buffered
+    /// data while the buffers of the connection exceed
+    /// `max_connection_buffer_bytes`.
+    blocked_streams: VecDeque<LocalStreamId>,
+    /// The number of bytes buffered across all substreams.
+    buffered_bytes: usize,
     /// Pending frames to send at the next opportunity.
     ///
     /// An opportunity for sending pending frames is every flush
@@ -126,7 +132,8 @@ where
             substreams: Default::default(),
             pending_flush_open: Default::default(),
             pending_frames: Default::default(),
-            blocking_stream: None,
+            blocked_streams: Default::default(),
+            buffered_bytes: 0,
             next_outbound_stream_id: LocalStreamId::dialer(0),
             notifier_read: Arc::new(NotifierRead {
                 read_stream: Mutex::new(Default::default()),
@@ -188,6 +195,7 @@ where
                 // I/O stream, hence clearing the buffer and substreams.
                 self.open_buffer = Default::default();
                 self.substreams = Default::default();
+                self.discard_buffered();
                 self.status = Status::Closed;
                 Poll::Ready(Ok(()))
             }
@@ -333,7 +341,11 @@ where
         // Remove the substream, scheduling pending frames as necessary.
         match self.substreams.remove(&id) {
             None => {}
-            Some(state) => {
+            Some(mut state) => {
+                // Discard any data that has not been read.
+                let unread = state.recv_buf().iter().map(Bytes::len).sum();
+                self.on_unbuffered(unread);
+
                 // If we fell below the substream limit, notify tasks that had
                 // interest in opening an outbound substream earlier.
                 let below_limit = self.substreams.len() == self.config.max_substreams - 1;
@@ -436,12 +448,8 @@ where
         if let Some(state) = self.substreams.get_mut(&id) {
             let buf = state.recv_buf();
             if !buf.is_empty() {
-                if self.blocking_stream == Some(id) {
-                    // Unblock reading new frames.
-                    self.blocking_stream = None;
-                    ArcWake::wake_by_ref(&self.notifier_read);
-                }
                 let data = buf.remove(0);
+                self.on_unbuffered(data.len());
                 return Poll::Ready(Ok(Some(data)));
             }
             // If the stream buffer "spilled" onto the heap, free that memory.
@@ -649,15 +657,14 @@ where
             }
         }
 
-        // Check if there is a blocked stream.
-        if let Some(blocked_id) = &self.blocking_stream {
-            // We have a blocked stream and cannot continue reading
-            // new frames until frames are taken from the blocked stream's
-            // buffer.
+        // Check if there are blocked streams.
+        if !self.blocked_streams.is_empty() {
+            // We have blocked streams and cannot continue reading
+            // new frames until frames are taken from their buffers.
 
-            // Try to wake a pending reader of the blocked stream.
-            if !self.notifier_read.wake_read_stream(*blocked_id) {
-                // No task dedicated to the blocked stream woken, so schedule
+            // Try to wake a pending reader of the next blocked stream.
+            if !self.wake_next_blocked_stream() {
+                // No task dedicated to a blocked stream woken, so schedule
                 // this task again to have a chance at progress.
                 tracing::trace!(
                     connection=%self.id,
@@ -668,7 +675,7 @@ where
                 // We woke some other task, but are still interested in
                 // reading `Data` frames from the current stream when unblocked.
                 debug_assert!(
-                    blocked_id != &id,
+                    !self.blocked_streams.contains(&id),
                     "Unexpected attempt at reading a new \
                     frame from a substream with a full buffer."
                 );
@@ -877,6 +884,7 @@ where
         self.pending_frames = Default::default();
         self.substreams = Default::default();
         self.open_buffer = Default::default();
+        self.discard_buffered();
         Err(e)
     }
 
@@ -940,9 +948,13 @@ where
             data_buffer=%buf.len() + 1,
             "Buffering data for substream"
         );
+        let len = data.len();
         buf.push(data);
         self.notifier_read.wake_read_stream(id);
-        if buf.len() > self.config.max_buffer_len {
+        let was_blocked = !self.blocked_streams.is_empty();
+        self.buffered_bytes += len;
+        self.config.metrics.add_buffered_bytes(len);
+        if is_full(buf, &self.config) {
             tracing::debug!(
                 connection=%self.id,
                 substream=%id,
@@ -960,15 +972,97 @@ where
                     );
                     self.pending_frames
                         .push_front(Frame::Reset { stream_id: id });
+                    self.config.metrics.inc_overflow_resets();
                 }
                 MaxBufferBehaviour::Block => {
-                    self.blocking_stream = Some(id);
+                    self.blocked_streams.push_back(id);
                 }
             }
         }
+        if self.buffered_bytes > self.config.max_connection_buffer_bytes {
+            tracing::debug!(
+                connection=%self.id,
+                buffered_bytes=%self.buffered_bytes,
+                "Buffer of connection is full"
+            );
+            // Every stream with buffered data needs to be drained.
+            for (id, state) in self.substreams.iter_mut() {
+                if !state.recv_buf().is_empty() && !self.blocked_streams.contains(id) {
+                    self.blocked_streams.push_back(*id);
+                }
+            }
+        }
+        if !was_blocked && !self.blocked_streams.is_empty() {
+            self.config.metrics.inc_blocked_reads();
+        }
 
         Ok(())
     }
+
+    /// Accounts for the buffers of all substreams having been discarded.
+    fn discard_buffered(&mut self) {
+        self.blocked_streams = Default::default();
+        self.config.metrics.sub_buffered_bytes(self.buffered_bytes);
+        self.buffered_bytes = 0;
+    }
+
+    /// Accounts for `len` bytes having been taken from the buffer of a
+    /// substream, unblocking reading new frames if possible.
+    fn on_unbuffered(&mut self, len: usize) {
+        self.buffered_bytes -= len;
+        self.config.metrics.sub_buffered_bytes(len);
+
+        if self.blocked_streams.is_empty() {
+            return;
+        }
+
+        let connection_full = self.buffered_bytes > self.config.max_connection_buffer_bytes;
+        let config = &self.config;
+        let substreams = &mut self.substreams;
+        self.blocked_streams.retain(|id| {
+            let Some(buf) = substreams.get_mut(id).map(SubstreamState::recv_buf) else {
+                return false;
+            };
+            let stream_full =
+                config.max_buffer_behaviour == MaxBufferBehaviour::Block && is_full(buf, config);
+            stream_full || (connection_full && !buf.is_empty())
+        });
+
+        if self.blocked_streams.is_empty() {
+            // Unblock reading new frames.
+            ArcWake::wake_by_ref(&self.notifier_read);
+        }
+    }
+
+    /// Wakes the reader of the next blocked stream that has one, rotating
+    /// the blocked streams such that their readers are woken round-robin.
+    ///
+    /// Returns `false` if no reader was woken.
+    fn wake_next_blocked_stream(&mut self) -> bool {
+        for _ in 0..self.blocked_streams.len() {
+            let Some(id) = self.blocked_streams.pop_front() else {
+                break;
+            };
+            self.blocked_streams.push_back(id);
+            if self.notifier_read.wake_read_stream(id) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl<C> Drop for Multiplexed<C> {
+    fn drop(&mut self) {
+        self.config.metrics.sub_buffered_bytes(self.buffered_bytes);
+    }
+}
+
+/// Checks whether a substream buffer exceeds the configured
+/// number of frames or bytes.
+fn is_full(buf: &RecvBuf, config: &MplexConfig) -> bool {
+    buf.len() > config.max_buffer_len
+        || buf.iter().map(Bytes::len).sum::<usize>() > config.max_buffer_bytes
 }
 
 type RecvBuf = SmallVec<[Bytes; 10]>;
@@ -1170,7 +1264,7 @@ mod tests {
                 max_buffer_len: g.gen_range(1..1000),
                 max_buffer_behaviour: MaxBufferBehaviour::arbitrary(g),
                 split_send_size: g.gen_range(1..10000),
-                protocol_name: crate::config::DEFAULT_MPLEX_PROTOCOL_NAME,
+                ..Default::default()
             }
         }
     }
@@ -1362,6 +1456,87 @@ mod tests {
         quickcheck(prop as fn(_, _))
     }
 
+    #[test]
+    fn max_connection_buffer_bytes() {
+        let mut r_buf = BytesMut::new();
+        let mut codec = Codec::new();
+
+        // Open two inbound streams and send data on both.
+        let stream_0 = LocalStreamId::dialer(0);
+        let stream_1 = LocalStreamId::dialer(1);
+        let data = Bytes::from("Hello world");
+        let frames = [
+            Frame::Open {
+                stream_id: stream_0,
+            },
+            Frame::Open {
+                stream_id: stream_1,
+            },
+            Frame::Data {
+                stream_id: stream_0,
+                data: data.clone(),
+            },
+            Frame::Data {
+                stream_id: stream_1,
+                data: data.clone(),
+            },
+            Frame::Data {
+                stream_id: stream_0,
+                data: data.clone(),
+            },
+        ];
+        for frame in frames {
+            codec.encode(frame, &mut r_buf).unwrap();
+        }
+
+        let mut cfg = MplexConfig::new();
+        cfg.set_max_connection_buffer_bytes(2 * data.len() - 1);
+        let metrics = cfg.metrics();
+        let conn = Connection {
+            r_buf,
+            w_buf: BytesMut::new(),
+            eof: false,
+        };
+        let mut m = Multiplexed::new(conn, cfg);
+
+        task::block_on(future::poll_fn(move |cx| {
+            let id_0 = LocalStreamId::listener(0);
+            let id_1 = LocalStreamId::listener(1);
+            assert!(matches!(m.poll_next_stream(cx), Poll::Ready(Ok(id)) if id == id_0));
+            assert!(matches!(m.poll_next_stream(cx), Poll::Ready(Ok(id)) if id == id_1));
+
+            // Buffering the data of both streams exceeds the connection budget,
+            // blocking reading new frames until either stream is drained.
+            assert!(m.poll_next_stream(cx).is_pending());
+            assert_eq!(metrics.buffered_bytes(), 2 * data.len());
+            assert_eq!(metrics.blocked_reads(), 1);
+            assert_eq!(m.blocked_streams.len(), 2);
+            assert!(m.blocked_streams.contains(&id_0));
+            assert!(m.blocked_streams.contains(&id_1));
+            assert!(m.poll_next_stream(cx).is_pending());
+
+            // Reading from the buffer of one stream unblocks the connection.
+            match m.poll_read_stream(cx, id_1) {
+                Poll::Ready(Ok(Some(bytes))) => assert_eq!(bytes, data),
+                poll => panic!("Unexpected: {poll:?}"),
+            }
+            assert!(m.blocked_streams.is_empty());
+            assert_eq!(metrics.buffered_bytes(), data.len());
+
+            // Reading the next frame for stream 1 buffers another frame of
+            // stream 0, exceeding the budget again.
+            assert!(m.poll_read_stream(cx, id_1).is_pending());
+            assert_eq!(m.blocked_streams, [id_0]);
+            assert_eq!(metrics.blocked_reads(), 2);
+
+            m.drop_stream(id_0);
+            assert!(m.blocked_streams.is_empty());
+            assert_eq!(metrics.buffered_bytes(), 0);
+
+            Poll::Ready(())
+        }));
+    }
+
     #[test]
     fn close_on_error() {
         use tracing_subscriber::EnvFilter;
@@ -25,8 +25,10 @@
 mod codec;
 mod config;
 mod io;
+mod metrics;
 
 pub use config::{MaxBufferBehaviour, MplexConfig};
+pub use metrics::MplexMetrics;
 
 use bytes::Bytes;
 use codec::LocalStreamId;
@@ -0,0 +1,76 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Counters of the receive buffers of all connections using the same [`MplexConfig`].
+///
+/// Obtained via [`MplexConfig::metrics`]. Clones of a configuration share their metrics.
+///
+/// [`MplexConfig`]: crate::MplexConfig
+/// [`MplexConfig::metrics`]: crate::MplexConfig::metrics
+#[derive(Debug, Clone, Default)]
+pub struct MplexMetrics {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    buffered_bytes: AtomicUsize,
+    blocked_reads: AtomicU64,
+    overflow_resets: AtomicU64,
+}
+
+impl MplexMetrics {
+    /// The number of received bytes currently buffered and not yet read by any substream.
+    pub fn buffered_bytes(&self) -> usize {
+        self.inner.buffered_bytes.load(Ordering::Relaxed)
+    }
+
+    /// How often reading from a connection has been blocked on a full buffer.
+    pub fn blocked_reads(&self) -> u64 {
+        self.inner.blocked_reads.load(Ordering::Relaxed)
+    }
+
+    /// How many substreams have been reset because their buffer overflowed,
+    /// see [`MaxBufferBehaviour::ResetStream`](crate::MaxBufferBehaviour::ResetStream).
+    pub fn overflow_resets(&self) -> u64 {
+        self.inner.overflow_resets.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add_buffered_bytes(&self, n: usize) {
+        self.inner.buffered_bytes.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn sub_buffered_bytes(&self, n: usize) {
+        self.inner.buffered_bytes.fetch_sub(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inc_blocked_reads(&self) {
+        self.inner.blocked_reads.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn inc_overflow_resets(&self) {
+        self.inner.overflow_resets.fetch_add(1, Ordering::Relaxed);
+    }
+}
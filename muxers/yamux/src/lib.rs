@@ -24,7 +24,7 @@
 
 use either::Either;
 use futures::{prelude::*, ready};
-use libp2p_core::muxing::{StreamMuxer, StreamMuxerEvent};
+use libp2p_core::muxing::{MuxerCounters, StreamCounters, StreamMuxer, StreamMuxerEvent};
 use libp2p_core::upgrade::{InboundConnectionUpgrade, OutboundConnectionUpgrade, UpgradeInfo};
 use std::collections::VecDeque;
 use std::io::{IoSlice, IoSliceMut};
@@ -50,11 +50,15 @@ pub struct Muxer<C> {
     /// This buffer stores inbound streams that are created whilst [`StreamMuxer::poll`] is called.
     /// Once the buffer is full, new inbound streams are dropped.
     inbound_stream_buffer: VecDeque<Stream>,
+    /// The maximum number of streams in [`Muxer::inbound_stream_buffer`].
+    max_buffered_inbound_streams: usize,
     /// Waker to be called when new inbound streams are available.
     inbound_stream_waker: Option<Waker>,
+    /// The counters of the connection, see [`StreamMuxer::counters`].
+    counters: MuxerCounters,
 }
 
-/// How many streams to buffer before we start resetting them.
+/// How many streams to buffer by default before we start resetting them.
 ///
 /// This is equal to the ACK BACKLOG in `rust-yamux`.
 /// Thus, for peers running on a recent version of `rust-libp2p`, we should never need to reset streams because they'll voluntarily stop opening them once they hit the ACK backlog.
@@ -65,11 +69,16 @@ where
     C: AsyncRead + AsyncWrite + Send + Unpin + 'static,
 {
     /// Create a new Yamux connection.
-    fn new(connection: Either<yamux012::Connection<C>, yamux013::Connection<C>>) -> Self {
+    fn new(
+        connection: Either<yamux012::Connection<C>, yamux013::Connection<C>>,
+        max_buffered_inbound_streams: usize,
+    ) -> Self {
         Muxer {
             connection,
             inbound_stream_buffer: VecDeque::default(),
+            max_buffered_inbound_streams,
             inbound_stream_waker: None,
+            counters: MuxerCounters::default(),
         }
     }
 }
@@ -106,12 +115,18 @@ where
         let stream = match self.connection.as_mut() {
             Either::Left(c) => ready!(c.poll_new_outbound(cx))
                 .map_err(|e| Error(Either::Left(e)))
-                .map(|s| Stream(Either::Left(s))),
+                .map(Either::Left),
             Either::Right(c) => ready!(c.poll_new_outbound(cx))
                 .map_err(|e| Error(Either::Right(e)))
-                .map(|s| Stream(Either::Right(s))),
-        }?;
-        Poll::Ready(Ok(stream))
+                .map(Either::Right),
+        };
+        match stream {
+            Ok(stream) => Poll::Ready(Ok(Stream(stream, self.counters.new_stream()))),
+            Err(e) => {
+                self.counters.record_open_failure();
+                Poll::Ready(Err(e))
+            }
+        }
     }
 
     #[tracing::instrument(level = "trace", name = "StreamMuxer::poll_close", skip(self, cx))]
@@ -131,7 +146,7 @@ where
 
         let inbound_stream = ready!(this.poll_inner(cx))?;
 
-        if this.inbound_stream_buffer.len() >= MAX_BUFFERED_INBOUND_STREAMS {
+        if this.inbound_stream_buffer.len() >= this.max_buffered_inbound_streams {
             tracing::warn!(
                 stream=%inbound_stream.0,
                 "dropping stream because buffer is full"
@@ -149,11 +164,33 @@ where
         cx.waker().wake_by_ref();
         Poll::Pending
     }
+
+    fn counters(&self) -> Option<MuxerCounters> {
+        Some(self.counters.clone())
+    }
 }
 
 /// A stream produced by the yamux multiplexer.
 #[derive(Debug)]
-pub struct Stream(Either<yamux012::Stream, yamux013::Stream>);
+pub struct Stream(Either<yamux012::Stream, yamux013::Stream>, StreamCounters);
+
+impl Stream {
+    fn record_read(&self, read: &Poll<io::Result<usize>>) {
+        if let Poll::Ready(Ok(num_bytes)) = read {
+            self.1.record_inbound(*num_bytes);
+        }
+    }
+
+    /// Records the written bytes, or a window stall if the write is pending, i.e. yamux waits
+    /// for the remote to grant more credit.
+    fn record_write(&self, write: &Poll<io::Result<usize>>) {
+        match write {
+            Poll::Ready(Ok(num_bytes)) => self.1.record_outbound(*num_bytes),
+            Poll::Pending => self.1.record_window_stall(),
+            Poll::Ready(Err(_)) => {}
+        }
+    }
+}
 
 impl AsyncRead for Stream {
     fn poll_read(
@@ -161,7 +198,9 @@ impl AsyncRead for Stream {
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<io::Result<usize>> {
-        either::for_both!(self.0.as_mut(), s => Pin::new(s).poll_read(cx, buf))
+        let read = either::for_both!(self.0.as_mut(), s => Pin::new(s).poll_read(cx, buf));
+        self.record_read(&read);
+        read
     }
 
     fn poll_read_vectored(
@@ -169,7 +208,10 @@ impl AsyncRead for Stream {
         cx: &mut Context<'_>,
         bufs: &mut [IoSliceMut<'_>],
     ) -> Poll<io::Result<usize>> {
-        either::for_both!(self.0.as_mut(), s => Pin::new(s).poll_read_vectored(cx, bufs))
+        let read =
+            either::for_both!(self.0.as_mut(), s => Pin::new(s).poll_read_vectored(cx, bufs));
+        self.record_read(&read);
+        read
     }
 }
 
@@ -179,7 +221,9 @@ impl AsyncWrite for Stream {
         cx: &mut Context<'_>,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        either::for_both!(self.0.as_mut(), s => Pin::new(s).poll_write(cx, buf))
+        let write = either::for_both!(self.0.as_mut(), s => Pin::new(s).poll_write(cx, buf));
+        self.record_write(&write);
+        write
     }
 
     fn poll_write_vectored(
@@ -187,7 +231,10 @@ impl AsyncWrite for Stream {
         cx: &mut Context<'_>,
         bufs: &[IoSlice<'_>],
     ) -> Poll<io::Result<usize>> {
-        either::for_both!(self.0.as_mut(), s => Pin::new(s).poll_write_vectored(cx, bufs))
+        let write =
+            either::for_both!(self.0.as_mut(), s => Pin::new(s).poll_write_vectored(cx, bufs));
+        self.record_write(&write);
+        write
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
@@ -208,24 +255,27 @@ where
             Either::Left(c) => ready!(c.poll_next_inbound(cx))
                 .ok_or(Error(Either::Left(yamux012::ConnectionError::Closed)))?
                 .map_err(|e| Error(Either::Left(e)))
-                .map(|s| Stream(Either::Left(s)))?,
+                .map(Either::Left)?,
             Either::Right(c) => ready!(c.poll_next_inbound(cx))
                 .ok_or(Error(Either::Right(yamux013::ConnectionError::Closed)))?
                 .map_err(|e| Error(Either::Right(e)))
-                .map(|s| Stream(Either::Right(s)))?,
+                .map(Either::Right)?,
         };
 
-        Poll::Ready(Ok(stream))
+        Poll::Ready(Ok(Stream(stream, self.counters.new_stream())))
     }
 }
 
 /// The yamux configuration.
 #[derive(Debug, Clone)]
-pub struct Config(Either<Config012, Config013>);
+pub struct Config {
+    inner: Either<Config012, Config013>,
+    max_buffered_inbound_streams: usize,
+}
 
 impl Default for Config {
     fn default() -> Self {
-        Self(Either::Right(Config013::default()))
+        Self::from_inner(Either::Right(Config013::default()))
     }
 }
 
@@ -249,6 +299,28 @@ impl Default for Config012 {
 /// sent to the remote, giving it new credit to send more data.
 pub struct WindowUpdateMode(yamux012::WindowUpdateMode);
 
+/// How the receive window of a stream is sized, see [`Config::set_receive_window`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiveWindow {
+    /// A receive window of a fixed size (in bytes) per stream.
+    ///
+    /// Uses yamux v0.12, like the other settings of [`Config`] that are not available for
+    /// auto-tuned windows.
+    Fixed(u32),
+    /// A receive window starting at 256 KiB per stream that grows with the observed round-trip
+    /// time and throughput of the stream, i.e. its bandwidth-delay product.
+    ///
+    /// Small windows are kept on low-latency and low-bandwidth connections, while a single
+    /// stream can use the available bandwidth of high-latency or high-bandwidth connections.
+    /// The windows of all streams of a connection combined are limited to
+    /// `max_connection_window` bytes, or unlimited if `None`.
+    ///
+    /// This is the default, with a limit of 1 GiB.
+    AutoTuned {
+        max_connection_window: Option<usize>,
+    },
+}
+
 impl WindowUpdateMode {
     /// The window update mode whereby the remote is given
     /// new credit via a window update whenever the current
@@ -288,11 +360,18 @@ impl WindowUpdateMode {
 }
 
 impl Config {
+    fn from_inner(inner: Either<Config012, Config013>) -> Self {
+        Self {
+            inner,
+            max_buffered_inbound_streams: MAX_BUFFERED_INBOUND_STREAMS,
+        }
+    }
+
     /// Creates a new `YamuxConfig` in client mode, regardless of whether
     /// it will be used for an inbound or outbound upgrade.
     #[deprecated(note = "Will be removed with the next breaking release.")]
     pub fn client() -> Self {
-        Self(Either::Left(Config012 {
+        Self::from_inner(Either::Left(Config012 {
             mode: Some(yamux012::Mode::Client),
             ..Default::default()
         }))
@@ -302,7 +381,7 @@ impl Config {
     /// it will be used for an inbound or outbound upgrade.
     #[deprecated(note = "Will be removed with the next breaking release.")]
     pub fn server() -> Self {
-        Self(Either::Left(Config012 {
+        Self::from_inner(Either::Left(Config012 {
             mode: Some(yamux012::Mode::Server),
             ..Default::default()
         }))
@@ -327,6 +406,45 @@ impl Config {
         self.set(|cfg| cfg.set_max_num_streams(num_streams))
     }
 
+    /// Sets how the receive window of a stream is sized.
+    ///
+    /// Switching to [`ReceiveWindow::AutoTuned`] resets the settings only available for
+    /// [`ReceiveWindow::Fixed`], e.g. [`Config::set_max_num_streams`], to their defaults.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `max_connection_window` of [`ReceiveWindow::AutoTuned`] leaves less than
+    /// the initial 256 KiB for each of the up to 512 streams of a connection, i.e. is below
+    /// 128 MiB.
+    pub fn set_receive_window(&mut self, window: ReceiveWindow) -> &mut Self {
+        match window {
+            ReceiveWindow::Fixed(num_bytes) => self.set(|cfg| cfg.set_receive_window(num_bytes)),
+            ReceiveWindow::AutoTuned {
+                max_connection_window,
+            } => {
+                let cfg013 = match self.inner.as_mut() {
+                    Either::Left(_) => {
+                        self.inner = Either::Right(Config013::default());
+                        &mut self.inner.as_mut().unwrap_right().0
+                    }
+                    Either::Right(c) => &mut c.0,
+                };
+                cfg013.set_max_connection_receive_window(max_connection_window);
+
+                self
+            }
+        }
+    }
+
+    /// Sets the maximum number of inbound streams buffered while the local node does not accept
+    /// them, before new inbound streams are dropped.
+    ///
+    /// Defaults to 256, the number of unacknowledged streams a remote opens at most.
+    pub fn set_max_buffered_inbound_streams(&mut self, num_streams: usize) -> &mut Self {
+        self.max_buffered_inbound_streams = num_streams;
+        self
+    }
+
     /// Sets the window update mode that determines when the remote
     /// is given new credit for sending more data.
     #[deprecated(
@@ -337,11 +455,11 @@ impl Config {
     }
 
     fn set(&mut self, f: impl FnOnce(&mut yamux012::Config) -> &mut yamux012::Config) -> &mut Self {
-        let cfg012 = match self.0.as_mut() {
+        let cfg012 = match self.inner.as_mut() {
             Either::Left(c) => &mut c.inner,
             Either::Right(_) => {
-                self.0 = Either::Left(Config012::default());
-                &mut self.0.as_mut().unwrap_left().inner
+                self.inner = Either::Left(Config012::default());
+                &mut self.inner.as_mut().unwrap_left().inner
             }
         };
 
@@ -369,7 +487,7 @@ where
     type Future = future::Ready<Result<Self::Output, Self::Error>>;
 
     fn upgrade_inbound(self, io: C, _: Self::Info) -> Self::Future {
-        let connection = match self.0 {
+        let connection = match self.inner {
             Either::Left(Config012 { inner, mode }) => Either::Left(yamux012::Connection::new(
                 io,
                 inner,
@@ -380,7 +498,10 @@ where
             }
         };
 
-        future::ready(Ok(Muxer::new(connection)))
+        future::ready(Ok(Muxer::new(
+            connection,
+            self.max_buffered_inbound_streams,
+        )))
     }
 }
 
@@ -393,7 +514,7 @@ where
     type Future = future::Ready<Result<Self::Output, Self::Error>>;
 
     fn upgrade_outbound(self, io: C, _: Self::Info) -> Self::Future {
-        let connection = match self.0 {
+        let connection = match self.inner {
             Either::Left(Config012 { inner, mode }) => Either::Left(yamux012::Connection::new(
                 io,
                 inner,
@@ -404,7 +525,10 @@ where
             }
         };
 
-        future::ready(Ok(Muxer::new(connection)))
+        future::ready(Ok(Muxer::new(
+            connection,
+            self.max_buffered_inbound_streams,
+        )))
     }
 }
 
@@ -451,11 +575,57 @@ mod test {
         let mut cfg = Config::default();
         assert!(matches!(
             cfg,
-            Config(Either::Right(Config013(yamux013::Config { .. })))
+            Config {
+                inner: Either::Right(Config013(yamux013::Config { .. })),
+                ..
+            }
         ));
 
         // In case a user makes any configurations, use yamux v0.12 instead.
         cfg.set_max_num_streams(42);
-        assert!(matches!(cfg, Config(Either::Left(Config012 { .. }))));
+        assert!(matches!(
+            cfg,
+            Config {
+                inner: Either::Left(Config012 { .. }),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn receive_window_selects_version() {
+        let mut cfg = Config::default();
+
+        cfg.set_receive_window(ReceiveWindow::AutoTuned {
+            max_connection_window: Some(256 * 1024 * 1024),
+        });
+        assert!(matches!(cfg.inner, Either::Right(Config013(_))));
+
+        cfg.set_receive_window(ReceiveWindow::Fixed(1024 * 1024));
+        assert!(matches!(cfg.inner, Either::Left(Config012 { .. })));
+
+        cfg.set_receive_window(ReceiveWindow::AutoTuned {
+            max_connection_window: None,
+        });
+        assert!(matches!(cfg.inner, Either::Right(Config013(_))));
+    }
+
+    #[async_std::test]
+    async fn counts_open_streams() {
+        use libp2p_core::muxing::StreamMuxerExt;
+
+        let (mut alice, _bob) =
+            libp2p_muxer_test_harness::connected_muxers_on_memory_ring_buffer::<Config, _, _>()
+                .await;
+        let counters = alice.counters().unwrap();
+
+        let stream = future::poll_fn(|cx| alice.poll_outbound_unpin(cx))
+            .await
+            .unwrap();
+        assert_eq!(counters.stats().open_streams(), 1);
+
+        drop(stream);
+        assert_eq!(counters.stats().open_streams(), 0);
+        assert_eq!(counters.stats().stream_open_failures(), 0);
     }
 }
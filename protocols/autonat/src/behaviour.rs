@@ -20,6 +20,7 @@
 
 mod as_client;
 mod as_server;
+mod reachability;
 
 use crate::protocol::{AutoNatCodec, DialRequest, DialResponse, ResponseError};
 use crate::DEFAULT_PROTOCOL_NAME;
@@ -39,6 +40,8 @@ use libp2p_swarm::{
     ConnectionDenied, ConnectionId, ListenAddresses, NetworkBehaviour, THandler, THandlerInEvent,
     THandlerOutEvent, ToSwarm,
 };
+pub use reachability::Reachability;
+use reachability::ReachabilityTracker;
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     iter,
@@ -67,6 +70,9 @@ pub struct Config {
     /// Max confidence that can be reached in a public / private NAT status.
     /// Note: for [`NatStatus::Unknown`] the confidence is always 0.
     pub confidence_max: usize,
+    /// Time after which the evidence of past probes counts only half towards the
+    /// [`Reachability`] of the local node.
+    pub reachability_half_life: Duration,
 
     // Server Config
     /// Max addresses that are tried per peer.
@@ -94,6 +100,7 @@ impl Default for Config {
             throttle_server_period: Duration::from_secs(90),
             use_connected: true,
             confidence_max: 3,
+            reachability_half_life: Duration::from_secs(30 * 60),
             max_peer_addresses: 16,
             throttle_clients_global_max: 30,
             throttle_clients_peer_max: 3,
@@ -143,6 +150,16 @@ pub enum Event {
         /// New status.
         new: NatStatus,
     },
+    /// The [`Reachability`] of the local node changed.
+    ///
+    /// Contrary to [`Event::StatusChanged`] this is only reported once enough evidence for the
+    /// new reachability was collected, see [`Behaviour::reachability`].
+    ReachabilityChanged {
+        /// Former reachability.
+        old: Reachability,
+        /// New reachability.
+        new: Reachability,
+    },
 }
 
 /// [`NetworkBehaviour`] for AutoNAT.
@@ -176,6 +193,9 @@ pub struct Behaviour {
     // Confidence in the assumed NAT status.
     confidence: usize,
 
+    // Global reachability derived from the outcomes of all probes.
+    reachability: ReachabilityTracker,
+
     // Timer for the next probe.
     schedule_probe: Delay,
 
@@ -226,13 +246,13 @@ impl Behaviour {
             local_peer_id,
             inner,
             schedule_probe: Delay::new(config.boot_delay),
-            config,
             servers: HashSet::new(),
             ongoing_inbound: HashMap::default(),
             ongoing_outbound: HashMap::default(),
             connected: HashMap::default(),
             nat_status: NatStatus::Unknown,
             confidence: 0,
+            reachability: ReachabilityTracker::new(&config),
             throttled_servers: Vec::new(),
             throttled_clients: Vec::new(),
             last_probe: None,
@@ -240,6 +260,7 @@ impl Behaviour {
             probe_id: ProbeId(0),
             listen_addresses: Default::default(),
             other_candidates: Default::default(),
+            config,
         }
     }
 
@@ -262,6 +283,15 @@ impl Behaviour {
         self.confidence
     }
 
+    /// Global reachability of the local node.
+    ///
+    /// Contrary to [`Behaviour::nat_status`], the reachability only changes once enough
+    /// probes agreed on it and falls back to [`Reachability::Unknown`] when the evidence becomes
+    /// stale. Changes are reported through [`Event::ReachabilityChanged`].
+    pub fn reachability(&self) -> Reachability {
+        self.reachability.reachability()
+    }
+
     /// Add a peer to the list over servers that may be used for probes.
     /// These peers are used for dial-request even if they are currently not connection, in which case a connection will be
     /// establish before sending the dial-request.
@@ -296,6 +326,7 @@ impl Behaviour {
             throttled_servers: &mut self.throttled_servers,
             nat_status: &mut self.nat_status,
             confidence: &mut self.confidence,
+            reachability: &mut self.reachability,
             ongoing_outbound: &mut self.ongoing_outbound,
             last_probe: &mut self.last_probe,
             schedule_probe: &mut self.schedule_probe,
@@ -462,6 +493,13 @@ impl NetworkBehaviour for Behaviour {
                 Poll::Pending => {}
             }
 
+            if let Some((old, new)) = self.reachability.on_tick(Instant::now()) {
+                self.pending_actions.push_back(ToSwarm::GenerateEvent(
+                    Event::ReachabilityChanged { old, new },
+                ));
+                continue;
+            }
+
             return Poll::Pending;
         }
     }
@@ -530,10 +568,12 @@ impl NetworkBehaviour for Behaviour {
                 self.as_client().on_new_address();
             }
             FromSwarm::ExpiredListenAddr(e) => {
-                self.as_client().on_expired_address(e.addr);
+                let actions = self.as_client().on_expired_address(e.addr);
+                self.pending_actions.extend(actions);
             }
             FromSwarm::ExternalAddrExpired(e) => {
-                self.as_client().on_expired_address(e.addr);
+                let actions = self.as_client().on_expired_address(e.addr);
+                self.pending_actions.extend(actions);
             }
             FromSwarm::NewExternalAddrCandidate(e) => {
                 self.probe_address(e.addr.to_owned());
@@ -22,7 +22,7 @@ use crate::ResponseError;
 
 use super::{
     Action, AutoNatCodec, Config, DialRequest, DialResponse, Event, HandleInnerEvent, NatStatus,
-    ProbeId,
+    ProbeId, ReachabilityTracker,
 };
 use futures::FutureExt;
 use futures_timer::Delay;
@@ -91,6 +91,7 @@ pub(crate) struct AsClient<'a> {
     pub(crate) throttled_servers: &'a mut Vec<(PeerId, Instant)>,
     pub(crate) nat_status: &'a mut NatStatus,
     pub(crate) confidence: &'a mut usize,
+    pub(crate) reachability: &'a mut ReachabilityTracker,
     pub(crate) ongoing_outbound: &'a mut HashMap<OutboundRequestId, ProbeId>,
     pub(crate) last_probe: &'a mut Option<Instant>,
     pub(crate) schedule_probe: &'a mut Delay,
@@ -132,7 +133,7 @@ impl<'a> HandleInnerEvent for AsClient<'a> {
                     },
                 };
 
-                let mut actions = VecDeque::with_capacity(3);
+                let mut actions = VecDeque::with_capacity(4);
 
                 actions.push_back(ToSwarm::GenerateEvent(Event::OutboundProbe(event)));
 
@@ -143,6 +144,16 @@ impl<'a> HandleInnerEvent for AsClient<'a> {
                     }));
                 }
 
+                if let Some((old, new)) = self
+                    .reachability
+                    .on_probe_result(&response.result, Instant::now())
+                {
+                    actions.push_back(ToSwarm::GenerateEvent(Event::ReachabilityChanged {
+                        old,
+                        new,
+                    }));
+                }
+
                 if let Ok(address) = response.result {
                     actions.push_back(ToSwarm::ExternalAddrConfirmed(address));
                 }
@@ -231,7 +242,7 @@ impl<'a> AsClient<'a> {
         }
     }
 
-    pub(crate) fn on_expired_address(&mut self, addr: &Multiaddr) {
+    pub(crate) fn on_expired_address(&mut self, addr: &Multiaddr) -> Option<Action> {
         if let NatStatus::Public(public_address) = self.nat_status {
             if public_address == addr {
                 *self.confidence = 0;
@@ -239,6 +250,12 @@ impl<'a> AsClient<'a> {
                 self.schedule_next_probe(Duration::ZERO);
             }
         }
+
+        let (old, new) = self.reachability.on_expired_address(addr, Instant::now())?;
+        Some(ToSwarm::GenerateEvent(Event::ReachabilityChanged {
+            old,
+            new,
+        }))
     }
 
     // Select a random server for the probe.
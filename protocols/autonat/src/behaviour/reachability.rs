@@ -0,0 +1,254 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use super::Config;
+use crate::ResponseError;
+use instant::Instant;
+use libp2p_core::Multiaddr;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Score required to enter [`Reachability::Public`] or [`Reachability::Private`].
+const ENTER_THRESHOLD: f64 = 0.6;
+/// Score at which [`Reachability::Public`] or [`Reachability::Private`] is left again.
+///
+/// The gap to [`ENTER_THRESHOLD`] prevents the reachability from flapping on contradicting
+/// probe outcomes.
+const EXIT_THRESHOLD: f64 = 0.3;
+
+/// Global reachability of the local node, see [`Behaviour::reachability`](crate::Behaviour::reachability).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reachability {
+    /// Not enough recent evidence to tell whether the node is reachable.
+    Unknown,
+    /// The node is publicly reachable on at least one of its addresses.
+    Public,
+    /// The node is behind a NAT and / or firewall.
+    Private,
+}
+
+/// State machine combining the outcomes of probes into a stable [`Reachability`].
+///
+/// Each probe outcome moves a score in `[-1, 1]` by `1 / Config::confidence_max` towards `1` if
+/// the node was dialed back and towards `-1` if it was not. The score decays towards `0` over
+/// time, with a half-life of [`Config::reachability_half_life`], so that stale evidence is
+/// forgotten.
+#[derive(Debug)]
+pub(crate) struct ReachabilityTracker {
+    state: Reachability,
+    score: f64,
+    /// The time at which the score was last decayed.
+    updated: Instant,
+    /// How far each probe outcome moves the score.
+    weight: f64,
+    half_life: Duration,
+    /// The addresses confirmed by probes since the node was last considered public.
+    confirmed_addresses: HashSet<Multiaddr>,
+}
+
+impl ReachabilityTracker {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            state: Reachability::Unknown,
+            score: 0.0,
+            updated: Instant::now(),
+            weight: 1.0 / config.confidence_max.max(1) as f64,
+            half_life: config.reachability_half_life,
+            confirmed_addresses: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn reachability(&self) -> Reachability {
+        self.state
+    }
+
+    /// Takes the result of a probe into account.
+    ///
+    /// Returns the old and new reachability if it changed.
+    pub(crate) fn on_probe_result(
+        &mut self,
+        result: &Result<Multiaddr, ResponseError>,
+        now: Instant,
+    ) -> Option<(Reachability, Reachability)> {
+        self.decay(now);
+        let outcome = match result {
+            Ok(address) => {
+                self.confirmed_addresses.insert(address.clone());
+                1.0
+            }
+            Err(ResponseError::DialError) => -1.0,
+            // The probe was inconclusive.
+            Err(_) => return self.transition(),
+        };
+        self.score = (self.score + self.weight * outcome).clamp(-1.0, 1.0);
+        self.transition()
+    }
+
+    /// Forgets an address that is no longer used by the local node.
+    ///
+    /// If it was the last address confirmed by probes, the evidence for the node being
+    /// public is discarded.
+    pub(crate) fn on_expired_address(
+        &mut self,
+        address: &Multiaddr,
+        now: Instant,
+    ) -> Option<(Reachability, Reachability)> {
+        self.decay(now);
+        if self.confirmed_addresses.remove(address)
+            && self.confirmed_addresses.is_empty()
+            && self.score > 0.0
+        {
+            self.score = 0.0;
+        }
+        self.transition()
+    }
+
+    /// Applies the time-based decay of the score.
+    ///
+    /// Returns the old and new reachability if it changed.
+    pub(crate) fn on_tick(&mut self, now: Instant) -> Option<(Reachability, Reachability)> {
+        self.decay(now);
+        self.transition()
+    }
+
+    fn decay(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        if elapsed.is_zero() {
+            return;
+        }
+        self.updated = now;
+        self.score *= 0.5f64.powf(elapsed.as_secs_f64() / self.half_life.as_secs_f64());
+    }
+
+    fn transition(&mut self) -> Option<(Reachability, Reachability)> {
+        let new = match self.state {
+            Reachability::Public if self.score > EXIT_THRESHOLD => Reachability::Public,
+            Reachability::Private if self.score < -EXIT_THRESHOLD => Reachability::Private,
+            _ if self.score >= ENTER_THRESHOLD => Reachability::Public,
+            _ if self.score <= -ENTER_THRESHOLD => Reachability::Private,
+            _ => Reachability::Unknown,
+        };
+        if new == self.state {
+            return None;
+        }
+        if new != Reachability::Public {
+            self.confirmed_addresses.clear();
+        }
+        tracing::debug!(old=?self.state, ?new, score=%self.score, "Reachability changed");
+
+        Some((std::mem::replace(&mut self.state, new), new))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn address() -> Multiaddr {
+        "/ip4/1.2.3.4/tcp/4001".parse().unwrap()
+    }
+
+    #[test]
+    fn single_contradicting_probe_does_not_flip() {
+        let mut tracker = ReachabilityTracker::new(&Config::default());
+        let now = Instant::now();
+
+        assert_eq!(tracker.on_probe_result(&Ok(address()), now), None);
+        assert_eq!(
+            tracker.on_probe_result(&Ok(address()), now),
+            Some((Reachability::Unknown, Reachability::Public))
+        );
+        assert_eq!(tracker.on_probe_result(&Ok(address()), now), None);
+
+        // Single failed dial-backs don't change the reachability.
+        for _ in 0..2 {
+            assert_eq!(
+                tracker.on_probe_result(&Err(ResponseError::DialError), now),
+                None
+            );
+        }
+        assert_eq!(tracker.reachability(), Reachability::Public);
+
+        // Repeated ones do, passing through `Unknown`.
+        assert_eq!(
+            tracker.on_probe_result(&Err(ResponseError::DialError), now),
+            Some((Reachability::Public, Reachability::Unknown))
+        );
+        assert_eq!(
+            tracker.on_probe_result(&Err(ResponseError::DialError), now),
+            None
+        );
+        assert_eq!(
+            tracker.on_probe_result(&Err(ResponseError::DialError), now),
+            Some((Reachability::Unknown, Reachability::Private))
+        );
+    }
+
+    #[test]
+    fn inconclusive_probes_are_ignored() {
+        let mut tracker = ReachabilityTracker::new(&Config::default());
+        let now = Instant::now();
+
+        for _ in 0..5 {
+            assert_eq!(
+                tracker.on_probe_result(&Err(ResponseError::DialRefused), now),
+                None
+            );
+        }
+        assert_eq!(tracker.reachability(), Reachability::Unknown);
+    }
+
+    #[test]
+    fn evidence_decays_over_time() {
+        let mut tracker = ReachabilityTracker::new(&Config::default());
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            tracker.on_probe_result(&Err(ResponseError::DialError), now);
+        }
+        assert_eq!(tracker.reachability(), Reachability::Private);
+
+        let later = now + Config::default().reachability_half_life * 2;
+        assert_eq!(
+            tracker.on_tick(later),
+            Some((Reachability::Private, Reachability::Unknown))
+        );
+    }
+
+    #[test]
+    fn expiry_of_last_confirmed_address_resets_public_reachability() {
+        let mut tracker = ReachabilityTracker::new(&Config::default());
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            tracker.on_probe_result(&Ok(address()), now);
+        }
+        assert_eq!(tracker.reachability(), Reachability::Public);
+
+        assert_eq!(
+            tracker.on_expired_address(&"/ip4/5.6.7.8/tcp/4001".parse().unwrap(), now),
+            None
+        );
+        assert_eq!(
+            tracker.on_expired_address(&address(), now),
+            Some((Reachability::Public, Reachability::Unknown))
+        );
+    }
+}
@@ -20,7 +20,8 @@
 
 use async_std::task::JoinHandle;
 use libp2p_autonat::{
-    Behaviour, Config, Event, NatStatus, OutboundProbeError, OutboundProbeEvent, ResponseError,
+    Behaviour, Config, Event, NatStatus, OutboundProbeError, OutboundProbeEvent, Reachability,
+    ResponseError,
 };
 use libp2p_core::Multiaddr;
 use libp2p_identity::PeerId;
@@ -214,7 +215,19 @@ async fn test_confidence() {
                 other => panic!("Unexpected behaviour event: {other:?}."),
             }
         }
+
+        // Expect reachability to only change once a second probe confirmed the status
+        if i == 1 {
+            match client.next_behaviour_event().await {
+                Event::ReachabilityChanged { old, new } => {
+                    assert_eq!(old, Reachability::Unknown);
+                    assert_eq!(new == Reachability::Public, test_public);
+                }
+                other => panic!("Unexpected behaviour event: {other:?}."),
+            }
+        }
     }
+    assert_ne!(client.behaviour().reachability(), Reachability::Unknown);
 }
 
 #[async_std::test]
@@ -28,8 +28,8 @@ use libp2p_core::multiaddr::Multiaddr;
 use libp2p_core::upgrade::{DeniedUpgrade, ReadyUpgrade};
 use libp2p_core::ConnectedPoint;
 use libp2p_swarm::handler::{
-    ConnectionEvent, DialUpgradeError, FullyNegotiatedInbound, FullyNegotiatedOutbound,
-    ListenUpgradeError,
+    Anomaly, ConnectionEvent, DialUpgradeError, FullyNegotiatedInbound, FullyNegotiatedOutbound,
+    ListenUpgradeError, ProtocolMisbehavior, Severity,
 };
 use libp2p_swarm::{
     ConnectionHandler, ConnectionHandlerEvent, StreamProtocol, StreamUpgradeError,
@@ -242,9 +242,19 @@ impl ConnectionHandler for Handler {
                 ))
             }
             Poll::Ready(Ok(Err(error))) => {
+                if let inbound::Error::Protocol(violation) = &error {
+                    let class = match violation {
+                        inbound::ProtocolViolation::Codec(_) => Anomaly::DecodeError,
+                        _ => Anomaly::ProtocolViolation,
+                    };
+                    self.queued_events
+                        .push_back(ConnectionHandlerEvent::ReportMisbehavior(
+                            ProtocolMisbehavior::new(class, Severity::Low, violation.to_string()),
+                        ));
+                }
                 return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
                     Event::InboundConnectFailed { error },
-                ))
+                ));
             }
             Poll::Ready(Err(futures_bounded::Timeout { .. })) => {
                 return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
@@ -265,9 +275,19 @@ impl ConnectionHandler for Handler {
                 ))
             }
             Poll::Ready(Ok(Err(error))) => {
+                if let outbound::Error::Protocol(violation) = &error {
+                    let class = match violation {
+                        outbound::ProtocolViolation::Codec(_) => Anomaly::DecodeError,
+                        _ => Anomaly::ProtocolViolation,
+                    };
+                    self.queued_events
+                        .push_back(ConnectionHandlerEvent::ReportMisbehavior(
+                            ProtocolMisbehavior::new(class, Severity::Low, violation.to_string()),
+                        ));
+                }
                 return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
                     Event::OutboundConnectFailed { error },
-                ))
+                ));
             }
             Poll::Ready(Err(futures_bounded::Timeout { .. })) => {
                 return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
@@ -20,50 +20,70 @@
 
 use std::{
     cmp::{max, Ordering},
+    collections::hash_map::Entry,
     collections::HashSet,
     collections::VecDeque,
     collections::{BTreeSet, HashMap},
     fmt,
+    future::Future,
     net::IpAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering as AtomicOrdering},
+        Arc,
+    },
     task::{Context, Poll},
     time::Duration,
 };
 
-use futures::StreamExt;
+use futures::channel::oneshot;
+use futures::{ready, FutureExt, StreamExt};
 use futures_ticker::Ticker;
+use futures_timer::Delay;
 use prometheus_client::registry::Registry;
 use rand::{seq::SliceRandom, thread_rng};
 
 use instant::Instant;
-use libp2p_core::{multiaddr::Protocol::Ip4, multiaddr::Protocol::Ip6, Endpoint, Multiaddr};
+use libp2p_core::{
+    multiaddr::Protocol::Ip4, multiaddr::Protocol::Ip6, Endpoint, Multiaddr, PeerRecord,
+};
 use libp2p_identity::Keypair;
 use libp2p_identity::PeerId;
 use libp2p_swarm::{
-    behaviour::{AddressChange, ConnectionClosed, ConnectionEstablished, FromSwarm},
+    behaviour::{AddressChange, ConnectionClosed, ConnectionEstablished, DialFailure, FromSwarm},
     dial_opts::DialOpts,
     ConnectionDenied, ConnectionId, NetworkBehaviour, NotifyHandler, THandler, THandlerInEvent,
     THandlerOutEvent, ToSwarm,
 };
 
 use crate::backoff::BackoffStorage;
-use crate::config::{Config, ValidationMode};
+#[cfg(any(feature = "snappy", feature = "zstd"))]
+use crate::compression;
+use crate::config::{Config, TopicConfig, ValidationMode};
 use crate::gossip_promises::GossipPromises;
 use crate::handler::{Handler, HandlerEvent, HandlerIn};
 use crate::mcache::MessageCache;
-use crate::metrics::{Churn, Config as MetricsConfig, Inclusion, Metrics, Penalty};
-use crate::peer_score::{PeerScore, PeerScoreParams, PeerScoreThresholds, RejectReason};
+use crate::metrics::{Churn, Config as MetricsConfig, Direction, Inclusion, Metrics, Penalty};
+use crate::peer_score::{
+    PeerScore, PeerScoreParams, PeerScoreSnapshot, PeerScoreThresholds, RejectReason,
+};
 use crate::protocol::SIGNING_PREFIX;
+use crate::publish_status::{PublishStatus, PublishStatusTracker};
 use crate::subscription_filter::{AllowAllSubscriptionFilter, TopicSubscriptionFilter};
 use crate::time_cache::DuplicateCache;
-use crate::topic::{Hasher, Topic, TopicHash};
+use crate::topic::{Hasher, Topic, TopicHash, TopicPattern};
+use crate::tracer::{self, TraceEvent, Tracer};
 use crate::transform::{DataTransform, IdentityTransform};
 use crate::types::{
     ControlAction, Message, MessageAcceptance, MessageId, PeerInfo, RawMessage, Subscription,
     SubscriptionAction,
 };
 use crate::types::{PeerConnections, PeerKind, RpcOut};
+use crate::validation::{MessageValidator, ValidationOutcome, ValidationPipeline};
 use crate::{rpc_proto::proto, TopicScoreParams};
-use crate::{PublishError, SubscriptionError, ValidationError};
+use crate::{
+    DeliveryError, PublishError, SubscribeWithConfigError, SubscriptionError, ValidationError,
+};
 use instant::SystemTime;
 use quick_protobuf::{MessageWrite, Writer};
 use std::{cmp::Ordering::Equal, fmt::Debug};
@@ -268,6 +288,13 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     /// forward messages to, outside of the scoring system.
     explicit_peers: HashSet<PeerId>,
 
+    /// Addresses to dial explicit peers on, as provided via
+    /// [`Behaviour::add_explicit_peer_with_addresses`].
+    explicit_peer_addresses: HashMap<PeerId, Vec<Multiaddr>>,
+
+    /// Explicit peers we are currently dialing.
+    explicit_peer_dials: HashSet<PeerId>,
+
     /// A list of peers that have been blacklisted by the user.
     /// Messages are not sent to and are rejected from these peers.
     blacklisted_peers: HashSet<PeerId>,
@@ -300,6 +327,9 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     /// be removed from this list which may result in a true outbound rediscovery.
     px_peers: HashSet<PeerId>,
 
+    /// The most recent signed peer records of connected peers, included in PX when pruning.
+    signed_peer_records: HashMap<PeerId, PeerRecord>,
+
     /// Set of connected outbound peers (we only consider true outbound peers found through
     /// discovery and not by PX).
     outbound_peers: HashSet<PeerId>,
@@ -322,6 +352,9 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
     /// our own messages back if the messages are anonymous or use a random author.
     published_message_ids: DuplicateCache<MessageId>,
 
+    /// The propagation status of recently published messages.
+    publish_statuses: PublishStatusTracker,
+
     /// The filter used to handle message subscriptions.
     subscription_filter: F,
 
@@ -332,6 +365,28 @@ pub struct Behaviour<D = IdentityTransform, F = AllowAllSubscriptionFilter> {
 
     /// Keep track of a set of internal metrics relating to gossipsub.
     metrics: Option<Metrics>,
+
+    /// Validators registered for topics and the messages awaiting their verdict.
+    validation: ValidationPipeline,
+
+    /// Messages published via [`Behaviour::publish_with_feedback`] that have not been sent to
+    /// enough peers yet.
+    pending_publishes: HashMap<MessageId, PendingPublish>,
+
+    /// Topics whose subscription changed within the current
+    /// [`Config::subscription_batch_window`], mapped to whether we last announced to be
+    /// subscribed.
+    unannounced_subscriptions: HashMap<TopicHash, bool>,
+
+    /// Timer for announcing the `unannounced_subscriptions`.
+    subscription_announcement: Option<Delay>,
+
+    /// Receives per-message events, see [`Behaviour::set_tracer`].
+    tracer: Option<Box<dyn Tracer>>,
+
+    /// Patterns subscribed to via [`Behaviour::subscribe_wildcard`], mapped to the topics we
+    /// subscribed to on their behalf.
+    wildcard_subscriptions: HashMap<TopicPattern, BTreeSet<TopicHash>>,
 }
 
 impl<D, F> Behaviour<D, F>
@@ -445,6 +500,8 @@ where
             topic_peers: HashMap::new(),
             peer_topics: HashMap::new(),
             explicit_peers: HashSet::new(),
+            explicit_peer_addresses: HashMap::new(),
+            explicit_peer_dials: HashSet::new(),
             blacklisted_peers: HashSet::new(),
             mesh: HashMap::new(),
             fanout: HashMap::new(),
@@ -461,6 +518,7 @@ where
             ),
             heartbeat_ticks: 0,
             px_peers: HashSet::new(),
+            signed_peer_records: HashMap::new(),
             outbound_peers: HashSet::new(),
             peer_score: None,
             count_received_ihave: HashMap::new(),
@@ -468,6 +526,17 @@ where
             pending_iwant_msgs: HashSet::new(),
             connected_peers: HashMap::new(),
             published_message_ids: DuplicateCache::new(config.published_message_ids_cache_time()),
+            publish_statuses: PublishStatusTracker::new(config.publish_status_capacity()),
+            pending_publishes: HashMap::new(),
+            unannounced_subscriptions: HashMap::new(),
+            subscription_announcement: None,
+            tracer: None,
+            wildcard_subscriptions: HashMap::new(),
+            validation: ValidationPipeline::new(
+                config.max_concurrent_validations(),
+                config.max_queued_validations(),
+                config.validation_timeout(),
+            ),
             config,
             subscription_filter,
             data_transform,
@@ -510,6 +579,22 @@ where
         self.connected_peers.iter().map(|(k, v)| (k, &v.kind))
     }
 
+    /// Counts the connected peers subscribed to a topic by their negotiated protocol.
+    ///
+    /// Control messages sent to peers of an older protocol version are restricted to what
+    /// that version understands, e.g. PRUNEs to [`PeerKind::Gossipsub`] peers carry no peer
+    /// exchange. Once no such peers remain on a topic, older protocol versions can safely be
+    /// dropped, see [`ConfigBuilder::protocol_id`](crate::ConfigBuilder::protocol_id).
+    pub fn topic_protocol_mix(&self, topic_hash: &TopicHash) -> HashMap<PeerKind, usize> {
+        let mut mix = HashMap::new();
+        for peer_id in self.topic_peers.get(topic_hash).into_iter().flatten() {
+            if let Some(connections) = self.connected_peers.get(peer_id) {
+                *mix.entry(connections.kind.clone()).or_default() += 1;
+            }
+        }
+        mix
+    }
+
     /// Returns the gossipsub score for a given peer, if one exists.
     pub fn peer_score(&self, peer_id: &PeerId) -> Option<f64> {
         self.peer_score
@@ -517,62 +602,291 @@ where
             .map(|(score, ..)| score.score(peer_id))
     }
 
+    /// Returns a snapshot of the scoring state of all tracked peers, if peer scoring is enabled.
+    ///
+    /// The snapshot can be used to inspect why peers are being penalized, or persisted and fed to
+    /// [`Behaviour::restore_score_snapshot`] after a restart.
+    pub fn score_snapshot(&self) -> Option<PeerScoreSnapshot> {
+        self.peer_score.as_ref().map(|(score, ..)| score.snapshot())
+    }
+
+    /// Seeds the peer scoring system with a previously captured [`PeerScoreSnapshot`].
+    ///
+    /// Restored peers are considered disconnected until they connect, and their state is retained
+    /// for [`PeerScoreParams::retain_score`]. Peers that are already tracked are left untouched.
+    ///
+    /// The [`Self::with_peer_score()`] must first be called to initialise peer scoring.
+    pub fn restore_score_snapshot(
+        &mut self,
+        snapshot: PeerScoreSnapshot,
+    ) -> Result<(), &'static str> {
+        if let Some((peer_score, ..)) = &mut self.peer_score {
+            peer_score.restore(snapshot);
+            Ok(())
+        } else {
+            Err("Peer score must be initialised with `with_peer_score()`")
+        }
+    }
+
     /// Subscribe to a topic.
     ///
     /// Returns [`Ok(true)`] if the subscription worked. Returns [`Ok(false)`] if we were already
     /// subscribed.
     pub fn subscribe<H: Hasher>(&mut self, topic: &Topic<H>) -> Result<bool, SubscriptionError> {
         tracing::debug!(%topic, "Subscribing to topic");
-        let topic_hash = topic.hash();
+        self.subscribe_topic_hash(topic.hash())
+    }
+
+    fn subscribe_topic_hash(&mut self, topic_hash: TopicHash) -> Result<bool, SubscriptionError> {
         if !self.subscription_filter.can_subscribe(&topic_hash) {
             return Err(SubscriptionError::NotAllowed);
         }
 
         if self.mesh.contains_key(&topic_hash) {
-            tracing::debug!(%topic, "Topic is already in the mesh");
+            tracing::debug!(topic=%topic_hash, "Topic is already in the mesh");
             return Ok(false);
         }
 
-        // send subscription request to all peers
-        for peer in self.peer_topics.keys().copied().collect::<Vec<_>>() {
-            tracing::debug!(%peer, "Sending SUBSCRIBE to peer");
-            let event = RpcOut::Subscribe(topic_hash.clone());
-            self.send_message(peer, event);
+        if self.config.subscription_batch_window().is_zero() {
+            // send subscription request to all peers
+            for peer in self.peer_topics.keys().copied().collect::<Vec<_>>() {
+                tracing::debug!(%peer, "Sending SUBSCRIBE to peer");
+                let event = RpcOut::Subscribe {
+                    topic: topic_hash.clone(),
+                    compression: self.config.compression_for_topic(&topic_hash),
+                };
+                self.send_message(peer, event);
+            }
+        } else {
+            self.defer_subscription_announcement(topic_hash.clone(), false);
         }
 
         // call JOIN(topic)
         // this will add new peers to the mesh for the topic
         self.join(&topic_hash);
-        tracing::debug!(%topic, "Subscribed to topic");
+        tracing::debug!(topic=%topic_hash, "Subscribed to topic");
         Ok(true)
     }
 
+    /// Subscribe to a topic, overriding the mesh parameters of the [`Config`] for this topic.
+    ///
+    /// The overrides stay in effect for the topic until replaced by another call to this method.
+    /// If we were already subscribed, the new parameters apply from the next heartbeat on.
+    ///
+    /// Returns [`Ok(true)`] if the subscription worked. Returns [`Ok(false)`] if we were already
+    /// subscribed.
+    pub fn subscribe_with_config<H: Hasher>(
+        &mut self,
+        topic: &Topic<H>,
+        topic_config: TopicConfig,
+    ) -> Result<bool, SubscribeWithConfigError> {
+        let topic_hash = topic.hash();
+        let window = topic_config.duplicate_deliveries_window;
+        // The overrides have to be in place to join the mesh of the topic.
+        let previous = self
+            .config
+            .set_topic_config(topic_hash.clone(), topic_config)
+            .map_err(SubscribeWithConfigError::InvalidTopicConfig)?;
+        let subscribed = match self.subscribe(topic) {
+            Ok(subscribed) => subscribed,
+            Err(e) => {
+                self.config.restore_topic_config(topic_hash, previous);
+                return Err(e.into());
+            }
+        };
+
+        if let Some((peer_score, ..)) = &mut self.peer_score {
+            peer_score.set_delivery_record_ttl(topic_hash, window);
+        }
+        Ok(subscribed)
+    }
+
     /// Unsubscribes from a topic.
     ///
     /// Returns [`Ok(true)`] if we were subscribed to this topic.
     pub fn unsubscribe<H: Hasher>(&mut self, topic: &Topic<H>) -> Result<bool, PublishError> {
         tracing::debug!(%topic, "Unsubscribing from topic");
-        let topic_hash = topic.hash();
+        Ok(self.unsubscribe_topic_hash(topic.hash()))
+    }
 
+    fn unsubscribe_topic_hash(&mut self, topic_hash: TopicHash) -> bool {
         if !self.mesh.contains_key(&topic_hash) {
             tracing::debug!(topic=%topic_hash, "Already unsubscribed from topic");
             // we are not subscribed
-            return Ok(false);
+            return false;
         }
 
-        // announce to all peers
-        for peer in self.peer_topics.keys().copied().collect::<Vec<_>>() {
-            tracing::debug!(%peer, "Sending UNSUBSCRIBE to peer");
-            let event = RpcOut::Unsubscribe(topic_hash.clone());
-            self.send_message(peer, event);
+        if self.config.subscription_batch_window().is_zero() {
+            // announce to all peers
+            for peer in self.peer_topics.keys().copied().collect::<Vec<_>>() {
+                tracing::debug!(%peer, "Sending UNSUBSCRIBE to peer");
+                let event = RpcOut::Unsubscribe(topic_hash.clone());
+                self.send_message(peer, event);
+            }
+        } else {
+            self.defer_subscription_announcement(topic_hash.clone(), true);
         }
 
         // call LEAVE(topic)
         // this will remove the topic from the mesh
         self.leave(&topic_hash);
 
+        // free the slot of the topic if it was subscribed to on behalf of a wildcard
+        for topics in self.wildcard_subscriptions.values_mut() {
+            topics.remove(&topic_hash);
+        }
+
         tracing::debug!(topic=%topic_hash, "Unsubscribed from topic");
-        Ok(true)
+        true
+    }
+
+    /// Subscribes to all topics matching a hierarchical [`TopicPattern`], e.g. `app/region/*`.
+    ///
+    /// Matching topics that connected peers are subscribed to are subscribed to right away,
+    /// topics announced by peers later on as they are announced. The topics a pattern expanded to
+    /// are listed by [`Behaviour::wildcard_topics`]. A pattern expands to at most
+    /// [`Config::max_wildcard_topics`] topics. Unsubscribing from an expanded topic frees its
+    /// slot, the topic is subscribed to again once announced again.
+    ///
+    /// Only the raw names of [`IdentTopic`](crate::IdentTopic)s can match a pattern.
+    ///
+    /// Returns `true` if the pattern was added, `false` if we were already subscribed to it.
+    pub fn subscribe_wildcard(&mut self, pattern: &TopicPattern) -> bool {
+        if self.wildcard_subscriptions.contains_key(pattern) {
+            return false;
+        }
+        tracing::debug!(%pattern, "Subscribing to wildcard");
+        self.wildcard_subscriptions
+            .insert(pattern.clone(), BTreeSet::new());
+
+        let known_topics = self
+            .topic_peers
+            .iter()
+            .filter(|(_, peers)| !peers.is_empty())
+            .map(|(topic_hash, _)| topic_hash.clone())
+            .collect();
+        self.expand_wildcard_subscriptions(known_topics);
+        true
+    }
+
+    /// Unsubscribes from a pattern, as well as from the topics we subscribed to on its behalf.
+    ///
+    /// Returns `true` if we were subscribed to the pattern.
+    pub fn unsubscribe_wildcard(&mut self, pattern: &TopicPattern) -> bool {
+        let Some(topics) = self.wildcard_subscriptions.remove(pattern) else {
+            return false;
+        };
+        tracing::debug!(%pattern, "Unsubscribing from wildcard");
+        for topic_hash in topics {
+            self.unsubscribe_topic_hash(topic_hash);
+        }
+        true
+    }
+
+    /// Lists the patterns subscribed to via [`Behaviour::subscribe_wildcard`] with the topics
+    /// they expanded to.
+    pub fn wildcard_topics(&self) -> impl Iterator<Item = (&TopicPattern, Vec<&TopicHash>)> {
+        self.wildcard_subscriptions
+            .iter()
+            .map(|(pattern, topics)| (pattern, topics.iter().collect()))
+    }
+
+    /// Subscribes to the given topics that match a subscribed pattern with room for more
+    /// topics.
+    fn expand_wildcard_subscriptions(&mut self, topics: Vec<TopicHash>) {
+        let max_topics = self.config.max_wildcard_topics();
+        for topic_hash in topics {
+            if self.mesh.contains_key(&topic_hash) {
+                continue;
+            }
+            let mut matching = self
+                .wildcard_subscriptions
+                .iter()
+                .filter(|(pattern, _)| pattern.matches(&topic_hash))
+                .peekable();
+            if matching.peek().is_none() {
+                continue;
+            }
+            let Some(pattern) = matching
+                .find(|(_, expanded)| expanded.len() < max_topics)
+                .map(|(pattern, _)| pattern.clone())
+            else {
+                tracing::debug!(
+                    topic=%topic_hash,
+                    "Not subscribing to topic, wildcard expansion limit reached"
+                );
+                continue;
+            };
+
+            match self.subscribe_topic_hash(topic_hash.clone()) {
+                Ok(_) => {
+                    tracing::debug!(
+                        topic=%topic_hash,
+                        %pattern,
+                        "Subscribed to topic matching wildcard"
+                    );
+                    self.wildcard_subscriptions
+                        .get_mut(&pattern)
+                        .expect("pattern to be subscribed")
+                        .insert(topic_hash);
+                }
+                Err(error) => {
+                    tracing::debug!(
+                        topic=%topic_hash,
+                        %pattern,
+                        "Failed to subscribe to topic matching wildcard: {error}"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Delays announcing a subscription change until the end of the
+    /// [`Config::subscription_batch_window`], given whether we were subscribed before.
+    fn defer_subscription_announcement(&mut self, topic_hash: TopicHash, was_subscribed: bool) {
+        self.unannounced_subscriptions
+            .entry(topic_hash)
+            .or_insert(was_subscribed);
+        if self.subscription_announcement.is_none() {
+            self.subscription_announcement =
+                Some(Delay::new(self.config.subscription_batch_window()));
+        }
+    }
+
+    /// Announces the subscription changes of the past [`Config::subscription_batch_window`] to
+    /// all peers in a single message, skipping topics we subscribed to and unsubscribed from
+    /// again.
+    fn announce_subscriptions(&mut self) {
+        let subscriptions = self
+            .unannounced_subscriptions
+            .drain()
+            .filter_map(|(topic_hash, was_subscribed)| {
+                match (was_subscribed, self.mesh.contains_key(&topic_hash)) {
+                    (false, true) => Some(Subscription {
+                        action: SubscriptionAction::Subscribe,
+                        compression: self.config.compression_for_topic(&topic_hash),
+                        topic_hash,
+                    }),
+                    (true, false) => Some(Subscription {
+                        action: SubscriptionAction::Unsubscribe,
+                        topic_hash,
+                        compression: None,
+                    }),
+                    _ => None,
+                }
+            })
+            .collect::<Vec<_>>();
+        if subscriptions.is_empty() {
+            return;
+        }
+
+        tracing::debug!(
+            subscriptions=%subscriptions.len(),
+            "Announcing subscription changes to peers"
+        );
+        for peer in self.peer_topics.keys().copied().collect::<Vec<_>>() {
+            self.send_message(peer, RpcOut::Subscriptions(subscriptions.clone()));
+        }
     }
 
     /// Publishes a message with multiple topics to the network.
@@ -581,6 +895,64 @@ where
         topic: impl Into<TopicHash>,
         data: impl Into<Vec<u8>>,
     ) -> Result<MessageId, PublishError> {
+        self.publish_to_peers(topic, data).map(|(msg_id, _)| msg_id)
+    }
+
+    /// Publishes a message and returns a [`PublishHandle`] that resolves once the message has
+    /// been sent to at least `min_peers` peers.
+    ///
+    /// The handle fails with [`DeliveryError::QueueFull`] if too many peers dropped the message
+    /// because their send queue was full (see [`Config::max_queued_messages()`]), and with
+    /// [`DeliveryError::InsufficientPeers`] if the message was published to fewer than
+    /// `min_peers` peers or too many of them disconnected before it was sent. If the message has
+    /// not been sent to enough peers within [`Config::publish_feedback_timeout()`], the handle
+    /// fails with [`DeliveryError::Timeout`]. The number of peers that dropped the message is
+    /// available through [`PublishHandle::dropped()`].
+    pub fn publish_with_feedback(
+        &mut self,
+        topic: impl Into<TopicHash>,
+        data: impl Into<Vec<u8>>,
+        min_peers: usize,
+    ) -> Result<PublishHandle, PublishError> {
+        let (msg_id, recipient_peers) = self.publish_to_peers(topic, data)?;
+
+        let (sender, receiver) = oneshot::channel();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let pending = PendingPublish {
+            remaining: min_peers,
+            outstanding: recipient_peers,
+            dropped: dropped.clone(),
+            deadline: Instant::now() + self.config.publish_feedback_timeout(),
+            sender,
+        };
+        if pending.remaining == 0 {
+            let _ = pending.sender.send(Ok(()));
+        } else if pending.outstanding.len() < pending.remaining {
+            let _ = pending.sender.send(Err(DeliveryError::InsufficientPeers));
+        } else {
+            self.pending_publishes.insert(msg_id.clone(), pending);
+        }
+
+        Ok(PublishHandle {
+            message_id: msg_id,
+            dropped,
+            receiver,
+        })
+    }
+
+    /// Returns the propagation status of a recently published message.
+    ///
+    /// Statuses are kept for the last [`Config::publish_status_capacity()`] published messages.
+    pub fn publish_status(&self, message_id: &MessageId) -> Option<&PublishStatus> {
+        self.publish_statuses.get(message_id)
+    }
+
+    /// Publishes a message, returning its id and the peers it was sent to.
+    fn publish_to_peers(
+        &mut self,
+        topic: impl Into<TopicHash>,
+        data: impl Into<Vec<u8>>,
+    ) -> Result<(MessageId, HashSet<PeerId>), PublishError> {
         let data = data.into();
         let topic = topic.into();
 
@@ -588,6 +960,8 @@ where
         let transformed_data = self
             .data_transform
             .outbound_transform(&topic, data.clone())?;
+        #[cfg(any(feature = "snappy", feature = "zstd"))]
+        let transformed_data = self.compress(&topic, transformed_data)?;
 
         let raw_message = self.build_raw_message(topic, transformed_data)?;
 
@@ -621,7 +995,7 @@ where
 
         let mut recipient_peers = HashSet::new();
         if let Some(set) = self.topic_peers.get(&topic_hash) {
-            if self.config.flood_publish() {
+            if self.config.flood_publish_for_topic(&topic_hash) {
                 // Forward to all peers above score and all explicit peers
                 recipient_peers.extend(set.iter().filter(|p| {
                     self.explicit_peers.contains(*p)
@@ -643,7 +1017,7 @@ where
                             }
                         } else {
                             // We have no fanout peers, select mesh_n of them and add them to the fanout
-                            let mesh_n = self.config.mesh_n();
+                            let mesh_n = self.config.mesh_n_for_topic(&topic_hash);
                             let new_peers = get_random_peers(
                                 &self.topic_peers,
                                 &self.connected_peers,
@@ -699,7 +1073,10 @@ where
 
         // If the message isn't a duplicate and we have sent it to some peers add it to the
         // duplicate cache and memcache.
-        self.duplicate_cache.insert(msg_id.clone());
+        self.duplicate_cache.insert_with_ttl(
+            msg_id.clone(),
+            self.config.seen_ttl_for_topic(&raw_message.topic),
+        );
         self.mcache.put(&msg_id, raw_message.clone());
 
         // If the message is anonymous or has a random author add it to the published message ids
@@ -713,16 +1090,71 @@ where
         // Send to peers we know are subscribed to the topic.
         for peer_id in recipient_peers.iter() {
             tracing::trace!(peer=%peer_id, "Sending message to peer");
-            self.send_message(*peer_id, RpcOut::Publish(raw_message.clone()));
+            self.send_message(
+                *peer_id,
+                RpcOut::Publish {
+                    message: raw_message.clone(),
+                    message_id: msg_id.clone(),
+                },
+            );
         }
 
         tracing::debug!(message=%msg_id, "Published message");
 
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.trace(TraceEvent::PublishMessage {
+                message_id: &msg_id,
+                topic: &topic_hash,
+            });
+        }
+
         if let Some(metrics) = self.metrics.as_mut() {
             metrics.register_published_message(&topic_hash);
         }
 
-        Ok(msg_id)
+        self.publish_statuses
+            .published(msg_id.clone(), topic_hash, recipient_peers.clone());
+
+        Ok((msg_id, recipient_peers))
+    }
+
+    /// Updates the delivery state of a message published via
+    /// [`Behaviour::publish_with_feedback`] after `peer_id` sent, dropped or could no longer
+    /// receive it.
+    fn update_pending_publish(
+        &mut self,
+        msg_id: &MessageId,
+        peer_id: &PeerId,
+        outcome: Result<(), DeliveryError>,
+    ) {
+        let Entry::Occupied(mut entry) = self.pending_publishes.entry(msg_id.clone()) else {
+            return;
+        };
+        let pending = entry.get_mut();
+        if !pending.outstanding.remove(peer_id) {
+            return;
+        }
+        if let Err(DeliveryError::QueueFull) = outcome {
+            pending.dropped.fetch_add(1, AtomicOrdering::Relaxed);
+        }
+
+        let result = match outcome {
+            Ok(()) => {
+                pending.remaining -= 1;
+                if pending.remaining > 0 {
+                    return;
+                }
+                Ok(())
+            }
+            Err(e) => {
+                if pending.outstanding.len() >= pending.remaining {
+                    return;
+                }
+                Err(e)
+            }
+        };
+
+        let _ = entry.remove().sender.send(result);
     }
 
     /// This function should be called when [`Config::validate_messages()`] is `true` after
@@ -789,6 +1221,15 @@ where
                 metrics.register_msg_validation(&raw_message.topic, &acceptance);
             }
 
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.trace(TraceEvent::RejectMessage {
+                    message_id: Some(msg_id),
+                    topic: &raw_message.topic,
+                    received_from: propagation_source,
+                    reason: tracer::reject_reason(reject_reason),
+                });
+            }
+
             // Tell peer_score about reject
             // Reject the original source, and any duplicates we've seen from other peers.
             if let Some((peer_score, ..)) = &mut self.peer_score {
@@ -809,20 +1250,132 @@ where
         }
     }
 
+    /// Registers a validator that is run for every message received on `topic`, replacing any
+    /// previously registered one.
+    ///
+    /// Received messages are only emitted as [`Event::Message`] once the validator accepted them.
+    /// The outcome of each validation is reported through
+    /// [`Behaviour::report_message_validation_result`] automatically, so it must not be called for
+    /// messages on this topic. Messages are validated concurrently, up to
+    /// [`Config::max_concurrent_validations()`], and ignored if their validation exceeds
+    /// [`Config::validation_timeout()`].
+    ///
+    /// Validators only take effect if [`Config::validate_messages()`] is `true`, since otherwise
+    /// messages are forwarded as soon as they are received.
+    pub fn register_validator<H: Hasher>(
+        &mut self,
+        topic: &Topic<H>,
+        validator: impl MessageValidator,
+    ) {
+        if !self.config.validate_messages() {
+            tracing::warn!(
+                %topic,
+                "Registering a validator without `Config::validate_messages` has no effect"
+            );
+        }
+        self.validation.register(topic.hash(), Box::new(validator));
+    }
+
+    /// Removes the validator of `topic`.
+    ///
+    /// Messages that are still queued for validation are emitted as [`Event::Message`] and have to
+    /// be validated through [`Behaviour::report_message_validation_result`].
+    ///
+    /// Returns `true` if a validator was registered for `topic`.
+    pub fn unregister_validator<H: Hasher>(&mut self, topic: &Topic<H>) -> bool {
+        self.validation.unregister(&topic.hash())
+    }
+
+    /// Adds a signed peer record, which is shared with other peers via peer exchange when pruning.
+    ///
+    /// Records of peers that are not connected or that are older than an already known record of
+    /// the same peer are ignored. Returns `true` if the record was added.
+    pub fn add_signed_peer_record(&mut self, record: PeerRecord) -> bool {
+        let peer_id = record.peer_id();
+        if !self.connected_peers.contains_key(&peer_id) {
+            return false;
+        }
+        self.insert_signed_peer_record(record)
+    }
+
+    /// Stores a signed peer record unless a more recent one of the same peer is already known.
+    fn insert_signed_peer_record(&mut self, record: PeerRecord) -> bool {
+        match self.signed_peer_records.entry(record.peer_id()) {
+            Entry::Occupied(mut entry) => {
+                if entry.get().seq() >= record.seq() {
+                    return false;
+                }
+                entry.insert(record);
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(record);
+            }
+        }
+        true
+    }
+
     /// Adds a new peer to the list of explicitly connected peers.
     pub fn add_explicit_peer(&mut self, peer_id: &PeerId) {
         tracing::debug!(peer=%peer_id, "Adding explicit peer");
 
         self.explicit_peers.insert(*peer_id);
 
+        // Explicit peers are never part of the mesh, prune it from the topics it was grafted in.
+        let topics = self
+            .mesh
+            .iter()
+            .filter(|(_, peers)| peers.contains(peer_id))
+            .map(|(topic_hash, _)| topic_hash.clone())
+            .collect::<Vec<_>>();
+        for topic_hash in topics {
+            tracing::debug!(
+                peer=%peer_id,
+                topic=%topic_hash,
+                "Sending PRUNE to new explicit peer"
+            );
+            let control = self.make_prune(&topic_hash, peer_id, self.config.do_px(), false);
+            Self::control_pool_add(&mut self.control_pool, *peer_id, control);
+            self.remove_peer_from_mesh(peer_id, &topic_hash, None, false, Churn::Prune);
+        }
+
         self.check_explicit_peer_connection(peer_id);
     }
 
+    /// Adds a new peer to the list of explicitly connected peers, dialing it on the given
+    /// addresses whenever a connection to it has to be (re)established.
+    pub fn add_explicit_peer_with_addresses(
+        &mut self,
+        peer_id: &PeerId,
+        addresses: Vec<Multiaddr>,
+    ) {
+        self.explicit_peer_addresses.insert(*peer_id, addresses);
+        self.add_explicit_peer(peer_id);
+    }
+
     /// This removes the peer from explicitly connected peers, note that this does not disconnect
     /// the peer.
+    ///
+    /// The peer is treated like any other peer from then on and may be grafted into the mesh in
+    /// subsequent heartbeats.
     pub fn remove_explicit_peer(&mut self, peer_id: &PeerId) {
         tracing::debug!(peer=%peer_id, "Removing explicit peer");
         self.explicit_peers.remove(peer_id);
+        self.explicit_peer_addresses.remove(peer_id);
+        self.explicit_peer_dials.remove(peer_id);
+    }
+
+    /// Lists all explicit peers together with the state of our connection to them.
+    pub fn explicit_peers(&self) -> impl Iterator<Item = (&PeerId, ExplicitPeerStatus)> {
+        self.explicit_peers.iter().map(|peer_id| {
+            let status = if self.connected_peers.contains_key(peer_id) {
+                ExplicitPeerStatus::Connected
+            } else if self.explicit_peer_dials.contains(peer_id) {
+                ExplicitPeerStatus::Dialing
+            } else {
+                ExplicitPeerStatus::Disconnected
+            };
+            (peer_id, status)
+        })
     }
 
     /// Blacklists a peer. All messages from this peer will be rejected and any message that was
@@ -867,11 +1420,20 @@ where
         }
 
         let interval = Ticker::new(params.decay_interval);
-        let peer_score = PeerScore::new_with_message_delivery_time_callback(params, callback);
+        let mut peer_score = PeerScore::new_with_message_delivery_time_callback(params, callback);
+        for (topic_hash, window) in self.config.duplicate_deliveries_windows() {
+            peer_score.set_delivery_record_ttl(topic_hash.clone(), Some(window));
+        }
         self.peer_score = Some((peer_score, threshold, interval, GossipPromises::default()));
         Ok(())
     }
 
+    /// Registers a [`Tracer`] receiving an event for every published, received, validated,
+    /// rejected and forwarded message, replacing any previously registered one.
+    pub fn set_tracer(&mut self, tracer: impl Tracer) {
+        self.tracer = Some(Box::new(tracer));
+    }
+
     /// Sets scoring parameters for a topic.
     ///
     /// The [`Self::with_peer_score()`] must first be called to initialise peer scoring.
@@ -936,7 +1498,7 @@ where
 
             // Add up to mesh_n of them them to the mesh
             // NOTE: These aren't randomly added, currently FIFO
-            let add_peers = std::cmp::min(peers.len(), self.config.mesh_n());
+            let add_peers = std::cmp::min(peers.len(), self.config.mesh_n_for_topic(topic_hash));
             tracing::debug!(
                 topic=%topic_hash,
                 "JOIN: Adding {:?} peers from the fanout for topic",
@@ -959,13 +1521,13 @@ where
         }
 
         // check if we need to get more peers, which we randomly select
-        if added_peers.len() < self.config.mesh_n() {
+        if added_peers.len() < self.config.mesh_n_for_topic(topic_hash) {
             // get the peers
             let new_peers = get_random_peers(
                 &self.topic_peers,
                 &self.connected_peers,
                 topic_hash,
-                self.config.mesh_n() - added_peers.len(),
+                self.config.mesh_n_for_topic(topic_hash) - added_peers.len(),
                 |peer| {
                     !added_peers.contains(peer)
                         && !self.explicit_peers.contains(peer)
@@ -1061,7 +1623,10 @@ where
                 |p| p != peer && !self.score_below_threshold(p, |_| 0.0).0,
             )
             .into_iter()
-            .map(|p| PeerInfo { peer_id: Some(p) })
+            .map(|p| PeerInfo {
+                peer_id: Some(p),
+                signed_peer_record: self.signed_peer_records.get(&p).cloned(),
+            })
             .collect()
         } else {
             Vec::new()
@@ -1117,12 +1682,24 @@ where
     /// Checks if the given peer is still connected and if not dials the peer again.
     fn check_explicit_peer_connection(&mut self, peer_id: &PeerId) {
         if !self.peer_topics.contains_key(peer_id) {
-            // Connect to peer
-            tracing::debug!(peer=%peer_id, "Connecting to explicit peer");
-            self.events.push_back(ToSwarm::Dial {
-                opts: DialOpts::peer_id(*peer_id).build(),
-            });
+            self.dial_explicit_peer(peer_id);
+        }
+    }
+
+    /// Dials an explicit peer unless a dial is already in progress.
+    fn dial_explicit_peer(&mut self, peer_id: &PeerId) {
+        if !self.explicit_peer_dials.insert(*peer_id) {
+            return;
         }
+
+        tracing::debug!(peer=%peer_id, "Connecting to explicit peer");
+        let opts = match self.explicit_peer_addresses.get(peer_id) {
+            Some(addresses) => DialOpts::peer_id(*peer_id)
+                .addresses(addresses.clone())
+                .build(),
+            None => DialOpts::peer_id(*peer_id).build(),
+        };
+        self.events.push_back(ToSwarm::Dial { opts });
     }
 
     /// Determines if a peer's score is below a given `PeerScoreThreshold` chosen via the
@@ -1190,7 +1767,7 @@ where
 
         tracing::trace!(peer=%peer_id, "Handling IHAVE for peer");
 
-        let mut iwant_ids = HashSet::new();
+        let mut iwant_ids = HashMap::new();
 
         let want_message = |id: &MessageId| {
             if self.duplicate_cache.contains(id) {
@@ -1219,7 +1796,8 @@ where
 
             for id in ids.into_iter().filter(want_message) {
                 // have not seen this message and are not currently requesting it
-                if iwant_ids.insert(id) {
+                if let Entry::Vacant(entry) = iwant_ids.entry(id) {
+                    entry.insert(topic.clone());
                     // Register the IWANT metric
                     if let Some(metrics) = self.metrics.as_mut() {
                         metrics.register_iwant(&topic);
@@ -1244,7 +1822,7 @@ where
             );
 
             // Ask in random order
-            let mut iwant_ids_vec: Vec<_> = iwant_ids.into_iter().collect();
+            let mut iwant_ids_vec: Vec<_> = iwant_ids.keys().cloned().collect();
             let mut rng = thread_rng();
             iwant_ids_vec.partial_shuffle(&mut rng, iask);
 
@@ -1254,6 +1832,9 @@ where
             for message_id in &iwant_ids_vec {
                 // Add all messages to the pending list
                 self.pending_iwant_msgs.insert(message_id.clone());
+                if let Some(metrics) = self.metrics.as_mut() {
+                    metrics.iwant_requested(&iwant_ids[message_id], message_id);
+                }
             }
 
             if let Some((_, _, _, gossip_promises)) = &mut self.peer_score {
@@ -1311,7 +1892,14 @@ where
                     );
                 } else {
                     tracing::debug!(peer=%peer_id, "IWANT: Sending cached messages to peer");
-                    self.send_message(*peer_id, RpcOut::Forward(msg));
+                    self.publish_statuses.iwant_served(&id, *peer_id);
+                    self.send_message(
+                        *peer_id,
+                        RpcOut::Forward {
+                            message: msg,
+                            message_id: id.clone(),
+                        },
+                    );
                 }
             }
         }
@@ -1323,6 +1911,12 @@ where
     fn handle_graft(&mut self, peer_id: &PeerId, topics: Vec<TopicHash>) {
         tracing::debug!(peer=%peer_id, "Handling GRAFT message for peer");
 
+        if let Some(m) = self.metrics.as_mut() {
+            for topic in &topics {
+                m.register_graft(topic, Direction::Received);
+            }
+        }
+
         let mut to_prune_topics = HashSet::new();
 
         let mut do_px = self.config.do_px();
@@ -1414,7 +2008,7 @@ where
 
                     // check mesh upper bound and only allow graft if the upper bound is not reached or
                     // if it is an outbound peer
-                    if peers.len() >= self.config.mesh_n_high()
+                    if peers.len() >= self.config.mesh_n_high_for_topic(&topic_hash)
                         && !self.outbound_peers.contains(peer_id)
                     {
                         to_prune_topics.insert(topic_hash.clone());
@@ -1539,6 +2133,9 @@ where
         let (below_threshold, score) =
             self.score_below_threshold(peer_id, |pst| pst.accept_px_threshold);
         for (topic_hash, px, backoff) in prune_data {
+            if let Some(m) = self.metrics.as_mut() {
+                m.register_prune(&topic_hash, Direction::Received);
+            }
             self.remove_peer_from_mesh(peer_id, &topic_hash, backoff, true, Churn::Prune);
 
             if self.mesh.contains_key(&topic_hash) {
@@ -1572,11 +2169,8 @@ where
 
     fn px_connect(&mut self, mut px: Vec<PeerInfo>) {
         let n = self.config.prune_peers();
-        // Ignore peerInfo with no ID
-        //
-        //TODO: Once signed records are spec'd: Can we use peerInfo without any IDs if they have a
-        // signed peer record?
-        px.retain(|p| p.peer_id.is_some());
+        // Ignore peerInfo with neither an ID nor a signed peer record
+        px.retain(|p| p.peer_id().is_some());
         if px.len() > n {
             // only use at most prune_peers many random peers
             let mut rng = thread_rng();
@@ -1585,17 +2179,30 @@ where
         }
 
         for p in px {
-            // TODO: Once signed records are spec'd: extract signed peer record if given and handle
-            // it, see https://github.com/libp2p/specs/pull/217
-            if let Some(peer_id) = p.peer_id {
-                // mark as px peer
-                self.px_peers.insert(peer_id);
-
-                // dial peer
-                self.events.push_back(ToSwarm::Dial {
-                    opts: DialOpts::peer_id(peer_id).build(),
-                });
+            let Some(peer_id) = p.peer_id() else {
+                continue;
+            };
+
+            // mark as px peer
+            self.px_peers.insert(peer_id);
+
+            let mut addresses = Vec::new();
+            if let Some(record) = p.signed_peer_record {
+                // The record was verified on receipt, share its addresses with the swarm.
+                addresses = record.addresses().to_vec();
+                for address in &addresses {
+                    self.events.push_back(ToSwarm::NewExternalAddrOfPeer {
+                        peer_id,
+                        address: address.clone(),
+                    });
+                }
+                self.insert_signed_peer_record(record);
             }
+
+            // dial peer
+            self.events.push_back(ToSwarm::Dial {
+                opts: DialOpts::peer_id(peer_id).addresses(addresses).build(),
+            });
         }
     }
 
@@ -1628,6 +2235,14 @@ where
                 );
                 gossip_promises.reject_message(msg_id, &RejectReason::BlackListedPeer);
             }
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.trace(TraceEvent::RejectMessage {
+                    message_id: Some(msg_id),
+                    topic: &raw_message.topic,
+                    received_from: propagation_source,
+                    reason: tracer::reject_reason(RejectReason::BlackListedPeer),
+                });
+            }
             return false;
         }
 
@@ -1665,6 +2280,7 @@ where
             };
 
         if self_published {
+            self.publish_statuses.echoed(msg_id, *propagation_source);
             tracing::debug!(
                 message=%msg_id,
                 source=%propagation_source,
@@ -1691,7 +2307,7 @@ where
         }
 
         // Try and perform the data transform to the message. If it fails, consider it invalid.
-        let message = match self.data_transform.inbound_transform(raw_message.clone()) {
+        let message = match self.inbound_transform(raw_message.clone()) {
             Ok(message) => message,
             Err(e) => {
                 tracing::debug!("Invalid message. Transform error: {:?}", e);
@@ -1715,8 +2331,22 @@ where
             return;
         }
 
-        if !self.duplicate_cache.insert(msg_id.clone()) {
+        if !self.duplicate_cache.insert_with_ttl(
+            msg_id.clone(),
+            self.config.seen_ttl_for_topic(&message.topic),
+        ) {
             tracing::debug!(message=%msg_id, "Message already received, ignoring");
+            self.publish_statuses.echoed(&msg_id, *propagation_source);
+            if let Some(tracer) = self.tracer.as_mut() {
+                tracer.trace(TraceEvent::DuplicateMessage {
+                    message_id: &msg_id,
+                    topic: &message.topic,
+                    received_from: propagation_source,
+                });
+            }
+            if let Some(metrics) = self.metrics.as_mut() {
+                metrics.msg_duplicate(&message.topic);
+            }
             if let Some((peer_score, ..)) = &mut self.peer_score {
                 peer_score.duplicated_message(propagation_source, &msg_id, &message.topic);
             }
@@ -1731,6 +2361,15 @@ where
         // Record the received message with the metrics
         if let Some(metrics) = self.metrics.as_mut() {
             metrics.msg_recvd(&message.topic);
+            metrics.iwant_fulfilled(&msg_id);
+        }
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.trace(TraceEvent::ReceiveMessage {
+                message_id: &msg_id,
+                topic: &message.topic,
+                received_from: propagation_source,
+            });
         }
 
         // Tells score that message arrived (but is maybe not fully validated yet).
@@ -1745,6 +2384,22 @@ where
 
         // Dispatch the message to the user if we are subscribed to any of the topics
         if self.mesh.contains_key(&message.topic) {
+            if self.config.validate_messages() && self.validation.has_validator(&message.topic) {
+                if self.validation.is_full() {
+                    tracing::debug!(message=%msg_id, "Validation queue full, ignoring message");
+                    if let Err(e) = self.report_message_validation_result(
+                        &msg_id,
+                        propagation_source,
+                        MessageAcceptance::Ignore,
+                    ) {
+                        tracing::error!(message=%msg_id, "Failed to ignore message: {e}");
+                    }
+                    return;
+                }
+                tracing::debug!(message=%msg_id, "Queueing received message for validation");
+                self.validation.push(msg_id, *propagation_source, message);
+                return;
+            }
             tracing::debug!("Sending received message to user");
             self.events
                 .push_back(ToSwarm::GenerateEvent(Event::Message {
@@ -1777,6 +2432,41 @@ where
         }
     }
 
+    /// Reports the outcome of a validation run by a registered [`MessageValidator`].
+    fn handle_validation_outcome(&mut self, outcome: ValidationOutcome) {
+        let ValidationOutcome {
+            message_id,
+            propagation_source,
+            message,
+            acceptance,
+        } = outcome;
+
+        let accepted = match acceptance {
+            Some(acceptance) => {
+                let accepted = matches!(acceptance, MessageAcceptance::Accept);
+                if let Err(e) = self.report_message_validation_result(
+                    &message_id,
+                    &propagation_source,
+                    acceptance,
+                ) {
+                    tracing::error!(message=%message_id, "Failed to forward message: {e}");
+                }
+                accepted
+            }
+            // The validator was removed, leave the validation to the user.
+            None => true,
+        };
+
+        if accepted {
+            self.events
+                .push_back(ToSwarm::GenerateEvent(Event::Message {
+                    propagation_source,
+                    message_id,
+                    message,
+                }));
+        }
+    }
+
     // Handles invalid messages received.
     fn handle_invalid_message(
         &mut self,
@@ -1784,14 +2474,30 @@ where
         raw_message: &RawMessage,
         reject_reason: RejectReason,
     ) {
+        if self.peer_score.is_none() && self.tracer.is_none() {
+            return;
+        }
+
+        let message = self
+            .inbound_transform(raw_message.clone())
+            .ok()
+            .map(|message| (self.config.message_id(&message), message));
+
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.trace(TraceEvent::RejectMessage {
+                message_id: message.as_ref().map(|(message_id, _)| message_id),
+                topic: &raw_message.topic,
+                received_from: propagation_source,
+                reason: tracer::reject_reason(reject_reason),
+            });
+        }
+
         if let Some((peer_score, .., gossip_promises)) = &mut self.peer_score {
             if let Some(metrics) = self.metrics.as_mut() {
                 metrics.register_invalid_message(&raw_message.topic);
             }
 
-            if let Ok(message) = self.data_transform.inbound_transform(raw_message.clone()) {
-                let message_id = self.config.message_id(&message);
-
+            if let Some((message_id, message)) = message {
                 peer_score.reject_message(
                     propagation_source,
                     &message_id,
@@ -1834,6 +2540,9 @@ where
         // Collect potential graft topics for the peer.
         let mut topics_to_graft = Vec::new();
 
+        // Collect announced topics we are not subscribed to, for wildcard subscriptions.
+        let mut announced_topics = Vec::new();
+
         // Notify the application about the subscription, after the grafts are sent.
         let mut application_event = Vec::new();
 
@@ -1853,8 +2562,21 @@ where
         };
 
         for subscription in filtered_topics {
-            // get the peers from the mapping, or insert empty lists if the topic doesn't exist
             let topic_hash = &subscription.topic_hash;
+
+            // We can't decode messages the peer compresses differently.
+            if subscription.action == SubscriptionAction::Subscribe
+                && subscription.compression != self.config.compression_for_topic(topic_hash)
+            {
+                tracing::warn!(
+                    peer=%propagation_source,
+                    topic=%topic_hash,
+                    "Ignoring subscription of peer using a different compression for the topic"
+                );
+                continue;
+            }
+
+            // get the peers from the mapping, or insert empty lists if the topic doesn't exist
             let peer_list = self.topic_peers.entry(topic_hash.clone()).or_default();
 
             match subscription.action {
@@ -1870,6 +2592,10 @@ where
                     // add to the peer_topics mapping
                     subscribed_topics.insert(topic_hash.clone());
 
+                    if !self.mesh.contains_key(topic_hash) {
+                        announced_topics.push(topic_hash.clone());
+                    }
+
                     // if the mesh needs peers add the peer to the mesh
                     if !self.explicit_peers.contains(propagation_source)
                         && matches!(
@@ -1889,7 +2615,7 @@ where
                             .is_backoff_with_slack(topic_hash, propagation_source)
                     {
                         if let Some(peers) = self.mesh.get_mut(topic_hash) {
-                            if peers.len() < self.config.mesh_n_low()
+                            if peers.len() < self.config.mesh_n_low_for_topic(topic_hash)
                                 && peers.insert(*propagation_source)
                             {
                                 tracing::debug!(
@@ -1977,6 +2703,10 @@ where
             self.events.push_back(event);
         }
 
+        if !self.wildcard_subscriptions.is_empty() {
+            self.expand_wildcard_subscriptions(announced_topics);
+        }
+
         tracing::trace!(
             source=%propagation_source,
             "Completed handling subscriptions from source"
@@ -2009,10 +2739,28 @@ where
         // clean up expired backoffs
         self.backoffs.heartbeat();
 
+        // fail published messages that were not sent to enough peers in time
+        let expired_publishes = self
+            .pending_publishes
+            .iter()
+            .filter(|(_, pending)| pending.deadline <= start)
+            .map(|(msg_id, _)| msg_id.clone())
+            .collect::<Vec<_>>();
+        for msg_id in expired_publishes {
+            if let Some(pending) = self.pending_publishes.remove(&msg_id) {
+                let _ = pending.sender.send(Err(DeliveryError::Timeout));
+            }
+        }
+
         // clean up ihave counters
         self.count_sent_iwant.clear();
         self.count_received_ihave.clear();
 
+        // stop tracking iwant requests that were not fulfilled in time
+        if let Some(metrics) = self.metrics.as_mut() {
+            metrics.expire_iwant_requests(self.config.iwant_followup_time());
+        }
+
         // apply iwant penalties
         self.apply_iwant_penalties();
 
@@ -2076,15 +2824,15 @@ where
             }
 
             // too little peers - add some
-            if peers.len() < self.config.mesh_n_low() {
+            if peers.len() < self.config.mesh_n_low_for_topic(topic_hash) {
                 tracing::debug!(
                     topic=%topic_hash,
                     "HEARTBEAT: Mesh low. Topic contains: {} needs: {}",
                     peers.len(),
-                    self.config.mesh_n_low()
+                    self.config.mesh_n_low_for_topic(topic_hash)
                 );
                 // not enough peers - get mesh_n - current_length more
-                let desired_peers = self.config.mesh_n() - peers.len();
+                let desired_peers = self.config.mesh_n_for_topic(topic_hash) - peers.len();
                 let peer_list = get_random_peers(
                     topic_peers,
                     &self.connected_peers,
@@ -2110,14 +2858,14 @@ where
             }
 
             // too many peers - remove some
-            if peers.len() > self.config.mesh_n_high() {
+            if peers.len() > self.config.mesh_n_high_for_topic(topic_hash) {
                 tracing::debug!(
                     topic=%topic_hash,
                     "HEARTBEAT: Mesh high. Topic contains: {} needs: {}",
                     peers.len(),
-                    self.config.mesh_n_high()
+                    self.config.mesh_n_high_for_topic(topic_hash)
                 );
-                let excess_peer_no = peers.len() - self.config.mesh_n();
+                let excess_peer_no = peers.len() - self.config.mesh_n_for_topic(topic_hash);
 
                 // shuffle the peers and then sort by score ascending beginning with the worst
                 let mut rng = thread_rng();
@@ -2171,7 +2919,7 @@ where
             }
 
             // do we have enough outbound peers?
-            if peers.len() >= self.config.mesh_n_low() {
+            if peers.len() >= self.config.mesh_n_low_for_topic(topic_hash) {
                 // count number of outbound peers we have
                 let outbound = { peers.iter().filter(|p| outbound_peers.contains(*p)).count() };
 
@@ -2330,13 +3078,13 @@ where
             }
 
             // not enough peers
-            if peers.len() < self.config.mesh_n() {
+            if peers.len() < self.config.mesh_n_for_topic(topic_hash) {
                 tracing::debug!(
                     "HEARTBEAT: Fanout low. Contains: {:?} needs: {:?}",
                     peers.len(),
-                    self.config.mesh_n()
+                    self.config.mesh_n_for_topic(topic_hash)
                 );
-                let needed_peers = self.config.mesh_n() - peers.len();
+                let needed_peers = self.config.mesh_n_for_topic(topic_hash) - peers.len();
                 let explicit_peers = &self.explicit_peers;
                 let new_peers = get_random_peers(
                     &self.topic_peers,
@@ -2426,7 +3174,7 @@ where
             let n_map = |m| {
                 max(
                     self.config.gossip_lazy(),
-                    (self.config.gossip_factor() * m as f64) as usize,
+                    (self.config.gossip_factor_for_topic(topic_hash) * m as f64) as usize,
                 )
             };
             // get gossip_lazy random peers
@@ -2565,6 +3313,13 @@ where
                 peer_score.deliver_message(peer, msg_id, &message.topic);
             }
         }
+        if let (Some(tracer), Some(peer)) = (self.tracer.as_mut(), propagation_source) {
+            tracer.trace(TraceEvent::DeliverMessage {
+                message_id: msg_id,
+                topic: &message.topic,
+                received_from: peer,
+            });
+        }
 
         tracing::debug!(message=%msg_id, "Forwarding message");
         let mut recipient_peers = HashSet::new();
@@ -2600,9 +3355,20 @@ where
             }
         }
 
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer.trace(TraceEvent::ForwardMessage {
+                message_id: msg_id,
+                topic: &message.topic,
+                peers: recipient_peers.len(),
+            });
+        }
+
         // forward the message to peers
         if !recipient_peers.is_empty() {
-            let event = RpcOut::Forward(message.clone());
+            let event = RpcOut::Forward {
+                message: message.clone(),
+                message_id: msg_id.clone(),
+            };
 
             for peer in recipient_peers.iter() {
                 tracing::debug!(%peer, message=%msg_id, "Sending message to peer");
@@ -2615,6 +3381,38 @@ where
         }
     }
 
+    /// Compresses the data of a message published to `topic` if configured for the topic.
+    #[cfg(any(feature = "snappy", feature = "zstd"))]
+    fn compress(&mut self, topic: &TopicHash, data: Vec<u8>) -> Result<Vec<u8>, std::io::Error> {
+        let Some(compression) = self
+            .config
+            .topic_config(topic)
+            .and_then(|c| c.compression.as_ref())
+        else {
+            return Ok(data);
+        };
+
+        let uncompressed_len = data.len();
+        let compressed = compression.compress(data)?;
+        if let Some(metrics) = self.metrics.as_mut() {
+            if uncompressed_len > 0 {
+                metrics.observe_compression_ratio(
+                    topic,
+                    compressed.len() as f64 / uncompressed_len as f64,
+                );
+            }
+        }
+        Ok(compressed)
+    }
+
+    /// Decompresses the data of a received message if configured for its topic and applies the
+    /// [`DataTransform`].
+    fn inbound_transform(&self, raw_message: RawMessage) -> Result<Message, std::io::Error> {
+        #[cfg(any(feature = "snappy", feature = "zstd"))]
+        let raw_message = compression::decompress_raw(&self.config, raw_message)?;
+        self.data_transform.inbound_transform(raw_message)
+    }
+
     /// Constructs a [`RawMessage`] performing message signing if required.
     pub(crate) fn build_raw_message(
         &mut self,
@@ -2732,9 +3530,18 @@ where
     /// is not already an arc.
     fn send_message(&mut self, peer_id: PeerId, rpc: RpcOut) {
         if let Some(m) = self.metrics.as_mut() {
-            if let RpcOut::Publish(ref message) | RpcOut::Forward(ref message) = rpc {
-                // register bytes sent on the internal metrics.
-                m.msg_sent(&message.topic, message.raw_protobuf_len());
+            match rpc {
+                RpcOut::Publish { ref message, .. } | RpcOut::Forward { ref message, .. } => {
+                    // register bytes sent on the internal metrics.
+                    m.msg_sent(&message.topic, message.raw_protobuf_len());
+                }
+                RpcOut::Control(ControlAction::Graft { ref topic_hash }) => {
+                    m.register_graft(topic_hash, Direction::Sent);
+                }
+                RpcOut::Control(ControlAction::Prune { ref topic_hash, .. }) => {
+                    m.register_prune(topic_hash, Direction::Sent);
+                }
+                _ => {}
             }
         }
 
@@ -2755,6 +3562,8 @@ where
             ..
         }: ConnectionEstablished,
     ) {
+        self.explicit_peer_dials.remove(&peer_id);
+
         // Diverging from the go implementation we only want to consider a peer as outbound peer
         // if its first connection is outbound.
 
@@ -2811,7 +3620,14 @@ where
         tracing::debug!(peer=%peer_id, "New peer connected");
         // We need to send our subscriptions to the newly-connected node.
         for topic_hash in self.mesh.clone().into_keys() {
-            self.send_message(peer_id, RpcOut::Subscribe(topic_hash));
+            let compression = self.config.compression_for_topic(&topic_hash);
+            self.send_message(
+                peer_id,
+                RpcOut::Subscribe {
+                    topic: topic_hash,
+                    compression,
+                },
+            );
         }
     }
 
@@ -2838,6 +3654,18 @@ where
             }
         }
 
+        // Published messages not sent to this peer yet may have been queued on the closed
+        // connection, so don't count on them being sent anymore.
+        let pending_publishes = self
+            .pending_publishes
+            .iter()
+            .filter(|(_, pending)| pending.outstanding.contains(&peer_id))
+            .map(|(msg_id, _)| msg_id.clone())
+            .collect::<Vec<_>>();
+        for msg_id in pending_publishes {
+            self.update_pending_publish(&msg_id, &peer_id, Err(DeliveryError::InsufficientPeers));
+        }
+
         if remaining_established != 0 {
             // Remove the connection from the list
             if let Some(connections) = self.connected_peers.get_mut(&peer_id) {
@@ -2922,6 +3750,7 @@ where
             // Forget px and outbound status for this peer
             self.px_peers.remove(&peer_id);
             self.outbound_peers.remove(&peer_id);
+            self.signed_peer_records.remove(&peer_id);
 
             // Remove peer from peer_topics and connected_peers
             // NOTE: It is possible the peer has already been removed from all mappings if it does not
@@ -3052,6 +3881,25 @@ where
                     }
                 }
             }
+            HandlerEvent::MessageSent(message_id) => {
+                self.publish_statuses.sent(&message_id, propagation_source);
+                self.update_pending_publish(&message_id, &propagation_source, Ok(()));
+            }
+            HandlerEvent::MessageDropped(message_id) => {
+                tracing::debug!(
+                    peer=%propagation_source,
+                    message=%message_id,
+                    "Message dropped, send queue is full"
+                );
+                if let Some(m) = self.metrics.as_mut() {
+                    m.message_dropped();
+                }
+                self.update_pending_publish(
+                    &message_id,
+                    &propagation_source,
+                    Err(DeliveryError::QueueFull),
+                );
+            }
             HandlerEvent::Message {
                 rpc,
                 invalid_messages,
@@ -3073,7 +3921,7 @@ where
                 }
 
                 // Handle any invalid messages from this peer
-                if self.peer_score.is_some() {
+                if self.peer_score.is_some() || self.tracer.is_some() {
                     for (raw_message, validation_error) in invalid_messages {
                         self.handle_invalid_message(
                             &propagation_source,
@@ -3151,6 +3999,13 @@ where
             return Poll::Ready(event);
         }
 
+        while let Poll::Ready(outcome) = self.validation.poll(cx) {
+            self.handle_validation_outcome(outcome);
+            if let Some(event) = self.events.pop_front() {
+                return Poll::Ready(event);
+            }
+        }
+
         // update scores
         if let Some((peer_score, _, interval, _)) = &mut self.peer_score {
             while let Poll::Ready(Some(_)) = interval.poll_next_unpin(cx) {
@@ -3162,6 +4017,16 @@ where
             self.heartbeat();
         }
 
+        if let Some(timer) = self.subscription_announcement.as_mut() {
+            if timer.poll_unpin(cx).is_ready() {
+                self.subscription_announcement = None;
+                self.announce_subscriptions();
+                if let Some(event) = self.events.pop_front() {
+                    return Poll::Ready(event);
+                }
+            }
+        }
+
         Poll::Pending
     }
 
@@ -3174,9 +4039,91 @@ where
                 self.on_connection_closed(connection_closed)
             }
             FromSwarm::AddressChange(address_change) => self.on_address_change(address_change),
+            FromSwarm::DialFailure(DialFailure {
+                peer_id: Some(peer_id),
+                ..
+            }) => {
+                // Explicit peers are retried every `check_explicit_peers_ticks` heartbeats.
+                self.explicit_peer_dials.remove(&peer_id);
+                if !self.connected_peers.contains_key(&peer_id) {
+                    self.signed_peer_records.remove(&peer_id);
+                }
+            }
             _ => {}
         }
     }
+
+    fn on_shutdown(&mut self) {
+        // Leave all meshes so that our mesh peers can replace us right away instead of only
+        // noticing once the connections are closed.
+        let topics = self.mesh.keys().cloned().collect::<Vec<_>>();
+        for topic_hash in topics {
+            self.leave(&topic_hash);
+        }
+        // The heartbeat won't run anymore, send the PRUNEs right away.
+        self.flush_control_pool();
+    }
+}
+
+/// A message published via [`Behaviour::publish_with_feedback`] whose delivery is tracked.
+struct PendingPublish {
+    /// The number of peers the message still has to be sent to.
+    remaining: usize,
+    /// The peers the message has been queued for but not sent to yet.
+    outstanding: HashSet<PeerId>,
+    /// The number of peers that dropped the message, shared with the [`PublishHandle`].
+    dropped: Arc<AtomicUsize>,
+    /// When to give up on the message being sent to enough peers.
+    deadline: Instant,
+    sender: oneshot::Sender<Result<(), DeliveryError>>,
+}
+
+/// A future resolving to the [`MessageId`] of a message published via
+/// [`Behaviour::publish_with_feedback`] once it has been sent to enough peers.
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct PublishHandle {
+    message_id: MessageId,
+    dropped: Arc<AtomicUsize>,
+    receiver: oneshot::Receiver<Result<(), DeliveryError>>,
+}
+
+impl PublishHandle {
+    /// The [`MessageId`] of the published message.
+    pub fn message_id(&self) -> &MessageId {
+        &self.message_id
+    }
+
+    /// The number of peers that dropped the message because their send queue was full (see
+    /// [`Config::max_queued_messages()`]). Drops are only counted until the handle resolves.
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(AtomicOrdering::Relaxed)
+    }
+}
+
+impl Future for PublishHandle {
+    type Output = Result<MessageId, DeliveryError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match ready!(self.receiver.poll_unpin(cx)) {
+            Ok(Ok(())) => Poll::Ready(Ok(self.message_id.clone())),
+            Ok(Err(e)) => Poll::Ready(Err(e)),
+            // The behaviour has been dropped.
+            Err(oneshot::Canceled) => Poll::Ready(Err(DeliveryError::InsufficientPeers)),
+        }
+    }
+}
+
+/// The state of our connection to an explicit peer, see [`Behaviour::explicit_peers`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExplicitPeerStatus {
+    /// We are connected to the peer.
+    Connected,
+    /// We are currently dialing the peer.
+    Dialing,
+    /// We are not connected to the peer. A connection will be attempted on the next explicit
+    /// peer check, see [`Config::check_explicit_peers_ticks`].
+    Disconnected,
 }
 
 /// This is called when peers are added to any mesh. It checks if the peer existed
@@ -3405,10 +4352,19 @@ mod local_test {
     impl Arbitrary for RpcOut {
         fn arbitrary(g: &mut Gen) -> Self {
             match u8::arbitrary(g) % 5 {
-                0 => RpcOut::Subscribe(IdentTopic::new("TestTopic").hash()),
+                0 => RpcOut::Subscribe {
+                    topic: IdentTopic::new("TestTopic").hash(),
+                    compression: None,
+                },
                 1 => RpcOut::Unsubscribe(IdentTopic::new("TestTopic").hash()),
-                2 => RpcOut::Publish(test_message()),
-                3 => RpcOut::Forward(test_message()),
+                2 => RpcOut::Publish {
+                    message: test_message(),
+                    message_id: MessageId(vec![1u8]),
+                },
+                3 => RpcOut::Forward {
+                    message: test_message(),
+                    message_id: MessageId(vec![1u8]),
+                },
                 4 => RpcOut::Control(test_control()),
                 _ => panic!("outside range"),
             }
@@ -27,6 +27,7 @@ use async_std::net::Ipv4Addr;
 use byteorder::{BigEndian, ByteOrder};
 use libp2p_core::ConnectedPoint;
 use rand::Rng;
+use std::sync::{Arc, Mutex};
 use std::thread::sleep;
 
 #[derive(Default, Debug)]
@@ -239,6 +240,7 @@ where
                 .map(|t| Subscription {
                     action: SubscriptionAction::Subscribe,
                     topic_hash: t,
+                    compression: None,
                 })
                 .collect::<Vec<_>>(),
             &peer,
@@ -332,15 +334,7 @@ fn proto_to_message(rpc: &proto::RPC) -> Rpc {
             let peers = prune
                 .peers
                 .into_iter()
-                .filter_map(|info| {
-                    info.peer_id
-                        .and_then(|id| PeerId::from_bytes(&id).ok())
-                        .map(|peer_id|
-                            //TODO signedPeerRecord, see https://github.com/libp2p/specs/pull/217
-                            PeerInfo {
-                                peer_id: Some(peer_id),
-                            })
-                })
+                .filter_map(crate::protocol::decode_peer_info)
                 .collect::<Vec<PeerInfo>>();
 
             let topic_hash = TopicHash::from_raw(prune.topic_id.unwrap_or_default());
@@ -369,6 +363,7 @@ fn proto_to_message(rpc: &proto::RPC) -> Rpc {
                     SubscriptionAction::Unsubscribe
                 },
                 topic_hash: TopicHash::from_raw(sub.topic_id.unwrap_or_default()),
+                compression: sub.compression,
             })
             .collect(),
         control_msgs,
@@ -403,7 +398,7 @@ fn test_subscribe() {
             matches!(
                 e,
                 ToSwarm::NotifyHandler {
-                    event: HandlerIn::Message(RpcOut::Subscribe(_)),
+                    event: HandlerIn::Message(RpcOut::Subscribe { .. }),
                     ..
                 }
             )
@@ -414,6 +409,44 @@ fn test_subscribe() {
     assert_eq!(subscriptions, 20);
 }
 
+#[test]
+/// Test that per-topic mesh parameters are applied when joining a topic.
+fn test_subscribe_with_config() {
+    let topic = Topic::new("test_subscribe_with_config");
+    let (mut gs, _, topic_hashes) = inject_nodes1()
+        .peer_no(20)
+        .topics(vec![String::from("test_subscribe_with_config")])
+        .to_subscribe(true)
+        .create_network();
+
+    assert!(gs.unsubscribe(&topic).unwrap());
+
+    let invalid = TopicConfig {
+        mesh_n: Some(3),
+        ..Default::default()
+    };
+    assert!(
+        matches!(
+            gs.subscribe_with_config(&topic, invalid),
+            Err(SubscribeWithConfigError::InvalidTopicConfig(_))
+        ),
+        "mesh_n below mesh_n_low should be rejected"
+    );
+
+    let topic_config = TopicConfig {
+        mesh_n: Some(4),
+        mesh_n_low: Some(2),
+        ..Default::default()
+    };
+    assert!(gs.subscribe_with_config(&topic, topic_config).unwrap());
+
+    assert_eq!(
+        gs.mesh.get(&topic_hashes[0]).unwrap().len(),
+        4,
+        "Mesh should be filled up to the per-topic mesh_n"
+    );
+}
+
 #[test]
 /// Test unsubscribe.
 fn test_unsubscribe() {
@@ -462,7 +495,7 @@ fn test_unsubscribe() {
         .iter()
         .fold(0, |collected_subscriptions, e| match e {
             ToSwarm::NotifyHandler {
-                event: HandlerIn::Message(RpcOut::Subscribe(_)),
+                event: HandlerIn::Message(RpcOut::Subscribe { .. }),
                 ..
             } => collected_subscriptions + 1,
             _ => collected_subscriptions,
@@ -597,6 +630,134 @@ fn test_join() {
     );
 }
 
+#[test]
+fn test_publish_with_feedback() {
+    // With flood publishing, messages are sent to all peers subscribed to the topic.
+    let (mut gs, peers, topic_hashes) = inject_nodes1()
+        .peer_no(3)
+        .topics(vec!["test_publish".into()])
+        .to_subscribe(true)
+        .create_network();
+
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+
+    // The handle resolves once the message has been sent to two peers.
+    let mut handle = gs
+        .publish_with_feedback(topic_hashes[0].clone(), vec![1; 42], 2)
+        .unwrap();
+    let msg_id = handle.message_id().clone();
+    for peer in &peers[..2] {
+        assert!(handle.poll_unpin(&mut cx).is_pending());
+        gs.on_connection_handler_event(
+            *peer,
+            ConnectionId::new_unchecked(0),
+            HandlerEvent::MessageSent(msg_id.clone()),
+        );
+    }
+    assert_eq!(handle.poll_unpin(&mut cx), Poll::Ready(Ok(msg_id)));
+    assert_eq!(handle.dropped(), 0);
+
+    // The handle fails once too many peers dropped the message.
+    let mut handle = gs
+        .publish_with_feedback(topic_hashes[0].clone(), vec![2; 42], 2)
+        .unwrap();
+    let msg_id = handle.message_id().clone();
+    for peer in &peers[..2] {
+        assert!(handle.poll_unpin(&mut cx).is_pending());
+        gs.on_connection_handler_event(
+            *peer,
+            ConnectionId::new_unchecked(0),
+            HandlerEvent::MessageDropped(msg_id.clone()),
+        );
+    }
+    assert_eq!(
+        handle.poll_unpin(&mut cx),
+        Poll::Ready(Err(DeliveryError::QueueFull))
+    );
+    assert_eq!(handle.dropped(), 2);
+
+    // The handle fails once too many peers disconnected before the message was sent.
+    let mut handle = gs
+        .publish_with_feedback(topic_hashes[0].clone(), vec![4; 42], 2)
+        .unwrap();
+    for peer in &peers[..2] {
+        assert!(handle.poll_unpin(&mut cx).is_pending());
+        disconnect_peer(&mut gs, peer);
+    }
+    assert_eq!(
+        handle.poll_unpin(&mut cx),
+        Poll::Ready(Err(DeliveryError::InsufficientPeers))
+    );
+
+    // Publishing to fewer peers than requested fails immediately.
+    let mut handle = gs
+        .publish_with_feedback(topic_hashes[0].clone(), vec![3; 42], peers.len() + 1)
+        .unwrap();
+    assert_eq!(
+        handle.poll_unpin(&mut cx),
+        Poll::Ready(Err(DeliveryError::InsufficientPeers))
+    );
+}
+
+#[test]
+fn test_publish_with_feedback_times_out() {
+    let config = ConfigBuilder::default()
+        .publish_feedback_timeout(Duration::ZERO)
+        .build()
+        .unwrap();
+    let (mut gs, _, topic_hashes) = inject_nodes1()
+        .peer_no(3)
+        .topics(vec!["test_publish".into()])
+        .to_subscribe(true)
+        .gs_config(config)
+        .create_network();
+
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+
+    let mut handle = gs
+        .publish_with_feedback(topic_hashes[0].clone(), vec![1; 42], 1)
+        .unwrap();
+    assert!(handle.poll_unpin(&mut cx).is_pending());
+
+    // The message was never sent, so the next heartbeat gives up on it.
+    gs.heartbeat();
+    assert_eq!(
+        handle.poll_unpin(&mut cx),
+        Poll::Ready(Err(DeliveryError::Timeout))
+    );
+    assert!(gs.pending_publishes.is_empty());
+}
+
+#[test]
+fn test_publish_status() {
+    let (mut gs, peers, topic_hashes) = inject_nodes1()
+        .peer_no(3)
+        .topics(vec!["test_publish".into()])
+        .to_subscribe(true)
+        .create_network();
+
+    let msg_id = gs.publish(topic_hashes[0].clone(), vec![1; 42]).unwrap();
+
+    let status = gs.publish_status(&msg_id).unwrap();
+    assert_eq!(status.topic(), &topic_hashes[0]);
+    assert_eq!(status.recipients().len(), peers.len());
+    assert!(status.sent_to().is_empty());
+
+    gs.on_connection_handler_event(
+        peers[0],
+        ConnectionId::new_unchecked(0),
+        HandlerEvent::MessageSent(msg_id.clone()),
+    );
+    gs.handle_iwant(&peers[1], vec![msg_id.clone()]);
+    let raw_message = gs.mcache.get(&msg_id).unwrap().clone();
+    gs.handle_received_message(raw_message, &peers[2]);
+
+    let status = gs.publish_status(&msg_id).unwrap();
+    assert_eq!(status.sent_to(), &HashSet::from([peers[0]]));
+    assert_eq!(status.iwant_served(), &HashSet::from([peers[1]]));
+    assert_eq!(status.echoed_by(), &HashSet::from([peers[2]]));
+}
+
 /// Test local node publish to subscribed topic
 #[test]
 fn test_publish_without_flood_publishing() {
@@ -640,7 +801,7 @@ fn test_publish_without_flood_publishing() {
         .into_iter()
         .fold(vec![], |mut collected_publish, e| match e {
             ToSwarm::NotifyHandler {
-                event: HandlerIn::Message(RpcOut::Publish(message)),
+                event: HandlerIn::Message(RpcOut::Publish { message, .. }),
                 ..
             } => {
                 collected_publish.push(message);
@@ -727,7 +888,7 @@ fn test_fanout() {
         .into_iter()
         .fold(vec![], |mut collected_publish, e| match e {
             ToSwarm::NotifyHandler {
-                event: HandlerIn::Message(RpcOut::Publish(message)),
+                event: HandlerIn::Message(RpcOut::Publish { message, .. }),
                 ..
             } => {
                 collected_publish.push(message);
@@ -777,7 +938,7 @@ fn test_inject_connected() {
         .into_iter()
         .filter_map(|e| match e {
             ToSwarm::NotifyHandler {
-                event: HandlerIn::Message(RpcOut::Subscribe(topic)),
+                event: HandlerIn::Message(RpcOut::Subscribe { topic, .. }),
                 peer_id,
                 ..
             } => Some((peer_id, topic)),
@@ -813,6 +974,73 @@ fn test_inject_connected() {
     }
 }
 
+#[test]
+/// Test matching hierarchical topic names against patterns.
+fn test_topic_pattern_matches() {
+    let matches = |pattern: &str, topic: &str| {
+        TopicPattern::new(pattern).matches(&TopicHash::from_raw(topic))
+    };
+
+    assert!(matches("app/eu/blocks", "app/eu/blocks"));
+    assert!(matches("app/*/blocks", "app/eu/blocks"));
+    assert!(!matches("app/*/blocks", "app/eu/txs"));
+    assert!(!matches("app/*", "app/eu/blocks"));
+    assert!(!matches("app/*", "app"));
+    assert!(matches("app/**", "app/eu/blocks"));
+    assert!(!matches("app/**", "app"));
+    assert!(!matches("app/eu", "app/eu/blocks"));
+}
+
+#[test]
+/// Test that wildcard subscriptions expand to topics announced by peers, up to the limit.
+fn test_wildcard_subscription_expansion() {
+    let config = ConfigBuilder::default()
+        .max_wildcard_topics(2)
+        .build()
+        .unwrap();
+    let (mut gs, _, topic_hashes) = inject_nodes1()
+        .peer_no(1)
+        .topics(vec![String::from("app/eu/blocks")])
+        .to_subscribe(true)
+        .gs_config(config)
+        .create_network();
+    let pattern = TopicPattern::new("app/*/blocks");
+    assert!(gs.unsubscribe(&Topic::new("app/eu/blocks")).unwrap());
+    gs.events.clear();
+
+    // Topics connected peers are subscribed to are subscribed to right away.
+    assert!(gs.subscribe_wildcard(&pattern));
+    assert!(!gs.subscribe_wildcard(&pattern));
+    assert!(gs.mesh.contains_key(&topic_hashes[0]));
+
+    // Topics announced later are subscribed to up to the limit.
+    let us_blocks = TopicHash::from_raw("app/us/blocks");
+    let asia_blocks = TopicHash::from_raw("app/asia/blocks");
+    let us_txs = TopicHash::from_raw("app/us/txs");
+    add_peer(&mut gs, &[us_blocks.clone(), us_txs.clone()], false, false);
+    add_peer(&mut gs, &[asia_blocks.clone()], false, false);
+    assert!(gs.mesh.contains_key(&us_blocks));
+    assert!(!gs.mesh.contains_key(&us_txs));
+    assert!(
+        !gs.mesh.contains_key(&asia_blocks),
+        "Subscribing beyond the expansion limit"
+    );
+
+    let expanded = gs.wildcard_topics().collect::<Vec<_>>();
+    assert_eq!(expanded.len(), 1);
+    assert_eq!(expanded[0].0, &pattern);
+    let mut subscribed = expanded[0].1.clone();
+    subscribed.sort();
+    let mut expected = vec![&topic_hashes[0], &us_blocks];
+    expected.sort();
+    assert_eq!(subscribed, expected);
+
+    // Unsubscribing from the pattern unsubscribes from the expanded topics.
+    assert!(gs.unsubscribe_wildcard(&pattern));
+    assert!(gs.mesh.is_empty());
+    assert!(!gs.unsubscribe_wildcard(&pattern));
+}
+
 #[test]
 /// Test subscription handling
 fn test_handle_received_subscriptions() {
@@ -838,12 +1066,14 @@ fn test_handle_received_subscriptions() {
         .map(|topic_hash| Subscription {
             action: SubscriptionAction::Subscribe,
             topic_hash: topic_hash.clone(),
+            compression: None,
         })
         .collect::<Vec<Subscription>>();
 
     subscriptions.push(Subscription {
         action: SubscriptionAction::Unsubscribe,
         topic_hash: topic_hashes[topic_hashes.len() - 1].clone(),
+        compression: None,
     });
 
     let unknown_peer = PeerId::random();
@@ -886,6 +1116,7 @@ fn test_handle_received_subscriptions() {
         &[Subscription {
             action: SubscriptionAction::Unsubscribe,
             topic_hash: topic_hashes[0].clone(),
+            compression: None,
         }],
         &peers[0],
     );
@@ -1018,7 +1249,7 @@ fn test_handle_iwant_msg_cached() {
         Vec::<RawMessage>::new(),
         |mut collected_messages, e| match e {
             ToSwarm::NotifyHandler { event, .. } => {
-                if let HandlerIn::Message(RpcOut::Forward(message)) = event {
+                if let HandlerIn::Message(RpcOut::Forward { message, .. }) = event {
                     collected_messages.push(message);
                 }
                 collected_messages
@@ -1074,7 +1305,7 @@ fn test_handle_iwant_msg_cached_shifted() {
         // is the message is being sent?
         let message_exists = gs.events.iter().any(|e| match e {
             ToSwarm::NotifyHandler {
-                event: HandlerIn::Message(RpcOut::Forward(message)),
+                event: HandlerIn::Message(RpcOut::Forward { message, .. }),
                 ..
             } => {
                 gs.config.message_id(
@@ -1358,6 +1589,73 @@ fn test_explicit_peer_gets_connected() {
     );
 }
 
+#[test]
+fn test_explicit_peers_status() {
+    let (mut gs, others, _) = inject_nodes1()
+        .peer_no(1)
+        .topics(Vec::new())
+        .to_subscribe(true)
+        .create_network();
+
+    let connected = others[0];
+    let unknown = PeerId::random();
+
+    gs.add_explicit_peer(&connected);
+    gs.add_explicit_peer(&unknown);
+
+    let statuses = gs.explicit_peers().collect::<HashMap<_, _>>();
+    assert_eq!(statuses[&connected], ExplicitPeerStatus::Connected);
+    assert_eq!(statuses[&unknown], ExplicitPeerStatus::Dialing);
+
+    // Adding the peer again while the dial is in progress should not dial twice.
+    gs.add_explicit_peer(&unknown);
+    let num_dials = gs
+        .events
+        .iter()
+        .filter(|e| match e {
+            ToSwarm::Dial { opts } => opts.get_peer_id() == Some(unknown),
+            _ => false,
+        })
+        .count();
+    assert_eq!(num_dials, 1);
+
+    gs.remove_explicit_peer(&unknown);
+    assert_eq!(gs.explicit_peers().count(), 1);
+}
+
+#[test]
+fn test_add_explicit_peer_prunes_mesh_peer() {
+    let (mut gs, peers, topic_hashes) = inject_nodes1()
+        .peer_no(2)
+        .topics(vec![String::from("topic1")])
+        .to_subscribe(true)
+        .create_network();
+
+    let peer = peers[0];
+    assert!(gs.mesh[&topic_hashes[0]].contains(&peer));
+    flush_events(&mut gs);
+
+    gs.add_explicit_peer(&peer);
+
+    assert!(!gs.mesh[&topic_hashes[0]].contains(&peer));
+    assert!(gs.backoffs.is_backoff_with_slack(&topic_hashes[0], &peer));
+    assert_eq!(
+        count_control_msgs(&gs, |peer_id, m| peer_id == &peer
+            && matches!(m, ControlAction::Prune { topic_hash, .. } if topic_hash == &topic_hashes[0])),
+        1,
+        "No PRUNE sent to the new explicit peer"
+    );
+    // The peer is no longer in any mesh, so its handler is told it may stop sending.
+    assert!(gs.events.iter().any(|e| matches!(
+        e,
+        ToSwarm::NotifyHandler {
+            peer_id,
+            event: HandlerIn::LeftMesh,
+            ..
+        } if peer_id == &peer
+    )));
+}
+
 #[test]
 fn test_explicit_peer_reconnects() {
     let config = ConfigBuilder::default()
@@ -1529,7 +1827,7 @@ fn do_forward_messages_to_explicit_peers() {
             .filter(|e| match e {
                 ToSwarm::NotifyHandler {
                     peer_id,
-                    event: HandlerIn::Message(RpcOut::Forward(m)),
+                    event: HandlerIn::Message(RpcOut::Forward { message: m, .. }),
                     ..
                 } => {
                     peer_id == &peers[0] && m.data == message.data
@@ -1560,6 +1858,7 @@ fn explicit_peers_not_added_to_mesh_on_subscribe() {
             &[Subscription {
                 action: SubscriptionAction::Subscribe,
                 topic_hash: topic_hash.clone(),
+                compression: None,
             }],
             peer,
         );
@@ -1588,6 +1887,78 @@ fn explicit_peers_not_added_to_mesh_on_subscribe() {
     );
 }
 
+#[test]
+fn subscriptions_with_different_compression_are_ignored() {
+    let (mut gs, peers, _) = inject_nodes1()
+        .peer_no(2)
+        .topics(Vec::new())
+        .to_subscribe(true)
+        .create_network();
+
+    // We don't compress message data of the topic.
+    let topic_hash = Topic::new(String::from("t")).hash();
+    for (peer, compression) in peers.iter().zip([None, Some(u32::MAX)]) {
+        gs.handle_received_subscriptions(
+            &[Subscription {
+                action: SubscriptionAction::Subscribe,
+                topic_hash: topic_hash.clone(),
+                compression,
+            }],
+            peer,
+        );
+    }
+
+    assert_eq!(gs.topic_peers[&topic_hash], BTreeSet::from([peers[0]]));
+    assert!(!gs.peer_topics[&peers[1]].contains(&topic_hash));
+}
+
+#[cfg(feature = "snappy")]
+#[test]
+fn compression_is_advertised_in_subscriptions() {
+    let topic_hash = Topic::new(String::from("t")).hash();
+    let config = ConfigBuilder::default()
+        .topic_config(
+            topic_hash.clone(),
+            TopicConfig {
+                compression: Some(crate::CompressionConfig::new(crate::Compression::Snappy)),
+                ..Default::default()
+            },
+        )
+        .build()
+        .unwrap();
+    let compression = config.compression_for_topic(&topic_hash);
+    assert!(compression.is_some());
+
+    let (mut gs, peers, _) = inject_nodes1()
+        .peer_no(2)
+        .topics(Vec::new())
+        .to_subscribe(true)
+        .gs_config(config)
+        .create_network();
+
+    gs.subscribe(&Topic::new(String::from("t"))).unwrap();
+    assert!(gs.events.iter().any(|e| matches!(
+        e,
+        ToSwarm::NotifyHandler {
+            event: HandlerIn::Message(RpcOut::Subscribe { topic, compression: c }),
+            ..
+        } if *topic == topic_hash && *c == compression
+    )));
+
+    // Peers not compressing message data of the topic are ignored.
+    for (peer, compression) in peers.iter().zip([compression, None]) {
+        gs.handle_received_subscriptions(
+            &[Subscription {
+                action: SubscriptionAction::Subscribe,
+                topic_hash: topic_hash.clone(),
+                compression,
+            }],
+            peer,
+        );
+    }
+    assert_eq!(gs.topic_peers[&topic_hash], BTreeSet::from([peers[0]]));
+}
+
 #[test]
 fn explicit_peers_not_added_to_mesh_from_fanout_on_subscribe() {
     let (mut gs, peers, _) = inject_nodes1()
@@ -1606,6 +1977,7 @@ fn explicit_peers_not_added_to_mesh_from_fanout_on_subscribe() {
             &[Subscription {
                 action: SubscriptionAction::Subscribe,
                 topic_hash: topic_hash.clone(),
+                compression: None,
             }],
             peer,
         );
@@ -1759,6 +2131,7 @@ fn test_connect_to_px_peers_on_handle_prune() {
     for _ in 0..config.prune_peers() + 5 {
         px.push(PeerInfo {
             peer_id: Some(PeerId::random()),
+            signed_peer_record: None,
         });
     }
 
@@ -1797,6 +2170,63 @@ fn test_connect_to_px_peers_on_handle_prune() {
     ));
 }
 
+#[test]
+fn test_px_with_signed_peer_record() {
+    let config = ConfigBuilder::default().prune_peers(16).build().unwrap();
+
+    let (mut gs, peers, topics) = inject_nodes1()
+        .peer_no(1)
+        .topics(vec!["test".into()])
+        .to_subscribe(true)
+        .gs_config(config.clone())
+        .create_network();
+
+    let key = Keypair::generate_ed25519();
+    let address: Multiaddr = "/ip4/127.0.0.1/tcp/1234".parse().unwrap();
+    let record = PeerRecord::new(&key, vec![address.clone()]).unwrap();
+    let px_peer = key.public().to_peer_id();
+
+    // The record is encoded and verified like one received over the wire.
+    let rpc: proto::RPC = RpcOut::Control(ControlAction::Prune {
+        topic_hash: topics[0].clone(),
+        peers: vec![PeerInfo {
+            peer_id: None,
+            signed_peer_record: Some(record),
+        }],
+        backoff: None,
+    })
+    .into();
+    let px = rpc
+        .control
+        .unwrap()
+        .prune
+        .remove(0)
+        .peers
+        .into_iter()
+        .filter_map(crate::protocol::decode_peer_info)
+        .collect::<Vec<_>>();
+    assert_eq!(px.len(), 1);
+
+    gs.handle_prune(
+        &peers[0],
+        vec![(
+            topics[0].clone(),
+            px,
+            Some(config.prune_backoff().as_secs()),
+        )],
+    );
+
+    assert!(gs.events.iter().any(|e| matches!(
+        e,
+        ToSwarm::NewExternalAddrOfPeer { peer_id, address: a } if *peer_id == px_peer && *a == address
+    )));
+    assert!(gs.events.iter().any(|e| matches!(
+        e,
+        ToSwarm::Dial { opts } if opts.get_peer_id() == Some(px_peer)
+    )));
+    assert!(gs.signed_peer_records.contains_key(&px_peer));
+}
+
 #[test]
 fn test_send_px_and_backoff_in_prune() {
     let config: Config = Config::default();
@@ -2069,7 +2499,7 @@ fn test_flood_publish() {
         .into_iter()
         .fold(vec![], |mut collected_publish, e| match e {
             ToSwarm::NotifyHandler { event, .. } => {
-                if let HandlerIn::Message(RpcOut::Publish(message)) = event {
+                if let HandlerIn::Message(RpcOut::Publish { message, .. }) = event {
                     collected_publish.push(message);
                 }
                 collected_publish
@@ -2416,6 +2846,7 @@ fn test_ignore_px_from_negative_scored_peer() {
     //handle prune from single peer with px peers
     let px = vec![PeerInfo {
         peer_id: Some(PeerId::random()),
+        signed_peer_record: None,
     }];
 
     gs.handle_prune(
@@ -2627,7 +3058,7 @@ fn test_iwant_msg_from_peer_below_gossip_threshold_gets_ignored() {
         .into_iter()
         .fold(vec![], |mut collected_messages, e| match e {
             ToSwarm::NotifyHandler { event, peer_id, .. } => {
-                if let HandlerIn::Message(RpcOut::Forward(message)) = event {
+                if let HandlerIn::Message(RpcOut::Forward { message, .. }) = event {
                     collected_messages.push((peer_id, message));
                 }
                 collected_messages
@@ -2772,7 +3203,7 @@ fn test_do_not_publish_to_peer_below_publish_threshold() {
         .into_iter()
         .fold(vec![], |mut collected_publish, e| match e {
             ToSwarm::NotifyHandler { event, peer_id, .. } => {
-                if let HandlerIn::Message(RpcOut::Publish(message)) = event {
+                if let HandlerIn::Message(RpcOut::Publish { message, .. }) = event {
                     collected_publish.push((peer_id, message));
                 }
                 collected_publish
@@ -2826,7 +3257,7 @@ fn test_do_not_flood_publish_to_peer_below_publish_threshold() {
         .into_iter()
         .fold(vec![], |mut collected_publish, e| match e {
             ToSwarm::NotifyHandler { event, peer_id, .. } => {
-                if let HandlerIn::Message(RpcOut::Publish(message)) = event {
+                if let HandlerIn::Message(RpcOut::Publish { message, .. }) = event {
                     collected_publish.push((peer_id, message));
                 }
                 collected_publish
@@ -2918,6 +3349,7 @@ fn test_ignore_rpc_from_peers_below_graylist_threshold() {
     let subscription = Subscription {
         action: SubscriptionAction::Subscribe,
         topic_hash: topics[0].clone(),
+        compression: None,
     };
 
     let control_action = ControlAction::IHave {
@@ -2998,6 +3430,7 @@ fn test_ignore_px_from_peers_below_accept_px_threshold() {
     // Handle prune from peer peers[0] with px peers
     let px = vec![PeerInfo {
         peer_id: Some(PeerId::random()),
+        signed_peer_record: None,
     }];
     gs.handle_prune(
         &peers[0],
@@ -3020,6 +3453,7 @@ fn test_ignore_px_from_peers_below_accept_px_threshold() {
     //handle prune from peer peers[1] with px peers
     let px = vec![PeerInfo {
         peer_id: Some(PeerId::random()),
+        signed_peer_record: None,
     }];
     gs.handle_prune(
         &peers[1],
@@ -3522,6 +3956,153 @@ fn test_scoring_p4_valid_message() {
     assert_eq!(gs.peer_score.as_ref().unwrap().0.score(&peers[0]), 0.0);
 }
 
+#[test]
+fn test_registered_validator() {
+    let config = ConfigBuilder::default()
+        .validate_messages()
+        .build()
+        .unwrap();
+    let (mut gs, peers, topics) = inject_nodes1()
+        .peer_no(1)
+        .topics(vec!["test".into()])
+        .to_subscribe(true)
+        .gs_config(config.clone())
+        .create_network();
+
+    // Accept messages starting with an even byte.
+    gs.register_validator(&Topic::new("test"), |_: &PeerId, message: &Message| {
+        let accept = message.data[0] % 2 == 0;
+        async move {
+            if accept {
+                MessageAcceptance::Accept
+            } else {
+                MessageAcceptance::Reject
+            }
+        }
+    });
+    gs.events.clear();
+
+    let mut seq = 0;
+    let mut valid = random_message(&mut seq, &topics);
+    valid.data[0] = 0;
+    valid.validated = false;
+    let mut invalid = random_message(&mut seq, &topics);
+    invalid.data[0] = 1;
+    invalid.validated = false;
+
+    let valid_id = config.message_id(&gs.data_transform.inbound_transform(valid.clone()).unwrap());
+    let invalid_id = config.message_id(
+        &gs.data_transform
+            .inbound_transform(invalid.clone())
+            .unwrap(),
+    );
+
+    gs.handle_received_message(valid, &peers[0]);
+    gs.handle_received_message(invalid, &peers[0]);
+
+    // Messages are not emitted before they have been validated.
+    assert!(gs.events.is_empty());
+
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    let mut received = Vec::new();
+    while let Poll::Ready(event) = gs.poll(&mut cx) {
+        if let ToSwarm::GenerateEvent(Event::Message { message_id, .. }) = event {
+            received.push(message_id);
+        }
+    }
+
+    assert_eq!(received, vec![valid_id.clone()]);
+    assert!(gs.mcache.get(&valid_id).unwrap().validated);
+    assert!(gs.mcache.get(&invalid_id).is_none());
+}
+
+#[test]
+fn test_registered_validator_timeout() {
+    let config = ConfigBuilder::default()
+        .validate_messages()
+        .validation_timeout(Duration::from_millis(10))
+        .build()
+        .unwrap();
+    let (mut gs, peers, topics) = inject_nodes1()
+        .peer_no(1)
+        .topics(vec!["test".into()])
+        .to_subscribe(true)
+        .gs_config(config.clone())
+        .create_network();
+
+    // A validator that never completes.
+    gs.register_validator(&Topic::new("test"), |_: &PeerId, _: &Message| {
+        futures::future::pending::<MessageAcceptance>()
+    });
+    gs.events.clear();
+
+    let mut seq = 0;
+    let mut message = random_message(&mut seq, &topics);
+    message.validated = false;
+    let message_id = config.message_id(
+        &gs.data_transform
+            .inbound_transform(message.clone())
+            .unwrap(),
+    );
+    gs.handle_received_message(message, &peers[0]);
+
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    while gs.poll(&mut cx).is_ready() {}
+    assert!(gs.mcache.get(&message_id).is_some());
+
+    sleep(Duration::from_millis(50));
+
+    // The message is ignored once the validation timed out.
+    while let Poll::Ready(event) = gs.poll(&mut cx) {
+        assert!(
+            !matches!(event, ToSwarm::GenerateEvent(Event::Message { .. })),
+            "Unexpected message event"
+        );
+    }
+    assert!(gs.mcache.get(&message_id).is_none());
+}
+
+#[test]
+fn test_registered_validator_queue_full() {
+    let config = ConfigBuilder::default()
+        .validate_messages()
+        .max_concurrent_validations(1)
+        .max_queued_validations(1)
+        .build()
+        .unwrap();
+    let (mut gs, peers, topics) = inject_nodes1()
+        .peer_no(1)
+        .topics(vec!["test".into()])
+        .to_subscribe(true)
+        .gs_config(config.clone())
+        .create_network();
+
+    gs.register_validator(&Topic::new("test"), |_: &PeerId, _: &Message| {
+        futures::future::pending::<MessageAcceptance>()
+    });
+    gs.events.clear();
+
+    let mut seq = 0;
+    let message_ids = (0..3)
+        .map(|_| {
+            let mut message = random_message(&mut seq, &topics);
+            message.validated = false;
+            let message_id = config.message_id(
+                &gs.data_transform
+                    .inbound_transform(message.clone())
+                    .unwrap(),
+            );
+            gs.handle_received_message(message, &peers[0]);
+            message_id
+        })
+        .collect::<Vec<_>>();
+
+    // Only the first message fits into the queue, the others are ignored.
+    assert!(gs.mcache.get(&message_ids[0]).is_some());
+    assert!(gs.mcache.get(&message_ids[1]).is_none());
+    assert!(gs.mcache.get(&message_ids[2]).is_none());
+}
+
 #[test]
 fn test_scoring_p4_invalid_signature() {
     let config = ConfigBuilder::default()
@@ -4349,7 +4930,7 @@ fn test_ignore_too_many_iwants_from_same_peer_for_same_message() {
             .filter(|e| matches!(
                 e,
                 ToSwarm::NotifyHandler {
-                    event: HandlerIn::Message(RpcOut::Forward(_)),
+                    event: HandlerIn::Message(RpcOut::Forward { .. }),
                     ..
                 }
             ))
@@ -4755,7 +5336,7 @@ fn test_publish_to_floodsub_peers_without_flood_publish() {
         .fold(vec![], |mut collected_publish, e| match e {
             ToSwarm::NotifyHandler { peer_id, event, .. } => {
                 if peer_id == &p1 || peer_id == &p2 {
-                    if let HandlerIn::Message(RpcOut::Publish(message)) = event {
+                    if let HandlerIn::Message(RpcOut::Publish { message, .. }) = event {
                         collected_publish.push(message);
                     }
                 }
@@ -4809,7 +5390,7 @@ fn test_do_not_use_floodsub_in_fanout() {
         .fold(vec![], |mut collected_publish, e| match e {
             ToSwarm::NotifyHandler { peer_id, event, .. } => {
                 if peer_id == &p1 || peer_id == &p2 {
-                    if let HandlerIn::Message(RpcOut::Publish(message)) = event {
+                    if let HandlerIn::Message(RpcOut::Publish { message, .. }) = event {
                         collected_publish.push(message);
                     }
                 }
@@ -5007,6 +5588,34 @@ fn test_subscribe_to_invalid_topic() {
     assert!(gs.subscribe(&t2).is_err());
 }
 
+#[test]
+fn test_subscribe_with_config_to_invalid_topic() {
+    let t1 = Topic::new("t1");
+    let t2 = Topic::new("t2");
+    let (mut gs, _, _) = inject_nodes::<IdentityTransform, _>()
+        .subscription_filter(WhitelistSubscriptionFilter(
+            vec![t1.hash()].into_iter().collect(),
+        ))
+        .to_subscribe(false)
+        .create_network();
+
+    let topic_config = TopicConfig {
+        mesh_n: Some(4),
+        mesh_n_low: Some(2),
+        ..Default::default()
+    };
+    assert!(matches!(
+        gs.subscribe_with_config(&t2, topic_config),
+        Err(SubscribeWithConfigError::Subscription(
+            SubscriptionError::NotAllowed
+        ))
+    ));
+    assert!(
+        gs.config.topic_config(&t2.hash()).is_none(),
+        "Overrides of a failed subscription should be rolled back"
+    );
+}
+
 #[test]
 fn test_subscribe_and_graft_with_negative_score() {
     //simulate a communication between two gossipsub instances
@@ -5108,3 +5717,221 @@ fn test_graft_without_subscribe() {
     // We unsubscribe from the topic.
     let _ = gs.unsubscribe(&Topic::new(topic));
 }
+
+#[test]
+fn test_topic_protocol_mix() {
+    let (mut gs, _, topics) = inject_nodes1()
+        .peer_no(2)
+        .topics(vec![String::from("topic1"), String::from("topic2")])
+        .to_subscribe(true)
+        .create_network();
+
+    add_peer_with_addr_and_kind(
+        &mut gs,
+        &topics[..1],
+        false,
+        false,
+        Multiaddr::empty(),
+        Some(PeerKind::Gossipsub),
+    );
+
+    assert_eq!(
+        gs.topic_protocol_mix(&topics[0]),
+        HashMap::from([(PeerKind::Gossipsubv1_1, 2), (PeerKind::Gossipsub, 1)])
+    );
+    assert_eq!(
+        gs.topic_protocol_mix(&topics[1]),
+        HashMap::from([(PeerKind::Gossipsubv1_1, 2)])
+    );
+    assert!(gs
+        .topic_protocol_mix(&TopicHash::from_raw("unknown"))
+        .is_empty());
+}
+
+#[test]
+fn test_no_px_in_prune_to_v1_0_peers() {
+    let config = ConfigBuilder::default()
+        .do_px()
+        .prune_peers(16)
+        .build()
+        .unwrap();
+    let (mut gs, peers, topics) = inject_nodes1()
+        .peer_no(3)
+        .topics(vec![String::from("topic")])
+        .to_subscribe(true)
+        .gs_config(config)
+        .create_network();
+
+    let v1_0_peer = add_peer_with_addr_and_kind(
+        &mut gs,
+        &topics,
+        false,
+        false,
+        Multiaddr::empty(),
+        Some(PeerKind::Gossipsub),
+    );
+
+    match gs.make_prune(&topics[0], &v1_0_peer, true, false) {
+        ControlAction::Prune { peers, backoff, .. } => {
+            assert!(peers.is_empty(), "v1.0 peers can't parse peer exchange");
+            assert!(backoff.is_none(), "v1.0 peers can't parse backoff");
+        }
+        _ => unreachable!(),
+    }
+    match gs.make_prune(&topics[0], &peers[0], true, false) {
+        ControlAction::Prune { peers, backoff, .. } => {
+            assert!(!peers.is_empty());
+            assert!(backoff.is_some());
+        }
+        _ => unreachable!(),
+    }
+}
+
+#[test]
+fn test_prune_mesh_peers_on_shutdown() {
+    let (mut gs, _, topics) = inject_nodes1()
+        .peer_no(20)
+        .topics(vec![String::from("topic1"), String::from("topic2")])
+        .to_subscribe(true)
+        .create_network();
+
+    let mesh_peers = topics
+        .iter()
+        .map(|topic_hash| gs.mesh[topic_hash].len())
+        .sum::<usize>();
+    assert!(mesh_peers > 0);
+    flush_events(&mut gs);
+
+    gs.on_shutdown();
+
+    assert!(gs.mesh.is_empty(), "All meshes should have been left");
+    assert!(
+        gs.control_pool.is_empty(),
+        "PRUNEs should not wait for a heartbeat"
+    );
+    let prunes = gs
+        .events
+        .iter()
+        .filter(|e| {
+            matches!(
+                e,
+                ToSwarm::NotifyHandler {
+                    event: HandlerIn::Message(RpcOut::Control(ControlAction::Prune { .. })),
+                    ..
+                }
+            )
+        })
+        .count();
+    assert_eq!(prunes, mesh_peers);
+}
+
+#[test]
+fn test_subscription_announcements_are_batched() {
+    let config = ConfigBuilder::default()
+        .subscription_batch_window(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let (mut gs, _, _) = inject_nodes1()
+        .peer_no(5)
+        .topics(vec![])
+        .gs_config(config)
+        .create_network();
+    gs.events.clear();
+
+    let topics = ["topic1", "topic2", "topic3"].map(Topic::new);
+    for topic in &topics {
+        assert!(gs.subscribe(topic).unwrap());
+    }
+    assert!(gs.unsubscribe(&topics[2]).unwrap());
+
+    // nothing is announced within the batch window
+    assert!(!gs.events.iter().any(|e| matches!(
+        e,
+        ToSwarm::NotifyHandler {
+            event: HandlerIn::Message(
+                RpcOut::Subscribe { .. } | RpcOut::Unsubscribe(_) | RpcOut::Subscriptions(_)
+            ),
+            ..
+        }
+    )));
+
+    gs.announce_subscriptions();
+    assert!(gs.unannounced_subscriptions.is_empty());
+
+    let announcements = gs
+        .events
+        .iter()
+        .filter_map(|e| match e {
+            ToSwarm::NotifyHandler {
+                event: HandlerIn::Message(RpcOut::Subscriptions(subscriptions)),
+                ..
+            } => Some(subscriptions),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    // one announcement per peer, without the topic that was left again
+    assert_eq!(announcements.len(), 5);
+    for subscriptions in announcements {
+        let mut announced = subscriptions
+            .iter()
+            .map(|s| {
+                assert_eq!(s.action, SubscriptionAction::Subscribe);
+                s.topic_hash.clone()
+            })
+            .collect::<Vec<_>>();
+        announced.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        assert_eq!(
+            announced,
+            vec![topics[0].hash().clone(), topics[1].hash().clone()]
+        );
+    }
+}
+
+#[test]
+fn test_tracer_receives_message_events() {
+    let (mut gs, peers, topic_hashes) = inject_nodes1()
+        .peer_no(3)
+        .topics(vec!["test".into()])
+        .to_subscribe(true)
+        .create_network();
+    gs.mesh
+        .get_mut(&topic_hashes[0])
+        .unwrap()
+        .extend(peers.iter().copied());
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    gs.set_tracer({
+        let events = events.clone();
+        move |event: TraceEvent<'_>| {
+            let peers = match event {
+                TraceEvent::ForwardMessage { peers, .. } => Some(peers),
+                _ => None,
+            };
+            events.lock().unwrap().push((event.kind(), peers));
+        }
+    });
+
+    let raw_message = random_message(&mut 0, &topic_hashes);
+    gs.handle_received_message(raw_message.clone(), &peers[0]);
+    gs.handle_received_message(raw_message, &peers[1]);
+    gs.publish(topic_hashes[0].clone(), vec![1; 10]).unwrap();
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        vec![
+            ("RECEIVE_MESSAGE", None),
+            ("DELIVER_MESSAGE", None),
+            ("FORWARD_MESSAGE", Some(2)),
+            ("DUPLICATE_MESSAGE", None),
+            ("PUBLISH_MESSAGE", None),
+        ]
+    );
+
+    // Messages from blacklisted peers are reported as rejected.
+    events.lock().unwrap().clear();
+    gs.blacklist_peer(&peers[2]);
+    gs.handle_received_message(random_message(&mut 1, &topic_hashes), &peers[2]);
+    assert_eq!(*events.lock().unwrap(), vec![("REJECT_MESSAGE", None)]);
+}
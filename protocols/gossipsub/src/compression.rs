@@ -0,0 +1,233 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Transparent compression of message data for topics configured via
+//! [`TopicConfig::compression`](crate::TopicConfig::compression).
+//!
+//! Compressed data is prefixed with a single byte identifying the algorithm, so that data below
+//! the threshold or not benefiting from compression can be sent as is. As the prefix is not
+//! understood by peers not configuring compression for a topic, the algorithm is advertised in
+//! the subscriptions to the topic and peers advertising a different one are ignored for it.
+//!
+//! The compression API is only available with the `snappy` or `zstd` features.
+
+use std::io;
+
+use crate::config::Config;
+use crate::RawMessage;
+
+/// Prefix of data that is not compressed.
+const UNCOMPRESSED: u8 = 0;
+/// Prefix of data compressed with [`Compression::Snappy`].
+#[cfg(feature = "snappy")]
+const SNAPPY: u8 = 1;
+/// Prefix of data compressed with [`Compression::Zstd`].
+#[cfg(feature = "zstd")]
+const ZSTD: u8 = 2;
+
+/// A compression algorithm for message data.
+///
+/// Each algorithm is available through the feature of the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Snappy compression.
+    #[cfg(feature = "snappy")]
+    Snappy,
+    /// Zstandard compression at the given level.
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+}
+
+impl Compression {
+    /// The prefix identifying the algorithm, also advertised in subscriptions.
+    pub(crate) fn id(self) -> u8 {
+        match self {
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => SNAPPY,
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => ZSTD,
+        }
+    }
+
+    fn compress(self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "snappy")]
+            Compression::Snappy => snap::raw::Encoder::new()
+                .compress_vec(data)
+                .map_err(io::Error::other),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => zstd::bulk::compress(data, level),
+        }
+    }
+}
+
+/// Compression of the message data of a topic.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompressionConfig {
+    algorithm: Compression,
+    threshold: usize,
+    max_decompressed_size: usize,
+}
+
+impl CompressionConfig {
+    /// Compresses message data with the given algorithm.
+    pub fn new(algorithm: Compression) -> Self {
+        Self {
+            algorithm,
+            threshold: 512,
+            max_decompressed_size: 10 * 1024 * 1024,
+        }
+    }
+
+    /// Message data smaller than `threshold` bytes is not compressed. The default is 512 bytes.
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Received messages decompressing to more than `max_decompressed_size` bytes are rejected.
+    /// The default is 10 MiB.
+    pub fn with_max_decompressed_size(mut self, max_decompressed_size: usize) -> Self {
+        self.max_decompressed_size = max_decompressed_size;
+        self
+    }
+
+    /// The compression algorithm.
+    pub fn algorithm(&self) -> Compression {
+        self.algorithm
+    }
+
+    /// The size in bytes above which message data is compressed.
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    /// The maximum size in bytes of decompressed message data.
+    pub fn max_decompressed_size(&self) -> usize {
+        self.max_decompressed_size
+    }
+
+    /// Compresses `data` if it reaches the threshold, prefixing it with the algorithm used.
+    pub(crate) fn compress(&self, data: Vec<u8>) -> io::Result<Vec<u8>> {
+        if data.len() >= self.threshold {
+            let compressed = self.algorithm.compress(&data)?;
+            // Incompressible data is sent as is.
+            if compressed.len() < data.len() {
+                let mut framed = Vec::with_capacity(compressed.len() + 1);
+                framed.push(self.algorithm.id());
+                framed.extend_from_slice(&compressed);
+                return Ok(framed);
+            }
+        }
+
+        let mut framed = Vec::with_capacity(data.len() + 1);
+        framed.push(UNCOMPRESSED);
+        framed.extend_from_slice(&data);
+        Ok(framed)
+    }
+
+    /// Decompresses data produced by [`CompressionConfig::compress`].
+    pub(crate) fn decompress(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let Some((prefix, data)) = data.split_first() else {
+            return Err(invalid_data("missing compression prefix"));
+        };
+
+        match *prefix {
+            UNCOMPRESSED => Ok(data.to_vec()),
+            #[cfg(feature = "snappy")]
+            SNAPPY => {
+                let len = snap::raw::decompress_len(data).map_err(io::Error::other)?;
+                if len > self.max_decompressed_size {
+                    return Err(invalid_data("decompressed data too large"));
+                }
+                snap::raw::Decoder::new()
+                    .decompress_vec(data)
+                    .map_err(io::Error::other)
+            }
+            #[cfg(feature = "zstd")]
+            ZSTD => zstd::bulk::decompress(data, self.max_decompressed_size),
+            _ => Err(invalid_data("unknown compression prefix")),
+        }
+    }
+}
+
+/// Decompresses the data of a received message if compression is configured for its topic.
+pub(crate) fn decompress_raw(
+    config: &Config,
+    mut raw_message: RawMessage,
+) -> io::Result<RawMessage> {
+    if let Some(compression) = config
+        .topic_config(&raw_message.topic)
+        .and_then(|c| c.compression.as_ref())
+    {
+        raw_message.data = compression.decompress(&raw_message.data)?;
+    }
+    Ok(raw_message)
+}
+
+fn invalid_data(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "snappy")]
+    #[test]
+    fn data_below_threshold_is_not_compressed() {
+        let config = CompressionConfig::new(Compression::Snappy).with_threshold(16);
+        let data = vec![1; 8];
+
+        let compressed = config.compress(data.clone()).unwrap();
+
+        assert_eq!(compressed[0], UNCOMPRESSED);
+        assert_eq!(config.decompress(&compressed).unwrap(), data);
+    }
+
+    #[cfg(feature = "snappy")]
+    #[test]
+    fn snappy_roundtrip() {
+        let config = CompressionConfig::new(Compression::Snappy).with_threshold(16);
+        let data = vec![1; 1024];
+
+        let compressed = config.compress(data.clone()).unwrap();
+
+        assert_eq!(compressed[0], SNAPPY);
+        assert!(compressed.len() < data.len());
+        assert_eq!(config.decompress(&compressed).unwrap(), data);
+        assert!(config
+            .with_max_decompressed_size(512)
+            .decompress(&compressed)
+            .is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_roundtrip() {
+        let config = CompressionConfig::new(Compression::Zstd { level: 3 }).with_threshold(16);
+        let data = vec![1; 1024];
+
+        let compressed = config.compress(data.clone()).unwrap();
+
+        assert_eq!(compressed[0], ZSTD);
+        assert_eq!(config.decompress(&compressed).unwrap(), data);
+    }
+}
@@ -19,12 +19,16 @@
 // DEALINGS IN THE SOFTWARE.
 
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 
+#[cfg(any(feature = "snappy", feature = "zstd"))]
+use crate::compression::CompressionConfig;
 use crate::error::ConfigBuilderError;
 use crate::protocol::{ProtocolConfig, ProtocolId, FLOODSUB_PROTOCOL};
 use crate::types::{Message, MessageId, PeerKind};
+use crate::TopicHash;
 
 use libp2p_identity::PeerId;
 use libp2p_swarm::StreamProtocol;
@@ -59,6 +63,43 @@ pub enum Version {
     V1_1,
 }
 
+/// Mesh parameters overriding those of the global [`Config`] for a single topic.
+///
+/// Parameters left as `None` fall back to the corresponding value of the [`Config`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TopicConfig {
+    /// Overrides [`Config::mesh_n`].
+    pub mesh_n: Option<usize>,
+    /// Overrides [`Config::mesh_n_low`].
+    pub mesh_n_low: Option<usize>,
+    /// Overrides [`Config::mesh_n_high`].
+    pub mesh_n_high: Option<usize>,
+    /// Overrides [`Config::gossip_factor`].
+    pub gossip_factor: Option<f64>,
+    /// Overrides [`Config::flood_publish`].
+    pub flood_publish: Option<bool>,
+    /// Compresses the data of messages published to the topic and decompresses received ones.
+    ///
+    /// The algorithm is advertised when subscribing to the topic. Peers advertising a different
+    /// algorithm, or none, are not considered subscribed to the topic.
+    #[cfg(any(feature = "snappy", feature = "zstd"))]
+    pub compression: Option<CompressionConfig>,
+    /// Overrides [`Config::duplicate_cache_time`] for messages of the topic.
+    ///
+    /// Independent of the window during which deliveries of a message are tracked for scoring,
+    /// see [`TopicConfig::duplicate_deliveries_window`].
+    pub seen_ttl: Option<Duration>,
+    /// The time after first seeing a message during which its deliveries are tracked for peer
+    /// scoring, 2 minutes if unset.
+    ///
+    /// Duplicates from mesh peers within the `mesh_message_deliveries_window` of the
+    /// [`TopicScoreParams`](crate::TopicScoreParams) count towards P3 and forwarders of an invalid
+    /// message are penalised under P4 only while its deliveries are tracked. Windows shorter than
+    /// `mesh_message_deliveries_window` are extended to it. Independent of
+    /// [`TopicConfig::seen_ttl`].
+    pub duplicate_deliveries_window: Option<Duration>,
+}
+
 /// Configuration parameters that define the performance of the gossipsub network.
 #[derive(Clone)]
 pub struct Config {
@@ -77,6 +118,10 @@ pub struct Config {
     check_explicit_peers_ticks: u64,
     duplicate_cache_time: Duration,
     validate_messages: bool,
+    max_concurrent_validations: usize,
+    max_queued_validations: usize,
+    validation_timeout: Duration,
+    publish_feedback_timeout: Duration,
     message_id_fn: Arc<dyn Fn(&Message) -> MessageId + Send + Sync + 'static>,
     allow_self_origin: bool,
     do_px: bool,
@@ -95,6 +140,10 @@ pub struct Config {
     max_ihave_messages: usize,
     iwant_followup_time: Duration,
     published_message_ids_cache_time: Duration,
+    publish_status_capacity: usize,
+    subscription_batch_window: Duration,
+    max_wildcard_topics: usize,
+    topic_configs: HashMap<TopicHash, TopicConfig>,
 }
 
 impl Config {
@@ -182,6 +231,13 @@ impl Config {
         self.protocol.max_transmit_size
     }
 
+    /// The maximum number of published or forwarded messages queued for sending on a single
+    /// connection. Further messages are dropped, failing their [`crate::PublishHandle`] if they
+    /// have one. Control messages are never dropped. Default is 5000.
+    pub fn max_queued_messages(&self) -> usize {
+        self.protocol.max_queued_messages
+    }
+
     /// Duplicates are prevented by storing message id's of known messages in an LRU time cache.
     /// This settings sets the time period that messages are stored in the cache. Duplicates can be
     /// received if duplicate messages are sent at a time greater than this setting apart. The
@@ -199,6 +255,31 @@ impl Config {
         self.validate_messages
     }
 
+    /// The maximum number of messages validated concurrently by the validators registered with
+    /// [`crate::Behaviour::register_validator()`]. Further messages are queued until a validation
+    /// completes. The default is 64.
+    pub fn max_concurrent_validations(&self) -> usize {
+        self.max_concurrent_validations
+    }
+
+    /// The maximum number of messages waiting for a validation slot. Messages received while the
+    /// queue is full are ignored. The default is 1024.
+    pub fn max_queued_validations(&self) -> usize {
+        self.max_queued_validations
+    }
+
+    /// The time a validator registered with [`crate::Behaviour::register_validator()`] has to
+    /// validate a message before it is ignored. The default is 5 seconds.
+    pub fn validation_timeout(&self) -> Duration {
+        self.validation_timeout
+    }
+
+    /// The time a message published via [`crate::Behaviour::publish_with_feedback()`] has to be
+    /// sent to enough peers before its [`crate::PublishHandle`] fails. The default is 30 seconds.
+    pub fn publish_feedback_timeout(&self) -> Duration {
+        self.publish_feedback_timeout
+    }
+
     /// Determines the level of validation used when receiving messages. See [`ValidationMode`]
     /// for the available types. The default is ValidationMode::Strict.
     pub fn validation_mode(&self) -> &ValidationMode {
@@ -350,6 +431,142 @@ impl Config {
     pub fn published_message_ids_cache_time(&self) -> Duration {
         self.published_message_ids_cache_time
     }
+
+    /// The number of recently published messages for which a
+    /// [`PublishStatus`](crate::PublishStatus) is tracked. A value of zero disables tracking.
+    /// The default is 128.
+    pub fn publish_status_capacity(&self) -> usize {
+        self.publish_status_capacity
+    }
+
+    /// The time SUBSCRIBE and UNSUBSCRIBE announcements are delayed to batch them into a single
+    /// message per peer. Subscribing to and unsubscribing from a topic again within the window
+    /// is not announced at all. The default is zero, announcing every change right away.
+    pub fn subscription_batch_window(&self) -> Duration {
+        self.subscription_batch_window
+    }
+
+    /// The maximum number of topics a subscription via
+    /// [`Behaviour::subscribe_wildcard`](crate::Behaviour::subscribe_wildcard) expands to.
+    /// Further matching topics announced by peers are ignored. The default is 64.
+    pub fn max_wildcard_topics(&self) -> usize {
+        self.max_wildcard_topics
+    }
+
+    /// Returns the per-topic overrides for the given topic, if any.
+    pub fn topic_config(&self, topic: &TopicHash) -> Option<&TopicConfig> {
+        self.topic_configs.get(topic)
+    }
+
+    /// [`Config::mesh_n`], taking the overrides of `topic` into account.
+    pub(crate) fn mesh_n_for_topic(&self, topic: &TopicHash) -> usize {
+        self.topic_config(topic)
+            .and_then(|c| c.mesh_n)
+            .unwrap_or(self.mesh_n)
+    }
+
+    /// [`Config::mesh_n_low`], taking the overrides of `topic` into account.
+    pub(crate) fn mesh_n_low_for_topic(&self, topic: &TopicHash) -> usize {
+        self.topic_config(topic)
+            .and_then(|c| c.mesh_n_low)
+            .unwrap_or(self.mesh_n_low)
+    }
+
+    /// [`Config::mesh_n_high`], taking the overrides of `topic` into account.
+    pub(crate) fn mesh_n_high_for_topic(&self, topic: &TopicHash) -> usize {
+        self.topic_config(topic)
+            .and_then(|c| c.mesh_n_high)
+            .unwrap_or(self.mesh_n_high)
+    }
+
+    /// [`Config::gossip_factor`], taking the overrides of `topic` into account.
+    pub(crate) fn gossip_factor_for_topic(&self, topic: &TopicHash) -> f64 {
+        self.topic_config(topic)
+            .and_then(|c| c.gossip_factor)
+            .unwrap_or(self.gossip_factor)
+    }
+
+    /// [`Config::flood_publish`], taking the overrides of `topic` into account.
+    pub(crate) fn flood_publish_for_topic(&self, topic: &TopicHash) -> bool {
+        self.topic_config(topic)
+            .and_then(|c| c.flood_publish)
+            .unwrap_or(self.flood_publish)
+    }
+
+    /// [`Config::duplicate_cache_time`], taking the overrides of `topic` into account.
+    pub(crate) fn seen_ttl_for_topic(&self, topic: &TopicHash) -> Duration {
+        self.topic_config(topic)
+            .and_then(|c| c.seen_ttl)
+            .unwrap_or(self.duplicate_cache_time)
+    }
+
+    /// The compression algorithm advertised in subscriptions to `topic`, if any.
+    #[cfg(any(feature = "snappy", feature = "zstd"))]
+    pub(crate) fn compression_for_topic(&self, topic: &TopicHash) -> Option<u32> {
+        self.topic_config(topic)?
+            .compression
+            .as_ref()
+            .map(|c| c.algorithm().id().into())
+    }
+
+    /// The compression algorithm advertised in subscriptions to `topic`, if any.
+    #[cfg(not(any(feature = "snappy", feature = "zstd")))]
+    pub(crate) fn compression_for_topic(&self, _: &TopicHash) -> Option<u32> {
+        None
+    }
+
+    /// Returns the topics whose deliveries are tracked for a custom time, see
+    /// [`TopicConfig::duplicate_deliveries_window`].
+    pub(crate) fn duplicate_deliveries_windows(
+        &self,
+    ) -> impl Iterator<Item = (&TopicHash, Duration)> {
+        self.topic_configs
+            .iter()
+            .filter_map(|(topic, c)| Some((topic, c.duplicate_deliveries_window?)))
+    }
+
+    /// Validates `topic_config` against this configuration and, if valid, applies it to `topic`,
+    /// returning the overrides it replaces.
+    pub(crate) fn set_topic_config(
+        &mut self,
+        topic: TopicHash,
+        topic_config: TopicConfig,
+    ) -> Result<Option<TopicConfig>, ConfigBuilderError> {
+        validate_mesh_parameters(
+            self.mesh_outbound_min,
+            topic_config.mesh_n_low.unwrap_or(self.mesh_n_low),
+            topic_config.mesh_n.unwrap_or(self.mesh_n),
+            topic_config.mesh_n_high.unwrap_or(self.mesh_n_high),
+        )?;
+        Ok(self.topic_configs.insert(topic, topic_config))
+    }
+
+    /// Restores the overrides of `topic` replaced by [`Config::set_topic_config`].
+    pub(crate) fn restore_topic_config(&mut self, topic: TopicHash, previous: Option<TopicConfig>) {
+        match previous {
+            Some(topic_config) => self.topic_configs.insert(topic, topic_config),
+            None => self.topic_configs.remove(&topic),
+        };
+    }
+}
+
+/// Checks that `mesh_outbound_min <= mesh_n_low <= mesh_n <= mesh_n_high` and
+/// `mesh_outbound_min <= mesh_n / 2` hold.
+fn validate_mesh_parameters(
+    mesh_outbound_min: usize,
+    mesh_n_low: usize,
+    mesh_n: usize,
+    mesh_n_high: usize,
+) -> Result<(), ConfigBuilderError> {
+    if !(mesh_outbound_min <= mesh_n_low && mesh_n_low <= mesh_n && mesh_n <= mesh_n_high) {
+        return Err(ConfigBuilderError::MeshParametersInvalid);
+    }
+
+    if mesh_outbound_min * 2 > mesh_n {
+        return Err(ConfigBuilderError::MeshOutboundInvalid);
+    }
+
+    Ok(())
 }
 
 impl Default for Config {
@@ -386,6 +603,10 @@ impl Default for ConfigBuilder {
                 check_explicit_peers_ticks: 300,
                 duplicate_cache_time: Duration::from_secs(60),
                 validate_messages: false,
+                max_concurrent_validations: 64,
+                max_queued_validations: 1024,
+                validation_timeout: Duration::from_secs(5),
+                publish_feedback_timeout: Duration::from_secs(30),
                 message_id_fn: Arc::new(|message| {
                     // default message id is: source + sequence number
                     // NOTE: If either the peer_id or source is not provided, we set to 0;
@@ -417,6 +638,10 @@ impl Default for ConfigBuilder {
                 max_ihave_messages: 10,
                 iwant_followup_time: Duration::from_secs(3),
                 published_message_ids_cache_time: Duration::from_secs(10),
+                publish_status_capacity: 128,
+                subscription_batch_window: Duration::ZERO,
+                max_wildcard_topics: 64,
+                topic_configs: HashMap::new(),
             },
             invalid_protocol: false,
         }
@@ -577,6 +802,13 @@ impl ConfigBuilder {
         self
     }
 
+    /// The maximum number of published or forwarded messages queued for sending on a single
+    /// connection (default is 5000).
+    pub fn max_queued_messages(&mut self, max_queued_messages: usize) -> &mut Self {
+        self.config.protocol.max_queued_messages = max_queued_messages;
+        self
+    }
+
     /// Duplicates are prevented by storing message id's of known messages in an LRU time cache.
     /// This settings sets the time period that messages are stored in the cache. Duplicates can be
     /// received if duplicate messages are sent at a time greater than this setting apart. The
@@ -595,6 +827,34 @@ impl ConfigBuilder {
         self
     }
 
+    /// The maximum number of messages validated concurrently by the validators registered with
+    /// [`crate::Behaviour::register_validator()`] (default is 64).
+    pub fn max_concurrent_validations(&mut self, max_concurrent_validations: usize) -> &mut Self {
+        self.config.max_concurrent_validations = max_concurrent_validations;
+        self
+    }
+
+    /// The maximum number of messages waiting for a validation slot, further messages are ignored
+    /// (default is 1024).
+    pub fn max_queued_validations(&mut self, max_queued_validations: usize) -> &mut Self {
+        self.config.max_queued_validations = max_queued_validations;
+        self
+    }
+
+    /// The time a validator registered with [`crate::Behaviour::register_validator()`] has to
+    /// validate a message before it is ignored (default is 5 seconds).
+    pub fn validation_timeout(&mut self, validation_timeout: Duration) -> &mut Self {
+        self.config.validation_timeout = validation_timeout;
+        self
+    }
+
+    /// The time a message published via [`crate::Behaviour::publish_with_feedback()`] has to be
+    /// sent to enough peers before its [`crate::PublishHandle`] fails (default is 30 seconds).
+    pub fn publish_feedback_timeout(&mut self, publish_feedback_timeout: Duration) -> &mut Self {
+        self.config.publish_feedback_timeout = publish_feedback_timeout;
+        self
+    }
+
     /// Determines the level of validation used when receiving messages. See [`ValidationMode`]
     /// for the available types. The default is ValidationMode::Strict.
     pub fn validation_mode(&mut self, validation_mode: ValidationMode) -> &mut Self {
@@ -782,6 +1042,37 @@ impl ConfigBuilder {
         self
     }
 
+    /// The number of recently published messages for which a
+    /// [`PublishStatus`](crate::PublishStatus) is tracked. A value of zero disables tracking.
+    /// The default is 128.
+    pub fn publish_status_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.config.publish_status_capacity = capacity;
+        self
+    }
+
+    /// The time SUBSCRIBE and UNSUBSCRIBE announcements are delayed to batch them into a single
+    /// message per peer. Subscribing to and unsubscribing from a topic again within the window
+    /// is not announced at all. The default is zero, announcing every change right away.
+    pub fn subscription_batch_window(&mut self, window: Duration) -> &mut Self {
+        self.config.subscription_batch_window = window;
+        self
+    }
+
+    /// The maximum number of topics a subscription via
+    /// [`Behaviour::subscribe_wildcard`](crate::Behaviour::subscribe_wildcard) expands to.
+    /// Further matching topics announced by peers are ignored. The default is 64.
+    pub fn max_wildcard_topics(&mut self, max_wildcard_topics: usize) -> &mut Self {
+        self.config.max_wildcard_topics = max_wildcard_topics;
+        self
+    }
+
+    /// Overrides mesh parameters for the given topic. The overrides are validated against the
+    /// remaining configuration in [`ConfigBuilder::build`].
+    pub fn topic_config(&mut self, topic: TopicHash, topic_config: TopicConfig) -> &mut Self {
+        self.config.topic_configs.insert(topic, topic_config);
+        self
+    }
+
     /// Constructs a [`Config`] from the given configuration and validates the settings.
     pub fn build(&self) -> Result<Config, ConfigBuilderError> {
         // check all constraints on config
@@ -794,15 +1085,16 @@ impl ConfigBuilder {
             return Err(ConfigBuilderError::HistoryLengthTooSmall);
         }
 
-        if !(self.config.mesh_outbound_min <= self.config.mesh_n_low
-            && self.config.mesh_n_low <= self.config.mesh_n
-            && self.config.mesh_n <= self.config.mesh_n_high)
-        {
-            return Err(ConfigBuilderError::MeshParametersInvalid);
-        }
+        validate_mesh_parameters(
+            self.config.mesh_outbound_min,
+            self.config.mesh_n_low,
+            self.config.mesh_n,
+            self.config.mesh_n_high,
+        )?;
 
-        if self.config.mesh_outbound_min * 2 > self.config.mesh_n {
-            return Err(ConfigBuilderError::MeshOutboundInvalid);
+        let mut config = self.config.clone();
+        for (topic, topic_config) in std::mem::take(&mut config.topic_configs) {
+            config.set_topic_config(topic, topic_config)?;
         }
 
         if self.config.unsubscribe_backoff.as_millis() == 0 {
@@ -813,7 +1105,7 @@ impl ConfigBuilder {
             return Err(ConfigBuilderError::InvalidProtocol);
         }
 
-        Ok(self.config.clone())
+        Ok(config)
     }
 }
 
@@ -834,6 +1126,13 @@ impl std::fmt::Debug for Config {
         let _ = builder.field("fanout_ttl", &self.fanout_ttl);
         let _ = builder.field("duplicate_cache_time", &self.duplicate_cache_time);
         let _ = builder.field("validate_messages", &self.validate_messages);
+        let _ = builder.field(
+            "max_concurrent_validations",
+            &self.max_concurrent_validations,
+        );
+        let _ = builder.field("max_queued_validations", &self.max_queued_validations);
+        let _ = builder.field("validation_timeout", &self.validation_timeout);
+        let _ = builder.field("publish_feedback_timeout", &self.publish_feedback_timeout);
         let _ = builder.field("allow_self_origin", &self.allow_self_origin);
         let _ = builder.field("do_px", &self.do_px);
         let _ = builder.field("prune_peers", &self.prune_peers);
@@ -852,6 +1151,10 @@ impl std::fmt::Debug for Config {
             "published_message_ids_cache_time",
             &self.published_message_ids_cache_time,
         );
+        let _ = builder.field("publish_status_capacity", &self.publish_status_capacity);
+        let _ = builder.field("subscription_batch_window", &self.subscription_batch_window);
+        let _ = builder.field("max_wildcard_topics", &self.max_wildcard_topics);
+        let _ = builder.field("topic_configs", &self.topic_configs);
         builder.finish()
     }
 }
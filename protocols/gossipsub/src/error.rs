@@ -54,6 +54,35 @@ impl std::error::Error for PublishError {
     }
 }
 
+/// Error returned by a [`crate::PublishHandle`] if a message could not be sent to enough peers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryError {
+    /// The message was dropped by too many peers because their send queue was full.
+    QueueFull,
+    /// The message was published to too few peers, or too many of them disconnected before it
+    /// was sent.
+    InsufficientPeers,
+    /// The message was not sent to enough peers within [`crate::Config::publish_feedback_timeout`].
+    Timeout,
+}
+
+impl std::fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::QueueFull => write!(
+                f,
+                "Message was dropped by too many peers because their send queue was full"
+            ),
+            Self::InsufficientPeers => {
+                write!(f, "Message could not be sent to enough connected peers")
+            }
+            Self::Timeout => write!(f, "Message was not sent to enough peers in time"),
+        }
+    }
+}
+
+impl std::error::Error for DeliveryError {}
+
 /// Error associated with subscribing to a topic.
 #[derive(Debug)]
 pub enum SubscriptionError {
@@ -78,6 +107,36 @@ impl std::error::Error for SubscriptionError {
     }
 }
 
+/// Error returned by [`crate::Behaviour::subscribe_with_config`].
+#[derive(Debug)]
+pub enum SubscribeWithConfigError {
+    /// Subscribing to the topic failed.
+    Subscription(SubscriptionError),
+    /// The per-topic configuration is inconsistent with the global configuration.
+    InvalidTopicConfig(ConfigBuilderError),
+}
+
+impl std::fmt::Display for SubscribeWithConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+impl std::error::Error for SubscribeWithConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Subscription(err) => Some(err),
+            Self::InvalidTopicConfig(err) => Some(err),
+        }
+    }
+}
+
+impl From<SubscriptionError> for SubscribeWithConfigError {
+    fn from(error: SubscriptionError) -> Self {
+        SubscribeWithConfigError::Subscription(error)
+    }
+}
+
 impl From<SigningError> for PublishError {
     fn from(error: SigningError) -> Self {
         PublishError::SigningError(error)
@@ -20,7 +20,7 @@
 
 use crate::protocol::{GossipsubCodec, ProtocolConfig};
 use crate::rpc_proto::proto;
-use crate::types::{PeerKind, RawMessage, Rpc, RpcOut};
+use crate::types::{MessageId, PeerKind, RawMessage, Rpc, RpcOut};
 use crate::ValidationError;
 use asynchronous_codec::Framed;
 use futures::future::Either;
@@ -35,6 +35,7 @@ use libp2p_swarm::handler::{
 use libp2p_swarm::Stream;
 use smallvec::SmallVec;
 use std::{
+    collections::VecDeque,
     pin::Pin,
     task::{Context, Poll},
 };
@@ -55,6 +56,10 @@ pub enum HandlerEvent {
     /// An inbound or outbound substream has been established with the peer and this informs over
     /// which protocol. This message only occurs once per connection.
     PeerKind(PeerKind),
+    /// A published message has been sent and flushed to the peer.
+    MessageSent(MessageId),
+    /// A published or forwarded message has been dropped because the send queue was full.
+    MessageDropped(MessageId),
 }
 
 /// A message sent from the behaviour to the handler.
@@ -95,7 +100,10 @@ pub struct EnabledHandler {
     inbound_substream: Option<InboundSubstreamState>,
 
     /// Queue of values that we want to send to the remote.
-    send_queue: SmallVec<[proto::RPC; 16]>,
+    send_queue: SmallVec<[QueuedRpc; 16]>,
+
+    /// Events to report to the behaviour.
+    pending_events: VecDeque<HandlerEvent>,
 
     /// Flag indicating that an outbound substream is being established to prevent duplicate
     /// requests.
@@ -145,14 +153,24 @@ enum InboundSubstreamState {
     Poisoned,
 }
 
+/// An RPC waiting in the send queue.
+struct QueuedRpc {
+    rpc: proto::RPC,
+    /// The id of the published or forwarded message carried by the RPC, if any.
+    message_id: Option<MessageId>,
+    /// Whether the behaviour is notified once the RPC has been sent.
+    notify_sent: bool,
+}
+
 /// State of the outbound substream, opened either by us or by the remote.
 enum OutboundSubstreamState {
     /// Waiting for the user to send a message. The idle state for an outbound substream.
     WaitingOutput(Framed<Stream, GossipsubCodec>),
     /// Waiting to send a message to the remote.
-    PendingSend(Framed<Stream, GossipsubCodec>, proto::RPC),
-    /// Waiting to flush the substream so that the data arrives to the remote.
-    PendingFlush(Framed<Stream, GossipsubCodec>),
+    PendingSend(Framed<Stream, GossipsubCodec>, QueuedRpc),
+    /// Waiting to flush the substream so that the data arrives to the remote. Contains the id of
+    /// the published message to report as sent, if any.
+    PendingFlush(Framed<Stream, GossipsubCodec>, Option<MessageId>),
     /// An error occurred during processing.
     Poisoned,
 }
@@ -168,6 +186,7 @@ impl Handler {
             outbound_substream_attempts: 0,
             inbound_substream_attempts: 0,
             send_queue: SmallVec::new(),
+            pending_events: VecDeque::new(),
             peer_kind: None,
             peer_kind_sent: false,
             last_io_activity: Instant::now(),
@@ -177,6 +196,35 @@ impl Handler {
 }
 
 impl EnabledHandler {
+    /// Adds `rpc` to the send queue, dropping it if it carries a message and the queue is full.
+    fn queue_rpc(&mut self, rpc: RpcOut) {
+        let (message_id, notify_sent) = match &rpc {
+            RpcOut::Publish { message_id, .. } => (Some(message_id.clone()), true),
+            RpcOut::Forward { message_id, .. } => (Some(message_id.clone()), false),
+            _ => (None, false),
+        };
+
+        if let Some(message_id) = &message_id {
+            let queued_messages = self
+                .send_queue
+                .iter()
+                .filter(|queued| queued.message_id.is_some())
+                .count();
+            if queued_messages >= self.listen_protocol.max_queued_messages {
+                tracing::debug!(message=%message_id, "Send queue is full, dropping message");
+                self.pending_events
+                    .push_back(HandlerEvent::MessageDropped(message_id.clone()));
+                return;
+            }
+        }
+
+        self.send_queue.push(QueuedRpc {
+            rpc: rpc.into_protobuf(),
+            message_id,
+            notify_sent,
+        });
+    }
+
     fn on_fully_negotiated_inbound(
         &mut self,
         (substream, peer_kind): (Framed<Stream, GossipsubCodec>, PeerKind),
@@ -231,6 +279,10 @@ impl EnabledHandler {
             }
         }
 
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event));
+        }
+
         // determine if we need to create the outbound stream
         if !self.send_queue.is_empty()
             && self.outbound_substream.is_none()
@@ -264,10 +316,12 @@ impl EnabledHandler {
                 Some(OutboundSubstreamState::PendingSend(mut substream, message)) => {
                     match Sink::poll_ready(Pin::new(&mut substream), cx) {
                         Poll::Ready(Ok(())) => {
-                            match Sink::start_send(Pin::new(&mut substream), message) {
+                            let published = message.message_id.filter(|_| message.notify_sent);
+                            match Sink::start_send(Pin::new(&mut substream), message.rpc) {
                                 Ok(()) => {
-                                    self.outbound_substream =
-                                        Some(OutboundSubstreamState::PendingFlush(substream))
+                                    self.outbound_substream = Some(
+                                        OutboundSubstreamState::PendingFlush(substream, published),
+                                    )
                                 }
                                 Err(e) => {
                                     tracing::debug!(
@@ -290,12 +344,17 @@ impl EnabledHandler {
                         }
                     }
                 }
-                Some(OutboundSubstreamState::PendingFlush(mut substream)) => {
+                Some(OutboundSubstreamState::PendingFlush(mut substream, published)) => {
                     match Sink::poll_flush(Pin::new(&mut substream), cx) {
                         Poll::Ready(Ok(())) => {
                             self.last_io_activity = Instant::now();
                             self.outbound_substream =
-                                Some(OutboundSubstreamState::WaitingOutput(substream))
+                                Some(OutboundSubstreamState::WaitingOutput(substream));
+                            if let Some(message_id) = published {
+                                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                                    HandlerEvent::MessageSent(message_id),
+                                ));
+                            }
                         }
                         Poll::Ready(Err(e)) => {
                             tracing::debug!("Failed to flush outbound stream: {e}");
@@ -304,7 +363,7 @@ impl EnabledHandler {
                         }
                         Poll::Pending => {
                             self.outbound_substream =
-                                Some(OutboundSubstreamState::PendingFlush(substream));
+                                Some(OutboundSubstreamState::PendingFlush(substream, published));
                             break;
                         }
                     }
@@ -409,7 +468,7 @@ impl ConnectionHandler for Handler {
     fn on_behaviour_event(&mut self, message: HandlerIn) {
         match self {
             Handler::Enabled(handler) => match message {
-                HandlerIn::Message(m) => handler.send_queue.push(m.into_protobuf()),
+                HandlerIn::Message(m) => handler.queue_rpc(m),
                 HandlerIn::JoinedMesh => {
                     handler.in_mesh = true;
                 }
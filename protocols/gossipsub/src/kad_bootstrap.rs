@@ -0,0 +1,153 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Discovery of initial topic peers through Kademlia provider records.
+//!
+//! Gossipsub only builds its mesh over existing connections, so a freshly started node has to
+//! find peers subscribed to its topics some other way. [`TopicBootstrap`] announces the local
+//! node as a provider of a key derived from each topic hash and looks up other providers of the
+//! same key, reporting them so that they can be dialed.
+//!
+//! ```ignore
+//! let mut bootstrap = TopicBootstrap::new(*swarm.local_peer_id());
+//! bootstrap.announce(&mut swarm.behaviour_mut().kad, &topic.hash())?;
+//!
+//! // In the event loop:
+//! if let SwarmEvent::Behaviour(BehaviourEvent::Kad(event)) = event {
+//!     for (_topic, peer) in bootstrap.on_kad_event(&event) {
+//!         swarm.dial(peer)?;
+//!     }
+//! }
+//! ```
+//!
+//! Provider records expire, so [`TopicBootstrap::announce`] should be called again periodically,
+//! e.g. every [`libp2p_kad::Config::set_provider_publication_interval`].
+
+use crate::TopicHash;
+use libp2p_identity::PeerId;
+use libp2p_kad::store::{self, RecordStore};
+use libp2p_kad::{GetProvidersOk, QueryId, QueryResult, RecordKey};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// The prefix of the hashed provider key of a topic.
+const TOPIC_KEY_PREFIX: &[u8] = b"/libp2p/gossipsub/topic/";
+
+/// Returns the Kademlia provider key under which subscribers of `topic` are announced.
+pub fn topic_provider_key(topic: &TopicHash) -> RecordKey {
+    let mut hasher = Sha256::new();
+    hasher.update(TOPIC_KEY_PREFIX);
+    hasher.update(topic.as_str().as_bytes());
+    RecordKey::new(&hasher.finalize())
+}
+
+/// Tracks Kademlia queries issued on behalf of gossipsub topics.
+#[derive(Debug)]
+pub struct TopicBootstrap {
+    /// The local peer, excluded from the discovered subscribers.
+    local_peer_id: PeerId,
+    /// In-flight `GET_PROVIDERS` queries and the topic they are for.
+    queries: HashMap<QueryId, TopicHash>,
+}
+
+impl TopicBootstrap {
+    /// Creates a new [`TopicBootstrap`] for the given local peer.
+    pub fn new(local_peer_id: PeerId) -> Self {
+        Self {
+            local_peer_id,
+            queries: HashMap::new(),
+        }
+    }
+
+    /// Announces the local node as a subscriber of `topic` and starts looking for other
+    /// subscribers.
+    pub fn announce<TStore>(
+        &mut self,
+        kad: &mut libp2p_kad::Behaviour<TStore>,
+        topic: &TopicHash,
+    ) -> Result<QueryId, store::Error>
+    where
+        TStore: RecordStore + Send + 'static,
+    {
+        kad.start_providing(topic_provider_key(topic))?;
+        Ok(self.discover(kad, topic))
+    }
+
+    /// Withdraws the local node's announcement for `topic`, e.g. after unsubscribing.
+    pub fn withdraw<TStore>(&mut self, kad: &mut libp2p_kad::Behaviour<TStore>, topic: &TopicHash)
+    where
+        TStore: RecordStore + Send + 'static,
+    {
+        kad.stop_providing(&topic_provider_key(topic));
+    }
+
+    /// Looks for subscribers of `topic` without announcing the local node.
+    pub fn discover<TStore>(
+        &mut self,
+        kad: &mut libp2p_kad::Behaviour<TStore>,
+        topic: &TopicHash,
+    ) -> QueryId
+    where
+        TStore: RecordStore + Send + 'static,
+    {
+        let id = kad.get_providers(topic_provider_key(topic));
+        self.queries.insert(id, topic.clone());
+        id
+    }
+
+    /// Processes an event of the Kademlia behaviour, returning the subscribers discovered for
+    /// each topic.
+    ///
+    /// Events of queries that were not issued through this [`TopicBootstrap`] are ignored.
+    pub fn on_kad_event(&mut self, event: &libp2p_kad::Event) -> Vec<(TopicHash, PeerId)> {
+        let libp2p_kad::Event::OutboundQueryProgressed {
+            id, result, step, ..
+        } = event
+        else {
+            return Vec::new();
+        };
+
+        let QueryResult::GetProviders(result) = result else {
+            return Vec::new();
+        };
+
+        let topic = if step.last {
+            self.queries.remove(id)
+        } else {
+            self.queries.get(id).cloned()
+        };
+        let Some(topic) = topic else {
+            return Vec::new();
+        };
+
+        match result {
+            Ok(GetProvidersOk::FoundProviders { providers, .. }) => providers
+                .iter()
+                .filter(|peer| **peer != self.local_peer_id)
+                .map(|peer| (topic.clone(), *peer))
+                .collect(),
+            Ok(GetProvidersOk::FinishedWithNoAdditionalRecord { .. }) => Vec::new(),
+            Err(e) => {
+                tracing::debug!(%topic, "Failed to discover topic peers: {e}");
+                Vec::new()
+            }
+        }
+    }
+}
@@ -95,37 +95,54 @@
 
 mod backoff;
 mod behaviour;
+#[cfg(any(feature = "snappy", feature = "zstd"))]
+mod compression;
 mod config;
 mod error;
 mod gossip_promises;
 mod handler;
+#[cfg(feature = "kad")]
+pub mod kad_bootstrap;
 mod mcache;
 mod metrics;
 mod peer_score;
 mod protocol;
+mod publish_status;
 mod rpc_proto;
 mod subscription_filter;
 mod time_cache;
 mod topic;
+mod tracer;
 mod transform;
 mod types;
+mod validation;
 
-pub use self::behaviour::{Behaviour, Event, MessageAuthenticity};
-pub use self::config::{Config, ConfigBuilder, ValidationMode, Version};
-pub use self::error::{ConfigBuilderError, PublishError, SubscriptionError, ValidationError};
+pub use self::behaviour::{
+    Behaviour, Event, ExplicitPeerStatus, MessageAuthenticity, PublishHandle,
+};
+#[cfg(any(feature = "snappy", feature = "zstd"))]
+pub use self::compression::{Compression, CompressionConfig};
+pub use self::config::{Config, ConfigBuilder, TopicConfig, ValidationMode, Version};
+pub use self::error::{
+    ConfigBuilderError, DeliveryError, PublishError, SubscribeWithConfigError, SubscriptionError,
+    ValidationError,
+};
 pub use self::metrics::Config as MetricsConfig;
 pub use self::peer_score::{
-    score_parameter_decay, score_parameter_decay_with_base, PeerScoreParams, PeerScoreThresholds,
-    TopicScoreParams,
+    score_parameter_decay, score_parameter_decay_with_base, PeerScoreParams, PeerScoreSnapshot,
+    PeerScoreSnapshotEntry, PeerScoreThresholds, TopicScoreParams, TopicScoreSnapshot,
 };
+pub use self::publish_status::PublishStatus;
 pub use self::subscription_filter::{
     AllowAllSubscriptionFilter, CallbackSubscriptionFilter, CombinedSubscriptionFilters,
     MaxCountSubscriptionFilter, RegexSubscriptionFilter, TopicSubscriptionFilter,
     WhitelistSubscriptionFilter,
 };
-pub use self::topic::{Hasher, Topic, TopicHash};
+pub use self::topic::{Hasher, Topic, TopicHash, TopicPattern};
+pub use self::tracer::{TraceEvent, Tracer};
 pub use self::transform::{DataTransform, IdentityTransform};
-pub use self::types::{Message, MessageAcceptance, MessageId, RawMessage};
+pub use self::types::{Message, MessageAcceptance, MessageId, PeerKind, RawMessage};
+pub use self::validation::MessageValidator;
 
 #[deprecated(note = "Will be removed from the public API.")]
 pub type Rpc = self::types::Rpc;
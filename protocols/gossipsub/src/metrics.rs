@@ -22,16 +22,19 @@
 //! protocol.
 
 use std::collections::HashMap;
+use std::time::Duration;
+
+use instant::Instant;
 
 use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue};
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::{Family, MetricConstructor};
 use prometheus_client::metrics::gauge::Gauge;
-use prometheus_client::metrics::histogram::{linear_buckets, Histogram};
+use prometheus_client::metrics::histogram::{exponential_buckets, linear_buckets, Histogram};
 use prometheus_client::registry::Registry;
 
 use crate::topic::TopicHash;
-use crate::types::{MessageAcceptance, PeerKind};
+use crate::types::{MessageAcceptance, MessageId, PeerKind};
 
 // Default value that limits for how many topics do we store metrics.
 const DEFAULT_MAX_TOPICS: usize = 300;
@@ -111,6 +114,8 @@ pub(crate) struct Metrics {
     /* Auxiliary variables */
     /// Information needed to decide if a topic is allowed or not.
     topic_info: HashMap<TopicHash, EverSubscribed>,
+    /// Outstanding IWANT requests, used to measure how long it takes for them to be fulfilled.
+    iwant_requests: HashMap<MessageId, (TopicHash, Instant)>,
 
     /* Metrics per known topic */
     /// Status of our subscription to this topic. This metric allows analyzing other topic metrics
@@ -136,6 +141,10 @@ pub(crate) struct Metrics {
     mesh_peer_inclusion_events: Family<InclusionLabel, Counter>,
     /// Number of times we remove peers in a topic mesh for different reasons.
     mesh_peer_churn_events: Family<ChurnLabel, Counter>,
+    /// Number of GRAFT control messages sent and received for each topic.
+    topic_graft_msgs: Family<ControlLabel, Counter>,
+    /// Number of PRUNE control messages sent and received for each topic.
+    topic_prune_msgs: Family<ControlLabel, Counter>,
 
     /* Metrics regarding messages sent/received */
     /// Number of gossip messages sent to each topic.
@@ -151,6 +160,12 @@ pub(crate) struct Metrics {
     topic_msg_recv_counts: Family<TopicHash, Counter>,
     /// Bytes received from gossip messages for each topic.
     topic_msg_recv_bytes: Family<TopicHash, Counter>,
+    /// Histogram of the ratio between compressed and uncompressed size of published messages.
+    #[cfg(any(feature = "snappy", feature = "zstd"))]
+    topic_compression_ratio: Family<TopicHash, Histogram, HistBuilder>,
+    /// Number of duplicate gossipsub messages received on each topic. Together with
+    /// `topic_msg_recv_counts_unfiltered` this gives the duplicate ratio of a topic.
+    topic_msg_duplicate_counts: Family<TopicHash, Counter>,
 
     /* Metrics related to scoring */
     /// Histogram of the scores for each mesh topic.
@@ -171,9 +186,14 @@ pub(crate) struct Metrics {
     /// message expires from the memcache before it can be validated, we count this a cache miss
     /// and it is an indicator that the memcache size should be increased.
     memcache_misses: Counter,
+    /// The number of published or forwarded messages dropped because the send queue of a
+    /// connection was full. See [`crate::Config::max_queued_messages`].
+    dropped_messages: Counter,
     /// The number of times we have decided that an IWANT control message is required for this
     /// topic. A very high metric might indicate an underperforming network.
     topic_iwant_msgs: Family<TopicHash, Counter>,
+    /// Histogram of the time in seconds between requesting a message via IWANT and receiving it.
+    iwant_fulfillment_latency: Family<TopicHash, Histogram, HistBuilder>,
 }
 
 impl Metrics {
@@ -234,6 +254,14 @@ impl Metrics {
             "mesh_peer_churn_events",
             "Number of times a peer gets removed from our mesh for different reasons"
         );
+        let topic_graft_msgs = register_family!(
+            "topic_graft_msgs",
+            "Number of GRAFT control messages sent and received for each topic"
+        );
+        let topic_prune_msgs = register_family!(
+            "topic_prune_msgs",
+            "Number of PRUNE control messages sent and received for each topic"
+        );
         let topic_msg_sent_counts = register_family!(
             "topic_msg_sent_counts",
             "Number of gossip messages sent to each topic"
@@ -260,6 +288,21 @@ impl Metrics {
             "topic_msg_recv_bytes",
             "Bytes received from gossip messages for each topic"
         );
+        #[cfg(any(feature = "snappy", feature = "zstd"))]
+        let topic_compression_ratio: Family<_, _, HistBuilder> =
+            Family::new_with_constructor(HistBuilder {
+                buckets: linear_buckets(0.1, 0.1, 10).collect(),
+            });
+        #[cfg(any(feature = "snappy", feature = "zstd"))]
+        registry.register(
+            "topic_compression_ratio",
+            "Histogram of the compressed to uncompressed size ratio of published messages",
+            topic_compression_ratio.clone(),
+        );
+        let topic_msg_duplicate_counts = register_family!(
+            "topic_msg_duplicate_counts",
+            "Number of duplicate gossip messages received on each topic"
+        );
 
         let hist_builder = HistBuilder {
             buckets: score_buckets,
@@ -292,6 +335,15 @@ impl Metrics {
             "topic_iwant_msgs",
             "Number of times we have decided an IWANT is required for this topic"
         );
+        let iwant_fulfillment_latency: Family<_, _, HistBuilder> =
+            Family::new_with_constructor(HistBuilder {
+                buckets: exponential_buckets(0.005, 2.0, 12).collect(),
+            });
+        registry.register(
+            "iwant_fulfillment_latency",
+            "Histogram of the seconds it takes for an IWANT request to be fulfilled",
+            iwant_fulfillment_latency.clone(),
+        );
         let memcache_misses = {
             let metric = Counter::default();
             registry.register(
@@ -301,11 +353,21 @@ impl Metrics {
             );
             metric
         };
+        let dropped_messages = {
+            let metric = Counter::default();
+            registry.register(
+                "dropped_messages",
+                "Number of messages dropped because the send queue of a connection was full",
+                metric.clone(),
+            );
+            metric
+        };
 
         Self {
             max_topics,
             max_never_subscribed_topics,
             topic_info: HashMap::default(),
+            iwant_requests: HashMap::default(),
             topic_subscription_status,
             topic_peers_count,
             invalid_messages,
@@ -315,18 +377,25 @@ impl Metrics {
             mesh_peer_counts,
             mesh_peer_inclusion_events,
             mesh_peer_churn_events,
+            topic_graft_msgs,
+            topic_prune_msgs,
             topic_msg_sent_counts,
             topic_msg_sent_bytes,
             topic_msg_published,
             topic_msg_recv_counts_unfiltered,
             topic_msg_recv_counts,
             topic_msg_recv_bytes,
+            #[cfg(any(feature = "snappy", feature = "zstd"))]
+            topic_compression_ratio,
+            topic_msg_duplicate_counts,
             score_per_mesh,
             scoring_penalties,
             peers_per_protocol,
             heartbeat_duration,
             memcache_misses,
+            dropped_messages,
             topic_iwant_msgs,
+            iwant_fulfillment_latency,
         }
     }
 
@@ -471,6 +540,47 @@ impl Metrics {
         }
     }
 
+    /// Observe the compression ratio of a published message.
+    #[cfg(any(feature = "snappy", feature = "zstd"))]
+    pub(crate) fn observe_compression_ratio(&mut self, topic: &TopicHash, ratio: f64) {
+        if self.register_topic(topic).is_ok() {
+            self.topic_compression_ratio
+                .get_or_create(topic)
+                .observe(ratio);
+        }
+    }
+
+    /// Register that a duplicate message was received.
+    pub(crate) fn msg_duplicate(&mut self, topic: &TopicHash) {
+        if self.register_topic(topic).is_ok() {
+            self.topic_msg_duplicate_counts.get_or_create(topic).inc();
+        }
+    }
+
+    /// Register a GRAFT control message for this topic.
+    pub(crate) fn register_graft(&mut self, topic: &TopicHash, direction: Direction) {
+        if self.register_topic(topic).is_ok() {
+            self.topic_graft_msgs
+                .get_or_create(&ControlLabel {
+                    hash: topic.to_string(),
+                    direction,
+                })
+                .inc();
+        }
+    }
+
+    /// Register a PRUNE control message for this topic.
+    pub(crate) fn register_prune(&mut self, topic: &TopicHash, direction: Direction) {
+        if self.register_topic(topic).is_ok() {
+            self.topic_prune_msgs
+                .get_or_create(&ControlLabel {
+                    hash: topic.to_string(),
+                    direction,
+                })
+                .inc();
+        }
+    }
+
     pub(crate) fn register_msg_validation(
         &mut self,
         topic: &TopicHash,
@@ -490,6 +600,11 @@ impl Metrics {
         self.memcache_misses.inc();
     }
 
+    /// Register a message dropped because a send queue was full.
+    pub(crate) fn message_dropped(&mut self) {
+        self.dropped_messages.inc();
+    }
+
     /// Register sending an IWANT msg for this topic.
     pub(crate) fn register_iwant(&mut self, topic: &TopicHash) {
         if self.register_topic(topic).is_ok() {
@@ -497,6 +612,32 @@ impl Metrics {
         }
     }
 
+    /// Register that a message has been requested via IWANT, to later measure how long it took
+    /// to receive it.
+    pub(crate) fn iwant_requested(&mut self, topic: &TopicHash, message_id: &MessageId) {
+        if self.register_topic(topic).is_ok() {
+            self.iwant_requests
+                .entry(message_id.clone())
+                .or_insert_with(|| (topic.clone(), Instant::now()));
+        }
+    }
+
+    /// Register that a message has been received. If it was requested via IWANT, the time it
+    /// took to fulfill the request is observed.
+    pub(crate) fn iwant_fulfilled(&mut self, message_id: &MessageId) {
+        if let Some((topic, requested)) = self.iwant_requests.remove(message_id) {
+            self.iwant_fulfillment_latency
+                .get_or_create(&topic)
+                .observe(requested.elapsed().as_secs_f64());
+        }
+    }
+
+    /// Stops tracking IWANT requests that have not been fulfilled within `max_age`.
+    pub(crate) fn expire_iwant_requests(&mut self, max_age: Duration) {
+        self.iwant_requests
+            .retain(|_, (_, requested)| requested.elapsed() < max_age);
+    }
+
     /// Observes a heartbeat duration.
     pub(crate) fn observe_heartbeat_duration(&mut self, millis: u64) {
         self.heartbeat_duration.observe(millis as f64);
@@ -556,6 +697,15 @@ pub(crate) enum Churn {
     Excess,
 }
 
+/// Direction of a control message.
+#[derive(PartialEq, Eq, Hash, EncodeLabelValue, Clone, Debug)]
+pub(crate) enum Direction {
+    /// The control message was sent by us.
+    Sent,
+    /// The control message was received from a peer.
+    Received,
+}
+
 /// Kinds of reasons a peer's score has been penalized
 #[derive(PartialEq, Eq, Hash, EncodeLabelValue, Clone, Debug)]
 pub(crate) enum Penalty {
@@ -583,6 +733,13 @@ struct ChurnLabel {
     reason: Churn,
 }
 
+/// Label for the GRAFT and PRUNE control message metrics.
+#[derive(PartialEq, Eq, Hash, EncodeLabelSet, Clone, Debug)]
+struct ControlLabel {
+    hash: String,
+    direction: Direction,
+}
+
 /// Label for the kinds of protocols peers can connect as.
 #[derive(PartialEq, Eq, Hash, EncodeLabelSet, Clone, Debug)]
 struct ProtocolLabel {
@@ -40,7 +40,8 @@ pub use params::{
 #[cfg(test)]
 mod tests;
 
-/// The number of seconds delivery messages are stored in the cache.
+/// The number of seconds delivery messages are stored in the cache, unless overridden for the
+/// topic.
 const TIME_CACHE_DURATION: u64 = 120;
 
 pub(crate) struct PeerScore {
@@ -51,6 +52,8 @@ pub(crate) struct PeerScore {
     peer_ips: HashMap<IpAddr, HashSet<PeerId>>,
     /// Message delivery tracking. This is a time-cache of [`DeliveryRecord`]s.
     deliveries: TimeCache<MessageId, DeliveryRecord>,
+    /// Topics whose [`DeliveryRecord`]s are kept for a custom time.
+    delivery_record_ttls: HashMap<TopicHash, Duration>,
     /// callback for monitoring message delivery times
     message_delivery_time_callback: Option<fn(&PeerId, &TopicHash, f64)>,
 }
@@ -208,10 +211,34 @@ impl PeerScore {
             peer_stats: HashMap::new(),
             peer_ips: HashMap::new(),
             deliveries: TimeCache::new(Duration::from_secs(TIME_CACHE_DURATION)),
+            delivery_record_ttls: HashMap::new(),
             message_delivery_time_callback: callback,
         }
     }
 
+    /// Sets the time the deliveries of a message in the topic are tracked, see
+    /// [`TopicConfig::duplicate_deliveries_window`](crate::TopicConfig::duplicate_deliveries_window).
+    pub(crate) fn set_delivery_record_ttl(&mut self, topic_hash: TopicHash, ttl: Option<Duration>) {
+        match ttl {
+            Some(ttl) => self.delivery_record_ttls.insert(topic_hash, ttl),
+            None => self.delivery_record_ttls.remove(&topic_hash),
+        };
+    }
+
+    /// Returns the time the deliveries of a message in the topic are tracked, never shorter than
+    /// the `mesh_message_deliveries_window` of the topic.
+    fn delivery_record_ttl(&self, topic_hash: &TopicHash) -> Duration {
+        let ttl = self
+            .delivery_record_ttls
+            .get(topic_hash)
+            .copied()
+            .unwrap_or(Duration::from_secs(TIME_CACHE_DURATION));
+        match self.params.topics.get(topic_hash) {
+            Some(params) => ttl.max(params.mesh_message_deliveries_window),
+            None => ttl,
+        }
+    }
+
     /// Returns the score for a peer
     pub(crate) fn score(&self, peer_id: &PeerId) -> f64 {
         self.metric_score(peer_id, None)
@@ -565,7 +592,10 @@ impl PeerScore {
         topic_hash: &TopicHash,
     ) {
         // adds an empty record with the message id
-        self.deliveries.entry(msg_id.clone()).or_default();
+        let ttl = self.delivery_record_ttl(topic_hash);
+        self.deliveries
+            .entry_with_ttl(msg_id.clone(), ttl)
+            .or_default();
 
         if let Some(callback) = self.message_delivery_time_callback {
             if self
@@ -588,7 +618,11 @@ impl PeerScore {
     ) {
         self.mark_first_message_delivery(from, topic_hash);
 
-        let record = self.deliveries.entry(msg_id.clone()).or_default();
+        let ttl = self.delivery_record_ttl(topic_hash);
+        let record = self
+            .deliveries
+            .entry_with_ttl(msg_id.clone(), ttl)
+            .or_default();
 
         // this should be the first delivery trace
         if record.status != DeliveryStatus::Unknown {
@@ -644,7 +678,11 @@ impl PeerScore {
         }
 
         let peers: Vec<_> = {
-            let record = self.deliveries.entry(msg_id.clone()).or_default();
+            let ttl = self.delivery_record_ttl(topic_hash);
+            let record = self
+                .deliveries
+                .entry_with_ttl(msg_id.clone(), ttl)
+                .or_default();
 
             // Multiple peers can now reject the same message as we track which peers send us the
             // message. If we have already updated the status, return.
@@ -678,7 +716,11 @@ impl PeerScore {
         msg_id: &MessageId,
         topic_hash: &TopicHash,
     ) {
-        let record = self.deliveries.entry(msg_id.clone()).or_default();
+        let ttl = self.delivery_record_ttl(topic_hash);
+        let record = self
+            .deliveries
+            .entry_with_ttl(msg_id.clone(), ttl)
+            .or_default();
 
         if record.peers.contains(from) {
             // we have already seen this duplicate!
@@ -892,6 +934,129 @@ impl PeerScore {
             .and_then(|s| s.topics.get(topic))
             .map(|t| t.mesh_message_deliveries)
     }
+
+    /// Captures the scoring state of all tracked peers.
+    pub(crate) fn snapshot(&self) -> PeerScoreSnapshot {
+        let peers = self
+            .peer_stats
+            .iter()
+            .map(|(peer_id, peer_stats)| {
+                let topics = peer_stats
+                    .topics
+                    .iter()
+                    .map(|(topic, topic_stats)| {
+                        let snapshot = TopicScoreSnapshot {
+                            in_mesh: topic_stats.in_mesh(),
+                            first_message_deliveries: topic_stats.first_message_deliveries,
+                            mesh_message_deliveries: topic_stats.mesh_message_deliveries,
+                            mesh_failure_penalty: topic_stats.mesh_failure_penalty,
+                            invalid_message_deliveries: topic_stats.invalid_message_deliveries,
+                        };
+                        (topic.clone(), snapshot)
+                    })
+                    .collect();
+                let snapshot = PeerScoreSnapshotEntry {
+                    score: self.score(peer_id),
+                    connected: matches!(peer_stats.status, ConnectionStatus::Connected),
+                    topics,
+                    known_ips: peer_stats.known_ips.iter().copied().collect(),
+                    behaviour_penalty: peer_stats.behaviour_penalty,
+                    application_score: peer_stats.application_score,
+                };
+                (*peer_id, snapshot)
+            })
+            .collect();
+
+        PeerScoreSnapshot { peers }
+    }
+
+    /// Seeds the scoring state from a previously captured [`PeerScoreSnapshot`].
+    ///
+    /// Peers that are already tracked keep their current state. Restored peers are treated as
+    /// disconnected and are thus subject to [`PeerScoreParams::retain_score`] until they connect.
+    /// Restored mesh membership is dropped, the peers have to GRAFT again.
+    pub(crate) fn restore(&mut self, snapshot: PeerScoreSnapshot) {
+        let expire = Instant::now() + self.params.retain_score;
+
+        for (peer_id, entry) in snapshot.peers {
+            let hash_map::Entry::Vacant(vacant) = self.peer_stats.entry(peer_id) else {
+                continue;
+            };
+
+            let topics = entry
+                .topics
+                .into_iter()
+                .map(|(topic, snapshot)| {
+                    let stats = TopicStats {
+                        mesh_status: MeshStatus::InActive,
+                        first_message_deliveries: snapshot.first_message_deliveries,
+                        mesh_message_deliveries_active: false,
+                        mesh_message_deliveries: snapshot.mesh_message_deliveries,
+                        mesh_failure_penalty: snapshot.mesh_failure_penalty,
+                        invalid_message_deliveries: snapshot.invalid_message_deliveries,
+                    };
+                    (topic, stats)
+                })
+                .collect();
+
+            for ip in entry.known_ips.iter() {
+                self.peer_ips.entry(*ip).or_default().insert(peer_id);
+            }
+
+            vacant.insert(PeerStats {
+                status: ConnectionStatus::Disconnected { expire },
+                topics,
+                known_ips: entry.known_ips.into_iter().collect(),
+                behaviour_penalty: entry.behaviour_penalty,
+                application_score: entry.application_score,
+            });
+        }
+    }
+}
+
+/// The scoring state of all peers tracked by the peer scoring system.
+///
+/// Obtained via [`Behaviour::score_snapshot`](crate::Behaviour::score_snapshot) and restored via
+/// [`Behaviour::restore_score_snapshot`](crate::Behaviour::restore_score_snapshot).
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeerScoreSnapshot {
+    /// The scoring state per peer.
+    pub peers: HashMap<PeerId, PeerScoreSnapshotEntry>,
+}
+
+/// The scoring state of a single peer.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PeerScoreSnapshotEntry {
+    /// The score of the peer at the time the snapshot was taken. Ignored on restore.
+    pub score: f64,
+    /// Whether the peer was connected at the time the snapshot was taken. Ignored on restore.
+    pub connected: bool,
+    /// Per-topic counters.
+    pub topics: HashMap<TopicHash, TopicScoreSnapshot>,
+    /// The IP addresses the peer has been seen with, used for the P6 IP colocation penalty.
+    pub known_ips: Vec<IpAddr>,
+    /// The P7 behavioural penalty counter.
+    pub behaviour_penalty: f64,
+    /// The P5 application specific score.
+    pub application_score: f64,
+}
+
+/// The scoring counters of a single peer in a single topic.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopicScoreSnapshot {
+    /// Whether the peer was in our mesh for the topic. Ignored on restore.
+    pub in_mesh: bool,
+    /// The P2 first message deliveries counter.
+    pub first_message_deliveries: f64,
+    /// The P3 mesh message deliveries counter.
+    pub mesh_message_deliveries: f64,
+    /// The P3b sticky mesh failure penalty.
+    pub mesh_failure_penalty: f64,
+    /// The P4 invalid message deliveries counter.
+    pub invalid_message_deliveries: f64,
 }
 
 /// The reason a Gossipsub message has been rejected.
@@ -976,3 +976,86 @@ fn test_score_retention() {
         "Score should be the application specific score"
     );
 }
+
+#[test]
+fn test_score_snapshot_restore() {
+    let topic = Topic::new("test");
+    let topic_hash = topic.hash();
+    let mut params = PeerScoreParams {
+        app_specific_weight: 1.0,
+        ..Default::default()
+    };
+
+    let topic_params = TopicScoreParams {
+        topic_weight: 1.0,
+        first_message_deliveries_weight: 1.0,
+        first_message_deliveries_decay: 1.0,
+        first_message_deliveries_cap: 2000.0,
+        time_in_mesh_weight: 0.0,
+        ..Default::default()
+    };
+    params.topics.insert(topic_hash.clone(), topic_params);
+
+    let peer_id = PeerId::random();
+
+    let mut peer_score = PeerScore::new(params.clone());
+    peer_score.add_peer(peer_id);
+    peer_score.graft(&peer_id, topic);
+    peer_score.set_application_score(&peer_id, -10.0);
+
+    let messages = 5;
+    for seq in 0..messages {
+        let (id, msg) = make_test_message(seq);
+        peer_score.validate_message(&peer_id, &id, &msg.topic);
+        peer_score.deliver_message(&peer_id, &id, &msg.topic);
+    }
+
+    let snapshot = peer_score.snapshot();
+    let entry = snapshot.peers.get(&peer_id).expect("peer to be tracked");
+    assert!(entry.connected);
+    assert_eq!(entry.score, peer_score.score(&peer_id));
+    assert!(entry.topics[&topic_hash].in_mesh);
+    assert_eq!(
+        entry.topics[&topic_hash].first_message_deliveries,
+        messages as f64
+    );
+
+    let mut restored = PeerScore::new(params);
+    restored.restore(snapshot);
+
+    assert_eq!(restored.score(&peer_id), peer_score.score(&peer_id));
+    let entry = restored.snapshot().peers.remove(&peer_id).unwrap();
+    assert!(!entry.connected, "Restored peers should be disconnected");
+    assert!(!entry.topics[&topic_hash].in_mesh);
+}
+
+#[test]
+fn test_delivery_record_ttl() {
+    let topic_hash = Topic::new("test").hash();
+    let mut params = PeerScoreParams::default();
+    let topic_params = TopicScoreParams {
+        mesh_message_deliveries_window: Duration::from_secs(10),
+        ..Default::default()
+    };
+    params.topics.insert(topic_hash.clone(), topic_params);
+    let mut peer_score = PeerScore::new(params);
+
+    let default_ttl = Duration::from_secs(TIME_CACHE_DURATION);
+    assert_eq!(peer_score.delivery_record_ttl(&topic_hash), default_ttl);
+
+    peer_score.set_delivery_record_ttl(topic_hash.clone(), Some(Duration::from_secs(30)));
+    assert_eq!(
+        peer_score.delivery_record_ttl(&topic_hash),
+        Duration::from_secs(30)
+    );
+
+    // Deliveries are tracked at least for the mesh message deliveries window.
+    peer_score.set_delivery_record_ttl(topic_hash.clone(), Some(Duration::from_secs(5)));
+    assert_eq!(
+        peer_score.delivery_record_ttl(&topic_hash),
+        Duration::from_secs(10)
+    );
+
+    peer_score.set_delivery_record_ttl(topic_hash.clone(), None);
+    assert_eq!(peer_score.delivery_record_ttl(&topic_hash), default_ttl);
+}
@@ -30,7 +30,7 @@ use asynchronous_codec::{Decoder, Encoder, Framed};
 use byteorder::{BigEndian, ByteOrder};
 use bytes::BytesMut;
 use futures::prelude::*;
-use libp2p_core::{InboundUpgrade, OutboundUpgrade, UpgradeInfo};
+use libp2p_core::{InboundUpgrade, OutboundUpgrade, PeerRecord, SignedEnvelope, UpgradeInfo};
 use libp2p_identity::{PeerId, PublicKey};
 use libp2p_swarm::StreamProtocol;
 use quick_protobuf::Writer;
@@ -61,6 +61,8 @@ pub struct ProtocolConfig {
     pub(crate) max_transmit_size: usize,
     /// Determines the level of validation to be done on incoming messages.
     pub(crate) validation_mode: ValidationMode,
+    /// The maximum number of published or forwarded messages queued per connection.
+    pub(crate) max_queued_messages: usize,
 }
 
 impl Default for ProtocolConfig {
@@ -68,6 +70,7 @@ impl Default for ProtocolConfig {
         Self {
             max_transmit_size: 65536,
             validation_mode: ValidationMode::Strict,
+            max_queued_messages: 5000,
             protocol_ids: vec![GOSSIPSUB_1_1_0_PROTOCOL, GOSSIPSUB_1_0_0_PROTOCOL],
         }
     }
@@ -449,16 +452,7 @@ impl Decoder for GossipsubCodec {
                 let peers = prune
                     .peers
                     .into_iter()
-                    .filter_map(|info| {
-                        info.peer_id
-                            .as_ref()
-                            .and_then(|id| PeerId::from_bytes(id).ok())
-                            .map(|peer_id|
-                                    //TODO signedPeerRecord, see https://github.com/libp2p/specs/pull/217
-                                    PeerInfo {
-                                        peer_id: Some(peer_id),
-                                    })
-                    })
+                    .filter_map(decode_peer_info)
                     .collect::<Vec<PeerInfo>>();
 
                 let topic_hash = TopicHash::from_raw(prune.topic_id.unwrap_or_default());
@@ -488,6 +482,7 @@ impl Decoder for GossipsubCodec {
                             SubscriptionAction::Unsubscribe
                         },
                         topic_hash: TopicHash::from_raw(sub.topic_id.unwrap_or_default()),
+                        compression: sub.compression,
                     })
                     .collect(),
                 control_msgs,
@@ -497,6 +492,37 @@ impl Decoder for GossipsubCodec {
     }
 }
 
+/// Decodes a [`PeerInfo`] of a PRUNE control message, verifying its signed peer record.
+///
+/// Returns `None` if neither a valid peer id nor a valid signed peer record is given. Signed peer
+/// records with an invalid signature or belonging to a different peer are discarded.
+pub(crate) fn decode_peer_info(info: proto::PeerInfo) -> Option<PeerInfo> {
+    let peer_id = info.peer_id.and_then(|id| PeerId::from_bytes(&id).ok());
+    let signed_peer_record = info
+        .signed_peer_record
+        .and_then(
+            |bytes| match SignedEnvelope::from_protobuf_encoding(&bytes) {
+                Ok(envelope) => PeerRecord::from_signed_envelope(envelope)
+                    .map_err(|e| tracing::debug!("Invalid signed peer record in PRUNE: {e}"))
+                    .ok(),
+                Err(e) => {
+                    tracing::debug!("Failed to decode signed peer record in PRUNE: {e}");
+                    None
+                }
+            },
+        )
+        .filter(|record| peer_id.map_or(true, |id| id == record.peer_id()));
+
+    if peer_id.is_none() && signed_peer_record.is_none() {
+        return None;
+    }
+
+    Some(PeerInfo {
+        peer_id,
+        signed_peer_record,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
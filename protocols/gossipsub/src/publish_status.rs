@@ -0,0 +1,164 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Tracking of the propagation of recently published messages.
+
+use crate::{MessageId, TopicHash};
+use instant::Instant;
+use libp2p_identity::PeerId;
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The propagation status of a message published by the local node.
+///
+/// Peers echoing the message back, e.g. as part of their mesh forwarding, are evidence that the
+/// message propagated through the network.
+#[derive(Debug, Clone)]
+pub struct PublishStatus {
+    topic: TopicHash,
+    published_at: Instant,
+    recipients: HashSet<PeerId>,
+    sent_to: HashSet<PeerId>,
+    iwant_served: HashSet<PeerId>,
+    echoed_by: HashSet<PeerId>,
+}
+
+impl PublishStatus {
+    /// The topic the message was published to.
+    pub fn topic(&self) -> &TopicHash {
+        &self.topic
+    }
+
+    /// The time the message was published at.
+    pub fn published_at(&self) -> Instant {
+        self.published_at
+    }
+
+    /// The peers the message was queued for when publishing.
+    pub fn recipients(&self) -> &HashSet<PeerId> {
+        &self.recipients
+    }
+
+    /// The peers the message was actually sent to.
+    pub fn sent_to(&self) -> &HashSet<PeerId> {
+        &self.sent_to
+    }
+
+    /// The peers that requested the message via IWANT and were served.
+    pub fn iwant_served(&self) -> &HashSet<PeerId> {
+        &self.iwant_served
+    }
+
+    /// The peers the message was received from after publishing it.
+    pub fn echoed_by(&self) -> &HashSet<PeerId> {
+        &self.echoed_by
+    }
+}
+
+/// Keeps the [`PublishStatus`] of a bounded number of recently published messages.
+pub(crate) struct PublishStatusTracker {
+    capacity: usize,
+    statuses: HashMap<MessageId, PublishStatus>,
+    /// Message ids in the order they were published, used to evict the oldest status.
+    order: VecDeque<MessageId>,
+}
+
+impl PublishStatusTracker {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            statuses: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Starts tracking a published message.
+    pub(crate) fn published(
+        &mut self,
+        message_id: MessageId,
+        topic: TopicHash,
+        recipients: HashSet<PeerId>,
+    ) {
+        if self.capacity == 0 || self.statuses.contains_key(&message_id) {
+            return;
+        }
+        while self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.statuses.remove(&oldest);
+            }
+        }
+        self.order.push_back(message_id.clone());
+        self.statuses.insert(
+            message_id,
+            PublishStatus {
+                topic,
+                published_at: Instant::now(),
+                recipients,
+                sent_to: HashSet::new(),
+                iwant_served: HashSet::new(),
+                echoed_by: HashSet::new(),
+            },
+        );
+    }
+
+    pub(crate) fn get(&self, message_id: &MessageId) -> Option<&PublishStatus> {
+        self.statuses.get(message_id)
+    }
+
+    /// Records that a published message has been sent to `peer`.
+    pub(crate) fn sent(&mut self, message_id: &MessageId, peer: PeerId) {
+        if let Some(status) = self.statuses.get_mut(message_id) {
+            status.sent_to.insert(peer);
+        }
+    }
+
+    /// Records that a published message has been served to `peer` in response to an IWANT.
+    pub(crate) fn iwant_served(&mut self, message_id: &MessageId, peer: PeerId) {
+        if let Some(status) = self.statuses.get_mut(message_id) {
+            status.iwant_served.insert(peer);
+        }
+    }
+
+    /// Records that a published message has been received from `peer`.
+    pub(crate) fn echoed(&mut self, message_id: &MessageId, peer: PeerId) {
+        if let Some(status) = self.statuses.get_mut(message_id) {
+            status.echoed_by.insert(peer);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evicts_oldest_status() {
+        let mut tracker = PublishStatusTracker::new(2);
+        let topic = TopicHash::from_raw("topic");
+        let ids: Vec<_> = (0..3u8).map(|i| MessageId::new(&[i])).collect();
+
+        for id in &ids {
+            tracker.published(id.clone(), topic.clone(), HashSet::new());
+        }
+
+        assert!(tracker.get(&ids[0]).is_none());
+        assert!(tracker.get(&ids[1]).is_some());
+        assert!(tracker.get(&ids[2]).is_some());
+    }
+}
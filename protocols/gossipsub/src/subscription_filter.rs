@@ -225,22 +225,27 @@ mod test {
             Subscription {
                 action: Unsubscribe,
                 topic_hash: t1.clone(),
+                compression: None,
             },
             Subscription {
                 action: Unsubscribe,
                 topic_hash: t2.clone(),
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t2,
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t1.clone(),
+                compression: None,
             },
             Subscription {
                 action: Unsubscribe,
                 topic_hash: t1,
+                compression: None,
             },
         ];
 
@@ -262,10 +267,12 @@ mod test {
             Subscription {
                 action: Subscribe,
                 topic_hash: t1,
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t2,
+                compression: None,
             },
         ];
 
@@ -291,14 +298,17 @@ mod test {
             Subscription {
                 action: Subscribe,
                 topic_hash: t1.clone(),
+                compression: None,
             },
             Subscription {
                 action: Unsubscribe,
                 topic_hash: t1.clone(),
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t1,
+                compression: None,
             },
         ];
 
@@ -324,10 +334,12 @@ mod test {
             Subscription {
                 action: Subscribe,
                 topic_hash: t[2].clone(),
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t[3].clone(),
+                compression: None,
             },
         ];
 
@@ -353,22 +365,27 @@ mod test {
             Subscription {
                 action: Subscribe,
                 topic_hash: t[4].clone(),
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t[2].clone(),
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t[3].clone(),
+                compression: None,
             },
             Subscription {
                 action: Unsubscribe,
                 topic_hash: t[0].clone(),
+                compression: None,
             },
             Subscription {
                 action: Unsubscribe,
                 topic_hash: t[1].clone(),
+                compression: None,
             },
         ];
 
@@ -390,10 +407,12 @@ mod test {
             Subscription {
                 action: Subscribe,
                 topic_hash: t1,
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t2,
+                compression: None,
             },
         ];
 
@@ -416,14 +435,17 @@ mod test {
             Subscription {
                 action: Subscribe,
                 topic_hash: t1,
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t2,
+                compression: None,
             },
             Subscription {
                 action: Subscribe,
                 topic_hash: t3,
+                compression: None,
             },
         ];
 
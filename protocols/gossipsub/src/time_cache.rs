@@ -20,13 +20,12 @@
 
 //! This implements a time-based LRU cache for checking gossipsub message duplicates.
 
-use fnv::FnvHashMap;
 use instant::Instant;
 use std::collections::hash_map::{
     self,
     Entry::{Occupied, Vacant},
 };
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::time::Duration;
 
 struct ExpiringElement<Element> {
@@ -39,7 +38,7 @@ struct ExpiringElement<Element> {
 pub(crate) struct TimeCache<Key, Value> {
     /// Mapping a key to its value together with its latest expire time (can be updated through
     /// reinserts).
-    map: FnvHashMap<Key, ExpiringElement<Value>>,
+    map: HashMap<Key, ExpiringElement<Value>>,
     /// An ordered list of keys by expires time.
     list: VecDeque<ExpiringElement<Key>>,
     /// The time elements remain in the cache.
@@ -110,7 +109,7 @@ where
 {
     pub(crate) fn new(ttl: Duration) -> Self {
         TimeCache {
-            map: FnvHashMap::default(),
+            map: HashMap::default(),
             list: VecDeque::new(),
             ttl,
         }
@@ -131,12 +130,24 @@ where
     }
 
     pub(crate) fn entry(&mut self, key: Key) -> Entry<Key, Value> {
+        self.entry_with_ttl(key, self.ttl)
+    }
+
+    /// Like [`TimeCache::entry`], but a vacant entry expires after `ttl` instead of the
+    /// default time of the cache.
+    ///
+    /// Entries with different times to live may be removed later than they expire,
+    /// but expired entries are never returned.
+    pub(crate) fn entry_with_ttl(&mut self, key: Key, ttl: Duration) -> Entry<Key, Value> {
         let now = Instant::now();
         self.remove_expired_keys(now);
+        if self.map.get(&key).map_or(false, |e| e.expires <= now) {
+            self.map.remove(&key);
+        }
         match self.map.entry(key) {
             Occupied(entry) => Entry::Occupied(OccupiedEntry { entry }),
             Vacant(entry) => Entry::Vacant(VacantEntry {
-                expiration: now + self.ttl,
+                expiration: now + ttl,
                 entry,
                 list: &mut self.list,
             }),
@@ -151,7 +162,9 @@ where
     }
 
     pub(crate) fn contains_key(&self, key: &Key) -> bool {
-        self.map.contains_key(key)
+        self.map
+            .get(key)
+            .map_or(false, |e| e.expires > Instant::now())
     }
 }
 
@@ -178,6 +191,16 @@ where
         }
     }
 
+    // Like `insert`, but the key expires after `ttl` instead of the default time of the cache.
+    pub(crate) fn insert_with_ttl(&mut self, key: Key, ttl: Duration) -> bool {
+        if let Entry::Vacant(entry) = self.0.entry_with_ttl(key, ttl) {
+            entry.insert(());
+            true
+        } else {
+            false
+        }
+    }
+
     pub(crate) fn contains(&self, key: &Key) -> bool {
         self.0.contains_key(key)
     }
@@ -216,4 +239,20 @@ mod test {
         // should be removed from the cache
         assert!(cache.insert("t"));
     }
+
+    #[test]
+    fn cache_entries_expire_after_own_ttl() {
+        let mut cache = DuplicateCache::new(Duration::from_secs(10));
+
+        cache.insert("t");
+        cache.insert_with_ttl("e", Duration::from_millis(100));
+        assert!(cache.contains(&"e"));
+
+        // sleep until expiry of 'e', which is queued after the longer living 't'
+        std::thread::sleep(Duration::from_millis(101));
+
+        assert!(!cache.contains(&"e"));
+        assert!(cache.insert_with_ttl("e", Duration::from_millis(100)));
+        assert!(!cache.insert("t"));
+    }
 }
@@ -65,6 +65,7 @@ impl Hasher for Sha256Hash {
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, EncodeLabelSet)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TopicHash {
     /// The topic hash. Stored as a string to align with the protobuf API.
     hash: String,
@@ -116,6 +117,51 @@ impl<H: Hasher> fmt::Display for Topic<H> {
     }
 }
 
+/// A pattern matching hierarchical topic names, whose segments are separated by `/`.
+///
+/// A `*` segment matches any single segment, e.g. `app/*/blocks` matches `app/eu/blocks`, and a
+/// trailing `**` segment matches one or more segments, e.g. `app/**` matches `app/eu/blocks`.
+/// Patterns are matched against the raw topic names of [`IdentTopic`](crate::IdentTopic)s, as
+/// hashed topic names can't be matched.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TopicPattern {
+    pattern: String,
+}
+
+impl TopicPattern {
+    pub fn new(pattern: impl Into<String>) -> Self {
+        TopicPattern {
+            pattern: pattern.into(),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Returns whether the topic with the given [`TopicHash`] matches the pattern.
+    pub fn matches(&self, topic: &TopicHash) -> bool {
+        let mut segments = topic.as_str().split('/');
+        let mut pattern = self.pattern.split('/').peekable();
+        while let Some(expected) = pattern.next() {
+            if expected == "**" && pattern.peek().is_none() {
+                return segments.next().is_some();
+            }
+            match segments.next() {
+                Some(segment) if expected == "*" || expected == segment => {}
+                _ => return false,
+            }
+        }
+        segments.next().is_none()
+    }
+}
+
+impl fmt::Display for TopicPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
 impl fmt::Display for TopicHash {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.hash)
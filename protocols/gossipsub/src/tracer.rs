@@ -0,0 +1,130 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Hooks for exporting per-message events to external tracing systems.
+//!
+//! The events of a [`Tracer`] registered through [`crate::Behaviour::set_tracer`] mirror the
+//! message events of the go-libp2p tracer schema (`PUBLISH_MESSAGE`, `REJECT_MESSAGE`,
+//! `DUPLICATE_MESSAGE` and `DELIVER_MESSAGE`), including its reject reasons, so that traces of
+//! networks running mixed implementations can be aggregated in a single pipeline.
+
+use crate::error::ValidationError;
+use crate::peer_score::RejectReason;
+use crate::topic::TopicHash;
+use crate::types::MessageId;
+use libp2p_identity::PeerId;
+
+/// Receives a [`TraceEvent`] for every message handled by the behaviour.
+///
+/// Implemented for closures of the form `FnMut(TraceEvent<'_>)`.
+pub trait Tracer: Send + 'static {
+    /// Called synchronously while the behaviour handles a message.
+    fn trace(&mut self, event: TraceEvent<'_>);
+}
+
+impl<F> Tracer for F
+where
+    F: FnMut(TraceEvent<'_>) + Send + 'static,
+{
+    fn trace(&mut self, event: TraceEvent<'_>) {
+        self(event)
+    }
+}
+
+/// An event reported to a [`Tracer`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+#[allow(clippy::enum_variant_names)] // Named after the events of the go-libp2p tracer.
+pub enum TraceEvent<'a> {
+    /// We published a message.
+    PublishMessage {
+        message_id: &'a MessageId,
+        topic: &'a TopicHash,
+    },
+    /// A message was received for the first time and awaits validation.
+    ReceiveMessage {
+        message_id: &'a MessageId,
+        topic: &'a TopicHash,
+        received_from: &'a PeerId,
+    },
+    /// A message was received that has been seen before.
+    DuplicateMessage {
+        message_id: &'a MessageId,
+        topic: &'a TopicHash,
+        received_from: &'a PeerId,
+    },
+    /// A received message passed validation.
+    DeliverMessage {
+        message_id: &'a MessageId,
+        topic: &'a TopicHash,
+        received_from: &'a PeerId,
+    },
+    /// A received message was rejected or ignored.
+    RejectMessage {
+        /// The ID of the message, `None` if it could not be decoded.
+        message_id: Option<&'a MessageId>,
+        topic: &'a TopicHash,
+        received_from: &'a PeerId,
+        /// The reason as named by the go-libp2p tracer, e.g. `"validation failed"`.
+        reason: &'static str,
+    },
+    /// A validated message was forwarded to `peers` peers.
+    ForwardMessage {
+        message_id: &'a MessageId,
+        topic: &'a TopicHash,
+        peers: usize,
+    },
+}
+
+impl TraceEvent<'_> {
+    /// The type of the event as named by the go-libp2p tracer schema.
+    ///
+    /// [`TraceEvent::ReceiveMessage`] and [`TraceEvent::ForwardMessage`] have no counterpart in
+    /// the schema and are named `RECEIVE_MESSAGE` and `FORWARD_MESSAGE`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TraceEvent::PublishMessage { .. } => "PUBLISH_MESSAGE",
+            TraceEvent::ReceiveMessage { .. } => "RECEIVE_MESSAGE",
+            TraceEvent::DuplicateMessage { .. } => "DUPLICATE_MESSAGE",
+            TraceEvent::DeliverMessage { .. } => "DELIVER_MESSAGE",
+            TraceEvent::RejectMessage { .. } => "REJECT_MESSAGE",
+            TraceEvent::ForwardMessage { .. } => "FORWARD_MESSAGE",
+        }
+    }
+}
+
+/// The go-libp2p tracer name of a [`RejectReason`].
+pub(crate) fn reject_reason(reason: RejectReason) -> &'static str {
+    match reason {
+        RejectReason::ValidationError(ValidationError::InvalidSignature) => "invalid signature",
+        RejectReason::ValidationError(
+            ValidationError::SignaturePresent
+            | ValidationError::SequenceNumberPresent
+            | ValidationError::MessageSourcePresent,
+        ) => "unexpected auth info",
+        RejectReason::ValidationError(ValidationError::TransformFailed) => "transform failed",
+        RejectReason::ValidationError(_) => "invalid message",
+        RejectReason::SelfOrigin => "self originated message",
+        RejectReason::BlackListedPeer => "blacklisted peer",
+        RejectReason::BlackListedSource => "blacklisted source",
+        RejectReason::ValidationIgnored => "validation ignored",
+        RejectReason::ValidationFailed => "validation failed",
+    }
+}
@@ -20,12 +20,14 @@
 
 //! A collection of types using the Gossipsub system.
 use crate::TopicHash;
+use libp2p_core::PeerRecord;
 use libp2p_identity::PeerId;
 use libp2p_swarm::ConnectionId;
 use prometheus_client::encoding::EncodeLabelValue;
 use quick_protobuf::MessageWrite;
 use std::fmt;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 
 use crate::rpc_proto::proto;
 #[cfg(feature = "serde")]
@@ -81,6 +83,7 @@ pub(crate) struct PeerConnections {
 
 /// Describes the types of peers that can exist in the gossipsub context.
 #[derive(Debug, Clone, PartialEq, Hash, EncodeLabelValue, Eq)]
+#[non_exhaustive]
 pub enum PeerKind {
     /// A gossipsub 1.1 peer.
     Gossipsubv1_1,
@@ -183,6 +186,8 @@ pub struct Subscription {
     pub action: SubscriptionAction,
     /// The topic from which to subscribe or unsubscribe.
     pub topic_hash: TopicHash,
+    /// The algorithm the peer compresses message data of the topic with, if any.
+    pub(crate) compression: Option<u32>,
 }
 
 /// Action that a subscription wants to perform.
@@ -194,12 +199,49 @@ pub enum SubscriptionAction {
     Unsubscribe,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// A peer exchanged in a PRUNE control message.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PeerInfo {
     pub peer_id: Option<PeerId>,
-    //TODO add this when RFC: Signed Address Records got added to the spec (see pull request
-    // https://github.com/libp2p/specs/pull/217)
-    //pub signed_peer_record: ?,
+    /// The verified signed peer record of the peer, carrying its addresses.
+    pub(crate) signed_peer_record: Option<PeerRecord>,
+}
+
+impl PeerInfo {
+    /// Creates a [`PeerInfo`] without a signed peer record.
+    pub fn new(peer_id: Option<PeerId>) -> Self {
+        PeerInfo {
+            peer_id,
+            signed_peer_record: None,
+        }
+    }
+
+    /// Attaches the verified signed peer record of the peer, carrying its addresses.
+    pub fn with_signed_peer_record(mut self, record: PeerRecord) -> Self {
+        self.signed_peer_record = Some(record);
+        self
+    }
+
+    /// The verified signed peer record of the peer, carrying its addresses.
+    pub fn signed_peer_record(&self) -> Option<&PeerRecord> {
+        self.signed_peer_record.as_ref()
+    }
+
+    /// The id of the peer, taken from the signed peer record if no explicit id is given.
+    pub fn peer_id(&self) -> Option<PeerId> {
+        self.peer_id
+            .or_else(|| self.signed_peer_record.as_ref().map(|r| r.peer_id()))
+    }
+}
+
+impl Hash for PeerInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.peer_id.hash(state);
+        self.signed_peer_record
+            .as_ref()
+            .map(|r| (r.peer_id(), r.seq()))
+            .hash(state);
+    }
 }
 
 /// A Control message received by the gossipsub system.
@@ -237,13 +279,24 @@ pub enum ControlAction {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum RpcOut {
     /// Publish a Gossipsub message on network.
-    Publish(RawMessage),
+    Publish {
+        message: RawMessage,
+        message_id: MessageId,
+    },
     /// Forward a Gossipsub message to the network.
-    Forward(RawMessage),
-    /// Subscribe a topic.
-    Subscribe(TopicHash),
+    Forward {
+        message: RawMessage,
+        message_id: MessageId,
+    },
+    /// Subscribe a topic, advertising the compression algorithm used for it.
+    Subscribe {
+        topic: TopicHash,
+        compression: Option<u32>,
+    },
     /// Unsubscribe a topic.
     Unsubscribe(TopicHash),
+    /// Subscribe and unsubscribe several topics at once.
+    Subscriptions(Vec<Subscription>),
     /// List of Gossipsub control messages.
     Control(ControlAction),
 }
@@ -260,21 +313,22 @@ impl From<RpcOut> for proto::RPC {
     /// Converts the RPC into protobuf format.
     fn from(rpc: RpcOut) -> Self {
         match rpc {
-            RpcOut::Publish(message) => proto::RPC {
+            RpcOut::Publish { message, .. } => proto::RPC {
                 subscriptions: Vec::new(),
                 publish: vec![message.into()],
                 control: None,
             },
-            RpcOut::Forward(message) => proto::RPC {
+            RpcOut::Forward { message, .. } => proto::RPC {
                 publish: vec![message.into()],
                 subscriptions: Vec::new(),
                 control: None,
             },
-            RpcOut::Subscribe(topic) => proto::RPC {
+            RpcOut::Subscribe { topic, compression } => proto::RPC {
                 publish: Vec::new(),
                 subscriptions: vec![proto::SubOpts {
                     subscribe: Some(true),
                     topic_id: Some(topic.into_string()),
+                    compression,
                 }],
                 control: None,
             },
@@ -283,9 +337,22 @@ impl From<RpcOut> for proto::RPC {
                 subscriptions: vec![proto::SubOpts {
                     subscribe: Some(false),
                     topic_id: Some(topic.into_string()),
+                    compression: None,
                 }],
                 control: None,
             },
+            RpcOut::Subscriptions(subscriptions) => proto::RPC {
+                publish: Vec::new(),
+                subscriptions: subscriptions
+                    .into_iter()
+                    .map(|s| proto::SubOpts {
+                        subscribe: Some(s.action == SubscriptionAction::Subscribe),
+                        topic_id: Some(s.topic_hash.into_string()),
+                        compression: s.compression,
+                    })
+                    .collect(),
+                control: None,
+            },
             RpcOut::Control(ControlAction::IHave {
                 topic_hash,
                 message_ids,
@@ -330,29 +397,28 @@ impl From<RpcOut> for proto::RPC {
                 topic_hash,
                 peers,
                 backoff,
-            }) => {
-                proto::RPC {
-                    publish: Vec::new(),
-                    subscriptions: vec![],
-                    control: Some(proto::ControlMessage {
-                        ihave: vec![],
-                        iwant: vec![],
-                        graft: vec![],
-                        prune: vec![proto::ControlPrune {
-                            topic_id: Some(topic_hash.into_string()),
-                            peers: peers
-                                .into_iter()
-                                .map(|info| proto::PeerInfo {
-                                    peer_id: info.peer_id.map(|id| id.to_bytes()),
-                                    // TODO, see https://github.com/libp2p/specs/pull/217
-                                    signed_peer_record: None,
-                                })
-                                .collect(),
-                            backoff,
-                        }],
-                    }),
-                }
-            }
+            }) => proto::RPC {
+                publish: Vec::new(),
+                subscriptions: vec![],
+                control: Some(proto::ControlMessage {
+                    ihave: vec![],
+                    iwant: vec![],
+                    graft: vec![],
+                    prune: vec![proto::ControlPrune {
+                        topic_id: Some(topic_hash.into_string()),
+                        peers: peers
+                            .into_iter()
+                            .map(|info| proto::PeerInfo {
+                                peer_id: info.peer_id.map(|id| id.to_bytes()),
+                                signed_peer_record: info.signed_peer_record.map(|record| {
+                                    record.into_signed_envelope().into_protobuf_encoding()
+                                }),
+                            })
+                            .collect(),
+                        backoff,
+                    }],
+                }),
+            },
         }
     }
 }
@@ -402,6 +468,7 @@ impl From<Rpc> for proto::RPC {
             .map(|sub| proto::SubOpts {
                 subscribe: Some(sub.action == SubscriptionAction::Subscribe),
                 topic_id: Some(sub.topic_hash.into_string()),
+                compression: sub.compression,
             })
             .collect::<Vec<_>>();
 
@@ -451,8 +518,9 @@ impl From<Rpc> for proto::RPC {
                             .into_iter()
                             .map(|info| proto::PeerInfo {
                                 peer_id: info.peer_id.map(|id| id.to_bytes()),
-                                // TODO, see https://github.com/libp2p/specs/pull/217
-                                signed_peer_record: None,
+                                signed_peer_record: info.signed_peer_record.map(|record| {
+                                    record.into_signed_envelope().into_protobuf_encoding()
+                                }),
                             })
                             .collect(),
                         backoff,
@@ -0,0 +1,193 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Asynchronous validation of received messages.
+//!
+//! A [`MessageValidator`] registered for a topic through [`crate::Behaviour::register_validator`]
+//! is run by the behaviour for every message received on that topic. The behaviour bounds the
+//! number of concurrent validations by [`crate::Config::max_concurrent_validations`] and of queued
+//! ones by [`crate::Config::max_queued_validations`], ignores messages whose validation exceeds
+//! [`crate::Config::validation_timeout`] and reports each outcome through
+//! [`crate::Behaviour::report_message_validation_result`].
+
+use crate::topic::TopicHash;
+use crate::types::{Message, MessageAcceptance, MessageId};
+use futures::future::{self, BoxFuture, Either};
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::{Future, FutureExt};
+use futures_timer::Delay;
+use libp2p_identity::PeerId;
+use std::collections::{HashMap, VecDeque};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Validates messages received on a topic.
+///
+/// Implemented for closures of the form
+/// `FnMut(&PeerId, &Message) -> impl Future<Output = MessageAcceptance>`.
+pub trait MessageValidator: Send + 'static {
+    /// Starts validating `message`, received from `propagation_source`.
+    fn validate(
+        &mut self,
+        propagation_source: &PeerId,
+        message: &Message,
+    ) -> BoxFuture<'static, MessageAcceptance>;
+}
+
+impl<F, Fut> MessageValidator for F
+where
+    F: FnMut(&PeerId, &Message) -> Fut + Send + 'static,
+    Fut: Future<Output = MessageAcceptance> + Send + 'static,
+{
+    fn validate(
+        &mut self,
+        propagation_source: &PeerId,
+        message: &Message,
+    ) -> BoxFuture<'static, MessageAcceptance> {
+        self(propagation_source, message).boxed()
+    }
+}
+
+/// A message waiting for a validation slot.
+struct PendingValidation {
+    message_id: MessageId,
+    propagation_source: PeerId,
+    message: Message,
+}
+
+/// The result of running a message through the [`ValidationPipeline`].
+pub(crate) struct ValidationOutcome {
+    pub(crate) message_id: MessageId,
+    pub(crate) propagation_source: PeerId,
+    pub(crate) message: Message,
+    /// The verdict of the validator, or `None` if the validator of the topic was removed before
+    /// the validation started.
+    pub(crate) acceptance: Option<MessageAcceptance>,
+}
+
+/// Drives the registered [`MessageValidator`]s with bounded concurrency.
+pub(crate) struct ValidationPipeline {
+    validators: HashMap<TopicHash, Box<dyn MessageValidator>>,
+    queued: VecDeque<PendingValidation>,
+    in_flight: FuturesUnordered<BoxFuture<'static, ValidationOutcome>>,
+    max_concurrent: usize,
+    max_queued: usize,
+    timeout: Duration,
+}
+
+impl ValidationPipeline {
+    pub(crate) fn new(max_concurrent: usize, max_queued: usize, timeout: Duration) -> Self {
+        Self {
+            validators: HashMap::new(),
+            queued: VecDeque::new(),
+            in_flight: FuturesUnordered::new(),
+            max_concurrent: max_concurrent.max(1),
+            max_queued,
+            timeout,
+        }
+    }
+
+    /// Sets the validator of `topic`, returning the previous one if any.
+    pub(crate) fn register(
+        &mut self,
+        topic: TopicHash,
+        validator: Box<dyn MessageValidator>,
+    ) -> Option<Box<dyn MessageValidator>> {
+        self.validators.insert(topic, validator)
+    }
+
+    /// Removes the validator of `topic`, returning whether there was one.
+    pub(crate) fn unregister(&mut self, topic: &TopicHash) -> bool {
+        self.validators.remove(topic).is_some()
+    }
+
+    pub(crate) fn has_validator(&self, topic: &TopicHash) -> bool {
+        self.validators.contains_key(topic)
+    }
+
+    /// Returns whether no further messages can be queued for validation.
+    pub(crate) fn is_full(&self) -> bool {
+        self.queued.len() >= self.max_queued
+    }
+
+    /// Queues `message` for validation by the validator of its topic.
+    ///
+    /// Callers are expected to check [`ValidationPipeline::is_full`] first.
+    pub(crate) fn push(
+        &mut self,
+        message_id: MessageId,
+        propagation_source: PeerId,
+        message: Message,
+    ) {
+        self.queued.push_back(PendingValidation {
+            message_id,
+            propagation_source,
+            message,
+        });
+    }
+
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>) -> Poll<ValidationOutcome> {
+        while self.in_flight.len() < self.max_concurrent {
+            let Some(pending) = self.queued.pop_front() else {
+                break;
+            };
+            let PendingValidation {
+                message_id,
+                propagation_source,
+                message,
+            } = pending;
+
+            let Some(validator) = self.validators.get_mut(&message.topic) else {
+                return Poll::Ready(ValidationOutcome {
+                    message_id,
+                    propagation_source,
+                    message,
+                    acceptance: None,
+                });
+            };
+
+            let validation = validator.validate(&propagation_source, &message);
+            let timeout = Delay::new(self.timeout);
+            self.in_flight.push(
+                async move {
+                    let acceptance = match future::select(validation, timeout).await {
+                        Either::Left((acceptance, _)) => acceptance,
+                        Either::Right(_) => {
+                            tracing::debug!(message=%message_id, "Message validation timed out");
+                            MessageAcceptance::Ignore
+                        }
+                    };
+                    ValidationOutcome {
+                        message_id,
+                        propagation_source,
+                        message,
+                        acceptance: Some(acceptance),
+                    }
+                }
+                .boxed(),
+            );
+        }
+
+        match self.in_flight.poll_next_unpin(cx) {
+            Poll::Ready(Some(outcome)) => Poll::Ready(outcome),
+            _ => Poll::Pending,
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+#![cfg(feature = "kad")]
+
+use libp2p_gossipsub::kad_bootstrap::{topic_provider_key, TopicBootstrap};
+use libp2p_gossipsub::IdentTopic;
+use libp2p_identity::{Keypair, PeerId};
+use libp2p_kad::{store::MemoryStore, Event, Mode, QueryResult};
+use libp2p_swarm::{Swarm, SwarmEvent};
+use libp2p_swarm_test::SwarmExt as _;
+
+#[test]
+fn discovers_announced_topic_peers() {
+    async_std::task::block_on(async {
+        let topic = IdentTopic::new("test").hash();
+
+        let mut subscriber = Swarm::new_ephemeral(new_kad);
+        subscriber.behaviour_mut().set_mode(Some(Mode::Server));
+        let (subscriber_addr, _) = subscriber.listen().await;
+        let subscriber_peer_id = *subscriber.local_peer_id();
+
+        let mut subscriber_bootstrap = TopicBootstrap::new(subscriber_peer_id);
+        let discovery_id = subscriber_bootstrap
+            .announce(subscriber.behaviour_mut(), &topic)
+            .unwrap();
+
+        // The subscriber does not report itself as a subscriber of the topic.
+        let mut discovered = Vec::new();
+        let (mut announced, mut discovery_finished) = (false, false);
+        while !(announced && discovery_finished) {
+            if let SwarmEvent::Behaviour(event) = subscriber.next_swarm_event().await {
+                if let Event::OutboundQueryProgressed {
+                    id, result, step, ..
+                } = &event
+                {
+                    announced |= matches!(result, QueryResult::StartProviding(Ok(_)));
+                    discovery_finished |= *id == discovery_id && step.last;
+                }
+                discovered.extend(subscriber_bootstrap.on_kad_event(&event));
+            }
+        }
+        assert!(discovered.is_empty());
+        async_std::task::spawn(subscriber.loop_on_next());
+
+        let mut node = Swarm::new_ephemeral(new_kad);
+        node.behaviour_mut()
+            .add_address(&subscriber_peer_id, subscriber_addr);
+
+        let mut bootstrap = TopicBootstrap::new(*node.local_peer_id());
+        let query_id = bootstrap.discover(node.behaviour_mut(), &topic);
+
+        loop {
+            let event = node.next_behaviour_event().await;
+            if bootstrap
+                .on_kad_event(&event)
+                .contains(&(topic.clone(), subscriber_peer_id))
+            {
+                break;
+            }
+            if let Event::OutboundQueryProgressed { id, step, .. } = event {
+                assert!(
+                    id != query_id || !step.last,
+                    "query finished without discovering the subscriber"
+                );
+            }
+        }
+    });
+}
+
+#[test]
+fn ignores_other_queries() {
+    async_std::task::block_on(async {
+        let mut node = Swarm::new_ephemeral(new_kad);
+        let key = topic_provider_key(&IdentTopic::new("test").hash());
+        node.behaviour_mut().start_providing(key.clone()).unwrap();
+        node.behaviour_mut().get_providers(key);
+
+        let mut bootstrap = TopicBootstrap::new(PeerId::random());
+        loop {
+            let event = node.next_behaviour_event().await;
+            assert!(bootstrap.on_kad_event(&event).is_empty());
+            if let Event::OutboundQueryProgressed {
+                result: QueryResult::GetProviders(_),
+                step,
+                ..
+            } = event
+            {
+                if step.last {
+                    break;
+                }
+            }
+        }
+    });
+}
+
+fn new_kad(key: Keypair) -> libp2p_kad::Behaviour<MemoryStore> {
+    let peer_id = key.public().to_peer_id();
+    libp2p_kad::Behaviour::new(peer_id, MemoryStore::new(peer_id))
+}
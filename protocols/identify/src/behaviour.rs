@@ -59,6 +59,10 @@ pub struct Behaviour {
 
     listen_addresses: ListenAddresses,
     external_addresses: ExternalAddresses,
+
+    /// For each connected peer, the peers we asked it for address hints and
+    /// haven't received an answer for yet.
+    outstanding_address_hints: HashMap<PeerId, HashSet<PeerId>>,
 }
 
 /// Configuration for the [`identify::Behaviour`](Behaviour).
@@ -97,6 +101,11 @@ pub struct Config {
     ///
     /// Disabled by default.
     pub cache_size: usize,
+
+    /// Whether and how to answer address hint requests of remote peers.
+    ///
+    /// Disabled by default, see [`Config::with_address_hints`].
+    address_hints: Option<AddressHintsConfig>,
 }
 
 impl Config {
@@ -110,6 +119,7 @@ impl Config {
             interval: Duration::from_secs(5 * 60),
             push_listen_addr_updates: false,
             cache_size: 100,
+            address_hints: None,
         }
     }
 
@@ -139,6 +149,69 @@ impl Config {
         self.cache_size = cache_size;
         self
     }
+
+    /// Enables answering address hint requests of remote peers with the
+    /// addresses held in the cache of discovered peers.
+    ///
+    /// See [`Behaviour::request_address_hints`].
+    pub fn with_address_hints(mut self, config: AddressHintsConfig) -> Self {
+        self.address_hints = Some(config);
+        self
+    }
+}
+
+/// Configuration for answering and accepting address hints.
+///
+/// Address hints let a node ask a connected peer for the addresses it knows of
+/// third parties. They are exchanged on a separate protocol, see
+/// [`ADDRESS_HINTS_PROTOCOL_NAME`](crate::ADDRESS_HINTS_PROTOCOL_NAME), and answered
+/// from the cache of discovered peers, see [`Config::with_cache_size`].
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct AddressHintsConfig {
+    /// The maximum number of peers to request, answer or accept addresses for in a
+    /// single message.
+    ///
+    /// Defaults to 8.
+    pub max_peers: usize,
+    /// The maximum number of addresses to share or accept per peer.
+    ///
+    /// Defaults to 4.
+    pub max_addresses_per_peer: usize,
+    /// Whether to share loopback, private and link-local addresses of peers.
+    ///
+    /// Disabled by default.
+    pub share_private_addresses: bool,
+}
+
+impl Default for AddressHintsConfig {
+    fn default() -> Self {
+        Self {
+            max_peers: 8,
+            max_addresses_per_peer: 4,
+            share_private_addresses: false,
+        }
+    }
+}
+
+impl AddressHintsConfig {
+    /// Configures the maximum number of peers per message.
+    pub fn with_max_peers(mut self, max_peers: usize) -> Self {
+        self.max_peers = max_peers;
+        self
+    }
+
+    /// Configures the maximum number of addresses per peer.
+    pub fn with_max_addresses_per_peer(mut self, max_addresses_per_peer: usize) -> Self {
+        self.max_addresses_per_peer = max_addresses_per_peer;
+        self
+    }
+
+    /// Configures whether loopback, private and link-local addresses are shared.
+    pub fn with_share_private_addresses(mut self, b: bool) -> Self {
+        self.share_private_addresses = b;
+        self
+    }
 }
 
 impl Behaviour {
@@ -157,6 +230,7 @@ impl Behaviour {
             discovered_peers,
             listen_addresses: Default::default(),
             external_addresses: Default::default(),
+            outstanding_address_hints: Default::default(),
         }
     }
 
@@ -179,6 +253,124 @@ impl Behaviour {
         }
     }
 
+    /// Asks the given connected peer for the addresses it knows of `peers`.
+    ///
+    /// The request is sent once the peer has been identified, and only if it
+    /// supports [`ADDRESS_HINTS_PROTOCOL_NAME`](crate::ADDRESS_HINTS_PROTOCOL_NAME).
+    /// Each address of an answer is reported as [`ToSwarm::NewExternalAddrOfPeer`].
+    /// They are candidates only; the remote may not have verified them.
+    ///
+    /// At most [`AddressHintsConfig::max_peers`] peers are requested at once.
+    pub fn request_address_hints<I>(&mut self, peer_id: PeerId, peers: I)
+    where
+        I: IntoIterator<Item = PeerId>,
+    {
+        if !self.connected.contains_key(&peer_id) {
+            tracing::debug!(peer=%peer_id, "Not requesting address hints because we are not connected");
+            return;
+        }
+
+        let max_peers = self.address_hints_config().max_peers;
+        let peers = peers
+            .into_iter()
+            .filter(|p| *p != peer_id)
+            .take(max_peers)
+            .collect::<Vec<_>>();
+        if peers.is_empty() {
+            return;
+        }
+
+        self.outstanding_address_hints
+            .entry(peer_id)
+            .or_default()
+            .extend(peers.iter().copied());
+        self.events.push_back(ToSwarm::NotifyHandler {
+            peer_id,
+            handler: NotifyHandler::Any,
+            event: InEvent::RequestAddressHints(peers),
+        });
+    }
+
+    fn address_hints_config(&self) -> AddressHintsConfig {
+        self.config.address_hints.clone().unwrap_or_default()
+    }
+
+    fn on_address_hints_requested(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        peers: Vec<PeerId>,
+    ) {
+        let Some(config) = self.config.address_hints.clone() else {
+            tracing::debug!(peer=%peer_id, "Ignoring address hint request because address hints are disabled");
+            return;
+        };
+
+        let answers = peers
+            .into_iter()
+            .filter(|p| *p != peer_id)
+            .take(config.max_peers)
+            .filter_map(|p| {
+                let addrs = self
+                    .discovered_peers
+                    .get(&p)
+                    .into_iter()
+                    .filter(|a| config.share_private_addresses || !is_private_address(a))
+                    .take(config.max_addresses_per_peer)
+                    .collect::<Vec<_>>();
+
+                (!addrs.is_empty()).then_some((p, addrs))
+            })
+            .collect::<Vec<_>>();
+        if answers.is_empty() {
+            return;
+        }
+
+        self.events.push_back(ToSwarm::NotifyHandler {
+            peer_id,
+            handler: NotifyHandler::One(connection_id),
+            event: InEvent::AddressHints(answers),
+        });
+    }
+
+    fn on_address_hints_received(
+        &mut self,
+        peer_id: PeerId,
+        answers: Vec<(PeerId, Vec<Multiaddr>)>,
+    ) {
+        let config = self.address_hints_config();
+        let Some(outstanding) = self.outstanding_address_hints.get_mut(&peer_id) else {
+            tracing::debug!(peer=%peer_id, "Ignoring unsolicited address hints");
+            return;
+        };
+
+        let mut accepted = 0;
+        for (peer, mut addrs) in answers {
+            // Only accept answers for peers we asked for, and only once.
+            if accepted >= config.max_peers || !outstanding.remove(&peer) {
+                continue;
+            }
+
+            addrs.retain(|a| multiaddr_matches_peer_id(a, &peer));
+            addrs.truncate(config.max_addresses_per_peer);
+            if addrs.is_empty() {
+                continue;
+            }
+
+            tracing::debug!(peer=%peer_id, hinted_peer=%peer, ?addrs, "Received address hints");
+            accepted += 1;
+            for address in addrs {
+                self.events.push_back(ToSwarm::NewExternalAddrOfPeer {
+                    peer_id: peer,
+                    address,
+                });
+            }
+        }
+        if outstanding.is_empty() {
+            self.outstanding_address_hints.remove(&peer_id);
+        }
+    }
+
     fn on_connection_established(
         &mut self,
         ConnectionEstablished {
@@ -319,6 +511,17 @@ impl NetworkBehaviour for Behaviour {
                 self.events
                     .push_back(ToSwarm::GenerateEvent(Event::Error { peer_id, error }));
             }
+            handler::Event::AddressHints { requests, answers } => {
+                if !answers.is_empty() {
+                    self.on_address_hints_received(peer_id, answers);
+                }
+                if !requests.is_empty() {
+                    self.on_address_hints_requested(peer_id, id, requests);
+                }
+            }
+            handler::Event::AddressHintsError(error) => {
+                tracing::debug!(peer=%peer_id, "Failed to exchange address hints: {error}");
+            }
         }
     }
 
@@ -389,6 +592,7 @@ impl NetworkBehaviour for Behaviour {
             }) => {
                 if remaining_established == 0 {
                     self.connected.remove(&peer_id);
+                    self.outstanding_address_hints.remove(&peer_id);
                 } else if let Some(addrs) = self.connected.get_mut(&peer_id) {
                     addrs.remove(&connection_id);
                 }
@@ -454,6 +658,23 @@ fn multiaddr_matches_peer_id(addr: &Multiaddr, peer_id: &PeerId) -> bool {
     true
 }
 
+/// Whether the address is a loopback, private, link-local or unspecified IP address.
+fn is_private_address(addr: &Multiaddr) -> bool {
+    match addr.iter().next() {
+        Some(multiaddr::Protocol::Ip4(ip)) => {
+            ip.is_private() || ip.is_loopback() || ip.is_link_local() || ip.is_unspecified()
+        }
+        Some(multiaddr::Protocol::Ip6(ip)) => {
+            let segment = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || (segment & 0xfe00) == 0xfc00 // Unique local.
+                || (segment & 0xffc0) == 0xfe80 // Link local.
+        }
+        _ => false,
+    }
+}
+
 struct PeerCache(Option<PeerAddresses>);
 
 impl PeerCache {
@@ -499,4 +720,26 @@ mod tests {
         ));
         assert!(multiaddr_matches_peer_id(&addr_without_peer_id, &peer_id));
     }
+
+    #[test]
+    fn check_is_private_address() {
+        for addr in [
+            "/ip4/10.0.0.1/tcp/4001",
+            "/ip4/127.0.0.1/tcp/4001",
+            "/ip4/169.254.1.1/udp/4001/quic-v1",
+            "/ip6/::1/tcp/4001",
+            "/ip6/fd00::1/tcp/4001",
+            "/ip6/fe80::1/tcp/4001",
+        ] {
+            assert!(is_private_address(&addr.parse().unwrap()), "{addr}");
+        }
+
+        for addr in [
+            "/ip4/147.75.69.143/tcp/4001",
+            "/ip6/2001:db8::1/tcp/4001",
+            "/dns4/example.com/tcp/4001",
+        ] {
+            assert!(!is_private_address(&addr.parse().unwrap()), "{addr}");
+        }
+    }
 }
@@ -65,3 +65,75 @@ impl MessageWrite for Identify {
     }
 }
 
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct AddressHints {
+    pub requests: Vec<Vec<u8>>,
+    pub answers: Vec<structs::PeerAddresses>,
+}
+
+impl<'a> MessageRead<'a> for AddressHints {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.requests.push(r.read_bytes(bytes)?.to_owned()),
+                Ok(18) => msg.answers.push(r.read_message::<structs::PeerAddresses>(bytes)?),
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for AddressHints {
+    fn get_size(&self) -> usize {
+        0
+        + self.requests.iter().map(|s| 1 + sizeof_len((s).len())).sum::<usize>()
+        + self.answers.iter().map(|s| 1 + sizeof_len((s).get_size())).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        for s in &self.requests { w.write_with_tag(10, |w| w.write_bytes(&**s))?; }
+        for s in &self.answers { w.write_with_tag(18, |w| w.write_message(s))?; }
+        Ok(())
+    }
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct PeerAddresses {
+    pub peerId: Option<Vec<u8>>,
+    pub addrs: Vec<Vec<u8>>,
+}
+
+impl<'a> MessageRead<'a> for PeerAddresses {
+    fn from_reader(r: &mut BytesReader, bytes: &'a [u8]) -> Result<Self> {
+        let mut msg = Self::default();
+        while !r.is_eof() {
+            match r.next_tag(bytes) {
+                Ok(10) => msg.peerId = Some(r.read_bytes(bytes)?.to_owned()),
+                Ok(18) => msg.addrs.push(r.read_bytes(bytes)?.to_owned()),
+                Ok(t) => { r.read_unknown(bytes, t)?; }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(msg)
+    }
+}
+
+impl MessageWrite for PeerAddresses {
+    fn get_size(&self) -> usize {
+        0
+        + self.peerId.as_ref().map_or(0, |m| 1 + sizeof_len((m).len()))
+        + self.addrs.iter().map(|s| 1 + sizeof_len((s).len())).sum::<usize>()
+    }
+
+    fn write_message<W: WriterBackend>(&self, w: &mut Writer<W>) -> Result<()> {
+        if let Some(ref s) = self.peerId { w.write_with_tag(10, |w| w.write_bytes(&**s))?; }
+        for s in &self.addrs { w.write_with_tag(18, |w| w.write_bytes(&**s))?; }
+        Ok(())
+    }
+}
+
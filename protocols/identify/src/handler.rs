@@ -18,8 +18,8 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::protocol::{Info, PushInfo, UpgradeError};
-use crate::{protocol, PROTOCOL_NAME, PUSH_PROTOCOL_NAME};
+use crate::protocol::{AddressHints, Info, PushInfo, UpgradeError};
+use crate::{protocol, ADDRESS_HINTS_PROTOCOL_NAME, PROTOCOL_NAME, PUSH_PROTOCOL_NAME};
 use either::Either;
 use futures::prelude::*;
 use futures_bounded::Timeout;
@@ -54,13 +54,19 @@ pub struct Handler {
     /// Pending events to yield.
     events: SmallVec<
         [ConnectionHandlerEvent<
-            Either<ReadyUpgrade<StreamProtocol>, ReadyUpgrade<StreamProtocol>>,
-            (),
+            Either<
+                Either<ReadyUpgrade<StreamProtocol>, ReadyUpgrade<StreamProtocol>>,
+                ReadyUpgrade<StreamProtocol>,
+            >,
+            OutboundStream,
             Event,
         >; 4],
     >,
 
     active_streams: futures_bounded::FuturesSet<Result<Success, UpgradeError>>,
+    /// Streams exchanging address hints, kept apart so that their failures are not reported as
+    /// identification errors. Yields the hints received from the remote, if any.
+    address_hints_streams: futures_bounded::FuturesSet<Result<Option<AddressHints>, UpgradeError>>,
 
     /// Future that fires when we need to identify the node again.
     trigger_next_identify: Delay,
@@ -91,6 +97,11 @@ pub struct Handler {
     local_supported_protocols: SupportedProtocols,
     remote_supported_protocols: HashSet<StreamProtocol>,
     external_addresses: HashSet<Multiaddr>,
+
+    /// Address hint requests and answers to send to the remote.
+    pending_address_hints: AddressHints,
+    /// Whether we requested a stream to send `pending_address_hints` on.
+    address_hints_stream_requested: bool,
 }
 
 /// An event from `Behaviour` with the information requested by the `Handler`.
@@ -98,6 +109,19 @@ pub struct Handler {
 pub enum InEvent {
     AddressesChanged(HashSet<Multiaddr>),
     Push,
+    /// Ask the remote for addresses of the given peers.
+    RequestAddressHints(Vec<PeerId>),
+    /// Answer an address hint request of the remote.
+    AddressHints(Vec<(PeerId, Vec<Multiaddr>)>),
+}
+
+/// The kind of an outbound stream, used as its open info.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutboundStream {
+    /// An identify or identify push stream.
+    Identify,
+    /// A stream to send address hints on.
+    AddressHints,
 }
 
 /// Event produced by the `Handler`.
@@ -112,6 +136,14 @@ pub enum Event {
     IdentificationPushed(Info),
     /// Failed to identify the remote, or to reply to an identification request.
     IdentificationError(StreamUpgradeError<UpgradeError>),
+    /// The remote asked us for addresses of `requests` and shared addresses of peers we
+    /// asked it for in `answers`.
+    AddressHints {
+        requests: Vec<PeerId>,
+        answers: Vec<(PeerId, Vec<Multiaddr>)>,
+    },
+    /// Failed to exchange address hints with the remote.
+    AddressHintsError(StreamUpgradeError<UpgradeError>),
 }
 
 impl Handler {
@@ -132,6 +164,10 @@ impl Handler {
                 STREAM_TIMEOUT,
                 MAX_CONCURRENT_STREAMS_PER_CONNECTION,
             ),
+            address_hints_streams: futures_bounded::FuturesSet::new(
+                STREAM_TIMEOUT,
+                MAX_CONCURRENT_STREAMS_PER_CONNECTION,
+            ),
             trigger_next_identify: Delay::new(Duration::ZERO),
             exchanged_one_periodic_identify: false,
             interval,
@@ -143,6 +179,8 @@ impl Handler {
             remote_supported_protocols: HashSet::default(),
             remote_info: Default::default(),
             external_addresses,
+            pending_address_hints: AddressHints::default(),
+            address_hints_stream_requested: false,
         }
     }
 
@@ -156,7 +194,7 @@ impl Handler {
         >,
     ) {
         match output {
-            future::Either::Left(stream) => {
+            future::Either::Left(future::Either::Left(stream)) => {
                 let info = self.build_info();
 
                 if self
@@ -171,7 +209,7 @@ impl Handler {
                     self.exchanged_one_periodic_identify = true;
                 }
             }
-            future::Either::Right(stream) => {
+            future::Either::Left(future::Either::Right(stream)) => {
                 if self
                     .active_streams
                     .try_push(protocol::recv_push(stream).map_ok(Success::ReceivedIdentifyPush))
@@ -182,6 +220,17 @@ impl Handler {
                     );
                 }
             }
+            future::Either::Right(stream) => {
+                if self
+                    .address_hints_streams
+                    .try_push(protocol::recv_address_hints(stream).map_ok(Some))
+                    .is_err()
+                {
+                    tracing::warn!(
+                        "Dropping inbound address hints stream because we are at capacity"
+                    );
+                }
+            }
         }
     }
 
@@ -195,7 +244,7 @@ impl Handler {
         >,
     ) {
         match output {
-            future::Either::Left(stream) => {
+            future::Either::Left(future::Either::Left(stream)) => {
                 if self
                     .active_streams
                     .try_push(protocol::recv_identify(stream).map_ok(Success::ReceivedIdentify))
@@ -204,7 +253,7 @@ impl Handler {
                     tracing::warn!("Dropping outbound identify stream because we are at capacity");
                 }
             }
-            future::Either::Right(stream) => {
+            future::Either::Left(future::Either::Right(stream)) => {
                 let info = self.build_info();
 
                 if self
@@ -219,7 +268,55 @@ impl Handler {
                     );
                 }
             }
+            future::Either::Right(stream) => {
+                self.address_hints_stream_requested = false;
+                let hints = std::mem::take(&mut self.pending_address_hints);
+                if hints.is_empty() {
+                    return;
+                }
+
+                if self
+                    .address_hints_streams
+                    .try_push(protocol::send_address_hints(stream, hints).map_ok(|()| None))
+                    .is_err()
+                {
+                    tracing::warn!(
+                        "Dropping outbound address hints stream because we are at capacity"
+                    );
+                }
+            }
+        }
+    }
+
+    /// Requests a stream to send the pending address hints on, once the remote is known to
+    /// support the address hints protocol.
+    fn request_address_hints_stream(&mut self) {
+        if self.pending_address_hints.is_empty() || self.address_hints_stream_requested {
+            return;
         }
+        if self.remote_info.is_none() {
+            return;
+        }
+        if !self
+            .remote_supported_protocols
+            .contains(&ADDRESS_HINTS_PROTOCOL_NAME)
+        {
+            tracing::debug!(
+                peer=%self.remote_peer_id,
+                "Remote does not support address hints, dropping pending hints"
+            );
+            self.pending_address_hints = AddressHints::default();
+            return;
+        }
+
+        self.address_hints_stream_requested = true;
+        self.events
+            .push(ConnectionHandlerEvent::OutboundSubstreamRequest {
+                protocol: SubstreamProtocol::new(
+                    Either::Right(ReadyUpgrade::new(ADDRESS_HINTS_PROTOCOL_NAME)),
+                    OutboundStream::AddressHints,
+                ),
+            });
     }
 
     fn build_info(&mut self) -> Info {
@@ -237,6 +334,7 @@ impl Handler {
         self.remote_info.replace(info.clone());
 
         self.update_supported_protocols_for_remote(info);
+        self.request_address_hints_stream();
     }
 
     fn update_supported_protocols_for_remote(&mut self, remote_info: &Info) {
@@ -281,17 +379,25 @@ impl Handler {
 impl ConnectionHandler for Handler {
     type FromBehaviour = InEvent;
     type ToBehaviour = Event;
-    type InboundProtocol =
-        SelectUpgrade<ReadyUpgrade<StreamProtocol>, ReadyUpgrade<StreamProtocol>>;
-    type OutboundProtocol = Either<ReadyUpgrade<StreamProtocol>, ReadyUpgrade<StreamProtocol>>;
-    type OutboundOpenInfo = ();
+    type InboundProtocol = SelectUpgrade<
+        SelectUpgrade<ReadyUpgrade<StreamProtocol>, ReadyUpgrade<StreamProtocol>>,
+        ReadyUpgrade<StreamProtocol>,
+    >;
+    type OutboundProtocol = Either<
+        Either<ReadyUpgrade<StreamProtocol>, ReadyUpgrade<StreamProtocol>>,
+        ReadyUpgrade<StreamProtocol>,
+    >;
+    type OutboundOpenInfo = OutboundStream;
     type InboundOpenInfo = ();
 
     fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol, Self::InboundOpenInfo> {
         SubstreamProtocol::new(
             SelectUpgrade::new(
-                ReadyUpgrade::new(PROTOCOL_NAME),
-                ReadyUpgrade::new(PUSH_PROTOCOL_NAME),
+                SelectUpgrade::new(
+                    ReadyUpgrade::new(PROTOCOL_NAME),
+                    ReadyUpgrade::new(PUSH_PROTOCOL_NAME),
+                ),
+                ReadyUpgrade::new(ADDRESS_HINTS_PROTOCOL_NAME),
             ),
             (),
         )
@@ -306,11 +412,19 @@ impl ConnectionHandler for Handler {
                 self.events
                     .push(ConnectionHandlerEvent::OutboundSubstreamRequest {
                         protocol: SubstreamProtocol::new(
-                            Either::Right(ReadyUpgrade::new(PUSH_PROTOCOL_NAME)),
-                            (),
+                            Either::Left(Either::Right(ReadyUpgrade::new(PUSH_PROTOCOL_NAME))),
+                            OutboundStream::Identify,
                         ),
                     });
             }
+            InEvent::RequestAddressHints(peers) => {
+                self.pending_address_hints.requests.extend(peers);
+                self.request_address_hints_stream();
+            }
+            InEvent::AddressHints(answers) => {
+                self.pending_address_hints.answers.extend(answers);
+                self.request_address_hints_stream();
+            }
         }
     }
 
@@ -328,55 +442,80 @@ impl ConnectionHandler for Handler {
             self.trigger_next_identify.reset(self.interval);
             let event = ConnectionHandlerEvent::OutboundSubstreamRequest {
                 protocol: SubstreamProtocol::new(
-                    Either::Left(ReadyUpgrade::new(PROTOCOL_NAME)),
-                    (),
+                    Either::Left(Either::Left(ReadyUpgrade::new(PROTOCOL_NAME))),
+                    OutboundStream::Identify,
                 ),
             };
             return Poll::Ready(event);
         }
 
-        match self.active_streams.poll_unpin(cx) {
-            Poll::Ready(Ok(Ok(Success::ReceivedIdentify(remote_info)))) => {
-                self.handle_incoming_info(&remote_info);
-
-                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(Event::Identified(
-                    remote_info,
-                )));
-            }
-            Poll::Ready(Ok(Ok(Success::SentIdentifyPush(info)))) => {
-                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
-                    Event::IdentificationPushed(info),
-                ));
-            }
-            Poll::Ready(Ok(Ok(Success::SentIdentify))) => {
-                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
-                    Event::Identification,
-                ));
+        loop {
+            match self.address_hints_streams.poll_unpin(cx) {
+                Poll::Ready(Ok(Ok(Some(hints)))) => {
+                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                        Event::AddressHints {
+                            requests: hints.requests,
+                            answers: hints.answers,
+                        },
+                    ));
+                }
+                Poll::Ready(Ok(Ok(None))) => {}
+                Poll::Ready(Ok(Err(e))) => {
+                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                        Event::AddressHintsError(StreamUpgradeError::Apply(e)),
+                    ));
+                }
+                Poll::Ready(Err(Timeout { .. })) => {
+                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                        Event::AddressHintsError(StreamUpgradeError::Timeout),
+                    ));
+                }
+                Poll::Pending => break,
             }
-            Poll::Ready(Ok(Ok(Success::ReceivedIdentifyPush(remote_push_info)))) => {
-                if let Some(mut info) = self.remote_info.clone() {
-                    info.merge(remote_push_info);
-                    self.handle_incoming_info(&info);
+        }
+
+        loop {
+            match self.active_streams.poll_unpin(cx) {
+                Poll::Ready(Ok(Ok(Success::ReceivedIdentify(remote_info)))) => {
+                    self.handle_incoming_info(&remote_info);
 
                     return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
-                        Event::Identified(info),
+                        Event::Identified(remote_info),
                     ));
-                };
-            }
-            Poll::Ready(Ok(Err(e))) => {
-                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
-                    Event::IdentificationError(StreamUpgradeError::Apply(e)),
-                ));
-            }
-            Poll::Ready(Err(Timeout { .. })) => {
-                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
-                    Event::IdentificationError(StreamUpgradeError::Timeout),
-                ));
+                }
+                Poll::Ready(Ok(Ok(Success::SentIdentifyPush(info)))) => {
+                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                        Event::IdentificationPushed(info),
+                    ));
+                }
+                Poll::Ready(Ok(Ok(Success::SentIdentify))) => {
+                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                        Event::Identification,
+                    ));
+                }
+                Poll::Ready(Ok(Ok(Success::ReceivedIdentifyPush(remote_push_info)))) => {
+                    if let Some(mut info) = self.remote_info.clone() {
+                        info.merge(remote_push_info);
+                        self.handle_incoming_info(&info);
+
+                        return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                            Event::Identified(info),
+                        ));
+                    };
+                }
+                Poll::Ready(Ok(Err(e))) => {
+                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                        Event::IdentificationError(StreamUpgradeError::Apply(e)),
+                    ));
+                }
+                Poll::Ready(Err(Timeout { .. })) => {
+                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                        Event::IdentificationError(StreamUpgradeError::Timeout),
+                    ));
+                }
+                Poll::Pending => return Poll::Pending,
             }
-            Poll::Pending => {}
         }
-
-        Poll::Pending
     }
 
     fn on_connection_event(
@@ -395,13 +534,24 @@ impl ConnectionHandler for Handler {
             ConnectionEvent::FullyNegotiatedOutbound(fully_negotiated_outbound) => {
                 self.on_fully_negotiated_outbound(fully_negotiated_outbound)
             }
-            ConnectionEvent::DialUpgradeError(DialUpgradeError { error, .. }) => {
-                self.events.push(ConnectionHandlerEvent::NotifyBehaviour(
-                    Event::IdentificationError(
-                        error.map_upgrade_err(|e| void::unreachable(e.into_inner())),
-                    ),
-                ));
-                self.trigger_next_identify.reset(self.interval);
+            ConnectionEvent::DialUpgradeError(DialUpgradeError { info, error }) => {
+                let error = error.map_upgrade_err(|e| {
+                    void::unreachable(e.map_left(Either::into_inner).into_inner())
+                });
+                match info {
+                    OutboundStream::Identify => {
+                        self.events.push(ConnectionHandlerEvent::NotifyBehaviour(
+                            Event::IdentificationError(error),
+                        ));
+                        self.trigger_next_identify.reset(self.interval);
+                    }
+                    OutboundStream::AddressHints => {
+                        self.address_hints_stream_requested = false;
+                        self.events.push(ConnectionHandlerEvent::NotifyBehaviour(
+                            Event::AddressHintsError(error),
+                        ));
+                    }
+                }
             }
             ConnectionEvent::LocalProtocolsChange(change) => {
                 let before = tracing::enabled!(Level::DEBUG)
@@ -423,8 +573,8 @@ impl ConnectionHandler for Handler {
                     self.events
                         .push(ConnectionHandlerEvent::OutboundSubstreamRequest {
                             protocol: SubstreamProtocol::new(
-                                Either::Right(ReadyUpgrade::new(PUSH_PROTOCOL_NAME)),
-                                (),
+                                Either::Left(Either::Right(ReadyUpgrade::new(PUSH_PROTOCOL_NAME))),
+                                OutboundStream::Identify,
                             ),
                         });
                 }
@@ -440,3 +590,42 @@ enum Success {
     SentIdentifyPush(Info),
     ReceivedIdentifyPush(PushInfo),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::noop_waker_ref;
+    use libp2p_identity::Keypair;
+
+    #[test]
+    fn address_hints_dial_failure_is_not_an_identification_error() {
+        let mut handler = Handler::new(
+            Duration::from_secs(300),
+            PeerId::random(),
+            Keypair::generate_ed25519().public(),
+            "/test/1.0.0".to_owned(),
+            "test".to_owned(),
+            Multiaddr::empty(),
+            HashSet::new(),
+        );
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        handler.address_hints_stream_requested = true;
+        handler.on_connection_event(ConnectionEvent::DialUpgradeError(DialUpgradeError {
+            info: OutboundStream::AddressHints,
+            error: StreamUpgradeError::NegotiationFailed,
+        }));
+
+        assert!(matches!(
+            handler.poll(&mut cx),
+            Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                Event::AddressHintsError(StreamUpgradeError::NegotiationFailed)
+            ))
+        ));
+        assert!(!handler.address_hints_stream_requested);
+        assert!(!matches!(
+            handler.poll(&mut cx),
+            Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(_))
+        ));
+    }
+}
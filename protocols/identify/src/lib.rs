@@ -41,8 +41,10 @@
 
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
-pub use self::behaviour::{Behaviour, Config, Event};
-pub use self::protocol::{Info, UpgradeError, PROTOCOL_NAME, PUSH_PROTOCOL_NAME};
+pub use self::behaviour::{AddressHintsConfig, Behaviour, Config, Event};
+pub use self::protocol::{
+    Info, UpgradeError, ADDRESS_HINTS_PROTOCOL_NAME, PROTOCOL_NAME, PUSH_PROTOCOL_NAME,
+};
 
 mod behaviour;
 mod handler;
@@ -51,5 +53,5 @@ mod protocol;
 mod proto {
     #![allow(unreachable_pub)]
     include!("generated/mod.rs");
-    pub(crate) use self::structs::Identify;
+    pub(crate) use self::structs::{AddressHints, Identify, PeerAddresses};
 }
@@ -23,8 +23,9 @@ use asynchronous_codec::{FramedRead, FramedWrite};
 use futures::prelude::*;
 use libp2p_core::{multiaddr, Multiaddr};
 use libp2p_identity as identity;
-use libp2p_identity::PublicKey;
+use libp2p_identity::{PeerId, PublicKey};
 use libp2p_swarm::StreamProtocol;
+use quick_protobuf::MessageWrite;
 use std::io;
 use thiserror::Error;
 
@@ -34,6 +35,13 @@ pub const PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/ipfs/id/1.0.0");
 
 pub const PUSH_PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/ipfs/id/push/1.0.0");
 
+/// The protocol address hints are exchanged on, see
+/// [`Behaviour::request_address_hints`](crate::Behaviour::request_address_hints).
+///
+/// This is an extension of rust-libp2p, not part of the identify specification.
+pub const ADDRESS_HINTS_PROTOCOL_NAME: StreamProtocol =
+    StreamProtocol::new("/rust-libp2p/identify/address-hints/1.0.0");
+
 /// Identify information of a peer sent in protocol messages.
 #[derive(Debug, Clone)]
 pub struct Info {
@@ -88,6 +96,86 @@ pub struct PushInfo {
     pub observed_addr: Option<Multiaddr>,
 }
 
+/// Address hint requests and answers sent in a single message.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct AddressHints {
+    /// Peers the sender wants to learn addresses for.
+    pub(crate) requests: Vec<PeerId>,
+    /// Known addresses of peers the receiver previously asked for.
+    pub(crate) answers: Vec<(PeerId, Vec<Multiaddr>)>,
+}
+
+impl AddressHints {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.requests.is_empty() && self.answers.is_empty()
+    }
+}
+
+pub(crate) async fn send_address_hints<T>(io: T, hints: AddressHints) -> Result<(), UpgradeError>
+where
+    T: AsyncWrite + Unpin,
+{
+    tracing::trace!(?hints, "Sending address hints");
+
+    let message = address_hints_to_proto(hints);
+
+    let mut framed_io = FramedWrite::new(
+        io,
+        quick_protobuf_codec::Codec::<proto::AddressHints>::new(MAX_MESSAGE_SIZE_BYTES),
+    );
+
+    framed_io.send(message).await?;
+    framed_io.close().await?;
+
+    Ok(())
+}
+
+pub(crate) async fn recv_address_hints<T>(socket: T) -> Result<AddressHints, UpgradeError>
+where
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let msg = FramedRead::new(
+        socket,
+        quick_protobuf_codec::Codec::<proto::AddressHints>::new(MAX_MESSAGE_SIZE_BYTES),
+    )
+    .next()
+    .await
+    .ok_or(UpgradeError::StreamClosed)??;
+
+    let hints = parse_address_hints(msg);
+
+    tracing::trace!(?hints, "Received address hints");
+
+    Ok(hints)
+}
+
+/// Encodes `hints`, leaving out the requests and answers that don't fit in a single message.
+fn address_hints_to_proto(hints: AddressHints) -> proto::AddressHints {
+    let mut message = proto::AddressHints::default();
+
+    for peer in hints.requests {
+        message.requests.push(peer.to_bytes());
+        if message.get_size() > MAX_MESSAGE_SIZE_BYTES {
+            message.requests.pop();
+            tracing::debug!("Address hint requests exceed the maximum message size, dropping some");
+            break;
+        }
+    }
+    for (peer, addrs) in hints.answers {
+        message.answers.push(proto::PeerAddresses {
+            peerId: Some(peer.to_bytes()),
+            addrs: addrs.iter().map(|addr| addr.to_vec()).collect(),
+        });
+        if message.get_size() > MAX_MESSAGE_SIZE_BYTES {
+            message.answers.pop();
+            tracing::debug!("Address hints exceed the maximum message size, dropping some");
+            break;
+        }
+    }
+
+    message
+}
+
 pub(crate) async fn send_identify<T>(io: T, info: Info) -> Result<Info, UpgradeError>
 where
     T: AsyncWrite + Unpin,
@@ -173,6 +261,36 @@ fn parse_listen_addrs(listen_addrs: Vec<Vec<u8>>) -> Vec<Multiaddr> {
         .collect()
 }
 
+fn parse_address_hints(msg: proto::AddressHints) -> AddressHints {
+    let requests = msg
+        .requests
+        .into_iter()
+        .filter_map(|bytes| match PeerId::from_bytes(&bytes) {
+            Ok(p) => Some(p),
+            Err(e) => {
+                tracing::debug!("Unable to parse address hint request: {e:?}");
+                None
+            }
+        })
+        .collect();
+    let answers = msg
+        .answers
+        .into_iter()
+        .filter_map(|hint| {
+            let peer = match PeerId::from_bytes(&hint.peerId.unwrap_or_default()) {
+                Ok(p) => p,
+                Err(e) => {
+                    tracing::debug!("Unable to parse peer of address hint: {e:?}");
+                    return None;
+                }
+            };
+            Some((peer, parse_listen_addrs(hint.addrs)))
+        })
+        .collect();
+
+    AddressHints { requests, answers }
+}
+
 fn parse_protocols(protocols: Vec<String>) -> Vec<StreamProtocol> {
     protocols
         .into_iter()
@@ -295,4 +413,49 @@ mod tests {
 
         assert_eq!(info.listen_addrs, vec![valid_multiaddr])
     }
+
+    #[test]
+    fn skip_invalid_address_hints() {
+        let peer = PeerId::random();
+        let addr: Multiaddr = "/ip4/1.2.3.4/tcp/1234".parse().unwrap();
+
+        let payload = proto::AddressHints {
+            requests: vec![peer.to_bytes(), vec![255; 8]],
+            answers: vec![
+                proto::PeerAddresses {
+                    peerId: Some(peer.to_bytes()),
+                    addrs: vec![addr.to_vec(), vec![255; 8]],
+                },
+                proto::PeerAddresses {
+                    peerId: None,
+                    addrs: vec![addr.to_vec()],
+                },
+            ],
+        };
+
+        let hints = parse_address_hints(payload);
+
+        assert_eq!(hints.requests, vec![peer]);
+        assert_eq!(hints.answers, vec![(peer, vec![addr])]);
+    }
+
+    #[test]
+    fn address_hints_fit_in_max_message_size() {
+        let addr: Multiaddr = "/dns4/a-rather-long-host-name.example.com/tcp/4001"
+            .parse()
+            .unwrap();
+        let hints = AddressHints {
+            requests: (0..50).map(|_| PeerId::random()).collect(),
+            answers: (0..100)
+                .map(|_| (PeerId::random(), vec![addr.clone(); 4]))
+                .collect(),
+        };
+
+        let message = address_hints_to_proto(hints);
+
+        assert!(message.get_size() <= MAX_MESSAGE_SIZE_BYTES);
+        assert_eq!(message.requests.len(), 50);
+        assert!(!message.answers.is_empty());
+        assert!(message.answers.len() < 100);
+    }
 }
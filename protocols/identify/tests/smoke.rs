@@ -280,6 +280,64 @@ async fn identify_push() {
     assert!(swarm1_received_info.listen_addrs.is_empty());
 }
 
+#[async_std::test]
+async fn address_hints() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let mut swarm1 = Swarm::new_ephemeral(|identity| {
+        identify::Behaviour::new(
+            identify::Config::new("a".to_string(), identity.public())
+                .with_address_hints(identify::AddressHintsConfig::default()),
+        )
+    });
+    let mut swarm2 = Swarm::new_ephemeral(|identity| {
+        identify::Behaviour::new(identify::Config::new("a".to_string(), identity.public()))
+    });
+    let mut swarm3 = Swarm::new_ephemeral(|identity| {
+        identify::Behaviour::new(identify::Config::new("a".to_string(), identity.public()))
+    });
+    let swarm1_peer_id = *swarm1.local_peer_id();
+    let swarm3_peer_id = *swarm3.local_peer_id();
+
+    swarm1.listen().with_memory_addr_external().await;
+    let (swarm3_memory_listen, swarm3_tcp_listen) = swarm3.listen().await;
+    swarm3.connect(&mut swarm1).await;
+
+    // Let swarm1 learn the addresses of swarm3.
+    let ([_, _], [_, _]): ([identify::Event; 2], [identify::Event; 2]) =
+        libp2p_swarm_test::drive(&mut swarm1, &mut swarm3).await;
+    async_std::task::spawn(swarm3.loop_on_next());
+
+    swarm2.connect(&mut swarm1).await;
+    async_std::task::spawn(swarm1.loop_on_next());
+
+    swarm2
+        .behaviour_mut()
+        .request_address_hints(swarm1_peer_id, iter::once(swarm3_peer_id));
+
+    let swarm3_memory_addr = swarm3_memory_listen.with(Protocol::P2p(swarm3_peer_id));
+    let swarm3_tcp_addr = swarm3_tcp_listen.with(Protocol::P2p(swarm3_peer_id));
+    let mut hinted = Vec::new();
+    while !hinted.contains(&swarm3_memory_addr) {
+        let address = swarm2
+            .wait(|event| match event {
+                SwarmEvent::NewExternalAddrOfPeer { peer_id, address }
+                    if peer_id == swarm3_peer_id =>
+                {
+                    Some(address)
+                }
+                _ => None,
+            })
+            .await;
+        hinted.push(address);
+    }
+
+    // Loopback addresses are not shared by default.
+    assert!(!hinted.contains(&swarm3_tcp_addr));
+}
+
 #[async_std::test]
 async fn discover_peer_after_disconnect() {
     let _ = tracing_subscriber::fmt()
@@ -27,7 +27,7 @@ use crate::bootstrap;
 use crate::handler::{Handler, HandlerEvent, HandlerIn, RequestId};
 use crate::kbucket::{self, Distance, KBucketsTable, NodeStatus};
 use crate::protocol::{ConnectionType, KadPeer, ProtocolConfig};
-use crate::query::{Query, QueryConfig, QueryId, QueryPool, QueryPoolState};
+use crate::query::{PeerLatency, Query, QueryConfig, QueryId, QueryPool, QueryPoolState};
 use crate::record::{
     self,
     store::{self, RecordStore},
@@ -35,12 +35,13 @@ use crate::record::{
 };
 use crate::K_VALUE;
 use crate::{jobs::*, protocol};
-use fnv::{FnvHashMap, FnvHashSet};
+use futures::future::BoxFuture;
+use futures::Future;
 use instant::Instant;
 use libp2p_core::{ConnectedPoint, Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
 use libp2p_swarm::behaviour::{
-    AddressChange, ConnectionClosed, ConnectionEstablished, DialFailure, FromSwarm,
+    AddressChange, ConnectionClosed, ConnectionEstablished, DialFailure, FromSwarm, PeerAddresses,
 };
 use libp2p_swarm::{
     dial_opts::{self, DialOpts},
@@ -81,7 +82,7 @@ pub struct Behaviour<TStore> {
     /// The currently connected peers.
     ///
     /// This is a superset of the connected peers currently in the routing table.
-    connected_peers: FnvHashSet<PeerId>,
+    connected_peers: HashSet<PeerId>,
 
     /// Periodic job for re-publication of provider records for keys
     /// provided by the local node.
@@ -118,8 +119,28 @@ pub struct Behaviour<TStore> {
     /// The record storage.
     store: TStore,
 
+    /// The hook consulted before storing inbound records.
+    /// See [`Behaviour::set_record_admission`].
+    record_admission: Option<RecordAdmission>,
+
+    /// Inbound records awaiting the decision of the admission hook.
+    pending_admissions: futures_bounded::FuturesTupleSet<bool, PendingAdmission>,
+
     /// Tracks the status of the current bootstrap.
     bootstrap_status: bootstrap::Status,
+
+    /// See [`Config::set_newer_protocol_names`].
+    newer_protocol_names: Vec<StreamProtocol>,
+
+    /// See [`Config::set_protocol_skew_threshold`].
+    protocol_skew_threshold: f64,
+
+    /// The peers known to support one of the `newer_protocol_names`.
+    peers_with_newer_protocol: HashSet<PeerId>,
+
+    /// Whether an [`Event::ProtocolSkew`] was emitted since the share of
+    /// `peers_with_newer_protocol` in the routing table last reached the threshold.
+    protocol_skew_reported: bool,
 }
 
 /// The configurable strategies for the insertion of peers
@@ -187,6 +208,10 @@ pub struct Config {
     caching: Caching,
     periodic_bootstrap_interval: Option<Duration>,
     automatic_bootstrap_throttle: Option<Duration>,
+    max_concurrent_record_admissions: usize,
+    record_admission_timeout: Duration,
+    newer_protocol_names: Vec<StreamProtocol>,
+    protocol_skew_threshold: f64,
 }
 
 impl Default for Config {
@@ -198,6 +223,17 @@ impl Default for Config {
     }
 }
 
+/// A hook consulted before storing inbound records.
+type RecordAdmission = Box<dyn FnMut(PeerId, &Record) -> BoxFuture<'static, bool> + Send>;
+
+/// An inbound record awaiting the decision of the admission hook.
+struct PendingAdmission {
+    source: PeerId,
+    connection: ConnectionId,
+    request_id: RequestId,
+    record: Record,
+}
+
 /// The configuration for Kademlia "write-back" caching after successful
 /// lookups via [`Behaviour::get_record`].
 #[derive(Debug, Clone)]
@@ -230,6 +266,10 @@ impl Config {
             caching: Caching::Enabled { max_peers: 1 },
             periodic_bootstrap_interval: Some(Duration::from_secs(5 * 60)),
             automatic_bootstrap_throttle: Some(bootstrap::DEFAULT_AUTOMATIC_THROTTLE),
+            max_concurrent_record_admissions: 16,
+            record_admission_timeout: Duration::from_secs(10),
+            newer_protocol_names: Vec::new(),
+            protocol_skew_threshold: 0.5,
         }
     }
 
@@ -306,6 +346,24 @@ impl Config {
         self
     }
 
+    /// Sets the upper bound of adaptive request timeouts, enabling them if `Some`.
+    ///
+    /// When enabled, the response latency of every peer is tracked and each
+    /// request of an iterative query times out after a duration derived from
+    /// the latency previously observed for the peer, or for all peers if the
+    /// peer did not respond yet, but never later than the given bound. Slow
+    /// peers are thus given more time while unresponsive peers are skipped
+    /// quickly. Timed out peers can still deliver results, see
+    /// [`Config::set_query_timeout`] for the timeout of the whole query.
+    ///
+    /// Use [`Behaviour::peer_latency`] to inspect the estimates.
+    ///
+    /// The default is `None`, i.e. every request times out after 10 seconds.
+    pub fn set_adaptive_peer_timeout(&mut self, max_timeout: Option<Duration>) -> &mut Self {
+        self.query_config.adaptive_peer_timeout = max_timeout;
+        self
+    }
+
     /// Sets the TTL for stored records.
     ///
     /// The TTL should be significantly longer than the (re-)publication
@@ -329,6 +387,49 @@ impl Config {
         self
     }
 
+    /// Sets the maximum number of inbound records that are concurrently being checked by the
+    /// hook registered via [`Behaviour::set_record_admission`].
+    ///
+    /// Inbound records exceeding this limit are rejected.
+    /// Defaults to 16.
+    pub fn set_max_concurrent_record_admissions(&mut self, max: usize) -> &mut Self {
+        self.max_concurrent_record_admissions = max;
+        self
+    }
+
+    /// Sets the time after which an inbound record is rejected if the hook registered via
+    /// [`Behaviour::set_record_admission`] did not decide on it.
+    ///
+    /// Defaults to 10 seconds.
+    pub fn set_record_admission_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.record_admission_timeout = timeout;
+        self
+    }
+
+    /// Sets the protocol names of newer revisions of the Kademlia protocol that
+    /// the local node does not speak (yet).
+    ///
+    /// Peers in the routing table announcing support for any of these names, e.g. via
+    /// identify, are tracked and an [`Event::ProtocolSkew`] is emitted once their share
+    /// reaches [`Config::set_protocol_skew_threshold`]. This indicates that the local
+    /// node should be upgraded, e.g. by adding the newer name to its protocol names.
+    ///
+    /// Empty by default, i.e. protocol skew is not tracked.
+    pub fn set_newer_protocol_names(&mut self, names: Vec<StreamProtocol>) -> &mut Self {
+        self.newer_protocol_names = names;
+        self
+    }
+
+    /// Sets the share of peers in the routing table, between `0.0` and `1.0`, that must
+    /// support one of the [newer protocol names](Config::set_newer_protocol_names) for an
+    /// [`Event::ProtocolSkew`] to be emitted.
+    ///
+    /// Defaults to `0.5`.
+    pub fn set_protocol_skew_threshold(&mut self, threshold: f64) -> &mut Self {
+        self.protocol_skew_threshold = threshold;
+        self
+    }
+
     /// Sets the (re-)replication interval for stored records.
     ///
     /// Periodic replication of stored records ensures that the records
@@ -505,9 +606,42 @@ where
                 config.periodic_bootstrap_interval,
                 config.automatic_bootstrap_throttle,
             ),
+            record_admission: None,
+            pending_admissions: futures_bounded::FuturesTupleSet::new(
+                config.record_admission_timeout,
+                config.max_concurrent_record_admissions,
+            ),
+            newer_protocol_names: config.newer_protocol_names,
+            protocol_skew_threshold: config.protocol_skew_threshold,
+            peers_with_newer_protocol: Default::default(),
+            protocol_skew_reported: false,
         }
     }
 
+    /// Sets a hook that is consulted before an inbound record is stored or, with
+    /// [`StoreInserts::FilterBoth`], reported via [`InboundRequest::PutRecord`].
+    ///
+    /// The returned future resolves to `true` if the record should be admitted. Records are
+    /// rejected, and the request of the remote is reset, if the future resolves to `false`, does
+    /// not resolve within [`Config::set_record_admission_timeout`] or if more than
+    /// [`Config::set_max_concurrent_record_admissions`] records are awaiting a decision.
+    pub fn set_record_admission<F, Fut>(&mut self, mut admission: F)
+    where
+        F: FnMut(PeerId, &Record) -> Fut + Send + 'static,
+        Fut: Future<Output = bool> + Send + 'static,
+    {
+        self.record_admission = Some(Box::new(move |peer, record| {
+            Box::pin(admission(peer, record))
+        }));
+    }
+
+    /// Removes the hook set via [`Behaviour::set_record_admission`].
+    ///
+    /// Records that are currently awaiting a decision are still processed.
+    pub fn remove_record_admission(&mut self) {
+        self.record_admission = None;
+    }
+
     /// Gets an iterator over immutable references to all running queries.
     pub fn iter_queries(&self) -> impl Iterator<Item = QueryRef<'_>> {
         self.queries.iter().filter_map(|query| {
@@ -552,6 +686,24 @@ where
         })
     }
 
+    /// Returns the response latency estimated for `peer` and the resulting
+    /// request timeout, see [`Config::set_adaptive_peer_timeout`].
+    ///
+    /// Returns `None` if adaptive request timeouts are disabled or no response
+    /// of the peer was observed yet.
+    pub fn peer_latency(&self, peer: &PeerId) -> Option<PeerLatency> {
+        self.queries.peer_latency(peer)
+    }
+
+    /// Returns the response latency estimated over all peers, which applies
+    /// to peers without an estimate of their own.
+    ///
+    /// Returns `None` if adaptive request timeouts are disabled or no response
+    /// was observed yet.
+    pub fn global_latency(&self) -> Option<PeerLatency> {
+        self.queries.global_latency()
+    }
+
     /// Adds a known listen address of a peer participating in the DHT to the
     /// routing table.
     ///
@@ -620,6 +772,7 @@ where
                                     .expect("Not kbucket::Entry::SelfEntry."),
                             },
                         ));
+                        self.check_protocol_skew();
                         RoutingUpdate::Success
                     }
                     kbucket::InsertResult::Full => {
@@ -655,7 +808,7 @@ where
     ) -> Option<kbucket::EntryView<kbucket::Key<PeerId>, Addresses>> {
         let address = &address.to_owned().with_p2p(*peer).ok()?;
         let key = kbucket::Key::from(*peer);
-        match self.kbuckets.entry(&key)? {
+        let removed = match self.kbuckets.entry(&key)? {
             kbucket::Entry::Present(mut entry, _) => {
                 if entry.value().remove(address).is_err() {
                     Some(entry.remove()) // it is the last address, thus remove the peer.
@@ -671,7 +824,11 @@ where
                 }
             }
             kbucket::Entry::Absent(..) => None,
+        };
+        if removed.is_some() {
+            self.on_removed_from_routing_table(peer);
         }
+        removed
     }
 
     /// Removes a peer from the routing table.
@@ -683,11 +840,15 @@ where
         peer: &PeerId,
     ) -> Option<kbucket::EntryView<kbucket::Key<PeerId>, Addresses>> {
         let key = kbucket::Key::from(*peer);
-        match self.kbuckets.entry(&key)? {
+        let removed = match self.kbuckets.entry(&key)? {
             kbucket::Entry::Present(entry, _) => Some(entry.remove()),
             kbucket::Entry::Pending(entry, _) => Some(entry.remove()),
             kbucket::Entry::Absent(..) => None,
+        };
+        if removed.is_some() {
+            self.on_removed_from_routing_table(peer);
         }
+        removed
     }
 
     /// Returns an iterator over all non-empty buckets in the routing table.
@@ -697,6 +858,22 @@ where
         self.kbuckets.iter().filter(|b| !b.is_empty())
     }
 
+    /// Adds the addresses of all peers in the routing table to `peer_addresses`, e.g. to seed
+    /// reconnect candidates with the peers the DHT learned about during the session.
+    ///
+    /// Returns the number of addresses that were not in `peer_addresses` before.
+    pub fn export_addresses(&self, peer_addresses: &mut PeerAddresses) -> usize {
+        let mut added = 0;
+        for (key, addresses) in self.kbuckets.entries() {
+            for address in addresses.iter() {
+                if peer_addresses.add(*key.preimage(), address.clone()) {
+                    added += 1;
+                }
+            }
+        }
+        added
+    }
+
     /// Returns the k-bucket for the distance to the given key.
     ///
     /// Returns `None` if the given key refers to the local key.
@@ -1347,6 +1524,7 @@ where
                                         .expect("Not kbucket::Entry::SelfEntry."),
                                 };
                                 self.queued_events.push_back(ToSwarm::GenerateEvent(event));
+                                self.check_protocol_skew();
                             }
                             kbucket::InsertResult::Full => {
                                 tracing::debug!(
@@ -1773,7 +1951,7 @@ where
         source: PeerId,
         connection: ConnectionId,
         request_id: RequestId,
-        mut record: Record,
+        record: Record,
     ) {
         if record.publisher.as_ref() == Some(self.kbuckets.local_key().preimage()) {
             // If the (alleged) publisher is the local node, do nothing. The record of
@@ -1791,6 +1969,39 @@ where
             return;
         }
 
+        if let Some(admission) = self.record_admission.as_mut() {
+            let future = admission(source, &record);
+            let pending = PendingAdmission {
+                source,
+                connection,
+                request_id,
+                record,
+            };
+            if let Err((_, pending)) = self.pending_admissions.try_push(future, pending) {
+                tracing::debug!(
+                    record=?pending.record.key,
+                    "Record not stored: too many records awaiting admission"
+                );
+                self.queued_events.push_back(ToSwarm::NotifyHandler {
+                    peer_id: pending.source,
+                    handler: NotifyHandler::One(pending.connection),
+                    event: HandlerIn::Reset(pending.request_id),
+                });
+            }
+            return;
+        }
+
+        self.record_admitted(source, connection, request_id, record);
+    }
+
+    /// Processes a record received from a peer that has been admitted for storage.
+    fn record_admitted(
+        &mut self,
+        source: PeerId,
+        connection: ConnectionId,
+        request_id: RequestId,
+        mut record: Record,
+    ) {
         let now = Instant::now();
 
         // Calculate the expiration exponentially inversely proportional to the
@@ -2101,6 +2312,56 @@ where
             }
             self.connection_updated(peer_id, None, NodeStatus::Disconnected);
             self.connected_peers.remove(&peer_id);
+
+            // The protocols of a disconnected peer are reported again once it reconnects.
+            if self.peers_with_newer_protocol.remove(&peer_id) {
+                self.check_protocol_skew();
+            }
+        }
+    }
+
+    /// Stops counting a peer that left the routing table towards the protocol skew.
+    fn on_removed_from_routing_table(&mut self, peer: &PeerId) {
+        self.peers_with_newer_protocol.remove(peer);
+        self.check_protocol_skew();
+    }
+
+    /// Emits an [`Event::ProtocolSkew`] if the share of peers in the routing table
+    /// supporting a newer protocol revision reached the configured threshold.
+    fn check_protocol_skew(&mut self) {
+        if self.newer_protocol_names.is_empty() {
+            return;
+        }
+
+        let mut routing_table_peers = 0;
+        let mut newer_protocol_peers = 0;
+        for bucket in self.kbuckets.iter() {
+            for entry in bucket.iter() {
+                routing_table_peers += 1;
+                if self
+                    .peers_with_newer_protocol
+                    .contains(entry.node.key.preimage())
+                {
+                    newer_protocol_peers += 1;
+                }
+            }
+        }
+
+        if routing_table_peers == 0
+            || (newer_protocol_peers as f64 / routing_table_peers as f64)
+                < self.protocol_skew_threshold
+        {
+            self.protocol_skew_reported = false;
+            return;
+        }
+
+        if !self.protocol_skew_reported {
+            self.protocol_skew_reported = true;
+            self.queued_events
+                .push_back(ToSwarm::GenerateEvent(Event::ProtocolSkew {
+                    newer_protocol_peers,
+                    routing_table_peers,
+                }));
         }
     }
 
@@ -2152,6 +2413,7 @@ where
 
         let mut handler = Handler::new(
             self.protocol_config.clone(),
+            self.newer_protocol_names.clone(),
             connected_point,
             peer,
             self.mode,
@@ -2175,6 +2437,7 @@ where
 
         let mut handler = Handler::new(
             self.protocol_config.clone(),
+            self.newer_protocol_names.clone(),
             connected_point,
             peer,
             self.mode,
@@ -2247,6 +2510,15 @@ where
                 self.connection_updated(source, address, NodeStatus::Disconnected);
             }
 
+            HandlerEvent::NewerProtocolSupport { supported } => {
+                if supported {
+                    self.peers_with_newer_protocol.insert(source);
+                } else {
+                    self.peers_with_newer_protocol.remove(&source);
+                }
+                self.check_protocol_skew();
+            }
+
             HandlerEvent::FindNodeReq { key, request_id } => {
                 let closer_peers = self.find_closest(&kbucket::Key::new(key), &source);
 
@@ -2532,6 +2804,35 @@ where
             }
         }
 
+        // Process inbound records for which the admission hook decided.
+        while let Poll::Ready((result, pending)) = self.pending_admissions.poll_unpin(cx) {
+            let PendingAdmission {
+                source,
+                connection,
+                request_id,
+                record,
+            } = pending;
+            match result {
+                Ok(true) => self.record_admitted(source, connection, request_id, record),
+                Ok(false) => {
+                    tracing::debug!(record=?record.key, "Record not stored: rejected by admission");
+                    self.queued_events.push_back(ToSwarm::NotifyHandler {
+                        peer_id: source,
+                        handler: NotifyHandler::One(connection),
+                        event: HandlerIn::Reset(request_id),
+                    });
+                }
+                Err(_) => {
+                    tracing::debug!(record=?record.key, "Record not stored: admission timed out");
+                    self.queued_events.push_back(ToSwarm::NotifyHandler {
+                        peer_id: source,
+                        handler: NotifyHandler::One(connection),
+                        event: HandlerIn::Reset(request_id),
+                    });
+                }
+            }
+        }
+
         loop {
             // Drain queued events first.
             if let Some(event) = self.queued_events.pop_front() {
@@ -2541,6 +2842,11 @@ where
             // Drain applied pending entries from the routing table.
             if let Some(entry) = self.kbuckets.take_applied_pending() {
                 let kbucket::Node { key, value } = entry.inserted;
+                let old_peer = entry.evicted.map(|n| n.key.into_preimage());
+                match &old_peer {
+                    Some(evicted) => self.on_removed_from_routing_table(evicted),
+                    None => self.check_protocol_skew(),
+                }
                 let event = Event::RoutingUpdated {
                     bucket_range: self
                         .kbuckets
@@ -2550,7 +2856,7 @@ where
                     peer: key.into_preimage(),
                     is_new_peer: true,
                     addresses: value,
-                    old_peer: entry.evicted.map(|n| n.key.into_preimage()),
+                    old_peer,
                 };
                 return Poll::Ready(ToSwarm::GenerateEvent(event));
             }
@@ -2749,6 +3055,18 @@ pub enum Event {
     /// This happens in response to an external
     /// address being added or removed.
     ModeChanged { new_mode: Mode },
+
+    /// The share of peers in the routing table that support a newer revision of the
+    /// Kademlia protocol reached [`Config::set_protocol_skew_threshold`].
+    ///
+    /// See [`Config::set_newer_protocol_names`]. The event is emitted again only after
+    /// the share dropped below the threshold in the meantime.
+    ProtocolSkew {
+        /// The number of peers in the routing table supporting a newer protocol revision.
+        newer_protocol_peers: usize,
+        /// The number of peers in the routing table.
+        routing_table_peers: usize,
+    },
 }
 
 /// Information about progress events.
@@ -3099,7 +3417,7 @@ struct QueryInner {
     /// The query-specific state.
     info: QueryInfo,
     /// Addresses of peers discovered during a query.
-    addresses: FnvHashMap<PeerId, SmallVec<[Multiaddr; 8]>>,
+    addresses: HashMap<PeerId, SmallVec<[Multiaddr; 8]>>,
     /// A map of pending requests to peers.
     ///
     /// A request is pending if the targeted peer is not currently connected
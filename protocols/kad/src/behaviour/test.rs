@@ -483,6 +483,79 @@ fn get_record_not_found() {
     }))
 }
 
+#[test]
+fn put_record_admission() {
+    let mut swarms = build_nodes(2);
+
+    let swarm_ids: Vec<_> = swarms
+        .iter()
+        .map(|(_addr, swarm)| *swarm.local_peer_id())
+        .collect();
+
+    let second = swarms[1].0.clone();
+    swarms[0]
+        .1
+        .behaviour_mut()
+        .add_address(&swarm_ids[1], second);
+    swarms[1]
+        .1
+        .behaviour_mut()
+        .set_record_admission(|_, record| {
+            let admit = record.value == b"good";
+            async move {
+                Delay::new(Duration::from_millis(10)).await;
+                admit
+            }
+        });
+
+    let mut swarms = swarms
+        .into_iter()
+        .map(|(_addr, swarm)| swarm)
+        .collect::<Vec<_>>();
+
+    for (value, admitted) in [(b"good".to_vec(), true), (b"bad".to_vec(), false)] {
+        let record = Record::new(random_multihash(), value);
+        let qid = swarms[0]
+            .behaviour_mut()
+            .put_record(record.clone(), Quorum::One)
+            .unwrap();
+
+        block_on(poll_fn(|ctx| {
+            for swarm in &mut swarms {
+                loop {
+                    match swarm.poll_next_unpin(ctx) {
+                        Poll::Ready(Some(SwarmEvent::Behaviour(
+                            Event::OutboundQueryProgressed {
+                                id,
+                                result: QueryResult::PutRecord(result),
+                                ..
+                            },
+                        ))) if id == qid => {
+                            assert_eq!(result.is_ok(), admitted);
+                            return Poll::Ready(());
+                        }
+                        // Ignore any other event.
+                        Poll::Ready(Some(_)) => (),
+                        e @ Poll::Ready(_) => panic!("Unexpected return value: {e:?}"),
+                        Poll::Pending => break,
+                    }
+                }
+            }
+
+            Poll::Pending
+        }));
+
+        assert_eq!(
+            swarms[1]
+                .behaviour_mut()
+                .store_mut()
+                .get(&record.key)
+                .is_some(),
+            admitted
+        );
+    }
+}
+
 /// A node joining a fully connected network via three (ALPHA_VALUE) bootnodes
 /// should be able to put a record to the X closest nodes of the network where X
 /// is equal to the configured replication factor.
@@ -1533,3 +1606,103 @@ fn get_providers_limit_n_2() {
 fn get_providers_limit_n_5() {
     get_providers_limit::<5>();
 }
+
+#[test]
+fn export_addresses() {
+    let (_, mut swarm) = build_node();
+    let peer = PeerId::random();
+    let address = multiaddr![Ip4([1, 2, 3, 4]), Tcp(4001u16)];
+    swarm.behaviour_mut().add_address(&peer, address.clone());
+
+    let mut peer_addresses = swarm::PeerAddresses::new(NonZeroUsize::new(10).unwrap());
+    assert_eq!(swarm.behaviour().export_addresses(&mut peer_addresses), 1);
+    assert_eq!(
+        peer_addresses.get(&peer).collect::<Vec<_>>(),
+        vec![address.with_p2p(peer).unwrap()]
+    );
+
+    // Addresses already known are not counted again.
+    assert_eq!(swarm.behaviour().export_addresses(&mut peer_addresses), 0);
+}
+
+#[test]
+fn protocol_skew_tracks_routing_table() {
+    fn take_skew_events(kademlia: &mut Behaviour<MemoryStore>) -> Vec<(usize, usize)> {
+        kademlia
+            .queued_events
+            .drain(..)
+            .filter_map(|e| match e {
+                ToSwarm::GenerateEvent(Event::ProtocolSkew {
+                    newer_protocol_peers,
+                    routing_table_peers,
+                }) => Some((newer_protocol_peers, routing_table_peers)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    let mut config = Config::new(PROTOCOL_NAME);
+    config.set_newer_protocol_names(vec![StreamProtocol::new("/ipfs/kad/2.0.0")]);
+    let local_peer_id = PeerId::random();
+    let mut kademlia =
+        Behaviour::with_config(local_peer_id, MemoryStore::new(local_peer_id), config);
+
+    let remote_peer_id = PeerId::random();
+    let connection_id = ConnectionId::new_unchecked(0);
+    let address: Multiaddr = Protocol::Memory(1).into();
+    let endpoint = ConnectedPoint::Dialer {
+        address: address.clone(),
+        role_override: Endpoint::Dialer,
+    };
+
+    kademlia.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+        peer_id: remote_peer_id,
+        connection_id,
+        endpoint: &endpoint,
+        failed_addresses: &[],
+        other_established: 0,
+    }));
+    kademlia.on_connection_handler_event(
+        remote_peer_id,
+        connection_id,
+        HandlerEvent::ProtocolConfirmed {
+            endpoint: endpoint.clone(),
+        },
+    );
+    kademlia.on_connection_handler_event(
+        remote_peer_id,
+        connection_id,
+        HandlerEvent::NewerProtocolSupport { supported: true },
+    );
+    assert_eq!(take_skew_events(&mut kademlia), vec![(1, 1)]);
+
+    // A newly inserted peer without support for the newer protocol lowers the share
+    // below the threshold, so the skew is reported again once it is reached again.
+    let other_peer_id = PeerId::random();
+    kademlia.add_address(&other_peer_id, Protocol::Memory(2).into());
+    kademlia.add_address(&PeerId::random(), Protocol::Memory(3).into());
+    assert!(take_skew_events(&mut kademlia).is_empty());
+    kademlia.remove_peer(&other_peer_id);
+    assert_eq!(take_skew_events(&mut kademlia), vec![(1, 2)]);
+
+    // Disconnected peers no longer count, even though they stay in the routing table.
+    kademlia.on_swarm_event(FromSwarm::ConnectionClosed(ConnectionClosed {
+        peer_id: remote_peer_id,
+        connection_id,
+        endpoint: &endpoint,
+        remaining_established: 0,
+    }));
+    assert!(kademlia.peers_with_newer_protocol.is_empty());
+    assert!(!kademlia.protocol_skew_reported);
+
+    // Peers leaving the routing table are pruned as well.
+    kademlia.on_connection_handler_event(
+        remote_peer_id,
+        connection_id,
+        HandlerEvent::NewerProtocolSupport { supported: true },
+    );
+    assert_eq!(take_skew_events(&mut kademlia), vec![(1, 2)]);
+    kademlia.remove_peer(&remote_peer_id);
+    assert!(kademlia.peers_with_newer_protocol.is_empty());
+    assert!(!kademlia.protocol_skew_reported);
+}
@@ -32,8 +32,8 @@ use libp2p_core::{upgrade, ConnectedPoint};
 use libp2p_identity::PeerId;
 use libp2p_swarm::handler::{ConnectionEvent, FullyNegotiatedInbound, FullyNegotiatedOutbound};
 use libp2p_swarm::{
-    ConnectionHandler, ConnectionHandlerEvent, Stream, StreamUpgradeError, SubstreamProtocol,
-    SupportedProtocols,
+    ConnectionHandler, ConnectionHandlerEvent, Stream, StreamProtocol, StreamUpgradeError,
+    SubstreamProtocol, SupportedProtocols,
 };
 use std::collections::VecDeque;
 use std::task::Waker;
@@ -53,6 +53,14 @@ pub struct Handler {
     /// Configuration of the wire protocol.
     protocol_config: ProtocolConfig,
 
+    /// Configuration of the wire protocol for outbound streams, restricted to the
+    /// protocol names supported by the remote, if known.
+    outbound_protocol_config: ProtocolConfig,
+
+    /// Protocol names of newer revisions of the Kademlia protocol, see
+    /// [`Config::set_newer_protocol_names`](crate::Config::set_newer_protocol_names).
+    newer_protocol_names: Vec<StreamProtocol>,
+
     /// In client mode, we don't accept inbound substreams.
     mode: Mode,
 
@@ -84,6 +92,9 @@ pub struct Handler {
     /// The current state of protocol confirmation.
     protocol_status: Option<ProtocolStatus>,
 
+    /// Whether the remote supports one of the `newer_protocol_names`.
+    newer_protocol_status: Option<ProtocolStatus>,
+
     remote_supported_protocols: SupportedProtocols,
 }
 
@@ -91,7 +102,7 @@ pub struct Handler {
 /// handler transitions through.
 #[derive(Debug, Copy, Clone, PartialEq)]
 struct ProtocolStatus {
-    /// Whether the remote node supports one of our kademlia protocols.
+    /// Whether the remote node supports one of the kademlia protocols.
     supported: bool,
     /// Whether we reported the state to the behaviour.
     reported: bool,
@@ -184,6 +195,9 @@ pub enum HandlerEvent {
     /// The configured protocol name(s) are not or no longer supported by the peer on the provided
     /// connection and it should be removed from the routing table.
     ProtocolNotSupported { endpoint: ConnectedPoint },
+    /// Whether the peer supports one of the protocol names of newer revisions of the Kademlia
+    /// protocol, see [`Config::set_newer_protocol_names`](crate::Config::set_newer_protocol_names).
+    NewerProtocolSupport { supported: bool },
 
     /// Request for the list of nodes whose IDs are the closest to `key`. The number of nodes
     /// returned is not specified, but should be around 20.
@@ -426,6 +440,7 @@ struct UniqueConnecId(u64);
 impl Handler {
     pub fn new(
         protocol_config: ProtocolConfig,
+        newer_protocol_names: Vec<StreamProtocol>,
         endpoint: ConnectedPoint,
         remote_peer_id: PeerId,
         mode: Mode,
@@ -448,7 +463,9 @@ impl Handler {
         }
 
         Handler {
+            outbound_protocol_config: protocol_config.clone(),
             protocol_config,
+            newer_protocol_names,
             mode,
             endpoint,
             remote_peer_id,
@@ -461,6 +478,7 @@ impl Handler {
             pending_streams: Default::default(),
             pending_messages: Default::default(),
             protocol_status: None,
+            newer_protocol_status: None,
             remote_supported_protocols: Default::default(),
         }
     }
@@ -732,6 +750,19 @@ impl ConnectionHandler for Handler {
                 _ => {}
             }
 
+            match &mut self.newer_protocol_status {
+                Some(status) if !status.reported => {
+                    status.reported = true;
+
+                    return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                        HandlerEvent::NewerProtocolSupport {
+                            supported: status.supported,
+                        },
+                    ));
+                }
+                _ => {}
+            }
+
             match self.outbound_substreams.poll_unpin(cx) {
                 Poll::Ready((Ok(Ok(Some(response))), query_id)) => {
                     return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
@@ -768,7 +799,7 @@ impl ConnectionHandler for Handler {
                 if let Some((msg, id)) = self.pending_messages.pop_front() {
                     self.queue_new_stream(id, msg);
                     return Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest {
-                        protocol: SubstreamProtocol::new(self.protocol_config.clone(), ()),
+                        protocol: SubstreamProtocol::new(self.outbound_protocol_config.clone(), ()),
                     });
                 }
             }
@@ -810,7 +841,29 @@ impl ConnectionHandler for Handler {
                     self.protocol_status = Some(compute_new_protocol_status(
                         remote_supports_our_kademlia_protocols,
                         self.protocol_status,
-                    ))
+                    ));
+
+                    // Only offer the protocol names the remote supports on outbound streams,
+                    // saving the round-trips of negotiating unsupported ones.
+                    self.outbound_protocol_config = self
+                        .protocol_config
+                        .restricted_to(self.remote_supported_protocols.iter());
+
+                    if !self.newer_protocol_names.is_empty() {
+                        let remote_supports_newer_protocols = self
+                            .remote_supported_protocols
+                            .iter()
+                            .any(|p| self.newer_protocol_names.contains(p));
+
+                        if self.newer_protocol_status.map(|s| s.supported)
+                            != Some(remote_supports_newer_protocols)
+                        {
+                            self.newer_protocol_status = Some(ProtocolStatus {
+                                supported: remote_supports_newer_protocols,
+                                reported: false,
+                            });
+                        }
+                    }
                 }
             }
             _ => {}
@@ -201,6 +201,14 @@ where
         })
     }
 
+    /// Returns an iterator over the keys and values of all entries, without applying pending
+    /// entries.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&TKey, &TVal)> {
+        self.buckets
+            .iter()
+            .flat_map(|b| b.iter().map(|(n, _)| (&n.key, &n.value)))
+    }
+
     /// Returns the bucket for the distance to the given key.
     ///
     /// Returns `None` if the given key refers to the local key.
@@ -70,7 +70,7 @@ pub use kbucket::{
     Distance as KBucketDistance, EntryView, KBucketRef, Key as KBucketKey, NodeStatus,
 };
 pub use protocol::ConnectionType;
-pub use query::QueryId;
+pub use query::{PeerLatency, QueryId};
 pub use record::{store, Key as RecordKey, ProviderRecord, Record};
 
 use libp2p_swarm::StreamProtocol;
@@ -175,6 +175,32 @@ impl ProtocolConfig {
     pub fn set_max_packet_size(&mut self, size: usize) {
         self.max_packet_size = size;
     }
+
+    /// Returns the configuration restricted to the protocol names supported by a remote,
+    /// in the order of our preference.
+    ///
+    /// All protocol names are retained if the remote supports none of them, e.g.
+    /// because its supported protocols are not known.
+    pub(crate) fn restricted_to<'a>(
+        &self,
+        supported: impl IntoIterator<Item = &'a StreamProtocol>,
+    ) -> Self {
+        let supported = supported.into_iter().collect::<Vec<_>>();
+        let protocol_names = self
+            .protocol_names
+            .iter()
+            .filter(|p| supported.contains(p))
+            .cloned()
+            .collect::<Vec<_>>();
+        if protocol_names.is_empty() {
+            return self.clone();
+        }
+
+        ProtocolConfig {
+            protocol_names,
+            max_packet_size: self.max_packet_size,
+        }
+    }
 }
 
 impl Default for ProtocolConfig {
@@ -667,6 +693,25 @@ mod tests {
         assert_eq!(peer.multiaddrs, vec![valid_multiaddr])
     }
 
+    #[test]
+    fn restrict_protocol_names_to_supported() {
+        let old = StreamProtocol::new("/ipfs/kad/1.0.0");
+        let new = StreamProtocol::new("/ipfs/kad/2.0.0");
+        let other = StreamProtocol::new("/other/1.0.0");
+
+        let mut config = ProtocolConfig::new(new.clone());
+        config.protocol_names.push(old.clone());
+
+        let restricted = config.restricted_to([&other, &old]);
+        assert_eq!(restricted.protocol_names(), [old.clone()]);
+
+        let restricted = config.restricted_to([&old, &new]);
+        assert_eq!(restricted.protocol_names(), [new.clone(), old.clone()]);
+
+        let restricted = config.restricted_to([&other]);
+        assert_eq!(restricted.protocol_names(), [new, old]);
+    }
+
     /*// TODO: restore
     use self::libp2p_tcp::TcpTransport;
     use self::tokio::runtime::current_thread::Runtime;
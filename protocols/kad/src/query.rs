@@ -18,8 +18,12 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+mod latency;
 mod peers;
 
+pub use latency::PeerLatency;
+
+use latency::LatencyEstimator;
 use peers::closest::{
     disjoint::ClosestDisjointPeersIter, ClosestPeersIter, ClosestPeersIterConfig,
 };
@@ -32,7 +36,7 @@ use either::Either;
 use fnv::FnvHashMap;
 use instant::Instant;
 use libp2p_identity::PeerId;
-use std::{num::NonZeroUsize, time::Duration};
+use std::{collections::HashMap, num::NonZeroUsize, time::Duration};
 
 /// A `QueryPool` provides an aggregate state machine for driving `Query`s to completion.
 ///
@@ -43,6 +47,8 @@ pub(crate) struct QueryPool<TInner> {
     next_id: usize,
     config: QueryConfig,
     queries: FnvHashMap<QueryId, Query<TInner>>,
+    /// The response latencies observed per peer, if adaptive request timeouts are enabled.
+    latency: Option<LatencyEstimator>,
 }
 
 /// The observable states emitted by [`QueryPool::poll`].
@@ -63,6 +69,7 @@ impl<TInner> QueryPool<TInner> {
     pub(crate) fn new(config: QueryConfig) -> Self {
         QueryPool {
             next_id: 0,
+            latency: config.adaptive_peer_timeout.map(LatencyEstimator::new),
             config,
             queries: Default::default(),
         }
@@ -73,6 +80,18 @@ impl<TInner> QueryPool<TInner> {
         &self.config
     }
 
+    /// Returns the latency estimate for `peer`, if adaptive request timeouts
+    /// are enabled and a response of the peer was observed.
+    pub(crate) fn peer_latency(&self, peer: &PeerId) -> Option<PeerLatency> {
+        self.latency.as_ref()?.peer_latency(peer)
+    }
+
+    /// Returns the latency estimate over the responses of all peers, if adaptive
+    /// request timeouts are enabled and any response was observed.
+    pub(crate) fn global_latency(&self) -> Option<PeerLatency> {
+        self.latency.as_ref()?.global_latency()
+    }
+
     /// Returns an iterator over the queries in the pool.
     pub(crate) fn iter(&self) -> impl Iterator<Item = &Query<TInner>> {
         self.queries.values()
@@ -134,11 +153,14 @@ impl<TInner> QueryPool<TInner> {
         T: Into<KeyBytes> + Clone,
         I: IntoIterator<Item = Key<PeerId>>,
     {
-        let cfg = ClosestPeersIterConfig {
+        let mut cfg = ClosestPeersIterConfig {
             num_results: self.config.replication_factor,
             parallelism: self.config.parallelism,
             ..ClosestPeersIterConfig::default()
         };
+        if let Some(max_timeout) = self.config.adaptive_peer_timeout {
+            cfg.peer_timeout = max_timeout;
+        }
 
         let peer_iter = if self.config.disjoint_query_paths {
             QueryPeerIter::ClosestDisjoint(ClosestDisjointPeersIter::with_config(
@@ -175,6 +197,11 @@ impl<TInner> QueryPool<TInner> {
         let mut waiting = None;
 
         for (&query_id, query) in self.queries.iter_mut() {
+            if let Some(latency) = &mut self.latency {
+                for (peer, rtt) in query.latencies.drain(..) {
+                    latency.record(peer, rtt, now);
+                }
+            }
             query.stats.start = query.stats.start.or(Some(now));
             match query.next(now) {
                 PeersIterState::Finished => {
@@ -198,6 +225,10 @@ impl<TInner> QueryPool<TInner> {
 
         if let Some((query_id, peer_id)) = waiting {
             let query = self.queries.get_mut(&query_id).expect("s.a.");
+            if let Some(latency) = &self.latency {
+                query.set_peer_deadline(&peer_id, now + latency.timeout(&peer_id));
+                query.requests.insert(peer_id, now);
+            }
             return QueryPoolState::Waiting(Some((query, peer_id)));
         }
 
@@ -250,6 +281,10 @@ pub(crate) struct QueryConfig {
     ///
     /// See [`crate::behaviour::Config::disjoint_query_paths`] for details.
     pub(crate) disjoint_query_paths: bool,
+    /// The upper bound of adaptive request timeouts, if enabled.
+    ///
+    /// See [`crate::behaviour::Config::set_adaptive_peer_timeout`] for details.
+    pub(crate) adaptive_peer_timeout: Option<Duration>,
 }
 
 impl Default for QueryConfig {
@@ -259,6 +294,7 @@ impl Default for QueryConfig {
             replication_factor: NonZeroUsize::new(K_VALUE.get()).expect("K_VALUE > 0"),
             parallelism: ALPHA_VALUE,
             disjoint_query_paths: false,
+            adaptive_peer_timeout: None,
         }
     }
 }
@@ -271,6 +307,11 @@ pub(crate) struct Query<TInner> {
     peer_iter: QueryPeerIter,
     /// Execution statistics of the query.
     stats: QueryStats,
+    /// The instants at which the pending requests were sent, if adaptive
+    /// request timeouts are enabled.
+    requests: HashMap<PeerId, Instant>,
+    /// The response latencies observed since the query was last polled.
+    latencies: Vec<(PeerId, Duration)>,
     /// The opaque inner query state.
     pub(crate) inner: TInner,
 }
@@ -290,6 +331,8 @@ impl<TInner> Query<TInner> {
             inner,
             peer_iter,
             stats: QueryStats::empty(),
+            requests: HashMap::default(),
+            latencies: Vec::new(),
         }
     }
 
@@ -305,6 +348,7 @@ impl<TInner> Query<TInner> {
 
     /// Informs the query that the attempt to contact `peer` failed.
     pub(crate) fn on_failure(&mut self, peer: &PeerId) {
+        self.requests.remove(peer);
         let updated = match &mut self.peer_iter {
             QueryPeerIter::Closest(iter) => iter.on_failure(peer),
             QueryPeerIter::ClosestDisjoint(iter) => iter.on_failure(peer),
@@ -322,6 +366,11 @@ impl<TInner> Query<TInner> {
     where
         I: IntoIterator<Item = PeerId>,
     {
+        // Responses arriving after the request timed out still count
+        // towards the latency estimate of the peer.
+        if let Some(sent) = self.requests.remove(peer) {
+            self.latencies.push((*peer, Instant::now() - sent));
+        }
         let updated = match &mut self.peer_iter {
             QueryPeerIter::Closest(iter) => iter.on_success(peer, new_peers),
             QueryPeerIter::ClosestDisjoint(iter) => iter.on_success(peer, new_peers),
@@ -332,6 +381,21 @@ impl<TInner> Query<TInner> {
         }
     }
 
+    /// Sets the instant after which the query considers `peer` unresponsive.
+    ///
+    /// Queries to a fixed set of peers wait for every peer until the query times out.
+    fn set_peer_deadline(&mut self, peer: &PeerId, deadline: Instant) {
+        match &mut self.peer_iter {
+            QueryPeerIter::Closest(iter) => {
+                iter.set_peer_deadline(peer, deadline);
+            }
+            QueryPeerIter::ClosestDisjoint(iter) => {
+                iter.set_peer_deadline(peer, deadline);
+            }
+            QueryPeerIter::Fixed(_) => {}
+        }
+    }
+
     /// Advances the state of the underlying peer iterator.
     fn next(&mut self, now: Instant) -> PeersIterState<'_> {
         let state = match &mut self.peer_iter {
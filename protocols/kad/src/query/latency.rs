@@ -0,0 +1,197 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use instant::Instant;
+use libp2p_identity::PeerId;
+use std::{collections::HashMap, time::Duration};
+
+/// The lower bound of adaptive request timeouts, guarding against
+/// timeouts shorter than the jitter of otherwise fast peers.
+const MIN_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// The maximum number of peers for which latency estimates are kept.
+const MAX_TRACKED_PEERS: usize = 1024;
+
+/// A snapshot of the response latency estimated for a peer, see
+/// [`Behaviour::peer_latency`](crate::Behaviour::peer_latency).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerLatency {
+    /// The smoothed response latency.
+    pub smoothed: Duration,
+    /// The mean deviation of the response latency.
+    pub variance: Duration,
+    /// The number of responses the estimate is based on.
+    pub samples: u32,
+    /// The timeout applied to new requests to the peer.
+    pub timeout: Duration,
+}
+
+/// Estimates per-peer request timeouts from observed response latencies,
+/// following the retransmission timer computation of RFC 6298.
+///
+/// Peers without samples fall back to an estimate over all responses,
+/// and all timeouts are capped by the configured maximum.
+pub(crate) struct LatencyEstimator {
+    max_timeout: Duration,
+    global: Option<Estimate>,
+    peers: HashMap<PeerId, Estimate>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Estimate {
+    smoothed: Duration,
+    variance: Duration,
+    samples: u32,
+    last_sample: Instant,
+}
+
+impl Estimate {
+    fn new(rtt: Duration, now: Instant) -> Self {
+        Estimate {
+            smoothed: rtt,
+            variance: rtt / 2,
+            samples: 1,
+            last_sample: now,
+        }
+    }
+
+    fn update(&mut self, rtt: Duration, now: Instant) {
+        let deviation = if self.smoothed > rtt {
+            self.smoothed - rtt
+        } else {
+            rtt - self.smoothed
+        };
+        self.variance = (self.variance * 3 + deviation) / 4;
+        self.smoothed = (self.smoothed * 7 + rtt) / 8;
+        self.samples = self.samples.saturating_add(1);
+        self.last_sample = now;
+    }
+
+    fn timeout(&self, max: Duration) -> Duration {
+        (self.smoothed + self.variance * 4).clamp(MIN_TIMEOUT.min(max), max)
+    }
+}
+
+impl LatencyEstimator {
+    pub(crate) fn new(max_timeout: Duration) -> Self {
+        LatencyEstimator {
+            max_timeout,
+            global: None,
+            peers: HashMap::default(),
+        }
+    }
+
+    /// Records the time `peer` took to respond to a request.
+    pub(crate) fn record(&mut self, peer: PeerId, rtt: Duration, now: Instant) {
+        match &mut self.global {
+            Some(estimate) => estimate.update(rtt, now),
+            None => self.global = Some(Estimate::new(rtt, now)),
+        }
+        if let Some(estimate) = self.peers.get_mut(&peer) {
+            estimate.update(rtt, now);
+            return;
+        }
+        if self.peers.len() >= MAX_TRACKED_PEERS {
+            let stalest = self
+                .peers
+                .iter()
+                .min_by_key(|(_, estimate)| estimate.last_sample)
+                .map(|(peer, _)| *peer);
+            if let Some(stalest) = stalest {
+                self.peers.remove(&stalest);
+            }
+        }
+        self.peers.insert(peer, Estimate::new(rtt, now));
+    }
+
+    /// Returns the timeout for a new request to `peer`.
+    pub(crate) fn timeout(&self, peer: &PeerId) -> Duration {
+        self.peers
+            .get(peer)
+            .or(self.global.as_ref())
+            .map_or(self.max_timeout, |estimate| {
+                estimate.timeout(self.max_timeout)
+            })
+    }
+
+    /// Returns the latency estimate for `peer`, if any response was observed.
+    pub(crate) fn peer_latency(&self, peer: &PeerId) -> Option<PeerLatency> {
+        self.peers.get(peer).map(|estimate| self.snapshot(estimate))
+    }
+
+    /// Returns the latency estimate over the responses of all peers.
+    pub(crate) fn global_latency(&self) -> Option<PeerLatency> {
+        self.global.as_ref().map(|estimate| self.snapshot(estimate))
+    }
+
+    fn snapshot(&self, estimate: &Estimate) -> PeerLatency {
+        PeerLatency {
+            smoothed: estimate.smoothed,
+            variance: estimate.variance,
+            samples: estimate.samples,
+            timeout: estimate.timeout(self.max_timeout),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeouts_follow_observed_latency() {
+        let max = Duration::from_secs(10);
+        let mut estimator = LatencyEstimator::new(max);
+        let fast = PeerId::random();
+        let slow = PeerId::random();
+        let unknown = PeerId::random();
+        let now = Instant::now();
+
+        assert_eq!(estimator.timeout(&unknown), max);
+
+        for _ in 0..20 {
+            estimator.record(fast, Duration::from_millis(50), now);
+            estimator.record(slow, Duration::from_secs(4), now);
+        }
+
+        assert_eq!(estimator.timeout(&fast), MIN_TIMEOUT);
+        let slow_timeout = estimator.timeout(&slow);
+        assert!(slow_timeout >= Duration::from_secs(4) && slow_timeout < max);
+        assert_eq!(estimator.peer_latency(&slow).unwrap().samples, 20);
+
+        // Unknown peers use the estimate over all peers.
+        assert_eq!(
+            estimator.timeout(&unknown),
+            estimator.global_latency().unwrap().timeout
+        );
+        assert!(estimator.peer_latency(&unknown).is_none());
+    }
+
+    #[test]
+    fn timeouts_are_capped() {
+        let max = Duration::from_secs(2);
+        let mut estimator = LatencyEstimator::new(max);
+        let peer = PeerId::random();
+
+        estimator.record(peer, Duration::from_secs(5), Instant::now());
+
+        assert_eq!(estimator.timeout(&peer), max);
+    }
+}
@@ -272,6 +272,26 @@ impl ClosestPeersIter {
         true
     }
 
+    /// Sets the instant after which the iterator considers `peer` unresponsive,
+    /// in place of the configured `peer_timeout`.
+    ///
+    /// Returns `false` if the iterator is not currently waiting for a result
+    /// from `peer`, in which case calling this function has no effect.
+    pub fn set_peer_deadline(&mut self, peer: &PeerId, deadline: Instant) -> bool {
+        let distance = Key::from(*peer).distance(&self.target);
+
+        match self.closest_peers.get_mut(&distance) {
+            Some(Peer {
+                state: PeerState::Waiting(timeout),
+                ..
+            }) => {
+                *timeout = deadline;
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Returns the list of peers for which the iterator is currently waiting
     /// for results.
     pub fn waiting(&self) -> impl Iterator<Item = &PeerId> {
@@ -786,6 +806,27 @@ mod tests {
         QuickCheck::new().tests(10).quickcheck(prop as fn(_) -> _)
     }
 
+    #[test]
+    fn peer_deadline() {
+        fn prop(mut iter: ClosestPeersIter) {
+            let now = Instant::now();
+            let peer = match iter.next(now) {
+                PeersIterState::Waiting(Some(id)) => id.into_owned(),
+                _ => panic!("Expected iterator to yield a peer to query."),
+            };
+
+            let deadline = now + iter.config.peer_timeout / 2;
+            assert!(iter.set_peer_deadline(&peer, deadline));
+
+            // The peer is unresponsive once the new deadline passed.
+            let _ = iter.next(deadline);
+            assert!(!iter.is_waiting(&peer));
+            assert!(!iter.set_peer_deadline(&peer, deadline));
+        }
+
+        QuickCheck::new().tests(10).quickcheck(prop as fn(_))
+    }
+
     #[test]
     fn without_success_try_up_to_k_peers() {
         fn prop(mut iter: ClosestPeersIter) {
@@ -186,6 +186,19 @@ impl ClosestDisjointPeersIter {
         updated
     }
 
+    /// Sets the instant after which the iterator that contacted `peer`
+    /// considers it unresponsive.
+    ///
+    /// See [`ClosestPeersIter::set_peer_deadline`] for details.
+    pub(crate) fn set_peer_deadline(&mut self, peer: &PeerId, deadline: Instant) -> bool {
+        match self.contacted_peers.get(peer) {
+            Some(PeerState { initiated_by, .. }) => {
+                self.iters[*initiated_by].set_peer_deadline(peer, deadline)
+            }
+            None => false,
+        }
+    }
+
     pub(crate) fn next(&mut self, now: Instant) -> PeersIterState<'_> {
         let mut state = None;
 
@@ -20,8 +20,11 @@
 
 use super::*;
 
-use fnv::FnvHashMap;
-use std::{collections::hash_map::Entry, num::NonZeroUsize, vec};
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    num::NonZeroUsize,
+    vec,
+};
 
 /// A peer iterator for a fixed set of peers.
 pub(crate) struct FixedPeersIter {
@@ -29,7 +32,7 @@ pub(crate) struct FixedPeersIter {
     parallelism: NonZeroUsize,
 
     /// The state of peers emitted by the iterator.
-    peers: FnvHashMap<PeerId, PeerState>,
+    peers: HashMap<PeerId, PeerState>,
 
     /// The backlog of peers that can still be emitted.
     iter: vec::IntoIter<PeerId>,
@@ -66,7 +69,7 @@ impl FixedPeersIter {
 
         Self {
             parallelism,
-            peers: FnvHashMap::default(),
+            peers: HashMap::default(),
             iter: peers.into_iter(),
             state: State::Waiting { num_waiting: 0 },
         }
@@ -20,7 +20,7 @@
 
 mod memory;
 
-pub use memory::{MemoryStore, MemoryStoreConfig};
+pub use memory::{EvictionPolicy, MemoryStore, MemoryStoreConfig, MemoryStoreStats};
 use thiserror::Error;
 
 use super::*;
@@ -44,6 +44,10 @@ pub enum Error {
     /// The store cannot store this value because it is too large.
     #[error("the value is too large to be stored")]
     ValueTooLarge,
+
+    /// The store is at capacity w.r.t. the total size of stored records in bytes.
+    #[error("the store cannot contain any more bytes")]
+    MaxBytes,
 }
 
 /// Trait for types implementing a record store.
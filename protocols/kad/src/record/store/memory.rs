@@ -23,7 +23,7 @@ use super::*;
 use crate::kbucket;
 use smallvec::SmallVec;
 use std::collections::{hash_map, hash_set, HashMap, HashSet};
-use std::iter;
+use std::{iter, mem};
 
 /// In-memory implementation of a `RecordStore`.
 pub struct MemoryStore {
@@ -39,6 +39,12 @@ pub struct MemoryStore {
     ///
     /// Must be kept in sync with `providers`.
     provided: HashSet<ProviderRecord>,
+    /// The approximate size of all stored records and provider records in bytes.
+    ///
+    /// Must be kept in sync with `records` and `providers`.
+    bytes: usize,
+    /// The number of records evicted to stay within [`MemoryStoreConfig::max_bytes`].
+    evicted_records: u64,
 }
 
 /// Configuration for a `MemoryStore`.
@@ -55,6 +61,40 @@ pub struct MemoryStoreConfig {
     /// The maximum number of provider records for which the
     /// local node is the provider.
     pub max_provided_keys: usize,
+    /// The maximum approximate size of all records and provider records in bytes, if any.
+    ///
+    /// Records vary considerably in size, making [`MemoryStoreConfig::max_records`] a poor
+    /// protection against memory exhaustion on its own.
+    pub max_bytes: Option<usize>,
+    /// What to do with a new record exceeding [`MemoryStoreConfig::max_bytes`].
+    pub eviction_policy: EvictionPolicy,
+}
+
+/// What a [`MemoryStore`] does with a new record exceeding [`MemoryStoreConfig::max_bytes`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Reject the new record with [`Error::MaxBytes`].
+    #[default]
+    Reject,
+    /// Evict the records expiring soonest to make room for the new record, records without
+    /// expiration last.
+    ///
+    /// Records published by the local node are never evicted. Provider records are not evicted
+    /// and new provider records exceeding the budget are rejected with [`Error::MaxBytes`].
+    EvictSoonestExpiring,
+}
+
+/// Statistics of a [`MemoryStore`], see [`MemoryStore::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStoreStats {
+    /// The number of stored records.
+    pub records: usize,
+    /// The number of stored provider records.
+    pub provider_records: usize,
+    /// The approximate size of all records and provider records in bytes.
+    pub bytes: usize,
+    /// The number of records evicted to stay within [`MemoryStoreConfig::max_bytes`].
+    pub evicted_records: u64,
 }
 
 impl Default for MemoryStoreConfig {
@@ -64,6 +104,8 @@ impl Default for MemoryStoreConfig {
             max_value_bytes: 65 * 1024,
             max_provided_keys: 1024,
             max_providers_per_key: K_VALUE.get(),
+            max_bytes: None,
+            eviction_policy: EvictionPolicy::default(),
         }
     }
 }
@@ -82,16 +124,91 @@ impl MemoryStore {
             records: HashMap::default(),
             provided: HashSet::default(),
             providers: HashMap::default(),
+            bytes: 0,
+            evicted_records: 0,
         }
     }
 
     /// Retains the records satisfying a predicate.
-    pub fn retain<F>(&mut self, f: F)
+    pub fn retain<F>(&mut self, mut f: F)
     where
         F: FnMut(&Key, &mut Record) -> bool,
     {
-        self.records.retain(f);
+        let bytes = &mut self.bytes;
+        self.records.retain(|k, r| {
+            *bytes -= record_size(r);
+            let retain = f(k, r);
+            if retain {
+                *bytes += record_size(r);
+            }
+            retain
+        });
+    }
+
+    /// Returns statistics about the stored records.
+    pub fn stats(&self) -> MemoryStoreStats {
+        MemoryStoreStats {
+            records: self.records.len(),
+            provider_records: self.providers.values().map(|ps| ps.len()).sum(),
+            bytes: self.bytes,
+            evicted_records: self.evicted_records,
+        }
     }
+
+    /// Checks that `added` bytes fit into the budget once `removed` bytes are freed, evicting
+    /// records other than `key` if the eviction policy allows it.
+    fn reserve(&mut self, added: usize, removed: usize, key: &Key, evict: bool) -> Result<()> {
+        let Some(max_bytes) = self.config.max_bytes else {
+            return Ok(());
+        };
+        let required = (self.bytes - removed + added).saturating_sub(max_bytes);
+        if required == 0 {
+            return Ok(());
+        }
+        if !evict || self.config.eviction_policy == EvictionPolicy::Reject {
+            return Err(Error::MaxBytes);
+        }
+
+        let local_id = *self.local_key.preimage();
+        let mut candidates = self
+            .records
+            .values()
+            .filter(|r| &r.key != key && r.publisher != Some(local_id))
+            .map(|r| (r.expires, r.key.clone(), record_size(r)))
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(expires, ..)| (expires.is_none(), *expires));
+
+        let mut freed = 0;
+        let mut evicted = Vec::new();
+        for (_, key, size) in candidates {
+            if freed >= required {
+                break;
+            }
+            freed += size;
+            evicted.push(key);
+        }
+        if freed < required {
+            return Err(Error::MaxBytes);
+        }
+
+        for key in evicted {
+            self.remove(&key);
+            self.evicted_records += 1;
+        }
+        Ok(())
+    }
+}
+
+/// The approximate memory footprint of a record in bytes.
+fn record_size(r: &Record) -> usize {
+    mem::size_of::<Record>() + r.key.as_ref().len() + r.value.len()
+}
+
+/// The approximate memory footprint of a provider record in bytes.
+fn provider_record_size(r: &ProviderRecord) -> usize {
+    mem::size_of::<ProviderRecord>()
+        + r.key.as_ref().len()
+        + r.addresses.iter().map(|a| a.len()).sum::<usize>()
 }
 
 impl RecordStore for MemoryStore {
@@ -112,25 +229,25 @@ impl RecordStore for MemoryStore {
             return Err(Error::ValueTooLarge);
         }
 
-        let num_records = self.records.len();
-
-        match self.records.entry(r.key.clone()) {
-            hash_map::Entry::Occupied(mut e) => {
-                e.insert(r);
-            }
-            hash_map::Entry::Vacant(e) => {
-                if num_records >= self.config.max_records {
-                    return Err(Error::MaxRecords);
-                }
-                e.insert(r);
-            }
+        let replaced = self.records.get(&r.key).map(record_size);
+        if replaced.is_none() && self.records.len() >= self.config.max_records {
+            return Err(Error::MaxRecords);
         }
 
+        let size = record_size(&r);
+        let replaced = replaced.unwrap_or_default();
+        self.reserve(size, replaced, &r.key, true)?;
+
+        self.bytes = self.bytes - replaced + size;
+        self.records.insert(r.key.clone(), r);
+
         Ok(())
     }
 
     fn remove(&mut self, k: &Key) {
-        self.records.remove(k);
+        if let Some(r) = self.records.remove(k) {
+            self.bytes -= record_size(&r);
+        }
     }
 
     fn records(&self) -> Self::RecordsIter<'_> {
@@ -139,6 +256,13 @@ impl RecordStore for MemoryStore {
 
     fn add_provider(&mut self, record: ProviderRecord) -> Result<()> {
         let num_keys = self.providers.len();
+        let size = provider_record_size(&record);
+        let replaced = self
+            .providers
+            .get(&record.key)
+            .and_then(|ps| ps.iter().find(|p| p.provider == record.provider))
+            .map_or(0, provider_record_size);
+        self.reserve(size, replaced, &record.key, false)?;
 
         // Obtain the entry
         let providers = match self.providers.entry(record.key.clone()) {
@@ -155,6 +279,7 @@ impl RecordStore for MemoryStore {
         if let Some(i) = providers.iter().position(|p| p.provider == record.provider) {
             // In-place update of an existing provider record.
             providers.as_mut()[i] = record;
+            self.bytes = self.bytes - replaced + size;
         } else {
             // It is a new provider record for that key.
             let local_key = self.local_key;
@@ -169,9 +294,11 @@ impl RecordStore for MemoryStore {
                     self.provided.insert(record.clone());
                 }
                 providers.insert(i, record);
+                self.bytes += size;
                 // Remove the excess provider, if any.
                 if providers.len() > self.config.max_providers_per_key {
                     if let Some(p) = providers.pop() {
+                        self.bytes -= provider_record_size(&p);
                         self.provided.remove(&p);
                     }
                 }
@@ -182,6 +309,7 @@ impl RecordStore for MemoryStore {
                     self.provided.insert(record.clone());
                 }
                 providers.push(record);
+                self.bytes += size;
             }
         }
         Ok(())
@@ -202,6 +330,7 @@ impl RecordStore for MemoryStore {
             let providers = e.get_mut();
             if let Some(i) = providers.iter().position(|p| &p.provider == provider) {
                 let p = providers.remove(i);
+                self.bytes -= provider_record_size(&p);
                 self.provided.remove(&p);
             }
             if providers.is_empty() {
@@ -217,6 +346,7 @@ mod tests {
     use crate::SHA_256_MH;
     use quickcheck::*;
     use rand::Rng;
+    use std::time::Duration;
 
     fn random_multihash() -> Multihash<64> {
         Multihash::wrap(SHA_256_MH, &rand::thread_rng().gen::<[u8; 32]>()).unwrap()
@@ -319,4 +449,87 @@ mod tests {
             _ => panic!("Unexpected result"),
         }
     }
+
+    #[test]
+    fn bytes_are_accounted() {
+        fn prop(records: Vec<Record>, providers: Vec<ProviderRecord>) {
+            let mut store = MemoryStore::new(PeerId::random());
+            for r in &records {
+                store.put(r.clone()).unwrap();
+            }
+            for p in &providers {
+                store.add_provider(p.clone()).unwrap();
+            }
+            let bytes = store.records.values().map(record_size).sum::<usize>()
+                + store
+                    .providers
+                    .values()
+                    .flatten()
+                    .map(provider_record_size)
+                    .sum::<usize>();
+            assert_eq!(store.stats().bytes, bytes);
+
+            store.retain(|_, r| r.value.len() % 2 == 0);
+            for p in &providers {
+                store.remove_provider(&p.key, &p.provider);
+            }
+            let bytes = store.records.values().map(record_size).sum::<usize>();
+            assert_eq!(store.stats().bytes, bytes);
+
+            for r in &records {
+                store.remove(&r.key);
+            }
+            assert_eq!(store.stats(), MemoryStoreStats::default());
+        }
+        quickcheck(prop as fn(_, _))
+    }
+
+    #[test]
+    fn max_bytes() {
+        let record = |len: usize, expires: Option<Instant>| Record {
+            expires,
+            ..Record::new(random_multihash(), vec![0; len])
+        };
+        let budget = 3 * record_size(&record(100, None));
+        let config = MemoryStoreConfig {
+            max_bytes: Some(budget),
+            ..Default::default()
+        };
+
+        let mut store = MemoryStore::with_config(PeerId::random(), config.clone());
+        for _ in 0..3 {
+            store.put(record(100, None)).unwrap();
+        }
+        assert!(matches!(store.put(record(100, None)), Err(Error::MaxBytes)));
+
+        let mut store = MemoryStore::with_config(
+            PeerId::random(),
+            MemoryStoreConfig {
+                eviction_policy: EvictionPolicy::EvictSoonestExpiring,
+                ..config
+            },
+        );
+        let now = Instant::now();
+        let later = record(100, Some(now + Duration::from_secs(60)));
+        let sooner = record(100, Some(now + Duration::from_secs(10)));
+        let never = record(100, None);
+        for r in [&later, &sooner, &never] {
+            store.put(r.clone()).unwrap();
+        }
+
+        store.put(record(100, None)).unwrap();
+        assert!(store.get(&sooner.key).is_none());
+        assert!(store.get(&later.key).is_some());
+
+        store.put(record(150, None)).unwrap();
+        assert!(store.get(&later.key).is_none());
+        assert_eq!(store.stats().records, 2);
+        assert_eq!(store.stats().evicted_records, 3);
+        assert!(store.stats().bytes <= budget);
+
+        assert!(matches!(
+            store.put(record(budget, None)),
+            Err(Error::MaxBytes)
+        ));
+    }
 }
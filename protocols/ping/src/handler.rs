@@ -25,6 +25,7 @@ use futures_timer::Delay;
 use libp2p_core::upgrade::ReadyUpgrade;
 use libp2p_swarm::handler::{
     ConnectionEvent, DialUpgradeError, FullyNegotiatedInbound, FullyNegotiatedOutbound,
+    StreamPriority,
 };
 use libp2p_swarm::{
     ConnectionHandler, ConnectionHandlerEvent, Stream, StreamProtocol, StreamUpgradeError,
@@ -37,7 +38,6 @@ use std::{
     task::{Context, Poll},
     time::Duration,
 };
-use void::Void;
 
 /// The configuration for outbound pings.
 #[derive(Debug, Clone)]
@@ -46,6 +46,10 @@ pub struct Config {
     timeout: Duration,
     /// The duration between outbound pings.
     interval: Duration,
+    /// The size of the payload of outbound pings.
+    payload_size: usize,
+    /// The bounds of the ping interval if it adapts to connection activity.
+    adaptive_interval: Option<(Duration, Duration)>,
 }
 
 impl Config {
@@ -53,6 +57,7 @@ impl Config {
     ///
     ///   * [`Config::with_interval`] 15s
     ///   * [`Config::with_timeout`] 20s
+    ///   * [`Config::with_payload_size`] 32 bytes
     ///
     /// These settings have the following effect:
     ///
@@ -63,6 +68,8 @@ impl Config {
         Self {
             timeout: Duration::from_secs(20),
             interval: Duration::from_secs(15),
+            payload_size: protocol::PING_SIZE,
+            adaptive_interval: None,
         }
     }
 
@@ -77,6 +84,31 @@ impl Config {
         self.interval = d;
         self
     }
+
+    /// Sets the size of the payload of outbound pings in bytes, e.g. to probe the path MTU.
+    ///
+    /// Remotes echo the payload in chunks of 32 bytes, hence the size is rounded up to
+    /// a multiple of 32 bytes. The size is capped at 64 KiB.
+    pub fn with_payload_size(mut self, size: usize) -> Self {
+        let size = size.clamp(1, protocol::MAX_PAYLOAD_SIZE);
+        self.payload_size = size.div_ceil(protocol::PING_SIZE) * protocol::PING_SIZE;
+        self
+    }
+
+    /// Lets the ping interval adapt to the activity on the connection, within `min` and `max`.
+    ///
+    /// Starting from the interval set via [`Config::with_interval`], the interval is doubled
+    /// after each ping period in which the connection carried traffic and halved after each
+    /// period in which it was idle. Busy connections are thus probed rarely whereas idle ones
+    /// are checked more frequently.
+    ///
+    /// Pings themselves don't count as activity, otherwise two peers pinging each other
+    /// would keep backing off. Traffic of other protocols has to be reported via
+    /// [`Behaviour::record_activity`](crate::Behaviour::record_activity).
+    pub fn with_adaptive_interval(mut self, min: Duration, max: Duration) -> Self {
+        self.adaptive_interval = Some((min, max.max(min)));
+        self
+    }
 }
 
 impl Default for Config {
@@ -146,6 +178,17 @@ pub struct Handler {
     inbound: Option<PongFuture>,
     /// Tracks the state of our handler.
     state: State,
+    /// The duration between outbound pings, see [`Config::with_adaptive_interval`].
+    current_interval: Duration,
+    /// Whether the connection carried traffic since the last outbound ping.
+    active: bool,
+}
+
+/// An event sent from the [`Behaviour`](crate::Behaviour) to the [`Handler`].
+#[derive(Debug)]
+pub enum InEvent {
+    /// The connection carried traffic of another protocol.
+    Activity,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -164,8 +207,14 @@ enum State {
 impl Handler {
     /// Builds a new [`Handler`] with the given configuration.
     pub fn new(config: Config) -> Self {
+        let current_interval = match config.adaptive_interval {
+            Some((min, max)) => config.interval.clamp(min, max),
+            None => config.interval,
+        };
         Handler {
             config,
+            current_interval,
+            active: false,
             interval: Delay::new(Duration::new(0, 0)),
             pending_errors: VecDeque::with_capacity(2),
             failures: 0,
@@ -204,10 +253,23 @@ impl Handler {
 
         self.pending_errors.push_front(error);
     }
+
+    /// Computes the duration until the next outbound ping.
+    fn next_interval(&mut self) -> Duration {
+        let Some((min, max)) = self.config.adaptive_interval else {
+            return self.config.interval;
+        };
+        self.current_interval = if std::mem::take(&mut self.active) {
+            (self.current_interval * 2).min(max)
+        } else {
+            (self.current_interval / 2).max(min)
+        };
+        self.current_interval
+    }
 }
 
 impl ConnectionHandler for Handler {
-    type FromBehaviour = Void;
+    type FromBehaviour = InEvent;
     type ToBehaviour = Result<Duration, Failure>;
     type InboundProtocol = ReadyUpgrade<StreamProtocol>;
     type OutboundProtocol = ReadyUpgrade<StreamProtocol>;
@@ -218,7 +280,11 @@ impl ConnectionHandler for Handler {
         SubstreamProtocol::new(ReadyUpgrade::new(PROTOCOL_NAME), ())
     }
 
-    fn on_behaviour_event(&mut self, _: Void) {}
+    fn on_behaviour_event(&mut self, event: InEvent) {
+        match event {
+            InEvent::Activity => self.active = true,
+        }
+    }
 
     #[tracing::instrument(level = "trace", name = "ConnectionHandler::poll", skip(self, cx))]
     fn poll(
@@ -283,12 +349,14 @@ impl ConnectionHandler for Handler {
                     Poll::Ready(Ok((stream, rtt))) => {
                         tracing::debug!(?rtt, "ping succeeded");
                         self.failures = 0;
-                        self.interval.reset(self.config.interval);
+                        let interval = self.next_interval();
+                        self.interval.reset(interval);
                         self.outbound = Some(OutboundState::Idle(stream));
                         return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(Ok(rtt)));
                     }
                     Poll::Ready(Err(e)) => {
-                        self.interval.reset(self.config.interval);
+                        let interval = self.next_interval();
+                        self.interval.reset(interval);
                         self.pending_errors.push_front(e);
                     }
                 },
@@ -299,7 +367,8 @@ impl ConnectionHandler for Handler {
                     }
                     Poll::Ready(()) => {
                         self.outbound = Some(OutboundState::Ping(
-                            send_ping(stream, self.config.timeout).boxed(),
+                            send_ping(stream, self.config.timeout, self.config.payload_size)
+                                .boxed(),
                         ));
                     }
                 },
@@ -311,7 +380,9 @@ impl ConnectionHandler for Handler {
                     Poll::Pending => break,
                     Poll::Ready(()) => {
                         self.outbound = Some(OutboundState::OpenStream);
-                        let protocol = SubstreamProtocol::new(ReadyUpgrade::new(PROTOCOL_NAME), ());
+                        // Pings measure the round-trip time, thus must not queue behind bulk data.
+                        let protocol = SubstreamProtocol::new(ReadyUpgrade::new(PROTOCOL_NAME), ())
+                            .with_priority(StreamPriority::High);
                         return Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest {
                             protocol,
                         });
@@ -346,7 +417,7 @@ impl ConnectionHandler for Handler {
             }) => {
                 stream.ignore_for_keep_alive();
                 self.outbound = Some(OutboundState::Ping(
-                    send_ping(stream, self.config.timeout).boxed(),
+                    send_ping(stream, self.config.timeout, self.config.payload_size).boxed(),
                 ));
             }
             ConnectionEvent::DialUpgradeError(dial_upgrade_error) => {
@@ -371,8 +442,12 @@ enum OutboundState {
 }
 
 /// A wrapper around [`protocol::send_ping`] that enforces a time out.
-async fn send_ping(stream: Stream, timeout: Duration) -> Result<(Stream, Duration), Failure> {
-    let ping = protocol::send_ping(stream);
+async fn send_ping(
+    stream: Stream,
+    timeout: Duration,
+    payload_size: usize,
+) -> Result<(Stream, Duration), Failure> {
+    let ping = protocol::send_ping(stream, payload_size);
     futures::pin_mut!(ping);
 
     match future::select(ping, Delay::new(timeout)).await {
@@ -381,3 +456,44 @@ async fn send_ping(stream: Stream, timeout: Duration) -> Result<(Stream, Duratio
         Either::Right(((), _)) => Err(Failure::Timeout),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adaptive_interval_follows_activity() {
+        let config = Config::new()
+            .with_interval(Duration::from_secs(4))
+            .with_adaptive_interval(Duration::from_secs(1), Duration::from_secs(8));
+        let mut handler = Handler::new(config);
+
+        assert_eq!(handler.next_interval(), Duration::from_secs(2));
+        assert_eq!(handler.next_interval(), Duration::from_secs(1));
+        assert_eq!(handler.next_interval(), Duration::from_secs(1));
+
+        for expected in [2, 4, 8, 8] {
+            handler.on_behaviour_event(InEvent::Activity);
+            assert_eq!(handler.next_interval(), Duration::from_secs(expected));
+        }
+    }
+
+    #[test]
+    fn payload_size_is_rounded_and_capped() {
+        assert_eq!(Config::new().with_payload_size(0).payload_size, 32);
+        assert_eq!(Config::new().with_payload_size(1000).payload_size, 1024);
+        assert_eq!(
+            Config::new().with_payload_size(usize::MAX).payload_size,
+            protocol::MAX_PAYLOAD_SIZE
+        );
+    }
+
+    #[test]
+    fn fixed_interval_ignores_activity() {
+        let mut handler = Handler::new(Config::new().with_interval(Duration::from_secs(4)));
+
+        handler.on_behaviour_event(InEvent::Activity);
+        assert_eq!(handler.next_interval(), Duration::from_secs(4));
+        assert_eq!(handler.next_interval(), Duration::from_secs(4));
+    }
+}
@@ -49,22 +49,25 @@
 
 mod handler;
 mod protocol;
+mod rtt;
 
-use handler::Handler;
+use handler::{Handler, InEvent};
 use libp2p_core::{Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
 use libp2p_swarm::{
-    behaviour::FromSwarm, ConnectionDenied, ConnectionId, NetworkBehaviour, THandler,
-    THandlerInEvent, THandlerOutEvent, ToSwarm,
+    behaviour::{ConnectionClosed, FromSwarm},
+    ConnectionDenied, ConnectionId, NetworkBehaviour, NotifyHandler, THandler, THandlerInEvent,
+    THandlerOutEvent, ToSwarm,
 };
 use std::time::Duration;
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, VecDeque},
     task::{Context, Poll},
 };
 
 pub use self::protocol::PROTOCOL_NAME;
 pub use handler::{Config, Failure};
+pub use rtt::RttSummary;
 
 /// A [`NetworkBehaviour`] that responds to inbound pings and
 /// periodically sends outbound pings on every established connection.
@@ -74,7 +77,9 @@ pub struct Behaviour {
     /// Configuration for outbound pings.
     config: Config,
     /// Queue of events to yield to the swarm.
-    events: VecDeque<Event>,
+    events: VecDeque<ToSwarm<Event, InEvent>>,
+    /// The round-trip times of the outbound pings to connected peers.
+    rtts: HashMap<PeerId, RttSummary>,
 }
 
 /// Event generated by the `Ping` network behaviour.
@@ -94,8 +99,29 @@ impl Behaviour {
         Self {
             config,
             events: VecDeque::new(),
+            rtts: HashMap::new(),
         }
     }
+
+    /// Returns a summary of the round-trip times of the successful outbound pings
+    /// on all connections to the peer, if any.
+    ///
+    /// The summary is discarded once the peer disconnects.
+    pub fn rtt_summary(&self, peer: &PeerId) -> Option<&RttSummary> {
+        self.rtts.get(peer)
+    }
+
+    /// Reports that the given connection recently carried traffic of another protocol.
+    ///
+    /// Has no effect unless [`Config::with_adaptive_interval`] is enabled, in which case
+    /// the ping interval of the connection backs off.
+    pub fn record_activity(&mut self, peer: PeerId, connection: ConnectionId) {
+        self.events.push_front(ToSwarm::NotifyHandler {
+            peer_id: peer,
+            handler: NotifyHandler::One(connection),
+            event: InEvent::Activity,
+        });
+    }
 }
 
 impl Default for Behaviour {
@@ -134,21 +160,36 @@ impl NetworkBehaviour for Behaviour {
         connection: ConnectionId,
         result: THandlerOutEvent<Self>,
     ) {
-        self.events.push_front(Event {
+        if let Ok(rtt) = result {
+            self.rtts
+                .entry(peer)
+                .and_modify(|summary| summary.record(rtt))
+                .or_insert_with(|| RttSummary::new(rtt));
+        }
+        self.events.push_front(ToSwarm::GenerateEvent(Event {
             peer,
             connection,
             result,
-        })
+        }))
     }
 
     #[tracing::instrument(level = "trace", name = "NetworkBehaviour::poll", skip(self))]
     fn poll(&mut self, _: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
         if let Some(e) = self.events.pop_back() {
-            Poll::Ready(ToSwarm::GenerateEvent(e))
+            Poll::Ready(e)
         } else {
             Poll::Pending
         }
     }
 
-    fn on_swarm_event(&mut self, _event: FromSwarm) {}
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        if let FromSwarm::ConnectionClosed(ConnectionClosed {
+            peer_id,
+            remaining_established: 0,
+            ..
+        }) = event
+        {
+            self.rtts.remove(&peer_id);
+        }
+    }
 }
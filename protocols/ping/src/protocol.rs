@@ -28,7 +28,7 @@ pub const PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/ipfs/ping/1.0.0"
 
 /// The `Ping` protocol upgrade.
 ///
-/// The ping protocol sends 32 bytes of random data in configurable
+/// The ping protocol sends 32 bytes, or a configurable multiple thereof, of random data in configurable
 /// intervals over a single outbound substream, expecting to receive
 /// the same bytes as a response. At the same time, incoming pings
 /// on inbound substreams are answered by sending back the received bytes.
@@ -46,18 +46,27 @@ pub const PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/ipfs/ping/1.0.0"
 /// >           connections.
 #[derive(Default, Debug, Copy, Clone)]
 pub(crate) struct Ping;
-const PING_SIZE: usize = 32;
+pub(crate) const PING_SIZE: usize = 32;
+/// Upper bound for the payload size of outbound pings.
+pub(crate) const MAX_PAYLOAD_SIZE: usize = 64 * 1024;
 
-/// Sends a ping and waits for the pong.
-pub(crate) async fn send_ping<S>(mut stream: S) -> io::Result<(S, Duration)>
+/// Sends a ping with a payload of `payload_size` bytes and waits for the pong.
+///
+/// The remote echoes the payload in chunks of [`PING_SIZE`] bytes, hence `payload_size`
+/// must be a multiple thereof.
+pub(crate) async fn send_ping<S>(mut stream: S, payload_size: usize) -> io::Result<(S, Duration)>
 where
     S: AsyncRead + AsyncWrite + Unpin,
 {
-    let payload: [u8; PING_SIZE] = thread_rng().sample(distributions::Standard);
+    debug_assert_eq!(payload_size % PING_SIZE, 0);
+    let payload: Vec<u8> = thread_rng()
+        .sample_iter(distributions::Standard)
+        .take(payload_size)
+        .collect();
     stream.write_all(&payload).await?;
     stream.flush().await?;
     let started = Instant::now();
-    let mut recv_payload = [0u8; PING_SIZE];
+    let mut recv_payload = vec![0u8; payload_size];
     stream.read_exact(&mut recv_payload).await?;
     if recv_payload == payload {
         Ok((stream, started.elapsed()))
@@ -115,7 +124,39 @@ mod tests {
                 .unwrap()
                 .await
                 .unwrap();
-            let (_, rtt) = send_ping(c).await.unwrap();
+            let (_, rtt) = send_ping(c, PING_SIZE).await.unwrap();
+            assert!(rtt > Duration::from_secs(0));
+        });
+    }
+
+    #[test]
+    fn ping_pong_with_larger_payload() {
+        let mem_addr = multiaddr![Memory(thread_rng().gen::<u64>())];
+        let mut transport = MemoryTransport::new().boxed();
+        transport.listen_on(ListenerId::next(), mem_addr).unwrap();
+
+        let listener_addr = transport
+            .select_next_some()
+            .now_or_never()
+            .and_then(|ev| ev.into_new_address())
+            .expect("MemoryTransport not listening on an address!");
+
+        async_std::task::spawn(async move {
+            let transport_event = transport.next().await.unwrap();
+            let (listener_upgrade, _) = transport_event.into_incoming().unwrap();
+            let mut conn = listener_upgrade.await.unwrap();
+            for _ in 0..4 {
+                conn = recv_ping(conn).await.unwrap();
+            }
+        });
+
+        async_std::task::block_on(async move {
+            let c = MemoryTransport::new()
+                .dial(listener_addr)
+                .unwrap()
+                .await
+                .unwrap();
+            let (_, rtt) = send_ping(c, 4 * PING_SIZE).await.unwrap();
             assert!(rtt > Duration::from_secs(0));
         });
     }
@@ -0,0 +1,101 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::time::Duration;
+
+/// The weight of the latest round-trip time in [`RttSummary::ewma`],
+/// as for the smoothed round-trip time of TCP (RFC 6298).
+const EWMA_ALPHA: f64 = 0.125;
+
+/// A summary of the round-trip times of the successful outbound pings to a peer,
+/// see [`Behaviour::rtt_summary`](crate::Behaviour::rtt_summary).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RttSummary {
+    count: u32,
+    min: Duration,
+    max: Duration,
+    total: Duration,
+    ewma: Duration,
+}
+
+impl RttSummary {
+    pub(crate) fn new(rtt: Duration) -> Self {
+        Self {
+            count: 1,
+            min: rtt,
+            max: rtt,
+            total: rtt,
+            ewma: rtt,
+        }
+    }
+
+    pub(crate) fn record(&mut self, rtt: Duration) {
+        self.count = self.count.saturating_add(1);
+        self.min = self.min.min(rtt);
+        self.max = self.max.max(rtt);
+        self.total = self.total.saturating_add(rtt);
+        self.ewma = self.ewma.mul_f64(1.0 - EWMA_ALPHA) + rtt.mul_f64(EWMA_ALPHA);
+    }
+
+    /// The number of round-trip times recorded.
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// The lowest round-trip time.
+    pub fn min(&self) -> Duration {
+        self.min
+    }
+
+    /// The highest round-trip time.
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// The mean of all round-trip times.
+    pub fn avg(&self) -> Duration {
+        self.total / self.count
+    }
+
+    /// The exponentially weighted moving average of the round-trip times,
+    /// favouring recent ones.
+    pub fn ewma(&self) -> Duration {
+        self.ewma
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summarises_rtts() {
+        let mut summary = RttSummary::new(Duration::from_millis(100));
+        summary.record(Duration::from_millis(300));
+        summary.record(Duration::from_millis(200));
+
+        assert_eq!(summary.count(), 3);
+        assert_eq!(summary.min(), Duration::from_millis(100));
+        assert_eq!(summary.max(), Duration::from_millis(300));
+        assert_eq!(summary.avg(), Duration::from_millis(200));
+        assert!(summary.ewma() > Duration::from_millis(100));
+        assert!(summary.ewma() < Duration::from_millis(200));
+    }
+}
@@ -55,6 +55,33 @@ fn ping_pong() {
     QuickCheck::new().tests(10).quickcheck(prop as fn(_))
 }
 
+#[test]
+fn rtt_summary_with_larger_payload() {
+    let cfg = ping::Config::new()
+        .with_interval(Duration::from_millis(10))
+        .with_payload_size(1000);
+
+    let mut swarm1 = Swarm::new_ephemeral(|_| ping::Behaviour::new(cfg.clone()));
+    let mut swarm2 = Swarm::new_ephemeral(|_| ping::Behaviour::new(cfg.clone()));
+    let peer2_id = *swarm2.local_peer_id();
+
+    async_std::task::block_on(async {
+        swarm1.listen().with_memory_addr_external().await;
+        swarm2.connect(&mut swarm1).await;
+
+        for _ in 0..3 {
+            let ([e1], [_]): ([ping::Event; 1], [ping::Event; 1]) =
+                libp2p_swarm_test::drive(&mut swarm1, &mut swarm2).await;
+            assert!(e1.result.is_ok());
+        }
+    });
+
+    let summary = swarm1.behaviour().rtt_summary(&peer2_id).unwrap();
+    assert_eq!(summary.count(), 3);
+    assert!(summary.min() <= summary.avg());
+    assert!(summary.avg() <= summary.max());
+}
+
 fn assert_ping_rtt_less_than_50ms(e: ping::Event) {
     let rtt = e.result.expect("a ping success");
 
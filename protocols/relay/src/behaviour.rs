@@ -247,6 +247,9 @@ pub struct Behaviour {
     queued_actions: VecDeque<ToSwarm<Event, THandlerInEvent<Self>>>,
 
     external_addresses: ExternalAddresses,
+
+    circuit_idle_timeout: Option<Duration>,
+    circuit_stall_timeout: Option<Duration>,
 }
 
 impl Behaviour {
@@ -258,9 +261,28 @@ impl Behaviour {
             circuits: Default::default(),
             queued_actions: Default::default(),
             external_addresses: Default::default(),
+            circuit_idle_timeout: None,
+            circuit_stall_timeout: None,
         }
     }
 
+    /// Closes a circuit if no data is relayed in either direction for the given duration.
+    ///
+    /// Disabled by default.
+    pub fn with_circuit_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.circuit_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Closes a circuit if data sent by one side is not accepted by the other side for the given
+    /// duration, e.g. because it does not read from the circuit.
+    ///
+    /// Disabled by default.
+    pub fn with_circuit_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.circuit_stall_timeout = Some(timeout);
+        self
+    }
+
     fn on_connection_closed(
         &mut self,
         ConnectionClosed {
@@ -314,6 +336,8 @@ impl NetworkBehaviour for Behaviour {
                 reservation_duration: self.config.reservation_duration,
                 max_circuit_duration: self.config.max_circuit_duration,
                 max_circuit_bytes: self.config.max_circuit_bytes,
+                circuit_idle_timeout: self.circuit_idle_timeout,
+                circuit_stall_timeout: self.circuit_stall_timeout,
             },
             ConnectedPoint::Listener {
                 local_addr: local_addr.clone(),
@@ -339,6 +363,8 @@ impl NetworkBehaviour for Behaviour {
                 reservation_duration: self.config.reservation_duration,
                 max_circuit_duration: self.config.max_circuit_duration,
                 max_circuit_bytes: self.config.max_circuit_bytes,
+                circuit_idle_timeout: self.circuit_idle_timeout,
+                circuit_stall_timeout: self.circuit_stall_timeout,
             },
             ConnectedPoint::Dialer {
                 address: addr.clone(),
@@ -52,6 +52,8 @@ pub struct Config {
     pub reservation_duration: Duration,
     pub max_circuit_duration: Duration,
     pub max_circuit_bytes: u64,
+    pub circuit_idle_timeout: Option<Duration>,
+    pub circuit_stall_timeout: Option<Duration>,
 }
 
 pub enum In {
@@ -753,6 +755,8 @@ impl ConnectionHandler for Handler {
                     } = parts;
                     let max_circuit_duration = self.config.max_circuit_duration;
                     let max_circuit_bytes = self.config.max_circuit_bytes;
+                    let idle_timeout = self.config.circuit_idle_timeout;
+                    let stall_timeout = self.config.circuit_stall_timeout;
 
                     let circuit = async move {
                         let (result_1, result_2) = futures::future::join(
@@ -768,6 +772,8 @@ impl ConnectionHandler for Handler {
                             dst_stream,
                             max_circuit_duration,
                             max_circuit_bytes,
+                            idle_timeout,
+                            stall_timeout,
                         )
                         .await?;
 
@@ -30,10 +30,10 @@ use futures::io::{AsyncBufRead, BufReader};
 use futures::io::{AsyncRead, AsyncWrite};
 use futures::ready;
 use futures_timer::Delay;
-use std::io;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
+use std::{error, fmt, io};
 
 pub(crate) struct CopyFuture<S, D> {
     src: BufReader<S>,
@@ -42,6 +42,16 @@ pub(crate) struct CopyFuture<S, D> {
     max_circuit_duration: Delay,
     max_circuit_bytes: u64,
     bytes_sent: u64,
+
+    /// Closes the circuit if no data is forwarded in either direction for the duration.
+    idle_timeout: Option<(Duration, Delay)>,
+    /// Closes the circuit if data read from one side can not be written to the other side for
+    /// the duration.
+    stall_timeout: Option<Duration>,
+    /// Started while data read from `src` waits to be written to `dst`.
+    src_stall: Option<Delay>,
+    /// Started while data read from `dst` waits to be written to `src`.
+    dst_stall: Option<Delay>,
 }
 
 impl<S: AsyncRead, D: AsyncRead> CopyFuture<S, D> {
@@ -50,6 +60,8 @@ impl<S: AsyncRead, D: AsyncRead> CopyFuture<S, D> {
         dst: D,
         max_circuit_duration: Duration,
         max_circuit_bytes: u64,
+        idle_timeout: Option<Duration>,
+        stall_timeout: Option<Duration>,
     ) -> Self {
         CopyFuture {
             src: BufReader::new(src),
@@ -57,10 +69,44 @@ impl<S: AsyncRead, D: AsyncRead> CopyFuture<S, D> {
             max_circuit_duration: Delay::new(max_circuit_duration),
             max_circuit_bytes,
             bytes_sent: Default::default(),
+            idle_timeout: idle_timeout.map(|timeout| (timeout, Delay::new(timeout))),
+            stall_timeout,
+            src_stall: None,
+            dst_stall: None,
         }
     }
 }
 
+/// The reason for closing a circuit before either side closed it, see
+/// [`Behaviour::with_circuit_idle_timeout`](crate::Behaviour::with_circuit_idle_timeout) and
+/// [`Behaviour::with_circuit_stall_timeout`](crate::Behaviour::with_circuit_stall_timeout).
+///
+/// It is the inner error of the [`io::Error`] of [`Event::CircuitClosed`](crate::Event::CircuitClosed).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitTimeout {
+    /// No data was forwarded in either direction.
+    Idle,
+    /// One side did not accept the data sent by the other side.
+    Stalled,
+}
+
+impl fmt::Display for CircuitTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitTimeout::Idle => write!(f, "Circuit idle timeout reached."),
+            CircuitTimeout::Stalled => write!(f, "Circuit stalled."),
+        }
+    }
+}
+
+impl error::Error for CircuitTimeout {}
+
+impl From<CircuitTimeout> for io::Error {
+    fn from(timeout: CircuitTimeout) -> Self {
+        io::Error::new(io::ErrorKind::TimedOut, timeout)
+    }
+}
+
 impl<S, D> Future for CopyFuture<S, D>
 where
     S: AsyncRead + AsyncWrite + Unpin,
@@ -90,6 +136,7 @@ where
                 Poll::Ready(Ok(0)) => Status::Done,
                 Poll::Ready(Ok(i)) => {
                     this.bytes_sent += i;
+                    this.src_stall = None;
                     Status::Progressed
                 }
                 Poll::Pending => Status::Pending,
@@ -100,11 +147,19 @@ where
                 Poll::Ready(Ok(0)) => Status::Done,
                 Poll::Ready(Ok(i)) => {
                     this.bytes_sent += i;
+                    this.dst_stall = None;
                     Status::Progressed
                 }
                 Poll::Pending => Status::Pending,
             };
 
+            if matches!(src_status, Status::Progressed) || matches!(dst_status, Status::Progressed)
+            {
+                if let Some((timeout, timer)) = this.idle_timeout.as_mut() {
+                    timer.reset(*timeout);
+                }
+            }
+
             match (src_status, dst_status) {
                 // Both source and destination are done sending data.
                 (Status::Done, Status::Done) => return Poll::Ready(Ok(())),
@@ -123,10 +178,45 @@ where
             return Poll::Ready(Err(io::ErrorKind::TimedOut.into()));
         }
 
+        if let Some((_, timer)) = this.idle_timeout.as_mut() {
+            if timer.poll_unpin(cx).is_ready() {
+                return Poll::Ready(Err(CircuitTimeout::Idle.into()));
+            }
+        }
+
+        if let Some(timeout) = this.stall_timeout {
+            let src_stalled = poll_stall(&mut this.src_stall, &this.src, timeout, cx);
+            let dst_stalled = poll_stall(&mut this.dst_stall, &this.dst, timeout, cx);
+            if src_stalled || dst_stalled {
+                return Poll::Ready(Err(CircuitTimeout::Stalled.into()));
+            }
+        }
+
         Poll::Pending
     }
 }
 
+/// Polls the stall timer of the data buffered in `src`, starting it if there is any data and
+/// stopping it otherwise.
+///
+/// Returns `true` if the data has been waiting to be written for `timeout`.
+fn poll_stall<S: AsyncRead>(
+    timer: &mut Option<Delay>,
+    src: &BufReader<S>,
+    timeout: Duration,
+    cx: &mut Context<'_>,
+) -> bool {
+    if src.buffer().is_empty() {
+        *timer = None;
+        return false;
+    }
+
+    timer
+        .get_or_insert_with(|| Delay::new(timeout))
+        .poll_unpin(cx)
+        .is_ready()
+}
+
 /// Forwards data from `source` to `destination`.
 ///
 /// Returns `0` when done, i.e. `source` having reached EOF, returns number of bytes sent otherwise,
@@ -227,6 +317,8 @@ mod tests {
                 connection_b,
                 Duration::from_secs(60),
                 max_circuit_bytes,
+                None,
+                None,
             );
 
             match block_on(&mut copy_future) {
@@ -288,6 +380,8 @@ mod tests {
             PendingConnection {},
             Duration::from_millis(1),
             u64::MAX,
+            None,
+            None,
         );
 
         std::thread::sleep(Duration::from_millis(2));
@@ -297,6 +391,100 @@ mod tests {
         assert_eq!(error.kind(), ErrorKind::TimedOut);
     }
 
+    #[test]
+    fn circuit_timeouts() {
+        struct Connection {
+            read: Vec<u8>,
+            writable: bool,
+        }
+
+        impl AsyncWrite for Connection {
+            fn poll_write(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                buf: &[u8],
+            ) -> Poll<std::io::Result<usize>> {
+                if self.writable {
+                    return Poll::Ready(Ok(buf.len()));
+                }
+                Poll::Pending
+            }
+
+            fn poll_flush(
+                self: std::pin::Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                if self.writable {
+                    return Poll::Ready(Ok(()));
+                }
+                Poll::Pending
+            }
+
+            fn poll_close(
+                self: std::pin::Pin<&mut Self>,
+                cx: &mut Context<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                self.poll_flush(cx)
+            }
+        }
+
+        impl AsyncRead for Connection {
+            fn poll_read(
+                mut self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                buf: &mut [u8],
+            ) -> Poll<std::io::Result<usize>> {
+                if let Some(b) = self.read.pop() {
+                    buf[0] = b;
+                    return Poll::Ready(Ok(1));
+                }
+                Poll::Pending
+            }
+        }
+
+        let idle = CopyFuture::new(
+            Connection {
+                read: vec![],
+                writable: true,
+            },
+            Connection {
+                read: vec![],
+                writable: true,
+            },
+            Duration::from_secs(60),
+            u64::MAX,
+            Some(Duration::from_millis(1)),
+            None,
+        );
+        let error = block_on(idle).expect_err("Expect circuit idle timeout to be reached.");
+        assert_eq!(error.kind(), ErrorKind::TimedOut);
+        assert_eq!(
+            error.get_ref().and_then(|e| e.downcast_ref()),
+            Some(&CircuitTimeout::Idle)
+        );
+
+        // The destination never accepts the data sent by the source.
+        let stalled = CopyFuture::new(
+            Connection {
+                read: vec![1, 2, 3],
+                writable: true,
+            },
+            Connection {
+                read: vec![],
+                writable: false,
+            },
+            Duration::from_secs(60),
+            u64::MAX,
+            None,
+            Some(Duration::from_millis(1)),
+        );
+        let error = block_on(stalled).expect_err("Expect circuit to be detected as stalled.");
+        assert_eq!(
+            error.get_ref().and_then(|e| e.downcast_ref()),
+            Some(&CircuitTimeout::Stalled)
+        );
+    }
+
     #[test]
     fn forward_data_should_flush_on_pending_source() {
         struct NeverEndingSource {
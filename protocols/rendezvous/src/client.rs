@@ -24,14 +24,16 @@ use futures::future::BoxFuture;
 use futures::future::FutureExt;
 use futures::stream::FuturesUnordered;
 use futures::stream::StreamExt;
+use futures_timer::Delay;
 use libp2p_core::{Endpoint, Multiaddr, PeerRecord};
 use libp2p_identity::{Keypair, PeerId, SigningError};
 use libp2p_request_response::{OutboundRequestId, ProtocolSupport};
+use libp2p_swarm::dial_opts::{DialOpts, PeerCondition};
 use libp2p_swarm::{
     ConnectionDenied, ConnectionId, ExternalAddresses, FromSwarm, NetworkBehaviour, THandler,
     THandlerInEvent, THandlerOutEvent, ToSwarm,
 };
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::iter;
 use std::task::{Context, Poll};
 use std::time::Duration;
@@ -55,6 +57,38 @@ pub struct Behaviour {
     expiring_registrations: FuturesUnordered<BoxFuture<'static, (PeerId, Namespace)>>,
 
     external_addresses: ExternalAddresses,
+
+    /// Namespaces periodically discovered at a rendezvous node, see [`Behaviour::watch`].
+    watches: HashMap<(PeerId, Namespace), Watch>,
+
+    /// What to do with peers newly discovered in a watched namespace.
+    watch_policy: WatchPolicy,
+
+    /// Events to report before polling the inner behaviour.
+    pending_events: VecDeque<ToSwarm<Event, THandlerInEvent<Self>>>,
+}
+
+/// What to do with peers newly discovered in a namespace watched with [`Behaviour::watch`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WatchPolicy {
+    /// Report new peers through [`Event::PeerDiscovered`].
+    #[default]
+    Report,
+    /// Report new peers through [`Event::PeerDiscovered`] and dial them unless already connected.
+    Dial,
+}
+
+/// A namespace periodically discovered at a rendezvous node.
+struct Watch {
+    interval: Duration,
+    /// Timer for the next discovery.
+    next: Delay,
+    /// Cookie of the last discovery, to only fetch new registrations.
+    cookie: Option<Cookie>,
+    /// The discovery in progress, if any.
+    request: Option<OutboundRequestId>,
+    /// Peers registered in the namespace whose registration did not expire yet.
+    peers: HashSet<PeerId>,
 }
 
 impl Behaviour {
@@ -75,9 +109,20 @@ impl Behaviour {
                 futures::future::pending().boxed()
             ]),
             external_addresses: Default::default(),
+            watches: Default::default(),
+            watch_policy: Default::default(),
+            pending_events: Default::default(),
         }
     }
 
+    /// Set what to do with peers newly discovered in a watched namespace.
+    ///
+    /// Defaults to [`WatchPolicy::Report`].
+    pub fn with_watch_policy(mut self, policy: WatchPolicy) -> Self {
+        self.watch_policy = policy;
+        self
+    }
+
     /// Register our external addresses in the given namespace with the given rendezvous peer.
     ///
     /// External addresses are either manually added via [`libp2p_swarm::Swarm::add_external_address`] or reported
@@ -139,6 +184,58 @@ impl Behaviour {
         self.waiting_for_discovery
             .insert(req_id, (rendezvous_node, namespace));
     }
+
+    /// Discover peers in the given namespace at the given rendezvous peer every `interval`,
+    /// starting immediately.
+    ///
+    /// Subsequent discoveries use the cookie of the previous one and thus only return new
+    /// registrations. Peers that newly registered are handled according to the
+    /// [`WatchPolicy`]. Every discovery is also reported through [`Event::Discovered`] or
+    /// [`Event::DiscoverFailed`].
+    ///
+    /// Watching a namespace again replaces the interval of the existing watch.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn watch(&mut self, namespace: Namespace, rendezvous_node: PeerId, interval: Duration) {
+        assert!(!interval.is_zero(), "watch interval must not be zero");
+        match self.watches.get_mut(&(rendezvous_node, namespace.clone())) {
+            Some(watch) => {
+                watch.interval = interval;
+                watch.next.reset(interval);
+            }
+            None => {
+                let req_id = self.inner.send_request(
+                    &rendezvous_node,
+                    Discover {
+                        namespace: Some(namespace.clone()),
+                        cookie: None,
+                        limit: None,
+                    },
+                );
+                self.waiting_for_discovery
+                    .insert(req_id, (rendezvous_node, Some(namespace.clone())));
+                self.watches.insert(
+                    (rendezvous_node, namespace),
+                    Watch {
+                        interval,
+                        next: Delay::new(interval),
+                        cookie: None,
+                        request: Some(req_id),
+                        peers: HashSet::new(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Stop watching the given namespace at the given rendezvous peer.
+    ///
+    /// Returns `false` if the namespace was not watched.
+    pub fn unwatch(&mut self, namespace: Namespace, rendezvous_node: PeerId) -> bool {
+        self.watches.remove(&(rendezvous_node, namespace)).is_some()
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -178,6 +275,13 @@ pub enum Event {
     },
     /// The connection details we learned from this node expired.
     Expired { peer: PeerId },
+    /// A peer newly registered in a namespace watched with [`Behaviour::watch`].
+    PeerDiscovered {
+        rendezvous_node: PeerId,
+        namespace: Namespace,
+        peer: PeerId,
+        addresses: Vec<Multiaddr>,
+    },
 }
 
 impl NetworkBehaviour for Behaviour {
@@ -246,6 +350,10 @@ impl NetworkBehaviour for Behaviour {
         use libp2p_request_response as req_res;
 
         loop {
+            if let Some(event) = self.pending_events.pop_front() {
+                return Poll::Ready(event);
+            }
+
             match self.inner.poll(cx) {
                 Poll::Ready(ToSwarm::GenerateEvent(req_res::Event::Message {
                     message:
@@ -293,12 +401,22 @@ impl NetworkBehaviour for Behaviour {
             if let Poll::Ready(Some(expired_registration)) =
                 self.expiring_registrations.poll_next_unpin(cx)
             {
+                let (peer, namespace) = &expired_registration;
+                for ((_, watched), watch) in self.watches.iter_mut() {
+                    if watched == namespace {
+                        watch.peers.remove(peer);
+                    }
+                }
                 self.discovered_peers.remove(&expired_registration);
                 return Poll::Ready(ToSwarm::GenerateEvent(Event::Expired {
                     peer: expired_registration.0,
                 }));
             }
 
+            if self.poll_watches(cx) {
+                continue;
+            }
+
             return Poll::Pending;
         }
     }
@@ -328,6 +446,95 @@ impl NetworkBehaviour for Behaviour {
 }
 
 impl Behaviour {
+    /// Start the discoveries of watched namespaces whose interval elapsed.
+    ///
+    /// Returns `true` if a discovery was started.
+    fn poll_watches(&mut self, cx: &mut Context<'_>) -> bool {
+        let mut started = false;
+        for ((rendezvous_node, namespace), watch) in self.watches.iter_mut() {
+            let mut due = false;
+            while watch.next.poll_unpin(cx).is_ready() {
+                watch.next.reset(watch.interval);
+                due = true;
+            }
+            // Skip this round if the previous discovery is still in progress.
+            if !due || watch.request.is_some() {
+                continue;
+            }
+
+            let req_id = self.inner.send_request(
+                rendezvous_node,
+                Discover {
+                    namespace: Some(namespace.clone()),
+                    cookie: watch.cookie.clone(),
+                    limit: None,
+                },
+            );
+            self.waiting_for_discovery
+                .insert(req_id, (*rendezvous_node, Some(namespace.clone())));
+            watch.request = Some(req_id);
+            started = true;
+        }
+        started
+    }
+
+    /// Update the watch the discovery `req_id` was made for, if any.
+    fn on_watch_discovered(
+        &mut self,
+        req_id: &OutboundRequestId,
+        rendezvous_node: PeerId,
+        registrations: &[Registration],
+        cookie: &Cookie,
+    ) {
+        let Some(((_, namespace), watch)) = self
+            .watches
+            .iter_mut()
+            .find(|(_, watch)| watch.request.as_ref() == Some(req_id))
+        else {
+            return;
+        };
+        watch.request = None;
+        watch.cookie = Some(cookie.clone());
+
+        let local_peer_id = self.keypair.public().to_peer_id();
+        for registration in registrations {
+            let peer = registration.record.peer_id();
+            if peer == local_peer_id || !watch.peers.insert(peer) {
+                continue;
+            }
+
+            self.pending_events
+                .push_back(ToSwarm::GenerateEvent(Event::PeerDiscovered {
+                    rendezvous_node,
+                    namespace: namespace.clone(),
+                    peer,
+                    addresses: registration.record.addresses().to_vec(),
+                }));
+            if self.watch_policy == WatchPolicy::Dial {
+                self.pending_events.push_back(ToSwarm::Dial {
+                    opts: DialOpts::peer_id(peer)
+                        .condition(PeerCondition::DisconnectedAndNotDialing)
+                        .build(),
+                });
+            }
+        }
+    }
+
+    /// Allow the watch the discovery `req_id` was made for, if any, to start the next one.
+    fn on_watch_discover_failed(&mut self, req_id: &OutboundRequestId, error: ErrorCode) {
+        let Some(watch) = self
+            .watches
+            .values_mut()
+            .find(|watch| watch.request.as_ref() == Some(req_id))
+        else {
+            return;
+        };
+        watch.request = None;
+        if error == ErrorCode::InvalidCookie {
+            watch.cookie = None;
+        }
+    }
+
     fn event_for_outbound_failure(&mut self, req_id: &OutboundRequestId) -> Option<Event> {
         if let Some((rendezvous_node, namespace)) = self.waiting_for_register.remove(req_id) {
             return Some(Event::RegisterFailed {
@@ -338,6 +545,7 @@ impl Behaviour {
         };
 
         if let Some((rendezvous_node, namespace)) = self.waiting_for_discovery.remove(req_id) {
+            self.on_watch_discover_failed(req_id, ErrorCode::Unavailable);
             return Some(Event::DiscoverFailed {
                 rendezvous_node,
                 namespace,
@@ -408,6 +616,8 @@ impl Behaviour {
                             .boxed()
                         }));
 
+                    self.on_watch_discovered(request_id, rendezvous_node, &registrations, &cookie);
+
                     return Some(Event::Discovered {
                         rendezvous_node,
                         registrations,
@@ -419,6 +629,7 @@ impl Behaviour {
             }
             DiscoverResponse(Err(error_code)) => {
                 if let Some((rendezvous_node, ns)) = self.waiting_for_discovery.remove(request_id) {
+                    self.on_watch_discover_failed(request_id, error_code);
                     return Some(Event::DiscoverFailed {
                         rendezvous_node,
                         namespace: ns,
@@ -419,6 +419,82 @@ async fn registration_on_clients_expire() {
     assert!(matches!(error, DialError::NoAddresses));
 }
 
+#[tokio::test]
+async fn watch_reports_newly_registered_peers() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+    let namespace = rendezvous::Namespace::from_static("some-namespace");
+    let ([mut alice, mut bob, mut charlie], robert) =
+        new_server_with_connected_clients(rendezvous::server::Config::default()).await;
+
+    let roberts_peer_id = *robert.local_peer_id();
+    tokio::spawn(robert.loop_on_next());
+
+    alice
+        .behaviour_mut()
+        .register(namespace.clone(), roberts_peer_id, None)
+        .unwrap();
+    match alice.next_behaviour_event().await {
+        rendezvous::client::Event::Registered { .. } => {}
+        event => panic!("Unexpected event: {event:?}"),
+    }
+
+    bob.behaviour_mut()
+        .watch(namespace.clone(), roberts_peer_id, Duration::from_secs(1));
+    match bob.next_behaviour_event().await {
+        rendezvous::client::Event::Discovered { registrations, .. } => {
+            assert_eq!(registrations.len(), 1);
+        }
+        event => panic!("Unexpected event: {event:?}"),
+    }
+    match bob.next_behaviour_event().await {
+        rendezvous::client::Event::PeerDiscovered { peer, .. } => {
+            assert_eq!(&peer, alice.local_peer_id());
+        }
+        event => panic!("Unexpected event: {event:?}"),
+    }
+
+    charlie
+        .behaviour_mut()
+        .register(namespace.clone(), roberts_peer_id, None)
+        .unwrap();
+    match charlie.next_behaviour_event().await {
+        rendezvous::client::Event::Registered { .. } => {}
+        event => panic!("Unexpected event: {event:?}"),
+    }
+
+    // Later discoveries use the cookie, thus only return charlie's registration.
+    loop {
+        match bob.next_behaviour_event().await {
+            rendezvous::client::Event::Discovered { registrations, .. } => {
+                if registrations.is_empty() {
+                    continue;
+                }
+                assert_eq!(registrations.len(), 1);
+                break;
+            }
+            event => panic!("Unexpected event: {event:?}"),
+        }
+    }
+    match bob.next_behaviour_event().await {
+        rendezvous::client::Event::PeerDiscovered {
+            peer,
+            namespace: discovered_namespace,
+            ..
+        } => {
+            assert_eq!(&peer, charlie.local_peer_id());
+            assert_eq!(discovered_namespace, namespace);
+        }
+        event => panic!("Unexpected event: {event:?}"),
+    }
+
+    assert!(bob
+        .behaviour_mut()
+        .unwatch(namespace.clone(), roberts_peer_id));
+    assert!(!bob.behaviour_mut().unwatch(namespace, roberts_peer_id));
+}
+
 async fn new_server_with_connected_clients<const N: usize>(
     config: rendezvous::server::Config,
 ) -> (
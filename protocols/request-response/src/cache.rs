@@ -0,0 +1,264 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Caching of responses to inbound requests.
+
+use crate::InboundRequestId;
+use instant::Instant;
+use smallvec::SmallVec;
+use std::{
+    collections::{hash_map::RandomState, BTreeMap, HashMap},
+    hash::{BuildHasher, Hash},
+    num::NonZeroUsize,
+    time::Duration,
+};
+
+/// The configuration of a cache of responses to inbound requests,
+/// see [`Behaviour::enable_response_cache`](crate::Behaviour::enable_response_cache).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResponseCacheConfig {
+    capacity: NonZeroUsize,
+    ttl: Duration,
+}
+
+impl ResponseCacheConfig {
+    /// Creates a new configuration caching up to `capacity` responses, each for `ttl`
+    /// after it was sent.
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Self { capacity, ttl }
+    }
+}
+
+/// A bounded cache of responses to requests.
+///
+/// Requests are looked up by their hash and compared by value, so that requests with
+/// colliding hashes are never answered with each other's response. Responses are evicted
+/// in the order in which they were inserted, once they expired or to make room for new ones.
+pub(crate) struct ResponseCache<TRequest, TResponse> {
+    config: ResponseCacheConfig,
+    hasher: RandomState,
+    hash: fn(&RandomState, &TRequest) -> u64,
+    eq: fn(&TRequest, &TRequest) -> bool,
+    clone_request: fn(&TRequest) -> TRequest,
+    clone_response: fn(&TResponse) -> TResponse,
+    /// The cached responses by the order of their insertion.
+    entries: BTreeMap<u64, Entry<TRequest, TResponse>>,
+    /// The keys of `entries` by the hash of their request.
+    index: HashMap<u64, SmallVec<[u64; 1]>>,
+    next_entry: u64,
+    /// The requests passed to the application whose responses are yet to be sent.
+    pending: HashMap<InboundRequestId, TRequest>,
+}
+
+struct Entry<TRequest, TResponse> {
+    hash: u64,
+    request: TRequest,
+    response: TResponse,
+    inserted: Instant,
+}
+
+impl<TRequest, TResponse> ResponseCache<TRequest, TResponse> {
+    pub(crate) fn new(config: ResponseCacheConfig) -> Self
+    where
+        TRequest: Hash + Eq + Clone,
+        TResponse: Clone,
+    {
+        Self {
+            config,
+            hasher: RandomState::new(),
+            hash: |hasher, request| hasher.hash_one(request),
+            eq: TRequest::eq,
+            clone_request: TRequest::clone,
+            clone_response: TResponse::clone,
+            entries: BTreeMap::new(),
+            index: HashMap::new(),
+            next_entry: 0,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Returns the cached response to the request, unless it expired.
+    pub(crate) fn get(&self, request: &TRequest, now: Instant) -> Option<TResponse> {
+        let hash = (self.hash)(&self.hasher, request);
+        self.find(hash, request)
+            .map(|key| &self.entries[&key])
+            .filter(|entry| now.duration_since(entry.inserted) < self.config.ttl)
+            .map(|entry| (self.clone_response)(&entry.response))
+    }
+
+    /// Remembers the request with the given ID, so that its response can be cached once sent.
+    pub(crate) fn track(&mut self, request_id: InboundRequestId, request: &TRequest) {
+        self.pending
+            .insert(request_id, (self.clone_request)(request));
+    }
+
+    /// Forgets the request with the given ID, e.g. because it failed.
+    pub(crate) fn forget(&mut self, request_id: InboundRequestId) {
+        self.pending.remove(&request_id);
+    }
+
+    /// Caches the response to the tracked request with the given ID.
+    ///
+    /// An unexpired response that is already cached for the request is kept.
+    pub(crate) fn insert(
+        &mut self,
+        request_id: InboundRequestId,
+        response: &TResponse,
+        now: Instant,
+    ) {
+        let Some(request) = self.pending.remove(&request_id) else {
+            return;
+        };
+        self.remove_expired(now);
+        let hash = (self.hash)(&self.hasher, &request);
+        if self.find(hash, &request).is_some() {
+            return;
+        }
+        while self.entries.len() >= self.config.capacity.get() {
+            let Some(&oldest) = self.entries.keys().next() else {
+                break;
+            };
+            self.remove(oldest);
+        }
+
+        let key = self.next_entry;
+        self.next_entry += 1;
+        self.entries.insert(
+            key,
+            Entry {
+                hash,
+                request,
+                response: (self.clone_response)(response),
+                inserted: now,
+            },
+        );
+        self.index.entry(hash).or_default().push(key);
+    }
+
+    fn find(&self, hash: u64, request: &TRequest) -> Option<u64> {
+        self.index
+            .get(&hash)?
+            .iter()
+            .copied()
+            .find(|key| (self.eq)(&self.entries[key].request, request))
+    }
+
+    fn remove(&mut self, key: u64) {
+        let Some(entry) = self.entries.remove(&key) else {
+            return;
+        };
+        if let Some(keys) = self.index.get_mut(&entry.hash) {
+            keys.retain(|k| *k != key);
+            if keys.is_empty() {
+                self.index.remove(&entry.hash);
+            }
+        }
+    }
+
+    fn remove_expired(&mut self, now: Instant) {
+        while let Some((&oldest, entry)) = self.entries.iter().next() {
+            if now.duration_since(entry.inserted) < self.config.ttl {
+                break;
+            }
+            self.remove(oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache(capacity: usize, ttl: Duration) -> ResponseCache<&'static str, u32> {
+        ResponseCache::new(ResponseCacheConfig::new(
+            NonZeroUsize::new(capacity).unwrap(),
+            ttl,
+        ))
+    }
+
+    fn insert(
+        cache: &mut ResponseCache<&'static str, u32>,
+        request: &'static str,
+        response: u32,
+        now: Instant,
+    ) {
+        cache.track(InboundRequestId(0), &request);
+        cache.insert(InboundRequestId(0), &response, now);
+    }
+
+    #[test]
+    fn responses_expire_after_ttl() {
+        let mut cache = cache(8, Duration::from_secs(10));
+        let now = Instant::now();
+
+        assert_eq!(cache.get(&"a", now), None);
+        insert(&mut cache, "a", 1, now);
+        assert_eq!(cache.get(&"a", now + Duration::from_secs(5)), Some(1));
+
+        // The first response is kept until it expires.
+        insert(&mut cache, "a", 2, now + Duration::from_secs(5));
+        assert_eq!(cache.get(&"a", now + Duration::from_secs(5)), Some(1));
+        assert_eq!(cache.get(&"a", now + Duration::from_secs(10)), None);
+
+        insert(&mut cache, "a", 2, now + Duration::from_secs(10));
+        assert_eq!(cache.get(&"a", now + Duration::from_secs(10)), Some(2));
+    }
+
+    #[test]
+    fn oldest_responses_are_evicted_at_capacity() {
+        let mut cache = cache(2, Duration::from_secs(10));
+        let now = Instant::now();
+
+        insert(&mut cache, "a", 1, now);
+        insert(&mut cache, "b", 2, now);
+        insert(&mut cache, "c", 3, now);
+
+        assert_eq!(cache.get(&"a", now), None);
+        assert_eq!(cache.get(&"b", now), Some(2));
+        assert_eq!(cache.get(&"c", now), Some(3));
+    }
+
+    #[test]
+    fn requests_with_colliding_hashes_are_told_apart() {
+        let mut cache = cache(8, Duration::from_secs(10));
+        cache.hash = |_, _| 0;
+        let now = Instant::now();
+
+        insert(&mut cache, "a", 1, now);
+        assert_eq!(cache.get(&"b", now), None);
+
+        insert(&mut cache, "b", 2, now);
+        assert_eq!(cache.get(&"a", now), Some(1));
+        assert_eq!(cache.get(&"b", now), Some(2));
+    }
+
+    #[test]
+    fn forgotten_requests_are_not_cached() {
+        let mut cache = cache(8, Duration::from_secs(10));
+        let now = Instant::now();
+
+        cache.track(InboundRequestId(0), &"a");
+        cache.forget(InboundRequestId(0));
+        cache.insert(InboundRequestId(0), &1, now);
+
+        assert_eq!(cache.get(&"a", now), None);
+        assert!(cache.pending.is_empty());
+    }
+}
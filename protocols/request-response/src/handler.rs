@@ -24,10 +24,14 @@ pub use protocol::ProtocolSupport;
 
 use crate::codec::Codec;
 use crate::handler::protocol::Protocol;
+use crate::rate_limit::PeerRateLimiter;
 use crate::{InboundRequestId, OutboundRequestId, EMPTY_QUEUE_SHRINK_THRESHOLD};
 
 use futures::channel::mpsc;
+use futures::future::{BoxFuture, Either};
+use futures::stream::FuturesUnordered;
 use futures::{channel::oneshot, prelude::*};
+use futures_timer::Delay;
 use libp2p_swarm::handler::{
     ConnectionEvent, DialUpgradeError, FullyNegotiatedInbound, FullyNegotiatedOutbound,
     ListenUpgradeError,
@@ -41,7 +45,7 @@ use std::{
     collections::VecDeque,
     fmt, io,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicU64, AtomicUsize, Ordering},
         Arc,
     },
     task::{Context, Poll},
@@ -59,8 +63,12 @@ where
     codec: TCodec,
     /// Queue of events to emit in `poll()`.
     pending_events: VecDeque<Event<TCodec>>,
-    /// Outbound upgrades waiting to be emitted as an `OutboundSubstreamRequest`.
+    /// Outbound upgrades waiting to be emitted as an `OutboundSubstreamRequest`,
+    /// ordered by descending priority.
     pending_outbound: VecDeque<OutboundMessage<TCodec>>,
+    /// The number of outbound requests sent by the behaviour that have not
+    /// yet been emitted as an `OutboundSubstreamRequest`.
+    queued_outbound: Arc<AtomicUsize>,
 
     requested_outbound: VecDeque<OutboundMessage<TCodec>>,
     /// A channel for receiving inbound requests.
@@ -78,13 +86,16 @@ where
 
     inbound_request_id: Arc<AtomicU64>,
 
-    worker_streams: futures_bounded::FuturesMap<RequestId, Result<Event<TCodec>, io::Error>>,
-}
+    /// The default timeout of inbound and outbound requests.
+    request_timeout: Duration,
+    /// The upper bound for the number of concurrent inbound + outbound streams.
+    max_concurrent_streams: usize,
+    /// The inbound rate limits of the remote peer, if any.
+    rate_limiter: Option<PeerRateLimiter>,
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum RequestId {
-    Inbound(InboundRequestId),
-    Outbound(OutboundRequestId),
+    worker_streams: futures_bounded::FuturesMap<InboundRequestId, Result<Event<TCodec>, io::Error>>,
+    /// Outbound streams, each with the timeout of its request.
+    outbound_streams: FuturesUnordered<BoxFuture<'static, Event<TCodec>>>,
 }
 
 impl<TCodec> Handler<TCodec>
@@ -97,24 +108,43 @@ where
         substream_timeout: Duration,
         inbound_request_id: Arc<AtomicU64>,
         max_concurrent_streams: usize,
+        rate_limiter: Option<PeerRateLimiter>,
     ) -> Self {
         let (inbound_sender, inbound_receiver) = mpsc::channel(0);
         Self {
             inbound_protocols,
             codec,
             pending_outbound: VecDeque::new(),
+            queued_outbound: Default::default(),
             requested_outbound: Default::default(),
             inbound_receiver,
             inbound_sender,
             pending_events: VecDeque::new(),
             inbound_request_id,
+            request_timeout: substream_timeout,
+            max_concurrent_streams,
+            rate_limiter,
             worker_streams: futures_bounded::FuturesMap::new(
                 substream_timeout,
                 max_concurrent_streams,
             ),
+            outbound_streams: FuturesUnordered::new(),
         }
     }
 
+    /// Whether the upper bound for the number of concurrent inbound streams is reached.
+    fn at_capacity(&self) -> bool {
+        self.worker_streams.len() >= self.max_concurrent_streams
+    }
+
+    /// Returns the number of outbound requests waiting for a stream, shared
+    /// with the behaviour to report the queue depth of a connection.
+    ///
+    /// The behaviour increments it for every request it sends to the handler.
+    pub(crate) fn queued_outbound(&self) -> Arc<AtomicUsize> {
+        self.queued_outbound.clone()
+    }
+
     /// Returns the next inbound request ID.
     fn next_inbound_request_id(&mut self) -> InboundRequestId {
         InboundRequestId(self.inbound_request_id.fetch_add(1, Ordering::Relaxed))
@@ -130,8 +160,35 @@ where
             <Self as ConnectionHandler>::InboundOpenInfo,
         >,
     ) {
-        let mut codec = self.codec.clone();
+        if self.at_capacity() {
+            tracing::warn!("Dropping inbound stream because we are at capacity");
+            return;
+        }
+
         let request_id = self.next_inbound_request_id();
+        if self
+            .rate_limiter
+            .as_ref()
+            .map_or(false, |limiter| !limiter.try_acquire())
+        {
+            tracing::debug!("Closing inbound stream because the rate limit is exceeded");
+            let reject = async move {
+                // Closing the stream without reading the request tells the
+                // remote that no response will follow.
+                let _ = stream.close().await;
+                Ok(Event::InboundRateLimited(request_id))
+            };
+            if self
+                .worker_streams
+                .try_push(request_id, reject.boxed())
+                .is_err()
+            {
+                tracing::warn!("Dropping inbound stream because we are at capacity")
+            }
+            return;
+        }
+
+        let mut codec = self.codec.clone();
         let mut sender = self.inbound_sender.clone();
 
         let recv = async move {
@@ -161,7 +218,7 @@ where
 
         if self
             .worker_streams
-            .try_push(RequestId::Inbound(request_id), recv.boxed())
+            .try_push(request_id, recv.boxed())
             .is_err()
         {
             tracing::warn!("Dropping inbound stream because we are at capacity")
@@ -185,6 +242,7 @@ where
 
         let mut codec = self.codec.clone();
         let request_id = message.request_id;
+        let timeout = message.timeout.unwrap_or(self.request_timeout);
 
         let send = async move {
             let write = codec.write_request(&protocol, &mut stream, message.request);
@@ -199,13 +257,22 @@ where
             })
         };
 
-        if self
-            .worker_streams
-            .try_push(RequestId::Outbound(request_id), send.boxed())
-            .is_err()
-        {
-            tracing::warn!("Dropping outbound stream because we are at capacity")
+        if self.outbound_streams.len() >= self.max_concurrent_streams {
+            self.pending_events.push_back(Event::OutboundStreamFailed {
+                request_id,
+                error: io::Error::new(io::ErrorKind::Other, "too many concurrent outbound streams"),
+            });
+            return;
         }
+
+        let send = async move {
+            match future::select(send.boxed(), Delay::new(timeout)).await {
+                Either::Left((Ok(event), _)) => event,
+                Either::Left((Err(error), _)) => Event::OutboundStreamFailed { request_id, error },
+                Either::Right(_) => Event::OutboundTimeout(request_id),
+            }
+        };
+        self.outbound_streams.push(send.boxed());
     }
 
     fn on_dial_upgrade_error(
@@ -288,6 +355,9 @@ where
     /// An inbound request timed out while waiting for the request
     /// or sending the response.
     InboundTimeout(InboundRequestId),
+    /// An inbound stream was closed without reading the request because
+    /// it exceeded the inbound rate limits.
+    InboundRateLimited(InboundRequestId),
     InboundStreamFailed {
         request_id: InboundRequestId,
         error: io::Error,
@@ -337,6 +407,10 @@ impl<TCodec: Codec> fmt::Debug for Event<TCodec> {
                 .debug_tuple("Event::InboundTimeout")
                 .field(request_id)
                 .finish(),
+            Event::InboundRateLimited(request_id) => f
+                .debug_tuple("Event::InboundRateLimited")
+                .field(request_id)
+                .finish(),
             Event::InboundStreamFailed { request_id, error } => f
                 .debug_struct("Event::InboundStreamFailed")
                 .field("request_id", &request_id)
@@ -350,6 +424,10 @@ pub struct OutboundMessage<TCodec: Codec> {
     pub(crate) request_id: OutboundRequestId,
     pub(crate) request: TCodec::Request,
     pub(crate) protocols: SmallVec<[TCodec::Protocol; 2]>,
+    /// Overrides the default request timeout, see [`RequestOptions::timeout`](crate::RequestOptions::timeout).
+    pub(crate) timeout: Option<Duration>,
+    /// See [`RequestOptions::priority`](crate::RequestOptions::priority).
+    pub(crate) priority: u8,
 }
 
 impl<TCodec> fmt::Debug for OutboundMessage<TCodec>
@@ -382,7 +460,13 @@ where
     }
 
     fn on_behaviour_event(&mut self, request: Self::FromBehaviour) {
-        self.pending_outbound.push_back(request);
+        // Queue the request after all requests of the same or a higher priority.
+        let index = self
+            .pending_outbound
+            .iter()
+            .position(|m| m.priority < request.priority)
+            .unwrap_or(self.pending_outbound.len());
+        self.pending_outbound.insert(index, request);
     }
 
     #[tracing::instrument(level = "trace", name = "ConnectionHandler::poll", skip(self, cx))]
@@ -394,7 +478,7 @@ where
             Poll::Ready((_, Ok(Ok(event)))) => {
                 return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event));
             }
-            Poll::Ready((RequestId::Inbound(id), Ok(Err(e)))) => {
+            Poll::Ready((id, Ok(Err(e)))) => {
                 return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
                     Event::InboundStreamFailed {
                         request_id: id,
@@ -402,27 +486,18 @@ where
                     },
                 ));
             }
-            Poll::Ready((RequestId::Outbound(id), Ok(Err(e)))) => {
-                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
-                    Event::OutboundStreamFailed {
-                        request_id: id,
-                        error: e,
-                    },
-                ));
-            }
-            Poll::Ready((RequestId::Inbound(id), Err(futures_bounded::Timeout { .. }))) => {
+            Poll::Ready((id, Err(futures_bounded::Timeout { .. }))) => {
                 return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
                     Event::InboundTimeout(id),
                 ));
             }
-            Poll::Ready((RequestId::Outbound(id), Err(futures_bounded::Timeout { .. }))) => {
-                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
-                    Event::OutboundTimeout(id),
-                ));
-            }
             Poll::Pending => {}
         }
 
+        if let Poll::Ready(Some(event)) = self.outbound_streams.poll_next_unpin(cx) {
+            return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event));
+        }
+
         // Drain pending events that were produced by `worker_streams`.
         if let Some(event) = self.pending_events.pop_front() {
             return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event));
@@ -441,14 +516,22 @@ where
             }));
         }
 
-        // Emit outbound requests.
+        // Emit outbound requests, keeping them queued while at the limit of concurrent
+        // outbound streams. Inbound streams have their own budget in `worker_streams`.
+        let num_outbound = self.outbound_streams.len() + self.requested_outbound.len();
+        if num_outbound >= self.max_concurrent_streams {
+            return Poll::Pending;
+        }
         if let Some(request) = self.pending_outbound.pop_front() {
+            self.queued_outbound.fetch_sub(1, Ordering::Relaxed);
             let protocols = request.protocols.clone();
+            let mut protocol = SubstreamProtocol::new(Protocol { protocols }, ());
+            if let Some(timeout) = request.timeout {
+                protocol = protocol.with_timeout(timeout);
+            }
             self.requested_outbound.push_back(request);
 
-            return Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest {
-                protocol: SubstreamProtocol::new(Protocol { protocols }, ()),
-            });
+            return Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest { protocol });
         }
 
         debug_assert!(self.pending_outbound.is_empty());
@@ -47,6 +47,15 @@
 //! - [`cbor::Behaviour`] for CBOR-encoded messages
 //! - [`json::Behaviour`] for JSON-encoded messages
 //!
+//! For other data formats, [`serde_codec::Behaviour`] is generic over any
+//! [`serde_codec::Format`] and supports compression and size limits per protocol.
+//!
+//! ## Streamed bodies
+//!
+//! For large, file-like payloads that should not be buffered in memory, [`streaming::Behaviour`]
+//! exposes requests and responses as a [`streaming::Body`] implementing
+//! [`AsyncRead`](futures::AsyncRead).
+//!
 //! ## Protocol Families
 //!
 //! A single [`Behaviour`] instance can be used with an entire
@@ -66,19 +75,28 @@
 
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+mod cache;
 #[cfg(feature = "cbor")]
 pub mod cbor;
 mod codec;
 mod handler;
 #[cfg(feature = "json")]
 pub mod json;
+mod rate_limit;
+#[cfg(feature = "serde-codec")]
+pub mod serde_codec;
+pub mod streaming;
 
+pub use cache::ResponseCacheConfig;
 pub use codec::Codec;
 pub use handler::ProtocolSupport;
+pub use rate_limit::RateLimit;
 
 use crate::handler::OutboundMessage;
+use cache::ResponseCache;
 use futures::channel::oneshot;
 use handler::Handler;
+use instant::Instant;
 use libp2p_core::{ConnectedPoint, Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
 use libp2p_swarm::{
@@ -87,11 +105,17 @@ use libp2p_swarm::{
     ConnectionDenied, ConnectionHandler, ConnectionId, NetworkBehaviour, NotifyHandler,
     PeerAddresses, THandler, THandlerInEvent, THandlerOutEvent, ToSwarm,
 };
+use rate_limit::{PeerRateLimiter, RateLimiter};
 use smallvec::SmallVec;
 use std::{
     collections::{HashMap, HashSet, VecDeque},
-    fmt, io,
-    sync::{atomic::AtomicU64, Arc},
+    fmt,
+    hash::Hash,
+    io,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
     task::{Context, Poll},
     time::Duration,
 };
@@ -223,6 +247,9 @@ pub enum InboundFailure {
     ResponseOmission,
     /// An IO failure happened on an inbound stream.
     Io(io::Error),
+    /// The inbound request exceeded a configured rate limit and its stream was
+    /// closed without reading the request, see [`Config::with_inbound_rate_limit`].
+    RateLimited,
 }
 
 impl fmt::Display for InboundFailure {
@@ -243,6 +270,9 @@ impl fmt::Display for InboundFailure {
                 "The response channel was dropped without sending a response to the remote"
             ),
             InboundFailure::Io(e) => write!(f, "IO error on inbound stream: {e}"),
+            InboundFailure::RateLimited => {
+                write!(f, "The inbound request exceeded the configured rate limit")
+            }
         }
     }
 }
@@ -255,6 +285,8 @@ impl std::error::Error for InboundFailure {}
 #[derive(Debug)]
 pub struct ResponseChannel<TResponse> {
     sender: oneshot::Sender<TResponse>,
+    /// The ID of the request tracked by the response cache, if enabled.
+    cached_request: Option<InboundRequestId>,
 }
 
 impl<TResponse> ResponseChannel<TResponse> {
@@ -301,6 +333,9 @@ impl fmt::Display for OutboundRequestId {
 pub struct Config {
     request_timeout: Duration,
     max_concurrent_streams: usize,
+    inbound_rate_limit: Option<RateLimit>,
+    inbound_rate_limit_per_peer: Option<RateLimit>,
+    peer_address_resolution: bool,
 }
 
 impl Default for Config {
@@ -308,6 +343,9 @@ impl Default for Config {
         Self {
             request_timeout: Duration::from_secs(10),
             max_concurrent_streams: 100,
+            inbound_rate_limit: None,
+            inbound_rate_limit_per_peer: None,
+            peer_address_resolution: true,
         }
     }
 }
@@ -331,6 +369,84 @@ impl Config {
         self.max_concurrent_streams = num_streams;
         self
     }
+
+    /// Limits the rate of inbound requests across all peers.
+    ///
+    /// Streams of requests exceeding the limit are closed before the request is
+    /// read, without a response. They are never passed to the application but
+    /// reported as [`InboundFailure::RateLimited`]. Only applies to [`Behaviour`].
+    ///
+    /// Unlimited by default.
+    pub fn with_inbound_rate_limit(mut self, limit: RateLimit) -> Self {
+        self.inbound_rate_limit = Some(limit);
+        self
+    }
+
+    /// Limits the rate of inbound requests of each individual peer.
+    ///
+    /// See [`Config::with_inbound_rate_limit`] for how excess requests are handled.
+    ///
+    /// Unlimited by default.
+    pub fn with_inbound_rate_limit_per_peer(mut self, limit: RateLimit) -> Self {
+        self.inbound_rate_limit_per_peer = Some(limit);
+        self
+    }
+
+    /// Sets whether requests to disconnected peers dial the addresses the swarm knows of them.
+    ///
+    /// If enabled, the addresses reported via [`Swarm::add_peer_address`](libp2p_swarm::Swarm::add_peer_address)
+    /// or [`ToSwarm::NewExternalAddrOfPeer`] are dialed, starting with the address of the last
+    /// successful outbound connection to the peer. Otherwise only addresses of established
+    /// connections, those added via [`Behaviour::add_address`] and those provided by other
+    /// behaviours are dialed.
+    ///
+    /// Enabled by default.
+    pub fn with_peer_address_resolution(mut self, enabled: bool) -> Self {
+        self.peer_address_resolution = enabled;
+        self
+    }
+}
+
+/// Options for an individual outbound request, see [`Behaviour::send_request_with`].
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    /// Overrides the [`Config`] request timeout for this request.
+    pub timeout: Option<Duration>,
+    /// How often the request is retried on another established connection to the peer
+    /// if sending it or receiving the response fails.
+    ///
+    /// Only use this for idempotent requests, as the remote may have processed a failed attempt.
+    pub retries: u8,
+    /// Which established connection to the peer to send the request on.
+    pub connection_preference: ConnectionPreference,
+    /// The priority of the request among the queued requests to the same peer.
+    ///
+    /// Requests are queued while the peer is not connected or the connection is at its
+    /// limit of concurrent streams, see [`Config::with_max_concurrent_streams`]. Queued
+    /// requests with a higher priority are sent first, those of equal priority in the
+    /// order in which they were made. Defaults to `0`, the lowest priority.
+    pub priority: u8,
+}
+
+/// The established connection to send a request on, see [`RequestOptions`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConnectionPreference {
+    /// Spread requests over all established connections.
+    #[default]
+    Any,
+    /// Prefer the connection that has been established first.
+    Oldest,
+    /// Prefer the connection that has been established last.
+    Newest,
+}
+
+/// A request that is retried on another connection if it fails.
+struct PendingRetry<TRequest> {
+    request: TRequest,
+    clone: fn(&TRequest) -> TRequest,
+    options: RequestOptions,
+    /// The connections the request has been sent on.
+    tried: SmallVec<[ConnectionId; 2]>,
 }
 
 /// A request/response protocol for some message codec.
@@ -361,6 +477,12 @@ where
     /// Requests that have not yet been sent and are waiting for a connection
     /// to be established.
     pending_outbound_requests: HashMap<PeerId, SmallVec<[OutboundMessage<TCodec>; 10]>>,
+    /// Outbound requests that are retried if they fail, see [`RequestOptions::retries`].
+    pending_retries: HashMap<OutboundRequestId, PendingRetry<TCodec::Request>>,
+    /// Token buckets of the configured inbound rate limits, shared with the handlers.
+    rate_limiter: Option<Arc<Mutex<RateLimiter>>>,
+    /// Cached responses to inbound requests, see [`Behaviour::enable_response_cache`].
+    response_cache: Option<ResponseCache<TCodec::Request, TCodec::Response>>,
 }
 
 impl<TCodec> Behaviour<TCodec>
@@ -396,16 +518,27 @@ where
                 outbound_protocols.push(p.clone());
             }
         }
+        let rate_limiter = (cfg.inbound_rate_limit_per_peer.is_some()
+            || cfg.inbound_rate_limit.is_some())
+        .then(|| {
+            Arc::new(Mutex::new(RateLimiter::new(
+                cfg.inbound_rate_limit_per_peer,
+                cfg.inbound_rate_limit,
+            )))
+        });
         Behaviour {
             inbound_protocols,
             outbound_protocols,
             next_outbound_request_id: OutboundRequestId(1),
             next_inbound_request_id: Arc::new(AtomicU64::new(1)),
+            rate_limiter,
+            response_cache: None,
             config: cfg,
             codec,
             pending_events: VecDeque::new(),
             connected: HashMap::new(),
             pending_outbound_requests: HashMap::new(),
+            pending_retries: HashMap::new(),
             addresses: PeerAddresses::default(),
         }
     }
@@ -420,27 +553,72 @@ where
     /// > the `RequestResonse` protocol must either be embedded
     /// > in another `NetworkBehaviour` that provides peer and
     /// > address discovery, or known addresses of peers must be
-    /// > managed via [`Behaviour::add_address`] and
-    /// > [`Behaviour::remove_address`].
+    /// > reported to the swarm, see [`Config::with_peer_address_resolution`].
     pub fn send_request(&mut self, peer: &PeerId, request: TCodec::Request) -> OutboundRequestId {
         let request_id = self.next_outbound_request_id();
+        self.enqueue_request(peer, request_id, request, &RequestOptions::default());
+        request_id
+    }
+
+    /// Initiates sending a request with the given [`RequestOptions`].
+    ///
+    /// Behaves like [`Behaviour::send_request`], but allows overriding the request timeout,
+    /// retrying the request on another established connection to the peer if it fails and
+    /// choosing the connection to send it on.
+    pub fn send_request_with(
+        &mut self,
+        peer: &PeerId,
+        request: TCodec::Request,
+        options: RequestOptions,
+    ) -> OutboundRequestId
+    where
+        TCodec::Request: Clone,
+    {
+        let request_id = self.next_outbound_request_id();
+        if options.retries > 0 {
+            self.pending_retries.insert(
+                request_id,
+                PendingRetry {
+                    request: request.clone(),
+                    clone: <TCodec::Request as Clone>::clone,
+                    options: options.clone(),
+                    tried: SmallVec::new(),
+                },
+            );
+        }
+        self.enqueue_request(peer, request_id, request, &options);
+        request_id
+    }
+
+    fn enqueue_request(
+        &mut self,
+        peer: &PeerId,
+        request_id: OutboundRequestId,
+        request: TCodec::Request,
+        options: &RequestOptions,
+    ) {
         let request = OutboundMessage {
             request_id,
             request,
             protocols: self.outbound_protocols.clone(),
+            timeout: options.timeout,
+            priority: options.priority,
         };
 
-        if let Some(request) = self.try_send_request(peer, request) {
+        if let Some(request) =
+            self.try_send_request(peer, request, options.connection_preference, &[])
+        {
             self.pending_events.push_back(ToSwarm::Dial {
                 opts: DialOpts::peer_id(*peer).build(),
             });
-            self.pending_outbound_requests
-                .entry(*peer)
-                .or_default()
-                .push(request);
+            // Queue the request after all requests of the same or a higher priority.
+            let pending = self.pending_outbound_requests.entry(*peer).or_default();
+            let index = pending
+                .iter()
+                .position(|m| m.priority < request.priority)
+                .unwrap_or(pending.len());
+            pending.insert(index, request);
         }
-
-        request_id
     }
 
     /// Initiates sending a response to an inbound request.
@@ -459,9 +637,26 @@ where
         ch: ResponseChannel<TCodec::Response>,
         rs: TCodec::Response,
     ) -> Result<(), TCodec::Response> {
+        if let (Some(cache), Some(request_id)) = (self.response_cache.as_mut(), ch.cached_request) {
+            cache.insert(request_id, &rs, Instant::now());
+        }
         ch.sender.send(rs)
     }
 
+    /// Enables caching of the responses sent via [`Behaviour::send_response`].
+    ///
+    /// An inbound request equal to an earlier request is answered with
+    /// the cached response to the latter, without emitting an [`Event::Message`] or
+    /// [`Event::ResponseSent`] for it. This is only correct for protocols whose responses
+    /// depend on nothing but the request, e.g. those serving static content.
+    pub fn enable_response_cache(&mut self, config: ResponseCacheConfig)
+    where
+        TCodec::Request: Hash + Eq + Clone,
+        TCodec::Response: Clone,
+    {
+        self.response_cache = Some(ResponseCache::new(config));
+    }
+
     /// Adds a known address for a peer that can be used for
     /// dialing attempts by the `Swarm`, i.e. is returned
     /// by [`NetworkBehaviour::handle_pending_outbound_connection`].
@@ -513,6 +708,25 @@ where
         est_conn || pen_conn
     }
 
+    /// Returns the number of outbound requests to the peer that are queued,
+    /// i.e. not yet sent because the peer is not connected or its connections
+    /// are at their limit of concurrent streams.
+    ///
+    /// See [`RequestOptions::priority`] for the order in which they are sent.
+    pub fn queued_requests(&self, peer: &PeerId) -> usize {
+        let not_connected = self
+            .pending_outbound_requests
+            .get(peer)
+            .map_or(0, |rps| rps.len());
+        let connected = self.connected.get(peer).map_or(0, |cs| {
+            cs.iter()
+                .map(|c| c.queued_outbound.load(Ordering::Relaxed))
+                .sum()
+        });
+
+        not_connected + connected
+    }
+
     /// Checks whether an inbound request from the peer with the provided
     /// [`PeerId`] is still pending, i.e. waiting for a response by the local
     /// node through [`Behaviour::send_response`].
@@ -540,23 +754,76 @@ where
         &mut self,
         peer: &PeerId,
         request: OutboundMessage<TCodec>,
+        preference: ConnectionPreference,
+        exclude: &[ConnectionId],
     ) -> Option<OutboundMessage<TCodec>> {
-        if let Some(connections) = self.connected.get_mut(peer) {
-            if connections.is_empty() {
-                return Some(request);
-            }
-            let ix = (request.request_id.0 as usize) % connections.len();
-            let conn = &mut connections[ix];
-            conn.pending_outbound_responses.insert(request.request_id);
-            self.pending_events.push_back(ToSwarm::NotifyHandler {
-                peer_id: *peer,
-                handler: NotifyHandler::One(conn.id),
-                event: request,
-            });
-            None
-        } else {
-            Some(request)
+        let Some(connections) = self.connected.get_mut(peer) else {
+            return Some(request);
+        };
+        let mut candidates = connections
+            .iter_mut()
+            .filter(|c| !exclude.contains(&c.id))
+            .collect::<SmallVec<[_; 2]>>();
+        if candidates.is_empty() {
+            return Some(request);
         }
+        let ix = match preference {
+            ConnectionPreference::Any => (request.request_id.0 as usize) % candidates.len(),
+            ConnectionPreference::Oldest => 0,
+            ConnectionPreference::Newest => candidates.len() - 1,
+        };
+        let conn = &mut candidates[ix];
+        conn.pending_outbound_responses.insert(request.request_id);
+        conn.queued_outbound.fetch_add(1, Ordering::Relaxed);
+        self.pending_events.push_back(ToSwarm::NotifyHandler {
+            peer_id: *peer,
+            handler: NotifyHandler::One(conn.id),
+            event: request,
+        });
+        None
+    }
+
+    /// Retries a failed outbound request on another established connection to the peer,
+    /// if permitted by its [`RequestOptions::retries`].
+    ///
+    /// Returns `true` if the request is retried.
+    fn try_retry(
+        &mut self,
+        peer: &PeerId,
+        connection: ConnectionId,
+        request_id: OutboundRequestId,
+    ) -> bool {
+        let Some(mut retry) = self.pending_retries.remove(&request_id) else {
+            return false;
+        };
+        if retry.options.retries == 0 {
+            return false;
+        }
+        retry.tried.push(connection);
+
+        let request = OutboundMessage {
+            request_id,
+            request: (retry.clone)(&retry.request),
+            protocols: self.outbound_protocols.clone(),
+            timeout: retry.options.timeout,
+            priority: retry.options.priority,
+        };
+        if self
+            .try_send_request(
+                peer,
+                request,
+                retry.options.connection_preference,
+                &retry.tried,
+            )
+            .is_some()
+        {
+            return false;
+        }
+
+        tracing::debug!(%peer, "Retrying outbound request {request_id} on another connection");
+        retry.options.retries -= 1;
+        self.pending_retries.insert(request_id, retry);
+        true
     }
 
     /// Remove pending outbound response for the given peer and connection.
@@ -591,6 +858,26 @@ where
             .unwrap_or(false)
     }
 
+    /// Removes an inbound request answered from the response cache, returning
+    /// whether there was one with the given ID.
+    fn remove_cached_inbound_response(
+        &mut self,
+        peer: &PeerId,
+        connection: ConnectionId,
+        request: InboundRequestId,
+    ) -> bool {
+        self.get_connection_mut(peer, connection)
+            .map(|c| c.cached_inbound_responses.remove(&request))
+            .unwrap_or(false)
+    }
+
+    /// Stops tracking an inbound request for the response cache, e.g. because it failed.
+    fn forget_cached_request(&mut self, request: InboundRequestId) {
+        if let Some(cache) = self.response_cache.as_mut() {
+            cache.forget(request);
+        }
+    }
+
     /// Returns a mutable reference to the connection in `self.connected`
     /// corresponding to the given [`PeerId`] and [`ConnectionId`].
     fn get_connection_mut(
@@ -654,6 +941,7 @@ where
         }
 
         for request_id in connection.pending_inbound_responses {
+            self.forget_cached_request(request_id);
             self.pending_events
                 .push_back(ToSwarm::GenerateEvent(Event::InboundFailure {
                     peer: peer_id,
@@ -663,6 +951,9 @@ where
         }
 
         for request_id in connection.pending_outbound_responses {
+            if self.try_retry(&peer_id, connection_id, request_id) {
+                continue;
+            }
             self.pending_events
                 .push_back(ToSwarm::GenerateEvent(Event::OutboundFailure {
                     peer: peer_id,
@@ -682,6 +973,7 @@ where
             // another, concurrent dialing attempt ongoing.
             if let Some(pending) = self.pending_outbound_requests.remove(&peer) {
                 for request in pending {
+                    self.pending_retries.remove(&request.request_id);
                     self.pending_events
                         .push_back(ToSwarm::GenerateEvent(Event::OutboundFailure {
                             peer,
@@ -702,12 +994,14 @@ where
         remote_address: Option<Multiaddr>,
     ) {
         let mut connection = Connection::new(connection_id, remote_address);
+        connection.queued_outbound = handler.queued_outbound();
 
         if let Some(pending_requests) = self.pending_outbound_requests.remove(&peer) {
             for request in pending_requests {
                 connection
                     .pending_outbound_responses
                     .insert(request.request_id);
+                connection.queued_outbound.fetch_add(1, Ordering::Relaxed);
                 handler.on_behaviour_event(request);
             }
         }
@@ -736,6 +1030,9 @@ where
             self.config.request_timeout,
             self.next_inbound_request_id.clone(),
             self.config.max_concurrent_streams,
+            self.rate_limiter
+                .clone()
+                .map(|limiter| PeerRateLimiter::new(peer, limiter)),
         );
 
         self.preload_new_handler(&mut handler, peer, connection_id, None);
@@ -760,6 +1057,14 @@ where
             addresses.extend(connections.iter().filter_map(|c| c.remote_address.clone()))
         }
 
+        if self.config.peer_address_resolution {
+            if let Some((address, _)) = self.addresses.last_successful_address(&peer) {
+                if !addresses.contains(address) {
+                    addresses.push(address.clone());
+                }
+            }
+        }
+
         let cached_addrs = self.addresses.get(&peer);
         addresses.extend(cached_addrs);
 
@@ -779,6 +1084,9 @@ where
             self.config.request_timeout,
             self.next_inbound_request_id.clone(),
             self.config.max_concurrent_streams,
+            self.rate_limiter
+                .clone()
+                .map(|limiter| PeerRateLimiter::new(peer, limiter)),
         );
 
         self.preload_new_handler(
@@ -792,7 +1100,10 @@ where
     }
 
     fn on_swarm_event(&mut self, event: FromSwarm) {
-        self.addresses.on_swarm_event(&event);
+        // Without address resolution, only prune addresses added via `add_address` on failed dials.
+        if self.config.peer_address_resolution || matches!(event, FromSwarm::DialFailure(_)) {
+            self.addresses.on_swarm_event(&event);
+        }
         match event {
             FromSwarm::ConnectionEstablished(_) => {}
             FromSwarm::ConnectionClosed(connection_closed) => {
@@ -820,6 +1131,7 @@ where
                     removed,
                     "Expect request_id to be pending before receiving response.",
                 );
+                self.pending_retries.remove(&request_id);
 
                 let message = Message::Response {
                     request_id,
@@ -832,25 +1144,53 @@ where
                 request_id,
                 request,
                 sender,
-            } => match self.get_connection_mut(&peer, connection) {
-                Some(connection) => {
-                    let inserted = connection.pending_inbound_responses.insert(request_id);
-                    debug_assert!(inserted, "Expect id of new request to be unknown.");
-
-                    let channel = ResponseChannel { sender };
-                    let message = Message::Request {
-                        request_id,
-                        request,
-                        channel,
-                    };
-                    self.pending_events
-                        .push_back(ToSwarm::GenerateEvent(Event::Message { peer, message }));
-                }
-                None => {
-                    tracing::debug!("Connection ({connection}) closed after `Event::Request` ({request_id}) has been emitted.");
+            } => {
+                let cached = self
+                    .response_cache
+                    .as_ref()
+                    .and_then(|c| c.get(&request, Instant::now()));
+                let cached_request = match self.response_cache.as_mut() {
+                    Some(cache) if cached.is_none() => {
+                        cache.track(request_id, &request);
+                        Some(request_id)
+                    }
+                    _ => None,
+                };
+                match (self.get_connection_mut(&peer, connection), cached) {
+                    (Some(connection), Some(response)) => {
+                        connection.cached_inbound_responses.insert(request_id);
+
+                        tracing::trace!(%peer, %request_id, "Answering inbound request from the response cache");
+                        let _ = sender.send(response);
+                    }
+                    (Some(connection), None) => {
+                        let inserted = connection.pending_inbound_responses.insert(request_id);
+                        debug_assert!(inserted, "Expect id of new request to be unknown.");
+
+                        let channel = ResponseChannel {
+                            sender,
+                            cached_request,
+                        };
+                        let message = Message::Request {
+                            request_id,
+                            request,
+                            channel,
+                        };
+                        self.pending_events
+                            .push_back(ToSwarm::GenerateEvent(Event::Message { peer, message }));
+                    }
+                    (None, _) => {
+                        self.forget_cached_request(request_id);
+                        tracing::debug!("Connection ({connection}) closed after `Event::Request` ({request_id}) has been emitted.");
+                    }
                 }
-            },
+            }
             handler::Event::ResponseSent(request_id) => {
+                if self.remove_cached_inbound_response(&peer, connection, request_id) {
+                    // Answered from the response cache.
+                    return;
+                }
+
                 let removed = self.remove_pending_inbound_response(&peer, connection, request_id);
                 debug_assert!(
                     removed,
@@ -864,6 +1204,7 @@ where
                     }));
             }
             handler::Event::ResponseOmission(request_id) => {
+                self.forget_cached_request(request_id);
                 let removed = self.remove_pending_inbound_response(&peer, connection, request_id);
                 debug_assert!(
                     removed,
@@ -884,6 +1225,9 @@ where
                     "Expect request_id to be pending before request times out."
                 );
 
+                if self.try_retry(&peer, connection, request_id) {
+                    return;
+                }
                 self.pending_events
                     .push_back(ToSwarm::GenerateEvent(Event::OutboundFailure {
                         peer,
@@ -897,6 +1241,7 @@ where
                     removed,
                     "Expect request_id to be pending before failing to connect.",
                 );
+                self.pending_retries.remove(&request_id);
 
                 self.pending_events
                     .push_back(ToSwarm::GenerateEvent(Event::OutboundFailure {
@@ -909,6 +1254,9 @@ where
                 let removed = self.remove_pending_outbound_response(&peer, connection, request_id);
                 debug_assert!(removed, "Expect request_id to be pending upon failure");
 
+                if self.try_retry(&peer, connection, request_id) {
+                    return;
+                }
                 self.pending_events
                     .push_back(ToSwarm::GenerateEvent(Event::OutboundFailure {
                         peer,
@@ -917,6 +1265,14 @@ where
                     }))
             }
             handler::Event::InboundTimeout(request_id) => {
+                self.forget_cached_request(request_id);
+                if self.remove_cached_inbound_response(&peer, connection, request_id) {
+                    tracing::debug!(
+                        "Inbound request timeout while sending cached response ({request_id})"
+                    );
+                    return;
+                }
+
                 let removed = self.remove_pending_inbound_response(&peer, connection, request_id);
 
                 if removed {
@@ -933,7 +1289,22 @@ where
                     );
                 }
             }
+            handler::Event::InboundRateLimited(request_id) => {
+                tracing::debug!(%peer, %request_id, "Rejected inbound request exceeding the rate limit");
+                self.pending_events
+                    .push_back(ToSwarm::GenerateEvent(Event::InboundFailure {
+                        peer,
+                        request_id,
+                        error: InboundFailure::RateLimited,
+                    }));
+            }
             handler::Event::InboundStreamFailed { request_id, error } => {
+                self.forget_cached_request(request_id);
+                if self.remove_cached_inbound_response(&peer, connection, request_id) {
+                    tracing::debug!("Inbound stream failed while sending cached response ({request_id}): {error}");
+                    return;
+                }
+
                 let removed = self.remove_pending_inbound_response(&peer, connection, request_id);
 
                 if removed {
@@ -980,6 +1351,11 @@ struct Connection {
     /// Pending inbound responses for previously sent requests on this
     /// connection.
     pending_inbound_responses: HashSet<InboundRequestId>,
+    /// Inbound requests answered from the response cache, which are not reported
+    /// to the application.
+    cached_inbound_responses: HashSet<InboundRequestId>,
+    /// The number of outbound requests queued in the handler of this connection.
+    queued_outbound: Arc<AtomicUsize>,
 }
 
 impl Connection {
@@ -989,6 +1365,8 @@ impl Connection {
             remote_address,
             pending_outbound_responses: Default::default(),
             pending_inbound_responses: Default::default(),
+            cached_inbound_responses: Default::default(),
+            queued_outbound: Default::default(),
         }
     }
 }
@@ -0,0 +1,205 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Token bucket rate limiting of inbound requests.
+
+use instant::Instant;
+use libp2p_identity::PeerId;
+use lru::LruCache;
+use std::{
+    num::{NonZeroU32, NonZeroUsize},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+/// The maximum number of peers whose token buckets are remembered.
+///
+/// Buckets are kept after a peer disconnects, so that reconnecting doesn't reset its budget.
+/// Beyond this number the least recently used bucket is forgotten.
+const MAX_TRACKED_PEERS: usize = 10_000;
+
+/// A token bucket limit on the number of inbound requests.
+///
+/// Allows bursts of up to `max_requests` requests, refilling the bucket at a
+/// rate of `max_requests` per `interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    max_requests: NonZeroU32,
+    interval: Duration,
+}
+
+impl RateLimit {
+    /// Creates a new limit of `max_requests` requests per `interval`.
+    pub fn new(max_requests: NonZeroU32, interval: Duration) -> Self {
+        Self {
+            max_requests,
+            interval,
+        }
+    }
+}
+
+/// Tracks the token buckets for a global and a per-peer [`RateLimit`].
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    per_peer: Option<RateLimit>,
+    global: Option<Bucket>,
+    peers: LruCache<PeerId, Bucket>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(per_peer: Option<RateLimit>, global: Option<RateLimit>) -> Self {
+        Self {
+            per_peer,
+            global: global.map(Bucket::new),
+            peers: LruCache::new(NonZeroUsize::new(MAX_TRACKED_PEERS).expect("10000 > 0")),
+        }
+    }
+
+    /// Takes a token for a request of `peer` from the global and the peer's bucket.
+    ///
+    /// Returns `false` without taking any token if either bucket is empty.
+    pub(crate) fn try_acquire(&mut self, peer: PeerId, now: Instant) -> bool {
+        let peer_bucket = self
+            .per_peer
+            .map(|limit| self.peers.get_or_insert_mut(peer, || Bucket::new(limit)));
+
+        let has_global = self.global.as_mut().map_or(true, |b| b.refill(now) >= 1.0);
+        let has_peer = peer_bucket.map_or(true, |b| {
+            if b.refill(now) < 1.0 {
+                return false;
+            }
+            if has_global {
+                b.tokens -= 1.0;
+            }
+            true
+        });
+
+        if !(has_global && has_peer) {
+            return false;
+        }
+        if let Some(global) = self.global.as_mut() {
+            global.tokens -= 1.0;
+        }
+        true
+    }
+}
+
+/// The [`RateLimiter`] of a [`Behaviour`](crate::Behaviour) as seen by the handlers of one
+/// peer's connections, which check it before reading an inbound request.
+#[derive(Debug, Clone)]
+pub(crate) struct PeerRateLimiter {
+    peer: PeerId,
+    limiter: Arc<Mutex<RateLimiter>>,
+}
+
+impl PeerRateLimiter {
+    pub(crate) fn new(peer: PeerId, limiter: Arc<Mutex<RateLimiter>>) -> Self {
+        Self { peer, limiter }
+    }
+
+    /// Takes a token for a request of the peer, see [`RateLimiter::try_acquire`].
+    pub(crate) fn try_acquire(&self) -> bool {
+        self.limiter
+            .lock()
+            .expect("lock not to be poisoned")
+            .try_acquire(self.peer, Instant::now())
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    last_refill: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.max_requests.get()),
+            last_refill: None,
+        }
+    }
+
+    /// Adds the tokens accrued since the last refill and returns the current amount.
+    fn refill(&mut self, now: Instant) -> f64 {
+        let max = f64::from(self.limit.max_requests.get());
+        if let Some(last) = self.last_refill {
+            let elapsed = now.duration_since(last).as_secs_f64();
+            let interval = self.limit.interval.as_secs_f64();
+            let accrued = if interval > 0.0 {
+                elapsed / interval * max
+            } else {
+                max
+            };
+            self.tokens = (self.tokens + accrued).min(max);
+        }
+        self.last_refill = Some(now);
+        self.tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(max_requests: u32, interval: Duration) -> RateLimit {
+        RateLimit::new(NonZeroU32::new(max_requests).unwrap(), interval)
+    }
+
+    #[test]
+    fn per_peer_limit_refills_over_time() {
+        let mut limiter = RateLimiter::new(Some(limit(2, Duration::from_secs(1))), None);
+        let peer = PeerId::random();
+        let other = PeerId::random();
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(peer, now));
+        assert!(limiter.try_acquire(peer, now));
+        assert!(!limiter.try_acquire(peer, now));
+        assert!(limiter.try_acquire(other, now));
+
+        assert!(limiter.try_acquire(peer, now + Duration::from_millis(500)));
+        assert!(!limiter.try_acquire(peer, now + Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn global_limit_applies_across_peers() {
+        let mut limiter = RateLimiter::new(
+            Some(limit(2, Duration::from_secs(1000))),
+            Some(limit(3, Duration::from_secs(1))),
+        );
+        let peer = PeerId::random();
+        let other = PeerId::random();
+        let now = Instant::now();
+
+        assert!(limiter.try_acquire(peer, now));
+        assert!(limiter.try_acquire(peer, now));
+        assert!(!limiter.try_acquire(peer, now));
+        assert!(limiter.try_acquire(other, now));
+        assert!(!limiter.try_acquire(other, now));
+
+        // A rejected request doesn't consume a token of the peer's bucket.
+        let later = now + Duration::from_secs(1);
+        assert!(limiter.try_acquire(other, later));
+        assert!(!limiter.try_acquire(other, later));
+    }
+}
@@ -0,0 +1,506 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! A request-response codec generic over any [`serde`] data format, with optional compression
+//! and size limits configurable per protocol.
+//!
+//! Messages are serialized with a [`Format`], compressed according to the
+//! [`ProtocolConfig`] of the negotiated protocol and written as is, i.e. the end of a message
+//! is signalled by closing the stream. Both peers need to use the same configuration for a
+//! protocol, as compression is not negotiated.
+
+use async_trait::async_trait;
+use futures::prelude::*;
+use libp2p_swarm::StreamProtocol;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{collections::HashMap, fmt, io, marker::PhantomData};
+
+/// A request-response behaviour serializing messages with the [`Format`] `F`.
+///
+/// # Example
+///
+/// ```
+/// # #[cfg(feature = "cbor")] {
+/// # use libp2p_request_response::{serde_codec, ProtocolSupport, self as request_response};
+/// # use libp2p_swarm::StreamProtocol;
+/// #[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// struct GreetRequest {
+///     name: String,
+/// }
+///
+/// #[derive(Debug, serde::Serialize, serde::Deserialize)]
+/// struct GreetResponse {
+///     message: String,
+/// }
+///
+/// let protocol = StreamProtocol::new("/my-greet-protocol");
+/// let codec = serde_codec::Codec::<serde_codec::Cbor, GreetRequest, GreetResponse>::default()
+///     .with_protocol_config(
+///         protocol.clone(),
+///         serde_codec::ProtocolConfig::default().with_max_request_size(1024),
+///     );
+/// let behaviour: serde_codec::Behaviour<_, GreetRequest, GreetResponse> =
+///     request_response::Behaviour::with_codec(
+///         codec,
+///         [(protocol, ProtocolSupport::Full)],
+///         request_response::Config::default(),
+///     );
+/// # }
+/// ```
+pub type Behaviour<F, Req, Resp> = crate::Behaviour<Codec<F, Req, Resp>>;
+
+/// A serde data format used by [`Codec`] to serialize and deserialize messages.
+pub trait Format {
+    /// Serializes `value` into bytes.
+    fn to_vec<T: Serialize>(value: &T) -> io::Result<Vec<u8>>;
+
+    /// Deserializes a value from `bytes`.
+    fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> io::Result<T>;
+}
+
+/// The CBOR [`Format`], see [`crate::cbor`].
+#[cfg(feature = "cbor")]
+#[derive(Debug, Clone, Copy)]
+pub struct Cbor;
+
+#[cfg(feature = "cbor")]
+impl Format for Cbor {
+    fn to_vec<T: Serialize>(value: &T) -> io::Result<Vec<u8>> {
+        cbor4ii::serde::to_vec(Vec::new(), value).map_err(io::Error::other)
+    }
+
+    fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> io::Result<T> {
+        cbor4ii::serde::from_slice(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// The JSON [`Format`], see [`crate::json`].
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy)]
+pub struct Json;
+
+#[cfg(feature = "json")]
+impl Format for Json {
+    fn to_vec<T: Serialize>(value: &T) -> io::Result<Vec<u8>> {
+        Ok(serde_json::to_vec(value)?)
+    }
+
+    fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> io::Result<T> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+}
+
+/// A compression algorithm for messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    /// Messages are sent uncompressed.
+    #[default]
+    None,
+    /// Zstandard compression at the given level.
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+}
+
+#[cfg(feature = "zstd")]
+impl Compression {
+    fn compress(&self, data: Vec<u8>) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Zstd { level } => zstd::bulk::compress(&data, *level),
+        }
+    }
+
+    /// Decompresses `data`, failing if the output exceeds `max_size` bytes.
+    ///
+    /// The output is streamed rather than preallocated, so the buffer only grows as far as the
+    /// data actually decompresses.
+    fn decompress(&self, data: Vec<u8>, max_size: u64) -> io::Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Zstd { .. } => {
+                use std::io::Read;
+
+                let mut out = Vec::new();
+                zstd::stream::read::Decoder::with_buffer(&data[..])?
+                    .take(max_size.saturating_add(1))
+                    .read_to_end(&mut out)?;
+                if out.len() as u64 > max_size {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "message exceeds size limit",
+                    ));
+                }
+
+                Ok(out)
+            }
+        }
+    }
+}
+
+/// Size limits and compression of a protocol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolConfig {
+    max_request_size: u64,
+    max_response_size: u64,
+    compression: Compression,
+}
+
+impl Default for ProtocolConfig {
+    fn default() -> Self {
+        Self {
+            max_request_size: 1024 * 1024,
+            max_response_size: 10 * 1024 * 1024,
+            compression: Compression::None,
+        }
+    }
+}
+
+impl ProtocolConfig {
+    /// Sets the maximum size in bytes of a request, both serialized and on the wire.
+    /// The default is 1 MiB.
+    ///
+    /// Larger requests are rejected instead of being truncated.
+    pub fn with_max_request_size(mut self, max_request_size: u64) -> Self {
+        self.max_request_size = max_request_size;
+        self
+    }
+
+    /// Sets the maximum size in bytes of a response, both serialized and on the wire.
+    /// The default is 10 MiB.
+    ///
+    /// Larger responses are rejected instead of being truncated.
+    pub fn with_max_response_size(mut self, max_response_size: u64) -> Self {
+        self.max_response_size = max_response_size;
+        self
+    }
+
+    /// Sets the compression of requests and responses. The default is no compression.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+/// A [`Codec`](crate::Codec) serializing requests and responses with the [`Format`] `F`.
+pub struct Codec<F, Req, Resp> {
+    default_config: ProtocolConfig,
+    protocol_configs: HashMap<StreamProtocol, ProtocolConfig>,
+    phantom: PhantomData<fn() -> (F, Req, Resp)>,
+}
+
+impl<F, Req, Resp> Codec<F, Req, Resp> {
+    /// Sets the configuration of protocols without a configuration of their own.
+    pub fn with_default_config(mut self, config: ProtocolConfig) -> Self {
+        self.default_config = config;
+        self
+    }
+
+    /// Sets the configuration of the given protocol.
+    pub fn with_protocol_config(
+        mut self,
+        protocol: StreamProtocol,
+        config: ProtocolConfig,
+    ) -> Self {
+        self.protocol_configs.insert(protocol, config);
+        self
+    }
+
+    fn config(&self, protocol: &StreamProtocol) -> &ProtocolConfig {
+        self.protocol_configs
+            .get(protocol)
+            .unwrap_or(&self.default_config)
+    }
+}
+
+impl<F, Req, Resp> Default for Codec<F, Req, Resp> {
+    fn default() -> Self {
+        Codec {
+            default_config: ProtocolConfig::default(),
+            protocol_configs: HashMap::new(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, Req, Resp> Clone for Codec<F, Req, Resp> {
+    fn clone(&self) -> Self {
+        Codec {
+            default_config: self.default_config.clone(),
+            protocol_configs: self.protocol_configs.clone(),
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<F, Req, Resp> fmt::Debug for Codec<F, Req, Resp> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Codec")
+            .field("default_config", &self.default_config)
+            .field("protocol_configs", &self.protocol_configs)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<F, Req, Resp> crate::Codec for Codec<F, Req, Resp>
+where
+    F: Format,
+    Req: Send + Serialize + DeserializeOwned,
+    Resp: Send + Serialize + DeserializeOwned,
+{
+    type Protocol = StreamProtocol;
+    type Request = Req;
+    type Response = Resp;
+
+    async fn read_request<T>(&mut self, protocol: &Self::Protocol, io: &mut T) -> io::Result<Req>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let config = self.config(protocol);
+        let data = read_limited(io, config.max_request_size).await?;
+        #[cfg(feature = "zstd")]
+        let data = config
+            .compression
+            .decompress(data, config.max_request_size)?;
+
+        F::from_slice(&data)
+    }
+
+    async fn read_response<T>(&mut self, protocol: &Self::Protocol, io: &mut T) -> io::Result<Resp>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let config = self.config(protocol);
+        let data = read_limited(io, config.max_response_size).await?;
+        #[cfg(feature = "zstd")]
+        let data = config
+            .compression
+            .decompress(data, config.max_response_size)?;
+
+        F::from_slice(&data)
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        protocol: &Self::Protocol,
+        io: &mut T,
+        req: Self::Request,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let config = self.config(protocol);
+        let data = encode::<F, _>(&req, config.max_request_size)?;
+        #[cfg(feature = "zstd")]
+        let data = config.compression.compress(data)?;
+
+        io.write_all(&data).await?;
+
+        Ok(())
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        protocol: &Self::Protocol,
+        io: &mut T,
+        resp: Self::Response,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        let config = self.config(protocol);
+        let data = encode::<F, _>(&resp, config.max_response_size)?;
+        #[cfg(feature = "zstd")]
+        let data = config.compression.compress(data)?;
+
+        io.write_all(&data).await?;
+
+        Ok(())
+    }
+}
+
+/// Reads the remaining bytes of `io`, failing if there are more than `max_size`.
+async fn read_limited<T>(io: &mut T, max_size: u64) -> io::Result<Vec<u8>>
+where
+    T: AsyncRead + Unpin + Send,
+{
+    let mut vec = Vec::new();
+
+    io.take(max_size.saturating_add(1))
+        .read_to_end(&mut vec)
+        .await?;
+
+    if vec.len() as u64 > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message exceeds size limit",
+        ));
+    }
+
+    Ok(vec)
+}
+
+/// Serializes `value`, failing if it exceeds `max_size` bytes.
+fn encode<F, T>(value: &T, max_size: u64) -> io::Result<Vec<u8>>
+where
+    F: Format,
+    T: Serialize,
+{
+    let data = F::to_vec(value)?;
+    if data.len() as u64 > max_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "message exceeds size limit",
+        ));
+    }
+
+    Ok(data)
+}
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::{Codec, Compression, Json, ProtocolConfig};
+    use crate::Codec as _;
+    use futures::AsyncWriteExt;
+    use futures_ringbuf::Endpoint;
+    use libp2p_swarm::StreamProtocol;
+    use serde::{Deserialize, Serialize};
+    use std::io;
+
+    #[async_std::test]
+    async fn test_codec() {
+        roundtrip(Compression::None).await;
+    }
+
+    #[cfg(feature = "zstd")]
+    #[async_std::test]
+    async fn test_codec_zstd() {
+        roundtrip(Compression::Zstd { level: 3 }).await;
+    }
+
+    #[async_std::test]
+    async fn rejects_oversized_messages() {
+        let protocol = StreamProtocol::new("/test_serde/1");
+        let request = TestMessage {
+            payload: "a".repeat(64),
+        };
+        let mut codec = Codec::<Json, TestMessage, TestMessage>::default();
+        let mut strict_codec = Codec::<Json, TestMessage, TestMessage>::default()
+            .with_protocol_config(
+                protocol.clone(),
+                ProtocolConfig::default().with_max_request_size(32),
+            );
+
+        let (mut a, _b) = Endpoint::pair(124, 124);
+        let error = strict_codec
+            .write_request(&protocol, &mut a, request.clone())
+            .await
+            .expect_err("Should reject request");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let (mut a, mut b) = Endpoint::pair(124, 124);
+        codec
+            .write_request(&protocol, &mut a, request)
+            .await
+            .expect("Should write request");
+        a.close().await.unwrap();
+
+        let error = strict_codec
+            .read_request(&protocol, &mut b)
+            .await
+            .expect_err("Should reject request");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[async_std::test]
+    async fn rejects_oversized_decompressed_messages() {
+        let protocol = StreamProtocol::new("/test_serde/1");
+        let request = TestMessage {
+            payload: "a".repeat(1024),
+        };
+        let config = ProtocolConfig::default().with_compression(Compression::Zstd { level: 3 });
+        let mut codec = Codec::<Json, TestMessage, TestMessage>::default()
+            .with_protocol_config(protocol.clone(), config.clone());
+        let mut strict_codec = Codec::<Json, TestMessage, TestMessage>::default()
+            .with_protocol_config(protocol.clone(), config.with_max_request_size(512));
+
+        let (mut a, mut b) = Endpoint::pair(1024, 1024);
+        codec
+            .write_request(&protocol, &mut a, request)
+            .await
+            .expect("Should write request");
+        a.close().await.unwrap();
+
+        // The compressed request is within the limit, but not its decompressed form.
+        let error = strict_codec
+            .read_request(&protocol, &mut b)
+            .await
+            .expect_err("Should reject request");
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    async fn roundtrip(compression: Compression) {
+        let expected_request = TestMessage {
+            payload: "test_payload".repeat(16),
+        };
+        let expected_response = TestMessage {
+            payload: "test_payload".to_string(),
+        };
+        let protocol = StreamProtocol::new("/test_serde/1");
+        let mut codec = Codec::<Json, TestMessage, TestMessage>::default()
+            .with_default_config(ProtocolConfig::default().with_compression(compression));
+
+        let (mut a, mut b) = Endpoint::pair(1024, 1024);
+        codec
+            .write_request(&protocol, &mut a, expected_request.clone())
+            .await
+            .expect("Should write request");
+        a.close().await.unwrap();
+
+        let actual_request = codec
+            .read_request(&protocol, &mut b)
+            .await
+            .expect("Should read request");
+        b.close().await.unwrap();
+
+        assert_eq!(actual_request, expected_request);
+
+        let (mut a, mut b) = Endpoint::pair(1024, 1024);
+        codec
+            .write_response(&protocol, &mut a, expected_response.clone())
+            .await
+            .expect("Should write response");
+        a.close().await.unwrap();
+
+        let actual_response = codec
+            .read_response(&protocol, &mut b)
+            .await
+            .expect("Should read response");
+        b.close().await.unwrap();
+
+        assert_eq!(actual_response, expected_response);
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct TestMessage {
+        payload: String,
+    }
+}
@@ -0,0 +1,607 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Request/response protocols with streamed bodies.
+//!
+//! Unlike [`crate::Behaviour`], which encodes requests and responses with a [`Codec`](crate::Codec)
+//! and thus buffers them in memory, the [`Behaviour`] of this module exposes requests and
+//! responses as a [`Body`] implementing [`AsyncRead`]. This allows exchanging large, file-like
+//! payloads without buffering them fully.
+//!
+//! On the wire, the requesting peer writes the request body and closes its write half of the
+//! stream. The responding peer writes the response body and closes the stream.
+//!
+//! Inbound requests are emitted as soon as the stream is negotiated, while the request body is
+//! still being received. Responses are emitted as soon as the request body has been sent, while
+//! the response body is still being received. The [`Config`](crate::Config) request timeout thus
+//! only covers sending the request body respectively waiting for and sending the response body.
+
+mod handler;
+
+use crate::{
+    Config, Connection, Event, InboundFailure, InboundRequestId, Message, OutboundFailure,
+    OutboundRequestId, ProtocolSupport, ResponseChannel, EMPTY_QUEUE_SHRINK_THRESHOLD,
+};
+use futures::io::ReadHalf;
+use futures::prelude::*;
+use handler::{Handler, OutboundMessage};
+use libp2p_core::{ConnectedPoint, Endpoint, Multiaddr};
+use libp2p_identity::PeerId;
+use libp2p_swarm::{
+    behaviour::{AddressChange, ConnectionClosed, DialFailure, FromSwarm},
+    dial_opts::DialOpts,
+    ConnectionDenied, ConnectionHandler, ConnectionId, NetworkBehaviour, NotifyHandler,
+    PeerAddresses, Stream, StreamProtocol, THandler, THandlerInEvent, THandlerOutEvent, ToSwarm,
+};
+use smallvec::SmallVec;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt, io,
+    pin::Pin,
+    sync::{atomic::AtomicU64, Arc},
+    task::{Context, Poll},
+};
+
+/// The body of a streamed request or response.
+///
+/// Bodies to be sent are created from any [`AsyncRead`] via [`Body::new`]. Received bodies read
+/// directly from the underlying stream.
+pub struct Body {
+    inner: BodyInner,
+}
+
+enum BodyInner {
+    Reader(Pin<Box<dyn AsyncRead + Send>>),
+    Stream(ReadHalf<Stream>),
+}
+
+impl Body {
+    /// Creates a body that is read from `reader`.
+    pub fn new<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        Self {
+            inner: BodyInner::Reader(Box::pin(reader)),
+        }
+    }
+
+    /// Creates a body from in-memory bytes.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        Self::new(futures::io::Cursor::new(bytes.into()))
+    }
+
+    fn from_stream(stream: ReadHalf<Stream>) -> Self {
+        Self {
+            inner: BodyInner::Stream(stream),
+        }
+    }
+}
+
+impl AsyncRead for Body {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        match &mut self.get_mut().inner {
+            BodyInner::Reader(reader) => reader.as_mut().poll_read(cx, buf),
+            BodyInner::Stream(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Body").finish_non_exhaustive()
+    }
+}
+
+/// A request/response protocol with streamed request and response bodies.
+pub struct Behaviour {
+    /// The supported inbound protocols.
+    inbound_protocols: SmallVec<[StreamProtocol; 2]>,
+    /// The supported outbound protocols.
+    outbound_protocols: SmallVec<[StreamProtocol; 2]>,
+    /// The next (local) request ID.
+    next_outbound_request_id: OutboundRequestId,
+    /// The next (inbound) request ID.
+    next_inbound_request_id: Arc<AtomicU64>,
+    /// The protocol configuration.
+    config: Config,
+    /// Pending events to return from `poll`.
+    pending_events: VecDeque<ToSwarm<Event<Body, Body>, OutboundMessage>>,
+    /// The currently connected peers, their pending outbound and inbound responses and their known,
+    /// reachable addresses, if any.
+    connected: HashMap<PeerId, SmallVec<[Connection; 2]>>,
+    /// Addresses of peers added via `Swarm::add_peer_address`.
+    addresses: PeerAddresses,
+    /// Requests that have not yet been sent and are waiting for a connection
+    /// to be established.
+    pending_outbound_requests: HashMap<PeerId, SmallVec<[OutboundMessage; 10]>>,
+}
+
+impl Behaviour {
+    /// Creates a new `Behaviour` for the given protocols and configuration.
+    pub fn new<I>(protocols: I, cfg: Config) -> Self
+    where
+        I: IntoIterator<Item = (StreamProtocol, ProtocolSupport)>,
+    {
+        let mut inbound_protocols = SmallVec::new();
+        let mut outbound_protocols = SmallVec::new();
+        for (p, s) in protocols {
+            if s.inbound() {
+                inbound_protocols.push(p.clone());
+            }
+            if s.outbound() {
+                outbound_protocols.push(p.clone());
+            }
+        }
+        Behaviour {
+            inbound_protocols,
+            outbound_protocols,
+            next_outbound_request_id: OutboundRequestId(1),
+            next_inbound_request_id: Arc::new(AtomicU64::new(1)),
+            config: cfg,
+            pending_events: VecDeque::new(),
+            connected: HashMap::new(),
+            pending_outbound_requests: HashMap::new(),
+            addresses: PeerAddresses::default(),
+        }
+    }
+
+    /// Initiates sending a request with the given body.
+    ///
+    /// If the targeted peer is currently not connected, a dialing
+    /// attempt is initiated and the request is sent as soon as a
+    /// connection is established.
+    pub fn send_request(&mut self, peer: &PeerId, body: Body) -> OutboundRequestId {
+        let request_id = self.next_outbound_request_id();
+        let request = OutboundMessage {
+            request_id,
+            body,
+            protocols: self.outbound_protocols.clone(),
+        };
+
+        if let Some(request) = self.try_send_request(peer, request) {
+            self.pending_events.push_back(ToSwarm::Dial {
+                opts: DialOpts::peer_id(*peer).build(),
+            });
+            self.pending_outbound_requests
+                .entry(*peer)
+                .or_default()
+                .push(request);
+        }
+
+        request_id
+    }
+
+    /// Initiates sending a response body to an inbound request.
+    ///
+    /// If the [`ResponseChannel`] is already closed due to a timeout or the
+    /// connection being closed, the body is returned as an `Err` for
+    /// further handling. Once the body has been fully sent on the
+    /// corresponding connection, [`Event::ResponseSent`] is
+    /// emitted. In all other cases [`Event::InboundFailure`]
+    /// will be or has been emitted.
+    pub fn send_response(&mut self, ch: ResponseChannel<Body>, body: Body) -> Result<(), Body> {
+        ch.sender.send(body)
+    }
+
+    /// Checks whether a peer is currently connected.
+    pub fn is_connected(&self, peer: &PeerId) -> bool {
+        self.connected
+            .get(peer)
+            .map(|connections| !connections.is_empty())
+            .unwrap_or(false)
+    }
+
+    /// Checks whether an outbound request to the peer with the provided
+    /// [`PeerId`] initiated by [`Behaviour::send_request`] is still
+    /// pending, i.e. sending the request body or waiting for the response.
+    pub fn is_pending_outbound(&self, peer: &PeerId, request_id: &OutboundRequestId) -> bool {
+        let est_conn = self
+            .connected
+            .get(peer)
+            .map(|cs| {
+                cs.iter()
+                    .any(|c| c.pending_outbound_responses.contains(request_id))
+            })
+            .unwrap_or(false);
+        let pen_conn = self
+            .pending_outbound_requests
+            .get(peer)
+            .map(|rps| rps.iter().any(|rp| rp.request_id == *request_id))
+            .unwrap_or(false);
+
+        est_conn || pen_conn
+    }
+
+    /// Checks whether an inbound request from the peer with the provided
+    /// [`PeerId`] is still pending, i.e. waiting for a response body to be
+    /// sent through [`Behaviour::send_response`].
+    pub fn is_pending_inbound(&self, peer: &PeerId, request_id: &InboundRequestId) -> bool {
+        self.connected
+            .get(peer)
+            .map(|cs| {
+                cs.iter()
+                    .any(|c| c.pending_inbound_responses.contains(request_id))
+            })
+            .unwrap_or(false)
+    }
+
+    fn next_outbound_request_id(&mut self) -> OutboundRequestId {
+        let request_id = self.next_outbound_request_id;
+        self.next_outbound_request_id.0 += 1;
+        request_id
+    }
+
+    /// Tries to send a request by queueing an appropriate event to be
+    /// emitted to the `Swarm`. If the peer is not currently connected,
+    /// the given request is return unchanged.
+    fn try_send_request(
+        &mut self,
+        peer: &PeerId,
+        request: OutboundMessage,
+    ) -> Option<OutboundMessage> {
+        let Some(connections) = self.connected.get_mut(peer) else {
+            return Some(request);
+        };
+        if connections.is_empty() {
+            return Some(request);
+        }
+        let ix = (request.request_id.0 as usize) % connections.len();
+        let conn = &mut connections[ix];
+        conn.pending_outbound_responses.insert(request.request_id);
+        self.pending_events.push_back(ToSwarm::NotifyHandler {
+            peer_id: *peer,
+            handler: NotifyHandler::One(conn.id),
+            event: request,
+        });
+        None
+    }
+
+    fn remove_pending_outbound_response(
+        &mut self,
+        peer: &PeerId,
+        connection: ConnectionId,
+        request: OutboundRequestId,
+    ) -> bool {
+        self.get_connection_mut(peer, connection)
+            .map(|c| c.pending_outbound_responses.remove(&request))
+            .unwrap_or(false)
+    }
+
+    fn remove_pending_inbound_response(
+        &mut self,
+        peer: &PeerId,
+        connection: ConnectionId,
+        request: InboundRequestId,
+    ) -> bool {
+        self.get_connection_mut(peer, connection)
+            .map(|c| c.pending_inbound_responses.remove(&request))
+            .unwrap_or(false)
+    }
+
+    fn get_connection_mut(
+        &mut self,
+        peer: &PeerId,
+        connection: ConnectionId,
+    ) -> Option<&mut Connection> {
+        self.connected
+            .get_mut(peer)
+            .and_then(|connections| connections.iter_mut().find(|c| c.id == connection))
+    }
+
+    fn on_address_change(
+        &mut self,
+        AddressChange {
+            peer_id,
+            connection_id,
+            new,
+            ..
+        }: AddressChange,
+    ) {
+        let new_address = match new {
+            ConnectedPoint::Dialer { address, .. } => Some(address.clone()),
+            ConnectedPoint::Listener { .. } => None,
+        };
+        if let Some(connection) = self.get_connection_mut(&peer_id, connection_id) {
+            connection.remote_address = new_address;
+        }
+    }
+
+    fn on_connection_closed(
+        &mut self,
+        ConnectionClosed {
+            peer_id,
+            connection_id,
+            ..
+        }: ConnectionClosed,
+    ) {
+        let Some(connections) = self.connected.get_mut(&peer_id) else {
+            return;
+        };
+        let Some(connection) = connections
+            .iter()
+            .position(|c| c.id == connection_id)
+            .map(|p| connections.remove(p))
+        else {
+            return;
+        };
+        if connections.is_empty() {
+            self.connected.remove(&peer_id);
+        }
+
+        for request_id in connection.pending_inbound_responses {
+            self.pending_events
+                .push_back(ToSwarm::GenerateEvent(Event::InboundFailure {
+                    peer: peer_id,
+                    request_id,
+                    error: InboundFailure::ConnectionClosed,
+                }));
+        }
+
+        for request_id in connection.pending_outbound_responses {
+            self.pending_events
+                .push_back(ToSwarm::GenerateEvent(Event::OutboundFailure {
+                    peer: peer_id,
+                    request_id,
+                    error: OutboundFailure::ConnectionClosed,
+                }));
+        }
+    }
+
+    fn on_dial_failure(&mut self, DialFailure { peer_id, .. }: DialFailure) {
+        let Some(peer) = peer_id else {
+            return;
+        };
+        if let Some(pending) = self.pending_outbound_requests.remove(&peer) {
+            for request in pending {
+                self.pending_events
+                    .push_back(ToSwarm::GenerateEvent(Event::OutboundFailure {
+                        peer,
+                        request_id: request.request_id,
+                        error: OutboundFailure::DialFailure,
+                    }));
+            }
+        }
+    }
+
+    fn new_handler(
+        &mut self,
+        peer: PeerId,
+        connection_id: ConnectionId,
+        remote_address: Option<Multiaddr>,
+    ) -> Handler {
+        let mut handler = Handler::new(
+            self.inbound_protocols.clone(),
+            self.config.request_timeout,
+            self.next_inbound_request_id.clone(),
+            self.config.max_concurrent_streams,
+        );
+        let mut connection = Connection::new(connection_id, remote_address);
+
+        if let Some(pending_requests) = self.pending_outbound_requests.remove(&peer) {
+            for request in pending_requests {
+                connection
+                    .pending_outbound_responses
+                    .insert(request.request_id);
+                handler.on_behaviour_event(request);
+            }
+        }
+
+        self.connected.entry(peer).or_default().push(connection);
+
+        handler
+    }
+
+    fn report_outbound_failure(
+        &mut self,
+        peer: PeerId,
+        connection: ConnectionId,
+        request_id: OutboundRequestId,
+        error: OutboundFailure,
+    ) {
+        let removed = self.remove_pending_outbound_response(&peer, connection, request_id);
+        debug_assert!(removed, "Expect request_id to be pending upon failure");
+
+        self.pending_events
+            .push_back(ToSwarm::GenerateEvent(Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            }));
+    }
+
+    fn report_inbound_failure(
+        &mut self,
+        peer: PeerId,
+        connection: ConnectionId,
+        request_id: InboundRequestId,
+        error: InboundFailure,
+    ) {
+        if self.remove_pending_inbound_response(&peer, connection, request_id) {
+            self.pending_events
+                .push_back(ToSwarm::GenerateEvent(Event::InboundFailure {
+                    peer,
+                    request_id,
+                    error,
+                }));
+        } else {
+            tracing::debug!(
+                "Inbound failure is reported for an unknown request_id ({request_id}): {error}"
+            );
+        }
+    }
+}
+
+impl NetworkBehaviour for Behaviour {
+    type ConnectionHandler = Handler;
+    type ToSwarm = Event<Body, Body>;
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        _: &Multiaddr,
+        _: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        Ok(self.new_handler(peer, connection_id, None))
+    }
+
+    fn handle_pending_outbound_connection(
+        &mut self,
+        _connection_id: ConnectionId,
+        maybe_peer: Option<PeerId>,
+        _addresses: &[Multiaddr],
+        _effective_role: Endpoint,
+    ) -> Result<Vec<Multiaddr>, ConnectionDenied> {
+        let Some(peer) = maybe_peer else {
+            return Ok(vec![]);
+        };
+
+        let mut addresses = Vec::new();
+        if let Some(connections) = self.connected.get(&peer) {
+            addresses.extend(connections.iter().filter_map(|c| c.remote_address.clone()))
+        }
+        addresses.extend(self.addresses.get(&peer));
+
+        Ok(addresses)
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        remote_address: &Multiaddr,
+        _: Endpoint,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        Ok(self.new_handler(peer, connection_id, Some(remote_address.clone())))
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        self.addresses.on_swarm_event(&event);
+        match event {
+            FromSwarm::ConnectionClosed(connection_closed) => {
+                self.on_connection_closed(connection_closed)
+            }
+            FromSwarm::AddressChange(address_change) => self.on_address_change(address_change),
+            FromSwarm::DialFailure(dial_failure) => self.on_dial_failure(dial_failure),
+            _ => {}
+        }
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        peer: PeerId,
+        connection: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        match event {
+            handler::Event::Response { request_id, body } => {
+                let removed = self.remove_pending_outbound_response(&peer, connection, request_id);
+                debug_assert!(
+                    removed,
+                    "Expect request_id to be pending before receiving response.",
+                );
+
+                let message = Message::Response {
+                    request_id,
+                    response: body,
+                };
+                self.pending_events
+                    .push_back(ToSwarm::GenerateEvent(Event::Message { peer, message }));
+            }
+            handler::Event::Request {
+                request_id,
+                body,
+                sender,
+            } => match self.get_connection_mut(&peer, connection) {
+                Some(connection) => {
+                    let inserted = connection.pending_inbound_responses.insert(request_id);
+                    debug_assert!(inserted, "Expect id of new request to be unknown.");
+
+                    let message = Message::Request {
+                        request_id,
+                        request: body,
+                        channel: ResponseChannel {
+                            sender,
+                            cached_request: None,
+                        },
+                    };
+                    self.pending_events
+                        .push_back(ToSwarm::GenerateEvent(Event::Message { peer, message }));
+                }
+                None => {
+                    tracing::debug!("Connection ({connection}) closed after `Event::Request` ({request_id}) has been emitted.");
+                }
+            },
+            handler::Event::ResponseSent(request_id) => {
+                let removed = self.remove_pending_inbound_response(&peer, connection, request_id);
+                debug_assert!(
+                    removed,
+                    "Expect request_id to be pending before response is sent."
+                );
+
+                self.pending_events
+                    .push_back(ToSwarm::GenerateEvent(Event::ResponseSent {
+                        peer,
+                        request_id,
+                    }));
+            }
+            handler::Event::ResponseOmission(request_id) => self.report_inbound_failure(
+                peer,
+                connection,
+                request_id,
+                InboundFailure::ResponseOmission,
+            ),
+            handler::Event::OutboundTimeout(request_id) => {
+                self.report_outbound_failure(peer, connection, request_id, OutboundFailure::Timeout)
+            }
+            handler::Event::OutboundUnsupportedProtocols(request_id) => self
+                .report_outbound_failure(
+                    peer,
+                    connection,
+                    request_id,
+                    OutboundFailure::UnsupportedProtocols,
+                ),
+            handler::Event::OutboundStreamFailed { request_id, error } => self
+                .report_outbound_failure(peer, connection, request_id, OutboundFailure::Io(error)),
+            handler::Event::InboundTimeout(request_id) => {
+                self.report_inbound_failure(peer, connection, request_id, InboundFailure::Timeout)
+            }
+            handler::Event::InboundStreamFailed { request_id, error } => {
+                self.report_inbound_failure(peer, connection, request_id, InboundFailure::Io(error))
+            }
+        }
+    }
+
+    #[tracing::instrument(level = "trace", name = "NetworkBehaviour::poll", skip(self))]
+    fn poll(&mut self, _: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        if let Some(ev) = self.pending_events.pop_front() {
+            return Poll::Ready(ev);
+        } else if self.pending_events.capacity() > EMPTY_QUEUE_SHRINK_THRESHOLD {
+            self.pending_events.shrink_to_fit();
+        }
+
+        Poll::Pending
+    }
+}
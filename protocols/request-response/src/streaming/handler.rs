@@ -0,0 +1,376 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use super::Body;
+use crate::handler::protocol::Protocol;
+use crate::{InboundRequestId, OutboundRequestId, EMPTY_QUEUE_SHRINK_THRESHOLD};
+
+use futures::channel::{mpsc, oneshot};
+use futures::prelude::*;
+use libp2p_swarm::handler::{
+    ConnectionEvent, DialUpgradeError, FullyNegotiatedInbound, FullyNegotiatedOutbound,
+    ListenUpgradeError,
+};
+use libp2p_swarm::{
+    handler::{ConnectionHandler, ConnectionHandlerEvent, StreamUpgradeError},
+    StreamProtocol, SubstreamProtocol,
+};
+use smallvec::SmallVec;
+use std::{
+    collections::VecDeque,
+    fmt, io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// A connection handler for a streaming request response [`Behaviour`](super::Behaviour).
+pub struct Handler {
+    /// The supported inbound protocols.
+    inbound_protocols: SmallVec<[StreamProtocol; 2]>,
+    /// Queue of events to emit in `poll()`.
+    pending_events: VecDeque<Event>,
+    /// Outbound upgrades waiting to be emitted as an `OutboundSubstreamRequest`.
+    pending_outbound: VecDeque<OutboundMessage>,
+
+    requested_outbound: VecDeque<OutboundMessage>,
+    /// A channel for receiving inbound requests.
+    inbound_receiver: mpsc::Receiver<(InboundRequestId, Body, oneshot::Sender<Body>)>,
+    /// The [`mpsc::Sender`] for the above receiver. Cloned for each inbound request.
+    inbound_sender: mpsc::Sender<(InboundRequestId, Body, oneshot::Sender<Body>)>,
+
+    inbound_request_id: Arc<AtomicU64>,
+
+    worker_streams: futures_bounded::FuturesMap<RequestId, Result<Event, io::Error>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RequestId {
+    Inbound(InboundRequestId),
+    Outbound(OutboundRequestId),
+}
+
+impl Handler {
+    pub(super) fn new(
+        inbound_protocols: SmallVec<[StreamProtocol; 2]>,
+        substream_timeout: Duration,
+        inbound_request_id: Arc<AtomicU64>,
+        max_concurrent_streams: usize,
+    ) -> Self {
+        let (inbound_sender, inbound_receiver) = mpsc::channel(0);
+        Self {
+            inbound_protocols,
+            pending_outbound: VecDeque::new(),
+            requested_outbound: Default::default(),
+            inbound_receiver,
+            inbound_sender,
+            pending_events: VecDeque::new(),
+            inbound_request_id,
+            worker_streams: futures_bounded::FuturesMap::new(
+                substream_timeout,
+                max_concurrent_streams,
+            ),
+        }
+    }
+
+    fn on_fully_negotiated_inbound(
+        &mut self,
+        FullyNegotiatedInbound {
+            protocol: (stream, _),
+            info: (),
+        }: FullyNegotiatedInbound<
+            <Self as ConnectionHandler>::InboundProtocol,
+            <Self as ConnectionHandler>::InboundOpenInfo,
+        >,
+    ) {
+        let request_id = InboundRequestId(self.inbound_request_id.fetch_add(1, Ordering::Relaxed));
+        let mut sender = self.inbound_sender.clone();
+
+        let recv = async move {
+            let (rs_send, rs_recv) = oneshot::channel();
+            let (read, mut write) = stream.split();
+
+            // The request body is read by the user while we wait for the response.
+            sender
+                .send((request_id, Body::from_stream(read), rs_send))
+                .await
+                .expect("`ConnectionHandler` owns both ends of the channel");
+            drop(sender);
+
+            if let Ok(body) = rs_recv.await {
+                futures::io::copy(body, &mut write).await?;
+                write.close().await?;
+                Ok(Event::ResponseSent(request_id))
+            } else {
+                write.close().await?;
+                Ok(Event::ResponseOmission(request_id))
+            }
+        };
+
+        if self
+            .worker_streams
+            .try_push(RequestId::Inbound(request_id), recv.boxed())
+            .is_err()
+        {
+            tracing::warn!("Dropping inbound stream because we are at capacity")
+        }
+    }
+
+    fn on_fully_negotiated_outbound(
+        &mut self,
+        FullyNegotiatedOutbound {
+            protocol: (stream, _),
+            info: (),
+        }: FullyNegotiatedOutbound<
+            <Self as ConnectionHandler>::OutboundProtocol,
+            <Self as ConnectionHandler>::OutboundOpenInfo,
+        >,
+    ) {
+        let message = self
+            .requested_outbound
+            .pop_front()
+            .expect("negotiated a stream without a pending message");
+        let request_id = message.request_id;
+
+        let send = async move {
+            let (read, mut write) = stream.split();
+            futures::io::copy(message.body, &mut write).await?;
+            write.close().await?;
+
+            // The response body is read by the user.
+            Ok(Event::Response {
+                request_id,
+                body: Body::from_stream(read),
+            })
+        };
+
+        if self
+            .worker_streams
+            .try_push(RequestId::Outbound(request_id), send.boxed())
+            .is_err()
+        {
+            tracing::warn!("Dropping outbound stream because we are at capacity")
+        }
+    }
+
+    fn on_dial_upgrade_error(
+        &mut self,
+        DialUpgradeError { error, info: () }: DialUpgradeError<
+            <Self as ConnectionHandler>::OutboundOpenInfo,
+            <Self as ConnectionHandler>::OutboundProtocol,
+        >,
+    ) {
+        let message = self
+            .requested_outbound
+            .pop_front()
+            .expect("negotiated a stream without a pending message");
+
+        match error {
+            StreamUpgradeError::Timeout => {
+                self.pending_events
+                    .push_back(Event::OutboundTimeout(message.request_id));
+            }
+            StreamUpgradeError::NegotiationFailed => {
+                self.pending_events
+                    .push_back(Event::OutboundUnsupportedProtocols(message.request_id));
+            }
+            StreamUpgradeError::Apply(e) => void::unreachable(e),
+            StreamUpgradeError::Io(e) => {
+                tracing::debug!(
+                    "outbound stream for request {} failed: {e}, retrying",
+                    message.request_id
+                );
+                self.requested_outbound.push_back(message);
+            }
+        }
+    }
+
+    fn on_listen_upgrade_error(
+        &mut self,
+        ListenUpgradeError { error, .. }: ListenUpgradeError<
+            <Self as ConnectionHandler>::InboundOpenInfo,
+            <Self as ConnectionHandler>::InboundProtocol,
+        >,
+    ) {
+        void::unreachable(error)
+    }
+}
+
+/// The events emitted by the [`Handler`].
+#[derive(Debug)]
+pub enum Event {
+    /// A request has been received. The body is still being received.
+    Request {
+        request_id: InboundRequestId,
+        body: Body,
+        sender: oneshot::Sender<Body>,
+    },
+    /// The request body has been sent. The response body is still being received.
+    Response {
+        request_id: OutboundRequestId,
+        body: Body,
+    },
+    /// A response body to an inbound request has been sent.
+    ResponseSent(InboundRequestId),
+    /// A response to an inbound request was omitted as a result
+    /// of dropping the response `sender` of an inbound `Request`.
+    ResponseOmission(InboundRequestId),
+    /// An outbound request timed out while sending the request body.
+    OutboundTimeout(OutboundRequestId),
+    /// An outbound request failed to negotiate a mutually supported protocol.
+    OutboundUnsupportedProtocols(OutboundRequestId),
+    OutboundStreamFailed {
+        request_id: OutboundRequestId,
+        error: io::Error,
+    },
+    /// An inbound request timed out while waiting for or sending the response body.
+    InboundTimeout(InboundRequestId),
+    InboundStreamFailed {
+        request_id: InboundRequestId,
+        error: io::Error,
+    },
+}
+
+pub struct OutboundMessage {
+    pub(super) request_id: OutboundRequestId,
+    pub(super) body: Body,
+    pub(super) protocols: SmallVec<[StreamProtocol; 2]>,
+}
+
+impl fmt::Debug for OutboundMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OutboundMessage").finish_non_exhaustive()
+    }
+}
+
+impl ConnectionHandler for Handler {
+    type FromBehaviour = OutboundMessage;
+    type ToBehaviour = Event;
+    type InboundProtocol = Protocol<StreamProtocol>;
+    type OutboundProtocol = Protocol<StreamProtocol>;
+    type OutboundOpenInfo = ();
+    type InboundOpenInfo = ();
+
+    fn listen_protocol(&self) -> SubstreamProtocol<Self::InboundProtocol, Self::InboundOpenInfo> {
+        SubstreamProtocol::new(
+            Protocol {
+                protocols: self.inbound_protocols.clone(),
+            },
+            (),
+        )
+    }
+
+    fn on_behaviour_event(&mut self, request: Self::FromBehaviour) {
+        self.pending_outbound.push_back(request);
+    }
+
+    #[tracing::instrument(level = "trace", name = "ConnectionHandler::poll", skip(self, cx))]
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<ConnectionHandlerEvent<Protocol<StreamProtocol>, (), Self::ToBehaviour>> {
+        match self.worker_streams.poll_unpin(cx) {
+            Poll::Ready((_, Ok(Ok(event)))) => {
+                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event));
+            }
+            Poll::Ready((RequestId::Inbound(request_id), Ok(Err(error)))) => {
+                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                    Event::InboundStreamFailed { request_id, error },
+                ));
+            }
+            Poll::Ready((RequestId::Outbound(request_id), Ok(Err(error)))) => {
+                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                    Event::OutboundStreamFailed { request_id, error },
+                ));
+            }
+            Poll::Ready((RequestId::Inbound(id), Err(futures_bounded::Timeout { .. }))) => {
+                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                    Event::InboundTimeout(id),
+                ));
+            }
+            Poll::Ready((RequestId::Outbound(id), Err(futures_bounded::Timeout { .. }))) => {
+                return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(
+                    Event::OutboundTimeout(id),
+                ));
+            }
+            Poll::Pending => {}
+        }
+
+        if let Some(event) = self.pending_events.pop_front() {
+            return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event));
+        } else if self.pending_events.capacity() > EMPTY_QUEUE_SHRINK_THRESHOLD {
+            self.pending_events.shrink_to_fit();
+        }
+
+        if let Poll::Ready(Some((request_id, body, sender))) =
+            self.inbound_receiver.poll_next_unpin(cx)
+        {
+            return Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(Event::Request {
+                request_id,
+                body,
+                sender,
+            }));
+        }
+
+        if let Some(request) = self.pending_outbound.pop_front() {
+            let protocols = request.protocols.clone();
+            self.requested_outbound.push_back(request);
+
+            return Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest {
+                protocol: SubstreamProtocol::new(Protocol { protocols }, ()),
+            });
+        }
+
+        if self.pending_outbound.capacity() > EMPTY_QUEUE_SHRINK_THRESHOLD {
+            self.pending_outbound.shrink_to_fit();
+        }
+
+        Poll::Pending
+    }
+
+    fn on_connection_event(
+        &mut self,
+        event: ConnectionEvent<
+            Self::InboundProtocol,
+            Self::OutboundProtocol,
+            Self::InboundOpenInfo,
+            Self::OutboundOpenInfo,
+        >,
+    ) {
+        match event {
+            ConnectionEvent::FullyNegotiatedInbound(fully_negotiated_inbound) => {
+                self.on_fully_negotiated_inbound(fully_negotiated_inbound)
+            }
+            ConnectionEvent::FullyNegotiatedOutbound(fully_negotiated_outbound) => {
+                self.on_fully_negotiated_outbound(fully_negotiated_outbound)
+            }
+            ConnectionEvent::DialUpgradeError(dial_upgrade_error) => {
+                self.on_dial_upgrade_error(dial_upgrade_error)
+            }
+            ConnectionEvent::ListenUpgradeError(listen_upgrade_error) => {
+                self.on_listen_upgrade_error(listen_upgrade_error)
+            }
+            _ => {}
+        }
+    }
+}
@@ -2,15 +2,19 @@ use anyhow::{bail, Result};
 use async_std::task::sleep;
 use async_trait::async_trait;
 use futures::prelude::*;
+use libp2p_core::{Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
 use libp2p_request_response as request_response;
 use libp2p_request_response::ProtocolSupport;
-use libp2p_swarm::{StreamProtocol, Swarm};
+use libp2p_swarm::{ConnectionId, NetworkBehaviour, NotifyHandler, StreamProtocol, Swarm, ToSwarm};
 use libp2p_swarm_test::SwarmExt;
 use request_response::{
-    Codec, InboundFailure, InboundRequestId, OutboundFailure, OutboundRequestId, ResponseChannel,
+    Codec, ConnectionPreference, InboundFailure, InboundRequestId, OutboundFailure,
+    OutboundRequestId, RateLimit, RequestOptions, ResponseChannel,
 };
+use std::num::NonZeroU32;
 use std::pin::pin;
+use std::task::{Context, Poll};
 use std::time::Duration;
 use std::{io, iter};
 use tracing_subscriber::EnvFilter;
@@ -161,6 +165,211 @@ async fn report_outbound_timeout_on_read_response() {
     futures::future::select(server_task, client_task).await;
 }
 
+#[async_std::test]
+async fn report_outbound_timeout_with_request_options() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let (peer1_id, mut swarm1) = new_swarm_with_timeout(Duration::from_millis(200));
+    // The per-request timeout overrides the default one of `swarm2`.
+    let (peer2_id, mut swarm2) = new_swarm_with_timeout(Duration::from_secs(10));
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+
+    let server_task = async move {
+        let (peer, req_id, action, resp_channel) = wait_request(&mut swarm1).await.unwrap();
+        assert_eq!(peer, peer2_id);
+        assert_eq!(action, Action::TimeoutOnReadResponse);
+        swarm1
+            .behaviour_mut()
+            .send_response(resp_channel, Action::TimeoutOnReadResponse)
+            .unwrap();
+
+        let (peer, req_id_done) = wait_response_sent(&mut swarm1).await.unwrap();
+        assert_eq!(peer, peer2_id);
+        assert_eq!(req_id_done, req_id);
+
+        // Keep the connection alive, otherwise swarm2 may receive `ConnectionClosed` instead
+        wait_no_events(&mut swarm1).await;
+    };
+
+    // Expects OutboundFailure::Timeout
+    let client_task = async move {
+        let req_id = swarm2.behaviour_mut().send_request_with(
+            &peer1_id,
+            Action::TimeoutOnReadResponse,
+            RequestOptions {
+                timeout: Some(Duration::from_millis(100)),
+                ..Default::default()
+            },
+        );
+
+        let (peer, req_id_done, error) = wait_outbound_failure(&mut swarm2).await.unwrap();
+        assert_eq!(peer, peer1_id);
+        assert_eq!(req_id_done, req_id);
+        assert!(matches!(error, OutboundFailure::Timeout));
+    };
+
+    let server_task = pin!(server_task);
+    let client_task = pin!(client_task);
+    futures::future::select(server_task, client_task).await;
+}
+
+#[async_std::test]
+async fn retry_outbound_request_on_another_connection() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let (peer1_id, mut swarm1) = new_swarm();
+    let (peer2_id, mut swarm2) = new_swarm();
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+    swarm2.connect(&mut swarm1).await;
+
+    // Fails the first attempt of the request and answers the retried one.
+    let server_task = async move {
+        let (peer, _, action, resp_channel) = wait_request(&mut swarm1).await.unwrap();
+        assert_eq!(peer, peer2_id);
+        assert_eq!(action, Action::FailOnWriteResponse);
+        swarm1
+            .behaviour_mut()
+            .send_response(resp_channel, Action::FailOnReadResponse)
+            .unwrap();
+        wait_response_sent(&mut swarm1).await.unwrap();
+
+        let (peer, _, action, resp_channel) = wait_request(&mut swarm1).await.unwrap();
+        assert_eq!(peer, peer2_id);
+        assert_eq!(action, Action::FailOnWriteResponse);
+        swarm1
+            .behaviour_mut()
+            .send_response(resp_channel, Action::FailOnReadRequest)
+            .unwrap();
+        wait_response_sent(&mut swarm1).await.unwrap();
+
+        wait_no_events(&mut swarm1).await;
+    };
+
+    // Expects the response to the retried request.
+    let client_task = async move {
+        let req_id = swarm2.behaviour_mut().send_request_with(
+            &peer1_id,
+            Action::FailOnWriteResponse,
+            RequestOptions {
+                retries: 1,
+                ..Default::default()
+            },
+        );
+
+        let (peer, req_id_done, response) = wait_response(&mut swarm2).await.unwrap();
+        assert_eq!(peer, peer1_id);
+        assert_eq!(req_id_done, req_id);
+        assert_eq!(response, Action::FailOnReadRequest);
+    };
+
+    let server_task = pin!(server_task);
+    let client_task = pin!(client_task);
+    futures::future::select(server_task, client_task).await;
+}
+
+#[async_std::test]
+async fn report_outbound_failure_without_another_connection_to_retry_on() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let (peer1_id, mut swarm1) = new_swarm();
+    let (peer2_id, mut swarm2) = new_swarm();
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+
+    let server_task = async move {
+        let (peer, _, _, resp_channel) = wait_request(&mut swarm1).await.unwrap();
+        assert_eq!(peer, peer2_id);
+        swarm1
+            .behaviour_mut()
+            .send_response(resp_channel, Action::FailOnReadResponse)
+            .unwrap();
+        wait_response_sent(&mut swarm1).await.unwrap();
+
+        // The request is not retried on the same connection.
+        wait_no_events(&mut swarm1).await;
+    };
+
+    let client_task = async move {
+        let req_id = swarm2.behaviour_mut().send_request_with(
+            &peer1_id,
+            Action::FailOnWriteResponse,
+            RequestOptions {
+                retries: 1,
+                ..Default::default()
+            },
+        );
+
+        let (peer, req_id_done, error) = wait_outbound_failure(&mut swarm2).await.unwrap();
+        assert_eq!(peer, peer1_id);
+        assert_eq!(req_id_done, req_id);
+        assert!(matches!(error, OutboundFailure::Io(_)));
+    };
+
+    let server_task = pin!(server_task);
+    let client_task = pin!(client_task);
+    futures::future::select(server_task, client_task).await;
+}
+
+#[test]
+fn send_request_on_preferred_connection() {
+    let protocols = iter::once((StreamProtocol::new("/test/1"), ProtocolSupport::Full));
+    let mut behaviour = request_response::Behaviour::<TestCodec>::new(
+        protocols,
+        request_response::Config::default(),
+    );
+    let peer = PeerId::random();
+    let oldest = ConnectionId::new_unchecked(1);
+    let newest = ConnectionId::new_unchecked(2);
+    for connection_id in [oldest, newest] {
+        let _ = behaviour
+            .handle_established_outbound_connection(
+                connection_id,
+                peer,
+                &Multiaddr::empty(),
+                Endpoint::Dialer,
+            )
+            .unwrap();
+    }
+
+    let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+    for (preference, expected) in [
+        (ConnectionPreference::Oldest, oldest),
+        (ConnectionPreference::Newest, newest),
+        (ConnectionPreference::Oldest, oldest),
+    ] {
+        behaviour.send_request_with(
+            &peer,
+            Action::FailOnWriteResponse,
+            RequestOptions {
+                connection_preference: preference,
+                ..Default::default()
+            },
+        );
+        match behaviour.poll(&mut cx) {
+            Poll::Ready(ToSwarm::NotifyHandler {
+                peer_id,
+                handler: NotifyHandler::One(connection_id),
+                ..
+            }) => {
+                assert_eq!(peer_id, peer);
+                assert_eq!(connection_id, expected, "{preference:?}");
+            }
+            e => panic!("Unexpected poll result: {e:?}"),
+        }
+    }
+}
+
 #[async_std::test]
 async fn report_inbound_failure_on_read_request() {
     let _ = tracing_subscriber::fmt()
@@ -321,6 +530,161 @@ async fn report_inbound_timeout_on_write_response() {
     futures::future::select(server_task, client_task).await;
 }
 
+#[async_std::test]
+async fn report_inbound_failure_on_rate_limit() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let protocols = iter::once((StreamProtocol::new("/test/1"), ProtocolSupport::Full));
+    let cfg = request_response::Config::default()
+        .with_request_timeout(Duration::from_secs(10))
+        .with_inbound_rate_limit_per_peer(RateLimit::new(
+            NonZeroU32::new(1).unwrap(),
+            Duration::from_secs(60),
+        ));
+    let mut swarm1 =
+        Swarm::new_ephemeral(|_| request_response::Behaviour::<TestCodec>::new(protocols, cfg));
+    let peer1_id = *swarm1.local_peer_id();
+    let (peer2_id, mut swarm2) = new_swarm_with_timeout(Duration::from_secs(10));
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+
+    // Expects one request to be passed on and the other one to be rejected.
+    let server_task = async move {
+        let mut channels = Vec::new();
+        let mut rate_limited = 0;
+        while channels.len() + rate_limited < 2 {
+            match swarm1.select_next_some().await.try_into_behaviour_event() {
+                Ok(request_response::Event::Message {
+                    peer,
+                    message: request_response::Message::Request { channel, .. },
+                }) => {
+                    assert_eq!(peer, peer2_id);
+                    channels.push(channel);
+                }
+                Ok(request_response::Event::InboundFailure {
+                    peer,
+                    error: InboundFailure::RateLimited,
+                    ..
+                }) => {
+                    assert_eq!(peer, peer2_id);
+                    rate_limited += 1;
+                }
+                Ok(ev) => panic!("Unexpected event: {ev:?}"),
+                Err(..) => {}
+            }
+        }
+        assert_eq!(channels.len(), 1);
+
+        // Keep the connection alive, otherwise swarm2 may receive `ConnectionClosed` instead.
+        wait_no_events(&mut swarm1).await;
+    };
+
+    // Expects the stream of the rejected request to be closed without a response.
+    let client_task = async move {
+        swarm2
+            .behaviour_mut()
+            .send_request(&peer1_id, Action::FailOnWriteResponse);
+        swarm2
+            .behaviour_mut()
+            .send_request(&peer1_id, Action::FailOnWriteResponse);
+
+        let (peer, _, error) = wait_outbound_failure(&mut swarm2).await.unwrap();
+        assert_eq!(peer, peer1_id);
+
+        match error {
+            OutboundFailure::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof => {}
+            e => panic!("Unexpected error: {e:?}"),
+        };
+    };
+
+    let server_task = pin!(server_task);
+    let client_task = pin!(client_task);
+    futures::future::select(server_task, client_task).await;
+}
+
+#[async_std::test]
+async fn queued_requests_are_sent_by_priority() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let protocols = iter::once((StreamProtocol::new("/test/1"), ProtocolSupport::Full));
+    let cfg = request_response::Config::default()
+        .with_request_timeout(Duration::from_secs(10))
+        .with_max_concurrent_streams(1);
+    let mut swarm2 =
+        Swarm::new_ephemeral(|_| request_response::Behaviour::<TestCodec>::new(protocols, cfg));
+    let peer2_id = *swarm2.local_peer_id();
+    let (peer1_id, mut swarm1) = new_swarm_with_timeout(Duration::from_secs(10));
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+
+    // The first request occupies the only stream, the others stay queued until it completes.
+    for (action, priority) in [
+        (Action::TimeoutOnReadResponse, 2),
+        (Action::FailOnReadResponse, 0),
+        (Action::FailOnWriteResponse, 1),
+    ] {
+        swarm2.behaviour_mut().send_request_with(
+            &peer1_id,
+            action,
+            RequestOptions {
+                priority,
+                ..Default::default()
+            },
+        );
+    }
+    assert_eq!(swarm2.behaviour().queued_requests(&peer1_id), 3);
+
+    // Expects the queued requests in the order of their priority.
+    let server_task = async move {
+        let mut actions = Vec::new();
+        while actions.len() < 3 {
+            match swarm1.select_next_some().await.try_into_behaviour_event() {
+                Ok(request_response::Event::Message {
+                    peer,
+                    message:
+                        request_response::Message::Request {
+                            request, channel, ..
+                        },
+                }) => {
+                    assert_eq!(peer, peer2_id);
+                    actions.push(request);
+                    swarm1
+                        .behaviour_mut()
+                        .send_response(channel, Action::FailOnWriteRequest)
+                        .unwrap();
+                }
+                Ok(request_response::Event::ResponseSent { .. }) => {}
+                Ok(ev) => panic!("Unexpected event: {ev:?}"),
+                Err(..) => {}
+            }
+        }
+        assert_eq!(
+            actions,
+            [
+                Action::TimeoutOnReadResponse,
+                Action::FailOnWriteResponse,
+                Action::FailOnReadResponse
+            ]
+        );
+    };
+
+    let client_task = async move {
+        loop {
+            swarm2.select_next_some().await;
+        }
+    };
+
+    let server_task = pin!(server_task);
+    let client_task = pin!(client_task);
+    futures::future::select(server_task, client_task).await;
+}
+
 #[derive(Clone, Default)]
 struct TestCodec;
 
@@ -531,6 +895,27 @@ async fn wait_response_sent(
     }
 }
 
+async fn wait_response(
+    swarm: &mut Swarm<request_response::Behaviour<TestCodec>>,
+) -> Result<(PeerId, OutboundRequestId, Action)> {
+    loop {
+        match swarm.select_next_some().await.try_into_behaviour_event() {
+            Ok(request_response::Event::Message {
+                peer,
+                message:
+                    request_response::Message::Response {
+                        request_id,
+                        response,
+                    },
+            }) => {
+                return Ok((peer, request_id, response));
+            }
+            Ok(ev) => bail!("Unexpected event: {ev:?}"),
+            Err(..) => {}
+        }
+    }
+}
+
 async fn wait_inbound_failure(
     swarm: &mut Swarm<request_response::Behaviour<TestCodec>>,
 ) -> Result<(PeerId, InboundRequestId, InboundFailure)> {
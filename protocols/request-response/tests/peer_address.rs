@@ -1,6 +1,6 @@
 use libp2p_core::ConnectedPoint;
 use libp2p_request_response as request_response;
-use libp2p_request_response::ProtocolSupport;
+use libp2p_request_response::{OutboundFailure, ProtocolSupport};
 use libp2p_swarm::{StreamProtocol, Swarm, SwarmEvent};
 use libp2p_swarm_test::SwarmExt;
 use serde::{Deserialize, Serialize};
@@ -55,6 +55,117 @@ async fn dial_succeeds_after_adding_peers_address() {
     assert_eq!(expected_address, connected_address.unwrap());
 }
 
+#[async_std::test]
+#[cfg(feature = "cbor")]
+async fn send_request_dials_peers_address() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let protocols = iter::once((StreamProtocol::new("/ping/1"), ProtocolSupport::Full));
+    let config = request_response::Config::default();
+
+    let mut swarm = Swarm::new_ephemeral(|_| {
+        request_response::cbor::Behaviour::<Ping, Pong>::new(protocols.clone(), config.clone())
+    });
+
+    let mut swarm2 = Swarm::new_ephemeral(|_| {
+        request_response::cbor::Behaviour::<Ping, Pong>::new(protocols.clone(), config.clone())
+    });
+
+    let peer_id2 = *swarm2.local_peer_id();
+
+    let (listen_addr, _) = swarm2.listen().with_memory_addr_external().await;
+
+    swarm.add_peer_address(peer_id2, listen_addr);
+
+    let request_id = swarm
+        .behaviour_mut()
+        .send_request(&peer_id2, Ping(b"ping".to_vec()));
+
+    async_std::task::spawn(async move {
+        loop {
+            if let Ok(request_response::Event::Message {
+                message: request_response::Message::Request { channel, .. },
+                ..
+            }) = swarm2.next_swarm_event().await.try_into_behaviour_event()
+            {
+                swarm2
+                    .behaviour_mut()
+                    .send_response(channel, Pong(b"pong".to_vec()))
+                    .unwrap();
+            }
+        }
+    });
+
+    let (peer, response_id, response) = swarm
+        .wait(|event| match event.try_into_behaviour_event() {
+            Ok(request_response::Event::Message {
+                peer,
+                message:
+                    request_response::Message::Response {
+                        request_id,
+                        response,
+                    },
+            }) => Some((peer, request_id, response)),
+            _ => None,
+        })
+        .await;
+
+    assert_eq!(peer, peer_id2);
+    assert_eq!(response_id, request_id);
+    assert_eq!(response, Pong(b"pong".to_vec()));
+}
+
+#[async_std::test]
+#[cfg(feature = "cbor")]
+async fn send_request_fails_without_peer_address_resolution() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let protocols = iter::once((StreamProtocol::new("/ping/1"), ProtocolSupport::Full));
+    let config = request_response::Config::default();
+
+    let mut swarm = Swarm::new_ephemeral(|_| {
+        request_response::cbor::Behaviour::<Ping, Pong>::new(
+            protocols.clone(),
+            config.clone().with_peer_address_resolution(false),
+        )
+    });
+
+    let mut swarm2 = Swarm::new_ephemeral(|_| {
+        request_response::cbor::Behaviour::<Ping, Pong>::new(protocols.clone(), config.clone())
+    });
+
+    let peer_id2 = *swarm2.local_peer_id();
+
+    let (listen_addr, _) = swarm2.listen().with_memory_addr_external().await;
+
+    swarm.add_peer_address(peer_id2, listen_addr);
+
+    let request_id = swarm
+        .behaviour_mut()
+        .send_request(&peer_id2, Ping(b"ping".to_vec()));
+
+    async_std::task::spawn(swarm2.loop_on_next());
+
+    let (peer, failed_id, error) = swarm
+        .wait(|event| match event.try_into_behaviour_event() {
+            Ok(request_response::Event::OutboundFailure {
+                peer,
+                request_id,
+                error,
+            }) => Some((peer, request_id, error)),
+            _ => None,
+        })
+        .await;
+
+    assert_eq!(peer, peer_id2);
+    assert_eq!(failed_id, request_id);
+    assert!(matches!(error, OutboundFailure::DialFailure));
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Ping(Vec<u8>);
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -28,7 +28,7 @@ use libp2p_swarm::{StreamProtocol, Swarm, SwarmEvent};
 use libp2p_swarm_test::SwarmExt;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::{io, iter};
+use std::{io, iter, num::NonZeroUsize, time::Duration};
 use tracing_subscriber::EnvFilter;
 
 #[async_std::test]
@@ -176,6 +176,82 @@ async fn ping_protocol() {
     peer2.await;
 }
 
+#[async_std::test]
+#[cfg(feature = "cbor")]
+async fn answers_repeated_requests_from_response_cache() {
+    let ping = Ping("ping".to_string().into_bytes());
+    let pong = Pong("pong".to_string().into_bytes());
+
+    let protocols = iter::once((StreamProtocol::new("/ping/1"), ProtocolSupport::Full));
+    let cfg = request_response::Config::default();
+
+    let mut swarm1 = Swarm::new_ephemeral(|_| {
+        let mut behaviour =
+            request_response::cbor::Behaviour::<Ping, Pong>::new(protocols.clone(), cfg.clone());
+        behaviour.enable_response_cache(request_response::ResponseCacheConfig::new(
+            NonZeroUsize::new(8).unwrap(),
+            Duration::from_secs(60),
+        ));
+        behaviour
+    });
+    let peer1_id = *swarm1.local_peer_id();
+    let mut swarm2 = Swarm::new_ephemeral(|_| {
+        request_response::cbor::Behaviour::<Ping, Pong>::new(protocols, cfg)
+    });
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+
+    let expected_pong = pong.clone();
+
+    // Expects only the first request to be passed on.
+    let peer1 = async move {
+        let mut num_requests = 0;
+        loop {
+            match swarm1.next_swarm_event().await.try_into_behaviour_event() {
+                Ok(request_response::Event::Message {
+                    message: request_response::Message::Request { channel, .. },
+                    ..
+                }) => {
+                    num_requests += 1;
+                    assert_eq!(num_requests, 1);
+                    swarm1
+                        .behaviour_mut()
+                        .send_response(channel, pong.clone())
+                        .unwrap();
+                }
+                Ok(request_response::Event::ResponseSent { .. }) => {}
+                Ok(e) => panic!("Peer1: Unexpected event: {e:?}"),
+                Err(..) => {}
+            }
+        }
+    };
+
+    let peer2 = async {
+        for _ in 0..3 {
+            swarm2.behaviour_mut().send_request(&peer1_id, ping.clone());
+
+            match swarm2
+                .next_swarm_event()
+                .await
+                .try_into_behaviour_event()
+                .unwrap()
+            {
+                request_response::Event::Message {
+                    message: request_response::Message::Response { response, .. },
+                    ..
+                } => {
+                    assert_eq!(&response, &expected_pong);
+                }
+                e => panic!("Peer2: Unexpected event: {e:?}"),
+            }
+        }
+    };
+
+    async_std::task::spawn(Box::pin(peer1));
+    peer2.await;
+}
+
 #[async_std::test]
 #[cfg(feature = "cbor")]
 async fn emits_inbound_connection_closed_failure() {
@@ -298,7 +374,7 @@ async fn emits_inbound_connection_closed_if_channel_is_dropped() {
 }
 
 // Simple Ping-Pong Protocol
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 struct Ping(Vec<u8>);
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 struct Pong(Vec<u8>);
@@ -0,0 +1,114 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Integration tests for the streaming `Behaviour`.
+
+use futures::prelude::*;
+use libp2p_request_response as request_response;
+use libp2p_request_response::streaming::{self, Body};
+use libp2p_request_response::ProtocolSupport;
+use libp2p_swarm::{StreamProtocol, Swarm};
+use libp2p_swarm_test::SwarmExt;
+use std::iter;
+
+#[async_std::test]
+async fn streams_request_and_response_bodies() {
+    let protocols = iter::once((StreamProtocol::new("/stream/1"), ProtocolSupport::Full));
+    let cfg = request_response::Config::default();
+
+    let mut swarm1 =
+        Swarm::new_ephemeral(|_| streaming::Behaviour::new(protocols.clone(), cfg.clone()));
+    let peer1_id = *swarm1.local_peer_id();
+    let mut swarm2 = Swarm::new_ephemeral(|_| streaming::Behaviour::new(protocols, cfg));
+
+    swarm1.listen().with_memory_addr_external().await;
+    swarm2.connect(&mut swarm1).await;
+
+    let request_data: Vec<u8> = (0..1024 * 1024).map(|i| i as u8).collect();
+    let response_data: Vec<u8> = request_data.iter().rev().copied().collect();
+    let expected_request = request_data.clone();
+    let expected_response = response_data.clone();
+
+    let peer1 = async move {
+        loop {
+            match swarm1.next_swarm_event().await.try_into_behaviour_event() {
+                Ok(request_response::Event::Message {
+                    message:
+                        request_response::Message::Request {
+                            request: mut body,
+                            channel,
+                            ..
+                        },
+                    ..
+                }) => {
+                    // Keep driving the swarm while reading the body, as it arrives in chunks.
+                    let mut received = Vec::new();
+                    let mut read = body.read_to_end(&mut received).boxed();
+                    loop {
+                        match future::select(read, swarm1.next_swarm_event().boxed()).await {
+                            future::Either::Left((result, _)) => {
+                                result.unwrap();
+                                break;
+                            }
+                            future::Either::Right((_, r)) => read = r,
+                        }
+                    }
+                    assert_eq!(received, expected_request);
+                    swarm1
+                        .behaviour_mut()
+                        .send_response(channel, Body::from_bytes(response_data.clone()))
+                        .unwrap();
+                }
+                Ok(request_response::Event::ResponseSent { .. }) => {}
+                Ok(e) => panic!("Peer1: Unexpected event: {e:?}"),
+                Err(..) => {}
+            }
+        }
+    };
+    async_std::task::spawn(Box::pin(peer1));
+
+    let request_id = swarm2
+        .behaviour_mut()
+        .send_request(&peer1_id, Body::from_bytes(request_data));
+
+    let mut body = loop {
+        match swarm2.next_swarm_event().await.try_into_behaviour_event() {
+            Ok(request_response::Event::Message {
+                message:
+                    request_response::Message::Response {
+                        request_id: id,
+                        response,
+                    },
+                ..
+            }) => {
+                assert_eq!(id, request_id);
+                break response;
+            }
+            Ok(e) => panic!("Peer2: Unexpected event: {e:?}"),
+            Err(..) => {}
+        }
+    };
+
+    async_std::task::spawn(swarm2.loop_on_next());
+
+    let mut received = Vec::new();
+    body.read_to_end(&mut received).await.unwrap();
+    assert_eq!(received, expected_response);
+}
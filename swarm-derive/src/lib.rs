@@ -224,6 +224,40 @@ fn build_struct(ast: &DeriveInput, data_struct: &DataStruct) -> syn::Result<Toke
             })
     };
 
+    // Build the list of statements to put in the body of `on_shutdown()`.
+    let on_shutdown_stmts = {
+        data_struct
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(field_n, field)| match field.ident {
+                Some(ref i) => quote! {
+                    self.#i.on_shutdown();
+                },
+                None => quote! {
+                    self.#field_n.on_shutdown();
+                },
+            })
+    };
+
+    // Build the list of expressions to put in the body of `poll_ready()`.
+    //
+    // Every child is polled, so that all of them can register the current task.
+    let poll_ready_exprs = {
+        data_struct
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(field_n, field)| match field.ident {
+                Some(ref i) => quote! {
+                    self.#i.poll_ready(cx).is_pending()
+                },
+                None => quote! {
+                    self.#field_n.poll_ready(cx).is_pending()
+                },
+            })
+    };
+
     // Build the list of variants to put in the body of `on_connection_handler_event()`.
     //
     // The event type is a construction of nested `#either_ident`s of the events of the children.
@@ -395,8 +429,24 @@ fn build_struct(ast: &DeriveInput, data_struct: &DataStruct) -> syn::Result<Toke
 
             let map_in_event = quote! { |event| #wrapped_event };
 
+            // With the `poll-metrics` feature, record the time spent polling the field.
+            let poll_field = if cfg!(feature = "poll-metrics") {
+                let behaviour_name = name.to_string();
+                let field_name = field.to_string();
+                quote! {
+                    {
+                        let started = #prelude_path::Instant::now();
+                        let poll = #trait_to_impl::poll(&mut self.#field, cx);
+                        #prelude_path::poll_metrics::record(#behaviour_name, #field_name, started.elapsed());
+                        poll
+                    }
+                }
+            } else {
+                quote! { #trait_to_impl::poll(&mut self.#field, cx) }
+            };
+
             quote! {
-                match #trait_to_impl::poll(&mut self.#field, cx) {
+                match #poll_field {
                     std::task::Poll::Ready(e) => return std::task::Poll::Ready(e.map_out(#map_out_event).map_in(#map_in_event)),
                     std::task::Poll::Pending => {},
                 }
@@ -483,6 +533,19 @@ fn build_struct(ast: &DeriveInput, data_struct: &DataStruct) -> syn::Result<Toke
             fn on_swarm_event(&mut self, event: #from_swarm) {
                 #(#on_swarm_event_stmts)*
             }
+
+            fn on_shutdown(&mut self) {
+                #(#on_shutdown_stmts)*
+            }
+
+            fn poll_ready(&mut self, cx: &mut std::task::Context) -> std::task::Poll<()> {
+                let pending = [#(#poll_ready_exprs),*];
+                if pending.contains(&true) {
+                    std::task::Poll::Pending
+                } else {
+                    std::task::Poll::Ready(())
+                }
+            }
         }
     };
 
@@ -18,18 +18,22 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+pub mod catch_panic;
 mod either;
+mod event_queue;
 mod external_addresses;
 mod listen_addresses;
 mod peer_addresses;
 pub mod toggle;
 
+pub use event_queue::EventQueue;
 pub use external_addresses::ExternalAddresses;
 pub use listen_addresses::ListenAddresses;
 pub use peer_addresses::PeerAddresses;
 
 use crate::connection::ConnectionId;
 use crate::dial_opts::DialOpts;
+use crate::handler::ProtocolMisbehavior;
 use crate::listen_opts::ListenOpts;
 use crate::{
     ConnectionDenied, ConnectionHandler, DialError, ListenError, THandler, THandlerInEvent,
@@ -37,7 +41,7 @@ use crate::{
 };
 use libp2p_core::{transport::ListenerId, ConnectedPoint, Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
-use std::{task::Context, task::Poll};
+use std::{any::Any, task::Context, task::Poll, time::Duration};
 
 /// A [`NetworkBehaviour`] defines the behaviour of the local node on the network.
 ///
@@ -213,6 +217,29 @@ pub trait NetworkBehaviour: 'static {
     /// order to wake it up at a later point in time.
     fn poll(&mut self, cx: &mut Context<'_>)
         -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>>;
+
+    /// Informs the behaviour that the swarm is about to shut down,
+    /// see [`Swarm::shutdown`](crate::Swarm::shutdown).
+    ///
+    /// The connections are still open at this point. Final messages to peers, e.g. a gossipsub
+    /// PRUNE or a rendezvous unregistration, can be sent through [`ToSwarm::NotifyHandler`]
+    /// from subsequent calls to [`NetworkBehaviour::poll`]. The swarm keeps polling the behaviour
+    /// until it returns [`Poll::Pending`] or the shutdown timeout elapsed, and only then closes
+    /// the connections, see [`FromSwarm::Shutdown`].
+    fn on_shutdown(&mut self) {}
+
+    /// Returns whether the behaviour is ready to process more events from connections and
+    /// listeners.
+    ///
+    /// Only consulted by a [`Swarm`](crate::Swarm) configured with
+    /// [`Config::with_behaviour_backpressure`](crate::Config::with_behaviour_backpressure).
+    /// While this returns [`Poll::Pending`], the swarm keeps polling the behaviour but no longer
+    /// polls its connections and listeners, so that backpressure propagates to the remotes.
+    /// The behaviour is responsible for waking the task once it is ready again, see
+    /// [`EventQueue`].
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<()> {
+        Poll::Ready(())
+    }
 }
 
 /// A command issued from a [`NetworkBehaviour`] for the [`Swarm`].
@@ -234,6 +261,16 @@ pub enum ToSwarm<TOutEvent, TInEvent> {
     /// This allows a [`NetworkBehaviour`] to identify a connection that resulted out of its own dial request.
     Dial { opts: DialOpts },
 
+    /// Instructs the swarm to start a dial after the given delay.
+    ///
+    /// The dial is identified by [`DialOpts::connection_id`] and can be cancelled via
+    /// [`ToSwarm::CancelDeferredDial`] before it is started.
+    /// See also [`Swarm::dial_after`](crate::Swarm::dial_after).
+    DialAfter { opts: DialOpts, delay: Duration },
+
+    /// Instructs the swarm to cancel a dial scheduled via [`ToSwarm::DialAfter`].
+    CancelDeferredDial { connection_id: ConnectionId },
+
     /// Instructs the [`Swarm`](crate::Swarm) to listen on the provided address.
     ListenOn { opts: ListenOpts },
 
@@ -308,6 +345,11 @@ pub enum ToSwarm<TOutEvent, TInEvent> {
 
     /// Reports external address of a remote peer to the [`Swarm`](crate::Swarm) and through that to other [`NetworkBehaviour`]s.
     NewExternalAddrOfPeer { peer_id: PeerId, address: Multiaddr },
+
+    /// Reports to the [`Swarm`](crate::Swarm) that the behaviour identified by `name` panicked and has been disabled.
+    ///
+    /// This is emitted by [`CatchPanic`](catch_panic::CatchPanic).
+    BehaviourPanicked { name: &'static str },
 }
 
 impl<TOutEvent, TInEventOld> ToSwarm<TOutEvent, TInEventOld> {
@@ -319,6 +361,10 @@ impl<TOutEvent, TInEventOld> ToSwarm<TOutEvent, TInEventOld> {
         match self {
             ToSwarm::GenerateEvent(e) => ToSwarm::GenerateEvent(e),
             ToSwarm::Dial { opts } => ToSwarm::Dial { opts },
+            ToSwarm::DialAfter { opts, delay } => ToSwarm::DialAfter { opts, delay },
+            ToSwarm::CancelDeferredDial { connection_id } => {
+                ToSwarm::CancelDeferredDial { connection_id }
+            }
             ToSwarm::ListenOn { opts } => ToSwarm::ListenOn { opts },
             ToSwarm::RemoveListener { id } => ToSwarm::RemoveListener { id },
             ToSwarm::NotifyHandler {
@@ -347,6 +393,7 @@ impl<TOutEvent, TInEventOld> ToSwarm<TOutEvent, TInEventOld> {
                 address: addr,
                 peer_id,
             },
+            ToSwarm::BehaviourPanicked { name } => ToSwarm::BehaviourPanicked { name },
         }
     }
 }
@@ -357,6 +404,10 @@ impl<TOutEvent, THandlerIn> ToSwarm<TOutEvent, THandlerIn> {
         match self {
             ToSwarm::GenerateEvent(e) => ToSwarm::GenerateEvent(f(e)),
             ToSwarm::Dial { opts } => ToSwarm::Dial { opts },
+            ToSwarm::DialAfter { opts, delay } => ToSwarm::DialAfter { opts, delay },
+            ToSwarm::CancelDeferredDial { connection_id } => {
+                ToSwarm::CancelDeferredDial { connection_id }
+            }
             ToSwarm::ListenOn { opts } => ToSwarm::ListenOn { opts },
             ToSwarm::RemoveListener { id } => ToSwarm::RemoveListener { id },
             ToSwarm::NotifyHandler {
@@ -385,6 +436,7 @@ impl<TOutEvent, THandlerIn> ToSwarm<TOutEvent, THandlerIn> {
                 address: addr,
                 peer_id,
             },
+            ToSwarm::BehaviourPanicked { name } => ToSwarm::BehaviourPanicked { name },
         }
     }
 }
@@ -453,6 +505,17 @@ pub enum FromSwarm<'a> {
     ExternalAddrExpired(ExternalAddrExpired<'a>),
     /// Informs the behaviour that we have discovered a new external address for a remote peer.
     NewExternalAddrOfPeer(NewExternalAddrOfPeer<'a>),
+    /// Informs the behaviour about an application-defined notification,
+    /// see [`Swarm::notify_behaviours`](crate::Swarm::notify_behaviours).
+    Notification(Notification<'a>),
+    /// Informs the behaviour that a handler detected a protocol violation of a peer,
+    /// see [`ConnectionHandlerEvent::ReportMisbehavior`](crate::ConnectionHandlerEvent::ReportMisbehavior).
+    MisbehaviorReport(MisbehaviorReport<'a>),
+    /// Informs the behaviour that the swarm is shutting down,
+    /// see [`Swarm::shutdown`](crate::Swarm::shutdown).
+    ///
+    /// All connections are closed subsequently and new ones are closed once established.
+    Shutdown,
 }
 
 /// [`FromSwarm`] variant that informs the behaviour about a newly established connection to a peer.
@@ -571,3 +634,31 @@ pub struct NewExternalAddrOfPeer<'a> {
     pub peer_id: PeerId,
     pub addr: &'a Multiaddr,
 }
+
+/// [`FromSwarm`] variant that informs the behaviour that a handler detected a protocol violation
+/// of a peer.
+///
+/// Reported to all behaviours, allowing e.g. a block list to act on
+/// [`ProtocolMisbehavior::suggested_penalty`].
+#[derive(Clone, Copy, Debug)]
+pub struct MisbehaviorReport<'a> {
+    pub peer_id: PeerId,
+    pub connection_id: ConnectionId,
+    pub misbehavior: &'a ProtocolMisbehavior,
+}
+
+/// [`FromSwarm`] variant that informs the behaviour about an application-defined notification.
+///
+/// Behaviours recover the notifications they are interested in by their type, see
+/// [`Notification::downcast_ref`], and ignore all others.
+#[derive(Clone, Copy, Debug)]
+pub struct Notification<'a> {
+    pub event: &'a dyn Any,
+}
+
+impl<'a> Notification<'a> {
+    /// Returns the notification if it is of type `T`.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&'a T> {
+        self.event.downcast_ref()
+    }
+}
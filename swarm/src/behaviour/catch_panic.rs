@@ -0,0 +1,297 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::behaviour::toggle::Toggle;
+use crate::behaviour::FromSwarm;
+use crate::connection::ConnectionId;
+use crate::{
+    ConnectionDenied, NetworkBehaviour, THandler, THandlerInEvent, THandlerOutEvent, ToSwarm,
+};
+use libp2p_core::{Endpoint, Multiaddr};
+use libp2p_identity::PeerId;
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::task::{Context, Poll, Waker};
+
+/// Implementation of `NetworkBehaviour` that disables the wrapped behaviour if it panics.
+///
+/// A panic in any of the inner behaviour's callbacks is caught, the inner behaviour is dropped and
+/// [`ToSwarm::BehaviourPanicked`] is reported, which the [`Swarm`](crate::Swarm) emits as
+/// [`SwarmEvent::BehaviourPanicked`](crate::SwarmEvent::BehaviourPanicked). From then on, the
+/// wrapper behaves like a disabled [`Toggle`], so the rest of the node keeps running in a degraded
+/// state instead of crashing.
+///
+/// Panics are only caught if the binary is compiled with `panic = "unwind"`, which is the default.
+/// Panics in the [`ConnectionHandler`](crate::ConnectionHandler)s of the behaviour are not caught.
+pub struct CatchPanic<TBehaviour> {
+    name: &'static str,
+    inner: Toggle<TBehaviour>,
+    /// Whether the panic of the inner behaviour still has to be reported.
+    report_panic: bool,
+    waker: Option<Waker>,
+}
+
+impl<TBehaviour> CatchPanic<TBehaviour> {
+    /// Wraps `behaviour`, which is identified by `name` in [`ToSwarm::BehaviourPanicked`].
+    pub fn new(name: &'static str, behaviour: TBehaviour) -> Self {
+        Self {
+            name,
+            inner: Toggle::from(Some(behaviour)),
+            report_panic: false,
+            waker: None,
+        }
+    }
+
+    /// Returns `true` if the inner behaviour has not panicked.
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_enabled()
+    }
+
+    /// Returns a reference to the inner `NetworkBehaviour`, unless it panicked.
+    pub fn as_ref(&self) -> Option<&TBehaviour> {
+        self.inner.as_ref()
+    }
+
+    /// Returns a mutable reference to the inner `NetworkBehaviour`, unless it panicked.
+    pub fn as_mut(&mut self) -> Option<&mut TBehaviour> {
+        self.inner.as_mut()
+    }
+
+    /// Runs `f` on the inner behaviour, returning `None` and disabling the behaviour if it panics.
+    fn guard<R>(&mut self, f: impl FnOnce(&mut Toggle<TBehaviour>) -> R) -> Option<R> {
+        let inner = &mut self.inner;
+        match panic::catch_unwind(AssertUnwindSafe(|| f(inner))) {
+            Ok(r) => Some(r),
+            Err(payload) => {
+                self.disable(payload);
+                None
+            }
+        }
+    }
+
+    fn disable(&mut self, payload: Box<dyn Any + Send>) {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("<non-string payload>");
+        tracing::error!(behaviour=%self.name, "Behaviour panicked, disabling it: {message}");
+
+        let inner = std::mem::replace(&mut self.inner, Toggle::from(None));
+        // The behaviour may be left in an inconsistent state, so dropping it could panic as well.
+        let _ = panic::catch_unwind(AssertUnwindSafe(move || drop(inner)));
+
+        self.report_panic = true;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+}
+
+impl<TBehaviour> NetworkBehaviour for CatchPanic<TBehaviour>
+where
+    TBehaviour: NetworkBehaviour,
+{
+    type ConnectionHandler = THandler<Toggle<TBehaviour>>;
+    type ToSwarm = TBehaviour::ToSwarm;
+
+    fn handle_pending_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<(), ConnectionDenied> {
+        self.guard(|inner| {
+            inner.handle_pending_inbound_connection(connection_id, local_addr, remote_addr)
+        })
+        .unwrap_or(Ok(()))
+    }
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        match self.guard(|inner| {
+            inner.handle_established_inbound_connection(
+                connection_id,
+                peer,
+                local_addr,
+                remote_addr,
+            )
+        }) {
+            Some(result) => result,
+            // The inner behaviour is disabled now, so this returns a disabled handler.
+            None => self.inner.handle_established_inbound_connection(
+                connection_id,
+                peer,
+                local_addr,
+                remote_addr,
+            ),
+        }
+    }
+
+    fn handle_pending_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        maybe_peer: Option<PeerId>,
+        addresses: &[Multiaddr],
+        effective_role: Endpoint,
+    ) -> Result<Vec<Multiaddr>, ConnectionDenied> {
+        self.guard(|inner| {
+            inner.handle_pending_outbound_connection(
+                connection_id,
+                maybe_peer,
+                addresses,
+                effective_role,
+            )
+        })
+        .unwrap_or(Ok(vec![]))
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        addr: &Multiaddr,
+        role_override: Endpoint,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        match self.guard(|inner| {
+            inner.handle_established_outbound_connection(connection_id, peer, addr, role_override)
+        }) {
+            Some(result) => result,
+            // The inner behaviour is disabled now, so this returns a disabled handler.
+            None => self.inner.handle_established_outbound_connection(
+                connection_id,
+                peer,
+                addr,
+                role_override,
+            ),
+        }
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        self.guard(|inner| inner.on_swarm_event(event));
+    }
+
+    fn on_shutdown(&mut self) {
+        self.guard(|inner| inner.on_shutdown());
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.guard(|inner| inner.poll_ready(cx))
+            .unwrap_or(Poll::Ready(()))
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        self.guard(|inner| inner.on_connection_handler_event(peer_id, connection_id, event));
+    }
+
+    fn poll(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        if !self.report_panic {
+            if let Some(poll) = self.guard(|inner| inner.poll(cx)) {
+                self.waker = Some(cx.waker().clone());
+                return poll;
+            }
+        }
+
+        if std::mem::take(&mut self.report_panic) {
+            return Poll::Ready(ToSwarm::BehaviourPanicked { name: self.name });
+        }
+
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dummy;
+    use futures::task::noop_waker_ref;
+
+    /// A behaviour that panics when polled.
+    struct Panicking;
+
+    impl NetworkBehaviour for Panicking {
+        type ConnectionHandler = dummy::ConnectionHandler;
+        type ToSwarm = void::Void;
+
+        fn handle_established_inbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: &Multiaddr,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn handle_established_outbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: Endpoint,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn on_swarm_event(&mut self, _: FromSwarm) {}
+
+        fn on_connection_handler_event(
+            &mut self,
+            _: PeerId,
+            _: ConnectionId,
+            event: THandlerOutEvent<Self>,
+        ) {
+            void::unreachable(event)
+        }
+
+        fn poll(
+            &mut self,
+            _: &mut Context<'_>,
+        ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+            panic!("poll panicked")
+        }
+    }
+
+    #[test]
+    fn panic_disables_behaviour() {
+        let mut behaviour = CatchPanic::new("panicking", Panicking);
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        assert!(matches!(
+            behaviour.poll(&mut cx),
+            Poll::Ready(ToSwarm::BehaviourPanicked { name: "panicking" })
+        ));
+        assert!(!behaviour.is_enabled());
+        assert!(behaviour.poll(&mut cx).is_pending());
+    }
+}
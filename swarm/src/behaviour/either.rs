@@ -129,6 +129,20 @@ where
         }
     }
 
+    fn on_shutdown(&mut self) {
+        match self {
+            Either::Left(b) => b.on_shutdown(),
+            Either::Right(b) => b.on_shutdown(),
+        }
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        match self {
+            Either::Left(b) => b.poll_ready(cx),
+            Either::Right(b) => b.poll_ready(cx),
+        }
+    }
+
     fn on_connection_handler_event(
         &mut self,
         peer_id: PeerId,
@@ -0,0 +1,96 @@
+use std::collections::VecDeque;
+use std::task::{Context, Poll, Waker};
+
+/// Utility struct for a bounded queue of events that a [`NetworkBehaviour`](crate::NetworkBehaviour)
+/// emits from its `poll`.
+///
+/// Once the queue is full, [`EventQueue::push_back`] hands the event back to the caller and
+/// [`EventQueue::poll_ready`] returns [`Poll::Pending`] until an event is taken out again.
+/// Forwarding [`EventQueue::poll_ready`] from [`NetworkBehaviour::poll_ready`](crate::NetworkBehaviour::poll_ready)
+/// lets a [`Swarm`](crate::Swarm) configured with
+/// [`Config::with_behaviour_backpressure`](crate::Config::with_behaviour_backpressure) stop
+/// feeding the behaviour while its consumer is lagging behind.
+#[derive(Debug)]
+pub struct EventQueue<T> {
+    events: VecDeque<T>,
+    capacity: usize,
+    waker: Option<Waker>,
+}
+
+impl<T> EventQueue<T> {
+    /// Creates an empty queue holding at most `capacity` events.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            events: VecDeque::new(),
+            capacity,
+            waker: None,
+        }
+    }
+
+    /// Appends an event, or returns it if the queue is full.
+    pub fn push_back(&mut self, event: T) -> Result<(), T> {
+        if self.is_full() {
+            return Err(event);
+        }
+        self.events.push_back(event);
+        Ok(())
+    }
+
+    /// Takes the oldest event out of the queue.
+    pub fn pop_front(&mut self) -> Option<T> {
+        let event = self.events.pop_front()?;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+        Some(event)
+    }
+
+    /// Returns [`Poll::Ready`] if the queue has room for another event.
+    ///
+    /// Otherwise the current task is woken up once an event is taken out of the queue.
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.is_full() {
+            self.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        Poll::Ready(())
+    }
+
+    /// Returns `true` if the queue can not take another event.
+    pub fn is_full(&self) -> bool {
+        self.events.len() >= self.capacity
+    }
+
+    /// Returns the number of queued events.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns `true` if no event is queued.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::task::noop_waker_ref;
+
+    #[test]
+    fn full_queue_rejects_events_until_drained() {
+        let mut cx = Context::from_waker(noop_waker_ref());
+        let mut queue = EventQueue::new(2);
+
+        assert_eq!(queue.push_back(1), Ok(()));
+        assert!(queue.poll_ready(&mut cx).is_ready());
+        assert_eq!(queue.push_back(2), Ok(()));
+        assert_eq!(queue.push_back(3), Err(3));
+        assert!(queue.poll_ready(&mut cx).is_pending());
+
+        assert_eq!(queue.pop_front(), Some(1));
+        assert!(queue.poll_ready(&mut cx).is_ready());
+        assert_eq!(queue.push_back(3), Ok(()));
+        assert_eq!(queue.len(), 2);
+    }
+}
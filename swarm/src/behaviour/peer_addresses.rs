@@ -1,29 +1,69 @@
-use crate::behaviour::FromSwarm;
+use crate::behaviour::{ConnectionEstablished, FromSwarm};
 use crate::{DialError, DialFailure, NewExternalAddrOfPeer};
 
-use libp2p_core::Multiaddr;
+use instant::Instant;
+use libp2p_core::{ConnectedPoint, Multiaddr};
 use libp2p_identity::PeerId;
 
 use lru::LruCache;
 
+use std::fmt;
 use std::num::NonZeroUsize;
+use std::ops::ControlFlow;
+
+/// A custom handler for [`FromSwarm`] events, see [`PeerAddresses::with_event_hook`].
+type EventHook = Box<dyn FnMut(&mut PeerAddresses, &FromSwarm) -> ControlFlow<bool> + Send + Sync>;
 
 /// Struct for tracking peers' external addresses of the [`Swarm`](crate::Swarm).
-#[derive(Debug)]
-pub struct PeerAddresses(LruCache<PeerId, LruCache<Multiaddr, ()>>);
+pub struct PeerAddresses {
+    addresses: LruCache<PeerId, LruCache<Multiaddr, ()>>,
+    /// The address of the most recent successful outbound connection to each peer and when it
+    /// was established.
+    last_successful: LruCache<PeerId, (Multiaddr, Instant)>,
+    /// Custom handling of [`FromSwarm`] events that runs ahead of the default one.
+    event_hook: Option<EventHook>,
+}
 
 impl PeerAddresses {
     /// Creates a [`PeerAddresses`] cache with capacity for the given number of peers.
     ///
     /// For each peer, we will at most store 10 addresses.
     pub fn new(number_of_peers: NonZeroUsize) -> Self {
-        Self(LruCache::new(number_of_peers))
+        Self {
+            addresses: LruCache::new(number_of_peers),
+            last_successful: LruCache::new(number_of_peers),
+            event_hook: None,
+        }
+    }
+
+    /// Registers custom handling of the [`FromSwarm`] events fed via
+    /// [`PeerAddresses::on_swarm_event`].
+    ///
+    /// The hook sees every event before the default handling and may modify the cache, e.g.
+    /// also remove addresses of failed dials without a known peer or add addresses of inbound
+    /// connections. Returning [`ControlFlow::Continue`] proceeds with the default handling,
+    /// whereas [`ControlFlow::Break`] skips it, e.g. to ignore connections over relayed
+    /// endpoints, and carries whether the hook changed the known addresses.
+    pub fn with_event_hook(
+        mut self,
+        hook: impl FnMut(&mut PeerAddresses, &FromSwarm) -> ControlFlow<bool> + Send + Sync + 'static,
+    ) -> Self {
+        self.event_hook = Some(Box::new(hook));
+        self
     }
 
     /// Feed a [`FromSwarm`] event to this struct.
     ///
     /// Returns whether the event changed peer's known external addresses.
     pub fn on_swarm_event(&mut self, event: &FromSwarm) -> bool {
+        if let Some(mut hook) = self.event_hook.take() {
+            let flow = hook(self, event);
+            self.event_hook = Some(hook);
+            if let ControlFlow::Break(changed) = flow {
+                return changed;
+            }
+        }
+
         match event {
             FromSwarm::NewExternalAddrOfPeer(NewExternalAddrOfPeer { peer_id, addr }) => {
                 self.add(*peer_id, (*addr).clone())
@@ -38,10 +78,32 @@ impl PeerAddresses {
                 }
                 true
             }
+            FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id,
+                endpoint: ConnectedPoint::Dialer { address, .. },
+                ..
+            }) => {
+                if let Ok(address) = prepare_addr(peer_id, address) {
+                    self.last_successful
+                        .put(*peer_id, (address, Instant::now()));
+                }
+                false
+            }
             _ => false,
         }
     }
 
+    /// Returns the address of the most recent successful outbound connection to `peer` and the
+    /// time the connection was established.
+    ///
+    /// This is tracked independently of the order of [`PeerAddresses::get`], so that reconnect
+    /// logic can try the address that worked last time first.
+    pub fn last_successful_address(&self, peer: &PeerId) -> Option<(&Multiaddr, Instant)> {
+        self.last_successful
+            .peek(peer)
+            .map(|(address, at)| (address, *at))
+    }
+
     /// Adds address to cache.
     /// Appends address to the existing set if peer addresses already exist.
     /// Creates a new cache entry for peer_id if no addresses are present.
@@ -50,12 +112,12 @@ impl PeerAddresses {
     pub fn add(&mut self, peer: PeerId, address: Multiaddr) -> bool {
         match prepare_addr(&peer, &address) {
             Ok(address) => {
-                if let Some(cached) = self.0.get_mut(&peer) {
+                if let Some(cached) = self.addresses.get_mut(&peer) {
                     cached.put(address, ()).is_none()
                 } else {
                     let mut set = LruCache::new(NonZeroUsize::new(10).expect("10 > 0"));
                     set.put(address, ());
-                    self.0.put(peer, set);
+                    self.addresses.put(peer, set);
 
                     true
                 }
@@ -66,7 +128,7 @@ impl PeerAddresses {
 
     /// Returns peer's external addresses.
     pub fn get(&mut self, peer: &PeerId) -> impl Iterator<Item = Multiaddr> + '_ {
-        self.0
+        self.addresses
             .get(peer)
             .into_iter()
             .flat_map(|c| c.iter().map(|(m, ())| m))
@@ -76,7 +138,7 @@ impl PeerAddresses {
     /// Removes address from peer addresses cache.
     /// Returns true if the address was removed.
     pub fn remove(&mut self, peer: &PeerId, address: &Multiaddr) -> bool {
-        match self.0.get_mut(peer) {
+        match self.addresses.get_mut(peer) {
             Some(addrs) => match prepare_addr(peer, address) {
                 Ok(address) => addrs.pop(&address).is_some(),
                 Err(_) => false,
@@ -90,9 +152,19 @@ fn prepare_addr(peer: &PeerId, addr: &Multiaddr) -> Result<Multiaddr, Multiaddr>
     addr.clone().with_p2p(*peer)
 }
 
+impl fmt::Debug for PeerAddresses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeerAddresses")
+            .field("addresses", &self.addresses)
+            .field("last_successful", &self.last_successful)
+            .field("has_event_hook", &self.event_hook.is_some())
+            .finish()
+    }
+}
+
 impl Default for PeerAddresses {
     fn default() -> Self {
-        Self(LruCache::new(NonZeroUsize::new(100).unwrap()))
+        Self::new(NonZeroUsize::new(100).unwrap())
     }
 }
 
@@ -290,6 +362,89 @@ mod tests {
         assert_eq!(cached, expected);
     }
 
+    #[test]
+    fn outbound_connection_updates_last_successful_address() {
+        let mut cache = PeerAddresses::default();
+        let peer_id = PeerId::random();
+        assert!(cache.last_successful_address(&peer_id).is_none());
+
+        let dialer = ConnectedPoint::Dialer {
+            address: MEMORY_ADDR_1000.clone(),
+            role_override: libp2p_core::Endpoint::Dialer,
+        };
+        cache.on_swarm_event(&connection_established(peer_id, &dialer));
+
+        let (address, _) = cache.last_successful_address(&peer_id).unwrap();
+        assert_eq!(
+            address,
+            &MEMORY_ADDR_1000.clone().with_p2p(peer_id).unwrap()
+        );
+
+        // Inbound connections don't tell us an address the peer can be dialed at.
+        let listener = ConnectedPoint::Listener {
+            local_addr: MEMORY_ADDR_2000.clone(),
+            send_back_addr: MEMORY_ADDR_2000.clone(),
+        };
+        cache.on_swarm_event(&connection_established(peer_id, &listener));
+
+        let (address, _) = cache.last_successful_address(&peer_id).unwrap();
+        assert_eq!(
+            address,
+            &MEMORY_ADDR_1000.clone().with_p2p(peer_id).unwrap()
+        );
+    }
+
+    #[test]
+    fn event_hook_runs_ahead_of_default_handling() {
+        let mut cache = PeerAddresses::default().with_event_hook(|cache, event| match event {
+            // Ignore connections over relayed endpoints.
+            FromSwarm::ConnectionEstablished(ConnectionEstablished { endpoint, .. })
+                if endpoint.is_relayed() =>
+            {
+                ControlFlow::Break(false)
+            }
+            // Also remember the addresses of inbound connections.
+            FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                peer_id,
+                endpoint: ConnectedPoint::Listener { send_back_addr, .. },
+                ..
+            }) => ControlFlow::Break(cache.add(*peer_id, send_back_addr.clone())),
+            _ => ControlFlow::Continue(()),
+        });
+        let peer_id = PeerId::random();
+
+        let relayed = ConnectedPoint::Dialer {
+            address: MEMORY_ADDR_1000.clone().with(Protocol::P2pCircuit),
+            role_override: libp2p_core::Endpoint::Dialer,
+        };
+        assert!(!cache.on_swarm_event(&connection_established(peer_id, &relayed)));
+        assert!(cache.last_successful_address(&peer_id).is_none());
+
+        let listener = ConnectedPoint::Listener {
+            local_addr: MEMORY_ADDR_1000.clone(),
+            send_back_addr: MEMORY_ADDR_2000.clone(),
+        };
+        assert!(cache.on_swarm_event(&connection_established(peer_id, &listener)));
+        assert_eq!(
+            cache.get(&peer_id).collect::<Vec<_>>(),
+            vec![MEMORY_ADDR_2000.clone().with_p2p(peer_id).unwrap()]
+        );
+
+        // Events not handled by the hook get the default handling.
+        assert!(cache.on_swarm_event(&new_external_addr_of_peer1(peer_id)));
+        assert_eq!(cache.get(&peer_id).count(), 2);
+    }
+
+    fn connection_established(peer_id: PeerId, endpoint: &ConnectedPoint) -> FromSwarm<'_> {
+        FromSwarm::ConnectionEstablished(ConnectionEstablished {
+            peer_id,
+            connection_id: ConnectionId::new_unchecked(0),
+            endpoint,
+            failed_addresses: &[],
+            other_established: 0,
+        })
+    }
+
     fn prepare_expected_addrs(
         peer_id: PeerId,
         addrs: impl Iterator<Item = Multiaddr>,
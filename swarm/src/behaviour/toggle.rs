@@ -18,7 +18,10 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::behaviour::FromSwarm;
+use crate::behaviour::{
+    AddressChange as SwarmAddressChange, ConnectionClosed, ConnectionEstablished, DialFailure,
+    FromSwarm, ListenFailure,
+};
 use crate::connection::ConnectionId;
 use crate::handler::{
     AddressChange, ConnectionEvent, ConnectionHandler, ConnectionHandlerEvent, DialUpgradeError,
@@ -32,13 +35,29 @@ use either::Either;
 use futures::future;
 use libp2p_core::{upgrade::DeniedUpgrade, Endpoint, Multiaddr};
 use libp2p_identity::PeerId;
-use std::{task::Context, task::Poll};
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use std::task::{Context, Poll, Waker};
 
 /// Implementation of `NetworkBehaviour` that can be either in the disabled or enabled state.
 ///
-/// The state can only be chosen at initialization.
+/// The state is chosen at initialization and can be changed later with [`Toggle::enable`] and
+/// [`Toggle::disable`], e.g. on `swarm.behaviour_mut()`.
+///
+/// An enabled behaviour only learns about connections established while it is enabled.
+/// Once it is disabled, the connection handlers it created stop making progress and no longer
+/// keep their connections alive.
 pub struct Toggle<TBehaviour> {
     inner: Option<TBehaviour>,
+    /// Shared with the handlers created by the current `inner`, cleared once it is disabled.
+    active: Arc<AtomicBool>,
+    /// Connections the current `inner` has been told about, with the peer once established.
+    connections: HashMap<ConnectionId, Option<PeerId>>,
+    /// Waker of the last `poll` while disabled.
+    waker: Option<Waker>,
 }
 
 impl<TBehaviour> Toggle<TBehaviour> {
@@ -47,6 +66,24 @@ impl<TBehaviour> Toggle<TBehaviour> {
         self.inner.is_some()
     }
 
+    /// Enables the `Toggle` with the given behaviour, returning the previously enabled one.
+    pub fn enable(&mut self, behaviour: TBehaviour) -> Option<TBehaviour> {
+        let previous = self.disable();
+        self.inner = Some(behaviour);
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+        previous
+    }
+
+    /// Disables the `Toggle`, returning the inner behaviour if it was enabled.
+    pub fn disable(&mut self) -> Option<TBehaviour> {
+        self.active.store(false, Ordering::Relaxed);
+        self.active = Arc::new(AtomicBool::new(true));
+        self.connections.clear();
+        self.inner.take()
+    }
+
     /// Returns a reference to the inner `NetworkBehaviour`.
     pub fn as_ref(&self) -> Option<&TBehaviour> {
         self.inner.as_ref()
@@ -60,7 +97,12 @@ impl<TBehaviour> Toggle<TBehaviour> {
 
 impl<TBehaviour> From<Option<TBehaviour>> for Toggle<TBehaviour> {
     fn from(inner: Option<TBehaviour>) -> Self {
-        Toggle { inner }
+        Toggle {
+            inner,
+            active: Arc::new(AtomicBool::new(true)),
+            connections: HashMap::new(),
+            waker: None,
+        }
     }
 }
 
@@ -81,6 +123,7 @@ where
             None => return Ok(()),
             Some(inner) => inner,
         };
+        self.connections.insert(connection_id, None);
 
         inner.handle_pending_inbound_connection(connection_id, local_addr, remote_addr)?;
 
@@ -95,9 +138,10 @@ where
         remote_addr: &Multiaddr,
     ) -> Result<THandler<Self>, ConnectionDenied> {
         let inner = match self.inner.as_mut() {
-            None => return Ok(ToggleConnectionHandler { inner: None }),
+            None => return Ok(ToggleConnectionHandler::disabled(&self.active)),
             Some(inner) => inner,
         };
+        self.connections.insert(connection_id, Some(peer));
 
         let handler = inner.handle_established_inbound_connection(
             connection_id,
@@ -106,9 +150,7 @@ where
             remote_addr,
         )?;
 
-        Ok(ToggleConnectionHandler {
-            inner: Some(handler),
-        })
+        Ok(ToggleConnectionHandler::enabled(handler, &self.active))
     }
 
     fn handle_pending_outbound_connection(
@@ -122,6 +164,7 @@ where
             None => return Ok(vec![]),
             Some(inner) => inner,
         };
+        self.connections.insert(connection_id, None);
 
         let addresses = inner.handle_pending_outbound_connection(
             connection_id,
@@ -141,9 +184,10 @@ where
         role_override: Endpoint,
     ) -> Result<THandler<Self>, ConnectionDenied> {
         let inner = match self.inner.as_mut() {
-            None => return Ok(ToggleConnectionHandler { inner: None }),
+            None => return Ok(ToggleConnectionHandler::disabled(&self.active)),
             Some(inner) => inner,
         };
+        self.connections.insert(connection_id, Some(peer));
 
         let handler = inner.handle_established_outbound_connection(
             connection_id,
@@ -152,14 +196,62 @@ where
             role_override,
         )?;
 
-        Ok(ToggleConnectionHandler {
-            inner: Some(handler),
-        })
+        Ok(ToggleConnectionHandler::enabled(handler, &self.active))
     }
 
     fn on_swarm_event(&mut self, event: FromSwarm) {
+        let Some(behaviour) = &mut self.inner else {
+            return;
+        };
+
+        // Only report connections the current behaviour knows about, and only count those.
+        match event {
+            FromSwarm::ConnectionEstablished(established) => {
+                if !self.connections.contains_key(&established.connection_id) {
+                    return;
+                }
+                let other_established =
+                    num_established(&self.connections, established.peer_id).saturating_sub(1);
+                behaviour.on_swarm_event(FromSwarm::ConnectionEstablished(ConnectionEstablished {
+                    other_established,
+                    ..established
+                }));
+            }
+            FromSwarm::ConnectionClosed(closed) => {
+                if self.connections.remove(&closed.connection_id).is_none() {
+                    return;
+                }
+                let remaining_established = num_established(&self.connections, closed.peer_id);
+                behaviour.on_swarm_event(FromSwarm::ConnectionClosed(ConnectionClosed {
+                    remaining_established,
+                    ..closed
+                }));
+            }
+            FromSwarm::AddressChange(SwarmAddressChange { connection_id, .. }) => {
+                if self.connections.contains_key(&connection_id) {
+                    behaviour.on_swarm_event(event);
+                }
+            }
+            FromSwarm::DialFailure(DialFailure { connection_id, .. })
+            | FromSwarm::ListenFailure(ListenFailure { connection_id, .. }) => {
+                if self.connections.remove(&connection_id).is_some() {
+                    behaviour.on_swarm_event(event);
+                }
+            }
+            event => behaviour.on_swarm_event(event),
+        }
+    }
+
+    fn on_shutdown(&mut self) {
         if let Some(behaviour) = &mut self.inner {
-            behaviour.on_swarm_event(event);
+            behaviour.on_shutdown();
+        }
+    }
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        match self.inner.as_mut() {
+            Some(behaviour) => behaviour.poll_ready(cx),
+            None => Poll::Ready(()),
         }
     }
 
@@ -170,7 +262,9 @@ where
         event: THandlerOutEvent<Self>,
     ) {
         if let Some(behaviour) = &mut self.inner {
-            behaviour.on_connection_handler_event(peer_id, connection_id, event)
+            if self.connections.contains_key(&connection_id) {
+                behaviour.on_connection_handler_event(peer_id, connection_id, event)
+            }
         }
     }
 
@@ -181,14 +275,45 @@ where
         if let Some(inner) = self.inner.as_mut() {
             inner.poll(cx)
         } else {
+            self.waker = Some(cx.waker().clone());
             Poll::Pending
         }
     }
 }
 
+fn num_established(connections: &HashMap<ConnectionId, Option<PeerId>>, peer: PeerId) -> usize {
+    connections.values().filter(|p| **p == Some(peer)).count()
+}
+
 /// Implementation of [`ConnectionHandler`] that can be in the disabled state.
 pub struct ToggleConnectionHandler<TInner> {
     inner: Option<TInner>,
+    /// Cleared once the behaviour that created `inner` is disabled.
+    active: Arc<AtomicBool>,
+}
+
+impl<TInner> ToggleConnectionHandler<TInner> {
+    fn enabled(inner: TInner, active: &Arc<AtomicBool>) -> Self {
+        Self {
+            inner: Some(inner),
+            active: Arc::clone(active),
+        }
+    }
+
+    fn disabled(active: &Arc<AtomicBool>) -> Self {
+        Self {
+            inner: None,
+            active: Arc::clone(active),
+        }
+    }
+
+    /// Returns the inner handler unless its behaviour was disabled in the meantime.
+    fn active_inner(&mut self) -> Option<&mut TInner> {
+        if !self.active.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.inner.as_mut()
+    }
 }
 
 impl<TInner> ToggleConnectionHandler<TInner>
@@ -281,17 +406,19 @@ where
     }
 
     fn on_behaviour_event(&mut self, event: Self::FromBehaviour) {
-        self.inner
-            .as_mut()
-            .expect("Can't receive events if disabled; QED")
-            .on_behaviour_event(event)
+        // The behaviour may have been enabled after this connection was established.
+        if let Some(inner) = self.active_inner() {
+            inner.on_behaviour_event(event)
+        }
     }
 
     fn connection_keep_alive(&self) -> bool {
-        self.inner
-            .as_ref()
-            .map(|h| h.connection_keep_alive())
-            .unwrap_or(false)
+        self.active.load(Ordering::Relaxed)
+            && self
+                .inner
+                .as_ref()
+                .map(|h| h.connection_keep_alive())
+                .unwrap_or(false)
     }
 
     fn poll(
@@ -300,7 +427,7 @@ where
     ) -> Poll<
         ConnectionHandlerEvent<Self::OutboundProtocol, Self::OutboundOpenInfo, Self::ToBehaviour>,
     > {
-        if let Some(inner) = self.inner.as_mut() {
+        if let Some(inner) = self.active_inner() {
             inner.poll(cx)
         } else {
             Poll::Pending
@@ -361,11 +488,16 @@ where
                     inner.on_connection_event(ConnectionEvent::RemoteProtocolsChange(change));
                 }
             }
+            ConnectionEvent::OutboundQueueChange(change) => {
+                if let Some(inner) = self.inner.as_mut() {
+                    inner.on_connection_event(ConnectionEvent::OutboundQueueChange(change));
+                }
+            }
         }
     }
 
     fn poll_close(&mut self, cx: &mut Context<'_>) -> Poll<Option<Self::ToBehaviour>> {
-        let Some(inner) = self.inner.as_mut() else {
+        let Some(inner) = self.active_inner() else {
             return Poll::Ready(None);
         };
 
@@ -21,18 +21,20 @@
 mod error;
 
 pub(crate) mod pool;
+mod stats;
 mod supported_protocols;
 
 pub use error::ConnectionError;
 pub(crate) use error::{
     PendingConnectionError, PendingInboundConnectionError, PendingOutboundConnectionError,
 };
+pub use stats::{ConnectionStats, CountingFuture, CountingStream, CountingTransport};
 pub use supported_protocols::SupportedProtocols;
 
 use crate::handler::{
-    AddressChange, ConnectionEvent, ConnectionHandler, DialUpgradeError, FullyNegotiatedInbound,
-    FullyNegotiatedOutbound, ListenUpgradeError, ProtocolSupport, ProtocolsAdded, ProtocolsChange,
-    UpgradeInfoSend,
+    AddressChange, Anomaly, ConnectionEvent, ConnectionHandler, DialUpgradeError,
+    FullyNegotiatedInbound, FullyNegotiatedOutbound, ListenUpgradeError, OutboundQueueChange,
+    ProtocolMisbehavior, ProtocolSupport, ProtocolsAdded, ProtocolsChange, UpgradeInfoSend,
 };
 use crate::stream::ActiveStreamCounter;
 use crate::upgrade::{InboundUpgradeSend, OutboundUpgradeSend};
@@ -47,14 +49,17 @@ use futures_timer::Delay;
 use instant::Instant;
 use libp2p_core::connection::ConnectedPoint;
 use libp2p_core::multiaddr::Multiaddr;
-use libp2p_core::muxing::{StreamMuxerBox, StreamMuxerEvent, StreamMuxerExt, SubstreamBox};
+use libp2p_core::muxing::{
+    StreamMuxer, StreamMuxerBox, StreamMuxerEvent, StreamMuxerExt, StreamPriority, SubstreamBox,
+};
 use libp2p_core::upgrade;
 use libp2p_core::upgrade::{NegotiationError, ProtocolError};
 use libp2p_core::Endpoint;
 use libp2p_identity::PeerId;
-use std::collections::HashSet;
+use std::collections::{HashSet, VecDeque};
 use std::fmt::{Display, Formatter};
 use std::future::Future;
+use std::num::NonZeroUsize;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::task::Waker;
 use std::time::Duration;
@@ -105,6 +110,10 @@ pub(crate) enum Event<T> {
     Handler(T),
     /// Address of the remote has changed.
     AddressChange(Multiaddr),
+    /// The remote misbehaved.
+    Anomaly(Anomaly),
+    /// The handler detected a protocol violation of the remote.
+    Misbehavior(ProtocolMisbehavior),
 }
 
 /// A multiplexed connection to a peer with an associated [`ConnectionHandler`].
@@ -145,6 +154,18 @@ where
     /// connection is the sum of negotiating and negotiated streams. A limit on
     /// the total number of streams can be enforced at the [`StreamMuxerBox`] level.
     max_negotiating_inbound_streams: usize,
+    /// The maximum number of outbound streams concurrently requested or negotiating on a
+    /// connection, if any. Outbound streams exceeding the limit are queued in
+    /// `queued_substreams` until a slot frees up.
+    max_negotiating_outbound_streams: Option<NonZeroUsize>,
+    /// Outbound stream requests of the handler waiting for the limit on concurrently negotiating
+    /// outbound streams.
+    ///
+    /// The upgrade timeout only starts once a request leaves this queue.
+    queued_substreams:
+        VecDeque<SubstreamProtocol<THandler::OutboundProtocol, THandler::OutboundOpenInfo>>,
+    /// The length of `queued_substreams` last reported to the handler.
+    reported_queued_substreams: usize,
     /// Contains all upgrades that are waiting for a new outbound substream.
     ///
     /// The upgrade timeout is already ticking here so this may fail in case the remote is not quick
@@ -184,6 +205,7 @@ where
         mut handler: THandler,
         substream_upgrade_protocol_override: Option<upgrade::Version>,
         max_negotiating_inbound_streams: usize,
+        max_negotiating_outbound_streams: Option<NonZeroUsize>,
         idle_timeout: Duration,
     ) -> Self {
         let initial_protocols = gather_supported_protocols(&handler);
@@ -200,6 +222,9 @@ where
             shutdown: Shutdown::None,
             substream_upgrade_protocol_override,
             max_negotiating_inbound_streams,
+            max_negotiating_outbound_streams,
+            queued_substreams: Default::default(),
+            reported_queued_substreams: 0,
             requested_substreams: Default::default(),
             local_supported_protocols: initial_protocols,
             remote_supported_protocols: Default::default(),
@@ -247,6 +272,9 @@ where
             negotiating_in,
             shutdown,
             max_negotiating_inbound_streams,
+            max_negotiating_outbound_streams,
+            queued_substreams,
+            reported_queued_substreams,
             substream_upgrade_protocol_override,
             local_supported_protocols: supported_protocols,
             remote_supported_protocols,
@@ -274,10 +302,7 @@ where
             match handler.poll(cx) {
                 Poll::Pending => {}
                 Poll::Ready(ConnectionHandlerEvent::OutboundSubstreamRequest { protocol }) => {
-                    let timeout = *protocol.timeout();
-                    let (upgrade, user_data) = protocol.into_upgrade();
-
-                    requested_substreams.push(SubstreamRequested::new(user_data, timeout, upgrade));
+                    queued_substreams.push_back(protocol);
                     continue; // Poll handler until exhausted.
                 }
                 Poll::Ready(ConnectionHandlerEvent::NotifyBehaviour(event)) => {
@@ -308,6 +333,39 @@ where
 
                     continue;
                 }
+                Poll::Ready(ConnectionHandlerEvent::ReportAnomaly(anomaly)) => {
+                    return Poll::Ready(Ok(Event::Anomaly(anomaly)));
+                }
+                Poll::Ready(ConnectionHandlerEvent::ReportMisbehavior(misbehavior)) => {
+                    return Poll::Ready(Ok(Event::Misbehavior(misbehavior)));
+                }
+            }
+
+            // Request queued outbound streams as long as the limit on concurrently negotiating
+            // outbound streams permits.
+            while max_negotiating_outbound_streams.map_or(true, |max| {
+                requested_substreams.len() + negotiating_out.len() < max.get()
+            }) {
+                let Some(protocol) = queued_substreams.pop_front() else {
+                    break;
+                };
+                let timeout = *protocol.timeout();
+                let priority = protocol.priority();
+                let (upgrade, user_data) = protocol.into_upgrade();
+
+                requested_substreams.push(SubstreamRequested::new(
+                    user_data, timeout, upgrade, priority,
+                ));
+            }
+
+            if queued_substreams.len() != *reported_queued_substreams {
+                *reported_queued_substreams = queued_substreams.len();
+                handler.on_connection_event(ConnectionEvent::OutboundQueueChange(
+                    OutboundQueueChange {
+                        queued: *reported_queued_substreams,
+                    },
+                ));
+                continue; // Go back to the top, handler can potentially make progress again.
             }
 
             // In case the [`ConnectionHandler`] can not make any more progress, poll the negotiating outbound streams.
@@ -345,7 +403,7 @@ where
                 }
                 Poll::Ready(Some((_, Err(StreamUpgradeError::Io(e))))) => {
                     tracing::debug!("failed to upgrade inbound stream: {e}");
-                    continue;
+                    return Poll::Ready(Ok(Event::Anomaly(Anomaly::NegotiationFailed)));
                 }
                 Poll::Ready(Some((_, Err(StreamUpgradeError::NegotiationFailed)))) => {
                     tracing::debug!("no protocol could be agreed upon for inbound stream");
@@ -362,6 +420,7 @@ where
             if negotiating_in.is_empty()
                 && negotiating_out.is_empty()
                 && requested_substreams.is_empty()
+                && queued_substreams.is_empty()
                 && stream_counter.has_no_active_streams()
             {
                 if let Some(new_timeout) =
@@ -394,8 +453,18 @@ where
                 }
             }
 
-            if let Some(requested_substream) = requested_substreams.iter_mut().next() {
-                match muxing.poll_outbound_unpin(cx)? {
+            // Open the requested substream with the highest priority first.
+            let mut next_requested_substream: Option<&mut SubstreamRequested<_, _>> = None;
+            for requested_substream in requested_substreams.iter_mut() {
+                if requested_substream.priority()
+                    > next_requested_substream.as_ref().and_then(|r| r.priority())
+                {
+                    next_requested_substream = Some(requested_substream);
+                }
+            }
+            if let Some(requested_substream) = next_requested_substream {
+                let priority = requested_substream.priority().unwrap_or_default();
+                match muxing.poll_outbound_with_priority_unpin(cx, priority)? {
                     Poll::Pending => {}
                     Poll::Ready(substream) => {
                         let (user_data, timeout, upgrade) = requested_substream.extract();
@@ -407,6 +476,7 @@ where
                             upgrade,
                             *substream_upgrade_protocol_override,
                             stream_counter.clone(),
+                            muxing.preferred_write_size(),
                         ));
 
                         continue; // Go back to the top, handler can potentially make progress again.
@@ -424,6 +494,7 @@ where
                             substream,
                             protocol,
                             stream_counter.clone(),
+                            muxing.preferred_write_size(),
                         ));
 
                         continue; // Go back to the top, handler can potentially make progress again.
@@ -528,6 +599,7 @@ impl<UserData, TOk, TErr> StreamUpgrade<UserData, TOk, TErr> {
         upgrade: Upgrade,
         version_override: Option<upgrade::Version>,
         counter: ActiveStreamCounter,
+        preferred_write_size: Option<usize>,
     ) -> Self
     where
         Upgrade: OutboundUpgradeSend<Output = TOk, Error = TErr>,
@@ -559,7 +631,7 @@ impl<UserData, TOk, TErr> StreamUpgrade<UserData, TOk, TErr> {
                 .map_err(to_stream_upgrade_error)?;
 
                 let output = upgrade
-                    .upgrade_outbound(Stream::new(stream, counter), info)
+                    .upgrade_outbound(Stream::new(stream, counter, preferred_write_size), info)
                     .await
                     .map_err(StreamUpgradeError::Apply)?;
 
@@ -574,6 +646,7 @@ impl<UserData, TOk, TErr> StreamUpgrade<UserData, TOk, TErr> {
         substream: SubstreamBox,
         protocol: SubstreamProtocol<Upgrade, UserData>,
         counter: ActiveStreamCounter,
+        preferred_write_size: Option<usize>,
     ) -> Self
     where
         Upgrade: InboundUpgradeSend<Output = TOk, Error = TErr>,
@@ -592,7 +665,7 @@ impl<UserData, TOk, TErr> StreamUpgrade<UserData, TOk, TErr> {
                         .map_err(to_stream_upgrade_error)?;
 
                 let output = upgrade
-                    .upgrade_inbound(Stream::new(stream, counter), info)
+                    .upgrade_inbound(Stream::new(stream, counter, preferred_write_size), info)
                     .await
                     .map_err(StreamUpgradeError::Apply)?;
 
@@ -646,6 +719,7 @@ enum SubstreamRequested<UserData, Upgrade> {
         user_data: UserData,
         timeout: Delay,
         upgrade: Upgrade,
+        priority: StreamPriority,
         /// A waker to notify our [`FuturesUnordered`] that we have extracted the data.
         ///
         /// This will ensure that we will get polled again in the next iteration which allows us to
@@ -656,15 +730,29 @@ enum SubstreamRequested<UserData, Upgrade> {
 }
 
 impl<UserData, Upgrade> SubstreamRequested<UserData, Upgrade> {
-    fn new(user_data: UserData, timeout: Duration, upgrade: Upgrade) -> Self {
+    fn new(
+        user_data: UserData,
+        timeout: Duration,
+        upgrade: Upgrade,
+        priority: StreamPriority,
+    ) -> Self {
         Self::Waiting {
             user_data,
             timeout: Delay::new(timeout),
             upgrade,
+            priority,
             extracted_waker: None,
         }
     }
 
+    /// The priority of the requested substream, `None` once it was extracted.
+    fn priority(&self) -> Option<StreamPriority> {
+        match self {
+            SubstreamRequested::Waiting { priority, .. } => Some(*priority),
+            SubstreamRequested::Done => None,
+        }
+    }
+
     fn extract(&mut self) -> (UserData, Delay, Upgrade) {
         match mem::replace(self, Self::Done) {
             SubstreamRequested::Waiting {
@@ -672,6 +760,7 @@ impl<UserData, Upgrade> SubstreamRequested<UserData, Upgrade> {
                 timeout,
                 upgrade,
                 extracted_waker: waker,
+                ..
             } => {
                 if let Some(waker) = waker {
                     waker.wake();
@@ -697,6 +786,7 @@ impl<UserData, Upgrade> Future for SubstreamRequested<UserData, Upgrade> {
                 user_data,
                 upgrade,
                 mut timeout,
+                priority,
                 ..
             } => match timeout.poll_unpin(cx) {
                 Poll::Ready(()) => Poll::Ready(Err(user_data)),
@@ -705,6 +795,7 @@ impl<UserData, Upgrade> Future for SubstreamRequested<UserData, Upgrade> {
                         user_data,
                         upgrade,
                         timeout,
+                        priority,
                         extracted_waker: Some(cx.waker().clone()),
                     };
                     Poll::Pending
@@ -742,7 +833,6 @@ mod tests {
     use futures::AsyncRead;
     use futures::AsyncWrite;
     use libp2p_core::upgrade::{DeniedUpgrade, InboundUpgrade, OutboundUpgrade, UpgradeInfo};
-    use libp2p_core::StreamMuxer;
     use quickcheck::*;
     use std::sync::{Arc, Weak};
     use std::time::Instant;
@@ -766,6 +856,7 @@ mod tests {
                 MockConnectionHandler::new(Duration::from_secs(10)),
                 None,
                 max_negotiating_inbound_streams,
+                None,
                 Duration::ZERO,
             );
 
@@ -790,6 +881,7 @@ mod tests {
             MockConnectionHandler::new(upgrade_timeout),
             None,
             2,
+            None,
             Duration::ZERO,
         );
 
@@ -806,6 +898,27 @@ mod tests {
         ))
     }
 
+    #[test]
+    fn queues_outbound_streams_above_limit() {
+        let mut connection = Connection::new(
+            StreamMuxerBox::new(PendingStreamMuxer),
+            ConfigurableProtocolConnectionHandler::default(),
+            None,
+            0,
+            NonZeroUsize::new(2),
+            Duration::ZERO,
+        );
+
+        for _ in 0..5 {
+            connection.handler.request_outbound();
+        }
+        let _ = connection.poll_noop_waker();
+
+        assert_eq!(connection.requested_substreams.len(), 2);
+        assert_eq!(connection.queued_substreams.len(), 3);
+        assert_eq!(connection.handler.outbound_queued, vec![3]);
+    }
+
     #[test]
     fn propagates_changes_to_supported_inbound_protocols() {
         let mut connection = Connection::new(
@@ -813,6 +926,7 @@ mod tests {
             ConfigurableProtocolConnectionHandler::default(),
             None,
             0,
+            None,
             Duration::ZERO,
         );
 
@@ -852,6 +966,7 @@ mod tests {
             ConfigurableProtocolConnectionHandler::default(),
             None,
             0,
+            None,
             Duration::ZERO,
         );
 
@@ -905,6 +1020,7 @@ mod tests {
             dummy::ConnectionHandler,
             None,
             0,
+            None,
             idle_timeout,
         );
 
@@ -1108,6 +1224,7 @@ mod tests {
         local_removed: Vec<Vec<StreamProtocol>>,
         remote_added: Vec<Vec<StreamProtocol>>,
         remote_removed: Vec<Vec<StreamProtocol>>,
+        outbound_queued: Vec<usize>,
     }
 
     impl ConfigurableProtocolConnectionHandler {
@@ -1115,6 +1232,13 @@ mod tests {
             self.active_protocols = protocols.iter().copied().map(StreamProtocol::new).collect();
         }
 
+        fn request_outbound(&mut self) {
+            self.events
+                .push(ConnectionHandlerEvent::OutboundSubstreamRequest {
+                    protocol: SubstreamProtocol::new(DeniedUpgrade, ()),
+                });
+        }
+
         fn remote_adds_support_for(&mut self, protocols: &[&'static str]) {
             self.events
                 .push(ConnectionHandlerEvent::ReportRemoteProtocols(
@@ -1172,7 +1296,8 @@ mod tests {
                 ConnectionEvent::AddressChange(_)
                 | ConnectionEvent::ListenUpgradeError(_)
                 | ConnectionEvent::LocalProtocolsChange(_)
-                | ConnectionEvent::RemoteProtocolsChange(_) => {}
+                | ConnectionEvent::RemoteProtocolsChange(_)
+                | ConnectionEvent::OutboundQueueChange(_) => {}
             }
         }
 
@@ -1247,6 +1372,9 @@ mod tests {
                 ConnectionEvent::RemoteProtocolsChange(ProtocolsChange::Removed(removed)) => {
                     self.remote_removed.push(removed.cloned().collect())
                 }
+                ConnectionEvent::OutboundQueueChange(OutboundQueueChange { queued }) => {
+                    self.outbound_queued.push(queued)
+                }
                 _ => {}
             }
         }
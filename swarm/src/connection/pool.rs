@@ -21,9 +21,11 @@
 use crate::connection::{Connection, ConnectionId, PendingPoint};
 use crate::{
     connection::{
+        stats::{self, ConnectionStats, Traffic},
         Connected, ConnectionError, IncomingInfo, PendingConnectionError,
         PendingInboundConnectionError, PendingOutboundConnectionError,
     },
+    handler::{Anomaly, ProtocolMisbehavior},
     transport::TransportError,
     ConnectedPoint, ConnectionHandler, Executor, Multiaddr, PeerId,
 };
@@ -39,13 +41,16 @@ use futures::{
 };
 use instant::{Duration, Instant};
 use libp2p_core::connection::Endpoint;
-use libp2p_core::muxing::{StreamMuxerBox, StreamMuxerExt};
+use libp2p_core::muxing::{
+    MuxerCounters, StreamMuxer, StreamMuxerBox, StreamMuxerExt, StreamMuxerStats,
+};
 use std::task::Waker;
 use std::{
     collections::HashMap,
-    fmt,
-    num::{NonZeroU8, NonZeroUsize},
+    error, fmt,
+    num::{NonZeroU32, NonZeroU8, NonZeroUsize},
     pin::Pin,
+    sync::Arc,
     task::Context,
     task::Poll,
 };
@@ -91,10 +96,8 @@ where
     counters: ConnectionCounters,
 
     /// The managed connections of each peer that are currently considered established.
-    established: FnvHashMap<
-        PeerId,
-        FnvHashMap<ConnectionId, EstablishedConnection<THandler::FromBehaviour>>,
-    >,
+    established:
+        HashMap<PeerId, FnvHashMap<ConnectionId, EstablishedConnection<THandler::FromBehaviour>>>,
 
     /// The pending connections that are currently being negotiated.
     pending: HashMap<ConnectionId, PendingConnection>,
@@ -105,6 +108,18 @@ where
     /// Number of addresses concurrently dialed for a single outbound connection attempt.
     dial_concurrency_factor: NonZeroU8,
 
+    /// The maximum number of concurrently pending outbound connections to a single peer, if any.
+    max_pending_dials_per_peer: Option<NonZeroUsize>,
+
+    /// The number of consecutive failed dials after which a peer is not dialed for the given
+    /// duration, if any.
+    dial_failure_cooldown: Option<(NonZeroU32, Duration)>,
+
+    /// Consecutive failed dials of peers, tracked if `dial_failure_cooldown` is configured.
+    ///
+    /// Only holds peers that failed within the last cooldown period.
+    dial_failures: HashMap<PeerId, DialFailures>,
+
     /// The configured override for substream protocol upgrades, if any.
     substream_upgrade_protocol_override: Option<libp2p_core::upgrade::Version>,
 
@@ -113,6 +128,9 @@ where
     /// See [`Connection::max_negotiating_inbound_streams`].
     max_negotiating_inbound_streams: usize,
 
+    /// See [`Connection::max_negotiating_outbound_streams`].
+    max_negotiating_outbound_streams: Option<NonZeroUsize>,
+
     /// How many [`task::EstablishedConnectionEvent`]s can be buffered before the connection is back-pressured.
     per_connection_event_buffer_size: usize,
 
@@ -146,6 +164,12 @@ pub(crate) struct EstablishedConnection<TInEvent> {
     endpoint: ConnectedPoint,
     /// Channel endpoint to send commands to the task.
     sender: mpsc::Sender<task::Command<TInEvent>>,
+    /// Bytes transferred over the connection, updated by its streams.
+    traffic: Arc<Traffic>,
+    /// The counters of the connection's muxer, if it keeps any.
+    muxer_counters: Option<MuxerCounters>,
+    /// Events sent to the handler that did not reach it yet.
+    handler_backlog: Arc<task::HandlerBacklog>,
 }
 
 impl<TInEvent> EstablishedConnection<TInEvent> {
@@ -161,10 +185,14 @@ impl<TInEvent> EstablishedConnection<TInEvent> {
     /// to close.
     pub(crate) fn notify_handler(&mut self, event: TInEvent) -> Result<(), TInEvent> {
         let cmd = task::Command::NotifyHandler(event);
-        self.sender.try_send(cmd).map_err(|e| match e.into_inner() {
-            task::Command::NotifyHandler(event) => event,
-            _ => unreachable!("Expect failed send to return initial event."),
-        })
+        self.sender
+            .try_send(cmd)
+            .map_err(|e| match e.into_inner() {
+                task::Command::NotifyHandler(event) => event,
+                _ => unreachable!("Expect failed send to return initial event."),
+            })?;
+        self.handler_backlog.push();
+        Ok(())
     }
 
     /// Checks if `notify_handler` is ready to accept an event.
@@ -180,6 +208,16 @@ impl<TInEvent> EstablishedConnection<TInEvent> {
         self.sender.poll_ready(cx).map_err(|_| ())
     }
 
+    /// Returns the number of bytes transferred over the connection so far.
+    pub(crate) fn stats(&self) -> ConnectionStats {
+        self.traffic.stats()
+    }
+
+    /// Returns the statistics of the connection's muxer, if it keeps any.
+    pub(crate) fn muxer_stats(&self) -> Option<StreamMuxerStats> {
+        self.muxer_counters.as_ref().map(MuxerCounters::stats)
+    }
+
     /// Initiates a graceful close of the connection.
     ///
     /// Has no effect if the connection is already closing.
@@ -201,6 +239,8 @@ struct PendingConnection {
     abort_notifier: Option<oneshot::Sender<Void>>,
     /// The moment we became aware of this possible connection, useful for timing metrics.
     accepted_at: Instant,
+    /// Bytes transferred over the connection, see [`stats::count_into`].
+    traffic: Arc<Traffic>,
 }
 
 impl PendingConnection {
@@ -293,6 +333,20 @@ pub(crate) enum PoolEvent<ToBehaviour> {
         event: ToBehaviour,
     },
 
+    /// The remote misbehaved on a connection.
+    Anomaly {
+        id: ConnectionId,
+        peer_id: PeerId,
+        anomaly: Anomaly,
+    },
+
+    /// A handler detected a protocol violation of the remote on a connection.
+    Misbehavior {
+        id: ConnectionId,
+        peer_id: PeerId,
+        misbehavior: ProtocolMisbehavior,
+    },
+
     /// The connection to a node has changed its address.
     AddressChange {
         id: ConnectionId,
@@ -322,8 +376,12 @@ where
             pending: Default::default(),
             task_command_buffer_size: config.task_command_buffer_size,
             dial_concurrency_factor: config.dial_concurrency_factor,
+            max_pending_dials_per_peer: config.max_pending_dials_per_peer,
+            dial_failure_cooldown: config.dial_failure_cooldown,
+            dial_failures: Default::default(),
             substream_upgrade_protocol_override: config.substream_upgrade_protocol_override,
             max_negotiating_inbound_streams: config.max_negotiating_inbound_streams,
+            max_negotiating_outbound_streams: config.max_negotiating_outbound_streams,
             per_connection_event_buffer_size: config.per_connection_event_buffer_size,
             idle_connection_timeout: config.idle_connection_timeout,
             executor,
@@ -350,6 +408,40 @@ where
             .find_map(|connections| connections.get_mut(&id))
     }
 
+    /// Returns the peer of an established connection by ID.
+    pub(crate) fn established_peer(&self, id: ConnectionId) -> Option<PeerId> {
+        self.established
+            .iter()
+            .find_map(|(peer, connections)| connections.contains_key(&id).then_some(*peer))
+    }
+
+    /// Returns the number of bytes transferred over an established connection.
+    pub(crate) fn connection_stats(&self, id: ConnectionId) -> Option<ConnectionStats> {
+        self.established
+            .values()
+            .find_map(|connections| connections.get(&id))
+            .map(EstablishedConnection::stats)
+    }
+
+    /// Returns the statistics of the muxer of an established connection.
+    pub(crate) fn muxer_stats(&self, id: ConnectionId) -> Option<StreamMuxerStats> {
+        self.established
+            .values()
+            .find_map(|connections| connections.get(&id))
+            .and_then(EstablishedConnection::muxer_stats)
+    }
+
+    /// Returns the number of bytes transferred over all established connections to a peer.
+    pub(crate) fn peer_stats(&self, peer: &PeerId) -> Option<ConnectionStats> {
+        let connections = self.established.get(peer)?;
+        Some(
+            connections
+                .values()
+                .map(EstablishedConnection::stats)
+                .fold(ConnectionStats::default(), |total, stats| total + stats),
+        )
+    }
+
     /// Returns true if we are connected to the given peer.
     ///
     /// This will return true only after a `NodeReached` event has been produced by `poll()`.
@@ -402,6 +494,78 @@ where
         })
     }
 
+    /// Checks whether another outbound connection to the given peer may be attempted, according to
+    /// the configured per-peer dial limits.
+    pub(crate) fn check_dial_limits(&mut self, peer: PeerId) -> Result<(), DialLimitExceeded> {
+        if let Some(failures) = self.dial_failures.get(&peer) {
+            match failures.cooldown_until {
+                Some(until) if until > Instant::now() => {
+                    return Err(DialLimitExceeded::CoolingDown {
+                        failures: failures.consecutive,
+                        remaining: until - Instant::now(),
+                    });
+                }
+                Some(_) => {
+                    self.dial_failures.remove(&peer);
+                }
+                None => {}
+            }
+        }
+
+        if let Some(limit) = self.max_pending_dials_per_peer {
+            let pending = self
+                .pending
+                .values()
+                .filter(|info| {
+                    matches!(info.endpoint, PendingPoint::Dialer { .. })
+                        && info.is_for_same_remote_as(peer)
+                })
+                .count();
+            if pending >= limit.get() {
+                return Err(DialLimitExceeded::PendingDials { limit: limit.get() });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Records a failed outbound connection attempt to the given peer, starting its cooldown
+    /// once the configured number of consecutive failures is reached.
+    fn record_dial_failure(&mut self, peer: PeerId) {
+        let Some((max_failures, cooldown)) = self.dial_failure_cooldown else {
+            return;
+        };
+        let now = Instant::now();
+
+        // Forget failures that are older than the cooldown, as well as elapsed cooldowns.
+        self.dial_failures.retain(|_, failures| {
+            failures
+                .cooldown_until
+                .unwrap_or(failures.last_failure + cooldown)
+                > now
+        });
+
+        let failures = self.dial_failures.entry(peer).or_insert(DialFailures {
+            consecutive: 0,
+            last_failure: now,
+            cooldown_until: None,
+        });
+        if failures.cooldown_until.is_some() {
+            return;
+        }
+        failures.consecutive += 1;
+        failures.last_failure = now;
+        if failures.consecutive >= max_failures.get() {
+            tracing::debug!(
+                %peer,
+                failures=%failures.consecutive,
+                ?cooldown,
+                "Not dialing peer until cooldown elapsed"
+            );
+            failures.cooldown_until = Some(now + cooldown);
+        }
+    }
+
     /// Returns an iterator over all connected peers, i.e. those that have
     /// at least one established connection in the pool.
     pub(crate) fn iter_connected(&self) -> impl Iterator<Item = &PeerId> {
@@ -424,6 +588,7 @@ where
         peer: Option<PeerId>,
         role_override: Endpoint,
         dial_concurrency_factor_override: Option<NonZeroU8>,
+        dial_stagger_delay: Option<Duration>,
         connection_id: ConnectionId,
     ) {
         let concurrency_factor =
@@ -432,16 +597,18 @@ where
         span.follows_from(tracing::Span::current());
 
         let (abort_notifier, abort_receiver) = oneshot::channel();
+        let traffic = Arc::new(Traffic::default());
 
-        self.executor.spawn(
+        self.executor.spawn(stats::count_into(
+            traffic.clone(),
             task::new_for_pending_outgoing_connection(
                 connection_id,
-                ConcurrentDial::new(dials, concurrency_factor),
+                ConcurrentDial::new(dials, concurrency_factor, dial_stagger_delay),
                 abort_receiver,
                 self.pending_connection_events_tx.clone(),
             )
             .instrument(span),
-        );
+        ));
 
         let endpoint = PendingPoint::Dialer { role_override };
 
@@ -453,6 +620,7 @@ where
                 endpoint,
                 abort_notifier: Some(abort_notifier),
                 accepted_at: Instant::now(),
+                traffic,
             },
         );
     }
@@ -474,7 +642,10 @@ where
         let span = tracing::debug_span!(parent: tracing::Span::none(), "new_incoming_connection", remote_addr = %info.send_back_addr, id = %connection_id);
         span.follows_from(tracing::Span::current());
 
-        self.executor.spawn(
+        let traffic = Arc::new(Traffic::default());
+
+        self.executor.spawn(stats::count_into(
+            traffic.clone(),
             task::new_for_pending_incoming_connection(
                 connection_id,
                 future,
@@ -482,7 +653,7 @@ where
                 self.pending_connection_events_tx.clone(),
             )
             .instrument(span),
-        );
+        ));
 
         self.counters.inc_pending_incoming();
         self.pending.insert(
@@ -492,6 +663,7 @@ where
                 endpoint: endpoint.into(),
                 abort_notifier: Some(abort_notifier),
                 accepted_at: Instant::now(),
+                traffic,
             },
         );
     }
@@ -504,11 +676,13 @@ where
         connection: NewConnection,
         handler: THandler,
     ) {
-        let connection = connection.extract();
+        let (connection, traffic) = connection.extract();
+        let muxer_counters = connection.counters();
         let conns = self.established.entry(obtained_peer_id).or_default();
         self.counters.inc_established(endpoint);
 
         let (command_sender, command_receiver) = mpsc::channel(self.task_command_buffer_size);
+        let handler_backlog = Arc::new(task::HandlerBacklog::default());
         let (event_sender, event_receiver) = mpsc::channel(self.per_connection_event_buffer_size);
 
         conns.insert(
@@ -516,6 +690,9 @@ where
             EstablishedConnection {
                 endpoint: endpoint.clone(),
                 sender: command_sender,
+                traffic,
+                muxer_counters,
+                handler_backlog: handler_backlog.clone(),
             },
         );
         self.established_connection_events.push(event_receiver);
@@ -528,6 +705,7 @@ where
             handler,
             self.substream_upgrade_protocol_override,
             self.max_negotiating_inbound_streams,
+            self.max_negotiating_outbound_streams,
             self.idle_connection_timeout,
         );
 
@@ -540,12 +718,21 @@ where
                 obtained_peer_id,
                 connection,
                 command_receiver,
+                handler_backlog,
                 event_sender,
             )
             .instrument(span),
         )
     }
 
+    /// Resolves once all events sent to the handlers of established connections reached them.
+    pub(crate) fn poll_handlers_drained(&self, cx: &mut Context<'_>) -> Poll<()> {
+        for connection in self.established.values().flat_map(|c| c.values()) {
+            ready!(connection.handler_backlog.poll_drained(cx));
+        }
+        Poll::Ready(())
+    }
+
     /// Polls the connection pool for events.
     #[tracing::instrument(level = "debug", name = "Pool::poll", skip(self, cx))]
     pub(crate) fn poll(&mut self, cx: &mut Context<'_>) -> Poll<PoolEvent<THandler::ToBehaviour>>
@@ -566,6 +753,28 @@ where
             Poll::Ready(Some(task::EstablishedConnectionEvent::Notify { id, peer_id, event })) => {
                 return Poll::Ready(PoolEvent::ConnectionEvent { peer_id, id, event });
             }
+            Poll::Ready(Some(task::EstablishedConnectionEvent::Anomaly {
+                id,
+                peer_id,
+                anomaly,
+            })) => {
+                return Poll::Ready(PoolEvent::Anomaly {
+                    peer_id,
+                    id,
+                    anomaly,
+                });
+            }
+            Poll::Ready(Some(task::EstablishedConnectionEvent::Misbehavior {
+                id,
+                peer_id,
+                misbehavior,
+            })) => {
+                return Poll::Ready(PoolEvent::Misbehavior {
+                    peer_id,
+                    id,
+                    misbehavior,
+                });
+            }
             Poll::Ready(Some(task::EstablishedConnectionEvent::AddressChange {
                 id,
                 peer_id,
@@ -641,6 +850,7 @@ where
                         endpoint,
                         abort_notifier: _,
                         accepted_at,
+                        traffic,
                     } = self
                         .pending
                         .remove(&id)
@@ -711,12 +921,15 @@ where
 
                         match endpoint {
                             ConnectedPoint::Dialer { .. } => {
+                                if let Some(peer) = expected_peer_id {
+                                    self.record_dial_failure(peer);
+                                }
                                 return Poll::Ready(PoolEvent::PendingOutboundConnectionError {
                                     id,
                                     error: error
                                         .map(|t| vec![(endpoint.get_remote_address().clone(), t)]),
                                     peer: expected_peer_id.or(Some(obtained_peer_id)),
-                                })
+                                });
                             }
                             ConnectedPoint::Listener {
                                 send_back_addr,
@@ -733,8 +946,9 @@ where
                     }
 
                     let established_in = accepted_at.elapsed();
+                    self.dial_failures.remove(&obtained_peer_id);
 
-                    let (connection, drop_listener) = NewConnection::new(muxer);
+                    let (connection, drop_listener) = NewConnection::new(muxer, traffic);
                     self.new_connection_dropped_listeners.push(drop_listener);
 
                     return Poll::Ready(PoolEvent::ConnectionEstablished {
@@ -752,12 +966,18 @@ where
                         endpoint,
                         abort_notifier: _,
                         accepted_at: _, // Ignoring the time it took for the connection to fail.
+                        traffic: _,
                     }) = self.pending.remove(&id)
                     {
                         self.counters.dec_pending(&endpoint);
 
                         match (endpoint, error) {
                             (PendingPoint::Dialer { .. }, Either::Left(error)) => {
+                                if let (Some(peer), false) =
+                                    (peer_id, matches!(error, PendingConnectionError::Aborted))
+                                {
+                                    self.record_dial_failure(peer);
+                                }
                                 return Poll::Ready(PoolEvent::PendingOutboundConnectionError {
                                     id,
                                     error,
@@ -806,23 +1026,28 @@ where
 pub(crate) struct NewConnection {
     connection: Option<StreamMuxerBox>,
     drop_sender: Option<oneshot::Sender<StreamMuxerBox>>,
+    traffic: Arc<Traffic>,
 }
 
 impl NewConnection {
-    fn new(conn: StreamMuxerBox) -> (Self, oneshot::Receiver<StreamMuxerBox>) {
+    fn new(
+        conn: StreamMuxerBox,
+        traffic: Arc<Traffic>,
+    ) -> (Self, oneshot::Receiver<StreamMuxerBox>) {
         let (sender, receiver) = oneshot::channel();
 
         (
             Self {
                 connection: Some(conn),
                 drop_sender: Some(sender),
+                traffic,
             },
             receiver,
         )
     }
 
-    fn extract(mut self) -> StreamMuxerBox {
-        self.connection.take().unwrap()
+    fn extract(mut self) -> (StreamMuxerBox, Arc<Traffic>) {
+        (self.connection.take().unwrap(), self.traffic.clone())
     }
 }
 
@@ -838,6 +1063,53 @@ impl Drop for NewConnection {
     }
 }
 
+/// Consecutive failed outbound connection attempts to a peer.
+#[derive(Debug)]
+struct DialFailures {
+    consecutive: u32,
+    last_failure: Instant,
+    /// Set once the configured number of consecutive failures is reached.
+    cooldown_until: Option<Instant>,
+}
+
+/// The per-peer dial limits configured via
+/// [`Config::with_max_pending_dials_per_peer`](crate::Config::with_max_pending_dials_per_peer) and
+/// [`Config::with_dial_failure_cooldown`](crate::Config::with_dial_failure_cooldown) prevented a
+/// dial.
+///
+/// Reported as the cause of [`DialError::Denied`](crate::DialError::Denied).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DialLimitExceeded {
+    /// The maximum number of pending outbound connections to the peer is reached.
+    PendingDials { limit: usize },
+    /// The peer is not dialed after too many consecutive failed dials.
+    CoolingDown {
+        /// The number of consecutive failed dials.
+        failures: u32,
+        /// The time left until the peer may be dialed again.
+        remaining: Duration,
+    },
+}
+
+impl fmt::Display for DialLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DialLimitExceeded::PendingDials { limit } => {
+                write!(f, "Reached limit of {limit} pending dials to peer")
+            }
+            DialLimitExceeded::CoolingDown {
+                failures,
+                remaining,
+            } => write!(
+                f,
+                "Peer not dialed for another {remaining:?} after {failures} consecutive failed dials"
+            ),
+        }
+    }
+}
+
+impl error::Error for DialLimitExceeded {}
+
 /// Network connection information.
 #[derive(Debug, Clone)]
 pub struct ConnectionCounters {
@@ -959,6 +1231,11 @@ pub(crate) struct PoolConfig {
     pub(crate) per_connection_event_buffer_size: usize,
     /// Number of addresses concurrently dialed for a single outbound connection attempt.
     pub(crate) dial_concurrency_factor: NonZeroU8,
+    /// The maximum number of concurrently pending outbound connections to a single peer, if any.
+    pub(crate) max_pending_dials_per_peer: Option<NonZeroUsize>,
+    /// The number of consecutive failed dials after which a peer is not dialed for the given
+    /// duration, if any.
+    pub(crate) dial_failure_cooldown: Option<(NonZeroU32, Duration)>,
     /// How long a connection should be kept alive once it is idling.
     pub(crate) idle_connection_timeout: Duration,
     /// The configured override for substream protocol upgrades, if any.
@@ -968,6 +1245,11 @@ pub(crate) struct PoolConfig {
     ///
     /// See [`Connection::max_negotiating_inbound_streams`].
     max_negotiating_inbound_streams: usize,
+
+    /// The maximum number of outbound streams concurrently negotiating on a connection, if any.
+    ///
+    /// See [`Connection::max_negotiating_outbound_streams`].
+    max_negotiating_outbound_streams: Option<NonZeroUsize>,
 }
 
 impl PoolConfig {
@@ -977,9 +1259,12 @@ impl PoolConfig {
             task_command_buffer_size: 32,
             per_connection_event_buffer_size: 7,
             dial_concurrency_factor: NonZeroU8::new(8).expect("8 > 0"),
+            max_pending_dials_per_peer: None,
+            dial_failure_cooldown: None,
             idle_connection_timeout: Duration::ZERO,
             substream_upgrade_protocol_override: None,
             max_negotiating_inbound_streams: 128,
+            max_negotiating_outbound_streams: None,
         }
     }
 
@@ -1012,6 +1297,22 @@ impl PoolConfig {
         self
     }
 
+    /// The maximum number of concurrently pending outbound connections to a single peer.
+    pub(crate) fn with_max_pending_dials_per_peer(mut self, limit: NonZeroUsize) -> Self {
+        self.max_pending_dials_per_peer = Some(limit);
+        self
+    }
+
+    /// Don't dial a peer for `cooldown` after `failures` consecutive failed dials.
+    pub(crate) fn with_dial_failure_cooldown(
+        mut self,
+        failures: NonZeroU32,
+        cooldown: Duration,
+    ) -> Self {
+        self.dial_failure_cooldown = Some((failures, cooldown));
+        self
+    }
+
     /// Configures an override for the substream upgrade protocol to use.
     pub(crate) fn with_substream_upgrade_protocol_override(
         mut self,
@@ -1028,4 +1329,12 @@ impl PoolConfig {
         self.max_negotiating_inbound_streams = v;
         self
     }
+
+    /// The maximum number of outbound streams concurrently negotiating on a connection.
+    ///
+    /// See [`Connection::max_negotiating_outbound_streams`].
+    pub(crate) fn with_max_negotiating_outbound_streams(mut self, v: NonZeroUsize) -> Self {
+        self.max_negotiating_outbound_streams = Some(v);
+        self
+    }
 }
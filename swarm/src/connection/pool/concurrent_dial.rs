@@ -23,13 +23,16 @@ use futures::{
     future::{BoxFuture, Future},
     ready,
     stream::{FuturesUnordered, StreamExt},
+    FutureExt,
 };
+use futures_timer::Delay;
 use libp2p_core::muxing::StreamMuxerBox;
 use libp2p_identity::PeerId;
 use std::{
     num::NonZeroU8,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 type Dial = BoxFuture<
@@ -42,20 +45,37 @@ type Dial = BoxFuture<
 
 pub(crate) struct ConcurrentDial {
     dials: FuturesUnordered<Dial>,
-    pending_dials: Box<dyn Iterator<Item = Dial> + Send>,
+    pending_dials: std::vec::IntoIter<Dial>,
     errors: Vec<(Multiaddr, TransportError<std::io::Error>)>,
+    concurrency_factor: NonZeroU8,
+    /// The delay after which the next pending dial is started alongside the ongoing ones, and
+    /// the timer for it.
+    stagger: Option<(Duration, Delay)>,
 }
 
 impl Unpin for ConcurrentDial {}
 
 impl ConcurrentDial {
-    pub(crate) fn new(pending_dials: Vec<Dial>, concurrency_factor: NonZeroU8) -> Self {
+    /// Creates a new [`ConcurrentDial`] of at most `concurrency_factor` simultaneous dials.
+    ///
+    /// Without a `stagger_delay`, the first `concurrency_factor` dials are started at once.
+    /// Otherwise only the first dial is started, with each subsequent one starting after
+    /// `stagger_delay` or as soon as a previous dial failed.
+    pub(crate) fn new(
+        pending_dials: Vec<Dial>,
+        concurrency_factor: NonZeroU8,
+        stagger_delay: Option<Duration>,
+    ) -> Self {
         let mut pending_dials = pending_dials.into_iter();
+        let initial_dials = match stagger_delay {
+            Some(_) => 1,
+            None => concurrency_factor.get() as usize,
+        };
 
         let dials = FuturesUnordered::new();
         for dial in pending_dials.by_ref() {
             dials.push(dial);
-            if dials.len() == concurrency_factor.get() as usize {
+            if dials.len() == initial_dials {
                 break;
             }
         }
@@ -63,7 +83,11 @@ impl ConcurrentDial {
         Self {
             dials,
             errors: Default::default(),
-            pending_dials: Box::new(pending_dials),
+            stagger: stagger_delay
+                .filter(|_| !pending_dials.as_slice().is_empty())
+                .map(|delay| (delay, Delay::new(delay))),
+            pending_dials,
+            concurrency_factor,
         }
     }
 }
@@ -82,20 +106,39 @@ impl Future for ConcurrentDial {
     >;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = &mut *self;
         loop {
-            match ready!(self.dials.poll_next_unpin(cx)) {
+            if let Some((delay, timer)) = this.stagger.as_mut() {
+                if timer.poll_unpin(cx).is_ready() {
+                    timer.reset(*delay);
+                    if this.dials.len() < this.concurrency_factor.get() as usize {
+                        if let Some(dial) = this.pending_dials.next() {
+                            this.dials.push(dial);
+                        }
+                    }
+                    if this.pending_dials.as_slice().is_empty() {
+                        this.stagger = None;
+                    }
+                    continue;
+                }
+            }
+
+            match ready!(this.dials.poll_next_unpin(cx)) {
                 Some((addr, Ok(output))) => {
-                    let errors = std::mem::take(&mut self.errors);
+                    let errors = std::mem::take(&mut this.errors);
                     return Poll::Ready(Ok((addr, output, errors)));
                 }
                 Some((addr, Err(e))) => {
-                    self.errors.push((addr, e));
-                    if let Some(dial) = self.pending_dials.next() {
-                        self.dials.push(dial)
+                    this.errors.push((addr, e));
+                    if let Some(dial) = this.pending_dials.next() {
+                        this.dials.push(dial)
+                    }
+                    if let Some((delay, timer)) = this.stagger.as_mut() {
+                        timer.reset(*delay);
                     }
                 }
                 None => {
-                    return Poll::Ready(Err(std::mem::take(&mut self.errors)));
+                    return Poll::Ready(Err(std::mem::take(&mut this.errors)));
                 }
             }
         }
@@ -27,16 +27,22 @@ use crate::{
         self, ConnectionError, ConnectionId, PendingInboundConnectionError,
         PendingOutboundConnectionError,
     },
+    handler::{Anomaly, ProtocolMisbehavior},
     transport::TransportError,
     ConnectionHandler, Multiaddr, PeerId,
 };
 use futures::{
     channel::{mpsc, oneshot},
     future::{poll_fn, Either, Future},
+    task::AtomicWaker,
     SinkExt, StreamExt,
 };
 use libp2p_core::muxing::StreamMuxerBox;
-use std::pin::Pin;
+use std::{
+    pin::Pin,
+    sync::atomic::{AtomicUsize, Ordering},
+    task::{Context, Poll},
+};
 use void::Void;
 
 /// Commands that can be sent to a task driving an established connection.
@@ -49,6 +55,40 @@ pub(crate) enum Command<T> {
     Close,
 }
 
+/// The number of [`Command::NotifyHandler`]s sent to a task that did not reach the connection
+/// handler yet.
+#[derive(Debug, Default)]
+pub(crate) struct HandlerBacklog {
+    undelivered: AtomicUsize,
+    waker: AtomicWaker,
+}
+
+impl HandlerBacklog {
+    /// Records an event sent to the task.
+    pub(crate) fn push(&self) {
+        self.undelivered.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records an event handed to the connection handler.
+    fn pop(&self) {
+        if self.undelivered.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.waker.wake();
+        }
+    }
+
+    /// Resolves once all events sent to the task reached the connection handler.
+    pub(crate) fn poll_drained(&self, cx: &mut Context<'_>) -> Poll<()> {
+        if self.undelivered.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(());
+        }
+        self.waker.register(cx.waker());
+        if self.undelivered.load(Ordering::Acquire) == 0 {
+            return Poll::Ready(());
+        }
+        Poll::Pending
+    }
+}
+
 pub(crate) enum PendingConnectionEvent {
     ConnectionEstablished {
         id: ConnectionId,
@@ -73,6 +113,18 @@ pub(crate) enum EstablishedConnectionEvent<ToBehaviour> {
         peer_id: PeerId,
         new_address: Multiaddr,
     },
+    /// The remote misbehaved on the connection.
+    Anomaly {
+        id: ConnectionId,
+        peer_id: PeerId,
+        anomaly: Anomaly,
+    },
+    /// The handler detected a protocol violation of the remote.
+    Misbehavior {
+        id: ConnectionId,
+        peer_id: PeerId,
+        misbehavior: ProtocolMisbehavior,
+    },
     /// Notify the manager of an event from the connection.
     Notify {
         id: ConnectionId,
@@ -171,6 +223,7 @@ pub(crate) async fn new_for_established_connection<THandler>(
     peer_id: PeerId,
     mut connection: crate::connection::Connection<THandler>,
     mut command_receiver: mpsc::Receiver<Command<THandler::FromBehaviour>>,
+    backlog: std::sync::Arc<HandlerBacklog>,
     mut events: mpsc::Sender<EstablishedConnectionEvent<THandler::ToBehaviour>>,
 ) where
     THandler: ConnectionHandler,
@@ -183,7 +236,10 @@ pub(crate) async fn new_for_established_connection<THandler>(
         .await
         {
             Either::Left((Some(command), _)) => match command {
-                Command::NotifyHandler(event) => connection.on_behaviour_event(event),
+                Command::NotifyHandler(event) => {
+                    connection.on_behaviour_event(event);
+                    backlog.pop();
+                }
                 Command::Close => {
                     command_receiver.close();
                     let (remaining_events, closing_muxer) = connection.close();
@@ -225,6 +281,24 @@ pub(crate) async fn new_for_established_connection<THandler>(
                             })
                             .await;
                     }
+                    Ok(connection::Event::Anomaly(anomaly)) => {
+                        let _ = events
+                            .send(EstablishedConnectionEvent::Anomaly {
+                                id: connection_id,
+                                peer_id,
+                                anomaly,
+                            })
+                            .await;
+                    }
+                    Ok(connection::Event::Misbehavior(misbehavior)) => {
+                        let _ = events
+                            .send(EstablishedConnectionEvent::Misbehavior {
+                                id: connection_id,
+                                peer_id,
+                                misbehavior,
+                            })
+                            .await;
+                    }
                     Ok(connection::Event::AddressChange(new_address)) => {
                         let _ = events
                             .send(EstablishedConnectionEvent::AddressChange {
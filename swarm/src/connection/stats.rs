@@ -0,0 +1,305 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::{
+    io::{IoSlice, IoSliceMut},
+    prelude::*,
+    ready,
+};
+use libp2p_core::{
+    transport::{ListenerId, TransportError, TransportEvent},
+    Multiaddr, Transport,
+};
+use std::{
+    cell::RefCell,
+    io,
+    ops::Add,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+thread_local! {
+    /// The counters of the pending connection whose task is currently polled, see [`count_into`].
+    static CURRENT_TRAFFIC: RefCell<Option<Arc<Traffic>>> = const { RefCell::new(None) };
+}
+
+/// Number of bytes transferred over a connection, see [`Swarm::connection_stats`](crate::Swarm::connection_stats).
+///
+/// Only connections of a transport wrapped in [`CountingTransport`] are counted. Wrapped below the
+/// security and multiplexing upgrades, the bytes are the ones on the wire, i.e. after encryption.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnectionStats {
+    bytes_inbound: u64,
+    bytes_outbound: u64,
+}
+
+impl ConnectionStats {
+    /// The number of bytes received from the remote.
+    pub fn bytes_inbound(&self) -> u64 {
+        self.bytes_inbound
+    }
+
+    /// The number of bytes sent to the remote.
+    pub fn bytes_outbound(&self) -> u64 {
+        self.bytes_outbound
+    }
+}
+
+impl Add for ConnectionStats {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self {
+            bytes_inbound: self.bytes_inbound.saturating_add(rhs.bytes_inbound),
+            bytes_outbound: self.bytes_outbound.saturating_add(rhs.bytes_outbound),
+        }
+    }
+}
+
+/// Byte counters shared between the streams of a connection and the pool.
+#[derive(Debug, Default)]
+pub(crate) struct Traffic {
+    inbound: AtomicU64,
+    outbound: AtomicU64,
+}
+
+impl Traffic {
+    pub(crate) fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            bytes_inbound: self.inbound.load(Ordering::Relaxed),
+            bytes_outbound: self.outbound.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Polls the task of a pending connection such that the connections a [`CountingTransport`]
+/// establishes in the meantime count towards `traffic`.
+pub(crate) fn count_into(
+    traffic: Arc<Traffic>,
+    task: impl Future<Output = ()> + Send + 'static,
+) -> impl Future<Output = ()> + Send + 'static {
+    /// Restores the previous counters, even if polling the task panics.
+    struct Reset(Option<Arc<Traffic>>);
+
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            CURRENT_TRAFFIC.with(|current| *current.borrow_mut() = self.0.take());
+        }
+    }
+
+    let mut task = Box::pin(task);
+    future::poll_fn(move |cx| {
+        let _reset = Reset(CURRENT_TRAFFIC.with(|current| current.replace(Some(traffic.clone()))));
+        task.as_mut().poll(cx)
+    })
+}
+
+/// Wraps a [`Transport`] and counts the bytes read from and written to each of its connections.
+///
+/// The counts are reported by [`Swarm::connection_stats`](crate::Swarm::connection_stats) and
+/// [`Swarm::peer_stats`](crate::Swarm::peer_stats). To count the bytes on the wire, wrap the
+/// transport before upgrading it:
+///
+/// ```
+/// # use libp2p_core::{transport::MemoryTransport, Transport};
+/// # use libp2p_swarm::CountingTransport;
+/// let transport = CountingTransport::new(MemoryTransport::default());
+/// // .upgrade(Version::V1).authenticate(..).multiplex(..)
+/// # let _ = transport;
+/// ```
+///
+/// Connections established outside of a [`Swarm`](crate::Swarm) are not counted.
+#[derive(Debug, Clone, Default)]
+pub struct CountingTransport<T> {
+    inner: T,
+}
+
+impl<T> CountingTransport<T> {
+    /// Wraps the given transport.
+    pub fn new(inner: T) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T> Transport for CountingTransport<T>
+where
+    T: Transport + Unpin,
+{
+    type Output = CountingStream<T::Output>;
+    type Error = T::Error;
+    type ListenerUpgrade = CountingFuture<T::ListenerUpgrade>;
+    type Dial = CountingFuture<T::Dial>;
+
+    fn listen_on(
+        &mut self,
+        id: ListenerId,
+        addr: Multiaddr,
+    ) -> Result<(), TransportError<Self::Error>> {
+        self.inner.listen_on(id, addr)
+    }
+
+    fn remove_listener(&mut self, id: ListenerId) -> bool {
+        self.inner.remove_listener(id)
+    }
+
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.inner.remove_listener_graceful(id, timeout)
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        Ok(CountingFuture::new(self.inner.dial(addr)?))
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        Ok(CountingFuture::new(self.inner.dial_as_listener(addr)?))
+    }
+
+    fn address_translation(&self, listen: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
+        self.inner.address_translation(listen, observed)
+    }
+
+    fn poll(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        Pin::new(&mut self.inner)
+            .poll(cx)
+            .map(|event| event.map_upgrade(CountingFuture::new))
+    }
+}
+
+/// The dial or listener upgrade future of a [`CountingTransport`].
+pub struct CountingFuture<F> {
+    inner: Pin<Box<F>>,
+}
+
+impl<F> CountingFuture<F> {
+    fn new(inner: F) -> Self {
+        Self {
+            inner: Box::pin(inner),
+        }
+    }
+}
+
+impl<F, O, E> Future for CountingFuture<F>
+where
+    F: Future<Output = Result<O, E>>,
+{
+    type Output = Result<CountingStream<O>, E>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = ready!(self.inner.as_mut().poll(cx))?;
+        let traffic = CURRENT_TRAFFIC
+            .with(|current| current.borrow().clone())
+            .unwrap_or_default();
+
+        Poll::Ready(Ok(CountingStream { inner, traffic }))
+    }
+}
+
+/// A connection of a [`CountingTransport`], adding the bytes read and written to the
+/// [`ConnectionStats`] of its connection.
+#[derive(Debug)]
+pub struct CountingStream<S> {
+    inner: S,
+    traffic: Arc<Traffic>,
+}
+
+impl<S> CountingStream<S> {
+    fn count_inbound(&self, num_bytes: usize) {
+        self.traffic
+            .inbound
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+
+    fn count_outbound(&self, num_bytes: usize) {
+        self.traffic
+            .outbound
+            .fetch_add(num_bytes as u64, Ordering::Relaxed);
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for CountingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let num_bytes = ready!(Pin::new(&mut self.inner).poll_read(cx, buf))?;
+        self.count_inbound(num_bytes);
+        Poll::Ready(Ok(num_bytes))
+    }
+
+    fn poll_read_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &mut [IoSliceMut<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let num_bytes = ready!(Pin::new(&mut self.inner).poll_read_vectored(cx, bufs))?;
+        self.count_inbound(num_bytes);
+        Poll::Ready(Ok(num_bytes))
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for CountingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let num_bytes = ready!(Pin::new(&mut self.inner).poll_write(cx, buf))?;
+        self.count_outbound(num_bytes);
+        Poll::Ready(Ok(num_bytes))
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let num_bytes = ready!(Pin::new(&mut self.inner).poll_write_vectored(cx, bufs))?;
+        self.count_outbound(num_bytes);
+        Poll::Ready(Ok(num_bytes))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_close(cx)
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::dial_opts::DialOpts;
+use crate::ConnectionId;
+use futures::{ready, FutureExt};
+use futures_timer::Delay;
+use instant::Instant;
+use std::collections::{BTreeMap, HashMap};
+use std::task::{Context, Poll, Waker};
+
+/// Dials scheduled for a later point in time, see [`Swarm::dial_after`](crate::Swarm::dial_after).
+#[derive(Default)]
+pub(crate) struct DeferredDials {
+    /// The scheduled dials, ordered by the time they are due.
+    queue: BTreeMap<(Instant, ConnectionId), DialOpts>,
+    /// The time each scheduled dial is due.
+    deadlines: HashMap<ConnectionId, Instant>,
+    /// Timer for the earliest scheduled dial.
+    timer: Option<Delay>,
+    waker: Option<Waker>,
+}
+
+impl DeferredDials {
+    /// Schedules a dial at the given time, replacing a dial scheduled for the same [`ConnectionId`].
+    pub(crate) fn schedule(&mut self, opts: DialOpts, at: Instant) {
+        let id = opts.connection_id();
+        if let Some(previous) = self.deadlines.insert(id, at) {
+            self.queue.remove(&(previous, id));
+        }
+        self.queue.insert((at, id), opts);
+
+        // Re-arm the timer in case the new dial is due earlier.
+        self.timer = None;
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
+    }
+
+    /// Cancels the dial scheduled for the given [`ConnectionId`], returning its [`DialOpts`].
+    pub(crate) fn cancel(&mut self, id: ConnectionId) -> Option<DialOpts> {
+        let at = self.deadlines.remove(&id)?;
+        self.queue.remove(&(at, id))
+    }
+
+    /// Returns the [`DialOpts`] of the next dial that is due.
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>) -> Poll<DialOpts> {
+        loop {
+            let Some(&(at, id)) = self.queue.keys().next() else {
+                self.timer = None;
+                self.waker = Some(cx.waker().clone());
+                return Poll::Pending;
+            };
+
+            let now = Instant::now();
+            if at <= now {
+                self.timer = None;
+                self.deadlines.remove(&id);
+                let opts = self
+                    .queue
+                    .remove(&(at, id))
+                    .expect("`queue` contains the first key");
+                return Poll::Ready(opts);
+            }
+
+            self.waker = Some(cx.waker().clone());
+            let timer = self.timer.get_or_insert_with(|| Delay::new(at - now));
+            ready!(timer.poll_unpin(cx));
+            self.timer = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::poll_fn;
+    use std::time::Duration;
+
+    #[test]
+    fn fires_in_order_and_skips_cancelled() {
+        let mut dials = DeferredDials::default();
+        let now = Instant::now();
+        let first = DialOpts::unknown_peer_id()
+            .address(libp2p_core::Multiaddr::empty())
+            .build();
+        let second = DialOpts::unknown_peer_id()
+            .address(libp2p_core::Multiaddr::empty())
+            .build();
+        let cancelled = DialOpts::unknown_peer_id()
+            .address(libp2p_core::Multiaddr::empty())
+            .build();
+        let (first_id, second_id, cancelled_id) = (
+            first.connection_id(),
+            second.connection_id(),
+            cancelled.connection_id(),
+        );
+
+        dials.schedule(second, now + Duration::from_millis(20));
+        dials.schedule(cancelled, now + Duration::from_millis(10));
+        dials.schedule(first, now);
+        assert!(dials.cancel(cancelled_id).is_some());
+
+        futures::executor::block_on(async {
+            let opts = poll_fn(|cx| dials.poll(cx)).await;
+            assert_eq!(opts.connection_id(), first_id);
+            let opts = poll_fn(|cx| dials.poll(cx)).await;
+            assert_eq!(opts.connection_id(), second_id);
+        });
+        assert!(dials.cancel(second_id).is_none());
+    }
+}
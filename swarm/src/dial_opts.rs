@@ -24,7 +24,9 @@ use libp2p_core::connection::Endpoint;
 use libp2p_core::multiaddr::Protocol;
 use libp2p_core::Multiaddr;
 use libp2p_identity::PeerId;
+use std::fmt;
 use std::num::NonZeroU8;
+use std::time::Duration;
 
 /// Options to configure a dial to a known or unknown peer.
 ///
@@ -44,6 +46,9 @@ pub struct DialOpts {
     extend_addresses_through_behaviour: bool,
     role_override: Endpoint,
     dial_concurrency_factor_override: Option<NonZeroU8>,
+    dial_stagger_delay: Option<Duration>,
+    dial_timeout_override: Option<Duration>,
+    address_ranking: Option<AddressRanking>,
     connection_id: ConnectionId,
 }
 
@@ -65,6 +70,9 @@ impl DialOpts {
             condition: Default::default(),
             role_override: Endpoint::Dialer,
             dial_concurrency_factor_override: Default::default(),
+            dial_stagger_delay: None,
+            dial_timeout_override: None,
+            address_ranking: None,
         }
     }
 
@@ -121,6 +129,22 @@ impl DialOpts {
         self.dial_concurrency_factor_override
     }
 
+    pub(crate) fn dial_stagger_delay(&self) -> Option<Duration> {
+        self.dial_stagger_delay
+    }
+
+    pub(crate) fn dial_timeout_override(&self) -> Option<Duration> {
+        self.dial_timeout_override
+    }
+
+    /// Applies the ranking configured via [`WithPeerId::rank_addresses`], if any.
+    pub(crate) fn rank_addresses(&mut self, addresses: Vec<Multiaddr>) -> Vec<Multiaddr> {
+        match self.address_ranking.take() {
+            Some(AddressRanking(rank)) => rank(addresses),
+            None => addresses,
+        }
+    }
+
     pub(crate) fn role_override(&self) -> Endpoint {
         self.role_override
     }
@@ -144,6 +168,9 @@ pub struct WithPeerId {
     condition: PeerCondition,
     role_override: Endpoint,
     dial_concurrency_factor_override: Option<NonZeroU8>,
+    dial_stagger_delay: Option<Duration>,
+    dial_timeout_override: Option<Duration>,
+    address_ranking: Option<AddressRanking>,
 }
 
 impl WithPeerId {
@@ -160,6 +187,57 @@ impl WithPeerId {
         self
     }
 
+    /// Override the timeout for dialing each address of this dial, including the upgrades of
+    /// the transport.
+    ///
+    /// Takes precedence over [`Config::with_dial_timeout`](crate::Config::with_dial_timeout)
+    /// and [`Config::with_protocol_dial_timeout`](crate::Config::with_protocol_dial_timeout),
+    /// e.g. to give a peer on the local network less time than a relay on another continent.
+    pub fn override_dial_timeout(mut self, timeout: Duration) -> Self {
+        self.dial_timeout_override = Some(timeout);
+        self
+    }
+
+    /// Race the addresses of the peer [Happy Eyeballs](https://datatracker.ietf.org/doc/html/rfc8305)
+    /// style instead of dialing them all at once.
+    ///
+    /// Addresses are tried in order of preference, QUIC before other transports and IPv6 before
+    /// IPv4. The next address is dialed once the previous dial failed or `delay` elapsed without
+    /// it succeeding, e.g. 250ms. The remaining dials are aborted as soon as one succeeds.
+    /// At most [`Self::override_dial_concurrency_factor`] addresses are dialed concurrently.
+    pub fn stagger_dials(mut self, delay: Duration) -> Self {
+        self.dial_stagger_delay = Some(delay);
+        self
+    }
+
+    /// Rank and filter the final list of addresses to dial, after
+    /// [`NetworkBehaviour::handle_pending_outbound_connection`](crate::behaviour::NetworkBehaviour::handle_pending_outbound_connection)
+    /// contributed its addresses.
+    ///
+    /// The returned addresses are dialed in the given order, e.g. only QUIC addresses:
+    ///
+    /// ```
+    /// # use libp2p_core::multiaddr::Protocol;
+    /// # use libp2p_identity::PeerId;
+    /// # use libp2p_swarm::dial_opts::DialOpts;
+    /// DialOpts::peer_id(PeerId::random())
+    ///     .rank_addresses(|mut addresses| {
+    ///         addresses.retain(|a| a.iter().any(|p| matches!(p, Protocol::QuicV1)));
+    ///         addresses
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// The dial fails with [`DialError::NoAddresses`](crate::DialError::NoAddresses) if no
+    /// address is returned.
+    pub fn rank_addresses(
+        mut self,
+        rank: impl FnOnce(Vec<Multiaddr>) -> Vec<Multiaddr> + Send + Sync + 'static,
+    ) -> Self {
+        self.address_ranking = Some(AddressRanking(Box::new(rank)));
+        self
+    }
+
     /// Specify a set of addresses to be used to dial the known peer.
     pub fn addresses(self, addresses: Vec<Multiaddr>) -> WithPeerIdWithAddresses {
         WithPeerIdWithAddresses {
@@ -169,6 +247,9 @@ impl WithPeerId {
             extend_addresses_through_behaviour: false,
             role_override: self.role_override,
             dial_concurrency_factor_override: self.dial_concurrency_factor_override,
+            dial_stagger_delay: self.dial_stagger_delay,
+            dial_timeout_override: self.dial_timeout_override,
+            address_ranking: self.address_ranking,
         }
     }
 
@@ -192,6 +273,9 @@ impl WithPeerId {
             extend_addresses_through_behaviour: true,
             role_override: self.role_override,
             dial_concurrency_factor_override: self.dial_concurrency_factor_override,
+            dial_stagger_delay: self.dial_stagger_delay,
+            dial_timeout_override: self.dial_timeout_override,
+            address_ranking: self.address_ranking,
             connection_id: ConnectionId::next(),
         }
     }
@@ -205,6 +289,9 @@ pub struct WithPeerIdWithAddresses {
     extend_addresses_through_behaviour: bool,
     role_override: Endpoint,
     dial_concurrency_factor_override: Option<NonZeroU8>,
+    dial_stagger_delay: Option<Duration>,
+    dial_timeout_override: Option<Duration>,
+    address_ranking: Option<AddressRanking>,
 }
 
 impl WithPeerIdWithAddresses {
@@ -239,6 +326,57 @@ impl WithPeerIdWithAddresses {
         self
     }
 
+    /// Override the timeout for dialing each address of this dial, including the upgrades of
+    /// the transport.
+    ///
+    /// Takes precedence over [`Config::with_dial_timeout`](crate::Config::with_dial_timeout)
+    /// and [`Config::with_protocol_dial_timeout`](crate::Config::with_protocol_dial_timeout),
+    /// e.g. to give a peer on the local network less time than a relay on another continent.
+    pub fn override_dial_timeout(mut self, timeout: Duration) -> Self {
+        self.dial_timeout_override = Some(timeout);
+        self
+    }
+
+    /// Race the addresses of the peer [Happy Eyeballs](https://datatracker.ietf.org/doc/html/rfc8305)
+    /// style instead of dialing them all at once.
+    ///
+    /// Addresses are tried in order of preference, QUIC before other transports and IPv6 before
+    /// IPv4. The next address is dialed once the previous dial failed or `delay` elapsed without
+    /// it succeeding, e.g. 250ms. The remaining dials are aborted as soon as one succeeds.
+    /// At most [`Self::override_dial_concurrency_factor`] addresses are dialed concurrently.
+    pub fn stagger_dials(mut self, delay: Duration) -> Self {
+        self.dial_stagger_delay = Some(delay);
+        self
+    }
+
+    /// Rank and filter the final list of addresses to dial, after
+    /// [`NetworkBehaviour::handle_pending_outbound_connection`](crate::behaviour::NetworkBehaviour::handle_pending_outbound_connection)
+    /// contributed its addresses.
+    ///
+    /// The returned addresses are dialed in the given order, e.g. only QUIC addresses:
+    ///
+    /// ```
+    /// # use libp2p_core::multiaddr::Protocol;
+    /// # use libp2p_identity::PeerId;
+    /// # use libp2p_swarm::dial_opts::DialOpts;
+    /// DialOpts::peer_id(PeerId::random())
+    ///     .rank_addresses(|mut addresses| {
+    ///         addresses.retain(|a| a.iter().any(|p| matches!(p, Protocol::QuicV1)));
+    ///         addresses
+    ///     })
+    ///     .build();
+    /// ```
+    ///
+    /// The dial fails with [`DialError::NoAddresses`](crate::DialError::NoAddresses) if no
+    /// address is returned.
+    pub fn rank_addresses(
+        mut self,
+        rank: impl FnOnce(Vec<Multiaddr>) -> Vec<Multiaddr> + Send + Sync + 'static,
+    ) -> Self {
+        self.address_ranking = Some(AddressRanking(Box::new(rank)));
+        self
+    }
+
     /// Build the final [`DialOpts`].
     pub fn build(self) -> DialOpts {
         DialOpts {
@@ -248,6 +386,9 @@ impl WithPeerIdWithAddresses {
             extend_addresses_through_behaviour: self.extend_addresses_through_behaviour,
             role_override: self.role_override,
             dial_concurrency_factor_override: self.dial_concurrency_factor_override,
+            dial_stagger_delay: self.dial_stagger_delay,
+            dial_timeout_override: self.dial_timeout_override,
+            address_ranking: self.address_ranking,
             connection_id: ConnectionId::next(),
         }
     }
@@ -262,6 +403,7 @@ impl WithoutPeerId {
         WithoutPeerIdWithAddress {
             address,
             role_override: Endpoint::Dialer,
+            dial_timeout_override: None,
         }
     }
 }
@@ -270,6 +412,7 @@ impl WithoutPeerId {
 pub struct WithoutPeerIdWithAddress {
     address: Multiaddr,
     role_override: Endpoint,
+    dial_timeout_override: Option<Duration>,
 }
 
 impl WithoutPeerIdWithAddress {
@@ -283,6 +426,16 @@ impl WithoutPeerIdWithAddress {
         self.role_override = Endpoint::Listener;
         self
     }
+
+    /// Override the timeout for dialing the address, including the upgrades of the transport.
+    ///
+    /// Takes precedence over [`Config::with_dial_timeout`](crate::Config::with_dial_timeout)
+    /// and [`Config::with_protocol_dial_timeout`](crate::Config::with_protocol_dial_timeout).
+    pub fn override_dial_timeout(mut self, timeout: Duration) -> Self {
+        self.dial_timeout_override = Some(timeout);
+        self
+    }
+
     /// Build the final [`DialOpts`].
     pub fn build(self) -> DialOpts {
         DialOpts {
@@ -292,11 +445,23 @@ impl WithoutPeerIdWithAddress {
             extend_addresses_through_behaviour: false,
             role_override: self.role_override,
             dial_concurrency_factor_override: None,
+            dial_stagger_delay: None,
+            dial_timeout_override: self.dial_timeout_override,
+            address_ranking: None,
             connection_id: ConnectionId::next(),
         }
     }
 }
 
+/// Closure ranking the addresses of a dial, see [`WithPeerId::rank_addresses`].
+struct AddressRanking(Box<dyn FnOnce(Vec<Multiaddr>) -> Vec<Multiaddr> + Send + Sync>);
+
+impl fmt::Debug for AddressRanking {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AddressRanking").finish_non_exhaustive()
+    }
+}
+
 /// The available conditions under which a new dialing attempt to
 /// a known peer is initiated.
 ///
@@ -0,0 +1,104 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_core::Multiaddr;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// The timeouts for dialing a single address, see [`Config::with_dial_timeout`](crate::Config::with_dial_timeout)
+/// and [`Config::with_protocol_dial_timeout`](crate::Config::with_protocol_dial_timeout).
+#[derive(Debug, Default, Clone)]
+pub(crate) struct DialTimeouts {
+    /// The timeout for addresses without a protocol specific timeout.
+    default: Option<Duration>,
+    /// The timeouts keyed by the tag of the protocol they apply to.
+    per_protocol: HashMap<&'static str, Duration>,
+}
+
+impl DialTimeouts {
+    pub(crate) fn set_default(&mut self, timeout: Duration) {
+        self.default = Some(timeout);
+    }
+
+    pub(crate) fn set_for_protocol(&mut self, protocol: &'static str, timeout: Duration) {
+        self.per_protocol.insert(protocol, timeout);
+    }
+
+    /// Returns the timeout for dialing the given address, if any.
+    ///
+    /// The timeout of the last protocol in the address with a configured timeout takes
+    /// precedence, as it is the outermost one, e.g. a relay circuit over TCP.
+    pub(crate) fn timeout_for(&self, address: &Multiaddr) -> Option<Duration> {
+        let per_protocol = address
+            .iter()
+            .filter_map(|protocol| self.per_protocol.get(protocol.tag()))
+            .last()
+            .copied();
+
+        per_protocol.or(self.default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outermost_protocol_timeout_applies() {
+        let mut timeouts = DialTimeouts::default();
+        timeouts.set_for_protocol("tcp", Duration::from_secs(15));
+        timeouts.set_for_protocol("quic-v1", Duration::from_secs(5));
+        timeouts.set_for_protocol("p2p-circuit", Duration::from_secs(30));
+
+        let timeout_for = |addr: &str| timeouts.timeout_for(&addr.parse().unwrap());
+
+        assert_eq!(
+            timeout_for("/ip4/127.0.0.1/tcp/1234"),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(
+            timeout_for("/ip4/127.0.0.1/udp/1234/quic-v1"),
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(
+            timeout_for(
+                "/ip4/127.0.0.1/tcp/1234/p2p/12D3KooWETLZBFBfkzvH3BQEtA1TJZPmjb4a18ss5TpwNU7DHDX6/p2p-circuit"
+            ),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(timeout_for("/ip4/127.0.0.1/udp/1234/webrtc-direct"), None);
+    }
+
+    #[test]
+    fn default_timeout_applies_without_protocol_timeout() {
+        let mut timeouts = DialTimeouts::default();
+        timeouts.set_default(Duration::from_secs(10));
+        timeouts.set_for_protocol("quic-v1", Duration::from_secs(5));
+
+        assert_eq!(
+            timeouts.timeout_for(&"/ip4/127.0.0.1/tcp/1234".parse().unwrap()),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            timeouts.timeout_for(&"/ip4/127.0.0.1/udp/1234/quic-v1".parse().unwrap()),
+            Some(Duration::from_secs(5))
+        );
+    }
+}
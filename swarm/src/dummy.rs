@@ -112,7 +112,8 @@ impl crate::handler::ConnectionHandler for ConnectionHandler {
             ConnectionEvent::AddressChange(_)
             | ConnectionEvent::ListenUpgradeError(_)
             | ConnectionEvent::LocalProtocolsChange(_)
-            | ConnectionEvent::RemoteProtocolsChange(_) => {}
+            | ConnectionEvent::RemoteProtocolsChange(_)
+            | ConnectionEvent::OutboundQueueChange(_) => {}
         }
     }
 }
@@ -0,0 +1,195 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::SwarmEvent;
+use futures::channel::mpsc;
+use futures::stream::FusedStream;
+use futures::StreamExt;
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The number of events buffered per subscription.
+const EVENT_BUFFER_SIZE: usize = 64;
+
+/// Coarse grouping of [`SwarmEvent`]s, see [`SwarmEvent::category`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum EventCategory {
+    /// Events of the [`NetworkBehaviour`](crate::NetworkBehaviour) itself.
+    Behaviour,
+    /// Events about the lifecycle and health of established and incoming connections.
+    Connection,
+    /// Events about outgoing dials.
+    Dial,
+    /// Events about listeners and their addresses.
+    Listener,
+    /// Events about external addresses of the local node and of remote peers.
+    ExternalAddress,
+}
+
+/// A stream of the [`SwarmEvent`]s matching the filter passed to
+/// [`Swarm::subscribe_events`](crate::Swarm::subscribe_events).
+///
+/// Events are only routed to the subscription while the [`Swarm`](crate::Swarm) is polled.
+/// The stream ends once the [`Swarm`](crate::Swarm) is dropped.
+pub struct EventSubscription<TBehaviourOutEvent> {
+    receiver: mpsc::Receiver<SwarmEvent<TBehaviourOutEvent>>,
+}
+
+impl<TBehaviourOutEvent> futures::Stream for EventSubscription<TBehaviourOutEvent> {
+    type Item = SwarmEvent<TBehaviourOutEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.receiver.poll_next_unpin(cx)
+    }
+}
+
+impl<TBehaviourOutEvent> FusedStream for EventSubscription<TBehaviourOutEvent> {
+    fn is_terminated(&self) -> bool {
+        self.receiver.is_terminated()
+    }
+}
+
+impl<TBehaviourOutEvent> fmt::Debug for EventSubscription<TBehaviourOutEvent> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSubscription").finish_non_exhaustive()
+    }
+}
+
+type EventFilter<TBehaviourOutEvent> = Box<dyn Fn(&SwarmEvent<TBehaviourOutEvent>) -> bool + Send>;
+
+/// The subscriptions created via [`Swarm::subscribe_events`](crate::Swarm::subscribe_events).
+pub(crate) struct EventSubscriptions<TBehaviourOutEvent> {
+    subscribers: Vec<(
+        EventFilter<TBehaviourOutEvent>,
+        mpsc::Sender<SwarmEvent<TBehaviourOutEvent>>,
+    )>,
+}
+
+impl<TBehaviourOutEvent> EventSubscriptions<TBehaviourOutEvent> {
+    pub(crate) fn new() -> Self {
+        Self {
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub(crate) fn subscribe(
+        &mut self,
+        filter: EventFilter<TBehaviourOutEvent>,
+    ) -> EventSubscription<TBehaviourOutEvent> {
+        let (sender, receiver) = mpsc::channel(EVENT_BUFFER_SIZE);
+        self.subscribers.push((filter, sender));
+        EventSubscription { receiver }
+    }
+
+    /// Hands the event to the oldest subscription whose filter matches it and that has space
+    /// left in its buffer.
+    ///
+    /// Returns the event if no subscription took it. Subscriptions that were dropped are removed.
+    pub(crate) fn route(
+        &mut self,
+        mut event: SwarmEvent<TBehaviourOutEvent>,
+    ) -> Option<SwarmEvent<TBehaviourOutEvent>> {
+        self.subscribers.retain(|(_, sender)| !sender.is_closed());
+
+        for (filter, sender) in self.subscribers.iter_mut() {
+            if !filter(&event) {
+                continue;
+            }
+            match sender.try_send(event) {
+                Ok(()) => return None,
+                Err(e) => event = e.into_inner(),
+            }
+        }
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p_core::Multiaddr;
+
+    fn external_addr_event() -> SwarmEvent<()> {
+        SwarmEvent::ExternalAddrConfirmed {
+            address: Multiaddr::empty(),
+        }
+    }
+
+    #[test]
+    fn events_are_routed_to_first_matching_subscription() {
+        let mut subscriptions = EventSubscriptions::new();
+        let mut behaviour = subscriptions.subscribe(Box::new(|e: &SwarmEvent<()>| {
+            e.category() == EventCategory::Behaviour
+        }));
+        let mut all_first = subscriptions.subscribe(Box::new(|_| true));
+        let mut all_second = subscriptions.subscribe(Box::new(|_| true));
+
+        assert!(subscriptions.route(SwarmEvent::Behaviour(())).is_none());
+        assert!(subscriptions.route(external_addr_event()).is_none());
+
+        assert!(matches!(
+            behaviour.receiver.try_next(),
+            Ok(Some(SwarmEvent::Behaviour(())))
+        ));
+        assert!(matches!(
+            all_first.receiver.try_next(),
+            Ok(Some(SwarmEvent::ExternalAddrConfirmed { .. }))
+        ));
+        assert!(all_second.receiver.try_next().is_err());
+
+        drop(all_first);
+        assert!(subscriptions.route(external_addr_event()).is_none());
+        assert!(matches!(
+            all_second.receiver.try_next(),
+            Ok(Some(SwarmEvent::ExternalAddrConfirmed { .. }))
+        ));
+
+        drop(all_second);
+        assert!(subscriptions.route(external_addr_event()).is_some());
+        assert_eq!(subscriptions.subscribers.len(), 1);
+    }
+
+    #[test]
+    fn events_are_passed_on_when_subscription_is_full() {
+        let mut subscriptions = EventSubscriptions::new();
+        let mut first = subscriptions.subscribe(Box::new(|_: &SwarmEvent<()>| true));
+
+        // The channel holds one extra message for its single sender.
+        for _ in 0..=EVENT_BUFFER_SIZE {
+            assert!(subscriptions.route(external_addr_event()).is_none());
+        }
+        assert!(subscriptions.route(external_addr_event()).is_some());
+
+        let mut second = subscriptions.subscribe(Box::new(|_| true));
+        assert!(subscriptions.route(external_addr_event()).is_none());
+        assert!(second.receiver.try_next().is_ok());
+
+        assert!(first.receiver.try_next().is_ok());
+        assert!(subscriptions.route(external_addr_event()).is_none());
+        assert_eq!(
+            (0..)
+                .map_while(|_| first.receiver.try_next().ok().flatten())
+                .count(),
+            EVENT_BUFFER_SIZE + 1
+        );
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_core::Multiaddr;
+use std::collections::HashMap;
+
+/// The maximum number of tracked external address candidates below the threshold.
+const MAX_CANDIDATES: usize = 64;
+
+/// How the confidence in external addresses of the local node is scored, see
+/// [`Config::with_external_address_scoring`](crate::Config::with_external_address_scoring).
+///
+/// Each [`ToSwarm::NewExternalAddrCandidate`](crate::ToSwarm::NewExternalAddrCandidate), e.g. an
+/// address observed by a remote via identify, adds the candidate weight to the score of the
+/// address. Each [`ToSwarm::ExternalAddrConfirmed`](crate::ToSwarm::ExternalAddrConfirmed), e.g.
+/// a successful AutoNAT probe, adds the confirmation weight. Only addresses whose score reached
+/// the threshold are confirmed and thus advertised to other peers.
+///
+/// The default scoring confirms an address with the first confirmation and never based on
+/// candidates alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressScoring {
+    threshold: u32,
+    candidate_weight: u32,
+    confirmation_weight: u32,
+}
+
+impl AddressScoring {
+    /// Creates a scoring confirming addresses with a score of at least `threshold`.
+    ///
+    /// Candidates have a weight of 1 and confirmations a weight of `threshold`.
+    pub fn new(threshold: u32) -> Self {
+        Self {
+            threshold,
+            candidate_weight: 1,
+            confirmation_weight: threshold,
+        }
+    }
+
+    /// Sets the score added by a reported candidate.
+    pub fn with_candidate_weight(mut self, weight: u32) -> Self {
+        self.candidate_weight = weight;
+        self
+    }
+
+    /// Sets the score added by a reported confirmation.
+    pub fn with_confirmation_weight(mut self, weight: u32) -> Self {
+        self.confirmation_weight = weight;
+        self
+    }
+}
+
+impl Default for AddressScoring {
+    fn default() -> Self {
+        Self::new(1).with_candidate_weight(0)
+    }
+}
+
+/// The confidence scores of the external addresses of the local node.
+#[derive(Debug)]
+pub(crate) struct ExternalAddrScores {
+    scoring: AddressScoring,
+    scores: HashMap<Multiaddr, u32>,
+}
+
+impl ExternalAddrScores {
+    pub(crate) fn new(scoring: AddressScoring) -> Self {
+        Self {
+            scoring,
+            scores: HashMap::new(),
+        }
+    }
+
+    /// Adds the weight of a candidate report.
+    ///
+    /// Returns `true` if the address just reached the threshold.
+    pub(crate) fn on_candidate(&mut self, address: &Multiaddr) -> bool {
+        let before = self.score(address);
+        let after = self.add(address, self.scoring.candidate_weight);
+
+        before < self.scoring.threshold && after >= self.scoring.threshold
+    }
+
+    /// Adds the weight of a confirmation.
+    ///
+    /// Returns `true` if the address is confirmed.
+    pub(crate) fn on_confirmation(&mut self, address: &Multiaddr) -> bool {
+        self.add(address, self.scoring.confirmation_weight) >= self.scoring.threshold
+    }
+
+    /// Raises the score of the address to the threshold, if below.
+    pub(crate) fn confirm(&mut self, address: Multiaddr) {
+        let score = self.scores.entry(address).or_default();
+        *score = (*score).max(self.scoring.threshold);
+    }
+
+    pub(crate) fn remove(&mut self, address: &Multiaddr) {
+        self.scores.remove(address);
+    }
+
+    /// Returns the addresses whose score reached the threshold.
+    pub(crate) fn confirmed(&self) -> impl Iterator<Item = &Multiaddr> {
+        self.scores
+            .iter()
+            .filter(|(_, score)| **score >= self.scoring.threshold)
+            .map(|(address, _)| address)
+    }
+
+    /// Returns all tracked addresses and their score.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&Multiaddr, u32)> {
+        self.scores.iter().map(|(address, score)| (address, *score))
+    }
+
+    fn score(&self, address: &Multiaddr) -> u32 {
+        self.scores.get(address).copied().unwrap_or_default()
+    }
+
+    fn add(&mut self, address: &Multiaddr, weight: u32) -> u32 {
+        if !self.scores.contains_key(address) {
+            self.evict_candidate();
+        }
+        let score = self.scores.entry(address.clone()).or_default();
+        *score = score.saturating_add(weight);
+        *score
+    }
+
+    /// Makes room for another candidate by removing the lowest scored one, if there are too many.
+    fn evict_candidate(&mut self) {
+        let threshold = self.scoring.threshold;
+        let candidates = self.scores.values().filter(|s| **s < threshold).count();
+        if candidates < MAX_CANDIDATES {
+            return;
+        }
+        if let Some(lowest) = self
+            .scores
+            .iter()
+            .filter(|(_, s)| **s < threshold)
+            .min_by_key(|(_, s)| **s)
+            .map(|(address, _)| address.clone())
+        {
+            self.scores.remove(&lowest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn candidates_reach_threshold() {
+        let mut scores = ExternalAddrScores::new(AddressScoring::new(3));
+        let address: Multiaddr = "/ip4/1.2.3.4/tcp/1234".parse().unwrap();
+
+        assert!(!scores.on_candidate(&address));
+        assert!(!scores.on_candidate(&address));
+        assert_eq!(scores.confirmed().count(), 0);
+        assert!(scores.on_candidate(&address));
+        assert!(!scores.on_candidate(&address));
+        assert_eq!(scores.confirmed().collect::<Vec<_>>(), vec![&address]);
+        assert_eq!(scores.iter().collect::<Vec<_>>(), vec![(&address, 4)]);
+    }
+
+    #[test]
+    fn default_scoring_only_confirms_confirmations() {
+        let mut scores = ExternalAddrScores::new(AddressScoring::default());
+        let address: Multiaddr = "/ip4/1.2.3.4/tcp/1234".parse().unwrap();
+
+        assert!(!scores.on_candidate(&address));
+        assert_eq!(scores.confirmed().count(), 0);
+        assert!(scores.on_confirmation(&address));
+        assert_eq!(scores.confirmed().count(), 1);
+    }
+
+    #[test]
+    fn lowest_candidate_is_evicted() {
+        let mut scores = ExternalAddrScores::new(AddressScoring::new(10));
+        let address = |port: u16| format!("/ip4/1.2.3.4/tcp/{port}").parse().unwrap();
+
+        scores.on_confirmation(&address(0));
+        for port in 1..=MAX_CANDIDATES as u16 {
+            scores.on_candidate(&address(port));
+            scores.on_candidate(&address(port));
+        }
+        scores.on_candidate(&address(1));
+        scores.on_candidate(&address(1000));
+
+        assert_eq!(scores.iter().count(), MAX_CANDIDATES + 1);
+        assert!(scores.iter().any(|(a, _)| a == &address(0)));
+        assert!(scores.iter().any(|(a, s)| a == &address(1000) && s == 1));
+    }
+}
@@ -47,6 +47,7 @@ mod pending;
 mod select;
 
 pub use crate::upgrade::{InboundUpgradeSend, OutboundUpgradeSend, SendWrapper, UpgradeInfoSend};
+pub use libp2p_core::muxing::StreamPriority;
 pub use map_in::MapInEvent;
 pub use map_out::MapOutEvent;
 pub use one_shot::{OneShotHandler, OneShotHandlerConfig};
@@ -226,6 +227,9 @@ pub enum ConnectionEvent<'a, IP: InboundUpgradeSend, OP: OutboundUpgradeSend, IO
     LocalProtocolsChange(ProtocolsChange<'a>),
     /// The remote [`ConnectionHandler`] now supports a different set of protocols.
     RemoteProtocolsChange(ProtocolsChange<'a>),
+    /// The number of outbound streams waiting for the limit on concurrently negotiating outbound
+    /// streams changed.
+    OutboundQueueChange(OutboundQueueChange),
 }
 
 impl<'a, IP, OP, IOI, OOI> fmt::Debug for ConnectionEvent<'a, IP, OP, IOI, OOI>
@@ -260,6 +264,9 @@ where
             ConnectionEvent::RemoteProtocolsChange(v) => {
                 f.debug_tuple("RemoteProtocolsChange").field(v).finish()
             }
+            ConnectionEvent::OutboundQueueChange(v) => {
+                f.debug_tuple("OutboundQueueChange").field(v).finish()
+            }
         }
     }
 }
@@ -270,9 +277,9 @@ impl<'a, IP: InboundUpgradeSend, OP: OutboundUpgradeSend, IOI, OOI>
     /// Whether the event concerns an outbound stream.
     pub fn is_outbound(&self) -> bool {
         match self {
-            ConnectionEvent::DialUpgradeError(_) | ConnectionEvent::FullyNegotiatedOutbound(_) => {
-                true
-            }
+            ConnectionEvent::DialUpgradeError(_)
+            | ConnectionEvent::FullyNegotiatedOutbound(_)
+            | ConnectionEvent::OutboundQueueChange(_) => true,
             ConnectionEvent::FullyNegotiatedInbound(_)
             | ConnectionEvent::AddressChange(_)
             | ConnectionEvent::LocalProtocolsChange(_)
@@ -291,7 +298,8 @@ impl<'a, IP: InboundUpgradeSend, OP: OutboundUpgradeSend, IOI, OOI>
             | ConnectionEvent::AddressChange(_)
             | ConnectionEvent::LocalProtocolsChange(_)
             | ConnectionEvent::RemoteProtocolsChange(_)
-            | ConnectionEvent::DialUpgradeError(_) => false,
+            | ConnectionEvent::DialUpgradeError(_)
+            | ConnectionEvent::OutboundQueueChange(_) => false,
         }
     }
 }
@@ -326,6 +334,17 @@ pub struct AddressChange<'a> {
     pub new_address: &'a Multiaddr,
 }
 
+/// [`ConnectionEvent`] variant that informs the handler about a change in the number of outbound
+/// streams queued because the connection reached its limit of concurrently negotiating outbound
+/// streams.
+///
+/// See [`Config::with_max_negotiating_outbound_streams`](crate::Config::with_max_negotiating_outbound_streams).
+#[derive(Debug, Clone, Copy)]
+pub struct OutboundQueueChange {
+    /// The number of outbound streams waiting to be opened.
+    pub queued: usize,
+}
+
 /// [`ConnectionEvent`] variant that informs the handler about a change in the protocols supported on the connection.
 #[derive(Debug, Clone)]
 pub enum ProtocolsChange<'a> {
@@ -468,18 +487,20 @@ pub struct SubstreamProtocol<TUpgrade, TInfo> {
     upgrade: TUpgrade,
     info: TInfo,
     timeout: Duration,
+    priority: StreamPriority,
 }
 
 impl<TUpgrade, TInfo> SubstreamProtocol<TUpgrade, TInfo> {
     /// Create a new `SubstreamProtocol` from the given upgrade.
     ///
     /// The default timeout for applying the given upgrade on a substream is
-    /// 10 seconds.
+    /// 10 seconds, and outbound substreams are opened with [`StreamPriority::Normal`].
     pub fn new(upgrade: TUpgrade, info: TInfo) -> Self {
         SubstreamProtocol {
             upgrade,
             info,
             timeout: Duration::from_secs(10),
+            priority: StreamPriority::Normal,
         }
     }
 
@@ -492,6 +513,7 @@ impl<TUpgrade, TInfo> SubstreamProtocol<TUpgrade, TInfo> {
             upgrade: f(self.upgrade),
             info: self.info,
             timeout: self.timeout,
+            priority: self.priority,
         }
     }
 
@@ -504,6 +526,7 @@ impl<TUpgrade, TInfo> SubstreamProtocol<TUpgrade, TInfo> {
             upgrade: self.upgrade,
             info: f(self.info),
             timeout: self.timeout,
+            priority: self.priority,
         }
     }
 
@@ -513,6 +536,17 @@ impl<TUpgrade, TInfo> SubstreamProtocol<TUpgrade, TInfo> {
         self
     }
 
+    /// Sets the priority hint for opening an outbound substream with this protocol.
+    ///
+    /// Requested outbound substreams with a higher priority are opened first, and the muxer may
+    /// send their data ahead of the data of other substreams, see
+    /// [`StreamMuxer::poll_outbound_with_priority`](libp2p_core::muxing::StreamMuxer::poll_outbound_with_priority).
+    /// Has no effect on inbound substreams.
+    pub fn with_priority(mut self, priority: StreamPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+
     /// Borrows the contained protocol upgrade.
     pub fn upgrade(&self) -> &TUpgrade {
         &self.upgrade
@@ -528,6 +562,11 @@ impl<TUpgrade, TInfo> SubstreamProtocol<TUpgrade, TInfo> {
         &self.timeout
     }
 
+    /// The priority hint for opening an outbound substream with this protocol.
+    pub fn priority(&self) -> StreamPriority {
+        self.priority
+    }
+
     /// Converts the substream protocol configuration into the contained upgrade.
     pub fn into_upgrade(self) -> (TUpgrade, TInfo) {
         (self.upgrade, self.info)
@@ -548,6 +587,17 @@ pub enum ConnectionHandlerEvent<TConnectionUpgrade, TOutboundOpenInfo, TCustom>
 
     /// Event that is sent to a [`NetworkBehaviour`](crate::behaviour::NetworkBehaviour).
     NotifyBehaviour(TCustom),
+
+    /// We observed the remote misbehaving.
+    ReportAnomaly(Anomaly),
+
+    /// We detected a protocol violation of the remote.
+    ///
+    /// Counts as an [`Anomaly`] of the report's class and is reported to all
+    /// [`NetworkBehaviour`](crate::behaviour::NetworkBehaviour)s via
+    /// [`FromSwarm::MisbehaviorReport`](crate::behaviour::FromSwarm::MisbehaviorReport)
+    /// and via [`SwarmEvent::ProtocolMisbehavior`](crate::SwarmEvent::ProtocolMisbehavior).
+    ReportMisbehavior(ProtocolMisbehavior),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -558,6 +608,90 @@ pub enum ProtocolSupport {
     Removed(HashSet<StreamProtocol>),
 }
 
+/// Misbehaviour of the remote observed on a connection.
+///
+/// Anomalies are aggregated per peer and reported via
+/// [`SwarmEvent::PeerAnomalyReport`](crate::SwarmEvent::PeerAnomalyReport), see
+/// [`Config::with_peer_anomaly_reports`](crate::Config::with_peer_anomaly_reports).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Anomaly {
+    /// An inbound stream failed while negotiating its protocol, e.g. because the remote reset it.
+    ///
+    /// Reported by the connection itself.
+    NegotiationFailed,
+    /// The remote violated a protocol, e.g. by sending a message it must not send.
+    ProtocolViolation,
+    /// A message sent by the remote could not be decoded.
+    DecodeError,
+}
+
+/// A protocol violation of the remote detected by a handler, see
+/// [`ConnectionHandlerEvent::ReportMisbehavior`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ProtocolMisbehavior {
+    /// The class of the violation.
+    pub class: Anomaly,
+    /// How severe the violation is.
+    pub severity: Severity,
+    /// Description of the violation, e.g. the error returned by the protocol.
+    pub details: String,
+}
+
+impl ProtocolMisbehavior {
+    /// Describes a violation of the given class and severity.
+    pub fn new(class: Anomaly, severity: Severity, details: impl Into<String>) -> Self {
+        Self {
+            class,
+            severity,
+            details: details.into(),
+        }
+    }
+
+    /// The penalty suggested for the remote, see [`Severity::suggested_penalty`].
+    pub fn suggested_penalty(&self) -> Penalty {
+        self.severity.suggested_penalty()
+    }
+}
+
+/// Severity of a [`ProtocolMisbehavior`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[non_exhaustive]
+pub enum Severity {
+    /// The violation may be caused by a bug or an incompatible implementation.
+    Low,
+    /// The violation leaves the protocol in an undefined state.
+    Medium,
+    /// The violation is likely deliberate, e.g. an attempt to exhaust resources.
+    High,
+}
+
+impl Severity {
+    /// The penalty suggested for a remote that misbehaved with this severity.
+    ///
+    /// The swarm does not enforce any penalty, behaviours like a block list can act on it.
+    pub fn suggested_penalty(&self) -> Penalty {
+        match self {
+            Severity::Low => Penalty::None,
+            Severity::Medium => Penalty::CloseConnection,
+            Severity::High => Penalty::BlockPeer,
+        }
+    }
+}
+
+/// Penalty suggested for a remote, see [`Severity::suggested_penalty`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Penalty {
+    /// Keep the connection, e.g. only count the violation.
+    None,
+    /// Close the connection the violation was detected on.
+    CloseConnection,
+    /// Close all connections to the peer and refuse new ones.
+    BlockPeer,
+}
+
 /// Event produced by a handler.
 impl<TConnectionUpgrade, TOutboundOpenInfo, TCustom>
     ConnectionHandlerEvent<TConnectionUpgrade, TOutboundOpenInfo, TCustom>
@@ -583,6 +717,12 @@ impl<TConnectionUpgrade, TOutboundOpenInfo, TCustom>
             ConnectionHandlerEvent::ReportRemoteProtocols(support) => {
                 ConnectionHandlerEvent::ReportRemoteProtocols(support)
             }
+            ConnectionHandlerEvent::ReportAnomaly(anomaly) => {
+                ConnectionHandlerEvent::ReportAnomaly(anomaly)
+            }
+            ConnectionHandlerEvent::ReportMisbehavior(misbehavior) => {
+                ConnectionHandlerEvent::ReportMisbehavior(misbehavior)
+            }
         }
     }
 
@@ -604,6 +744,12 @@ impl<TConnectionUpgrade, TOutboundOpenInfo, TCustom>
             ConnectionHandlerEvent::ReportRemoteProtocols(support) => {
                 ConnectionHandlerEvent::ReportRemoteProtocols(support)
             }
+            ConnectionHandlerEvent::ReportAnomaly(anomaly) => {
+                ConnectionHandlerEvent::ReportAnomaly(anomaly)
+            }
+            ConnectionHandlerEvent::ReportMisbehavior(misbehavior) => {
+                ConnectionHandlerEvent::ReportMisbehavior(misbehavior)
+            }
         }
     }
 
@@ -625,6 +771,12 @@ impl<TConnectionUpgrade, TOutboundOpenInfo, TCustom>
             ConnectionHandlerEvent::ReportRemoteProtocols(support) => {
                 ConnectionHandlerEvent::ReportRemoteProtocols(support)
             }
+            ConnectionHandlerEvent::ReportAnomaly(anomaly) => {
+                ConnectionHandlerEvent::ReportAnomaly(anomaly)
+            }
+            ConnectionHandlerEvent::ReportMisbehavior(misbehavior) => {
+                ConnectionHandlerEvent::ReportMisbehavior(misbehavior)
+            }
         }
     }
 }
@@ -225,6 +225,14 @@ where
                     ConnectionEvent::RemoteProtocolsChange(supported_protocols),
                 ),
             },
+            ConnectionEvent::OutboundQueueChange(change) => match self {
+                Either::Left(handler) => {
+                    handler.on_connection_event(ConnectionEvent::OutboundQueueChange(change))
+                }
+                Either::Right(handler) => {
+                    handler.on_connection_event(ConnectionEvent::OutboundQueueChange(change))
+                }
+            },
         }
     }
 }
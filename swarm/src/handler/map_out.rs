@@ -81,6 +81,12 @@ where
             ConnectionHandlerEvent::ReportRemoteProtocols(support) => {
                 ConnectionHandlerEvent::ReportRemoteProtocols(support)
             }
+            ConnectionHandlerEvent::ReportAnomaly(anomaly) => {
+                ConnectionHandlerEvent::ReportAnomaly(anomaly)
+            }
+            ConnectionHandlerEvent::ReportMisbehavior(misbehavior) => {
+                ConnectionHandlerEvent::ReportMisbehavior(misbehavior)
+            }
         })
     }
 
@@ -217,6 +217,11 @@ where
                     ));
                 }
             }
+            ConnectionEvent::OutboundQueueChange(change) => {
+                for h in self.handlers.values_mut() {
+                    h.on_connection_event(ConnectionEvent::OutboundQueueChange(change));
+                }
+            }
         }
     }
 
@@ -91,7 +91,8 @@ impl ConnectionHandler for PendingConnectionHandler {
             | ConnectionEvent::DialUpgradeError(_)
             | ConnectionEvent::ListenUpgradeError(_)
             | ConnectionEvent::LocalProtocolsChange(_)
-            | ConnectionEvent::RemoteProtocolsChange(_) => {}
+            | ConnectionEvent::RemoteProtocolsChange(_)
+            | ConnectionEvent::OutboundQueueChange(_) => {}
         }
     }
 }
@@ -234,6 +234,12 @@ where
             Poll::Ready(ConnectionHandlerEvent::ReportRemoteProtocols(support)) => {
                 return Poll::Ready(ConnectionHandlerEvent::ReportRemoteProtocols(support));
             }
+            Poll::Ready(ConnectionHandlerEvent::ReportAnomaly(anomaly)) => {
+                return Poll::Ready(ConnectionHandlerEvent::ReportAnomaly(anomaly));
+            }
+            Poll::Ready(ConnectionHandlerEvent::ReportMisbehavior(misbehavior)) => {
+                return Poll::Ready(ConnectionHandlerEvent::ReportMisbehavior(misbehavior));
+            }
             Poll::Pending => (),
         };
 
@@ -253,6 +259,12 @@ where
             Poll::Ready(ConnectionHandlerEvent::ReportRemoteProtocols(support)) => {
                 return Poll::Ready(ConnectionHandlerEvent::ReportRemoteProtocols(support));
             }
+            Poll::Ready(ConnectionHandlerEvent::ReportAnomaly(anomaly)) => {
+                return Poll::Ready(ConnectionHandlerEvent::ReportAnomaly(anomaly));
+            }
+            Poll::Ready(ConnectionHandlerEvent::ReportMisbehavior(misbehavior)) => {
+                return Poll::Ready(ConnectionHandlerEvent::ReportMisbehavior(misbehavior));
+            }
             Poll::Pending => (),
         };
 
@@ -345,6 +357,12 @@ where
                         supported_protocols,
                     ));
             }
+            ConnectionEvent::OutboundQueueChange(change) => {
+                self.proto1
+                    .on_connection_event(ConnectionEvent::OutboundQueueChange(change));
+                self.proto2
+                    .on_connection_event(ConnectionEvent::OutboundQueueChange(change));
+            }
         }
     }
 }
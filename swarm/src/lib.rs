@@ -56,7 +56,11 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
 mod connection;
+mod deferred_dial;
+mod dial_timeouts;
+mod event_subscription;
 mod executor;
+mod external_addr_scores;
 mod stream;
 mod stream_protocol;
 #[cfg(test)]
@@ -68,6 +72,10 @@ pub mod dial_opts;
 pub mod dummy;
 pub mod handler;
 mod listen_opts;
+mod peer_anomalies;
+#[cfg(feature = "poll-metrics")]
+pub mod poll_metrics;
+mod protocol_conflicts;
 
 /// Bundles all symbols required for the [`libp2p_swarm_derive::NetworkBehaviour`] macro.
 #[doc(hidden)]
@@ -88,6 +96,8 @@ pub mod derive_prelude {
     pub use crate::behaviour::NewListenAddr;
     pub use crate::behaviour::NewListener;
     pub use crate::connection::ConnectionId;
+    #[cfg(feature = "poll-metrics")]
+    pub use crate::poll_metrics;
     pub use crate::ConnectionDenied;
     pub use crate::ConnectionHandler;
     pub use crate::ConnectionHandlerSelect;
@@ -99,6 +109,8 @@ pub mod derive_prelude {
     pub use crate::ToSwarm;
     pub use either::Either;
     pub use futures::prelude as futures;
+    #[cfg(feature = "poll-metrics")]
+    pub use instant::Instant;
     pub use libp2p_core::transport::ListenerId;
     pub use libp2p_core::ConnectedPoint;
     pub use libp2p_core::Endpoint;
@@ -107,22 +119,28 @@ pub mod derive_prelude {
 }
 
 pub use behaviour::{
-    AddressChange, CloseConnection, ConnectionClosed, DialFailure, ExpiredListenAddr,
+    AddressChange, CloseConnection, ConnectionClosed, DialFailure, EventQueue, ExpiredListenAddr,
     ExternalAddrExpired, ExternalAddresses, FromSwarm, ListenAddresses, ListenFailure,
-    ListenerClosed, ListenerError, NetworkBehaviour, NewExternalAddrCandidate,
-    NewExternalAddrOfPeer, NewListenAddr, NotifyHandler, PeerAddresses, ToSwarm,
+    ListenerClosed, ListenerError, MisbehaviorReport, NetworkBehaviour, NewExternalAddrCandidate,
+    NewExternalAddrOfPeer, NewListenAddr, Notification, NotifyHandler, PeerAddresses, ToSwarm,
 };
-pub use connection::pool::ConnectionCounters;
-pub use connection::{ConnectionError, ConnectionId, SupportedProtocols};
+pub use connection::pool::{ConnectionCounters, DialLimitExceeded};
+pub use connection::{
+    ConnectionError, ConnectionId, ConnectionStats, CountingFuture, CountingStream,
+    CountingTransport, SupportedProtocols,
+};
+pub use event_subscription::{EventCategory, EventSubscription};
 pub use executor::Executor;
+pub use external_addr_scores::AddressScoring;
 pub use handler::{
     ConnectionHandler, ConnectionHandlerEvent, ConnectionHandlerSelect, OneShotHandler,
     OneShotHandlerConfig, StreamUpgradeError, SubstreamProtocol,
 };
 #[cfg(feature = "macros")]
 pub use libp2p_swarm_derive::NetworkBehaviour;
-pub use listen_opts::ListenOpts;
-pub use stream::Stream;
+pub use listen_opts::{ListenOpts, ListenerDetails};
+pub use protocol_conflicts::{ProtocolConflict, ProtocolConflicts};
+pub use stream::{Stream, DEFAULT_PREFERRED_WRITE_SIZE};
 pub use stream_protocol::{InvalidProtocol, StreamProtocol};
 
 use crate::behaviour::ExternalAddrConfirmed;
@@ -132,20 +150,30 @@ use connection::IncomingInfo;
 use connection::{
     PendingConnectionError, PendingInboundConnectionError, PendingOutboundConnectionError,
 };
+use deferred_dial::DeferredDials;
 use dial_opts::{DialOpts, PeerCondition};
+use dial_timeouts::DialTimeouts;
+use event_subscription::EventSubscriptions;
+use external_addr_scores::ExternalAddrScores;
 use futures::{prelude::*, stream::FusedStream};
+use futures_timer::Delay;
+use handler::{Anomaly, ProtocolMisbehavior};
+use instant::Instant;
 use libp2p_core::{
     connection::ConnectedPoint,
-    muxing::StreamMuxerBox,
+    multiaddr::Protocol,
+    muxing::{StreamMuxerBox, StreamMuxerStats},
     transport::{self, ListenerId, TransportError, TransportEvent},
     Endpoint, Multiaddr, Transport,
 };
 use libp2p_identity::PeerId;
+use peer_anomalies::PeerAnomalies;
 use smallvec::SmallVec;
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::num::{NonZeroU32, NonZeroU8, NonZeroUsize};
 use std::time::Duration;
 use std::{
+    any::Any,
     error, fmt, io,
     pin::Pin,
     task::{Context, Poll},
@@ -300,6 +328,49 @@ pub enum SwarmEvent<TBehaviourOutEvent> {
     ExternalAddrExpired { address: Multiaddr },
     /// We have discovered a new address of a peer.
     NewExternalAddrOfPeer { peer_id: PeerId, address: Multiaddr },
+    /// A behaviour wrapped in [`CatchPanic`](behaviour::catch_panic::CatchPanic) panicked and has
+    /// been disabled.
+    BehaviourPanicked {
+        /// The name the behaviour was registered with.
+        name: &'static str,
+    },
+    /// A dial scheduled via [`Swarm::dial_after`] or [`ToSwarm::DialAfter`] is due and is being
+    /// started.
+    ///
+    /// If starting the dial fails, an [`OutgoingConnectionError`](SwarmEvent::OutgoingConnectionError)
+    /// event is reported.
+    DeferredDialFired {
+        /// Identity of the peer that is dialed, if known.
+        peer_id: Option<PeerId>,
+        /// Identifier of the connection.
+        connection_id: ConnectionId,
+    },
+    /// A dial scheduled via [`Swarm::dial_after`] or [`ToSwarm::DialAfter`] was cancelled.
+    DeferredDialCancelled {
+        /// Identity of the peer that would have been dialed, if known.
+        peer_id: Option<PeerId>,
+        /// Identifier of the connection.
+        connection_id: ConnectionId,
+    },
+    /// A peer misbehaved at least as often as configured via
+    /// [`Config::with_peer_anomaly_reports`] within the last reporting interval.
+    PeerAnomalyReport {
+        /// Identity of the peer.
+        peer_id: PeerId,
+        /// The number of each kind of [`Anomaly`] observed on the connections to the peer within
+        /// the interval.
+        anomalies: HashMap<Anomaly, u32>,
+    },
+    /// A handler detected a protocol violation of a peer, see
+    /// [`ConnectionHandlerEvent::ReportMisbehavior`].
+    ProtocolMisbehavior {
+        /// Identity of the peer.
+        peer_id: PeerId,
+        /// Identifier of the connection the violation was detected on.
+        connection_id: ConnectionId,
+        /// The reported violation.
+        misbehavior: ProtocolMisbehavior,
+    },
 }
 
 impl<TBehaviourOutEvent> SwarmEvent<TBehaviourOutEvent> {
@@ -311,6 +382,42 @@ impl<TBehaviourOutEvent> SwarmEvent<TBehaviourOutEvent> {
             other => Err(other),
         }
     }
+
+    /// Returns the [`EventCategory`] of this event.
+    pub fn category(&self) -> EventCategory {
+        match self {
+            SwarmEvent::Behaviour(_) | SwarmEvent::BehaviourPanicked { .. } => {
+                EventCategory::Behaviour
+            }
+            SwarmEvent::ConnectionEstablished { .. }
+            | SwarmEvent::ConnectionClosed { .. }
+            | SwarmEvent::IncomingConnection { .. }
+            | SwarmEvent::IncomingConnectionError { .. }
+            | SwarmEvent::PeerAnomalyReport { .. }
+            | SwarmEvent::ProtocolMisbehavior { .. } => EventCategory::Connection,
+            SwarmEvent::Dialing { .. }
+            | SwarmEvent::OutgoingConnectionError { .. }
+            | SwarmEvent::DeferredDialFired { .. }
+            | SwarmEvent::DeferredDialCancelled { .. } => EventCategory::Dial,
+            SwarmEvent::NewListenAddr { .. }
+            | SwarmEvent::ExpiredListenAddr { .. }
+            | SwarmEvent::ListenerClosed { .. }
+            | SwarmEvent::ListenerError { .. } => EventCategory::Listener,
+            SwarmEvent::NewExternalAddrCandidate { .. }
+            | SwarmEvent::ExternalAddrConfirmed { .. }
+            | SwarmEvent::ExternalAddrExpired { .. }
+            | SwarmEvent::NewExternalAddrOfPeer { .. } => EventCategory::ExternalAddress,
+        }
+    }
+}
+
+/// The result of [`Swarm::shutdown`].
+#[derive(Debug)]
+pub struct ShutdownOutcome<TBehaviourOutEvent> {
+    /// Whether all connections were closed before the timeout.
+    pub completed: bool,
+    /// The events emitted during the shutdown, in order.
+    pub events: Vec<SwarmEvent<TBehaviourOutEvent>>,
 }
 
 /// Contains the state of the network, plus the way it should behave.
@@ -337,17 +444,55 @@ where
     /// List of protocols that the behaviour says it supports.
     supported_protocols: SmallVec<[Vec<u8>; 16]>,
 
-    confirmed_external_addr: HashSet<Multiaddr>,
+    /// Confidence scores of external addresses, see [`Config::with_external_address_scoring`].
+    external_addr_scores: ExternalAddrScores,
 
     /// Multiaddresses that our listeners are listening on,
     listened_addrs: HashMap<ListenerId, SmallVec<[Multiaddr; 1]>>,
 
+    /// Details of our listeners, apart from their addresses which are in `listened_addrs`.
+    listener_details: HashMap<ListenerId, ListenerDetails>,
+
     /// Pending event to be delivered to connection handlers
     /// (or dropped if the peer disconnected) before the `behaviour`
     /// can be polled again.
     pending_handler_event: Option<(PeerId, PendingNotifyHandler, THandlerInEvent<TBehaviour>)>,
 
     pending_swarm_events: VecDeque<SwarmEvent<TBehaviour::ToSwarm>>,
+
+    /// Dials scheduled via [`Swarm::dial_after`] and [`ToSwarm::DialAfter`].
+    deferred_dials: DeferredDials,
+
+    /// The timeouts for dialing a single address.
+    dial_timeouts: DialTimeouts,
+
+    /// Tags attached to established connections via [`Swarm::tag_connection`].
+    connection_tags: HashMap<ConnectionId, (PeerId, HashSet<String>)>,
+
+    /// See [`Config::with_behaviour_backpressure`].
+    behaviour_backpressure: bool,
+
+    /// See [`Config::with_poll_time_budget`].
+    poll_time_budget: Option<Duration>,
+
+    /// The source of the current time for the poll time budget.
+    clock: fn() -> Instant,
+
+    /// The time spent polling the fields of the behaviour.
+    #[cfg(feature = "poll-metrics")]
+    poll_metrics: poll_metrics::PollMetrics,
+
+    /// Anomalies observed per peer, see [`Config::with_peer_anomaly_reports`].
+    peer_anomalies: PeerAnomalies,
+
+    /// Subscriptions to filtered events, see [`Swarm::subscribe_events`].
+    event_subscriptions: EventSubscriptions<TBehaviour::ToSwarm>,
+
+    /// See [`Config::with_protocol_conflicts`].
+    protocol_conflicts: ProtocolConflicts,
+
+    /// Protocol conflicts that have already been logged.
+    logged_protocol_conflicts: HashSet<String>,
 }
 
 impl<TBehaviour> Unpin for Swarm<TBehaviour> where TBehaviour: NetworkBehaviour {}
@@ -372,13 +517,42 @@ where
             pool: Pool::new(local_peer_id, config.pool_config),
             behaviour,
             supported_protocols: Default::default(),
-            confirmed_external_addr: Default::default(),
+            external_addr_scores: ExternalAddrScores::new(config.address_scoring),
             listened_addrs: HashMap::new(),
+            listener_details: HashMap::new(),
             pending_handler_event: None,
             pending_swarm_events: VecDeque::default(),
+            deferred_dials: DeferredDials::default(),
+            dial_timeouts: config.dial_timeouts,
+            connection_tags: HashMap::new(),
+            behaviour_backpressure: config.behaviour_backpressure,
+            poll_time_budget: config.poll_time_budget,
+            clock: Instant::now,
+            #[cfg(feature = "poll-metrics")]
+            poll_metrics: Default::default(),
+            peer_anomalies: PeerAnomalies::new(config.peer_anomaly_reports),
+            event_subscriptions: EventSubscriptions::new(),
+            protocol_conflicts: config.protocol_conflicts,
+            logged_protocol_conflicts: HashSet::new(),
         }
     }
 
+    /// Returns the time spent polling every field of the behaviour so far, sorted by the total
+    /// time in descending order.
+    ///
+    /// Only behaviours composed via the [`NetworkBehaviour`] derive macro are measured.
+    #[cfg(feature = "poll-metrics")]
+    pub fn poll_metrics(&self) -> Vec<poll_metrics::PollStats> {
+        self.poll_metrics.snapshot()
+    }
+
+    /// Discards the measurements returned by [`Swarm::poll_metrics`], e.g. to only capture the
+    /// time spent within an interval.
+    #[cfg(feature = "poll-metrics")]
+    pub fn reset_poll_metrics(&mut self) {
+        self.poll_metrics.reset();
+    }
+
     /// Returns information about the connections underlying the [`Swarm`].
     pub fn network_info(&self) -> NetworkInfo {
         let num_peers = self.pool.num_peers();
@@ -389,6 +563,25 @@ where
         }
     }
 
+    /// Returns a stream of the [`SwarmEvent`]s for which `filter` returns `true`.
+    ///
+    /// Matching events are delivered to the oldest live subscription whose filter accepts them
+    /// instead of being returned by the [`Swarm`] itself, so subsystems can each consume their
+    /// share of the events, e.g. by filtering on [`SwarmEvent::category`] with
+    /// `|event| event.category() == EventCategory::Connection`.
+    ///
+    /// Each subscription buffers a bounded number of events. While its buffer is full, matching
+    /// events are handed to the next matching subscription or returned by the [`Swarm`].
+    ///
+    /// Note that the [`Swarm`] still needs to be polled for events to reach the subscriptions.
+    /// Dropping the returned [`EventSubscription`] ends the subscription.
+    pub fn subscribe_events(
+        &mut self,
+        filter: impl Fn(&SwarmEvent<TBehaviour::ToSwarm>) -> bool + Send + 'static,
+    ) -> EventSubscription<TBehaviour::ToSwarm> {
+        self.event_subscriptions.subscribe(Box::new(filter))
+    }
+
     /// Starts listening on the given address.
     /// Returns an error if the address is not supported.
     ///
@@ -409,6 +602,21 @@ where
         self.transport.remove_listener(listener_id)
     }
 
+    /// Remove some listener gracefully.
+    ///
+    /// The listener stops accepting new connections right away and is reported as
+    /// [`SwarmEvent::ListenerClosed`], but connections sharing resources of the listener
+    /// are given up to `timeout` to complete instead of being aborted.
+    ///
+    /// See [`Transport::remove_listener_graceful`] for details.
+    ///
+    /// Returns `true` if there was a listener with this ID, `false`
+    /// otherwise.
+    pub fn remove_listener_graceful(&mut self, listener_id: ListenerId, timeout: Duration) -> bool {
+        self.transport
+            .remove_listener_graceful(listener_id, timeout)
+    }
+
     /// Dial a known or unknown peer.
     ///
     /// See also [`DialOpts`].
@@ -437,8 +645,72 @@ where
     /// # }
     /// ```
     pub fn dial(&mut self, opts: impl Into<DialOpts>) -> Result<(), DialError> {
-        let dial_opts = opts.into();
+        self.dial_now(opts.into())
+    }
+
+    /// Schedules a dial to be started after the given delay.
+    ///
+    /// Returns the [`ConnectionId`] of the dial, which can be used to cancel it via
+    /// [`Swarm::cancel_deferred_dial`] before it is started. Once the dial is due,
+    /// [`SwarmEvent::DeferredDialFired`] is reported and the dial proceeds as with [`Swarm::dial`].
+    ///
+    /// Scheduling a dial with the [`ConnectionId`] of an already scheduled dial replaces the latter.
+    pub fn dial_after(&mut self, opts: impl Into<DialOpts>, delay: Duration) -> ConnectionId {
+        self.dial_at(opts, Instant::now() + delay)
+    }
+
+    /// Schedules a dial to be started at the given point in time.
+    ///
+    /// See [`Swarm::dial_after`].
+    pub fn dial_at(&mut self, opts: impl Into<DialOpts>, at: Instant) -> ConnectionId {
+        let opts = opts.into();
+        let connection_id = opts.connection_id();
+        self.deferred_dials.schedule(opts, at);
+        connection_id
+    }
+
+    /// Cancels a dial scheduled via [`Swarm::dial_after`] or [`Swarm::dial_at`].
+    ///
+    /// Returns `true` and reports [`SwarmEvent::DeferredDialCancelled`] if the dial was still
+    /// pending, `false` otherwise.
+    pub fn cancel_deferred_dial(&mut self, connection_id: ConnectionId) -> bool {
+        let Some(opts) = self.deferred_dials.cancel(connection_id) else {
+            return false;
+        };
+        self.pending_swarm_events
+            .push_back(SwarmEvent::DeferredDialCancelled {
+                peer_id: opts.get_peer_id(),
+                connection_id,
+            });
+        true
+    }
+
+    /// Starts a deferred dial that is due.
+    fn handle_deferred_dial(&mut self, opts: DialOpts) {
+        let peer_id = opts.get_peer_id();
+        let connection_id = opts.connection_id();
+        self.pending_swarm_events
+            .push_back(SwarmEvent::DeferredDialFired {
+                peer_id,
+                connection_id,
+            });
+        match self.dial_now(opts) {
+            Ok(()) => self.pending_swarm_events.push_back(SwarmEvent::Dialing {
+                peer_id,
+                connection_id,
+            }),
+            Err(error) => {
+                self.pending_swarm_events
+                    .push_back(SwarmEvent::OutgoingConnectionError {
+                        peer_id,
+                        connection_id,
+                        error,
+                    })
+            }
+        }
+    }
 
+    fn dial_now(&mut self, mut dial_opts: DialOpts) -> Result<(), DialError> {
         let peer_id = dial_opts.get_peer_id();
         let condition = dial_opts.peer_condition();
         let connection_id = dial_opts.connection_id();
@@ -466,6 +738,21 @@ where
             return Err(e);
         }
 
+        if let Err(exceeded) = peer_id.map_or(Ok(()), |peer| self.pool.check_dial_limits(peer)) {
+            let error = DialError::Denied {
+                cause: ConnectionDenied::new(exceeded),
+            };
+
+            self.behaviour
+                .on_swarm_event(FromSwarm::DialFailure(DialFailure {
+                    peer_id,
+                    error: &error,
+                    connection_id,
+                }));
+
+            return Err(error);
+        }
+
         let addresses = {
             let mut addresses_from_opts = dial_opts.get_addresses();
 
@@ -510,6 +797,23 @@ where
                     && unique_addresses.insert(addr.clone())
             });
 
+            if dial_opts.dial_stagger_delay().is_some() {
+                // Race the most preferable addresses first: QUIC before other transports and
+                // IPv6 before IPv4.
+                addresses_from_opts.sort_by_key(|addr| {
+                    let is_quic = addr
+                        .iter()
+                        .any(|p| matches!(p, Protocol::QuicV1 | Protocol::Quic));
+                    let is_ip6 = matches!(
+                        addr.iter().next(),
+                        Some(Protocol::Ip6(_) | Protocol::Dns6(_))
+                    );
+                    (!is_quic, !is_ip6)
+                });
+            }
+
+            let addresses_from_opts = dial_opts.rank_addresses(addresses_from_opts);
+
             if addresses_from_opts.is_empty() {
                 let error = DialError::NoAddresses;
                 self.behaviour
@@ -524,6 +828,7 @@ where
             addresses_from_opts
         };
 
+        let dial_timeout_override = dial_opts.dial_timeout_override();
         let dials = addresses
             .into_iter()
             .map(|a| match peer_id.map_or(Ok(a.clone()), |p| a.with_p2p(p)) {
@@ -541,10 +846,27 @@ where
                     span.follows_from(tracing::Span::current());
 
                     match dial {
-                        Ok(fut) => fut
-                            .map(|r| (address, r.map_err(TransportError::Other)))
+                        Ok(fut) => {
+                            let timeout = dial_timeout_override
+                                .or_else(|| self.dial_timeouts.timeout_for(&address));
+                            async move {
+                                let result = match timeout {
+                                    Some(timeout) => {
+                                        match future::select(fut, Delay::new(timeout)).await {
+                                            future::Either::Left((result, _)) => result,
+                                            future::Either::Right(_) => Err(io::Error::new(
+                                                io::ErrorKind::TimedOut,
+                                                "Dial timed out",
+                                            )),
+                                        }
+                                    }
+                                    None => fut.await,
+                                };
+                                (address, result.map_err(TransportError::Other))
+                            }
                             .instrument(span)
-                            .boxed(),
+                            .boxed()
+                        }
                         Err(err) => futures::future::ready((address, Err(err))).boxed(),
                     }
                 }
@@ -561,6 +883,7 @@ where
             peer_id,
             dial_opts.role_override(),
             dial_opts.dial_concurrency_override(),
+            dial_opts.dial_stagger_delay(),
             connection_id,
         );
 
@@ -572,6 +895,51 @@ where
         self.listened_addrs.values().flatten()
     }
 
+    /// Returns the details of each listener: the address it was requested to listen on, the
+    /// addresses it is bound to and the errors it reported.
+    pub fn listeners_detailed(&self) -> impl Iterator<Item = ListenerDetails> + '_ {
+        self.listener_details
+            .values()
+            .map(|details| ListenerDetails {
+                addresses: self
+                    .listened_addrs
+                    .get(&details.id)
+                    .map(|addrs| addrs.to_vec())
+                    .unwrap_or_default(),
+                ..details.clone()
+            })
+    }
+
+    /// Replaces a listener with a new one on the address it was requested to listen on,
+    /// e.g. after it was closed by an error.
+    ///
+    /// If the listener is still active it is removed first, thus the new listener may fail to
+    /// bind to a fixed port that is not yet released.
+    ///
+    /// Returns the [`ListenerId`] of the new listener.
+    pub fn relisten(
+        &mut self,
+        listener_id: ListenerId,
+    ) -> Result<ListenerId, TransportError<io::Error>> {
+        let Some(details) = self.listener_details.remove(&listener_id) else {
+            return Err(TransportError::Other(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Unknown listener",
+            )));
+        };
+        if !details.is_closed {
+            self.transport.remove_listener(listener_id);
+        }
+
+        self.listen_on(details.requested_address.clone())
+            .map_err(|e| {
+                if details.is_closed {
+                    self.listener_details.insert(listener_id, details);
+                }
+                e
+            })
+    }
+
     /// Returns the peer ID of the swarm passed as parameter.
     pub fn local_peer_id(&self) -> &PeerId {
         &self.local_peer_id
@@ -579,7 +947,15 @@ where
 
     /// List all **confirmed** external address for the local node.
     pub fn external_addresses(&self) -> impl Iterator<Item = &Multiaddr> {
-        self.confirmed_external_addr.iter()
+        self.external_addr_scores.confirmed()
+    }
+
+    /// List all external address candidates and confirmed external addresses of the local node
+    /// with their confidence score.
+    ///
+    /// See [`Config::with_external_address_scoring`].
+    pub fn external_addresses_scored(&self) -> impl Iterator<Item = (&Multiaddr, u32)> {
+        self.external_addr_scores.iter()
     }
 
     fn add_listener(&mut self, opts: ListenOpts) -> Result<(), TransportError<io::Error>> {
@@ -596,6 +972,16 @@ where
             return Err(e);
         }
 
+        self.listener_details.insert(
+            listener_id,
+            ListenerDetails {
+                id: listener_id,
+                requested_address: addr.clone(),
+                addresses: Vec::new(),
+                last_error: None,
+                is_closed: false,
+            },
+        );
         self.behaviour
             .on_swarm_event(FromSwarm::NewListener(behaviour::NewListener {
                 listener_id,
@@ -607,13 +993,14 @@ where
     /// Add a **confirmed** external address for the local node.
     ///
     /// This function should only be called with addresses that are guaranteed to be reachable.
+    /// The address is confirmed regardless of its confidence score.
     /// The address is broadcast to all [`NetworkBehaviour`]s via [`FromSwarm::ExternalAddrConfirmed`].
     pub fn add_external_address(&mut self, a: Multiaddr) {
         self.behaviour
             .on_swarm_event(FromSwarm::ExternalAddrConfirmed(ExternalAddrConfirmed {
                 addr: &a,
             }));
-        self.confirmed_external_addr.insert(a);
+        self.external_addr_scores.confirm(a);
     }
 
     /// Remove an external address for the local node.
@@ -622,7 +1009,7 @@ where
     pub fn remove_external_address(&mut self, addr: &Multiaddr) {
         self.behaviour
             .on_swarm_event(FromSwarm::ExternalAddrExpired(ExternalAddrExpired { addr }));
-        self.confirmed_external_addr.remove(addr);
+        self.external_addr_scores.remove(addr);
     }
 
     /// Add a new external address of a remote peer.
@@ -636,6 +1023,18 @@ where
             }))
     }
 
+    /// Broadcast an application-defined notification to all [`NetworkBehaviour`]s via [`FromSwarm::Notification`].
+    ///
+    /// This allows behaviours to adapt to changes of the application state, e.g. entering a
+    /// low-power mode, without a dedicated channel to each of them. Behaviours that don't
+    /// know the type `T` ignore the notification.
+    pub fn notify_behaviours<T: Any>(&mut self, notification: T) {
+        self.behaviour
+            .on_swarm_event(FromSwarm::Notification(Notification {
+                event: &notification,
+            }))
+    }
+
     /// Disconnects a peer by its peer ID, closing all connections to said peer.
     ///
     /// Returns `Ok(())` if there was one or more established connections to the peer.
@@ -672,6 +1071,163 @@ where
         false
     }
 
+    /// Shuts the swarm down gracefully.
+    ///
+    /// All listeners are removed and the behaviours are informed via
+    /// [`NetworkBehaviour::on_shutdown`]. The swarm is then driven until the behaviours are idle,
+    /// giving them the chance to send final messages to their peers. Afterwards the behaviours
+    /// are informed via [`FromSwarm::Shutdown`] and all connections are closed, polling
+    /// [`ConnectionHandler::poll_close`] to completion and closing the underlying muxer.
+    /// Connections established in the meantime, e.g. dials still in flight, are closed as well.
+    /// The swarm is driven until no connection is left or `timeout` elapsed, whatever comes
+    /// first.
+    ///
+    /// Returns the events emitted during the shutdown and whether all connections were closed
+    /// before the timeout.
+    pub async fn shutdown(mut self, timeout: Duration) -> ShutdownOutcome<TBehaviour::ToSwarm> {
+        tracing::debug!(?timeout, "Shutting down swarm");
+
+        let mut timeout = Delay::new(timeout);
+        let mut events = Vec::new();
+
+        let listeners = self
+            .listener_details
+            .keys()
+            .chain(self.listened_addrs.keys())
+            .copied()
+            .collect::<HashSet<_>>();
+        for listener_id in listeners {
+            self.transport.remove_listener(listener_id);
+        }
+        self.behaviour.on_shutdown();
+
+        // Drive the swarm until the behaviours are idle and their events reached the handlers.
+        let flush = future::poll_fn(|cx| loop {
+            match self.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) => events.push(event),
+                Poll::Ready(None) => unreachable!("Swarm never terminates"),
+                Poll::Pending if self.pending_handler_event.is_some() => return Poll::Pending,
+                Poll::Pending => return self.pool.poll_handlers_drained(cx),
+            }
+        });
+        futures::pin_mut!(flush);
+        if let future::Either::Right(_) = future::select(flush, &mut timeout).await {
+            tracing::debug!("Behaviours did not finish before swarm shutdown timed out");
+        }
+
+        self.behaviour.on_swarm_event(FromSwarm::Shutdown);
+
+        let peers = self.pool.iter_connected().copied().collect::<Vec<_>>();
+        for peer in peers {
+            self.pool.disconnect(peer);
+        }
+
+        let drain = future::poll_fn(|cx| loop {
+            let counters = self.pool.counters();
+            if counters.num_established() == 0 && counters.num_pending() == 0 {
+                return Poll::Ready(());
+            }
+            match self.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) => {
+                    if let SwarmEvent::ConnectionEstablished { connection_id, .. } = event {
+                        self.close_connection(connection_id);
+                    }
+                    events.push(event);
+                }
+                Poll::Ready(None) => unreachable!("Swarm never terminates"),
+                Poll::Pending => return Poll::Pending,
+            }
+        });
+        futures::pin_mut!(drain);
+
+        let completed = match future::select(drain, timeout).await {
+            future::Either::Left(((), _)) => true,
+            future::Either::Right(((), _)) => {
+                tracing::debug!("Swarm shutdown timed out");
+                false
+            }
+        };
+
+        ShutdownOutcome { completed, events }
+    }
+
+    /// Attaches a tag to an established connection, e.g. `"relay"` or `"bootstrap"`.
+    ///
+    /// Tags allow applications to group connections, see [`Swarm::connections_with_tag`], in
+    /// order to apply policies to them, e.g. never close tagged connections when pruning.
+    /// They are discarded once the connection closes.
+    ///
+    /// Returns `false` if the connection is not established.
+    pub fn tag_connection(&mut self, connection_id: ConnectionId, tag: impl Into<String>) -> bool {
+        let Some(peer_id) = self.pool.established_peer(connection_id) else {
+            return false;
+        };
+        self.connection_tags
+            .entry(connection_id)
+            .or_insert_with(|| (peer_id, HashSet::new()))
+            .1
+            .insert(tag.into());
+        true
+    }
+
+    /// Removes a tag from a connection.
+    ///
+    /// Returns `true` if the connection was tagged with it.
+    pub fn untag_connection(&mut self, connection_id: ConnectionId, tag: &str) -> bool {
+        let Some((_, tags)) = self.connection_tags.get_mut(&connection_id) else {
+            return false;
+        };
+        let removed = tags.remove(tag);
+        if tags.is_empty() {
+            self.connection_tags.remove(&connection_id);
+        }
+        removed
+    }
+
+    /// Returns the tags of a connection.
+    pub fn connection_tags(&self, connection_id: ConnectionId) -> impl Iterator<Item = &str> {
+        self.connection_tags
+            .get(&connection_id)
+            .into_iter()
+            .flat_map(|(_, tags)| tags.iter().map(String::as_str))
+    }
+
+    /// Returns the peers and IDs of all connections tagged with the given tag.
+    pub fn connections_with_tag<'a>(
+        &'a self,
+        tag: &'a str,
+    ) -> impl Iterator<Item = (PeerId, ConnectionId)> + 'a {
+        self.connection_tags
+            .iter()
+            .filter(move |(_, (_, tags))| tags.contains(tag))
+            .map(|(connection_id, (peer_id, _))| (*peer_id, *connection_id))
+    }
+
+    /// Returns the number of bytes transferred over an established connection.
+    ///
+    /// Only connections of a transport wrapped in [`CountingTransport`] are counted.
+    /// Returns `None` if the connection is not established.
+    pub fn connection_stats(&self, connection_id: ConnectionId) -> Option<ConnectionStats> {
+        self.pool.connection_stats(connection_id)
+    }
+
+    /// Returns the number of bytes transferred over all established connections to a peer.
+    ///
+    /// See [`Swarm::connection_stats`]. Connections that are already closed are not taken into account.
+    /// Returns `None` if the peer is not connected.
+    pub fn peer_stats(&self, peer_id: &PeerId) -> Option<ConnectionStats> {
+        self.pool.peer_stats(peer_id)
+    }
+
+    /// Returns the statistics of the stream muxer of an established connection, e.g. the
+    /// number of open streams and the bytes transferred over each of them.
+    ///
+    /// Returns `None` if the connection is not established or its muxer does not keep any
+    /// statistics, see [`StreamMuxer::counters`](libp2p_core::StreamMuxer::counters).
+    pub fn muxer_stats(&self, connection_id: ConnectionId) -> Option<StreamMuxerStats> {
+        self.pool.muxer_stats(connection_id)
+    }
+
     /// Checks whether there is an established connection to a peer.
     pub fn is_connected(&self, peer_id: &PeerId) -> bool {
         self.pool.is_connected(*peer_id)
@@ -771,12 +1327,17 @@ where
                     }
                 };
 
-                let supported_protocols = handler
+                let supported_protocols: SmallVec<[Vec<u8>; 16]> = handler
                     .listen_protocol()
                     .upgrade()
                     .protocol_info()
                     .map(|p| p.as_ref().as_bytes().to_vec())
                     .collect();
+                if let Err(cause) = self.check_protocol_conflicts(&supported_protocols) {
+                    self.deny_established_connection(id, peer_id, &endpoint, cause);
+                    return;
+                }
+
                 let other_established_connection_ids = self
                     .pool
                     .iter_established_connections_of_peer(&peer_id)
@@ -899,6 +1460,7 @@ where
                 }
                 let peer_id = connected.peer_id;
                 let endpoint = connected.endpoint;
+                self.connection_tags.remove(&id);
                 let num_established =
                     u32::try_from(remaining_established_connection_ids.len()).unwrap();
 
@@ -922,6 +1484,41 @@ where
                 self.behaviour
                     .on_connection_handler_event(peer_id, id, event);
             }
+            PoolEvent::Anomaly {
+                peer_id,
+                id,
+                anomaly,
+            } => {
+                tracing::debug!(peer=%peer_id, connection=%id, ?anomaly, "Remote misbehaved");
+                self.peer_anomalies.record(peer_id, anomaly);
+            }
+            PoolEvent::Misbehavior {
+                peer_id,
+                id,
+                misbehavior,
+            } => {
+                tracing::debug!(
+                    peer=%peer_id,
+                    connection=%id,
+                    class=?misbehavior.class,
+                    severity=?misbehavior.severity,
+                    details=%misbehavior.details,
+                    "Remote violated a protocol"
+                );
+                self.peer_anomalies.record(peer_id, misbehavior.class);
+                self.behaviour
+                    .on_swarm_event(FromSwarm::MisbehaviorReport(MisbehaviorReport {
+                        peer_id,
+                        connection_id: id,
+                        misbehavior: &misbehavior,
+                    }));
+                self.pending_swarm_events
+                    .push_back(SwarmEvent::ProtocolMisbehavior {
+                        peer_id,
+                        connection_id: id,
+                        misbehavior,
+                    });
+            }
             PoolEvent::AddressChange {
                 peer_id,
                 id,
@@ -939,6 +1536,81 @@ where
         }
     }
 
+    /// Applies the [`ProtocolConflicts`] policy to the protocols of a new connection's handler.
+    fn check_protocol_conflicts(&mut self, protocols: &[Vec<u8>]) -> Result<(), ConnectionDenied> {
+        if self.protocol_conflicts == ProtocolConflicts::PreferFirst {
+            return Ok(());
+        }
+        let Some(conflict) =
+            protocol_conflicts::find_conflicts(protocols.iter().map(|p| p.as_slice()))
+        else {
+            return Ok(());
+        };
+
+        match self.protocol_conflicts {
+            ProtocolConflicts::Deny => Err(ConnectionDenied::new(conflict)),
+            _ => {
+                for protocol in conflict.protocols() {
+                    if self.logged_protocol_conflicts.insert(protocol.clone()) {
+                        tracing::warn!(
+                            %protocol,
+                            "Protocol is claimed by multiple handlers, inbound streams are routed to the first one"
+                        );
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Reports an established connection that is denied after the [`NetworkBehaviour`] created
+    /// its handler as failed.
+    fn deny_established_connection(
+        &mut self,
+        id: ConnectionId,
+        peer_id: PeerId,
+        endpoint: &ConnectedPoint,
+        cause: ConnectionDenied,
+    ) {
+        match endpoint {
+            ConnectedPoint::Dialer { .. } => {
+                let dial_error = DialError::Denied { cause };
+                self.behaviour
+                    .on_swarm_event(FromSwarm::DialFailure(DialFailure {
+                        connection_id: id,
+                        error: &dial_error,
+                        peer_id: Some(peer_id),
+                    }));
+                self.pending_swarm_events
+                    .push_back(SwarmEvent::OutgoingConnectionError {
+                        peer_id: Some(peer_id),
+                        connection_id: id,
+                        error: dial_error,
+                    });
+            }
+            ConnectedPoint::Listener {
+                local_addr,
+                send_back_addr,
+            } => {
+                let listen_error = ListenError::Denied { cause };
+                self.behaviour
+                    .on_swarm_event(FromSwarm::ListenFailure(ListenFailure {
+                        local_addr,
+                        send_back_addr,
+                        error: &listen_error,
+                        connection_id: id,
+                    }));
+                self.pending_swarm_events
+                    .push_back(SwarmEvent::IncomingConnectionError {
+                        connection_id: id,
+                        local_addr: local_addr.clone(),
+                        send_back_addr: send_back_addr.clone(),
+                        error: listen_error,
+                    });
+            }
+        }
+    }
+
     fn handle_transport_event(
         &mut self,
         event: TransportEvent<
@@ -1056,6 +1728,17 @@ where
                     "Listener closed"
                 );
                 let addrs = self.listened_addrs.remove(&listener_id).unwrap_or_default();
+                match &reason {
+                    Ok(()) => {
+                        self.listener_details.remove(&listener_id);
+                    }
+                    Err(error) => {
+                        if let Some(details) = self.listener_details.get_mut(&listener_id) {
+                            details.last_error = Some(error.to_string());
+                            details.is_closed = true;
+                        }
+                    }
+                }
                 for addr in addrs.iter() {
                     self.behaviour.on_swarm_event(FromSwarm::ExpiredListenAddr(
                         ExpiredListenAddr { listener_id, addr },
@@ -1074,6 +1757,9 @@ where
                     })
             }
             TransportEvent::ListenerError { listener_id, error } => {
+                if let Some(details) = self.listener_details.get_mut(&listener_id) {
+                    details.last_error = Some(error.to_string());
+                }
                 self.behaviour
                     .on_swarm_event(FromSwarm::ListenerError(ListenerError {
                         listener_id,
@@ -1104,6 +1790,12 @@ where
                     });
                 }
             }
+            ToSwarm::DialAfter { opts, delay } => {
+                self.dial_after(opts, delay);
+            }
+            ToSwarm::CancelDeferredDial { connection_id } => {
+                self.cancel_deferred_dial(connection_id);
+            }
             ToSwarm::ListenOn { opts } => {
                 // Error is dispatched internally, safe to ignore.
                 let _ = self.add_listener(opts);
@@ -1148,28 +1840,34 @@ where
                 };
 
                 // If address translation yielded nothing, broadcast the original candidate address.
-                if translated_addresses.is_empty() {
+                let candidates = if translated_addresses.is_empty() {
+                    vec![addr]
+                } else {
+                    translated_addresses
+                };
+                for addr in candidates {
                     self.behaviour
                         .on_swarm_event(FromSwarm::NewExternalAddrCandidate(
                             NewExternalAddrCandidate { addr: &addr },
                         ));
+                    let reached_threshold = self.external_addr_scores.on_candidate(&addr);
                     self.pending_swarm_events
-                        .push_back(SwarmEvent::NewExternalAddrCandidate { address: addr });
-                } else {
-                    for addr in translated_addresses {
-                        self.behaviour
-                            .on_swarm_event(FromSwarm::NewExternalAddrCandidate(
-                                NewExternalAddrCandidate { addr: &addr },
-                            ));
+                        .push_back(SwarmEvent::NewExternalAddrCandidate {
+                            address: addr.clone(),
+                        });
+                    if reached_threshold {
+                        self.add_external_address(addr.clone());
                         self.pending_swarm_events
-                            .push_back(SwarmEvent::NewExternalAddrCandidate { address: addr });
+                            .push_back(SwarmEvent::ExternalAddrConfirmed { address: addr });
                     }
                 }
             }
             ToSwarm::ExternalAddrConfirmed(addr) => {
-                self.add_external_address(addr.clone());
-                self.pending_swarm_events
-                    .push_back(SwarmEvent::ExternalAddrConfirmed { address: addr });
+                if self.external_addr_scores.on_confirmation(&addr) {
+                    self.add_external_address(addr.clone());
+                    self.pending_swarm_events
+                        .push_back(SwarmEvent::ExternalAddrConfirmed { address: addr });
+                }
             }
             ToSwarm::ExternalAddrExpired(addr) => {
                 self.remove_external_address(&addr);
@@ -1198,6 +1896,10 @@ where
                 self.pending_swarm_events
                     .push_back(SwarmEvent::NewExternalAddrOfPeer { peer_id, address });
             }
+            ToSwarm::BehaviourPanicked { name } => {
+                self.pending_swarm_events
+                    .push_back(SwarmEvent::BehaviourPanicked { name });
+            }
         }
     }
 
@@ -1212,6 +1914,7 @@ where
         // We use a `this` variable because the compiler can't mutably borrow multiple times
         // across a `Deref`.
         let this = &mut *self;
+        let started = this.poll_time_budget.map(|budget| ((this.clock)(), budget));
 
         // This loop polls the components below in a prioritized order.
         //
@@ -1223,8 +1926,19 @@ where
         //
         // (2) is polled before (3) to prioritize existing connections over upgrading new incoming connections.
         loop {
+            // Yield to other tasks once the time budget of this poll is spent.
+            if let Some((started, budget)) = started {
+                if (this.clock)().saturating_duration_since(started) >= budget {
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+            }
+
             if let Some(swarm_event) = this.pending_swarm_events.pop_front() {
-                return Poll::Ready(swarm_event);
+                match this.event_subscriptions.route(swarm_event) {
+                    Some(swarm_event) => return Poll::Ready(swarm_event),
+                    None => continue,
+                }
             }
 
             match this.pending_handler_event.take() {
@@ -1253,20 +1967,45 @@ where
                     }
                 },
                 // No pending event. Allow the [`NetworkBehaviour`] to make progress.
-                None => match this.behaviour.poll(cx) {
-                    Poll::Pending => {}
-                    Poll::Ready(behaviour_event) => {
-                        this.handle_behaviour_event(behaviour_event);
-
-                        continue;
+                None => {
+                    #[cfg(feature = "poll-metrics")]
+                    let poll = this.poll_metrics.collect(|| this.behaviour.poll(cx));
+                    #[cfg(not(feature = "poll-metrics"))]
+                    let poll = this.behaviour.poll(cx);
+
+                    match poll {
+                        Poll::Pending => {}
+                        Poll::Ready(behaviour_event) => {
+                            this.handle_behaviour_event(behaviour_event);
+
+                            continue;
+                        }
                     }
-                },
+                }
             }
 
-            // Poll the known peers.
-            match this.pool.poll(cx) {
-                Poll::Pending => {}
-                Poll::Ready(pool_event) => {
+            // Only feed the [`NetworkBehaviour`] new work if it is ready for it.
+            if this.behaviour_backpressure && this.behaviour.poll_ready(cx).is_pending() {
+                return Poll::Pending;
+            }
+
+            // Start deferred dials that are due.
+            if let Poll::Ready(opts) = this.deferred_dials.poll(cx) {
+                this.handle_deferred_dial(opts);
+                continue;
+            }
+
+            if let Poll::Ready(reports) = this.peer_anomalies.poll(cx) {
+                this.pending_swarm_events.extend(reports.into_iter().map(
+                    |(peer_id, anomalies)| SwarmEvent::PeerAnomalyReport { peer_id, anomalies },
+                ));
+                continue;
+            }
+
+            // Poll the known peers.
+            match this.pool.poll(cx) {
+                Poll::Pending => {}
+                Poll::Ready(pool_event) => {
                     this.handle_pool_event(pool_event);
                     continue;
                 }
@@ -1402,6 +2141,12 @@ where
 
 pub struct Config {
     pool_config: PoolConfig,
+    dial_timeouts: DialTimeouts,
+    behaviour_backpressure: bool,
+    poll_time_budget: Option<Duration>,
+    address_scoring: AddressScoring,
+    peer_anomaly_reports: Option<(NonZeroU32, Duration)>,
+    protocol_conflicts: ProtocolConflicts,
 }
 
 impl Config {
@@ -1410,6 +2155,12 @@ impl Config {
     pub fn with_executor(executor: impl Executor + Send + 'static) -> Self {
         Self {
             pool_config: PoolConfig::new(Some(Box::new(executor))),
+            dial_timeouts: DialTimeouts::default(),
+            behaviour_backpressure: false,
+            poll_time_budget: None,
+            address_scoring: AddressScoring::default(),
+            peer_anomaly_reports: None,
+            protocol_conflicts: ProtocolConflicts::default(),
         }
     }
 
@@ -1481,6 +2232,33 @@ impl Config {
         self
     }
 
+    /// The maximum number of concurrently pending outbound connections to a single peer.
+    ///
+    /// Further dials to the peer fail with [`DialError::Denied`] and a [`DialLimitExceeded`]
+    /// cause, regardless of which [`NetworkBehaviour`] requested them.
+    /// Dials without a [`PeerId`] are not limited.
+    ///
+    /// Unlimited by default.
+    pub fn with_max_pending_dials_per_peer(mut self, limit: NonZeroUsize) -> Self {
+        self.pool_config = self.pool_config.with_max_pending_dials_per_peer(limit);
+        self
+    }
+
+    /// Don't dial a peer for `cooldown` after `failures` consecutive outbound connection attempts
+    /// to it failed.
+    ///
+    /// Dials during the cooldown fail with [`DialError::Denied`] and a [`DialLimitExceeded`]
+    /// cause. Any connection established with the peer resets its count of failures, and
+    /// failures further apart than `cooldown` are not considered consecutive.
+    ///
+    /// Disabled by default.
+    pub fn with_dial_failure_cooldown(mut self, failures: NonZeroU32, cooldown: Duration) -> Self {
+        self.pool_config = self
+            .pool_config
+            .with_dial_failure_cooldown(failures, cooldown);
+        self
+    }
+
     /// Configures an override for the substream upgrade protocol to use.
     ///
     /// The subtream upgrade protocol is the multistream-select protocol
@@ -1513,6 +2291,20 @@ impl Config {
         self
     }
 
+    /// The maximum number of outbound streams concurrently negotiating on a
+    /// connection. Outbound streams requested by a [`ConnectionHandler`]
+    /// exceeding the limit are queued until a negotiation completes.
+    ///
+    /// The upgrade timeout of a queued stream only starts once it leaves the
+    /// queue. Handlers are informed about the number of queued streams via
+    /// [`ConnectionEvent::OutboundQueueChange`](handler::ConnectionEvent::OutboundQueueChange).
+    ///
+    /// Unlimited by default.
+    pub fn with_max_negotiating_outbound_streams(mut self, v: NonZeroUsize) -> Self {
+        self.pool_config = self.pool_config.with_max_negotiating_outbound_streams(v);
+        self
+    }
+
     /// How long to keep a connection alive once it is idling.
     ///
     /// Defaults to 0.
@@ -1520,6 +2312,88 @@ impl Config {
         self.pool_config.idle_connection_timeout = timeout;
         self
     }
+
+    /// How long to wait for a single address to be dialed, including the upgrades
+    /// of the transport, before it is considered failed.
+    ///
+    /// Applies to addresses without a timeout set via [`Config::with_protocol_dial_timeout`].
+    /// By default only the timeouts of the transports apply. A single dial can override it via
+    /// [`WithPeerId::override_dial_timeout`](dial_opts::WithPeerId::override_dial_timeout).
+    pub fn with_dial_timeout(mut self, timeout: Duration) -> Self {
+        self.dial_timeouts.set_default(timeout);
+        self
+    }
+
+    /// How long to wait for dialing an address containing the given protocol, identified by
+    /// its [`Protocol::tag`](libp2p_core::multiaddr::Protocol::tag), e.g. `"quic-v1"`, `"tcp"`
+    /// or `"p2p-circuit"`.
+    ///
+    /// If an address contains several protocols with a timeout, the timeout of the last one
+    /// applies. A relayed address like `/ip4/.../tcp/.../p2p/<relay>/p2p-circuit` is thus
+    /// subject to the timeout of `"p2p-circuit"` rather than the one of `"tcp"`.
+    pub fn with_protocol_dial_timeout(mut self, protocol: &'static str, timeout: Duration) -> Self {
+        self.dial_timeouts.set_for_protocol(protocol, timeout);
+        self
+    }
+
+    /// Whether to stop polling connections and listeners while
+    /// [`NetworkBehaviour::poll_ready`] returns [`Poll::Pending`].
+    ///
+    /// Defaults to `false`.
+    pub fn with_behaviour_backpressure(mut self, enabled: bool) -> Self {
+        self.behaviour_backpressure = enabled;
+        self
+    }
+
+    /// The time a single poll of the [`Swarm`] may take before it yields to other tasks
+    /// of the executor, scheduling itself to be polled again.
+    ///
+    /// Without a budget, the [`Swarm`] keeps processing internal events until it has an
+    /// event to report or no more work is left. Enable the `poll-metrics` feature to find
+    /// out which [`NetworkBehaviour`] takes up the time, see `Swarm::poll_metrics`.
+    ///
+    /// Unlimited by default.
+    pub fn with_poll_time_budget(mut self, budget: Duration) -> Self {
+        self.poll_time_budget = Some(budget);
+        self
+    }
+
+    /// How the confidence in external address candidates and confirmations reported by
+    /// [`NetworkBehaviour`]s is scored.
+    ///
+    /// Only addresses whose score reaches the threshold of the [`AddressScoring`] are confirmed,
+    /// reported via [`SwarmEvent::ExternalAddrConfirmed`] and [`FromSwarm::ExternalAddrConfirmed`]
+    /// and thus advertised to other peers.
+    ///
+    /// Defaults to confirming an address with its first confirmation.
+    pub fn with_external_address_scoring(mut self, scoring: AddressScoring) -> Self {
+        self.address_scoring = scoring;
+        self
+    }
+
+    /// Report peers via [`SwarmEvent::PeerAnomalyReport`] that misbehaved at least `threshold`
+    /// times within `interval`.
+    ///
+    /// Anomalies are inbound streams failing during protocol negotiation, and protocol
+    /// violations or decode errors reported by [`ConnectionHandler`]s via
+    /// [`ConnectionHandlerEvent::ReportAnomaly`]. An interval starts with the first anomaly
+    /// observed after the previous one ended.
+    ///
+    /// Disabled by default.
+    pub fn with_peer_anomaly_reports(mut self, threshold: NonZeroU32, interval: Duration) -> Self {
+        self.peer_anomaly_reports = Some((threshold, interval));
+        self
+    }
+
+    /// How to handle protocols that are claimed by more than one [`ConnectionHandler`] of a
+    /// composed behaviour, e.g. by two request-response behaviours using the same protocol name.
+    ///
+    /// Conflicts are detected when a connection is established. Defaults to
+    /// [`ProtocolConflicts::Warn`].
+    pub fn with_protocol_conflicts(mut self, policy: ProtocolConflicts) -> Self {
+        self.protocol_conflicts = policy;
+        self
+    }
 }
 
 /// Possible errors when trying to establish or upgrade an outbound connection.
@@ -1791,7 +2665,7 @@ mod tests {
     ) -> Swarm<CallTraceBehaviour<MockBehaviour<dummy::ConnectionHandler, ()>>> {
         let id_keys = identity::Keypair::generate_ed25519();
         let local_public_key = id_keys.public();
-        let transport = transport::MemoryTransport::default()
+        let transport = CountingTransport::new(transport::MemoryTransport::default())
             .upgrade(upgrade::Version::V1)
             .authenticate(plaintext::Config::new(&id_keys))
             .multiplex(yamux::Config::default())
@@ -1909,6 +2783,514 @@ mod tests {
         .await
     }
 
+    /// Tags a connection, queries it by tag and expects the tags to be
+    /// discarded once the connection closes.
+    #[tokio::test]
+    async fn test_connection_tags() {
+        let mut swarm1 = new_test_swarm(Config::with_tokio_executor());
+        let mut swarm2 = new_test_swarm(Config::with_tokio_executor());
+
+        let addr2: Multiaddr = multiaddr::Protocol::Memory(rand::random::<u64>()).into();
+        swarm2.listen_on(addr2.clone()).unwrap();
+        swarm1.dial(addr2).unwrap();
+
+        let swarm2_id = *swarm2.local_peer_id();
+        let connection_id = future::poll_fn(|cx| loop {
+            let poll1 = Swarm::poll_next_event(Pin::new(&mut swarm1), cx);
+            let poll2 = Swarm::poll_next_event(Pin::new(&mut swarm2), cx);
+            if let Poll::Ready(SwarmEvent::ConnectionEstablished { connection_id, .. }) = poll1 {
+                return Poll::Ready(connection_id);
+            }
+            if poll1.is_pending() && poll2.is_pending() {
+                return Poll::Pending;
+            }
+        })
+        .await;
+
+        assert!(!swarm1.tag_connection(ConnectionId::new_unchecked(usize::MAX), "relay"));
+        assert!(swarm1.tag_connection(connection_id, "relay"));
+        assert!(swarm1.tag_connection(connection_id, "bootstrap"));
+        assert_eq!(
+            swarm1.connections_with_tag("relay").collect::<Vec<_>>(),
+            vec![(swarm2_id, connection_id)]
+        );
+        assert!(swarm1.untag_connection(connection_id, "bootstrap"));
+        assert!(!swarm1.untag_connection(connection_id, "bootstrap"));
+        assert_eq!(
+            swarm1.connection_tags(connection_id).collect::<Vec<_>>(),
+            vec!["relay"]
+        );
+
+        assert!(swarm1.close_connection(connection_id));
+        future::poll_fn(|cx| loop {
+            let poll1 = Swarm::poll_next_event(Pin::new(&mut swarm1), cx);
+            let poll2 = Swarm::poll_next_event(Pin::new(&mut swarm2), cx);
+            if let Poll::Ready(SwarmEvent::ConnectionClosed { .. }) = poll1 {
+                return Poll::Ready(());
+            }
+            if poll1.is_pending() && poll2.is_pending() {
+                return Poll::Pending;
+            }
+        })
+        .await;
+
+        assert_eq!(swarm1.connections_with_tag("relay").count(), 0);
+        assert_eq!(swarm1.connection_tags(connection_id).count(), 0);
+    }
+
+    /// Expects stats to be reported for established connections and their peer
+    /// only, and to be discarded once the connection closes.
+    #[tokio::test]
+    async fn test_connection_stats() {
+        let mut swarm1 = new_test_swarm(Config::with_tokio_executor());
+        let mut swarm2 = new_test_swarm(Config::with_tokio_executor());
+
+        let addr2: Multiaddr = multiaddr::Protocol::Memory(rand::random::<u64>()).into();
+        swarm2.listen_on(addr2.clone()).unwrap();
+        swarm1.dial(addr2).unwrap();
+
+        let swarm2_id = *swarm2.local_peer_id();
+        let connection_id = future::poll_fn(|cx| loop {
+            let poll1 = Swarm::poll_next_event(Pin::new(&mut swarm1), cx);
+            let poll2 = Swarm::poll_next_event(Pin::new(&mut swarm2), cx);
+            if let Poll::Ready(SwarmEvent::ConnectionEstablished { connection_id, .. }) = poll1 {
+                return Poll::Ready(connection_id);
+            }
+            if poll1.is_pending() && poll2.is_pending() {
+                return Poll::Pending;
+            }
+        })
+        .await;
+
+        let stats = swarm1.connection_stats(connection_id).unwrap();
+        // The handshakes of the security and multiplexing protocols went over the wire.
+        assert!(stats.bytes_inbound() > 0);
+        assert!(stats.bytes_outbound() > 0);
+        assert_eq!(swarm1.peer_stats(&swarm2_id), Some(stats));
+        assert_eq!(
+            swarm1.connection_stats(ConnectionId::new_unchecked(usize::MAX)),
+            None
+        );
+        assert!(swarm1.muxer_stats(connection_id).is_some());
+
+        assert!(swarm1.close_connection(connection_id));
+        future::poll_fn(|cx| loop {
+            let poll1 = Swarm::poll_next_event(Pin::new(&mut swarm1), cx);
+            let poll2 = Swarm::poll_next_event(Pin::new(&mut swarm2), cx);
+            if let Poll::Ready(SwarmEvent::ConnectionClosed { .. }) = poll1 {
+                return Poll::Ready(());
+            }
+            if poll1.is_pending() && poll2.is_pending() {
+                return Poll::Pending;
+            }
+        })
+        .await;
+
+        assert_eq!(swarm1.connection_stats(connection_id), None);
+        assert_eq!(swarm1.peer_stats(&swarm2_id), None);
+        assert_eq!(swarm1.muxer_stats(connection_id), None);
+    }
+
+    /// Establishes multiple connections between two peers,
+    /// after which one peer shuts down using [`Swarm::shutdown`].
+    ///
+    /// The test expects the shutdown to complete before the timeout
+    /// and the remaining peer to observe all connections being closed.
+    #[tokio::test]
+    async fn test_swarm_shutdown() {
+        let mut swarm1 = new_test_swarm(Config::with_tokio_executor());
+        let mut swarm2 = new_test_swarm(Config::with_tokio_executor());
+
+        let addr2: Multiaddr = multiaddr::Protocol::Memory(rand::random::<u64>()).into();
+        swarm2.listen_on(addr2.clone()).unwrap();
+
+        let num_connections = 3;
+        for _ in 0..num_connections {
+            swarm1.dial(addr2.clone()).unwrap();
+        }
+
+        future::poll_fn(|cx| loop {
+            let poll1 = Swarm::poll_next_event(Pin::new(&mut swarm1), cx);
+            let poll2 = Swarm::poll_next_event(Pin::new(&mut swarm2), cx);
+            if swarms_connected(&swarm1, &swarm2, num_connections) {
+                return Poll::Ready(());
+            }
+            if poll1.is_pending() && poll2.is_pending() {
+                return Poll::Pending;
+            }
+        })
+        .await;
+
+        let swarm1_id = *swarm1.local_peer_id();
+        let mut shutdown = swarm1.shutdown(Duration::from_secs(10)).boxed_local();
+
+        let outcome = future::poll_fn(|cx| {
+            while Swarm::poll_next_event(Pin::new(&mut swarm2), cx).is_ready() {}
+            shutdown.poll_unpin(cx)
+        })
+        .await;
+        assert!(outcome.completed, "Shutdown timed out");
+        assert_eq!(
+            outcome
+                .events
+                .iter()
+                .filter(|e| matches!(e, SwarmEvent::ConnectionClosed { .. }))
+                .count(),
+            num_connections
+        );
+
+        future::poll_fn(|cx| loop {
+            if !swarm2.is_connected(&swarm1_id) {
+                return Poll::Ready(());
+            }
+            if Swarm::poll_next_event(Pin::new(&mut swarm2), cx).is_pending() {
+                return Poll::Pending;
+            }
+        })
+        .await;
+        assert_eq!(swarm2.behaviour().num_connections_to_peer(swarm1_id), 0);
+    }
+
+    /// Records the order of the shutdown calls, emitting a final event once informed
+    /// via [`NetworkBehaviour::on_shutdown`].
+    struct ShutdownBehaviour {
+        calls: std::sync::Arc<std::sync::Mutex<Vec<&'static str>>>,
+        shutting_down: bool,
+    }
+
+    impl NetworkBehaviour for ShutdownBehaviour {
+        type ConnectionHandler = dummy::ConnectionHandler;
+        type ToSwarm = ();
+
+        fn handle_established_inbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: &Multiaddr,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn handle_established_outbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: Endpoint,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn on_swarm_event(&mut self, event: FromSwarm) {
+            if let FromSwarm::Shutdown = event {
+                self.calls.lock().unwrap().push("FromSwarm::Shutdown");
+            }
+        }
+
+        fn on_connection_handler_event(
+            &mut self,
+            _: PeerId,
+            _: ConnectionId,
+            event: THandlerOutEvent<Self>,
+        ) {
+            void::unreachable(event)
+        }
+
+        fn poll(&mut self, _: &mut Context<'_>) -> Poll<ToSwarm<(), THandlerInEvent<Self>>> {
+            if std::mem::take(&mut self.shutting_down) {
+                self.calls.lock().unwrap().push("poll");
+                return Poll::Ready(ToSwarm::GenerateEvent(()));
+            }
+            Poll::Pending
+        }
+
+        fn on_shutdown(&mut self) {
+            self.calls.lock().unwrap().push("on_shutdown");
+            self.shutting_down = true;
+        }
+    }
+
+    struct BackpressuredBehaviour {
+        ready: bool,
+    }
+
+    impl NetworkBehaviour for BackpressuredBehaviour {
+        type ConnectionHandler = dummy::ConnectionHandler;
+        type ToSwarm = void::Void;
+
+        fn handle_established_inbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: &Multiaddr,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn handle_established_outbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: Endpoint,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn on_swarm_event(&mut self, _: FromSwarm) {}
+
+        fn on_connection_handler_event(
+            &mut self,
+            _: PeerId,
+            _: ConnectionId,
+            event: THandlerOutEvent<Self>,
+        ) {
+            void::unreachable(event)
+        }
+
+        fn poll(
+            &mut self,
+            _: &mut Context<'_>,
+        ) -> Poll<ToSwarm<void::Void, THandlerInEvent<Self>>> {
+            Poll::Pending
+        }
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<()> {
+            if self.ready {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }
+    }
+
+    /// Expects a [`Swarm`] with behaviour backpressure to stop polling its listeners
+    /// while the behaviour is not ready.
+    #[tokio::test]
+    async fn test_behaviour_backpressure() {
+        let id_keys = identity::Keypair::generate_ed25519();
+        let transport = transport::MemoryTransport::default()
+            .upgrade(upgrade::Version::V1)
+            .authenticate(plaintext::Config::new(&id_keys))
+            .multiplex(yamux::Config::default())
+            .boxed();
+        let mut swarm = Swarm::new(
+            transport,
+            BackpressuredBehaviour { ready: false },
+            id_keys.public().to_peer_id(),
+            Config::with_tokio_executor().with_behaviour_backpressure(true),
+        );
+
+        let addr: Multiaddr = multiaddr::Protocol::Memory(rand::random::<u64>()).into();
+        swarm.listen_on(addr.clone()).unwrap();
+
+        let poll =
+            future::poll_fn(|cx| Poll::Ready(Swarm::poll_next_event(Pin::new(&mut swarm), cx)))
+                .await;
+        assert!(poll.is_pending());
+
+        swarm.behaviour_mut().ready = true;
+        match future::poll_fn(|cx| Swarm::poll_next_event(Pin::new(&mut swarm), cx)).await {
+            SwarmEvent::NewListenAddr { address, .. } => assert_eq!(address, addr),
+            e => panic!("Unexpected event: {e:?}"),
+        }
+    }
+
+    thread_local! {
+        static NOW: std::cell::Cell<Instant> = std::cell::Cell::new(Instant::now());
+    }
+
+    fn fake_clock() -> Instant {
+        NOW.with(|now| now.get())
+    }
+
+    /// Keeps the [`Swarm`] busy with internal work by closing the connections of unknown peers,
+    /// advancing the [`fake_clock`] by 5ms on every poll.
+    #[derive(Default)]
+    struct BusyBehaviour {
+        polls: usize,
+    }
+
+    impl NetworkBehaviour for BusyBehaviour {
+        type ConnectionHandler = dummy::ConnectionHandler;
+        type ToSwarm = void::Void;
+
+        fn handle_established_inbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: &Multiaddr,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn handle_established_outbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: Endpoint,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn on_swarm_event(&mut self, _: FromSwarm) {}
+
+        fn on_connection_handler_event(
+            &mut self,
+            _: PeerId,
+            _: ConnectionId,
+            event: THandlerOutEvent<Self>,
+        ) {
+            void::unreachable(event)
+        }
+
+        fn poll(
+            &mut self,
+            _: &mut Context<'_>,
+        ) -> Poll<ToSwarm<void::Void, THandlerInEvent<Self>>> {
+            self.polls += 1;
+            NOW.with(|now| now.set(now.get() + Duration::from_millis(5)));
+            Poll::Ready(ToSwarm::CloseConnection {
+                peer_id: PeerId::random(),
+                connection: CloseConnection::All,
+            })
+        }
+    }
+
+    /// Expects a [`Swarm`] with a poll time budget to yield while its behaviour keeps it busy.
+    #[tokio::test]
+    async fn test_poll_time_budget() {
+        let id_keys = identity::Keypair::generate_ed25519();
+        let transport = transport::MemoryTransport::default()
+            .upgrade(upgrade::Version::V1)
+            .authenticate(plaintext::Config::new(&id_keys))
+            .multiplex(yamux::Config::default())
+            .boxed();
+        let mut swarm = Swarm::new(
+            transport,
+            BusyBehaviour::default(),
+            id_keys.public().to_peer_id(),
+            Config::with_tokio_executor().with_poll_time_budget(Duration::from_millis(20)),
+        );
+        swarm.clock = fake_clock;
+
+        let poll =
+            future::poll_fn(|cx| Poll::Ready(Swarm::poll_next_event(Pin::new(&mut swarm), cx)))
+                .await;
+        assert!(poll.is_pending());
+        assert_eq!(swarm.behaviour().polls, 4);
+    }
+
+    /// Enables a [`Toggle`](crate::behaviour::toggle::Toggle) while connected and expects the
+    /// inner behaviour to only learn about connections established afterwards.
+    #[tokio::test]
+    async fn test_toggle_at_runtime() {
+        use crate::behaviour::toggle::Toggle;
+
+        let id_keys = identity::Keypair::generate_ed25519();
+        let transport = transport::MemoryTransport::default()
+            .upgrade(upgrade::Version::V1)
+            .authenticate(plaintext::Config::new(&id_keys))
+            .multiplex(yamux::Config::default())
+            .boxed();
+        let behaviour: Toggle<CallTraceBehaviour<MockBehaviour<dummy::ConnectionHandler, ()>>> =
+            Toggle::from(None);
+        let mut swarm1 = Swarm::new(
+            transport,
+            behaviour,
+            id_keys.public().to_peer_id(),
+            Config::with_tokio_executor().with_idle_connection_timeout(Duration::from_secs(5)),
+        );
+        let mut swarm2 = new_test_swarm(Config::with_tokio_executor());
+
+        let addr2: Multiaddr = multiaddr::Protocol::Memory(rand::random::<u64>()).into();
+        swarm2.listen_on(addr2.clone()).unwrap();
+
+        let mut established = Vec::new();
+        for _ in 0..2 {
+            swarm1.dial(addr2.clone()).unwrap();
+            let connection_id = future::poll_fn(|cx| loop {
+                let poll1 = Swarm::poll_next_event(Pin::new(&mut swarm1), cx);
+                let poll2 = Swarm::poll_next_event(Pin::new(&mut swarm2), cx);
+                if let Poll::Ready(SwarmEvent::ConnectionEstablished { connection_id, .. }) = poll1
+                {
+                    return Poll::Ready(connection_id);
+                }
+                if poll1.is_pending() && poll2.is_pending() {
+                    return Poll::Pending;
+                }
+            })
+            .await;
+            established.push(connection_id);
+
+            if !swarm1.behaviour().is_enabled() {
+                let inner = CallTraceBehaviour::new(MockBehaviour::new(dummy::ConnectionHandler));
+                assert!(swarm1.behaviour_mut().enable(inner).is_none());
+            }
+        }
+
+        let inner = swarm1.behaviour().as_ref().unwrap();
+        assert_eq!(inner.handle_established_outbound_connection.len(), 1);
+        assert_eq!(inner.on_connection_established.len(), 1);
+        let (_, connection_id, _, other_established) = &inner.on_connection_established[0];
+        assert_eq!(*connection_id, established[1]);
+        assert_eq!(*other_established, 0);
+
+        // Closing the connection that predates the behaviour is not reported to it.
+        assert!(swarm1.close_connection(established[0]));
+        future::poll_fn(|cx| loop {
+            let poll1 = Swarm::poll_next_event(Pin::new(&mut swarm1), cx);
+            let poll2 = Swarm::poll_next_event(Pin::new(&mut swarm2), cx);
+            if let Poll::Ready(SwarmEvent::ConnectionClosed { .. }) = poll1 {
+                return Poll::Ready(());
+            }
+            if poll1.is_pending() && poll2.is_pending() {
+                return Poll::Pending;
+            }
+        })
+        .await;
+        assert!(swarm1
+            .behaviour()
+            .as_ref()
+            .unwrap()
+            .on_connection_closed
+            .is_empty());
+
+        assert!(swarm1.behaviour_mut().disable().is_some());
+        assert!(!swarm1.behaviour().is_enabled());
+    }
+
+    /// Expects behaviours to be polled between [`NetworkBehaviour::on_shutdown`]
+    /// and [`FromSwarm::Shutdown`], allowing them to send final messages.
+    #[tokio::test]
+    async fn test_swarm_shutdown_hook() {
+        let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let swarm = Swarm::new(
+            transport::dummy::DummyTransport::new().boxed(),
+            ShutdownBehaviour {
+                calls: calls.clone(),
+                shutting_down: false,
+            },
+            PeerId::random(),
+            Config::with_tokio_executor(),
+        );
+
+        let outcome = swarm.shutdown(Duration::from_secs(10)).await;
+        assert!(outcome.completed);
+        assert!(matches!(
+            outcome.events.as_slice(),
+            [SwarmEvent::Behaviour(())]
+        ));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["on_shutdown", "poll", "FromSwarm::Shutdown"]
+        );
+    }
+
     /// Establishes multiple connections between two peers,
     /// after which one peer disconnects the other
     /// using [`ToSwarm::CloseConnection`] returned by a [`NetworkBehaviour`].
@@ -2123,6 +3505,142 @@ mod tests {
         QuickCheck::new().tests(10).quickcheck(prop as fn(_) -> _);
     }
 
+    #[tokio::test]
+    async fn staggered_dials() {
+        let mut swarm = new_test_swarm(Config::with_tokio_executor());
+
+        let mut transports = Vec::new();
+        let mut listen_addresses = Vec::new();
+        for _ in 0..2 {
+            let mut transport = transport::MemoryTransport::default().boxed();
+            transport
+                .listen_on(ListenerId::next(), "/memory/0".parse().unwrap())
+                .unwrap();
+
+            match transport.select_next_some().await {
+                TransportEvent::NewAddress { listen_addr, .. } => {
+                    listen_addresses.push(listen_addr);
+                }
+                _ => panic!("Expected `NewListenAddr` event."),
+            }
+
+            transports.push(transport);
+        }
+
+        let delay = Duration::from_millis(200);
+        let dialed_at = Instant::now();
+        swarm
+            .dial(
+                DialOpts::peer_id(PeerId::random())
+                    .addresses(listen_addresses)
+                    .stagger_dials(delay)
+                    .build(),
+            )
+            .unwrap();
+
+        // The second address is only dialed once the delay elapsed without the first dial
+        // succeeding. The inbound connections are kept open so that the dials don't fail.
+        let mut upgrades = Vec::new();
+        for (i, transport) in transports.iter_mut().enumerate() {
+            match futures::future::select(transport.select_next_some(), swarm.next()).await {
+                future::Either::Left((TransportEvent::Incoming { upgrade, .. }, _)) => {
+                    upgrades.push(upgrade);
+                }
+                future::Either::Left(_) => {
+                    panic!("Unexpected transport event.")
+                }
+                future::Either::Right((e, _)) => {
+                    panic!("Expect swarm to not emit any event {e:?}")
+                }
+            }
+            assert_eq!(dialed_at.elapsed() >= delay, i > 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn dial_timeout_override() {
+        let mut swarm = new_test_swarm(
+            Config::with_tokio_executor().with_dial_timeout(Duration::from_secs(60)),
+        );
+
+        // A listener that never completes the upgrades of the dialer.
+        let mut transport = transport::MemoryTransport::default().boxed();
+        transport
+            .listen_on(ListenerId::next(), "/memory/0".parse().unwrap())
+            .unwrap();
+        let listen_address = match transport.select_next_some().await {
+            TransportEvent::NewAddress { listen_addr, .. } => listen_addr,
+            _ => panic!("Expected `NewListenAddr` event."),
+        };
+
+        let timeout = Duration::from_millis(100);
+        let dialed_at = Instant::now();
+        swarm
+            .dial(
+                DialOpts::peer_id(PeerId::random())
+                    .addresses(vec![listen_address])
+                    .override_dial_timeout(timeout)
+                    .build(),
+            )
+            .unwrap();
+
+        let mut incoming = Vec::new();
+        loop {
+            match futures::future::select(transport.select_next_some(), swarm.next()).await {
+                future::Either::Left((TransportEvent::Incoming { upgrade, .. }, _)) => {
+                    incoming.push(upgrade);
+                }
+                future::Either::Left(_) => panic!("Unexpected transport event."),
+                future::Either::Right((
+                    Some(SwarmEvent::OutgoingConnectionError {
+                        error: DialError::Transport(errors),
+                        ..
+                    }),
+                    _,
+                )) => {
+                    assert!(matches!(
+                        &errors[..],
+                        [(_, TransportError::Other(e))] if e.kind() == io::ErrorKind::TimedOut
+                    ));
+                    break;
+                }
+                future::Either::Right((Some(SwarmEvent::Dialing { .. }), _)) => {}
+                future::Either::Right((e, _)) => panic!("Unexpected swarm event {e:?}"),
+            }
+        }
+        let elapsed = dialed_at.elapsed();
+        assert!(elapsed >= timeout && elapsed < Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn listeners_detailed() {
+        let mut swarm = new_test_swarm(Config::with_tokio_executor());
+
+        let requested: Multiaddr = "/memory/0".parse().unwrap();
+        let listener_id = swarm.listen_on(requested.clone()).unwrap();
+        let address = match swarm.next().await.unwrap() {
+            SwarmEvent::NewListenAddr { address, .. } => address,
+            e => panic!("Unexpected swarm event {e:?}"),
+        };
+
+        let details = swarm.listeners_detailed().collect::<Vec<_>>();
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].listener_id(), listener_id);
+        assert_eq!(details[0].requested_address(), &requested);
+        assert_eq!(details[0].addresses(), &[address]);
+        assert!(details[0].last_error().is_none());
+        assert!(!details[0].is_closed());
+
+        let new_listener_id = swarm.relisten(listener_id).unwrap();
+        assert_ne!(new_listener_id, listener_id);
+        assert!(swarm.relisten(listener_id).is_err());
+
+        let details = swarm.listeners_detailed().collect::<Vec<_>>();
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].listener_id(), new_listener_id);
+        assert_eq!(details[0].requested_address(), &requested);
+    }
+
     #[tokio::test]
     async fn invalid_peer_id() {
         // Checks whether dialing an address containing the wrong peer id raises an error
@@ -2299,6 +3817,195 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn per_peer_dial_limits() {
+        let target = PeerId::random();
+        let address: Multiaddr = multiaddr![Memory(rand::random::<u64>())];
+        let dial = || {
+            DialOpts::peer_id(target)
+                .condition(PeerCondition::Always)
+                .addresses(vec![address.clone()])
+                .build()
+        };
+
+        let mut swarm = new_test_swarm(
+            Config::with_tokio_executor()
+                .with_max_pending_dials_per_peer(NonZeroUsize::new(1).unwrap())
+                .with_dial_failure_cooldown(NonZeroU32::new(2).unwrap(), Duration::from_secs(60)),
+        );
+
+        for _ in 0..2 {
+            swarm.dial(dial()).unwrap();
+            match swarm.dial(dial()).unwrap_err() {
+                DialError::Denied { cause } => assert_eq!(
+                    cause.downcast::<DialLimitExceeded>().unwrap(),
+                    DialLimitExceeded::PendingDials { limit: 1 }
+                ),
+                e => panic!("Unexpected error: {e:?}"),
+            }
+
+            match swarm.next().await.unwrap() {
+                SwarmEvent::OutgoingConnectionError {
+                    error: DialError::Transport(_),
+                    ..
+                } => {}
+                e => panic!("Unexpected event: {e:?}"),
+            }
+        }
+
+        match swarm.dial(dial()).unwrap_err() {
+            DialError::Denied { cause } => assert!(matches!(
+                cause.downcast::<DialLimitExceeded>().unwrap(),
+                DialLimitExceeded::CoolingDown { failures: 2, .. }
+            )),
+            e => panic!("Unexpected error: {e:?}"),
+        }
+
+        // Dials without a peer ID are not limited.
+        swarm.dial(address).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dial_failures_expire() {
+        let target = PeerId::random();
+        let dial = || {
+            DialOpts::peer_id(target)
+                .condition(PeerCondition::Always)
+                .addresses(vec![multiaddr![Memory(rand::random::<u64>())]])
+                .build()
+        };
+        let cooldown = Duration::from_millis(100);
+
+        let mut swarm = new_test_swarm(
+            Config::with_tokio_executor()
+                .with_dial_failure_cooldown(NonZeroU32::new(2).unwrap(), cooldown),
+        );
+
+        // Two failures further apart than the cooldown are not consecutive.
+        for _ in 0..2 {
+            swarm.dial(dial()).unwrap();
+            match swarm.next().await.unwrap() {
+                SwarmEvent::OutgoingConnectionError {
+                    error: DialError::Transport(_),
+                    ..
+                } => {}
+                e => panic!("Unexpected event: {e:?}"),
+            }
+            tokio::time::sleep(cooldown * 2).await;
+        }
+        swarm.dial(dial()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn dial_opts_rank_addresses() {
+        let target = PeerId::random();
+        let tcp = multiaddr![Ip4([0, 0, 0, 0]), Tcp(rand::random::<u16>())];
+        let memory = multiaddr![Memory(rand::random::<u64>())];
+
+        let mut swarm = new_test_swarm(Config::with_tokio_executor());
+
+        swarm
+            .dial(
+                DialOpts::peer_id(target)
+                    .addresses(vec![tcp.clone(), memory.clone()])
+                    .rank_addresses(|addresses| addresses.into_iter().rev().take(1).collect())
+                    .build(),
+            )
+            .unwrap();
+
+        match swarm.next().await.unwrap() {
+            SwarmEvent::OutgoingConnectionError {
+                error: DialError::Transport(errors),
+                ..
+            } => {
+                let failed_addresses = errors.into_iter().map(|(addr, _)| addr).collect::<Vec<_>>();
+                assert_eq!(failed_addresses, vec![memory.with(Protocol::P2p(target))]);
+            }
+            e => panic!("Unexpected event: {e:?}"),
+        }
+
+        let error = swarm
+            .dial(
+                DialOpts::peer_id(target)
+                    .addresses(vec![tcp])
+                    .rank_addresses(|_| Vec::new())
+                    .build(),
+            )
+            .unwrap_err();
+        assert!(matches!(error, DialError::NoAddresses));
+    }
+
+    #[tokio::test]
+    async fn subscribe_events() {
+        let mut swarm = new_test_swarm(Config::with_tokio_executor());
+        let mut listener_events =
+            swarm.subscribe_events(|event| event.category() == EventCategory::Listener);
+
+        let addr: Multiaddr = multiaddr::Protocol::Memory(rand::random::<u64>()).into();
+        swarm.listen_on(addr.clone()).unwrap();
+
+        let event = future::poll_fn(|cx| {
+            if let Poll::Ready(event) = swarm.poll_next_unpin(cx) {
+                panic!("Unexpected event: {event:?}");
+            }
+            listener_events.poll_next_unpin(cx)
+        })
+        .await;
+        assert!(
+            matches!(event, Some(SwarmEvent::NewListenAddr { address, .. }) if address == addr)
+        );
+
+        // Events are returned by the swarm again once the subscription is dropped.
+        drop(listener_events);
+        let addr: Multiaddr = multiaddr::Protocol::Memory(rand::random::<u64>()).into();
+        swarm.listen_on(addr.clone()).unwrap();
+        let event = swarm.next().await;
+        assert!(
+            matches!(event, Some(SwarmEvent::NewListenAddr { address, .. }) if address == addr)
+        );
+    }
+
+    #[tokio::test]
+    async fn deny_protocol_conflicts() {
+        let id_keys = identity::Keypair::generate_ed25519();
+        let transport = transport::MemoryTransport::default()
+            .upgrade(upgrade::Version::V1)
+            .authenticate(plaintext::Config::new(&id_keys))
+            .multiplex(yamux::Config::default())
+            .boxed();
+        let handler = ConnectionHandlerSelect::new(
+            handler::PendingConnectionHandler::new("/a".to_owned()),
+            handler::PendingConnectionHandler::new("/a".to_owned()),
+        );
+        let mut dialer = Swarm::new(
+            transport,
+            MockBehaviour::<_, ()>::new(handler),
+            id_keys.public().to_peer_id(),
+            Config::with_tokio_executor().with_protocol_conflicts(ProtocolConflicts::Deny),
+        );
+        let mut listener = new_test_swarm(Config::with_tokio_executor());
+
+        listener.listen_on(multiaddr![Memory(0u64)]).unwrap();
+        let listener_address = match listener.next().await.unwrap() {
+            SwarmEvent::NewListenAddr { address, .. } => address,
+            e => panic!("Unexpected network event: {e:?}"),
+        };
+        dialer.dial(listener_address).unwrap();
+        tokio::spawn(listener.collect::<Vec<_>>());
+
+        match dialer.next().await.unwrap() {
+            SwarmEvent::OutgoingConnectionError {
+                error: DialError::Denied { cause },
+                ..
+            } => assert_eq!(
+                cause.downcast::<ProtocolConflict>().unwrap().protocols(),
+                ["/a".to_owned()]
+            ),
+            e => panic!("Unexpected swarm event {e:?}."),
+        }
+        assert_eq!(dialer.connected_peers().count(), 0);
+    }
+
     #[tokio::test]
     async fn aborting_pending_connection_surfaces_error() {
         let _ = tracing_subscriber::fmt()
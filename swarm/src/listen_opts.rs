@@ -31,3 +31,44 @@ impl From<Multiaddr> for ListenOpts {
         ListenOpts::new(addr)
     }
 }
+
+/// Details about a listener of the [`Swarm`](crate::Swarm),
+/// see [`Swarm::listeners_detailed`](crate::Swarm::listeners_detailed).
+#[derive(Debug, Clone)]
+pub struct ListenerDetails {
+    pub(crate) id: ListenerId,
+    pub(crate) requested_address: Multiaddr,
+    pub(crate) addresses: Vec<Multiaddr>,
+    pub(crate) last_error: Option<String>,
+    pub(crate) is_closed: bool,
+}
+
+impl ListenerDetails {
+    /// Get the [`ListenerId`] of the listener.
+    pub fn listener_id(&self) -> ListenerId {
+        self.id
+    }
+
+    /// Get the [`Multiaddr`] the listener was requested to listen on.
+    pub fn requested_address(&self) -> &Multiaddr {
+        &self.requested_address
+    }
+
+    /// Get the addresses the listener is bound to, e.g. with the port resolved for `/tcp/0`.
+    pub fn addresses(&self) -> &[Multiaddr] {
+        &self.addresses
+    }
+
+    /// Get the most recent error reported by the listener, if any.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Whether the listener was closed by an error.
+    ///
+    /// Closed listeners are kept until they are replaced through
+    /// [`Swarm::relisten`](crate::Swarm::relisten).
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+}
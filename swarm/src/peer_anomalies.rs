@@ -0,0 +1,127 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use crate::handler::Anomaly;
+use futures::FutureExt;
+use futures_timer::Delay;
+use libp2p_identity::PeerId;
+use std::collections::HashMap;
+use std::num::NonZeroU32;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+/// Anomalies of a peer counted over a reporting interval.
+pub(crate) type AnomalyCounts = HashMap<Anomaly, u32>;
+
+/// The anomalies observed per peer, see
+/// [`Config::with_peer_anomaly_reports`](crate::Config::with_peer_anomaly_reports).
+pub(crate) struct PeerAnomalies {
+    /// The number of anomalies within an interval for a peer to be reported, and the interval.
+    config: Option<(NonZeroU32, Duration)>,
+    /// The anomalies observed per peer within the current interval.
+    counts: HashMap<PeerId, AnomalyCounts>,
+    /// Timer for the end of the current interval, started by the first anomaly in it.
+    timer: Option<Delay>,
+}
+
+impl PeerAnomalies {
+    pub(crate) fn new(config: Option<(NonZeroU32, Duration)>) -> Self {
+        Self {
+            config,
+            counts: HashMap::new(),
+            timer: None,
+        }
+    }
+
+    pub(crate) fn record(&mut self, peer: PeerId, anomaly: Anomaly) {
+        let Some((_, interval)) = self.config else {
+            return;
+        };
+        *self
+            .counts
+            .entry(peer)
+            .or_default()
+            .entry(anomaly)
+            .or_default() += 1;
+        self.timer.get_or_insert_with(|| Delay::new(interval));
+    }
+
+    /// Returns the peers with at least the configured number of anomalies once the current
+    /// interval ended, and starts a new one.
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Vec<(PeerId, AnomalyCounts)>> {
+        let (Some(timer), Some((threshold, _))) = (self.timer.as_mut(), self.config) else {
+            return Poll::Pending;
+        };
+        if timer.poll_unpin(cx).is_pending() {
+            return Poll::Pending;
+        }
+        self.timer = None;
+
+        let reports = self
+            .counts
+            .drain()
+            .filter(|(_, counts)| counts.values().sum::<u32>() >= threshold.get())
+            .collect();
+
+        Poll::Ready(reports)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::poll_fn;
+
+    #[tokio::test]
+    async fn reports_peers_above_threshold() {
+        let mut anomalies = PeerAnomalies::new(Some((
+            NonZeroU32::new(2).unwrap(),
+            Duration::from_millis(10),
+        )));
+        let well_behaved = PeerId::random();
+        let misbehaving = PeerId::random();
+
+        anomalies.record(well_behaved, Anomaly::NegotiationFailed);
+        anomalies.record(misbehaving, Anomaly::DecodeError);
+        anomalies.record(misbehaving, Anomaly::ProtocolViolation);
+
+        let reports = poll_fn(|cx| anomalies.poll(cx)).await;
+        assert_eq!(
+            reports,
+            vec![(
+                misbehaving,
+                HashMap::from([(Anomaly::DecodeError, 1), (Anomaly::ProtocolViolation, 1)])
+            )]
+        );
+
+        // The next interval starts with the next anomaly.
+        assert!(anomalies.timer.is_none());
+        anomalies.record(misbehaving, Anomaly::DecodeError);
+        assert!(poll_fn(|cx| anomalies.poll(cx)).await.is_empty());
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let mut anomalies = PeerAnomalies::new(None);
+        anomalies.record(PeerId::random(), Anomaly::DecodeError);
+
+        assert!(anomalies.counts.is_empty());
+    }
+}
@@ -0,0 +1,156 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Time spent polling the fields of behaviours composed via the
+//! [`NetworkBehaviour`](crate::NetworkBehaviour) derive macro.
+//!
+//! With the `poll-metrics` feature, the generated
+//! [`NetworkBehaviour::poll`](crate::NetworkBehaviour::poll) measures each call to the `poll` of
+//! its fields. The measurements are kept per [`Swarm`](crate::Swarm) and can be retrieved via
+//! [`Swarm::poll_metrics`](crate::Swarm::poll_metrics), e.g. to identify which behaviour is
+//! starving its event loop.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::Duration;
+
+thread_local! {
+    /// The metrics of the [`Swarm`](crate::Swarm) currently polling its behaviour on this thread.
+    static CURRENT: RefCell<Option<PollMetrics>> = const { RefCell::new(None) };
+}
+
+/// The time spent polling a field of a composed behaviour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollStats {
+    /// The name of the composed behaviour.
+    pub behaviour: &'static str,
+    /// The name of the field within the composed behaviour.
+    pub field: &'static str,
+    /// The number of times the field was polled.
+    pub polls: u64,
+    /// The total time spent polling the field.
+    pub total: Duration,
+    /// The longest time spent in a single poll of the field.
+    pub max: Duration,
+}
+
+/// Records the time a single poll of `field` within `behaviour` took.
+///
+/// The measurement is discarded unless the behaviour is polled by a [`Swarm`](crate::Swarm).
+#[doc(hidden)]
+pub fn record(behaviour: &'static str, field: &'static str, elapsed: Duration) {
+    CURRENT.with(|current| {
+        if let Some(metrics) = current.borrow_mut().as_mut() {
+            metrics.record(behaviour, field, elapsed);
+        }
+    });
+}
+
+/// The time spent polling the fields of the behaviour of a [`Swarm`](crate::Swarm).
+#[derive(Debug, Default)]
+pub(crate) struct PollMetrics {
+    stats: HashMap<(&'static str, &'static str), PollStats>,
+}
+
+impl PollMetrics {
+    #[cfg(feature = "poll-metrics")]
+    /// Collects the measurements recorded while running `f` on this thread.
+    pub(crate) fn collect<R>(&mut self, f: impl FnOnce() -> R) -> R {
+        let outer = CURRENT.with(|current| current.replace(Some(std::mem::take(self))));
+        let result = f();
+        *self = CURRENT
+            .with(|current| current.replace(outer))
+            .expect("metrics to be installed");
+        result
+    }
+
+    fn record(&mut self, behaviour: &'static str, field: &'static str, elapsed: Duration) {
+        let stats = self.stats.entry((behaviour, field)).or_insert(PollStats {
+            behaviour,
+            field,
+            polls: 0,
+            total: Duration::ZERO,
+            max: Duration::ZERO,
+        });
+        stats.polls += 1;
+        stats.total += elapsed;
+        stats.max = stats.max.max(elapsed);
+    }
+
+    #[cfg(feature = "poll-metrics")]
+    /// Returns the measurements sorted by the total time in descending order.
+    pub(crate) fn snapshot(&self) -> Vec<PollStats> {
+        let mut snapshot = self.stats.values().copied().collect::<Vec<_>>();
+        snapshot.sort_by_key(|stats| std::cmp::Reverse(stats.total));
+        snapshot
+    }
+
+    #[cfg(feature = "poll-metrics")]
+    pub(crate) fn reset(&mut self) {
+        self.stats.clear();
+    }
+}
+
+#[cfg(all(test, feature = "poll-metrics"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn aggregates_polls_per_field() {
+        let mut metrics = PollMetrics::default();
+        metrics.collect(|| {
+            record("Behaviour", "slow", Duration::from_millis(30));
+            record("Behaviour", "fast", Duration::from_millis(1));
+            record("Behaviour", "slow", Duration::from_millis(10));
+        });
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(
+            snapshot[0],
+            PollStats {
+                behaviour: "Behaviour",
+                field: "slow",
+                polls: 2,
+                total: Duration::from_millis(40),
+                max: Duration::from_millis(30),
+            }
+        );
+        assert_eq!(snapshot[1].field, "fast");
+
+        metrics.reset();
+        assert!(metrics.snapshot().is_empty());
+    }
+
+    #[test]
+    fn metrics_are_kept_per_collector() {
+        let mut outer = PollMetrics::default();
+        let mut inner = PollMetrics::default();
+        outer.collect(|| {
+            record("Outer", "field", Duration::from_millis(1));
+            inner.collect(|| record("Inner", "field", Duration::from_millis(1)));
+        });
+        record("Uncollected", "field", Duration::from_millis(1));
+
+        assert_eq!(outer.snapshot().len(), 1);
+        assert_eq!(outer.snapshot()[0].behaviour, "Outer");
+        assert_eq!(inner.snapshot().len(), 1);
+        assert_eq!(inner.snapshot()[0].behaviour, "Inner");
+    }
+}
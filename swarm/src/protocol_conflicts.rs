@@ -0,0 +1,108 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Detection of protocols claimed by more than one [`ConnectionHandler`](crate::ConnectionHandler)
+//! of a composed behaviour, see [`Config::with_protocol_conflicts`](crate::Config::with_protocol_conflicts).
+
+use std::collections::HashSet;
+use std::{error, fmt};
+
+/// How the [`Swarm`](crate::Swarm) handles a protocol that is claimed by more than one of the
+/// composed [`ConnectionHandler`](crate::ConnectionHandler)s of a connection.
+///
+/// Inbound streams for such a protocol are always routed to the handler that lists it first, i.e.
+/// the one of the behaviour declared first in a `#[derive(NetworkBehaviour)]` struct.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ProtocolConflicts {
+    /// Log a warning the first time a conflict is detected.
+    #[default]
+    Warn,
+    /// Route inbound streams to the first handler without logging.
+    PreferFirst,
+    /// Deny the connection with a [`ProtocolConflict`] error.
+    Deny,
+}
+
+/// The handler of a connection claims protocols more than once, see
+/// [`ProtocolConflicts::Deny`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolConflict {
+    protocols: Vec<String>,
+}
+
+impl ProtocolConflict {
+    /// The protocols that are claimed more than once.
+    pub fn protocols(&self) -> &[String] {
+        &self.protocols
+    }
+}
+
+impl fmt::Display for ProtocolConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "protocols claimed by multiple handlers: {}",
+            self.protocols.join(", ")
+        )
+    }
+}
+
+impl error::Error for ProtocolConflict {}
+
+/// Returns the protocols that appear more than once, in the order of their second occurrence.
+pub(crate) fn find_conflicts<'a>(
+    protocols: impl IntoIterator<Item = &'a [u8]>,
+) -> Option<ProtocolConflict> {
+    let mut seen = HashSet::new();
+    let mut conflicts = Vec::new();
+    for protocol in protocols {
+        if !seen.insert(protocol) {
+            let protocol = String::from_utf8_lossy(protocol).into_owned();
+            if !conflicts.contains(&protocol) {
+                conflicts.push(protocol);
+            }
+        }
+    }
+
+    if conflicts.is_empty() {
+        return None;
+    }
+    Some(ProtocolConflict {
+        protocols: conflicts,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_duplicate_protocols() {
+        let protocols: [&[u8]; 5] = [b"/a", b"/b", b"/a", b"/c", b"/a"];
+        assert_eq!(
+            find_conflicts(protocols).unwrap().protocols(),
+            ["/a".to_owned()]
+        );
+
+        let protocols: [&[u8]; 2] = [b"/a", b"/b"];
+        assert!(find_conflicts(protocols).is_none());
+    }
+}
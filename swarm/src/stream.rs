@@ -8,6 +8,12 @@ use std::{
     task::{Context, Poll},
 };
 
+/// The preferred write size of a [`Stream`] if its muxer does not know it.
+///
+/// The minimum datagram size every QUIC path supports, which also fits into a single TCP segment
+/// on common paths.
+pub const DEFAULT_PREFERRED_WRITE_SIZE: usize = 1200;
+
 /// Counter for the number of active streams on a connection.
 #[derive(Debug, Clone)]
 pub(crate) struct ActiveStreamCounter(Arc<()>);
@@ -30,16 +36,32 @@ impl ActiveStreamCounter {
 pub struct Stream {
     stream: Negotiated<SubstreamBox>,
     counter: Option<ActiveStreamCounter>,
+    preferred_write_size: Option<usize>,
 }
 
 impl Stream {
-    pub(crate) fn new(stream: Negotiated<SubstreamBox>, counter: ActiveStreamCounter) -> Self {
+    pub(crate) fn new(
+        stream: Negotiated<SubstreamBox>,
+        counter: ActiveStreamCounter,
+        preferred_write_size: Option<usize>,
+    ) -> Self {
         Self {
             stream,
             counter: Some(counter),
+            preferred_write_size,
         }
     }
 
+    /// The advisory size in bytes of a single write that avoids fragmentation on the underlying
+    /// connection, as reported by [`StreamMuxer::preferred_write_size`](libp2p_core::muxing::StreamMuxer::preferred_write_size)
+    /// when the stream was opened.
+    ///
+    /// Falls back to [`DEFAULT_PREFERRED_WRITE_SIZE`] if the muxer does not know.
+    pub fn preferred_write_size(&self) -> usize {
+        self.preferred_write_size
+            .unwrap_or(DEFAULT_PREFERRED_WRITE_SIZE)
+    }
+
     /// Ignore this stream in the [Swarm](crate::Swarm)'s connection-keep-alive algorithm.
     ///
     /// By default, any active stream keeps a connection alive. For most protocols,
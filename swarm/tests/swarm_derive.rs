@@ -609,6 +609,95 @@ fn custom_out_event_no_type_parameters() {
     require_net_behaviour::<Behaviour<()>>();
 }
 
+#[test]
+fn notification_is_routed_to_all_behaviours() {
+    use libp2p_identity::PeerId;
+    use libp2p_swarm::{ConnectionId, Notification, ToSwarm};
+    use std::task::Context;
+    use std::task::Poll;
+
+    #[derive(Debug, PartialEq)]
+    struct LowPowerMode(bool);
+
+    #[derive(Default)]
+    struct Adaptive {
+        low_power: bool,
+    }
+
+    impl NetworkBehaviour for Adaptive {
+        type ConnectionHandler = dummy::ConnectionHandler;
+        type ToSwarm = void::Void;
+
+        fn handle_established_inbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: &Multiaddr,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn handle_established_outbound_connection(
+            &mut self,
+            _: ConnectionId,
+            _: PeerId,
+            _: &Multiaddr,
+            _: Endpoint,
+        ) -> Result<THandler<Self>, ConnectionDenied> {
+            Ok(dummy::ConnectionHandler)
+        }
+
+        fn on_connection_handler_event(
+            &mut self,
+            _peer: PeerId,
+            _connection: ConnectionId,
+            message: THandlerOutEvent<Self>,
+        ) {
+            void::unreachable(message);
+        }
+
+        fn poll(
+            &mut self,
+            _: &mut Context<'_>,
+        ) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+            Poll::Pending
+        }
+
+        fn on_swarm_event(&mut self, event: FromSwarm) {
+            if let FromSwarm::Notification(notification) = event {
+                if let Some(LowPowerMode(enabled)) = notification.downcast_ref() {
+                    self.low_power = *enabled;
+                }
+            }
+        }
+    }
+
+    #[allow(dead_code)]
+    #[derive(NetworkBehaviour)]
+    #[behaviour(prelude = "libp2p_swarm::derive_prelude")]
+    struct Foo {
+        a: Adaptive,
+        b: Adaptive,
+    }
+
+    let mut foo = Foo {
+        a: Adaptive::default(),
+        b: Adaptive::default(),
+    };
+
+    foo.on_swarm_event(FromSwarm::Notification(Notification {
+        event: &LowPowerMode(true),
+    }));
+    assert!(foo.a.low_power);
+    assert!(foo.b.low_power);
+
+    // Notifications of other types are ignored.
+    foo.on_swarm_event(FromSwarm::Notification(Notification { event: &false }));
+    assert!(foo.a.low_power);
+    assert!(foo.b.low_power);
+}
+
 #[test]
 fn ui() {
     let t = trybuild::TestCases::new();
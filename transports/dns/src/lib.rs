@@ -55,8 +55,22 @@
 //!
 //![trust-dns-resolver]: https://docs.rs/trust-dns-resolver/latest/trust_dns_resolver/#dns-over-tls-and-dns-over-https
 
+//!
+//! ## Custom resolvers
+//!
+//! The [`ResolverConfig`] given to `custom` selects the upstream name servers and search
+//! domains, e.g. [`ResolverConfig::add_search`]. DNS-over-HTTPS and DNS-over-TLS upstreams like
+//! `ResolverConfig::cloudflare_https` require activating the respective features of
+//! `hickory-resolver`. The [`ResolverOpts`] control the caching policy, e.g. the cache size and
+//! the bounds of the time-to-live of cached answers. A resolver built by other means can be
+//! passed to [`Transport::with_resolver`].
+//!
+//! [`Transport::cache_metrics`] reports how many lookups were answered from the cache.
+
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+mod metrics;
+
 #[cfg(feature = "async-std")]
 pub mod async_std {
     use async_std_resolver::AsyncStdResolver;
@@ -80,9 +94,13 @@ pub mod async_std {
         }
 
         /// Creates a [`Transport`] with a custom resolver configuration and options.
+        ///
+        /// See the [crate documentation](crate) for configuring DNS-over-HTTPS or DNS-over-TLS
+        /// upstreams, search domains and caching.
         pub async fn custom(inner: T, cfg: ResolverConfig, opts: ResolverOpts) -> Transport<T> {
             Transport {
                 inner: Arc::new(Mutex::new(inner)),
+                metrics: Default::default(),
                 resolver: async_std_resolver::resolver(cfg, opts).await,
             }
         }
@@ -92,6 +110,7 @@ pub mod async_std {
         pub fn system2(inner: T) -> Result<Transport<T>, io::Error> {
             Ok(Transport {
                 inner: Arc::new(Mutex::new(inner)),
+                metrics: Default::default(),
                 resolver: async_std_resolver::resolver_from_system_conf()
                     .now_or_never()
                     .expect(
@@ -105,6 +124,7 @@ pub mod async_std {
         pub fn custom2(inner: T, cfg: ResolverConfig, opts: ResolverOpts) -> Transport<T> {
             Transport {
                 inner: Arc::new(Mutex::new(inner)),
+                metrics: Default::default(),
                 resolver: async_std_resolver::resolver(cfg, opts)
                     .now_or_never()
                     .expect("async_std_resolver::resolver did not resolve immediately"),
@@ -132,6 +152,9 @@ pub mod tokio {
 
         /// Creates a [`Transport`] with a custom resolver configuration
         /// and options.
+        ///
+        /// See the [crate documentation](crate) for configuring DNS-over-HTTPS or DNS-over-TLS
+        /// upstreams, search domains and caching.
         pub fn custom(
             inner: T,
             cfg: hickory_resolver::config::ResolverConfig,
@@ -139,6 +162,7 @@ pub mod tokio {
         ) -> Transport<T> {
             Transport {
                 inner: Arc::new(Mutex::new(inner)),
+                metrics: Default::default(),
                 resolver: TokioAsyncResolver::tokio(cfg, opts),
             }
         }
@@ -163,8 +187,10 @@ use std::{
     str,
     sync::Arc,
     task::{Context, Poll},
+    time::Duration,
 };
 
+pub use crate::metrics::CacheMetrics;
 pub use hickory_resolver::config::{ResolverConfig, ResolverOpts};
 pub use hickory_resolver::error::{ResolveError, ResolveErrorKind};
 use hickory_resolver::lookup::{Ipv4Lookup, Ipv6Lookup, TxtLookup};
@@ -198,6 +224,34 @@ pub struct Transport<T, R> {
     inner: Arc<Mutex<T>>,
     /// The DNS resolver used when dialing addresses with DNS components.
     resolver: R,
+    /// Counters of the lookups performed by `resolver`.
+    metrics: Arc<metrics::Recorder>,
+}
+
+impl<T, R> Transport<T, R> {
+    /// Creates a [`Transport`] resolving names with the given resolver.
+    pub fn with_resolver(inner: T, resolver: R) -> Self {
+        Transport {
+            inner: Arc::new(Mutex::new(inner)),
+            resolver,
+            metrics: Default::default(),
+        }
+    }
+
+    /// Returns the counters of the DNS lookups performed so far.
+    pub fn cache_metrics(&self) -> CacheMetrics {
+        self.metrics.snapshot()
+    }
+}
+
+impl<T, C> Transport<T, AsyncResolver<C>>
+where
+    C: ConnectionProvider,
+{
+    /// Removes all answers from the resolver's cache.
+    pub fn clear_cache(&self) {
+        self.resolver.clear_cache();
+    }
 }
 
 impl<T, R> libp2p_core::Transport for Transport<T, R>
@@ -230,6 +284,10 @@ where
         self.inner.lock().remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.inner.lock().remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         self.do_dial(addr, Endpoint::Dialer)
     }
@@ -274,6 +332,7 @@ where
         TransportError<<Self as libp2p_core::Transport>::Error>,
     > {
         let resolver = self.resolver.clone();
+        let metrics = self.metrics.clone();
         let inner = self.inner.clone();
 
         // Asynchronously resolve all DNS names in the address before proceeding
@@ -308,7 +367,7 @@ where
                         continue;
                     }
                     dns_lookups += 1;
-                    match resolve(&name, &resolver).await {
+                    match resolve(&name, &resolver, &metrics).await {
                         Err(e) => {
                             if unresolved.is_empty() {
                                 return Err(e);
@@ -477,10 +536,11 @@ enum Resolved<'a> {
 fn resolve<'a, E: 'a + Send, R: Resolver>(
     proto: &Protocol<'a>,
     resolver: &'a R,
+    metrics: &'a metrics::Recorder,
 ) -> BoxFuture<'a, Result<Resolved<'a>, Error<E>>> {
     match proto {
-        Protocol::Dns(ref name) => resolver
-            .lookup_ip(name.clone().into_owned())
+        Protocol::Dns(ref name) => metrics
+            .observe(resolver.lookup_ip(name.clone().into_owned()))
             .map(move |res| match res {
                 Ok(ips) => {
                     let mut ips = ips.into_iter();
@@ -502,8 +562,8 @@ fn resolve<'a, E: 'a + Send, R: Resolver>(
                 Err(e) => Err(Error::ResolveError(e)),
             })
             .boxed(),
-        Protocol::Dns4(ref name) => resolver
-            .ipv4_lookup(name.clone().into_owned())
+        Protocol::Dns4(ref name) => metrics
+            .observe(resolver.ipv4_lookup(name.clone().into_owned()))
             .map(move |res| match res {
                 Ok(ips) => {
                     let mut ips = ips.into_iter();
@@ -526,8 +586,8 @@ fn resolve<'a, E: 'a + Send, R: Resolver>(
                 Err(e) => Err(Error::ResolveError(e)),
             })
             .boxed(),
-        Protocol::Dns6(ref name) => resolver
-            .ipv6_lookup(name.clone().into_owned())
+        Protocol::Dns6(ref name) => metrics
+            .observe(resolver.ipv6_lookup(name.clone().into_owned()))
             .map(move |res| match res {
                 Ok(ips) => {
                     let mut ips = ips.into_iter();
@@ -552,8 +612,8 @@ fn resolve<'a, E: 'a + Send, R: Resolver>(
             .boxed(),
         Protocol::Dnsaddr(ref name) => {
             let name = [DNSADDR_PREFIX, name].concat();
-            resolver
-                .txt_lookup(name)
+            metrics
+                .observe(resolver.txt_lookup(name))
                 .map(move |res| match res {
                     Ok(txts) => {
                         let mut addrs = Vec::new();
@@ -627,63 +687,69 @@ where
 #[cfg(all(test, any(feature = "tokio", feature = "async-std")))]
 mod tests {
     use super::*;
+    use hickory_resolver::lookup::Lookup;
+    use hickory_resolver::proto::op::Query;
+    use hickory_resolver::proto::rr::rdata::{A, AAAA, TXT};
+    use hickory_resolver::proto::rr::{Name, RData, RecordType};
     use libp2p_core::Transport;
     use libp2p_identity::PeerId;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    #[derive(Clone)]
+    struct CustomTransport;
+
+    impl Transport for CustomTransport {
+        type Output = ();
+        type Error = std::io::Error;
+        type ListenerUpgrade = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+        type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+        fn listen_on(
+            &mut self,
+            _: ListenerId,
+            _: Multiaddr,
+        ) -> Result<(), TransportError<Self::Error>> {
+            unreachable!()
+        }
 
-    #[test]
-    fn basic_resolve() {
-        let _ = tracing_subscriber::fmt()
-            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-            .try_init();
-
-        #[derive(Clone)]
-        struct CustomTransport;
-
-        impl Transport for CustomTransport {
-            type Output = ();
-            type Error = std::io::Error;
-            type ListenerUpgrade = BoxFuture<'static, Result<Self::Output, Self::Error>>;
-            type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
-
-            fn listen_on(
-                &mut self,
-                _: ListenerId,
-                _: Multiaddr,
-            ) -> Result<(), TransportError<Self::Error>> {
-                unreachable!()
-            }
-
-            fn remove_listener(&mut self, _: ListenerId) -> bool {
-                false
-            }
+        fn remove_listener(&mut self, _: ListenerId) -> bool {
+            false
+        }
 
-            fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
-                // Check that all DNS components have been resolved, i.e. replaced.
-                assert!(!addr.iter().any(|p| matches!(
-                    p,
-                    Protocol::Dns(_) | Protocol::Dns4(_) | Protocol::Dns6(_) | Protocol::Dnsaddr(_)
-                )));
-                Ok(Box::pin(future::ready(Ok(()))))
-            }
+        fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+            // Check that all DNS components have been resolved, i.e. replaced.
+            assert!(!addr.iter().any(|p| matches!(
+                p,
+                Protocol::Dns(_) | Protocol::Dns4(_) | Protocol::Dns6(_) | Protocol::Dnsaddr(_)
+            )));
+            Ok(Box::pin(future::ready(Ok(()))))
+        }
 
-            fn dial_as_listener(
-                &mut self,
-                addr: Multiaddr,
-            ) -> Result<Self::Dial, TransportError<Self::Error>> {
-                self.dial(addr)
-            }
+        fn dial_as_listener(
+            &mut self,
+            addr: Multiaddr,
+        ) -> Result<Self::Dial, TransportError<Self::Error>> {
+            self.dial(addr)
+        }
 
-            fn address_translation(&self, _: &Multiaddr, _: &Multiaddr) -> Option<Multiaddr> {
-                None
-            }
+        fn address_translation(&self, _: &Multiaddr, _: &Multiaddr) -> Option<Multiaddr> {
+            None
+        }
 
-            fn poll(
-                self: Pin<&mut Self>,
-                _: &mut Context<'_>,
-            ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
-                unreachable!()
-            }
+        fn poll(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+        ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+            unreachable!()
         }
+    }
+
+    #[test]
+    fn basic_resolve() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
 
         async fn run<T, R>(mut transport: super::Transport<T, R>)
         where
@@ -786,4 +852,93 @@ mod tests {
             rt.block_on(run(tokio::Transport::custom(CustomTransport, config, opts)));
         }
     }
+
+    /// Answers every query with a fixed record, waiting for a name server only on the first
+    /// query of each name and record type, like a caching resolver.
+    #[derive(Clone, Default)]
+    struct StubResolver {
+        cached: Arc<Mutex<HashSet<(String, RecordType)>>>,
+    }
+
+    impl StubResolver {
+        async fn lookup(&self, name: String, rdata: RData) -> Result<Lookup, ResolveError> {
+            if name.ends_with(".invalid") {
+                return Err(ResolveErrorKind::Message("no records found").into());
+            }
+            let query = Query::query(Name::from_str(&name).unwrap(), rdata.record_type());
+
+            let cached = !self.cached.lock().insert((name, query.query_type()));
+            if !cached {
+                let mut waited = false;
+                future::poll_fn(|cx| {
+                    if waited {
+                        return Poll::Ready(());
+                    }
+                    waited = true;
+                    cx.waker().wake_by_ref();
+                    Poll::Pending
+                })
+                .await;
+            }
+
+            Ok(Lookup::from_rdata(query, rdata))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Resolver for StubResolver {
+        async fn lookup_ip(&self, name: String) -> Result<LookupIp, ResolveError> {
+            let rdata = RData::A(A(Ipv4Addr::new(1, 2, 3, 4)));
+            self.lookup(name, rdata).await.map(Into::into)
+        }
+
+        async fn ipv4_lookup(&self, name: String) -> Result<Ipv4Lookup, ResolveError> {
+            let rdata = RData::A(A(Ipv4Addr::new(1, 2, 3, 4)));
+            self.lookup(name, rdata).await.map(Into::into)
+        }
+
+        async fn ipv6_lookup(&self, name: String) -> Result<Ipv6Lookup, ResolveError> {
+            let rdata = RData::AAAA(AAAA(Ipv6Addr::LOCALHOST));
+            self.lookup(name, rdata).await.map(Into::into)
+        }
+
+        async fn txt_lookup(&self, name: String) -> Result<TxtLookup, ResolveError> {
+            let rdata = RData::TXT(TXT::new(vec!["dnsaddr=/ip4/1.2.3.4/tcp/20000".to_owned()]));
+            self.lookup(name, rdata).await.map(Into::into)
+        }
+    }
+
+    #[test]
+    fn cache_metrics() {
+        let mut transport =
+            super::Transport::with_resolver(CustomTransport, StubResolver::default());
+
+        futures::executor::block_on(async {
+            for addr in [
+                "/dns4/example.com/tcp/20000",
+                "/dns4/example.com/tcp/20000",
+                "/dns6/example.com/tcp/20000",
+            ] {
+                transport
+                    .dial(addr.parse().unwrap())
+                    .unwrap()
+                    .await
+                    .unwrap();
+            }
+            assert!(transport
+                .dial("/dns4/example.invalid/tcp/20000".parse().unwrap())
+                .unwrap()
+                .await
+                .is_err());
+        });
+
+        assert_eq!(
+            transport.cache_metrics(),
+            CacheMetrics {
+                lookups: 4,
+                cache_hits: 1,
+                failures: 1,
+            }
+        );
+    }
 }
@@ -0,0 +1,76 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::future;
+use parking_lot::Mutex;
+use std::future::Future;
+use std::pin::pin;
+
+/// Counters of the DNS lookups performed by a [`Transport`](crate::Transport),
+/// see [`Transport::cache_metrics`](crate::Transport::cache_metrics).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheMetrics {
+    /// The number of lookups performed.
+    pub lookups: u64,
+    /// The number of successful lookups answered without waiting for a name server,
+    /// i.e. from the resolver's cache or hosts file.
+    pub cache_hits: u64,
+    /// The number of failed lookups, including those without records.
+    pub failures: u64,
+}
+
+/// Records the outcome of lookups.
+///
+/// The resolver does not report whether an answer was cached. A cached answer is however
+/// available immediately, whereas a name server has to be waited for otherwise.
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    metrics: Mutex<CacheMetrics>,
+}
+
+impl Recorder {
+    /// Performs the `lookup`, recording its outcome.
+    pub(crate) async fn observe<T, E>(
+        &self,
+        lookup: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let mut lookup = pin!(lookup);
+        let mut waited = false;
+        let result = future::poll_fn(|cx| {
+            let poll = lookup.as_mut().poll(cx);
+            waited |= poll.is_pending();
+            poll
+        })
+        .await;
+
+        let mut metrics = self.metrics.lock();
+        metrics.lookups += 1;
+        match result {
+            Ok(_) if !waited => metrics.cache_hits += 1,
+            Ok(_) => {}
+            Err(_) => metrics.failures += 1,
+        }
+        result
+    }
+
+    pub(crate) fn snapshot(&self) -> CacheMetrics {
+        *self.metrics.lock()
+    }
+}
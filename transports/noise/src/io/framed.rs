@@ -35,6 +35,9 @@ use std::mem::size_of;
 const MAX_NOISE_MSG_LEN: usize = 65535;
 /// Space given to the encryption buffer to hold key material.
 const EXTRA_ENCRYPT_SPACE: usize = 1024;
+/// Space given to the encryption buffer to hold the key material of handshake messages, e.g.
+/// the ML-KEM-1024 encapsulation key and ciphertext of 1568 bytes each of the hybrid handshake.
+const EXTRA_HANDSHAKE_ENCRYPT_SPACE: usize = 4096;
 /// Max. length for Noise protocol message payloads.
 pub(crate) const MAX_FRAME_LEN: usize = MAX_NOISE_MSG_LEN - EXTRA_ENCRYPT_SPACE;
 static_assertions::const_assert! {
@@ -114,6 +117,7 @@ impl Encoder for Codec<snow::HandshakeState> {
             &self.write_buffer[..item_size],
             dst,
             &mut self.encrypt_buffer,
+            EXTRA_HANDSHAKE_ENCRYPT_SPACE,
             |item, buffer| self.session.write_message(item, buffer),
         )?;
 
@@ -151,9 +155,13 @@ impl Encoder for Codec<snow::TransportState> {
     type Item<'a> = &'a [u8];
 
     fn encode(&mut self, item: Self::Item<'_>, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        encrypt(item, dst, &mut self.encrypt_buffer, |item, buffer| {
-            self.session.write_message(item, buffer)
-        })
+        encrypt(
+            item,
+            dst,
+            &mut self.encrypt_buffer,
+            EXTRA_ENCRYPT_SPACE,
+            |item, buffer| self.session.write_message(item, buffer),
+        )
     }
 }
 
@@ -175,11 +183,12 @@ fn encrypt(
     cleartext: &[u8],
     dst: &mut BytesMut,
     encrypt_buffer: &mut BytesMut,
+    extra_space: usize,
     encrypt_fn: impl FnOnce(&[u8], &mut [u8]) -> Result<usize, snow::Error>,
 ) -> io::Result<()> {
     tracing::trace!("Encrypting {} bytes", cleartext.len());
 
-    encrypt_buffer.resize(cleartext.len() + EXTRA_ENCRYPT_SPACE, 0);
+    encrypt_buffer.resize(cleartext.len() + extra_space, 0);
     let n = encrypt_fn(cleartext, encrypt_buffer).map_err(into_io_error)?;
 
     tracing::trace!("Outgoing ciphertext has {n} bytes");
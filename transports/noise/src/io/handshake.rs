@@ -30,7 +30,7 @@ pub(super) mod proto {
 use super::framed::Codec;
 use crate::io::Output;
 use crate::protocol::{KeypairIdentity, PublicKey, STATIC_KEY_DOMAIN};
-use crate::Error;
+use crate::{Error, MAX_EARLY_DATA_LEN};
 use asynchronous_codec::Framed;
 use futures::prelude::*;
 use libp2p_identity as identity;
@@ -57,11 +57,14 @@ pub(crate) struct State<T> {
     responder_webtransport_certhashes: Option<HashSet<Multihash<64>>>,
     /// The received extensions of the remote, if any.
     remote_extensions: Option<Extensions>,
+    /// The early data to attach to the local handshake message identifying the local node.
+    early_data: Vec<u8>,
 }
 
 /// Extensions
 struct Extensions {
     webtransport_certhashes: HashSet<Multihash<64>>,
+    early_data: Vec<u8>,
 }
 
 impl<T> State<T>
@@ -80,6 +83,7 @@ where
         identity: KeypairIdentity,
         expected_remote_key: Option<identity::PublicKey>,
         responder_webtransport_certhashes: Option<HashSet<Multihash<64>>>,
+        early_data: Vec<u8>,
     ) -> Self {
         Self {
             identity,
@@ -88,6 +92,7 @@ where
             id_remote_pubkey: expected_remote_key,
             responder_webtransport_certhashes,
             remote_extensions: None,
+            early_data,
         }
     }
 }
@@ -98,8 +103,13 @@ where
 {
     /// Finish a handshake, yielding the established remote identity and the
     /// [`Output`] for communicating on the encrypted channel.
-    pub(crate) fn finish(self) -> Result<(identity::PublicKey, Output<T>), Error> {
+    pub(crate) fn finish(mut self) -> Result<(identity::PublicKey, Output<T>), Error> {
         let is_initiator = self.io.codec().is_initiator();
+        let remote_early_data = self
+            .remote_extensions
+            .as_mut()
+            .map(|ext| mem::take(&mut ext.early_data))
+            .unwrap_or_default();
 
         let (pubkey, framed) = map_into_transport(self.io)?;
 
@@ -139,7 +149,7 @@ where
             }
         }
 
-        Ok((id_pk, Output::new(framed)))
+        Ok((id_pk, Output::new(framed, remote_early_data)))
     }
 }
 
@@ -175,6 +185,7 @@ impl From<proto::NoiseExtensions> for Extensions {
                 .into_iter()
                 .filter_map(|bytes| Multihash::read(&bytes[..]).ok())
                 .collect(),
+            early_data: value.early_data,
         }
     }
 }
@@ -232,6 +243,13 @@ where
     }
 
     if let Some(extensions) = pb.extensions {
+        if extensions.early_data.len() > MAX_EARLY_DATA_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Early data exceeds maximum size.",
+            )
+            .into());
+        }
         state.remote_extensions = Some(extensions.into());
     }
 
@@ -261,6 +279,13 @@ where
         }
     }
 
+    if !state.early_data.is_empty() {
+        pb.extensions
+            .get_or_insert_with(proto::NoiseExtensions::default)
+            .early_data
+            .clone_from(&state.early_data);
+    }
+
     state.io.send(&pb).await?;
 
     Ok(())
@@ -51,6 +51,14 @@
 //! # }
 //! ```
 //!
+//! # Post-quantum hybrid handshake
+//!
+//! With the `pq-hybrid` feature, [`Config::with_pq_hybrid`] enables the `XXhfs` handshake
+//! pattern, mixing the shared secret of an ML-KEM-1024 (FIPS 203) key encapsulation into the
+//! session keys in addition to the X25519 key agreement. The session keys remain secure as long
+//! as either of the two is unbroken. The hybrid handshake is negotiated via
+//! [`PQ_HYBRID_PROTOCOL_NAME`] and only interoperates with nodes supporting it.
+//!
 //! [noise]: http://noiseprotocol.org/
 
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
@@ -62,6 +70,8 @@ pub use io::Output;
 
 use crate::handshake::State;
 use crate::io::handshake;
+#[cfg(feature = "pq-hybrid")]
+use crate::protocol::PARAMS_XXHFS;
 use crate::protocol::{noise_params_into_builder, AuthenticKeypair, Keypair, PARAMS_XX};
 use futures::prelude::*;
 use libp2p_core::upgrade::{InboundConnectionUpgrade, OutboundConnectionUpgrade};
@@ -75,11 +85,34 @@ use std::collections::HashSet;
 use std::fmt::Write;
 use std::pin::Pin;
 
+/// The maximum size of the early data attached to a handshake message, see
+/// [`Config::with_early_data`].
+pub const MAX_EARLY_DATA_LEN: usize = 1024;
+
+/// The protocol name of the noise handshake using the `XX` pattern with X25519.
+pub const PROTOCOL_NAME: &str = "/noise";
+
+/// The protocol name of the hybrid noise handshake using the `XXhfs` pattern with X25519 and
+/// ML-KEM-1024, see [`Config::with_pq_hybrid`].
+pub const PQ_HYBRID_PROTOCOL_NAME: &str = "/noise/xxhfs-25519-mlkem1024";
+
+/// Whether the hybrid post-quantum handshake is offered alongside or instead of the classic
+/// one, see [`Config::with_pq_hybrid`].
+#[cfg(feature = "pq-hybrid")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PqHybrid {
+    /// Offer the hybrid handshake, falling back to the classic one with nodes not supporting it.
+    Preferred,
+    /// Only accept the hybrid handshake, failing the negotiation with nodes not supporting it.
+    Required,
+}
+
 /// The configuration for the noise handshake.
 #[derive(Clone)]
 pub struct Config {
     dh_keys: AuthenticKeypair,
-    params: NoiseParams,
+    /// The supported protocol names with the parameters of their handshake, by preference.
+    protocols: Vec<(&'static str, NoiseParams)>,
     webtransport_certhashes: Option<HashSet<Multihash<64>>>,
 
     /// Prologue to use in the noise handshake.
@@ -89,6 +122,9 @@ pub struct Config {
     ///
     /// For further information, see <https://noiseprotocol.org/noise.html#prologue>.
     prologue: Vec<u8>,
+
+    /// Application data to attach to the handshake message identifying the local node.
+    early_data: Vec<u8>,
 }
 
 impl Config {
@@ -98,9 +134,10 @@ impl Config {
 
         Ok(Self {
             dh_keys: noise_keys,
-            params: PARAMS_XX.clone(),
+            protocols: vec![(PROTOCOL_NAME, PARAMS_XX.clone())],
             webtransport_certhashes: None,
             prologue: vec![],
+            early_data: vec![],
         })
     }
 
@@ -110,6 +147,38 @@ impl Config {
         self
     }
 
+    /// Set application data to attach to the encrypted handshake message identifying the local
+    /// node, e.g. a token or version, saving a round trip after the upgrade.
+    ///
+    /// The remote reads it via [`Output::remote_early_data`]. Unlike the rest of the session,
+    /// the early data of the responder is sent before the initiator authenticated itself.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `early_data` is longer than [`MAX_EARLY_DATA_LEN`].
+    pub fn with_early_data(mut self, early_data: Vec<u8>) -> Self {
+        assert!(
+            early_data.len() <= MAX_EARLY_DATA_LEN,
+            "early data must not exceed {MAX_EARLY_DATA_LEN} bytes"
+        );
+        self.early_data = early_data;
+        self
+    }
+
+    /// Enable the hybrid post-quantum handshake, mixing an ML-KEM-1024 key encapsulation into the
+    /// session keys, negotiated via [`PQ_HYBRID_PROTOCOL_NAME`].
+    ///
+    /// The handshake messages grow by about 3 KiB.
+    #[cfg(feature = "pq-hybrid")]
+    pub fn with_pq_hybrid(mut self, mode: PqHybrid) -> Self {
+        let hybrid = (PQ_HYBRID_PROTOCOL_NAME, PARAMS_XXHFS.clone());
+        match mode {
+            PqHybrid::Preferred => self.protocols.insert(0, hybrid),
+            PqHybrid::Required => self.protocols = vec![hybrid],
+        }
+        self
+    }
+
     /// Set WebTransport certhashes extension.
     ///
     /// In case of initiator, these certhashes will be used to validate the ones reported by
@@ -121,14 +190,23 @@ impl Config {
         self
     }
 
-    fn into_responder<S: AsyncRead + AsyncWrite>(self, socket: S) -> Result<State<S>, Error> {
-        let session = noise_params_into_builder(
-            self.params,
-            &self.prologue,
-            self.dh_keys.keypair.secret(),
-            None,
-        )
-        .build_responder()?;
+    /// The parameters of the handshake negotiated via the given protocol name.
+    fn params(&self, protocol: &str) -> NoiseParams {
+        self.protocols
+            .iter()
+            .find(|(name, _)| *name == protocol)
+            .map(|(_, params)| params.clone())
+            .unwrap_or_else(|| PARAMS_XX.clone())
+    }
+
+    fn into_responder<S: AsyncRead + AsyncWrite>(
+        self,
+        socket: S,
+        params: NoiseParams,
+    ) -> Result<State<S>, Error> {
+        let session =
+            noise_params_into_builder(params, &self.prologue, self.dh_keys.keypair.secret(), None)
+                .build_responder()?;
 
         let state = State::new(
             socket,
@@ -136,19 +214,20 @@ impl Config {
             self.dh_keys.identity,
             None,
             self.webtransport_certhashes,
+            self.early_data,
         );
 
         Ok(state)
     }
 
-    fn into_initiator<S: AsyncRead + AsyncWrite>(self, socket: S) -> Result<State<S>, Error> {
-        let session = noise_params_into_builder(
-            self.params,
-            &self.prologue,
-            self.dh_keys.keypair.secret(),
-            None,
-        )
-        .build_initiator()?;
+    fn into_initiator<S: AsyncRead + AsyncWrite>(
+        self,
+        socket: S,
+        params: NoiseParams,
+    ) -> Result<State<S>, Error> {
+        let session =
+            noise_params_into_builder(params, &self.prologue, self.dh_keys.keypair.secret(), None)
+                .build_initiator()?;
 
         let state = State::new(
             socket,
@@ -156,6 +235,7 @@ impl Config {
             self.dh_keys.identity,
             None,
             self.webtransport_certhashes,
+            self.early_data,
         );
 
         Ok(state)
@@ -164,10 +244,14 @@ impl Config {
 
 impl UpgradeInfo for Config {
     type Info = &'static str;
-    type InfoIter = std::iter::Once<Self::Info>;
+    type InfoIter = std::vec::IntoIter<Self::Info>;
 
     fn protocol_info(&self) -> Self::InfoIter {
-        std::iter::once("/noise")
+        self.protocols
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .into_iter()
     }
 }
 
@@ -179,9 +263,10 @@ where
     type Error = Error;
     type Future = Pin<Box<dyn Future<Output = Result<Self::Output, Self::Error>> + Send>>;
 
-    fn upgrade_inbound(self, socket: T, _: Self::Info) -> Self::Future {
+    fn upgrade_inbound(self, socket: T, info: Self::Info) -> Self::Future {
         async move {
-            let mut state = self.into_responder(socket)?;
+            let params = self.params(info);
+            let mut state = self.into_responder(socket, params)?;
 
             handshake::recv_empty(&mut state).await?;
             handshake::send_identity(&mut state).await?;
@@ -203,9 +288,10 @@ where
     type Error = Error;
     type Future = Pin<Box<dyn Future<Output = Result<Self::Output, Self::Error>> + Send>>;
 
-    fn upgrade_outbound(self, socket: T, _: Self::Info) -> Self::Future {
+    fn upgrade_outbound(self, socket: T, info: Self::Info) -> Self::Future {
         async move {
-            let mut state = self.into_initiator(socket)?;
+            let params = self.params(info);
+            let mut state = self.into_initiator(socket, params)?;
 
             handshake::send_empty(&mut state).await?;
             handshake::recv_identity(&mut state).await?;
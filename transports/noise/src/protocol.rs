@@ -37,6 +37,28 @@ pub(crate) static PARAMS_XX: Lazy<NoiseParams> = Lazy::new(|| {
         .expect("Invalid protocol name")
 });
 
+/// Parameters of the hybrid `XXhfs` handshake.
+///
+/// `snow` only knows the round 3 Kyber1024 KEM by name, which it uses to select the KEM from the
+/// `Resolver`. The latter provides ML-KEM-1024 instead, hence the protocol name is given
+/// explicitly, as it is mixed into the handshake hash.
+#[cfg(feature = "pq-hybrid")]
+pub(crate) static PARAMS_XXHFS: Lazy<NoiseParams> = Lazy::new(|| {
+    let params: NoiseParams = "Noise_XXhfs_25519+Kyber1024_ChaChaPoly_SHA256"
+        .parse()
+        .expect("Invalid protocol name");
+
+    NoiseParams::new(
+        "Noise_XXhfs_25519+MLKEM1024_ChaChaPoly_SHA256".to_owned(),
+        params.base,
+        params.handshake,
+        params.dh,
+        params.kem,
+        params.cipher,
+        params.hash,
+    )
+});
+
 pub(crate) fn noise_params_into_builder<'b>(
     params: NoiseParams,
     prologue: &'b [u8],
@@ -162,7 +184,8 @@ impl AsRef<[u8]> for PublicKey {
 /// Custom `snow::CryptoResolver` which delegates to either the
 /// `RingResolver` on native or the `DefaultResolver` on wasm
 /// for hash functions and symmetric ciphers, while using x25519-dalek
-/// for Curve25519 DH.
+/// for Curve25519 DH and, with the `pq-hybrid` feature, the `ml-kem` crate for the
+/// ML-KEM-1024 KEM.
 struct Resolver;
 
 impl snow::resolvers::CryptoResolver for Resolver {
@@ -205,6 +228,13 @@ impl snow::resolvers::CryptoResolver for Resolver {
             snow::resolvers::RingResolver.resolve_cipher(choice)
         }
     }
+
+    #[cfg(feature = "pq-hybrid")]
+    fn resolve_kem(&self, choice: &snow::params::KemChoice) -> Option<Box<dyn snow::types::Kem>> {
+        match choice {
+            snow::params::KemChoice::Kyber1024 => Some(Box::<MlKem>::default()),
+        }
+    }
 }
 
 /// Wrapper around a CSPRNG to implement `snow::Random` trait for.
@@ -289,6 +319,70 @@ impl snow::types::Dh for Keypair {
     }
 }
 
+/// ML-KEM-1024 key encapsulation of the hybrid handshake.
+#[cfg(feature = "pq-hybrid")]
+#[derive(Default)]
+struct MlKem {
+    /// The decapsulation key and the encoded encapsulation key, once generated.
+    keys: Option<(
+        <ml_kem::MlKem1024 as ml_kem::KemCore>::DecapsulationKey,
+        Vec<u8>,
+    )>,
+}
+
+#[cfg(feature = "pq-hybrid")]
+impl snow::types::Kem for MlKem {
+    fn name(&self) -> &'static str {
+        "MLKEM1024"
+    }
+    fn pub_len(&self) -> usize {
+        1568
+    }
+    fn ciphertext_len(&self) -> usize {
+        1568
+    }
+    fn shared_secret_len(&self) -> usize {
+        32
+    }
+
+    fn generate(&mut self, mut rng: &mut dyn snow::types::Random) {
+        use ml_kem::{EncodedSizeUser as _, KemCore as _};
+
+        let (dk, ek) = ml_kem::MlKem1024::generate(&mut rng);
+        self.keys = Some((dk, ek.as_bytes().to_vec()));
+    }
+
+    fn pubkey(&self) -> &[u8] {
+        self.keys.as_ref().map_or(&[], |(_, ek)| ek.as_slice())
+    }
+
+    fn encapsulate(
+        &self,
+        pubkey: &[u8],
+        shared_secret_out: &mut [u8],
+        ciphertext_out: &mut [u8],
+    ) -> Result<(usize, usize), ()> {
+        use ml_kem::{kem::Encapsulate as _, EncodedSizeUser as _};
+
+        let encoded = pubkey.try_into().map_err(|_| ())?;
+        let ek = <ml_kem::MlKem1024 as ml_kem::KemCore>::EncapsulationKey::from_bytes(&encoded);
+        let (ciphertext, shared_secret) = ek.encapsulate(&mut rand::rngs::OsRng).map_err(|_| ())?;
+        shared_secret_out[..shared_secret.len()].copy_from_slice(&shared_secret);
+        ciphertext_out[..ciphertext.len()].copy_from_slice(&ciphertext);
+        Ok((shared_secret.len(), ciphertext.len()))
+    }
+
+    fn decapsulate(&self, ciphertext: &[u8], shared_secret_out: &mut [u8]) -> Result<usize, ()> {
+        use ml_kem::kem::Decapsulate as _;
+
+        let (dk, _) = self.keys.as_ref().ok_or(())?;
+        let ciphertext = ciphertext.try_into().map_err(|_| ())?;
+        let shared_secret = dk.decapsulate(&ciphertext).map_err(|_| ())?;
+        shared_secret_out[..shared_secret.len()].copy_from_slice(&shared_secret);
+        Ok(shared_secret.len())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
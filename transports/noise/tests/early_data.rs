@@ -0,0 +1,55 @@
+use libp2p_core::upgrade::{InboundConnectionUpgrade, OutboundConnectionUpgrade};
+use libp2p_identity as identity;
+use libp2p_noise as noise;
+
+#[test]
+fn early_data_is_exchanged() {
+    let (client_output, server_output) =
+        handshake(b"client-token".to_vec(), b"server-v2".to_vec()).unwrap();
+
+    assert_eq!(client_output.remote_early_data(), b"server-v2");
+    assert_eq!(server_output.remote_early_data(), b"client-token");
+}
+
+#[test]
+fn early_data_is_optional() {
+    let (client_output, server_output) = handshake(vec![], b"server-v2".to_vec()).unwrap();
+
+    assert_eq!(client_output.remote_early_data(), b"server-v2");
+    assert!(server_output.remote_early_data().is_empty());
+}
+
+#[test]
+#[should_panic(expected = "early data must not exceed")]
+fn early_data_is_limited() {
+    let id = identity::Keypair::generate_ed25519();
+
+    let _ = noise::Config::new(&id)
+        .unwrap()
+        .with_early_data(vec![0; noise::MAX_EARLY_DATA_LEN + 1]);
+}
+
+type Output = noise::Output<futures_ringbuf::Endpoint>;
+
+fn handshake(
+    client_early_data: Vec<u8>,
+    server_early_data: Vec<u8>,
+) -> Result<(Output, Output), noise::Error> {
+    let client_id = identity::Keypair::generate_ed25519();
+    let server_id = identity::Keypair::generate_ed25519();
+
+    let (client, server) = futures_ringbuf::Endpoint::pair(100, 100);
+
+    futures::executor::block_on(async move {
+        let client_config = noise::Config::new(&client_id)?.with_early_data(client_early_data);
+        let server_config = noise::Config::new(&server_id)?.with_early_data(server_early_data);
+
+        let ((_, server_output), (_, client_output)) = futures::future::try_join(
+            server_config.upgrade_inbound(server, ""),
+            client_config.upgrade_outbound(client, ""),
+        )
+        .await?;
+
+        Ok((client_output, server_output))
+    })
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "pq-hybrid")]
+
+use futures::prelude::*;
+use libp2p_core::upgrade::{InboundConnectionUpgrade, OutboundConnectionUpgrade};
+use libp2p_core::UpgradeInfo;
+use libp2p_identity as identity;
+use libp2p_noise as noise;
+
+#[test]
+fn hybrid_handshake_establishes_session() {
+    let (client_config, server_config) = configs(noise::PqHybrid::Required);
+
+    futures::executor::block_on(async move {
+        let (mut client_output, mut server_output) = handshake(
+            client_config,
+            server_config,
+            noise::PQ_HYBRID_PROTOCOL_NAME,
+            noise::PQ_HYBRID_PROTOCOL_NAME,
+        )
+        .await
+        .unwrap();
+
+        client_output.write_all(b"hello").await.unwrap();
+        client_output.flush().await.unwrap();
+        let mut buf = [0; 5];
+        server_output.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+    });
+}
+
+#[test]
+fn hybrid_handshake_is_offered_by_preference() {
+    let id = identity::Keypair::generate_ed25519();
+
+    let preferred = noise::Config::new(&id)
+        .unwrap()
+        .with_pq_hybrid(noise::PqHybrid::Preferred);
+    assert_eq!(
+        preferred.protocol_info().collect::<Vec<_>>(),
+        [noise::PQ_HYBRID_PROTOCOL_NAME, noise::PROTOCOL_NAME]
+    );
+
+    let required = noise::Config::new(&id)
+        .unwrap()
+        .with_pq_hybrid(noise::PqHybrid::Required);
+    assert_eq!(
+        required.protocol_info().collect::<Vec<_>>(),
+        [noise::PQ_HYBRID_PROTOCOL_NAME]
+    );
+}
+
+#[test]
+fn hybrid_handshake_fails_against_classic_handshake() {
+    let (client_config, server_config) = configs(noise::PqHybrid::Preferred);
+
+    let result = futures::executor::block_on(handshake(
+        client_config,
+        server_config,
+        noise::PQ_HYBRID_PROTOCOL_NAME,
+        noise::PROTOCOL_NAME,
+    ));
+
+    assert!(result.is_err());
+}
+
+type Output = noise::Output<futures_ringbuf::Endpoint>;
+
+fn configs(mode: noise::PqHybrid) -> (noise::Config, noise::Config) {
+    let client_id = identity::Keypair::generate_ed25519();
+    let server_id = identity::Keypair::generate_ed25519();
+
+    (
+        noise::Config::new(&client_id).unwrap().with_pq_hybrid(mode),
+        noise::Config::new(&server_id).unwrap().with_pq_hybrid(mode),
+    )
+}
+
+async fn handshake(
+    client_config: noise::Config,
+    server_config: noise::Config,
+    client_protocol: &'static str,
+    server_protocol: &'static str,
+) -> Result<(Output, Output), noise::Error> {
+    let (client, server) = futures_ringbuf::Endpoint::pair(4096, 4096);
+
+    let ((_, server_output), (_, client_output)) = futures::future::try_join(
+        server_config.upgrade_inbound(server, server_protocol),
+        client_config.upgrade_outbound(client, client_protocol),
+    )
+    .await?;
+
+    Ok((client_output, server_output))
+}
@@ -51,6 +51,10 @@ const KEY_SIZE: usize = 32;
 const NONCE_SIZE: usize = 24;
 const WRITE_BUFFER_SIZE: usize = 1024;
 const FINGERPRINT_SIZE: usize = 16;
+/// The multistream-select header the remote sends first on every libp2p connection, used to
+/// identify the key of the remote if more than one is accepted. The pnet protocol only exchanges
+/// nonces, so there is no explicit way for the remote to tell which key it uses.
+const MULTISTREAM_HEADER: &[u8] = b"\x13/multistream/1.0.0\n";
 
 /// A pre-shared key, consisting of 32 bytes of random data.
 #[derive(Copy, Clone, PartialEq, Eq)]
@@ -192,10 +196,33 @@ impl error::Error for KeyParseError {
 pub struct PnetConfig {
     /// the PreSharedKey to use for encryption
     key: PreSharedKey,
+    /// Another PreSharedKey the remote may use for encryption, if any.
+    additional_key: Option<PreSharedKey>,
 }
 impl PnetConfig {
     pub fn new(key: PreSharedKey) -> Self {
-        Self { key }
+        Self {
+            key,
+            additional_key: None,
+        }
+    }
+
+    /// Accept traffic of remotes encrypted with `key` in addition to the key passed to
+    /// [`PnetConfig::new`], which is still used to encrypt local traffic.
+    ///
+    /// The key of the remote is identified by decrypting the multistream-select header it sends
+    /// first on every libp2p connection, so this requires the connection to be upgraded with
+    /// multistream-select, as [`libp2p_core::upgrade`](https://docs.rs/libp2p-core) does. If the
+    /// first bytes of the remote decrypt to the header with neither key, the key passed to
+    /// [`PnetConfig::new`] is assumed. This allows rotating the key of a private network without
+    /// downtime:
+    ///
+    /// 1. Configure all nodes with the old key, accepting the new key in addition.
+    /// 2. Configure all nodes with the new key, accepting the old key in addition.
+    /// 3. Configure all nodes with only the new key.
+    pub fn with_additional_key(mut self, key: PreSharedKey) -> Self {
+        self.additional_key = Some(key);
+        self
     }
 
     /// upgrade a connection to use pre shared key encryption.
@@ -225,7 +252,18 @@ impl PnetConfig {
         tracing::trace!("setting up ciphers");
         let write_cipher = XSalsa20::new(&self.key.0.into(), &local_nonce.into());
         let read_cipher = XSalsa20::new(&self.key.0.into(), &remote_nonce.into());
-        Ok(PnetOutput::new(socket, write_cipher, read_cipher))
+        let key_probe = self.additional_key.map(|additional_key| KeyProbe {
+            key: self.key,
+            additional_key,
+            remote_nonce,
+            received: Vec::new(),
+        });
+        Ok(PnetOutput::new(
+            socket,
+            write_cipher,
+            read_cipher,
+            key_probe,
+        ))
     }
 }
 
@@ -236,24 +274,110 @@ pub struct PnetOutput<S> {
     #[pin]
     inner: CryptWriter<S>,
     read_cipher: XSalsa20,
+    /// Identifies the key of the remote if an additional key is accepted.
+    key_probe: Option<KeyProbe>,
+    /// The decrypted bytes of the probe not yet read.
+    decrypted: Vec<u8>,
 }
 
 impl<S: AsyncRead + AsyncWrite> PnetOutput<S> {
-    fn new(inner: S, write_cipher: XSalsa20, read_cipher: XSalsa20) -> Self {
+    fn new(
+        inner: S,
+        write_cipher: XSalsa20,
+        read_cipher: XSalsa20,
+        key_probe: Option<KeyProbe>,
+    ) -> Self {
         Self {
             inner: CryptWriter::with_capacity(WRITE_BUFFER_SIZE, inner, write_cipher),
             read_cipher,
+            key_probe,
+            decrypted: Vec::new(),
         }
     }
 }
 
+/// The state of identifying whether the remote encrypts with the additional key, based on the
+/// first bytes it sends.
+struct KeyProbe {
+    key: PreSharedKey,
+    additional_key: PreSharedKey,
+    remote_nonce: [u8; NONCE_SIZE],
+    /// The encrypted bytes received so far.
+    received: Vec<u8>,
+}
+
+impl KeyProbe {
+    /// Whether the bytes received so far decrypt to a prefix of the multistream-select header
+    /// with the given key.
+    fn matches(&self, key: &PreSharedKey) -> bool {
+        let mut decrypted = self.received.clone();
+        self.cipher(key).apply_keystream(&mut decrypted);
+        MULTISTREAM_HEADER.starts_with(&decrypted)
+    }
+
+    fn cipher(&self, key: &PreSharedKey) -> XSalsa20 {
+        XSalsa20::new(&key.0.into(), &self.remote_nonce.into())
+    }
+}
+
 impl<S: AsyncRead + AsyncWrite> AsyncRead for PnetOutput<S> {
     fn poll_read(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
         buf: &mut [u8],
     ) -> Poll<Result<usize, io::Error>> {
-        let this = self.project();
+        let mut this = self.project();
+
+        if let Some(probe) = this.key_probe.as_mut() {
+            // Read until the bytes received so far decrypt to a prefix of the multistream-select
+            // header with only one of the keys, which usually takes a single byte.
+            let uses_additional_key = loop {
+                let mut chunk = [0u8; MULTISTREAM_HEADER.len()];
+                let remaining = MULTISTREAM_HEADER.len() - probe.received.len();
+                let n = futures::ready!(this
+                    .inner
+                    .as_mut()
+                    .get_pin_mut()
+                    .poll_read(cx, &mut chunk[..remaining]))?;
+                if n == 0 {
+                    if probe.received.is_empty() {
+                        return Poll::Ready(Ok(0));
+                    }
+                    break false;
+                }
+                probe.received.extend_from_slice(&chunk[..n]);
+
+                let matches_key = probe.matches(&probe.key);
+                let matches_additional_key = probe.matches(&probe.additional_key);
+                if !matches_key && !matches_additional_key {
+                    tracing::debug!(
+                        "remote did not start with multistream-select, assuming the pre-shared key"
+                    );
+                }
+                if !(matches_key && matches_additional_key)
+                    || probe.received.len() == MULTISTREAM_HEADER.len()
+                {
+                    break matches_additional_key && !matches_key;
+                }
+            };
+
+            let probe = this.key_probe.take().expect("checked above");
+            if uses_additional_key {
+                tracing::debug!("remote uses the additional pre-shared key");
+                *this.read_cipher = probe.cipher(&probe.additional_key);
+            }
+            let mut decrypted = probe.received;
+            this.read_cipher.apply_keystream(&mut decrypted);
+            *this.decrypted = decrypted;
+        }
+
+        if !this.decrypted.is_empty() {
+            let n = buf.len().min(this.decrypted.len());
+            buf[..n].copy_from_slice(&this.decrypted[..n]);
+            this.decrypted.drain(..n);
+            return Poll::Ready(Ok(n));
+        }
+
         let result = this.inner.get_pin_mut().poll_read(cx, buf);
         if let Poll::Ready(Ok(size)) = &result {
             tracing::trace!(bytes=%size, "read bytes");
@@ -372,4 +496,60 @@ mod tests {
         let actual = key.fingerprint().to_string();
         assert_eq!(expected, actual);
     }
+
+    /// Reads `plaintext` sent by a remote encrypting with `remote_key` on a connection accepting
+    /// `key` and `additional_key`.
+    fn read_from_remote(
+        key: PreSharedKey,
+        additional_key: PreSharedKey,
+        remote_key: PreSharedKey,
+        plaintext: &[u8],
+    ) -> Vec<u8> {
+        let nonce = [7u8; NONCE_SIZE];
+        let mut encrypted = plaintext.to_vec();
+        XSalsa20::new(&remote_key.0.into(), &nonce.into()).apply_keystream(&mut encrypted);
+
+        let mut output = PnetOutput::new(
+            futures::io::Cursor::new(encrypted),
+            XSalsa20::new(&key.0.into(), &[0u8; NONCE_SIZE].into()),
+            XSalsa20::new(&key.0.into(), &nonce.into()),
+            Some(KeyProbe {
+                key,
+                additional_key,
+                remote_nonce: nonce,
+                received: Vec::new(),
+            }),
+        );
+        let mut read = Vec::new();
+        futures::executor::block_on(output.read_to_end(&mut read)).unwrap();
+        read
+    }
+
+    #[test]
+    fn identifies_key_of_remote() {
+        let key = PreSharedKey([1; KEY_SIZE]);
+        let additional_key = PreSharedKey([2; KEY_SIZE]);
+        let plaintext = [MULTISTREAM_HEADER, b"\x07/noise\n"].concat();
+
+        for remote_key in [key, additional_key] {
+            assert_eq!(
+                read_from_remote(key, additional_key, remote_key, &plaintext),
+                plaintext
+            );
+        }
+    }
+
+    #[test]
+    fn falls_back_to_key_without_multistream_header() {
+        let key = PreSharedKey([1; KEY_SIZE]);
+        let additional_key = PreSharedKey([2; KEY_SIZE]);
+
+        // Shorter and longer than the multistream-select header.
+        for plaintext in [&b"hello"[..], &[42; 64]] {
+            assert_eq!(
+                read_from_remote(key, additional_key, key, plaintext),
+                plaintext
+            );
+        }
+    }
 }
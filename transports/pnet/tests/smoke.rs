@@ -15,6 +15,8 @@ async fn can_establish_connection_memory() {
     can_establish_connection_inner_with_timeout(
         MemoryTransport::default,
         Protocol::Memory(0).into(),
+        default_pnet(),
+        default_pnet(),
     )
     .await
 }
@@ -24,6 +26,8 @@ async fn can_establish_connection_tcp() {
     can_establish_connection_inner_with_timeout(
         libp2p_tcp::tokio::Transport::default,
         "/ip4/127.0.0.1/tcp/0".parse().unwrap(),
+        default_pnet(),
+        default_pnet(),
     )
     .await
 }
@@ -33,13 +37,44 @@ async fn can_establish_connection_websocket() {
     can_establish_connection_inner_with_timeout(
         || libp2p_websocket::WsConfig::new(libp2p_tcp::tokio::Transport::default()),
         "/ip4/127.0.0.1/tcp/0/ws".parse().unwrap(),
+        default_pnet(),
+        default_pnet(),
     )
     .await
 }
 
+#[tokio::test]
+async fn can_establish_connection_during_key_rotation() {
+    let old_key = PreSharedKey::new([0; 32]);
+    let new_key = PreSharedKey::new([1; 32]);
+
+    // A node that already switched to the new key dials one still using the old key.
+    can_establish_connection_inner_with_timeout(
+        MemoryTransport::default,
+        Protocol::Memory(0).into(),
+        PnetConfig::new(old_key).with_additional_key(new_key),
+        PnetConfig::new(new_key).with_additional_key(old_key),
+    )
+    .await;
+    // A node that dropped the old key dials one that still accepts it.
+    can_establish_connection_inner_with_timeout(
+        MemoryTransport::default,
+        Protocol::Memory(0).into(),
+        PnetConfig::new(new_key).with_additional_key(old_key),
+        PnetConfig::new(new_key),
+    )
+    .await;
+}
+
+fn default_pnet() -> PnetConfig {
+    PnetConfig::new(PreSharedKey::new([0; 32]))
+}
+
 async fn can_establish_connection_inner_with_timeout<F, T>(
     build_transport: F,
     listen_addr: Multiaddr,
+    listener_pnet: PnetConfig,
+    dialer_pnet: PnetConfig,
 ) where
     F: Fn() -> T,
     T: Transport + Send + Unpin + 'static,
@@ -48,12 +83,17 @@ async fn can_establish_connection_inner_with_timeout<F, T>(
     <T as libp2p_core::Transport>::ListenerUpgrade: Send,
     <T as libp2p_core::Transport>::Dial: Send,
 {
-    let task = can_establish_connection_inner(build_transport, listen_addr);
+    let task =
+        can_establish_connection_inner(build_transport, listen_addr, listener_pnet, dialer_pnet);
     tokio::time::timeout(TIMEOUT, task).await.unwrap();
 }
 
-async fn can_establish_connection_inner<F, T>(build_transport: F, listen_addr: Multiaddr)
-where
+async fn can_establish_connection_inner<F, T>(
+    build_transport: F,
+    listen_addr: Multiaddr,
+    listener_pnet: PnetConfig,
+    dialer_pnet: PnetConfig,
+) where
     F: Fn() -> T,
     T: Transport + Send + Unpin + 'static,
     <T as libp2p_core::Transport>::Error: Send + Sync + 'static,
@@ -61,10 +101,8 @@ where
     <T as libp2p_core::Transport>::ListenerUpgrade: Send,
     <T as libp2p_core::Transport>::Dial: Send,
 {
-    let pnet = PnetConfig::new(PreSharedKey::new([0; 32]));
-
-    let mut swarm1 = make_swarm(build_transport(), pnet);
-    let mut swarm2 = make_swarm(build_transport(), pnet);
+    let mut swarm1 = make_swarm(build_transport(), listener_pnet);
+    let mut swarm2 = make_swarm(build_transport(), dialer_pnet);
 
     let listen_address = listen_on(&mut swarm1, listen_addr).await;
     swarm2.dial(listen_address).unwrap();
@@ -18,7 +18,10 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
+use libp2p_core::transport::PortConflictPolicy;
+use libp2p_identity::PeerId;
 use quinn::{
+    congestion,
     crypto::rustls::{QuicClientConfig, QuicServerConfig},
     MtuDiscoveryConfig, VarInt,
 };
@@ -60,6 +63,83 @@ pub struct Config {
     /// As client the version is chosen based on the remote's address.
     pub support_draft_29: bool,
 
+    /// What to do if the port of a listen address is already in use.
+    ///
+    /// Defaults to [`PortConflictPolicy::Fail`].
+    pub port_conflict_policy: PortConflictPolicy,
+
+    /// Server name indication (SNI) sent in the TLS `ClientHello` when dialing.
+    ///
+    /// Defaults to [`ServerName::Placeholder`].
+    pub server_name: ServerName,
+
+    /// Which server names are accepted on inbound connections.
+    ///
+    /// Defaults to [`InboundServerNamePolicy::AcceptAll`].
+    pub inbound_server_names: InboundServerNamePolicy,
+
+    /// Allow remotes to migrate inbound connections to a new path, e.g. when switching from
+    /// Wi-Fi to LTE, instead of dropping them.
+    ///
+    /// The new path is validated before it is used. Once the active path of a connection
+    /// changed, the connection reports the new remote address, surfacing as
+    /// [`FromSwarm::AddressChange`](https://docs.rs/libp2p-swarm/latest/libp2p_swarm/behaviour/enum.FromSwarm.html#variant.AddressChange)
+    /// in the swarm.
+    ///
+    /// Dialers survive local address changes as long as the remote allows migration.
+    ///
+    /// Defaults to `false`.
+    pub connection_migration: bool,
+
+    /// Whether to resume TLS sessions with peers dialed before, see [`SessionResumption`].
+    ///
+    /// Defaults to [`SessionResumption::Disabled`].
+    pub session_resumption: SessionResumption,
+
+    /// Whether listeners accept 0-RTT data of dialers resuming a session, see
+    /// [`SessionResumption::ZeroRtt`].
+    ///
+    /// 0-RTT data is not protected against replay: an attacker who recorded the first flight of
+    /// a connection can send it again, and the requests on the streams opened in it are
+    /// processed once more. Only enable this if the protocols used on the first streams of a
+    /// connection are idempotent. If disabled, dialers fall back to a regular handshake and
+    /// resend their data afterwards.
+    ///
+    /// Defaults to `false`.
+    pub accept_zero_rtt: bool,
+
+    /// Whether listeners on a single IP address follow changes of the network interfaces.
+    ///
+    /// If enabled, such a listener reports its address as expired via
+    /// [`TransportEvent::AddressExpired`](libp2p_core::transport::TransportEvent::AddressExpired)
+    /// once the address disappears from all interfaces. The socket of the endpoint stays bound
+    /// in the meantime, so once the address reappears, the listener accepts connections on the
+    /// same port again, keeping its connections, and the address is reported via
+    /// [`TransportEvent::NewAddress`](libp2p_core::transport::TransportEvent::NewAddress).
+    ///
+    /// Listeners on a wildcard address always report the addresses of appearing and
+    /// disappearing interfaces.
+    ///
+    /// Defaults to `false`.
+    pub rebind_on_interface_change: bool,
+
+    /// Congestion control algorithm of connections.
+    ///
+    /// Defaults to [`CongestionController::Cubic`].
+    pub congestion_controller: CongestionController,
+
+    /// Initial congestion window in bytes.
+    ///
+    /// Larger windows speed up the start of bulk transfers on high-bandwidth paths at the risk
+    /// of causing loss on congested ones. If `None`, the default of the
+    /// [`Config::congestion_controller`] is used.
+    pub initial_congestion_window: Option<u64>,
+
+    /// Round-trip time assumed before it is measured on a connection.
+    ///
+    /// If `None`, the default of [`quinn::TransportConfig::initial_rtt`] is used.
+    pub initial_rtt: Option<Duration>,
+
     /// TLS client config for the inner [`quinn::ClientConfig`].
     client_tls_config: Arc<QuicClientConfig>,
     /// TLS server config for the inner [`quinn::ServerConfig`].
@@ -85,6 +165,16 @@ impl Config {
             client_tls_config,
             server_tls_config,
             support_draft_29: false,
+            port_conflict_policy: PortConflictPolicy::Fail,
+            server_name: ServerName::default(),
+            inbound_server_names: InboundServerNamePolicy::default(),
+            connection_migration: false,
+            session_resumption: SessionResumption::default(),
+            accept_zero_rtt: false,
+            rebind_on_interface_change: false,
+            congestion_controller: CongestionController::default(),
+            initial_congestion_window: None,
+            initial_rtt: None,
             handshake_timeout: Duration::from_secs(5),
             max_idle_timeout: 10 * 1000,
             max_concurrent_stream_limit: 256,
@@ -113,12 +203,119 @@ impl Config {
     }
 }
 
+/// Server name indication (SNI) sent when dialing, see [`Config::server_name`].
+///
+/// Peers are authenticated through their libp2p certificate, so the name is never verified.
+/// It only changes how the `ClientHello` looks on the wire.
+/// Encrypted Client Hello (ECH) is not supported by the TLS backend used by this crate.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ServerName {
+    /// Send the placeholder name `l`, as done by all libp2p QUIC implementations.
+    #[default]
+    Placeholder,
+    /// Send the given DNS name, e.g. to look like a connection to a regular web server.
+    Domain(String),
+    /// Omit the SNI extension from the `ClientHello`.
+    Omitted,
+}
+
+/// Congestion control algorithm, see [`Config::congestion_controller`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CongestionController {
+    /// CUBIC (RFC 8312), reducing the window on loss.
+    #[default]
+    Cubic,
+    /// BBR, estimating bandwidth and round-trip time instead of reacting to loss.
+    ///
+    /// Experimental in [`quinn`], but may achieve higher throughput on lossy paths.
+    Bbr,
+    /// NewReno (RFC 6582), the most conservative algorithm.
+    NewReno,
+}
+
+/// Whether TLS sessions are resumed when reconnecting to a peer, see [`Config::session_resumption`].
+///
+/// Sessions are only resumed when dialing an address ending with `/p2p/<peer-id>`. The session
+/// tickets of each peer are kept separately, and only tickets of sessions in which the remote
+/// proved to be that peer are used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SessionResumption {
+    /// Perform a full handshake on every connection.
+    #[default]
+    Disabled,
+    /// Resume the session of a previous connection to the peer, if any.
+    Enabled,
+    /// Like [`SessionResumption::Enabled`], but also send data in the first flight (0-RTT),
+    /// reporting the connection as established before the handshake completed.
+    ///
+    /// 0-RTT data can be replayed by an attacker, thus only enable this if the protocols used
+    /// on the first streams of a connection are idempotent. Listeners only accept it with
+    /// [`Config::accept_zero_rtt`]. If the remote rejects the 0-RTT data, reading from or
+    /// writing to the streams opened before the handshake completed fails with an
+    /// [`std::io::Error`] wrapping [`ZeroRttRejected`](crate::ZeroRttRejected).
+    ZeroRtt,
+}
+
+/// Which server names are accepted on inbound connections, see [`Config::inbound_server_names`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum InboundServerNamePolicy {
+    /// Accept connections regardless of the server name.
+    #[default]
+    AcceptAll,
+    /// Only accept connections whose server name is one of the given names.
+    ///
+    /// Connections without a server name are refused.
+    OneOf(Vec<String>),
+}
+
+impl InboundServerNamePolicy {
+    pub(crate) fn accepts(&self, server_name: Option<&str>) -> bool {
+        match self {
+            InboundServerNamePolicy::AcceptAll => true,
+            InboundServerNamePolicy::OneOf(names) => {
+                server_name.is_some_and(|name| names.iter().any(|n| n == name))
+            }
+        }
+    }
+}
+
 /// Represents the inner configuration for [`quinn`].
 #[derive(Debug, Clone)]
 pub(crate) struct QuinnConfig {
     pub(crate) client_config: quinn::ClientConfig,
     pub(crate) server_config: quinn::ServerConfig,
     pub(crate) endpoint_config: quinn::EndpointConfig,
+    /// Name passed to [`quinn::Endpoint::connect_with`].
+    pub(crate) server_name: String,
+    /// Builds the client configs of peers whose sessions are resumed, if enabled.
+    pub(crate) session_resumption: Option<ResumptionConfig>,
+}
+
+/// Builds a client config per peer, see [`Config::session_resumption`].
+#[derive(Debug, Clone)]
+pub(crate) struct ResumptionConfig {
+    keypair: libp2p_identity::Keypair,
+    transport: Arc<quinn::TransportConfig>,
+    enable_sni: bool,
+    /// Whether to send data before the handshake completed.
+    pub(crate) zero_rtt: bool,
+}
+
+impl ResumptionConfig {
+    /// Creates the client config for dialing `peer_id`.
+    ///
+    /// Each config stores its own session tickets, so the tickets of a peer are never offered
+    /// to another one. The remote has to prove to be `peer_id` before a ticket is stored.
+    pub(crate) fn client_config(&self, peer_id: PeerId) -> quinn::ClientConfig {
+        let mut crypto = libp2p_tls::make_client_config(&self.keypair, Some(peer_id)).unwrap();
+        crypto.enable_sni = self.enable_sni;
+        crypto.enable_early_data = self.zero_rtt;
+
+        let mut client_config =
+            quinn::ClientConfig::new(Arc::new(QuicClientConfig::try_from(crypto).unwrap()));
+        client_config.transport_config(Arc::clone(&self.transport));
+        client_config
+    }
 }
 
 impl From<Config> for QuinnConfig {
@@ -132,6 +329,16 @@ impl From<Config> for QuinnConfig {
             max_connection_data,
             max_stream_data,
             support_draft_29,
+            port_conflict_policy: _,
+            server_name,
+            inbound_server_names: _,
+            connection_migration,
+            session_resumption,
+            accept_zero_rtt,
+            rebind_on_interface_change: _,
+            congestion_controller,
+            initial_congestion_window,
+            initial_rtt,
             handshake_timeout: _,
             keypair,
             mtu_discovery_config,
@@ -148,17 +355,74 @@ impl From<Config> for QuinnConfig {
         transport.stream_receive_window(max_stream_data.into());
         transport.receive_window(max_connection_data.into());
         transport.mtu_discovery_config(mtu_discovery_config);
+        if let Some(initial_rtt) = initial_rtt {
+            transport.initial_rtt(initial_rtt);
+        }
+        transport.congestion_controller_factory(match congestion_controller {
+            CongestionController::Cubic => {
+                let mut config = congestion::CubicConfig::default();
+                if let Some(window) = initial_congestion_window {
+                    config.initial_window(window);
+                }
+                Arc::new(config) as Arc<dyn congestion::ControllerFactory + Send + Sync>
+            }
+            CongestionController::Bbr => {
+                let mut config = congestion::BbrConfig::default();
+                if let Some(window) = initial_congestion_window {
+                    config.initial_window(window);
+                }
+                Arc::new(config)
+            }
+            CongestionController::NewReno => {
+                let mut config = congestion::NewRenoConfig::default();
+                if let Some(window) = initial_congestion_window {
+                    config.initial_window(window);
+                }
+                Arc::new(config)
+            }
+        });
         let transport = Arc::new(transport);
 
+        let server_tls_config = if accept_zero_rtt {
+            let mut crypto = libp2p_tls::make_server_config(&keypair).unwrap();
+            // QUIC requires the maximum to be `u32::MAX` if early data is accepted.
+            crypto.max_early_data_size = u32::MAX;
+            Arc::new(QuicServerConfig::try_from(crypto).unwrap())
+        } else {
+            server_tls_config
+        };
         let mut server_config = quinn::ServerConfig::with_crypto(server_tls_config);
         server_config.transport = Arc::clone(&transport);
-        // Disables connection migration.
-        // Long-term this should be enabled, however we then need to handle address change
-        // on connections in the `Connection`.
-        server_config.migration(false);
+        // Address changes of migrated connections are reported by the `Connection`.
+        server_config.migration(connection_migration);
+
+        let enable_sni = !matches!(server_name, ServerName::Omitted);
+        let client_tls_config = match server_name {
+            ServerName::Omitted => {
+                let mut crypto = libp2p_tls::make_client_config(&keypair, None).unwrap();
+                crypto.enable_sni = false;
+                Arc::new(QuicClientConfig::try_from(crypto).unwrap())
+            }
+            ServerName::Placeholder | ServerName::Domain(_) => client_tls_config,
+        };
+        // An empty string is an invalid domain name, thus `"l"` is used if no name is sent.
+        let server_name = match server_name {
+            ServerName::Domain(name) => name,
+            ServerName::Placeholder | ServerName::Omitted => "l".to_owned(),
+        };
 
         let mut client_config = quinn::ClientConfig::new(client_tls_config);
-        client_config.transport_config(transport);
+        client_config.transport_config(Arc::clone(&transport));
+
+        let session_resumption = match session_resumption {
+            SessionResumption::Disabled => None,
+            SessionResumption::Enabled | SessionResumption::ZeroRtt => Some(ResumptionConfig {
+                keypair: keypair.clone(),
+                transport,
+                enable_sni,
+                zero_rtt: session_resumption == SessionResumption::ZeroRtt,
+            }),
+        };
 
         let mut endpoint_config = keypair
             .derive_secret(b"libp2p quic stateless reset key")
@@ -176,6 +440,8 @@ impl From<Config> for QuinnConfig {
             client_config,
             server_config,
             endpoint_config,
+            server_name,
+            session_resumption,
         }
     }
 }
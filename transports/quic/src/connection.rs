@@ -24,15 +24,22 @@ mod stream;
 pub use connecting::Connecting;
 pub use stream::Stream;
 
+use crate::transport::{socketaddr_to_multiaddr, ProtocolVersion};
 use crate::{ConnectionError, Error};
 
 use futures::{future::BoxFuture, FutureExt};
-use libp2p_core::muxing::{StreamMuxer, StreamMuxerEvent};
+use futures_timer::Delay;
+use libp2p_core::muxing::{MuxerCounters, StreamMuxer, StreamMuxerEvent, StreamPriority};
 use std::{
+    net::SocketAddr,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
+/// How often the path of a connection is checked for changes, see [`PathMonitor`].
+const PATH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 /// State for a single opened QUIC connection.
 pub struct Connection {
     /// Underlying connection.
@@ -47,6 +54,40 @@ pub struct Connection {
     >,
     /// Future to wait for the connection to be closed.
     closing: Option<BoxFuture<'static, quinn::ConnectionError>>,
+    /// Detects migrations of the connection to a new path, if enabled.
+    path: Option<PathMonitor>,
+    /// Resolves once the handshake of a connection established with 0-RTT completed, to whether
+    /// the remote accepted the 0-RTT data.
+    zero_rtt: Option<BoxFuture<'static, bool>>,
+    /// The counters of the connection, see [`StreamMuxer::counters`].
+    counters: MuxerCounters,
+}
+
+/// Statistics of a QUIC connection, see [`Connection::stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionStats {
+    /// Current best estimate of the round-trip time.
+    pub rtt: Duration,
+    /// Current congestion window in bytes.
+    pub congestion_window: u64,
+    /// Number of times the congestion window was reduced.
+    pub congestion_events: u64,
+    /// Number of packets sent.
+    pub sent_packets: u64,
+    /// Number of packets lost.
+    pub lost_packets: u64,
+    /// Number of bytes lost.
+    pub lost_bytes: u64,
+}
+
+/// Tracks the remote address of a connection that may migrate.
+///
+/// [`quinn`] does not notify about path changes, thus the remote address is checked periodically.
+struct PathMonitor {
+    /// The remote address of the active path last reported.
+    remote_address: SocketAddr,
+    version: ProtocolVersion,
+    check: Delay,
 }
 
 impl Connection {
@@ -54,14 +95,52 @@ impl Connection {
     ///
     /// This function assumes that the [`quinn::Connection`] is completely fresh and none of
     /// its methods has ever been called. Failure to comply might lead to logic errors and panics.
-    fn new(connection: quinn::Connection) -> Self {
+    pub(crate) fn new(connection: quinn::Connection) -> Self {
         Self {
             connection,
             incoming: None,
             outgoing: None,
             closing: None,
+            path: None,
+            zero_rtt: None,
+            counters: MuxerCounters::default(),
+        }
+    }
+
+    /// Track whether the remote accepted the 0-RTT data of a connection whose handshake is
+    /// still in progress.
+    ///
+    /// Streams opened before the handshake completed fail if the 0-RTT data is rejected.
+    pub(crate) fn with_zero_rtt(mut self, accepted: quinn::ZeroRttAccepted) -> Self {
+        self.zero_rtt = Some(accepted.boxed());
+        self
+    }
+
+    /// Statistics of the connection's active path.
+    ///
+    /// Read them before the connection is boxed, e.g. by mapping the output of the transport.
+    pub fn stats(&self) -> ConnectionStats {
+        let path = self.connection.stats().path;
+        ConnectionStats {
+            rtt: path.rtt,
+            congestion_window: path.cwnd,
+            congestion_events: path.congestion_events,
+            sent_packets: path.sent_packets,
+            lost_packets: path.lost_packets,
+            lost_bytes: path.lost_bytes,
         }
     }
+
+    /// Report a change of the connection's active path through
+    /// [`StreamMuxerEvent::AddressChange`].
+    pub(crate) fn with_path_monitoring(mut self, version: ProtocolVersion) -> Self {
+        self.path = Some(PathMonitor {
+            remote_address: self.connection.remote_address(),
+            version,
+            check: Delay::new(PATH_CHECK_INTERVAL),
+        });
+        self
+    }
 }
 
 impl StreamMuxer for Connection {
@@ -81,13 +160,21 @@ impl StreamMuxer for Connection {
 
         let (send, recv) = futures::ready!(incoming.poll_unpin(cx)).map_err(ConnectionError)?;
         this.incoming.take();
-        let stream = Stream::new(send, recv);
+        let stream = Stream::new(send, recv, this.counters.new_stream());
         Poll::Ready(Ok(stream))
     }
 
     fn poll_outbound(
         self: Pin<&mut Self>,
         cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        self.poll_outbound_with_priority(cx, StreamPriority::Normal)
+    }
+
+    fn poll_outbound_with_priority(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        priority: StreamPriority,
     ) -> Poll<Result<Self::Substream, Self::Error>> {
         let this = self.get_mut();
 
@@ -96,19 +183,66 @@ impl StreamMuxer for Connection {
             async move { connection.open_bi().await }.boxed()
         });
 
-        let (send, recv) = futures::ready!(outgoing.poll_unpin(cx)).map_err(ConnectionError)?;
+        let result = futures::ready!(outgoing.poll_unpin(cx));
         this.outgoing.take();
-        let stream = Stream::new(send, recv);
+        let (send, recv) = match result {
+            Ok(streams) => streams,
+            Err(error) => {
+                this.counters.record_open_failure();
+                return Poll::Ready(Err(Error::Connection(ConnectionError(error))));
+            }
+        };
+        // Quinn sends the data of streams with a higher priority first.
+        let quinn_priority = match priority {
+            StreamPriority::Low => -1,
+            StreamPriority::Normal => 0,
+            StreamPriority::High => 1,
+        };
+        // Fails only if the stream was already closed.
+        let _ = send.set_priority(quinn_priority);
+        let stream = Stream::new(send, recv, this.counters.new_stream());
         Poll::Ready(Ok(stream))
     }
 
     fn poll(
         self: Pin<&mut Self>,
-        _cx: &mut Context<'_>,
+        cx: &mut Context<'_>,
     ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
-        // TODO: If connection migration is enabled (currently disabled) address
-        // change on the connection needs to be handled.
-        Poll::Pending
+        let this = self.get_mut();
+
+        if let Some(zero_rtt) = this.zero_rtt.as_mut() {
+            if let Poll::Ready(accepted) = zero_rtt.poll_unpin(cx) {
+                this.zero_rtt = None;
+                if !accepted {
+                    tracing::debug!(
+                        remote=%this.connection.remote_address(),
+                        "Remote rejected 0-RTT data, streams opened before the handshake failed"
+                    );
+                }
+            }
+        }
+
+        let Some(path) = this.path.as_mut() else {
+            return Poll::Pending;
+        };
+
+        loop {
+            futures::ready!(path.check.poll_unpin(cx));
+            path.check.reset(PATH_CHECK_INTERVAL);
+
+            let remote_address = this.connection.remote_address();
+            if remote_address != path.remote_address {
+                tracing::debug!(
+                    old=%path.remote_address,
+                    new=%remote_address,
+                    "QUIC connection migrated to a new path"
+                );
+                path.remote_address = remote_address;
+                return Poll::Ready(Ok(StreamMuxerEvent::AddressChange(
+                    socketaddr_to_multiaddr(&remote_address, path.version),
+                )));
+            }
+        }
     }
 
     fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
@@ -128,4 +262,14 @@ impl StreamMuxer for Connection {
 
         Poll::Ready(Ok(()))
     }
+
+    fn preferred_write_size(&self) -> Option<usize> {
+        // The payload of a datagram fits into a single packet on the current path, as does a
+        // write to a stream of the same size.
+        self.connection.max_datagram_size()
+    }
+
+    fn counters(&self) -> Option<MuxerCounters> {
+        Some(self.counters.clone())
+    }
 }
@@ -20,7 +20,9 @@
 
 //! Future that drives a QUIC connection until is has performed its TLS handshake.
 
-use crate::{Connection, ConnectionError, Error};
+use crate::{
+    config::InboundServerNamePolicy, transport::ProtocolVersion, Connection, ConnectionError, Error,
+};
 
 use futures::{
     future::{select, Either, FutureExt, Select},
@@ -31,6 +33,7 @@ use libp2p_identity::PeerId;
 use quinn::rustls::pki_types::CertificateDer;
 use std::{
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
     time::Duration,
 };
@@ -39,14 +42,33 @@ use std::{
 #[derive(Debug)]
 pub struct Connecting {
     connecting: Select<quinn::Connecting, Delay>,
+    /// Policy for the server name sent by the remote, only set for inbound connections.
+    server_name_policy: Option<Arc<InboundServerNamePolicy>>,
+    /// QUIC version of the connection, if changes of its path are reported.
+    path_monitoring: Option<ProtocolVersion>,
 }
 
 impl Connecting {
     pub(crate) fn new(connection: quinn::Connecting, timeout: Duration) -> Self {
         Connecting {
             connecting: select(connection, Delay::new(timeout)),
+            server_name_policy: None,
+            path_monitoring: None,
         }
     }
+
+    /// Refuse the connection once established if its server name is rejected by `policy`.
+    pub(crate) fn with_server_name_policy(mut self, policy: Arc<InboundServerNamePolicy>) -> Self {
+        self.server_name_policy = Some(policy);
+        self
+    }
+
+    /// Report changes of the connection's path once established, see
+    /// [`Config::connection_migration`](crate::Config::connection_migration).
+    pub(crate) fn with_path_monitoring(mut self, version: ProtocolVersion) -> Self {
+        self.path_monitoring = Some(version);
+        self
+    }
 }
 
 impl Connecting {
@@ -65,6 +87,15 @@ impl Connecting {
             .expect("the certificate was validated during TLS handshake; qed");
         p2p_cert.peer_id()
     }
+
+    /// Returns the server name the remote sent in its `ClientHello`, if any.
+    fn server_name(connection: &quinn::Connection) -> Option<String> {
+        connection
+            .handshake_data()?
+            .downcast::<quinn::crypto::rustls::HandshakeData>()
+            .ok()?
+            .server_name
+    }
 }
 
 impl Future for Connecting {
@@ -76,8 +107,26 @@ impl Future for Connecting {
             Either::Left((connection, _)) => connection.map_err(ConnectionError)?,
         };
 
+        if let Some(policy) = self.server_name_policy.as_ref() {
+            let server_name = Self::server_name(&connection);
+            if !policy.accepts(server_name.as_deref()) {
+                tracing::debug!(
+                    remote_address=%connection.remote_address(),
+                    ?server_name,
+                    "Refusing inbound connection with rejected server name"
+                );
+                connection.close(From::from(0u32), b"server name rejected");
+                return Poll::Ready(Err(Error::Connection(ConnectionError(
+                    quinn::ConnectionError::LocallyClosed,
+                ))));
+            }
+        }
+
         let peer_id = Self::remote_peer_id(&connection);
-        let muxer = Connection::new(connection);
+        let mut muxer = Connection::new(connection);
+        if let Some(version) = self.path_monitoring {
+            muxer = muxer.with_path_monitoring(version);
+        }
         Poll::Ready(Ok((peer_id, muxer)))
     }
 }
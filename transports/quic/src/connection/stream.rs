@@ -25,6 +25,9 @@ use std::{
 };
 
 use futures::{AsyncRead, AsyncWrite};
+use libp2p_core::muxing::StreamCounters;
+
+use crate::ZeroRttRejected;
 
 /// A single stream on a connection
 pub struct Stream {
@@ -34,14 +37,21 @@ pub struct Stream {
     recv: quinn::RecvStream,
     /// Whether the stream is closed or not
     close_result: Option<Result<(), io::ErrorKind>>,
+    /// The counters of the stream, see [`StreamMuxer::counters`](libp2p_core::StreamMuxer::counters).
+    counters: StreamCounters,
 }
 
 impl Stream {
-    pub(super) fn new(send: quinn::SendStream, recv: quinn::RecvStream) -> Self {
+    pub(super) fn new(
+        send: quinn::SendStream,
+        recv: quinn::RecvStream,
+        counters: StreamCounters,
+    ) -> Self {
         Self {
             send,
             recv,
             close_result: None,
+            counters,
         }
     }
 }
@@ -57,7 +67,10 @@ impl AsyncRead for Stream {
                 return Poll::Ready(Ok(0));
             }
         }
-        Pin::new(&mut self.recv).poll_read(cx, buf)
+        let num_bytes = futures::ready!(Pin::new(&mut self.recv).poll_read(cx, buf))
+            .map_err(map_zero_rtt_rejected)?;
+        self.counters.record_inbound(num_bytes);
+        Poll::Ready(Ok(num_bytes))
     }
 }
 
@@ -67,13 +80,20 @@ impl AsyncWrite for Stream {
         cx: &mut Context,
         buf: &[u8],
     ) -> Poll<io::Result<usize>> {
-        Pin::new(&mut self.send)
-            .poll_write(cx, buf)
-            .map_err(Into::into)
+        let Poll::Ready(result) = Pin::new(&mut self.send).poll_write(cx, buf) else {
+            // Quinn only blocks writes while the flow-control or congestion window is exhausted.
+            self.counters.record_window_stall();
+            return Poll::Pending;
+        };
+        let num_bytes = result.map_err(|e| map_zero_rtt_rejected(e.into()))?;
+        self.counters.record_outbound(num_bytes);
+        Poll::Ready(Ok(num_bytes))
     }
 
     fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.send).poll_flush(cx)
+        Pin::new(&mut self.send)
+            .poll_flush(cx)
+            .map_err(map_zero_rtt_rejected)
     }
 
     fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<io::Result<()>> {
@@ -81,8 +101,27 @@ impl AsyncWrite for Stream {
             // For some reason poll_close needs to be 'fuse'able
             return Poll::Ready(close_result.map_err(Into::into));
         }
-        let close_result = futures::ready!(Pin::new(&mut self.send).poll_close(cx));
+        let close_result =
+            futures::ready!(Pin::new(&mut self.send).poll_close(cx)).map_err(map_zero_rtt_rejected);
         self.close_result = Some(close_result.as_ref().map_err(|e| e.kind()).copied());
         Poll::Ready(close_result)
     }
 }
+
+/// Replaces the error of a stream whose 0-RTT data the remote rejected with [`ZeroRttRejected`].
+fn map_zero_rtt_rejected(error: io::Error) -> io::Error {
+    let rejected = error.get_ref().is_some_and(|inner| {
+        matches!(
+            inner.downcast_ref::<quinn::ReadError>(),
+            Some(quinn::ReadError::ZeroRttRejected)
+        ) || matches!(
+            inner.downcast_ref::<quinn::WriteError>(),
+            Some(quinn::WriteError::ZeroRttRejected)
+        )
+    });
+    if rejected {
+        return io::Error::new(io::ErrorKind::ConnectionReset, ZeroRttRejected);
+    }
+
+    error
+}
@@ -65,8 +65,10 @@ mod transport;
 
 use std::net::SocketAddr;
 
-pub use config::Config;
-pub use connection::{Connecting, Connection, Stream};
+pub use config::{
+    Config, CongestionController, InboundServerNamePolicy, ServerName, SessionResumption,
+};
+pub use connection::{Connecting, Connection, ConnectionStats, Stream};
 
 #[cfg(feature = "async-std")]
 pub use provider::async_std;
@@ -108,6 +110,15 @@ pub enum Error {
 #[error(transparent)]
 pub struct ConnectError(quinn::ConnectError);
 
+/// The remote rejected the 0-RTT data of a stream opened before the handshake completed, see
+/// [`SessionResumption::ZeroRtt`].
+///
+/// Reading from or writing to such a [`Stream`] fails with an [`std::io::Error`] of kind
+/// [`std::io::ErrorKind::ConnectionReset`] wrapping this error.
+#[derive(Debug, thiserror::Error)]
+#[error("The remote rejected the 0-RTT data of the stream")]
+pub struct ZeroRttRejected;
+
 /// Error on an established [`Connection`].
 #[derive(Debug, thiserror::Error)]
 #[error(transparent)]
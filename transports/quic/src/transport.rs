@@ -18,7 +18,7 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use crate::config::{Config, QuinnConfig};
+use crate::config::{Config, InboundServerNamePolicy, QuinnConfig};
 use crate::hole_punching::hole_puncher;
 use crate::provider::Provider;
 use crate::{ConnectError, Connecting, Connection, Error};
@@ -26,22 +26,25 @@ use crate::{ConnectError, Connecting, Connection, Error};
 use futures::channel::oneshot;
 use futures::future::{BoxFuture, Either};
 use futures::ready;
-use futures::stream::StreamExt;
+use futures::stream::{FuturesUnordered, StreamExt};
 use futures::{prelude::*, stream::SelectAll};
 
 use if_watch::IfEvent;
 
 use libp2p_core::{
     multiaddr::{Multiaddr, Protocol},
-    transport::{ListenerId, TransportError, TransportEvent},
+    transport::{ListenerId, PortConflictPolicy, TransportError, TransportEvent},
     Transport,
 };
 use libp2p_identity::PeerId;
+use lru::LruCache;
 use socket2::{Domain, Socket, Type};
 use std::collections::hash_map::{DefaultHasher, Entry};
 use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, UdpSocket};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
 use std::time::Duration;
 use std::{fmt, io};
 use std::{
@@ -50,6 +53,10 @@ use std::{
     task::{Context, Poll, Waker},
 };
 
+/// Maximum number of peers whose client configs, and thus session tickets, are kept. The least
+/// recently dialed peer is evicted first.
+const MAX_SESSION_CONFIGS: usize = 256;
+
 /// Implementation of the [`Transport`] trait for QUIC.
 ///
 /// By default only QUIC Version 1 (RFC 9000) is supported. In the [`Multiaddr`] this maps to
@@ -69,14 +76,27 @@ pub struct GenTransport<P: Provider> {
     handshake_timeout: Duration,
     /// Whether draft-29 is supported for dialing and listening.
     support_draft_29: bool,
+    /// What to do if a listen port is already in use.
+    port_conflict_policy: PortConflictPolicy,
+    /// Which server names are accepted on inbound connections.
+    inbound_server_names: Arc<InboundServerNamePolicy>,
+    /// Whether remotes may migrate inbound connections to a new path.
+    connection_migration: bool,
+    /// Whether listeners on a single interface are re-bound once its address reappears.
+    rebind_on_interface_change: bool,
     /// Streams of active [`Listener`]s.
     listeners: SelectAll<Listener<P>>,
+    /// Endpoints of gracefully removed listeners, closed once idle or their timeout elapsed.
+    draining_endpoints: FuturesUnordered<BoxFuture<'static, ()>>,
     /// Dialer for each socket family if no matching listener exists.
     dialer: HashMap<SocketFamily, quinn::Endpoint>,
     /// Waker to poll the transport again when a new dialer or listener is added.
     waker: Option<Waker>,
     /// Holepunching attempts
     hole_punch_attempts: HashMap<SocketAddr, oneshot::Sender<Connecting>>,
+    /// Client configs of recently dialed peers, storing their session tickets if sessions are
+    /// resumed.
+    session_configs: LruCache<PeerId, quinn::ClientConfig>,
 }
 
 impl<P: Provider> GenTransport<P> {
@@ -84,18 +104,39 @@ impl<P: Provider> GenTransport<P> {
     pub fn new(config: Config) -> Self {
         let handshake_timeout = config.handshake_timeout;
         let support_draft_29 = config.support_draft_29;
+        let port_conflict_policy = config.port_conflict_policy;
+        let inbound_server_names = Arc::new(config.inbound_server_names.clone());
+        let connection_migration = config.connection_migration;
+        let rebind_on_interface_change = config.rebind_on_interface_change;
         let quinn_config = config.into();
         Self {
             listeners: SelectAll::new(),
+            draining_endpoints: FuturesUnordered::new(),
             quinn_config,
             handshake_timeout,
             dialer: HashMap::new(),
             waker: None,
             support_draft_29,
+            port_conflict_policy,
+            inbound_server_names,
+            connection_migration,
+            rebind_on_interface_change,
             hole_punch_attempts: Default::default(),
+            session_configs: LruCache::new(
+                NonZeroUsize::new(MAX_SESSION_CONFIGS).expect("MAX_SESSION_CONFIGS > 0"),
+            ),
         }
     }
 
+    /// The client config for dialing `peer_id`, resuming a previous session if enabled.
+    fn session_config(&mut self, peer_id: PeerId) -> Option<quinn::ClientConfig> {
+        let resumption = self.quinn_config.session_resumption.as_ref()?;
+        let config = self
+            .session_configs
+            .get_or_insert(peer_id, || resumption.client_config(peer_id));
+        Some(config.clone())
+    }
+
     /// Create a new [`quinn::Endpoint`] with the given configs.
     fn new_endpoint(
         endpoint_config: quinn::EndpointConfig,
@@ -180,21 +221,22 @@ impl<P: Provider> GenTransport<P> {
             }
         }
     }
+}
 
-    fn create_socket(&self, socket_addr: SocketAddr) -> io::Result<UdpSocket> {
-        let socket = Socket::new(
-            Domain::for_address(socket_addr),
-            Type::DGRAM,
-            Some(socket2::Protocol::UDP),
-        )?;
-        if socket_addr.is_ipv6() {
-            socket.set_only_v6(true)?;
-        }
+/// Creates a UDP socket bound to `socket_addr`.
+fn create_socket(socket_addr: SocketAddr) -> io::Result<UdpSocket> {
+    let socket = Socket::new(
+        Domain::for_address(socket_addr),
+        Type::DGRAM,
+        Some(socket2::Protocol::UDP),
+    )?;
+    if socket_addr.is_ipv6() {
+        socket.set_only_v6(true)?;
+    }
 
-        socket.bind(&socket_addr.into())?;
+    socket.bind(&socket_addr.into())?;
 
-        Ok(socket.into())
-    }
+    Ok(socket.into())
 }
 
 impl<P: Provider> Transport for GenTransport<P> {
@@ -208,20 +250,37 @@ impl<P: Provider> Transport for GenTransport<P> {
         listener_id: ListenerId,
         addr: Multiaddr,
     ) -> Result<(), TransportError<Self::Error>> {
-        let (socket_addr, version, _peer_id) = self.remote_multiaddr_to_socketaddr(addr, false)?;
+        let (socket_addr, version, _peer_id) =
+            self.remote_multiaddr_to_socketaddr(addr.clone(), false)?;
         let endpoint_config = self.quinn_config.endpoint_config.clone();
         let server_config = self.quinn_config.server_config.clone();
-        let socket = self.create_socket(socket_addr).map_err(Self::Error::from)?;
+        let socket = match create_socket(socket_addr) {
+            Ok(socket) => socket,
+            Err(e)
+                if e.kind() == io::ErrorKind::AddrInUse
+                    && socket_addr.port() != 0
+                    && self.port_conflict_policy == PortConflictPolicy::UseEphemeral =>
+            {
+                let mut fallback_addr = socket_addr;
+                fallback_addr.set_port(0);
+                let socket = create_socket(fallback_addr).map_err(Self::Error::from)?;
+                let mut actual_addr = socket_addr;
+                actual_addr.set_port(socket.local_addr().map_err(Self::Error::from)?.port());
+                let actual = socketaddr_to_multiaddr(&actual_addr, version);
+                tracing::warn!(
+                    listener=%listener_id,
+                    requested=%addr,
+                    %actual,
+                    "Listen port in use, falling back to OS-assigned port"
+                );
+                socket
+            }
+            Err(e) => return Err(TransportError::Other(e.into())),
+        };
 
         let socket_c = socket.try_clone().map_err(Self::Error::from)?;
         let endpoint = Self::new_endpoint(endpoint_config, Some(server_config), socket)?;
-        let listener = Listener::new(
-            listener_id,
-            socket_c,
-            endpoint,
-            self.handshake_timeout,
-            version,
-        )?;
+        let listener = Listener::new(listener_id, socket_c, endpoint, self, version)?;
         self.listeners.push(listener);
 
         if let Some(waker) = self.waker.take() {
@@ -247,6 +306,22 @@ impl<P: Provider> Transport for GenTransport<P> {
         }
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        if let Some(listener) = self.listeners.iter_mut().find(|l| l.listener_id == id) {
+            // Stop accepting connections right away but keep the endpoint of the listener
+            // open for the remaining connections until the timeout.
+            if let Some(closing) = listener.drain(timeout) {
+                self.draining_endpoints.push(closing);
+                if let Some(waker) = self.waker.take() {
+                    waker.wake();
+                }
+            }
+            true
+        } else {
+            false
+        }
+    }
+
     fn address_translation(&self, listen: &Multiaddr, observed: &Multiaddr) -> Option<Multiaddr> {
         if !is_quic_addr(listen, self.support_draft_29)
             || !is_quic_addr(observed, self.support_draft_29)
@@ -257,7 +332,7 @@ impl<P: Provider> Transport for GenTransport<P> {
     }
 
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
-        let (socket_addr, version, _peer_id) = self.remote_multiaddr_to_socketaddr(addr, true)?;
+        let (socket_addr, version, peer_id) = self.remote_multiaddr_to_socketaddr(addr, true)?;
 
         let endpoint = match self.eligible_listener(&socket_addr) {
             None => {
@@ -287,17 +362,33 @@ impl<P: Provider> Transport for GenTransport<P> {
             Some(listener) => listener.endpoint.clone(),
         };
         let handshake_timeout = self.handshake_timeout;
-        let mut client_config = self.quinn_config.client_config.clone();
+        let session_config = peer_id.and_then(|peer_id| self.session_config(peer_id));
+        let zero_rtt = session_config.is_some()
+            && self
+                .quinn_config
+                .session_resumption
+                .as_ref()
+                .is_some_and(|resumption| resumption.zero_rtt);
+        let mut client_config =
+            session_config.unwrap_or_else(|| self.quinn_config.client_config.clone());
+        let server_name = self.quinn_config.server_name.clone();
         if version == ProtocolVersion::Draft29 {
             client_config.version(0xff00_001d);
         }
         Ok(Box::pin(async move {
-            // This `"l"` seems necessary because an empty string is an invalid domain
-            // name. While we don't use domain names, the underlying rustls library
-            // is based upon the assumption that we do.
             let connecting = endpoint
-                .connect_with(client_config, socket_addr, "l")
+                .connect_with(client_config, socket_addr, &server_name)
                 .map_err(ConnectError)?;
+            let connecting = match (zero_rtt, peer_id) {
+                (true, Some(peer_id)) => match connecting.into_0rtt() {
+                    // The remote is verified to be `peer_id` when resuming the session.
+                    Ok((connection, accepted)) => {
+                        return Ok((peer_id, Connection::new(connection).with_zero_rtt(accepted)));
+                    }
+                    Err(connecting) => connecting,
+                },
+                _ => connecting,
+            };
             Connecting::new(connecting, handshake_timeout).await
         }))
     }
@@ -366,6 +457,8 @@ impl<P: Provider> Transport for GenTransport<P> {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        while let Poll::Ready(Some(())) = self.draining_endpoints.poll_next_unpin(cx) {}
+
         while let Poll::Ready(Some(ev)) = self.listeners.poll_next_unpin(cx) {
             match ev {
                 TransportEvent::Incoming {
@@ -428,12 +521,21 @@ struct Listener<P: Provider> {
     accept: BoxFuture<'static, Option<quinn::Incoming>>,
     /// Timeout for connection establishment on inbound connections.
     handshake_timeout: Duration,
+    /// Which server names are accepted on inbound connections.
+    inbound_server_names: Arc<InboundServerNamePolicy>,
+    /// Whether remotes may migrate inbound connections to a new path.
+    connection_migration: bool,
 
     /// Watcher for network interface changes.
     ///
-    /// None if we are only listening on a single interface.
+    /// None if we are only listening on a single interface, unless the listener is re-bound
+    /// on interface changes.
     if_watcher: Option<P::IfWatcher>,
 
+    /// Whether the address of a listener on a single interface disappeared from all
+    /// interfaces, if the listener is re-bound on interface changes.
+    expired: Option<bool>,
+
     /// Whether the listener was closed and the stream should terminate.
     is_closed: bool,
 
@@ -451,18 +553,24 @@ impl<P: Provider> Listener<P> {
         listener_id: ListenerId,
         socket: UdpSocket,
         endpoint: quinn::Endpoint,
-        handshake_timeout: Duration,
+        transport: &GenTransport<P>,
         version: ProtocolVersion,
     ) -> Result<Self, Error> {
         let if_watcher;
         let pending_event;
+        let mut expired = None;
         let mut listening_addresses = HashSet::new();
         let local_addr = socket.local_addr()?;
         if local_addr.ip().is_unspecified() {
             if_watcher = Some(P::new_if_watcher()?);
             pending_event = None;
         } else {
-            if_watcher = None;
+            if transport.rebind_on_interface_change {
+                if_watcher = Some(P::new_if_watcher()?);
+                expired = Some(false);
+            } else {
+                if_watcher = None;
+            }
             listening_addresses.insert(local_addr.ip());
             let ma = socketaddr_to_multiaddr(&local_addr, version);
             pending_event = Some(TransportEvent::NewAddress {
@@ -480,8 +588,11 @@ impl<P: Provider> Listener<P> {
             accept,
             listener_id,
             version,
-            handshake_timeout,
+            handshake_timeout: transport.handshake_timeout,
+            inbound_server_names: Arc::clone(&transport.inbound_server_names),
+            connection_migration: transport.connection_migration,
             if_watcher,
+            expired,
             is_closed: false,
             pending_event,
             close_listener_waker: None,
@@ -496,6 +607,11 @@ impl<P: Provider> Listener<P> {
             return;
         }
         self.endpoint.close(From::from(0u32), &[]);
+        self.report_closed(reason);
+    }
+
+    /// Report the listener as closed, without closing its endpoint.
+    fn report_closed(&mut self, reason: Result<(), Error>) {
         self.pending_event = Some(TransportEvent::ListenerClosed {
             listener_id: self.listener_id,
             reason,
@@ -508,6 +624,32 @@ impl<P: Provider> Listener<P> {
         }
     }
 
+    /// Refuse new connections and close the listener right away.
+    ///
+    /// Connections that are still handshaking or established share the endpoint of the
+    /// listener. The returned future closes the endpoint, aborting the connections that
+    /// remain, once all of them are gone or `timeout` elapsed.
+    fn drain(&mut self, timeout: Duration) -> Option<BoxFuture<'static, ()>> {
+        if self.is_closed {
+            return None;
+        }
+        self.endpoint.set_server_config(None);
+        self.report_closed(Ok(()));
+
+        let endpoint = self.endpoint.clone();
+        Some(
+            async move {
+                {
+                    let idle = endpoint.wait_idle();
+                    futures::pin_mut!(idle);
+                    future::select(idle, P::sleep(timeout)).await;
+                }
+                endpoint.close(From::from(0u32), &[]);
+            }
+            .boxed(),
+        )
+    }
+
     /// Clone underlying socket (for hole punching).
     fn try_clone_socket(&self) -> std::io::Result<UdpSocket> {
         self.socket.try_clone()
@@ -526,7 +668,42 @@ impl<P: Provider> Listener<P> {
             return Poll::Pending;
         };
         loop {
-            match ready!(P::poll_if_event(if_watcher, cx)) {
+            let event = ready!(P::poll_if_event(if_watcher, cx));
+            if let Some(expired) = self.expired.as_mut() {
+                let listen_addr = socketaddr_to_multiaddr(&endpoint_addr, self.version);
+                match event {
+                    Ok(IfEvent::Up(inet)) if inet.addr() == endpoint_addr.ip() && *expired => {
+                        // The socket of the endpoint stays bound to the address while it is
+                        // gone, and is used again once the address reappears. Binding a new
+                        // socket to the same address and port would fail.
+                        *expired = false;
+                        tracing::debug!(address=%listen_addr, "Listen address reappeared");
+                        self.listening_addresses.insert(endpoint_addr.ip());
+                        return Poll::Ready(TransportEvent::NewAddress {
+                            listener_id: self.listener_id,
+                            listen_addr,
+                        });
+                    }
+                    Ok(IfEvent::Down(inet)) if inet.addr() == endpoint_addr.ip() && !*expired => {
+                        *expired = true;
+                        tracing::debug!(address=%listen_addr, "Expired listen address");
+                        self.listening_addresses.remove(&endpoint_addr.ip());
+                        return Poll::Ready(TransportEvent::AddressExpired {
+                            listener_id: self.listener_id,
+                            listen_addr,
+                        });
+                    }
+                    Ok(_) => continue,
+                    Err(err) => {
+                        return Poll::Ready(TransportEvent::ListenerError {
+                            listener_id: self.listener_id,
+                            error: err.into(),
+                        })
+                    }
+                }
+            }
+
+            match event {
                 Ok(IfEvent::Up(inet)) => {
                     if let Some(listen_addr) =
                         ip_to_listenaddr(&endpoint_addr, inet.addr(), self.version)
@@ -601,8 +778,14 @@ impl<P: Provider> Stream for Listener<P> {
                     let remote_addr = connecting.remote_address();
                     let send_back_addr = socketaddr_to_multiaddr(&remote_addr, self.version);
 
+                    let mut upgrade = Connecting::new(connecting, self.handshake_timeout)
+                        .with_server_name_policy(Arc::clone(&self.inbound_server_names));
+                    if self.connection_migration {
+                        upgrade = upgrade.with_path_monitoring(self.version);
+                    }
+
                     let event = TransportEvent::Incoming {
-                        upgrade: Connecting::new(connecting, self.handshake_timeout),
+                        upgrade,
                         local_addr,
                         send_back_addr,
                         listener_id: self.listener_id,
@@ -750,7 +933,10 @@ fn is_quic_addr(addr: &Multiaddr, support_draft_29: bool) -> bool {
 }
 
 /// Turns an IP address and port into the corresponding QUIC multiaddr.
-fn socketaddr_to_multiaddr(socket_addr: &SocketAddr, version: ProtocolVersion) -> Multiaddr {
+pub(crate) fn socketaddr_to_multiaddr(
+    socket_addr: &SocketAddr,
+    version: ProtocolVersion,
+) -> Multiaddr {
     let quic_proto = match version {
         ProtocolVersion::V1 => Protocol::QuicV1,
         ProtocolVersion::Draft29 => Protocol::Quic,
@@ -937,6 +1123,182 @@ mod tests {
         assert!(!transport.dialer.contains_key(&SocketFamily::Ipv4));
     }
 
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_port_conflict_fallback() {
+        let keypair = libp2p_identity::Keypair::generate_ed25519();
+        let mut config = Config::new(&keypair);
+        config.port_conflict_policy = PortConflictPolicy::UseEphemeral;
+        let mut transport = crate::tokio::Transport::new(config);
+
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let port = socket.local_addr().unwrap().port();
+        let requested: Multiaddr = format!("/ip4/127.0.0.1/udp/{port}/quic-v1")
+            .parse()
+            .unwrap();
+
+        let id = ListenerId::next();
+        transport.listen_on(id, requested.clone()).unwrap();
+
+        match poll_fn(|cx| Pin::new(&mut transport).as_mut().poll(cx)).await {
+            TransportEvent::NewAddress {
+                listener_id,
+                listen_addr,
+            } => {
+                assert_eq!(listener_id, id);
+                assert_ne!(listen_addr, requested);
+            }
+            e => panic!("Unexpected event: {e:?}"),
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_connection_migration() {
+        use libp2p_core::muxing::{StreamMuxer, StreamMuxerEvent};
+
+        let mut listener_config = Config::new(&libp2p_identity::Keypair::generate_ed25519());
+        listener_config.connection_migration = true;
+        let mut listener = crate::tokio::Transport::new(listener_config);
+        let mut dialer = crate::tokio::Transport::new(Config::new(
+            &libp2p_identity::Keypair::generate_ed25519(),
+        ));
+
+        listener
+            .listen_on(
+                ListenerId::next(),
+                "/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap(),
+            )
+            .unwrap();
+        let listen_addr = match poll_fn(|cx| Pin::new(&mut listener).as_mut().poll(cx)).await {
+            TransportEvent::NewAddress { listen_addr, .. } => listen_addr,
+            e => panic!("Unexpected event: {e:?}"),
+        };
+
+        // The dial has to make progress for the listener to report the incoming connection.
+        let dial = dialer.dial(listen_addr).unwrap();
+        let accept = async {
+            match poll_fn(|cx| Pin::new(&mut listener).as_mut().poll(cx)).await {
+                TransportEvent::Incoming { upgrade, .. } => upgrade.await,
+                e => panic!("Unexpected event: {e:?}"),
+            }
+        };
+        let ((_, mut dialer_connection), (_, mut listener_connection)) =
+            future::try_join(dial, accept).await.unwrap();
+
+        // Move the dialer to a new local address, as if it switched networks.
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let new_address = socket.local_addr().unwrap();
+        dialer.dialer[&SocketFamily::Ipv4].rebind(socket).unwrap();
+
+        let mut stream = poll_fn(|cx| Pin::new(&mut dialer_connection).poll_outbound(cx))
+            .await
+            .unwrap();
+        stream.write_all(b"ping").await.unwrap();
+
+        match poll_fn(|cx| Pin::new(&mut listener_connection).poll(cx))
+            .await
+            .unwrap()
+        {
+            StreamMuxerEvent::AddressChange(address) => assert_eq!(
+                address,
+                socketaddr_to_multiaddr(&new_address, ProtocolVersion::V1)
+            ),
+        }
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_rebind_on_interface_change() {
+        use futures::channel::mpsc;
+
+        /// Wraps the tokio [`Provider`] with an [`IfEvent`] stream that is driven by the test.
+        struct ControlledIf;
+
+        impl Provider for ControlledIf {
+            type IfWatcher = mpsc::Receiver<io::Result<IfEvent>>;
+
+            fn runtime() -> crate::provider::Runtime {
+                crate::tokio::Provider::runtime()
+            }
+
+            fn new_if_watcher() -> io::Result<Self::IfWatcher> {
+                Ok(mpsc::channel(0).1)
+            }
+
+            fn poll_if_event(
+                watcher: &mut Self::IfWatcher,
+                cx: &mut Context<'_>,
+            ) -> Poll<io::Result<IfEvent>> {
+                match ready!(watcher.poll_next_unpin(cx)) {
+                    Some(event) => Poll::Ready(event),
+                    None => Poll::Pending,
+                }
+            }
+
+            fn sleep(duration: Duration) -> BoxFuture<'static, ()> {
+                crate::tokio::Provider::sleep(duration)
+            }
+
+            fn send_to<'a>(
+                udp_socket: &'a UdpSocket,
+                buf: &'a [u8],
+                target: SocketAddr,
+            ) -> BoxFuture<'a, io::Result<usize>> {
+                crate::tokio::Provider::send_to(udp_socket, buf, target)
+            }
+        }
+
+        let mut listener_config = Config::new(&libp2p_identity::Keypair::generate_ed25519());
+        listener_config.rebind_on_interface_change = true;
+        let mut listener = GenTransport::<ControlledIf>::new(listener_config);
+        let mut dialer = crate::tokio::Transport::new(Config::new(
+            &libp2p_identity::Keypair::generate_ed25519(),
+        ));
+
+        listener
+            .listen_on(
+                ListenerId::next(),
+                "/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap(),
+            )
+            .unwrap();
+        let listen_addr = match poll_fn(|cx| Pin::new(&mut listener).as_mut().poll(cx)).await {
+            TransportEvent::NewAddress { listen_addr, .. } => listen_addr,
+            e => panic!("Unexpected event: {e:?}"),
+        };
+        let (mut if_tx, if_rx) = mpsc::channel(1);
+        listener.listeners.iter_mut().next().unwrap().if_watcher = Some(if_rx);
+        let ip_net = if_watch::IpNet::from(IpAddr::V4(Ipv4Addr::LOCALHOST));
+
+        if_tx.send(Ok(IfEvent::Down(ip_net))).await.unwrap();
+        match poll_fn(|cx| Pin::new(&mut listener).as_mut().poll(cx)).await {
+            TransportEvent::AddressExpired {
+                listen_addr: expired,
+                ..
+            } => assert_eq!(expired, listen_addr),
+            e => panic!("Unexpected event: {e:?}"),
+        }
+
+        if_tx.send(Ok(IfEvent::Up(ip_net))).await.unwrap();
+        match poll_fn(|cx| Pin::new(&mut listener).as_mut().poll(cx)).await {
+            TransportEvent::NewAddress {
+                listen_addr: reappeared,
+                ..
+            } => assert_eq!(reappeared, listen_addr),
+            e => panic!("Unexpected event: {e:?}"),
+        }
+
+        // The listener still accepts connections on its address.
+        let dial = dialer.dial(listen_addr).unwrap();
+        let accept = async {
+            match poll_fn(|cx| Pin::new(&mut listener).as_mut().poll(cx)).await {
+                TransportEvent::Incoming { upgrade, .. } => upgrade.await,
+                e => panic!("Unexpected event: {e:?}"),
+            }
+        };
+        future::try_join(dial, accept).await.unwrap();
+    }
+
     #[cfg(feature = "tokio")]
     #[tokio::test]
     async fn test_listens_ipv4_ipv6_separately() {
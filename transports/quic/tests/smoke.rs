@@ -458,6 +458,226 @@ async fn test_local_listener_reuse() {
     assert_eq!(send_back_addr, a_listen_addr);
 }
 
+#[cfg(feature = "async-std")]
+#[async_std::test]
+async fn remove_listener_graceful() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+    let (_, mut a_transport) = create_default_transport::<quic::async_std::Provider>();
+    let (_, mut b_transport) = create_default_transport::<quic::async_std::Provider>();
+    let (_, mut c_transport) = create_default_transport::<quic::async_std::Provider>();
+
+    let listener_id = ListenerId::next();
+    a_transport
+        .listen_on(listener_id, "/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap())
+        .unwrap();
+    let a_addr = a_transport
+        .select_next_some()
+        .await
+        .into_new_address()
+        .unwrap();
+    let ((_, _, mut a_connection), (_, mut b_connection)) =
+        connect(&mut a_transport, &mut b_transport, a_addr.clone()).await;
+
+    assert!(a_transport.remove_listener_graceful(listener_id, Duration::from_secs(1)));
+
+    // The listener is closed and new connections are refused right away.
+    match a_transport.select_next_some().await {
+        TransportEvent::ListenerClosed {
+            listener_id: id,
+            reason: Ok(()),
+        } => assert_eq!(id, listener_id),
+        e => panic!("Unexpected event: {e:?}"),
+    }
+    assert!(dial(&mut c_transport, a_addr).await.is_err());
+
+    // The established connection is kept open until the timeout.
+    let mut b_stream = poll_fn(|cx| b_connection.poll_outbound_unpin(cx))
+        .await
+        .unwrap();
+    b_stream.write_all(&[1]).await.unwrap();
+    let mut a_stream = poll_fn(|cx| a_connection.poll_inbound_unpin(cx))
+        .await
+        .unwrap();
+    let mut buf = [0];
+    a_stream.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, [1]);
+
+    // Once the timeout elapsed, the endpoint is closed.
+    match future::select(
+        a_transport.next(),
+        poll_fn(|cx| b_connection.poll_inbound_unpin(cx)),
+    )
+    .await
+    {
+        Either::Left((e, _)) => panic!("Unexpected event: {e:?}"),
+        Either::Right((stream, _)) => assert!(stream.is_err()),
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn inbound_server_name_policy() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+    let (_, mut a_transport) = create_transport::<quic::tokio::Provider>(|cfg| {
+        cfg.inbound_server_names =
+            quic::InboundServerNamePolicy::OneOf(vec!["example.com".to_owned()])
+    });
+    let (_, mut b_transport) = create_transport::<quic::tokio::Provider>(|cfg| {
+        cfg.server_name = quic::ServerName::Domain("example.com".to_owned())
+    });
+    let (_, mut c_transport) = create_default_transport::<quic::tokio::Provider>();
+    let (_, mut d_transport) = create_transport::<quic::tokio::Provider>(|cfg| {
+        cfg.server_name = quic::ServerName::Omitted
+    });
+
+    let a_addr = start_listening(&mut a_transport, "/ip4/127.0.0.1/udp/0/quic-v1").await;
+    connect(&mut a_transport, &mut b_transport, a_addr.clone()).await;
+
+    // The placeholder name and a missing name are both refused by `a`.
+    for dialer in [&mut c_transport, &mut d_transport] {
+        let (upgrade, _) = future::join(
+            async {
+                let (upgrade, _) = a_transport
+                    .select_next_some()
+                    .await
+                    .into_incoming()
+                    .unwrap();
+                upgrade.await
+            },
+            async {
+                let _ = dial(dialer, a_addr.clone()).await;
+            },
+        )
+        .await;
+        assert!(upgrade.is_err());
+    }
+
+    // Without a policy any name is accepted.
+    let c_addr = start_listening(&mut c_transport, "/ip4/127.0.0.1/udp/0/quic-v1").await;
+    connect(&mut c_transport, &mut d_transport, c_addr.clone()).await;
+    connect(&mut c_transport, &mut b_transport, c_addr).await;
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn congestion_controller_and_stats() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+    const INITIAL_WINDOW: u64 = 100_000;
+
+    let (_, mut a_transport) = create_default_transport::<quic::tokio::Provider>();
+    let keypair = generate_tls_keypair();
+    let mut config = quic::Config::new(&keypair);
+    config.congestion_controller = quic::CongestionController::NewReno;
+    config.initial_congestion_window = Some(INITIAL_WINDOW);
+    let (mut stats_tx, mut stats_rx) = mpsc::channel(1);
+    let mut b_transport = quic::tokio::Transport::new(config)
+        .map(move |(p, c), _| {
+            stats_tx.try_send(c.stats()).unwrap();
+            (p, StreamMuxerBox::new(c))
+        })
+        .boxed();
+
+    let a_addr = start_listening(&mut a_transport, "/ip4/127.0.0.1/udp/0/quic-v1").await;
+    connect(&mut a_transport, &mut b_transport, a_addr).await;
+
+    let stats = stats_rx.next().await.unwrap();
+    assert!(stats.rtt > Duration::ZERO);
+    // The window only grows without loss.
+    assert!(stats.congestion_window >= INITIAL_WINDOW);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn session_resumption_zero_rtt() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+    let (a_peer_id, mut a_transport) = create_transport::<quic::tokio::Provider>(|cfg| {
+        cfg.session_resumption = quic::SessionResumption::ZeroRtt;
+        cfg.accept_zero_rtt = true;
+    });
+    let (b_peer_id, mut b_transport) = create_transport::<quic::tokio::Provider>(|cfg| {
+        cfg.session_resumption = quic::SessionResumption::ZeroRtt
+    });
+
+    let a_addr = start_listening(&mut a_transport, "/ip4/127.0.0.1/udp/0/quic-v1")
+        .await
+        .with(Protocol::P2p(a_peer_id));
+
+    let ((peer_id, _, _first_a), (remote_peer_id, _first_b)) =
+        connect(&mut a_transport, &mut b_transport, a_addr.clone()).await;
+    assert_eq!(peer_id, b_peer_id);
+    assert_eq!(remote_peer_id, a_peer_id);
+
+    // Session tickets are sent after the handshake completed.
+    Delay::new(Duration::from_millis(100)).await;
+
+    // The second connection resumes the session of the first one.
+    let ((peer_id, _, _), (remote_peer_id, _)) =
+        connect(&mut a_transport, &mut b_transport, a_addr).await;
+    assert_eq!(peer_id, b_peer_id);
+    assert_eq!(remote_peer_id, a_peer_id);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn zero_rtt_rejection_fails_early_streams() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+    let keypair = generate_tls_keypair();
+    let a_peer_id = keypair.public().to_peer_id();
+    let create_listener = |accept_zero_rtt| {
+        let mut config = quic::Config::new(&keypair);
+        config.accept_zero_rtt = accept_zero_rtt;
+        quic::tokio::Transport::new(config)
+            .map(|(p, c), _| (p, StreamMuxerBox::new(c)))
+            .boxed()
+    };
+    let (_, mut b_transport) = create_transport::<quic::tokio::Provider>(|cfg| {
+        cfg.session_resumption = quic::SessionResumption::ZeroRtt
+    });
+
+    let mut a_transport = create_listener(true);
+    let a_addr = start_listening(&mut a_transport, "/ip4/127.0.0.1/udp/0/quic-v1")
+        .await
+        .with(Protocol::P2p(a_peer_id));
+    let ((_, _, _first_a), (_, _first_b)) =
+        connect(&mut a_transport, &mut b_transport, a_addr).await;
+    // Session tickets are sent after the handshake completed.
+    Delay::new(Duration::from_millis(100)).await;
+
+    // A restarted listener neither knows the session nor accepts 0-RTT data.
+    let mut a_transport = create_listener(false);
+    let a_addr = start_listening(&mut a_transport, "/ip4/127.0.0.1/udp/0/quic-v1")
+        .await
+        .with(Protocol::P2p(a_peer_id));
+    let (_, mut b_connection) = dial(&mut b_transport, a_addr).await.unwrap();
+    let mut stream = poll_fn(|cx| b_connection.poll_outbound_unpin(cx))
+        .await
+        .unwrap();
+    stream.write_all(b"ping").await.unwrap();
+
+    // Once the listener completed the handshake, the dialer learned that its 0-RTT data was
+    // rejected.
+    let (upgrade, _) = a_transport
+        .select_next_some()
+        .await
+        .into_incoming()
+        .unwrap();
+    let _a_connection = upgrade.await.unwrap();
+    let error = stream.read(&mut [0; 4]).await.unwrap_err();
+    assert!(error
+        .get_ref()
+        .is_some_and(|error| error.is::<quic::ZeroRttRejected>()));
+}
+
 async fn smoke<P: Provider>() {
     let _ = tracing_subscriber::fmt()
         .with_env_filter(EnvFilter::from_default_env())
@@ -42,7 +42,7 @@ use if_watch::IfEvent;
 use libp2p_core::{
     address_translation,
     multiaddr::{Multiaddr, Protocol},
-    transport::{ListenerId, TransportError, TransportEvent},
+    transport::{ListenerId, PortConflictPolicy, TransportError, TransportEvent},
 };
 use provider::{Incoming, Provider};
 use socket2::{Domain, Socket, Type};
@@ -63,10 +63,91 @@ pub struct Config {
     ttl: Option<u32>,
     /// `TCP_NODELAY` to set for opened sockets, or `None` to keep default.
     nodelay: Option<bool>,
+    /// TCP keepalive to set for opened sockets, or `None` to keep default.
+    keepalive: Option<Keepalive>,
+    /// `TCP_USER_TIMEOUT` to set for opened sockets, or `None` to keep default.
+    user_timeout: Option<Duration>,
+    /// `SO_SNDBUF` to set for opened sockets, or `None` to keep default.
+    send_buffer_size: Option<usize>,
+    /// `SO_RCVBUF` to set for opened sockets, or `None` to keep default.
+    recv_buffer_size: Option<usize>,
+    /// Whether TCP Fast Open is enabled for opened sockets.
+    fastopen: bool,
+    /// The network interface to bind opened sockets to, or `None` to not bind to one.
+    bind_device: Option<String>,
     /// Size of the listen backlog for listen sockets.
     backlog: u32,
     /// Whether port reuse should be enabled.
     enable_port_reuse: bool,
+    /// What to do if a listen port is already in use.
+    port_conflict_policy: PortConflictPolicy,
+    /// Whether listeners on a single interface are re-bound once its address reappears.
+    rebind_on_interface_change: bool,
+}
+
+/// TCP keepalive parameters, see [`Config::keepalive`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Keepalive {
+    time: Duration,
+    interval: Option<Duration>,
+    retries: Option<u32>,
+}
+
+impl Keepalive {
+    /// Creates keepalive parameters sending the first probe after the connection was idle for
+    /// `time`.
+    ///
+    /// The interval between probes and their number keep the defaults of the OS TCP stack.
+    pub fn new(time: Duration) -> Self {
+        Self {
+            time,
+            interval: None,
+            retries: None,
+        }
+    }
+
+    /// Sets the interval between two probes, if supported by the platform.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// Sets the number of unanswered probes after which the connection is dropped, if
+    /// supported by the platform.
+    pub fn with_retries(mut self, retries: u32) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    fn to_socket2(&self) -> socket2::TcpKeepalive {
+        let keepalive = socket2::TcpKeepalive::new().with_time(self.time);
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+            windows,
+        ))]
+        let keepalive = match self.interval {
+            Some(interval) => keepalive.with_interval(interval),
+            None => keepalive,
+        };
+        #[cfg(any(
+            target_os = "android",
+            target_os = "freebsd",
+            target_os = "ios",
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "netbsd",
+        ))]
+        let keepalive = match self.retries {
+            Some(retries) => keepalive.with_retries(retries),
+            None => keepalive,
+        };
+        keepalive
+    }
 }
 
 type Port = u16;
@@ -158,12 +239,26 @@ impl Config {
     ///     See [`Config::ttl`].
     ///   * The size of the listen backlog for new listening sockets is `1024`.
     ///     See [`Config::listen_backlog`].
+    ///   * Listening fails if the requested port is already in use.
+    ///     See [`Config::port_conflict_policy`].
+    ///   * No custom keepalive, user timeout or buffer sizes are set, TCP Fast Open is
+    ///     _disabled_ and sockets are not bound to a network interface.
+    ///     See [`Config::keepalive`], [`Config::user_timeout`], [`Config::send_buffer_size`],
+    ///     [`Config::recv_buffer_size`], [`Config::fastopen`] and [`Config::bind_device`].
     pub fn new() -> Self {
         Self {
             ttl: None,
             nodelay: None,
+            keepalive: None,
+            user_timeout: None,
+            send_buffer_size: None,
+            recv_buffer_size: None,
+            fastopen: false,
+            bind_device: None,
             backlog: 1024,
             enable_port_reuse: false,
+            port_conflict_policy: PortConflictPolicy::Fail,
+            rebind_on_interface_change: false,
         }
     }
 
@@ -185,6 +280,55 @@ impl Config {
         self
     }
 
+    /// Configures TCP keepalive for new sockets.
+    ///
+    /// Connections accepted by a listener inherit the keepalive of the listening socket.
+    pub fn keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.keepalive = Some(keepalive);
+        self
+    }
+
+    /// Configures the `TCP_USER_TIMEOUT` option for new sockets, i.e. how long transmitted
+    /// data may remain unacknowledged before the connection is dropped.
+    ///
+    /// Only supported on Linux and Android, ignored on other platforms.
+    pub fn user_timeout(mut self, timeout: Duration) -> Self {
+        self.user_timeout = Some(timeout);
+        self
+    }
+
+    /// Configures the `SO_SNDBUF` option for new sockets.
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Configures the `SO_RCVBUF` option for new sockets.
+    pub fn recv_buffer_size(mut self, size: usize) -> Self {
+        self.recv_buffer_size = Some(size);
+        self
+    }
+
+    /// Configures TCP Fast Open, sending data already with the `SYN` of a connection to a
+    /// remote whose cookie is known.
+    ///
+    /// Dialing sockets set `TCP_FASTOPEN_CONNECT` and listening sockets set `TCP_FASTOPEN`
+    /// with a queue as long as the listen backlog.
+    ///
+    /// Only supported on Linux, ignored on other platforms.
+    pub fn fastopen(mut self, enabled: bool) -> Self {
+        self.fastopen = enabled;
+        self
+    }
+
+    /// Configures the network interface new sockets are bound to via `SO_BINDTODEVICE`.
+    ///
+    /// Only supported on Linux and Android. Creating a socket fails on other platforms.
+    pub fn bind_device(mut self, interface: impl Into<String>) -> Self {
+        self.bind_device = Some(interface.into());
+        self
+    }
+
     /// Configures port reuse for local sockets, which implies
     /// reuse of listening ports for outgoing connections to
     /// enhance NAT traversal capabilities.
@@ -284,6 +428,98 @@ impl Config {
         self.enable_port_reuse = port_reuse;
         self
     }
+
+    /// Configures what happens if the port of a listen address is already in use.
+    ///
+    /// With [`PortConflictPolicy::UseEphemeral`], the transport falls back to a port assigned by
+    /// the OS, which the listener reports through its [`TransportEvent::NewAddress`] events.
+    pub fn port_conflict_policy(mut self, policy: PortConflictPolicy) -> Self {
+        self.port_conflict_policy = policy;
+        self
+    }
+
+    /// Configures whether listeners on a single IP address follow changes of the network
+    /// interfaces.
+    ///
+    /// If enabled, such a listener reports its address as expired via
+    /// [`TransportEvent::AddressExpired`] once the address disappears from all interfaces,
+    /// e.g. because the interface went down. Once the address reappears, the listening socket
+    /// is bound anew on the same port and the address is reported via
+    /// [`TransportEvent::NewAddress`].
+    ///
+    /// Listeners on a wildcard address always report the addresses of appearing and
+    /// disappearing interfaces.
+    ///
+    /// Disabled by default.
+    pub fn rebind_on_interface_change(mut self, enabled: bool) -> Self {
+        self.rebind_on_interface_change = enabled;
+        self
+    }
+
+    /// Creates a socket for dialing or listening on `socket_addr`.
+    fn create_socket(&self, socket_addr: SocketAddr, port_reuse: &PortReuse) -> io::Result<Socket> {
+        let socket = Socket::new(
+            Domain::for_address(socket_addr),
+            Type::STREAM,
+            Some(socket2::Protocol::TCP),
+        )?;
+        if socket_addr.is_ipv6() {
+            socket.set_only_v6(true)?;
+        }
+        if let Some(ttl) = self.ttl {
+            socket.set_ttl(ttl)?;
+        }
+        if let Some(nodelay) = self.nodelay {
+            socket.set_nodelay(nodelay)?;
+        }
+        if let Some(keepalive) = &self.keepalive {
+            socket.set_tcp_keepalive(&keepalive.to_socket2())?;
+        }
+        #[cfg(any(target_os = "android", target_os = "linux"))]
+        if let Some(timeout) = self.user_timeout {
+            socket.set_tcp_user_timeout(Some(timeout))?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(interface) = &self.bind_device {
+            #[cfg(any(target_os = "android", target_os = "linux"))]
+            socket.bind_device(Some(interface.as_bytes()))?;
+            #[cfg(not(any(target_os = "android", target_os = "linux")))]
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("binding to interface {interface} is not supported on this platform"),
+            ));
+        }
+        socket.set_reuse_address(true)?;
+        #[cfg(unix)]
+        if let PortReuse::Enabled { .. } = port_reuse {
+            socket.set_reuse_port(true)?;
+        }
+        #[cfg(not(unix))]
+        let _ = port_reuse;
+        Ok(socket)
+    }
+
+    /// Creates a listening socket bound to `socket_addr`.
+    fn bind_listener(
+        &self,
+        socket_addr: SocketAddr,
+        port_reuse: &PortReuse,
+    ) -> io::Result<TcpListener> {
+        let socket = self.create_socket(socket_addr, port_reuse)?;
+        #[cfg(target_os = "linux")]
+        if self.fastopen {
+            set_tcp_option(&socket, libc::TCP_FASTOPEN, self.backlog as _)?;
+        }
+        socket.bind(&socket_addr.into())?;
+        socket.listen(self.backlog as _)?;
+        socket.set_nonblocking(true)?;
+        Ok(socket.into())
+    }
 }
 
 impl Default for Config {
@@ -343,24 +579,15 @@ where
     }
 
     fn create_socket(&self, socket_addr: SocketAddr) -> io::Result<Socket> {
-        let socket = Socket::new(
-            Domain::for_address(socket_addr),
-            Type::STREAM,
-            Some(socket2::Protocol::TCP),
-        )?;
-        if socket_addr.is_ipv6() {
-            socket.set_only_v6(true)?;
-        }
-        if let Some(ttl) = self.config.ttl {
-            socket.set_ttl(ttl)?;
-        }
-        if let Some(nodelay) = self.config.nodelay {
-            socket.set_nodelay(nodelay)?;
-        }
-        socket.set_reuse_address(true)?;
-        #[cfg(unix)]
-        if let PortReuse::Enabled { .. } = &self.port_reuse {
-            socket.set_reuse_port(true)?;
+        self.config.create_socket(socket_addr, &self.port_reuse)
+    }
+
+    /// Creates a socket for dialing `socket_addr`.
+    fn create_dial_socket(&self, socket_addr: SocketAddr) -> io::Result<Socket> {
+        let socket = self.create_socket(socket_addr)?;
+        #[cfg(target_os = "linux")]
+        if self.config.fastopen {
+            set_tcp_option(&socket, libc::TCP_FASTOPEN_CONNECT, 1)?;
         }
         Ok(socket)
     }
@@ -370,11 +597,7 @@ where
         id: ListenerId,
         socket_addr: SocketAddr,
     ) -> io::Result<ListenStream<T>> {
-        let socket = self.create_socket(socket_addr)?;
-        socket.bind(&socket_addr.into())?;
-        socket.listen(self.config.backlog as _)?;
-        socket.set_nonblocking(true)?;
-        let listener: TcpListener = socket.into();
+        let listener = self.config.bind_listener(socket_addr, &self.port_reuse)?;
         let local_addr = listener.local_addr()?;
 
         if local_addr.ip().is_unspecified() {
@@ -383,6 +606,7 @@ where
                 listener,
                 Some(T::new_if_watcher()?),
                 self.port_reuse.clone(),
+                None,
             );
         }
 
@@ -392,7 +616,19 @@ where
             listener_id: id,
             listen_addr,
         });
-        ListenStream::<T>::new(id, listener, None, self.port_reuse.clone())
+        if self.config.rebind_on_interface_change {
+            return ListenStream::<T>::new(
+                id,
+                listener,
+                Some(T::new_if_watcher()?),
+                self.port_reuse.clone(),
+                Some(Rebind {
+                    config: self.config.clone(),
+                    expired: false,
+                }),
+            );
+        }
+        ListenStream::<T>::new(id, listener, None, self.port_reuse.clone(), None)
     }
 }
 
@@ -438,11 +674,31 @@ where
         addr: Multiaddr,
     ) -> Result<(), TransportError<Self::Error>> {
         let socket_addr = multiaddr_to_socketaddr(addr.clone())
-            .map_err(|_| TransportError::MultiaddrNotSupported(addr))?;
+            .map_err(|_| TransportError::MultiaddrNotSupported(addr.clone()))?;
         tracing::debug!("listening on {}", socket_addr);
-        let listener = self
-            .do_listen(id, socket_addr)
-            .map_err(TransportError::Other)?;
+        let listener = match self.do_listen(id, socket_addr) {
+            Ok(listener) => listener,
+            Err(e)
+                if e.kind() == io::ErrorKind::AddrInUse
+                    && socket_addr.port() != 0
+                    && self.config.port_conflict_policy == PortConflictPolicy::UseEphemeral =>
+            {
+                let mut fallback_addr = socket_addr;
+                fallback_addr.set_port(0);
+                let listener = self
+                    .do_listen(id, fallback_addr)
+                    .map_err(TransportError::Other)?;
+                let actual = ip_to_multiaddr(socket_addr.ip(), listener.listen_addr.port());
+                tracing::warn!(
+                    listener=%id,
+                    requested=%addr,
+                    %actual,
+                    "Listen port in use, falling back to OS-assigned port"
+                );
+                listener
+            }
+            Err(e) => return Err(TransportError::Other(e)),
+        };
         self.listeners.push(listener);
         Ok(())
     }
@@ -468,7 +724,7 @@ where
         tracing::debug!(address=%socket_addr, "dialing address");
 
         let socket = self
-            .create_socket(socket_addr)
+            .create_dial_socket(socket_addr)
             .map_err(TransportError::Other)?;
 
         if let Some(addr) = self.port_reuse.local_dial_addr(&socket_addr.ip()) {
@@ -560,13 +816,19 @@ where
     /// when listening on all interfaces for IPv4 respectively IPv6 connections.
     listen_addr: SocketAddr,
     /// The async listening socket for incoming connections.
-    listener: T::Listener,
+    ///
+    /// `None` while the address of a re-bound listener has disappeared, see [`Rebind`].
+    listener: Option<T::Listener>,
     /// Watcher for network interface changes.
     /// Reports [`IfEvent`]s for new / deleted ip-addresses when interfaces
     /// become or stop being available.
     ///
-    /// `None` if the socket is only listening on a single interface.
+    /// `None` if the socket is only listening on a single interface, unless it is re-bound
+    /// on interface changes.
     if_watcher: Option<T::IfWatcher>,
+    /// Re-binds the listener once its address reappears, if listening on a single interface
+    /// with [`Config::rebind_on_interface_change`].
+    rebind: Option<Rebind>,
     /// The port reuse configuration for outgoing connections.
     ///
     /// If enabled, all IP addresses on which this listening stream
@@ -598,16 +860,18 @@ where
         listener: TcpListener,
         if_watcher: Option<T::IfWatcher>,
         port_reuse: PortReuse,
+        rebind: Option<Rebind>,
     ) -> io::Result<Self> {
         let listen_addr = listener.local_addr()?;
         let listener = T::new_listener(listener)?;
 
         Ok(ListenStream {
             port_reuse,
-            listener,
+            listener: Some(listener),
             listener_id,
             listen_addr,
             if_watcher,
+            rebind,
             pause: None,
             sleep_on_error: Duration::from_millis(100),
             pending_event: None,
@@ -624,13 +888,13 @@ where
     /// Has no effect if port reuse is disabled.
     fn disable_port_reuse(&mut self) {
         match &self.if_watcher {
-            Some(if_watcher) => {
+            Some(if_watcher) if self.rebind.is_none() => {
                 for ip_net in T::addrs(if_watcher) {
                     self.port_reuse
                         .unregister(ip_net.addr(), self.listen_addr.port());
                 }
             }
-            None => self
+            _ => self
                 .port_reuse
                 .unregister(self.listen_addr.ip(), self.listen_addr.port()),
         }
@@ -665,6 +929,60 @@ where
         let my_listen_addr_port = self.listen_addr.port();
 
         while let Poll::Ready(Some(event)) = if_watcher.poll_next_unpin(cx) {
+            if let Some(rebind) = self.rebind.as_mut() {
+                let listen_ip = self.listen_addr.ip();
+                match event {
+                    Ok(IfEvent::Up(inet)) if inet.addr() == listen_ip && rebind.expired => {
+                        let listener = rebind
+                            .config
+                            .bind_listener(self.listen_addr, &self.port_reuse)
+                            .and_then(T::new_listener);
+                        match listener {
+                            Ok(listener) => {
+                                rebind.expired = false;
+                                self.listener = Some(listener);
+                                let ma = ip_to_multiaddr(listen_ip, my_listen_addr_port);
+                                tracing::debug!(address=%ma, "Re-bound listener");
+                                self.port_reuse.register(listen_ip, my_listen_addr_port);
+                                return Poll::Ready(TransportEvent::NewAddress {
+                                    listener_id: self.listener_id,
+                                    listen_addr: ma,
+                                });
+                            }
+                            Err(error) => {
+                                // Retried once the address reappears again.
+                                return Poll::Ready(TransportEvent::ListenerError {
+                                    listener_id: self.listener_id,
+                                    error,
+                                });
+                            }
+                        }
+                    }
+                    Ok(IfEvent::Down(inet)) if inet.addr() == listen_ip && !rebind.expired => {
+                        rebind.expired = true;
+                        // Close the socket so that it can be bound anew once the address
+                        // reappears.
+                        self.listener = None;
+                        let ma = ip_to_multiaddr(listen_ip, my_listen_addr_port);
+                        tracing::debug!(address=%ma, "Expired listen address");
+                        self.port_reuse.unregister(listen_ip, my_listen_addr_port);
+                        return Poll::Ready(TransportEvent::AddressExpired {
+                            listener_id: self.listener_id,
+                            listen_addr: ma,
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(error) => {
+                        self.pause = Some(Delay::new(self.sleep_on_error));
+                        return Poll::Ready(TransportEvent::ListenerError {
+                            listener_id: self.listener_id,
+                            error,
+                        });
+                    }
+                }
+                continue;
+            }
+
             match event {
                 Ok(IfEvent::Up(inet)) => {
                     let ip = inet.addr();
@@ -704,6 +1022,14 @@ where
     }
 }
 
+/// Re-binds a listener on a single interface, see [`Config::rebind_on_interface_change`].
+struct Rebind {
+    /// Config for creating the new listening socket.
+    config: Config,
+    /// Whether the address of the listener disappeared from all interfaces.
+    expired: bool,
+}
+
 impl<T> Drop for ListenStream<T>
 where
     T: Provider,
@@ -745,8 +1071,13 @@ where
             return Poll::Ready(Some(event));
         }
 
+        let Some(listener) = self.listener.as_mut() else {
+            self.close_listener_waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        };
+
         // Take the pending connection from the backlog.
-        match T::poll_accept(&mut self.listener, cx) {
+        match T::poll_accept(listener, cx) {
             Poll::Ready(Ok(Incoming {
                 local_addr,
                 remote_addr,
@@ -784,6 +1115,28 @@ where
     }
 }
 
+/// Sets a TCP level socket option not exposed by `socket2`.
+#[cfg(target_os = "linux")]
+fn set_tcp_option(socket: &Socket, option: libc::c_int, value: libc::c_int) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    // SAFETY: The file descriptor is valid for the lifetime of `socket` and `value` outlives the
+    // call.
+    let res = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            option,
+            &value as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if res == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
 /// Extracts a `SocketAddr` from a given `Multiaddr`.
 ///
 /// Fails if the given `Multiaddr` does not begin with an IP
@@ -1202,6 +1555,66 @@ mod tests {
         test("/ip4/127.0.0.1/tcp/0".parse().unwrap());
     }
 
+    #[test]
+    fn port_conflict_fallback() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        async fn listen_conflicting<T: Provider>(addr: Multiaddr) {
+            let mut tcp1 = Transport::<T>::new(Config::new());
+            tcp1.listen_on(ListenerId::next(), addr).unwrap();
+            let addr1 = match poll_fn(|cx| Pin::new(&mut tcp1).poll(cx)).await {
+                TransportEvent::NewAddress { listen_addr, .. } => listen_addr,
+                e => panic!("Unexpected transport event: {e:?}"),
+            };
+
+            // Without a fallback, listening on a port in use fails.
+            let mut tcp2 = Transport::<T>::new(Config::new());
+            match tcp2.listen_on(ListenerId::next(), addr1.clone()) {
+                Err(TransportError::Other(e)) => assert_eq!(e.kind(), io::ErrorKind::AddrInUse),
+                r => panic!("Unexpected result: {r:?}"),
+            }
+
+            let mut tcp3 = Transport::<T>::new(
+                Config::new().port_conflict_policy(PortConflictPolicy::UseEphemeral),
+            );
+            let id = ListenerId::next();
+            tcp3.listen_on(id, addr1.clone()).unwrap();
+            let addr3 = match poll_fn(|cx| Pin::new(&mut tcp3).poll(cx)).await {
+                TransportEvent::NewAddress {
+                    listener_id,
+                    listen_addr,
+                } => {
+                    assert_eq!(listener_id, id);
+                    listen_addr
+                }
+                e => panic!("Unexpected transport event: {e:?}"),
+            };
+            assert_ne!(addr1, addr3);
+        }
+
+        fn test(addr: Multiaddr) {
+            #[cfg(feature = "async-io")]
+            {
+                let listener = listen_conflicting::<async_io::Tcp>(addr.clone());
+                async_std::task::block_on(listener);
+            }
+
+            #[cfg(feature = "tokio")]
+            {
+                let listener = listen_conflicting::<tokio::Tcp>(addr);
+                let rt = ::tokio::runtime::Builder::new_current_thread()
+                    .enable_io()
+                    .build()
+                    .unwrap();
+                rt.block_on(listener);
+            }
+        }
+
+        test("/ip4/127.0.0.1/tcp/0".parse().unwrap());
+    }
+
     #[test]
     fn listen_port_0() {
         let _ = tracing_subscriber::fmt()
@@ -1262,6 +1675,26 @@ mod tests {
         test("/ip4/127.0.0.1/tcp/12345/tcp/12345".parse().unwrap());
     }
 
+    #[test]
+    fn socket_options_are_applied() {
+        let config = Config::new()
+            .nodelay(true)
+            .keepalive(
+                Keepalive::new(Duration::from_secs(30)).with_interval(Duration::from_secs(5)),
+            )
+            .send_buffer_size(64 * 1024)
+            .recv_buffer_size(64 * 1024);
+        let socket = config
+            .create_socket("127.0.0.1:0".parse().unwrap(), &PortReuse::Disabled)
+            .unwrap();
+
+        assert!(socket.nodelay().unwrap());
+        assert!(socket.keepalive().unwrap());
+        // The OS may round up or double the requested buffer sizes.
+        assert!(socket.send_buffer_size().unwrap() >= 64 * 1024);
+        assert!(socket.recv_buffer_size().unwrap() >= 64 * 1024);
+    }
+
     #[cfg(feature = "async-io")]
     #[test]
     fn test_address_translation_async_io() {
@@ -1340,6 +1773,153 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_remove_listener_graceful_stops_accepting() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        async fn drain_listener<T: Provider>() {
+            let mut tcp = Transport::<T>::default().boxed();
+            let listener_id = ListenerId::next();
+            tcp.listen_on(listener_id, "/ip4/127.0.0.1/tcp/0".parse().unwrap())
+                .unwrap();
+            let listen_addr = match tcp.select_next_some().await {
+                TransportEvent::NewAddress { listen_addr, .. } => listen_addr,
+                e => panic!("Unexpected transport event: {e:?}"),
+            };
+
+            // A connection in the backlog is not accepted anymore once the listener is removed.
+            let _stream =
+                std::net::TcpStream::connect(multiaddr_to_socketaddr(listen_addr).unwrap())
+                    .unwrap();
+            assert!(tcp.remove_listener_graceful(listener_id, Duration::from_secs(5)));
+
+            assert!(matches!(
+                tcp.select_next_some().await,
+                TransportEvent::ListenerClosed { reason: Ok(()), .. }
+            ));
+        }
+
+        #[cfg(feature = "async-io")]
+        {
+            async_std::task::block_on(drain_listener::<async_io::Tcp>());
+        }
+
+        #[cfg(feature = "tokio")]
+        {
+            let rt = ::tokio::runtime::Builder::new_current_thread()
+                .enable_io()
+                .build()
+                .unwrap();
+            rt.block_on(drain_listener::<tokio::Tcp>());
+        }
+    }
+
+    #[test]
+    fn rebind_on_interface_change() {
+        let _ = tracing_subscriber::fmt()
+            .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+            .try_init();
+
+        /// Wraps a [`Provider`] with an [`IfEvent`] stream that is driven by the test.
+        #[derive(Clone)]
+        struct ControlledIf<T>(std::marker::PhantomData<T>);
+
+        impl<T: Provider> Provider for ControlledIf<T> {
+            type Stream = T::Stream;
+            type Listener = T::Listener;
+            type IfWatcher = mpsc::Receiver<io::Result<IfEvent>>;
+
+            fn new_if_watcher() -> io::Result<Self::IfWatcher> {
+                unreachable!("The watcher is passed to the `ListenStream` directly.")
+            }
+
+            fn addrs(_: &Self::IfWatcher) -> Vec<if_watch::IpNet> {
+                Vec::new()
+            }
+
+            fn new_listener(listener: TcpListener) -> io::Result<Self::Listener> {
+                T::new_listener(listener)
+            }
+
+            fn new_stream(
+                stream: std::net::TcpStream,
+            ) -> futures::future::BoxFuture<'static, io::Result<Self::Stream>> {
+                T::new_stream(stream)
+            }
+
+            fn poll_accept(
+                listener: &mut Self::Listener,
+                cx: &mut Context<'_>,
+            ) -> Poll<io::Result<Incoming<Self::Stream>>> {
+                T::poll_accept(listener, cx)
+            }
+        }
+
+        async fn rebind<T: Provider>() {
+            let config = Config::new();
+            let listener = config
+                .bind_listener("127.0.0.1:0".parse().unwrap(), &PortReuse::Disabled)
+                .unwrap();
+            let socket_addr = listener.local_addr().unwrap();
+            let (mut if_tx, if_rx) = mpsc::channel(1);
+            let mut listen_stream = ListenStream::<ControlledIf<T>>::new(
+                ListenerId::next(),
+                listener,
+                Some(if_rx),
+                PortReuse::Disabled,
+                Some(Rebind {
+                    config,
+                    expired: false,
+                }),
+            )
+            .unwrap();
+            let ip_net = if_watch::IpNet::from(socket_addr.ip());
+            let expected_addr = ip_to_multiaddr(socket_addr.ip(), socket_addr.port());
+
+            if_tx.send(Ok(IfEvent::Down(ip_net))).await.unwrap();
+            match listen_stream.next().await.unwrap() {
+                TransportEvent::AddressExpired { listen_addr, .. } => {
+                    assert_eq!(listen_addr, expected_addr)
+                }
+                e => panic!("Unexpected transport event: {e:?}"),
+            }
+            // The socket is closed while the address is gone.
+            assert!(std::net::TcpStream::connect(socket_addr).is_err());
+
+            if_tx.send(Ok(IfEvent::Up(ip_net))).await.unwrap();
+            match listen_stream.next().await.unwrap() {
+                TransportEvent::NewAddress { listen_addr, .. } => {
+                    assert_eq!(listen_addr, expected_addr)
+                }
+                e => panic!("Unexpected transport event: {e:?}"),
+            }
+
+            let _stream = std::net::TcpStream::connect(socket_addr).unwrap();
+            match listen_stream.next().await.unwrap() {
+                TransportEvent::Incoming { local_addr, .. } => {
+                    assert_eq!(local_addr, expected_addr)
+                }
+                e => panic!("Unexpected transport event: {e:?}"),
+            }
+        }
+
+        #[cfg(feature = "async-io")]
+        {
+            async_std::task::block_on(rebind::<async_io::Tcp>());
+        }
+
+        #[cfg(feature = "tokio")]
+        {
+            let rt = ::tokio::runtime::Builder::new_current_thread()
+                .enable_io()
+                .build()
+                .unwrap();
+            rt.block_on(rebind::<tokio::Tcp>());
+        }
+    }
+
     #[test]
     fn test_listens_ipv4_ipv6_separately() {
         fn test<T: Provider>() {
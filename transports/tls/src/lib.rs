@@ -34,8 +34,9 @@ use libp2p_identity::PeerId;
 use std::sync::Arc;
 
 pub use futures_rustls::TlsStream;
-pub use upgrade::Config;
 pub use upgrade::UpgradeError;
+pub use upgrade::{Config, DEFAULT_SESSION_CACHE_SIZE};
+pub use verifier::{AdditionalVerifier, Extension};
 
 const P2P_ALPN: [u8; 6] = *b"libp2p";
 
@@ -43,8 +44,21 @@ const P2P_ALPN: [u8; 6] = *b"libp2p";
 pub fn make_client_config(
     keypair: &Keypair,
     remote_peer_id: Option<PeerId>,
+) -> Result<rustls::ClientConfig, certificate::GenError> {
+    make_client_config_with_extension(keypair, remote_peer_id, &Extension::default())
+}
+
+/// Create a TLS client configuration for libp2p with application-supplied additions to the
+/// certificate handling.
+pub fn make_client_config_with_extension(
+    keypair: &Keypair,
+    remote_peer_id: Option<PeerId>,
+    extension: &Extension,
 ) -> Result<rustls::ClientConfig, certificate::GenError> {
     let (certificate, private_key) = certificate::generate(keypair)?;
+    let certificates = std::iter::once(certificate)
+        .chain(extension.certificate_chain.iter().cloned())
+        .collect();
 
     let mut provider = rustls::crypto::ring::default_provider();
     provider.cipher_suites = verifier::CIPHERSUITES.to_vec();
@@ -54,9 +68,10 @@ pub fn make_client_config(
         .expect("Cipher suites and kx groups are configured; qed")
         .dangerous()
         .with_custom_certificate_verifier(Arc::new(
-            verifier::Libp2pCertificateVerifier::with_remote_peer_id(remote_peer_id),
+            verifier::Libp2pCertificateVerifier::with_remote_peer_id(remote_peer_id)
+                .with_additional(extension.verifier.clone()),
         ))
-        .with_client_auth_cert(vec![certificate], private_key)
+        .with_client_auth_cert(certificates, private_key)
         .expect("Client cert key DER is valid; qed");
     crypto.alpn_protocols = vec![P2P_ALPN.to_vec()];
 
@@ -66,8 +81,20 @@ pub fn make_client_config(
 /// Create a TLS server configuration for libp2p.
 pub fn make_server_config(
     keypair: &Keypair,
+) -> Result<rustls::ServerConfig, certificate::GenError> {
+    make_server_config_with_extension(keypair, &Extension::default())
+}
+
+/// Create a TLS server configuration for libp2p with application-supplied additions to the
+/// certificate handling.
+pub fn make_server_config_with_extension(
+    keypair: &Keypair,
+    extension: &Extension,
 ) -> Result<rustls::ServerConfig, certificate::GenError> {
     let (certificate, private_key) = certificate::generate(keypair)?;
+    let certificates = std::iter::once(certificate)
+        .chain(extension.certificate_chain.iter().cloned())
+        .collect();
 
     let mut provider = rustls::crypto::ring::default_provider();
     provider.cipher_suites = verifier::CIPHERSUITES.to_vec();
@@ -75,8 +102,10 @@ pub fn make_server_config(
     let mut crypto = rustls::ServerConfig::builder_with_provider(provider.into())
         .with_protocol_versions(verifier::PROTOCOL_VERSIONS)
         .expect("Cipher suites and kx groups are configured; qed")
-        .with_client_cert_verifier(Arc::new(verifier::Libp2pCertificateVerifier::new()))
-        .with_single_cert(vec![certificate], private_key)
+        .with_client_cert_verifier(Arc::new(
+            verifier::Libp2pCertificateVerifier::new().with_additional(extension.verifier.clone()),
+        ))
+        .with_single_cert(certificates, private_key)
         .expect("Server cert key DER is valid; qed");
     crypto.alpn_protocols = vec![P2P_ALPN.to_vec()];
 
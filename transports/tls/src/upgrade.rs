@@ -20,6 +20,7 @@
 
 use crate::certificate;
 use crate::certificate::P2pCertificate;
+use crate::Extension;
 use futures::future::BoxFuture;
 use futures::AsyncWrite;
 use futures::{AsyncRead, FutureExt};
@@ -28,6 +29,8 @@ use libp2p_core::upgrade::{InboundConnectionUpgrade, OutboundConnectionUpgrade};
 use libp2p_core::UpgradeInfo;
 use libp2p_identity as identity;
 use libp2p_identity::PeerId;
+use rustls::client::Resumption;
+use rustls::server::{NoServerSessionStorage, ServerSessionMemoryCache};
 use rustls::{pki_types::ServerName, CommonState};
 
 use std::net::{IpAddr, Ipv4Addr};
@@ -45,6 +48,13 @@ pub enum UpgradeError {
     BadCertificate(#[from] certificate::ParseError),
 }
 
+/// The default number of TLS sessions kept in memory for resumption, see
+/// [`Config::with_session_cache_size`].
+pub const DEFAULT_SESSION_CACHE_SIZE: usize = 256;
+
+/// The number of session tickets issued to a client per connection.
+const TICKETS_PER_CONNECTION: usize = 2;
+
 #[derive(Clone)]
 pub struct Config {
     server: rustls::ServerConfig,
@@ -56,7 +66,44 @@ impl Config {
         Ok(Self {
             server: crate::make_server_config(identity)?,
             client: crate::make_client_config(identity, None)?,
-        })
+        }
+        .with_session_cache_size(DEFAULT_SESSION_CACHE_SIZE))
+    }
+
+    /// Creates a configuration verifying and presenting certificates in addition to the libp2p
+    /// certificate as configured by `extension`.
+    pub fn with_extension(
+        identity: &identity::Keypair,
+        extension: &Extension,
+    ) -> Result<Self, certificate::GenError> {
+        Ok(Self {
+            server: crate::make_server_config_with_extension(identity, extension)?,
+            client: crate::make_client_config_with_extension(identity, None, extension)?,
+        }
+        .with_session_cache_size(DEFAULT_SESSION_CACHE_SIZE))
+    }
+
+    /// Sets the number of TLS sessions kept in memory to resume them on reconnects, skipping
+    /// the certificate exchange of a full handshake. `0` disables session resumption.
+    ///
+    /// As a listener, the sessions are kept for session tickets issued to dialers. As a dialer,
+    /// the session tickets received from listeners are kept and offered on the next connection.
+    /// As libp2p TLS does not send a server name, a dialer offers its most recent ticket
+    /// regardless of the peer it dials, allowing colluding peers to link its connections.
+    /// Disable session resumption in privacy-sensitive deployments.
+    ///
+    /// Defaults to [`DEFAULT_SESSION_CACHE_SIZE`].
+    pub fn with_session_cache_size(mut self, size: usize) -> Self {
+        if size == 0 {
+            self.client.resumption = Resumption::disabled();
+            self.server.session_storage = Arc::new(NoServerSessionStorage {});
+            self.server.send_tls13_tickets = 0;
+        } else {
+            self.client.resumption = Resumption::in_memory_sessions(size);
+            self.server.session_storage = ServerSessionMemoryCache::new(size);
+            self.server.send_tls13_tickets = TICKETS_PER_CONNECTION;
+        }
+        self
     }
 }
 
@@ -121,11 +168,12 @@ where
     }
 }
 
+/// Extracts the libp2p certificate, which precedes any certificates presented in addition.
 fn extract_single_certificate(
     state: &CommonState,
 ) -> Result<P2pCertificate<'_>, certificate::ParseError> {
-    let Some([cert]) = state.peer_certificates() else {
-        panic!("config enforces exactly one certificate");
+    let Some([cert, ..]) = state.peer_certificates() else {
+        panic!("config enforces at least one certificate");
     };
 
     certificate::parse(cert)
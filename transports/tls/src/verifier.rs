@@ -30,12 +30,12 @@ use rustls::{
     crypto::ring::cipher_suite::{
         TLS13_AES_128_GCM_SHA256, TLS13_AES_256_GCM_SHA384, TLS13_CHACHA20_POLY1305_SHA256,
     },
-    pki_types::CertificateDer,
+    pki_types::{CertificateDer, UnixTime},
     server::danger::{ClientCertVerified, ClientCertVerifier},
     CertificateError, DigitallySignedStruct, DistinguishedName, OtherError, SignatureScheme,
     SupportedCipherSuite, SupportedProtocolVersion,
 };
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
 
 /// The protocol versions supported by this verifier.
 ///
@@ -54,6 +54,58 @@ pub(crate) static CIPHERSUITES: &[SupportedCipherSuite] = &[
     TLS13_AES_128_GCM_SHA256,
 ];
 
+/// An application-supplied verification of the certificates presented by a remote, performed
+/// after the libp2p certificate passed the checks of the libp2p TLS spec.
+///
+/// This allows permissioned deployments, e.g. requiring that the libp2p certificate of a remote
+/// is accompanied by a certificate for its peer ID signed by an enterprise CA.
+pub trait AdditionalVerifier: fmt::Debug + Send + Sync {
+    /// Verifies the certificates presented by `peer_id`.
+    ///
+    /// `end_entity` is the libp2p certificate of the remote and `chain` the certificates it
+    /// presented in addition, see [`Extension::with_certificate_chain`].
+    fn verify(
+        &self,
+        peer_id: &PeerId,
+        end_entity: &CertificateDer<'_>,
+        chain: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<(), rustls::Error>;
+}
+
+/// Application-supplied additions to the libp2p certificate handling.
+///
+/// See [`Config::with_extension`](crate::Config::with_extension).
+#[derive(Clone, Debug, Default)]
+pub struct Extension {
+    pub(crate) verifier: Option<Arc<dyn AdditionalVerifier>>,
+    pub(crate) certificate_chain: Vec<CertificateDer<'static>>,
+}
+
+impl Extension {
+    /// Creates an extension that neither verifies nor presents additional certificates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Verifies the certificates of remotes with `verifier` in addition to the libp2p checks.
+    ///
+    /// Remotes are then allowed to present more than the libp2p certificate.
+    pub fn with_verifier(mut self, verifier: Arc<dyn AdditionalVerifier>) -> Self {
+        self.verifier = Some(verifier);
+        self
+    }
+
+    /// Presents `chain` to remotes after the libp2p certificate.
+    ///
+    /// Remotes not configured with an [`AdditionalVerifier`] abort the handshake, as the libp2p
+    /// TLS spec requires exactly one certificate.
+    pub fn with_certificate_chain(mut self, chain: Vec<CertificateDer<'static>>) -> Self {
+        self.certificate_chain = chain;
+        self
+    }
+}
+
 /// Implementation of the `rustls` certificate verification traits for libp2p.
 ///
 /// Only TLS 1.3 is supported. TLS 1.2 should be disabled in the configuration of `rustls`.
@@ -61,6 +113,8 @@ pub(crate) static CIPHERSUITES: &[SupportedCipherSuite] = &[
 pub(crate) struct Libp2pCertificateVerifier {
     /// The peer ID we intend to connect to
     remote_peer_id: Option<PeerId>,
+    /// The application-supplied verification performed after the libp2p checks.
+    additional: Option<Arc<dyn AdditionalVerifier>>,
 }
 
 /// libp2p requires the following of X.509 server certificate chains:
@@ -73,10 +127,21 @@ impl Libp2pCertificateVerifier {
     pub(crate) fn new() -> Self {
         Self {
             remote_peer_id: None,
+            additional: None,
         }
     }
     pub(crate) fn with_remote_peer_id(remote_peer_id: Option<PeerId>) -> Self {
-        Self { remote_peer_id }
+        Self {
+            remote_peer_id,
+            additional: None,
+        }
+    }
+    pub(crate) fn with_additional(
+        mut self,
+        additional: Option<Arc<dyn AdditionalVerifier>>,
+    ) -> Self {
+        self.additional = additional;
+        self
     }
 
     /// Return the list of SignatureSchemes that this verifier will handle,
@@ -109,9 +174,10 @@ impl ServerCertVerifier for Libp2pCertificateVerifier {
         intermediates: &[CertificateDer],
         _server_name: &rustls::pki_types::ServerName,
         _ocsp_response: &[u8],
-        _now: rustls::pki_types::UnixTime,
+        now: UnixTime,
     ) -> Result<ServerCertVerified, rustls::Error> {
-        let peer_id = verify_presented_certs(end_entity, intermediates)?;
+        let peer_id =
+            verify_presented_certs(end_entity, intermediates, self.additional.as_deref(), now)?;
 
         if let Some(remote_peer_id) = self.remote_peer_id {
             // The public host key allows the peer to calculate the peer ID of the peer
@@ -171,9 +237,9 @@ impl ClientCertVerifier for Libp2pCertificateVerifier {
         &self,
         end_entity: &CertificateDer,
         intermediates: &[CertificateDer],
-        _now: rustls::pki_types::UnixTime,
+        now: UnixTime,
     ) -> Result<ClientCertVerified, rustls::Error> {
-        verify_presented_certs(end_entity, intermediates)?;
+        verify_presented_certs(end_entity, intermediates, self.additional.as_deref(), now)?;
 
         Ok(ClientCertVerified::assertion())
     }
@@ -207,19 +273,27 @@ impl ClientCertVerifier for Libp2pCertificateVerifier {
 /// (b) if it is expired.
 /// Endpoints MUST abort the connection attempt if more than one certificate is received,
 /// or if the certificate’s self-signature is not valid.
+///
+/// With an [`AdditionalVerifier`], further certificates are allowed and passed to it.
 fn verify_presented_certs(
     end_entity: &CertificateDer,
     intermediates: &[CertificateDer],
+    additional: Option<&dyn AdditionalVerifier>,
+    now: UnixTime,
 ) -> Result<PeerId, rustls::Error> {
-    if !intermediates.is_empty() {
+    if !intermediates.is_empty() && additional.is_none() {
         return Err(rustls::Error::General(
             "libp2p-tls requires exactly one certificate".into(),
         ));
     }
 
-    let cert = certificate::parse(end_entity)?;
+    let peer_id = certificate::parse(end_entity)?.peer_id();
+
+    if let Some(additional) = additional {
+        additional.verify(&peer_id, end_entity, intermediates, now)?;
+    }
 
-    Ok(cert.peer_id())
+    Ok(peer_id)
 }
 
 fn verify_tls13_signature(
@@ -3,7 +3,12 @@ use libp2p_core::multiaddr::Protocol;
 use libp2p_core::transport::MemoryTransport;
 use libp2p_core::upgrade::Version;
 use libp2p_core::Transport;
+use libp2p_identity::PeerId;
 use libp2p_swarm::{dummy, Config, Swarm, SwarmEvent};
+use libp2p_tls::{AdditionalVerifier, Extension};
+use rustls::pki_types::{CertificateDer, UnixTime};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
 #[tokio::test]
@@ -56,12 +61,177 @@ async fn can_establish_connection() {
     assert_eq!(&outbound_peer_id, swarm1.local_peer_id());
 }
 
+#[tokio::test]
+async fn additional_verifier_checks_certificate_chain() {
+    let enterprise_cert = CertificateDer::from(
+        rcgen::generate_simple_self_signed(vec!["enterprise".to_owned()])
+            .unwrap()
+            .serialize_der()
+            .unwrap(),
+    );
+    let extension = Extension::new()
+        .with_verifier(Arc::new(RequireChain(enterprise_cert.clone())))
+        .with_certificate_chain(vec![enterprise_cert]);
+
+    let mut swarm1 = make_swarm_with_extension(Some(&extension));
+    let mut swarm2 = make_swarm_with_extension(Some(&extension));
+    let mut outsider = make_swarm_with_extension(None);
+
+    let expected_listener_id = swarm1.listen_on(Protocol::Memory(0).into()).unwrap();
+    let listen_address = loop {
+        match swarm1.next().await.unwrap() {
+            SwarmEvent::NewListenAddr {
+                address,
+                listener_id,
+            } if listener_id == expected_listener_id => break address,
+            _ => continue,
+        };
+    };
+    swarm2.dial(listen_address.clone()).unwrap();
+    outsider.dial(listen_address).unwrap();
+
+    let mut established = None;
+    let mut outsider_failed = false;
+    while established.is_none() || !outsider_failed {
+        tokio::select! {
+            event = swarm1.next() => match event.unwrap() {
+                SwarmEvent::ConnectionEstablished { peer_id, .. } => {
+                    assert_eq!(&peer_id, swarm2.local_peer_id());
+                    established = Some(peer_id);
+                }
+                _ => {}
+            },
+            event = swarm2.next() => if let SwarmEvent::OutgoingConnectionError { error, .. } = event.unwrap() {
+                panic!("Failed to dial: {error}")
+            },
+            event = outsider.next() => match event.unwrap() {
+                SwarmEvent::OutgoingConnectionError { .. } => outsider_failed = true,
+                SwarmEvent::ConnectionEstablished { .. } => panic!("Outsider must be rejected"),
+                _ => {}
+            },
+        }
+    }
+}
+
+#[tokio::test]
+async fn reconnects_resume_session() {
+    // Certificates are only verified in full handshakes, once by each side.
+    assert_eq!(count_verifications_of_two_connections(None).await, 2);
+    assert_eq!(count_verifications_of_two_connections(Some(0)).await, 4);
+}
+
+/// Connects twice to the same peer, returning how often certificates were verified.
+async fn count_verifications_of_two_connections(session_cache_size: Option<usize>) -> usize {
+    let verifier = Arc::new(CountVerifications::default());
+    let extension = Extension::new().with_verifier(verifier.clone());
+    let config = |identity: &libp2p_identity::Keypair| {
+        let config = libp2p_tls::Config::with_extension(identity, &extension).unwrap();
+        match session_cache_size {
+            Some(size) => config.with_session_cache_size(size),
+            None => config,
+        }
+    };
+    let mut swarm1 = make_swarm_with_config(config);
+    let mut swarm2 = make_swarm_with_config(config);
+
+    let expected_listener_id = swarm1.listen_on(Protocol::Memory(0).into()).unwrap();
+    let listen_address = loop {
+        match swarm1.next().await.unwrap() {
+            SwarmEvent::NewListenAddr {
+                address,
+                listener_id,
+            } if listener_id == expected_listener_id => break address,
+            _ => continue,
+        };
+    };
+
+    for _ in 0..2 {
+        swarm2.dial(listen_address.clone()).unwrap();
+        loop {
+            tokio::select! {
+                _ = swarm1.next() => {}
+                event = swarm2.next() => match event.unwrap() {
+                    SwarmEvent::ConnectionEstablished { .. } => break,
+                    SwarmEvent::OutgoingConnectionError { error, .. } => {
+                        panic!("Failed to dial: {error}")
+                    }
+                    _ => {}
+                },
+            }
+        }
+        // Session tickets are sent after the handshake completed.
+        let _ = tokio::time::timeout(Duration::from_millis(100), async {
+            loop {
+                tokio::select! {
+                    _ = swarm1.next() => {}
+                    _ = swarm2.next() => {}
+                }
+            }
+        })
+        .await;
+    }
+
+    verifier.0.load(Ordering::SeqCst)
+}
+
+/// Counts the verified certificates.
+#[derive(Debug, Default)]
+struct CountVerifications(AtomicUsize);
+
+impl AdditionalVerifier for CountVerifications {
+    fn verify(
+        &self,
+        _: &PeerId,
+        _: &CertificateDer<'_>,
+        _: &[CertificateDer<'_>],
+        _: UnixTime,
+    ) -> Result<(), rustls::Error> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Requires remotes to present the given certificate in addition to their libp2p certificate.
+#[derive(Debug)]
+struct RequireChain(CertificateDer<'static>);
+
+impl AdditionalVerifier for RequireChain {
+    fn verify(
+        &self,
+        _: &PeerId,
+        _: &CertificateDer<'_>,
+        chain: &[CertificateDer<'_>],
+        _: UnixTime,
+    ) -> Result<(), rustls::Error> {
+        if chain != [self.0.clone()] {
+            return Err(rustls::Error::General(
+                "missing enterprise certificate".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
 fn make_swarm() -> Swarm<dummy::Behaviour> {
+    make_swarm_with_extension(None)
+}
+
+fn make_swarm_with_extension(extension: Option<&Extension>) -> Swarm<dummy::Behaviour> {
+    make_swarm_with_config(|identity| match extension {
+        Some(extension) => libp2p_tls::Config::with_extension(identity, extension).unwrap(),
+        None => libp2p_tls::Config::new(identity).unwrap(),
+    })
+}
+
+fn make_swarm_with_config(
+    config: impl FnOnce(&libp2p_identity::Keypair) -> libp2p_tls::Config,
+) -> Swarm<dummy::Behaviour> {
     let identity = libp2p_identity::Keypair::generate_ed25519();
+    let config = config(&identity);
 
     let transport = MemoryTransport::default()
         .upgrade(Version::V1)
-        .authenticate(libp2p_tls::Config::new(&identity).unwrap())
+        .authenticate(config)
         .multiplex(libp2p_yamux::Config::default())
         .boxed();
 
@@ -0,0 +1,103 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+
+/// The credentials of the process on the other end of a Unix domain socket,
+/// as of the time the connection was established.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PeerCredentials {
+    /// The effective user ID of the process.
+    pub uid: u32,
+    /// The effective group ID of the process.
+    pub gid: u32,
+    /// The ID of the process, if provided by the platform.
+    pub pid: Option<i32>,
+}
+
+/// Returns the credentials of the process on the other end of a connection
+/// of the Unix domain sockets transport.
+///
+/// Uses `SO_PEERCRED` on Linux and Android and `getpeereid` on other platforms.
+pub fn peer_credentials(stream: &impl AsRawFd) -> io::Result<PeerCredentials> {
+    credentials(stream.as_raw_fd())
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn credentials(fd: RawFd) -> io::Result<PeerCredentials> {
+    let mut ucred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: `ucred` and `len` are valid for writes and `len` matches the size of `ucred`.
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut ucred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(PeerCredentials {
+        uid: ucred.uid,
+        gid: ucred.gid,
+        pid: Some(ucred.pid),
+    })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn credentials(fd: RawFd) -> io::Result<PeerCredentials> {
+    let mut uid = 0;
+    let mut gid = 0;
+    // SAFETY: `uid` and `gid` are valid for writes.
+    if unsafe { libc::getpeereid(fd, &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(PeerCredentials {
+        uid,
+        gid,
+        pid: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn credentials_of_own_process() {
+        let (a, _b) = UnixStream::pair().unwrap();
+
+        let credentials = peer_credentials(&a).unwrap();
+
+        // SAFETY: Always successful.
+        assert_eq!(credentials.uid, unsafe { libc::geteuid() });
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        assert_eq!(credentials.pid, Some(std::process::id() as i32));
+    }
+}
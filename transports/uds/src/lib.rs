@@ -27,9 +27,39 @@
 //! # Usage
 //!
 //! The `UdsConfig` transport supports multiaddresses of the form `/unix//tmp/foo`.
+//! On Linux and Android, sockets in the abstract namespace are addressed by a leading `@`,
+//! e.g. `/unix/@foo`.
 //!
 //! The `UdsConfig` structs implements the `Transport` trait of the `core` library. See the
 //! documentation of `core` and of libp2p in general to learn how to use the `Transport` trait.
+//!
+//! # Peer credentials
+//!
+//! The credentials of the process on the other end of a connection can be obtained via
+//! [`peer_credentials`], e.g. to only accept connections of processes of the same user
+//! before upgrading them:
+//!
+//! ```no_run
+//! # #[cfg(feature = "tokio")]
+//! # fn main() {
+//! use futures::future;
+//! use libp2p_core::Transport;
+//! use libp2p_uds::{peer_credentials, TokioUdsConfig};
+//! use std::io;
+//!
+//! let transport = TokioUdsConfig::new().and_then(|stream, _| {
+//!     let same_user = peer_credentials(&stream)
+//!         .map(|credentials| credentials.uid == unsafe { libc::geteuid() });
+//!     future::ready(match same_user {
+//!         Ok(true) => Ok(stream),
+//!         Ok(false) => Err(io::Error::from(io::ErrorKind::PermissionDenied)),
+//!         Err(e) => Err(e),
+//!     })
+//! });
+//! # }
+//! # #[cfg(not(feature = "tokio"))]
+//! # fn main() {}
+//! ```
 
 #![cfg(all(
     unix,
@@ -50,10 +80,15 @@ use libp2p_core::{
     Transport,
 };
 use std::collections::VecDeque;
+use std::os::unix::net::SocketAddr;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::{io, path::PathBuf};
 
+mod credentials;
+
+pub use credentials::{peer_credentials, PeerCredentials};
+
 pub type Listener<T> = BoxStream<
     'static,
     Result<
@@ -63,7 +98,7 @@ pub type Listener<T> = BoxStream<
 >;
 
 macro_rules! codegen {
-    ($feature_name:expr, $uds_config:ident, $build_listener:expr, $unix_stream:ty, $($mut_or_not:tt)*) => {
+    ($feature_name:expr, $uds_config:ident, $build_listener:expr, $from_std_stream:expr, $unix_stream:ty, $($mut_or_not:tt)*) => {
         /// Represents the configuration for a Unix domain sockets transport capability for libp2p.
         pub struct $uds_config {
             listeners: VecDeque<(ListenerId, Listener<Self>)>,
@@ -95,9 +130,9 @@ macro_rules! codegen {
                 id: ListenerId,
                 addr: Multiaddr,
             ) -> Result<(), TransportError<Self::Error>> {
-                if let Ok(path) = multiaddr_to_path(&addr) {
+                if let Ok(address) = multiaddr_to_address(&addr) {
                     #[allow(clippy::redundant_closure_call)]
-                    let listener = $build_listener(path)
+                    let listener = $build_listener(address)
                         .map_err(Err)
                         .map_ok(move |listener| {
                             stream::once({
@@ -161,11 +196,25 @@ macro_rules! codegen {
 
             fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
                 // TODO: Should we dial at all?
-                if let Ok(path) = multiaddr_to_path(&addr) {
-                    tracing::debug!(address=%addr, "Dialing address");
-                    Ok(async move { <$unix_stream>::connect(&path).await }.boxed())
-                } else {
-                    Err(TransportError::MultiaddrNotSupported(addr))
+                match multiaddr_to_address(&addr) {
+                    Ok(SocketAddress::Path(path)) => {
+                        tracing::debug!(address=%addr, "Dialing address");
+                        Ok(async move { <$unix_stream>::connect(&path).await }.boxed())
+                    }
+                    #[cfg(any(target_os = "linux", target_os = "android"))]
+                    Ok(address @ SocketAddress::Abstract(_)) => {
+                        tracing::debug!(address=%addr, "Dialing address");
+                        Ok(async move {
+                            // Connecting to a local socket doesn't block unless its backlog is full.
+                            let stream =
+                                std::os::unix::net::UnixStream::connect_addr(&address.to_socket_addr()?)?;
+                            stream.set_nonblocking(true)?;
+                            #[allow(clippy::redundant_closure_call)]
+                            $from_std_stream(stream)
+                        }
+                        .boxed())
+                    }
+                    Err(()) => Err(TransportError::MultiaddrNotSupported(addr)),
                 }
             }
 
@@ -221,42 +270,85 @@ macro_rules! codegen {
 codegen!(
     "async-std",
     UdsConfig,
-    |addr| async move { async_std::os::unix::net::UnixListener::bind(&addr).await },
+    |address: SocketAddress| async move {
+        let listener = std::os::unix::net::UnixListener::bind_addr(&address.to_socket_addr()?)?;
+        listener.set_nonblocking(true)?;
+        Ok::<_, io::Error>(async_std::os::unix::net::UnixListener::from(listener))
+    },
+    |stream: std::os::unix::net::UnixStream| {
+        Ok::<_, io::Error>(async_std::os::unix::net::UnixStream::from(stream))
+    },
     async_std::os::unix::net::UnixStream,
 );
 #[cfg(feature = "tokio")]
 codegen!(
     "tokio",
     TokioUdsConfig,
-    |addr| async move { tokio::net::UnixListener::bind(&addr) },
+    |address: SocketAddress| async move {
+        let listener = std::os::unix::net::UnixListener::bind_addr(&address.to_socket_addr()?)?;
+        listener.set_nonblocking(true)?;
+        tokio::net::UnixListener::from_std(listener)
+    },
+    tokio::net::UnixStream::from_std,
     tokio::net::UnixStream,
 );
 
-/// Turns a `Multiaddr` containing a single `Unix` component into a path.
+/// The address of a Unix domain socket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum SocketAddress {
+    /// A socket bound to a path in the file system.
+    Path(PathBuf),
+    /// A socket in the abstract namespace, independent of the file system.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Abstract(Vec<u8>),
+}
+
+impl SocketAddress {
+    fn to_socket_addr(&self) -> io::Result<SocketAddr> {
+        match self {
+            SocketAddress::Path(path) => SocketAddr::from_pathname(path),
+            #[cfg(target_os = "linux")]
+            SocketAddress::Abstract(name) => {
+                <SocketAddr as std::os::linux::net::SocketAddrExt>::from_abstract_name(name)
+            }
+            #[cfg(target_os = "android")]
+            SocketAddress::Abstract(name) => {
+                <SocketAddr as std::os::android::net::SocketAddrExt>::from_abstract_name(name)
+            }
+        }
+    }
+}
+
+/// Turns a `Multiaddr` containing a single `Unix` component into a socket address.
 ///
 /// Also returns an error if the path is not absolute, as we don't want to dial/listen on relative
-/// paths.
+/// paths. On Linux and Android, a path starting with `@` denotes a name in the abstract namespace.
 // This type of logic should probably be moved into the multiaddr package
-fn multiaddr_to_path(addr: &Multiaddr) -> Result<PathBuf, ()> {
+fn multiaddr_to_address(addr: &Multiaddr) -> Result<SocketAddress, ()> {
     let mut protocols = addr.iter();
-    match protocols.next() {
+    let address = match protocols.next() {
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        Some(Protocol::Unix(ref path)) if path.starts_with('@') => {
+            SocketAddress::Abstract(path.as_bytes()[1..].to_vec())
+        }
         Some(Protocol::Unix(ref path)) => {
             let path = PathBuf::from(path.as_ref());
             if !path.is_absolute() {
                 return Err(());
             }
-            match protocols.next() {
-                None | Some(Protocol::P2p(_)) => Ok(path),
-                Some(_) => Err(()),
-            }
+            SocketAddress::Path(path)
         }
-        _ => Err(()),
+        _ => return Err(()),
+    };
+    match protocols.next() {
+        None | Some(Protocol::P2p(_)) => Ok(address),
+        Some(_) => Err(()),
     }
 }
 
 #[cfg(all(test, feature = "async-std"))]
 mod tests {
-    use super::{multiaddr_to_path, UdsConfig};
+    use super::{multiaddr_to_address, SocketAddress, UdsConfig};
     use futures::{channel::oneshot, prelude::*};
     use libp2p_core::{
         multiaddr::{Multiaddr, Protocol},
@@ -266,18 +358,23 @@ mod tests {
     use std::{borrow::Cow, path::Path};
 
     #[test]
-    fn multiaddr_to_path_conversion() {
+    fn multiaddr_to_address_conversion() {
         assert!(
-            multiaddr_to_path(&"/ip4/127.0.0.1/udp/1234".parse::<Multiaddr>().unwrap()).is_err()
+            multiaddr_to_address(&"/ip4/127.0.0.1/udp/1234".parse::<Multiaddr>().unwrap()).is_err()
         );
 
         assert_eq!(
-            multiaddr_to_path(&Multiaddr::from(Protocol::Unix("/tmp/foo".into()))),
-            Ok(Path::new("/tmp/foo").to_owned())
+            multiaddr_to_address(&Multiaddr::from(Protocol::Unix("/tmp/foo".into()))),
+            Ok(SocketAddress::Path(Path::new("/tmp/foo").to_owned()))
         );
         assert_eq!(
-            multiaddr_to_path(&Multiaddr::from(Protocol::Unix("/home/bar/baz".into()))),
-            Ok(Path::new("/home/bar/baz").to_owned())
+            multiaddr_to_address(&Multiaddr::from(Protocol::Unix("/home/bar/baz".into()))),
+            Ok(SocketAddress::Path(Path::new("/home/bar/baz").to_owned()))
+        );
+        #[cfg(target_os = "linux")]
+        assert_eq!(
+            multiaddr_to_address(&Multiaddr::from(Protocol::Unix("@foo".into()))),
+            Ok(SocketAddress::Abstract(b"foo".to_vec()))
         );
     }
 
@@ -323,6 +420,53 @@ mod tests {
         });
     }
 
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn communicating_over_abstract_socket() {
+        let addr = Multiaddr::from(Protocol::Unix(Cow::Owned(format!(
+            "@libp2p-uds-{}",
+            rand_suffix()
+        ))));
+        let mut listener = UdsConfig::new().boxed();
+        listener
+            .listen_on(ListenerId::next(), addr.clone())
+            .unwrap();
+
+        async_std::task::block_on(async move {
+            let listen_addr = listener
+                .select_next_some()
+                .await
+                .into_new_address()
+                .expect("listen address");
+            assert_eq!(listen_addr, addr);
+
+            let mut socket = UdsConfig::new().dial(addr).unwrap().await.unwrap();
+            socket.write_all(&[1, 2, 3]).await.unwrap();
+
+            let (sock, _addr) = listener
+                .select_next_some()
+                .await
+                .into_incoming()
+                .expect("incoming stream");
+            let mut sock = sock.await.unwrap();
+            let mut buf = [0u8; 3];
+            sock.read_exact(&mut buf).await.unwrap();
+            assert_eq!(buf, [1, 2, 3]);
+            assert_eq!(
+                crate::peer_credentials(&sock).unwrap().pid,
+                Some(std::process::id() as i32)
+            );
+        });
+    }
+
+    #[cfg(target_os = "linux")]
+    fn rand_suffix() -> u128 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos()
+    }
+
     #[test]
     #[ignore] // TODO: for the moment unix addresses fail to parse
     fn larger_addr_denied() {
@@ -19,6 +19,8 @@
 // DEALINGS IN THE SOFTWARE.
 
 use rand::{distributions::DistString, CryptoRng, Rng};
+#[cfg(feature = "pem")]
+use std::{fs, io, path::Path};
 use webrtc::peer_connection::certificate::RTCCertificate;
 
 use crate::tokio::fingerprint::Fingerprint;
@@ -79,6 +81,33 @@ impl Certificate {
         self.inner.serialize_pem()
     }
 
+    /// Loads the certificate stored in PEM format at `path`, or generates a new one and stores
+    /// it there if the file does not exist.
+    ///
+    /// Keeps the certhash of a node stable across restarts, so that remotes which learned its
+    /// addresses, e.g. browsers, can still connect.
+    #[cfg(feature = "pem")]
+    pub fn load_or_generate(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        match std::fs::read_to_string(path) {
+            Ok(pem) => Self::from_pem(&pem),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let certificate = Self::generate(&mut rand::thread_rng())?;
+                certificate.persist(path)?;
+                Ok(certificate)
+            }
+            Err(e) => Err(Kind::Io(e).into()),
+        }
+    }
+
+    /// Stores the certificate (including the private key) in PEM format at `path`.
+    ///
+    /// The file is replaced atomically and, on unix, only readable by its owner.
+    #[cfg(feature = "pem")]
+    pub fn persist(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        write_atomic(path.as_ref(), self.serialize_pem().as_bytes()).map_err(|e| Kind::Io(e).into())
+    }
+
     /// Extract the [`RTCCertificate`] from this wrapper.
     ///
     /// This function is `pub(crate)` to avoid leaking the `webrtc` dependency to our users.
@@ -87,6 +116,26 @@ impl Certificate {
     }
 }
 
+/// Writes `contents` to a temporary file next to `path` and renames it to `path`.
+#[cfg(feature = "pem")]
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp)?;
+    io::Write::write_all(&mut file, contents)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
 #[derive(thiserror::Error, Debug)]
 #[error("Failed to generate certificate")]
 pub struct Error(#[from] Kind);
@@ -95,6 +144,9 @@ pub struct Error(#[from] Kind);
 enum Kind {
     #[error(transparent)]
     InvalidPEM(#[from] webrtc::Error),
+    #[cfg(feature = "pem")]
+    #[error(transparent)]
+    Io(#[from] io::Error),
 }
 
 #[cfg(all(test, feature = "pem"))]
@@ -111,4 +163,38 @@ mod test {
 
         assert_eq!(loaded_cert, cert)
     }
+
+    #[test]
+    fn test_certificate_load_or_generate() {
+        let path = std::env::temp_dir().join(format!(
+            "libp2p-webrtc-certificate-{}.pem",
+            rand::random::<u64>()
+        ));
+
+        let cert = Certificate::load_or_generate(&path).unwrap();
+        let loaded_cert = Certificate::load_or_generate(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded_cert, cert)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_certificate_persist_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!(
+            "libp2p-webrtc-certificate-{}.pem",
+            rand::random::<u64>()
+        ));
+
+        Certificate::generate(&mut thread_rng())
+            .unwrap()
+            .persist(&path)
+            .unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(mode & 0o777, 0o600);
+    }
 }
@@ -40,7 +40,7 @@ use std::{
     task::{Context, Poll},
 };
 
-use crate::tokio::{error::Error, stream, stream::Stream};
+use crate::tokio::{error::Error, stats::StatsHandle, stream, stream::Stream};
 
 /// Maximum number of unprocessed data channels.
 /// See [`Connection::poll_inbound`].
@@ -90,6 +90,13 @@ impl Connection {
         }
     }
 
+    /// Returns a handle for polling the statistics of this connection.
+    ///
+    /// See [`StatsHandle`] for details.
+    pub fn stats_handle(&self) -> StatsHandle {
+        StatsHandle::new(self.peer_conn.clone())
+    }
+
     /// Registers a handler for incoming data channels.
     ///
     /// NOTE: `mpsc::Sender` is wrapped in `Arc` because cloning a raw sender would make the channel
@@ -24,6 +24,7 @@ mod error;
 mod fingerprint;
 mod req_res_chan;
 mod sdp;
+mod stats;
 mod stream;
 mod transport;
 mod udp_mux;
@@ -33,4 +34,8 @@ pub use certificate::Certificate;
 pub use connection::Connection;
 pub use error::Error;
 pub use fingerprint::Fingerprint;
-pub use transport::Transport;
+pub use libp2p_webrtc_utils::stats::{
+    Candidate, CandidateKind, CandidatePair, ConnectionStats, DataChannelStats,
+};
+pub use stats::StatsHandle;
+pub use transport::{CertificateRotation, Transport};
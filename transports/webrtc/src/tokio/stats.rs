@@ -0,0 +1,112 @@
+// Copyright 2022 Parity Technologies (UK) Ltd.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::lock::Mutex as FutMutex;
+use libp2p_webrtc_utils::stats::{
+    Candidate, CandidateKind, CandidatePair, ConnectionStats, DataChannelStats,
+};
+use webrtc::ice::candidate::CandidateType;
+use webrtc::peer_connection::RTCPeerConnection;
+use webrtc::stats::{ICECandidateStats, StatsReportType};
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// A cloneable handle for polling the statistics of a [`Connection`](crate::tokio::Connection).
+///
+/// The handle remains valid after the connection has been handed to the
+/// [`Swarm`](https://docs.rs/libp2p-swarm), allowing an application to retain it (e.g. via
+/// [`Transport::map`](libp2p_core::Transport::map)) and poll it periodically.
+#[derive(Clone)]
+pub struct StatsHandle {
+    peer_conn: Arc<FutMutex<RTCPeerConnection>>,
+    /// Cumulative `(sent, received)` byte counts at the time of the previous snapshot.
+    last_totals: Arc<Mutex<(u64, u64)>>,
+}
+
+impl StatsHandle {
+    pub(crate) fn new(peer_conn: Arc<FutMutex<RTCPeerConnection>>) -> Self {
+        Self {
+            peer_conn,
+            last_totals: Default::default(),
+        }
+    }
+
+    /// Takes a snapshot of the connection's statistics.
+    ///
+    /// [`ConnectionStats::new_bytes_sent`] and [`ConnectionStats::new_bytes_received`] are
+    /// relative to the previous snapshot taken through this handle or any of its clones.
+    pub async fn stats(&self) -> ConnectionStats {
+        let report = self.peer_conn.lock().await.get_stats().await;
+
+        let mut stats = ConnectionStats::default();
+        let mut totals = (0, 0);
+
+        for s in report.reports.values() {
+            match s {
+                StatsReportType::CandidatePair(pair) if pair.nominated => {
+                    totals = (pair.bytes_sent, pair.bytes_received);
+                    stats.rtt = Some(Duration::from_secs_f64(pair.current_round_trip_time));
+
+                    let local = match report.reports.get(&pair.local_candidate_id) {
+                        Some(StatsReportType::LocalCandidate(c)) => candidate(c),
+                        _ => continue,
+                    };
+                    let remote = match report.reports.get(&pair.remote_candidate_id) {
+                        Some(StatsReportType::RemoteCandidate(c)) => candidate(c),
+                        _ => continue,
+                    };
+                    stats.selected_candidate_pair = Some(CandidatePair { local, remote });
+                }
+                StatsReportType::DataChannel(channel) => {
+                    stats.data_channels.push(DataChannelStats {
+                        id: channel.data_channel_identifier,
+                        bytes_sent: channel.bytes_sent as u64,
+                        bytes_received: channel.bytes_received as u64,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let mut last_totals = self.last_totals.lock().expect("not to be poisoned");
+        stats.new_bytes_sent = totals.0.saturating_sub(last_totals.0);
+        stats.new_bytes_received = totals.1.saturating_sub(last_totals.1);
+        *last_totals = totals;
+
+        stats
+    }
+}
+
+fn candidate(stats: &ICECandidateStats) -> Candidate {
+    let kind = match stats.candidate_type {
+        CandidateType::Host => CandidateKind::Host,
+        CandidateType::ServerReflexive => CandidateKind::ServerReflexive,
+        CandidateType::PeerReflexive => CandidateKind::PeerReflexive,
+        CandidateType::Relay => CandidateKind::Relay,
+        CandidateType::Unspecified => CandidateKind::Unknown,
+    };
+
+    Candidate {
+        address: stats.ip.clone(),
+        port: stats.port,
+        kind,
+    }
+}
@@ -19,6 +19,7 @@
 // DEALINGS IN THE SOFTWARE.
 
 use futures::{future::BoxFuture, prelude::*, stream::SelectAll};
+use futures_timer::Delay;
 use if_watch::{tokio::IfWatcher, IfEvent};
 use libp2p_core::{
     multiaddr::{Multiaddr, Protocol},
@@ -28,12 +29,16 @@ use libp2p_identity as identity;
 use libp2p_identity::PeerId;
 use webrtc::peer_connection::configuration::RTCConfiguration;
 
+use std::collections::{HashSet, VecDeque};
 use std::net::IpAddr;
+#[cfg(feature = "pem")]
+use std::path::PathBuf;
 use std::{
     io,
     net::SocketAddr,
     pin::Pin,
     task::{Context, Poll, Waker},
+    time::Duration,
 };
 
 use crate::tokio::{
@@ -51,6 +56,8 @@ pub struct Transport {
     config: Config,
     /// All the active listeners.
     listeners: SelectAll<ListenStream>,
+    /// Rotates the certificate, if enabled.
+    rotation: Option<Rotation>,
 }
 
 impl Transport {
@@ -70,6 +77,117 @@ impl Transport {
         Self {
             config: Config::new(id_keys, certificate),
             listeners: SelectAll::new(),
+            rotation: None,
+        }
+    }
+
+    /// Rotates the certificate according to the given schedule.
+    ///
+    /// Connections established before a rotation are not affected.
+    pub fn with_certificate_rotation(mut self, rotation: CertificateRotation) -> Self {
+        self.rotation = Some(Rotation::new(rotation));
+        self
+    }
+
+    /// Advances the certificate rotation, if due.
+    fn poll_rotation(&mut self, cx: &mut Context<'_>) {
+        let Some(rotation) = self.rotation.as_mut() else {
+            return;
+        };
+        if rotation.timer.poll_unpin(cx).is_pending() {
+            return;
+        }
+
+        match rotation.next.take() {
+            None => {
+                let next = Certificate::generate(&mut rand::thread_rng())
+                    .expect("generating a certificate to succeed");
+                tracing::debug!(fingerprint=?next.fingerprint(), "Announcing next certificate");
+
+                self.config.next_fingerprint = Some(next.fingerprint());
+                rotation.next = Some(next);
+                rotation.timer.reset(rotation.schedule.overlap);
+            }
+            Some(next) => {
+                tracing::debug!(fingerprint=?next.fingerprint(), "Rotating certificate");
+
+                #[cfg(feature = "pem")]
+                if let Some(path) = rotation.schedule.path.as_ref() {
+                    if let Err(error) = next.persist(path) {
+                        tracing::warn!(path=%path.display(), "Failed to persist certificate: {error}");
+                    }
+                }
+
+                self.config = Config::new(self.config.id_keys.clone(), next);
+                rotation
+                    .timer
+                    .reset(rotation.schedule.interval - rotation.schedule.overlap);
+            }
+        }
+
+        for listener in self.listeners.iter_mut() {
+            listener.set_config(self.config.clone());
+        }
+    }
+}
+
+/// Schedule for rotating the certificate of a [`Transport`], see
+/// [`Transport::with_certificate_rotation`].
+///
+/// The certificate is replaced by a newly generated one every `interval`. During the `overlap`
+/// before each rotation, the listen addresses carry the certhash of the next certificate in
+/// addition to the current one, so that remotes learning them in time, e.g. browsers, can
+/// still connect after the rotation.
+#[derive(Debug, Clone)]
+pub struct CertificateRotation {
+    interval: Duration,
+    overlap: Duration,
+    #[cfg(feature = "pem")]
+    path: Option<PathBuf>,
+}
+
+impl CertificateRotation {
+    /// Creates a new schedule rotating the certificate every `interval`, announcing the next
+    /// certificate `overlap` ahead.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `overlap` exceeds `interval`.
+    pub fn new(interval: Duration, overlap: Duration) -> Self {
+        assert!(overlap <= interval, "overlap must not exceed interval");
+
+        Self {
+            interval,
+            overlap,
+            #[cfg(feature = "pem")]
+            path: None,
+        }
+    }
+
+    /// Stores each new certificate in PEM format at `path` once it is in use, so that it can be
+    /// restored via [`Certificate::load_or_generate`] after a restart.
+    #[cfg(feature = "pem")]
+    pub fn persist(mut self, path: impl Into<PathBuf>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+}
+
+/// State of a [`CertificateRotation`].
+struct Rotation {
+    schedule: CertificateRotation,
+    /// The next certificate, once announced.
+    next: Option<Certificate>,
+    /// Fires once the next certificate should be announced, or be rotated to if announced.
+    timer: Delay,
+}
+
+impl Rotation {
+    fn new(schedule: CertificateRotation) -> Self {
+        Self {
+            timer: Delay::new(schedule.interval - schedule.overlap),
+            next: None,
+            schedule,
         }
     }
 }
@@ -112,6 +230,8 @@ impl libp2p_core::Transport for Transport {
         mut self: Pin<&mut Self>,
         cx: &mut Context<'_>,
     ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        self.poll_rotation(cx);
+
         match self.listeners.poll_next_unpin(cx) {
             Poll::Ready(Some(ev)) => Poll::Ready(ev),
             _ => Poll::Pending,
@@ -196,11 +316,15 @@ struct ListenStream {
     /// `None` if the socket is only listening on a single interface.
     if_watcher: Option<IfWatcher>,
 
-    /// Pending event to reported.
-    pending_event: Option<<Self as Stream>::Item>,
+    /// The IP addresses whose listen addresses have been reported.
+    addresses: HashSet<IpAddr>,
 
-    /// The stream must be awaken after it has been closed to deliver the last event.
-    close_listener_waker: Option<Waker>,
+    /// Pending events to be reported.
+    pending_events: VecDeque<<Self as Stream>::Item>,
+
+    /// The stream must be awaken after it has been closed or its certificate changed to deliver
+    /// the pending events.
+    waker: Option<Waker>,
 }
 
 impl ListenStream {
@@ -208,30 +332,61 @@ impl ListenStream {
     fn new(listener_id: ListenerId, config: Config, udp_mux: UDPMuxNewAddr) -> io::Result<Self> {
         let listen_addr = udp_mux.listen_addr();
 
-        let if_watcher;
-        let pending_event;
-        if listen_addr.ip().is_unspecified() {
-            if_watcher = Some(IfWatcher::new()?);
-            pending_event = None;
-        } else {
-            if_watcher = None;
-            let ma = socketaddr_to_multiaddr(&listen_addr, Some(config.fingerprint));
-            pending_event = Some(TransportEvent::NewAddress {
-                listener_id,
-                listen_addr: ma,
-            })
-        }
-
-        Ok(ListenStream {
+        let mut listener = ListenStream {
             listener_id,
             listen_addr,
             config,
             udp_mux,
             report_closed: None,
-            if_watcher,
-            pending_event,
-            close_listener_waker: None,
-        })
+            if_watcher: None,
+            addresses: HashSet::new(),
+            pending_events: VecDeque::new(),
+            waker: None,
+        };
+
+        if listen_addr.ip().is_unspecified() {
+            listener.if_watcher = Some(IfWatcher::new()?);
+        } else {
+            let ma = listener.listen_multiaddress(listen_addr.ip());
+            listener.addresses.insert(listen_addr.ip());
+            listener
+                .pending_events
+                .push_back(TransportEvent::NewAddress {
+                    listener_id,
+                    listen_addr: ma,
+                });
+        }
+
+        Ok(listener)
+    }
+
+    /// Replaces the config after a certificate rotation and reports the listen addresses with
+    /// the new certhashes.
+    fn set_config(&mut self, config: Config) {
+        let ips = self.addresses.iter().copied().collect::<Vec<_>>();
+        let expired = ips
+            .iter()
+            .map(|ip| self.listen_multiaddress(*ip))
+            .collect::<Vec<_>>();
+        self.config = config;
+
+        for (ip, expired) in ips.into_iter().zip(expired) {
+            let listen_addr = self.listen_multiaddress(ip);
+            self.pending_events.extend([
+                TransportEvent::AddressExpired {
+                    listener_id: self.listener_id,
+                    listen_addr: expired,
+                },
+                TransportEvent::NewAddress {
+                    listener_id: self.listener_id,
+                    listen_addr,
+                },
+            ]);
+        }
+
+        if let Some(waker) = self.waker.take() {
+            waker.wake();
+        }
     }
 
     /// Report the listener as closed in a [`TransportEvent::ListenerClosed`] and
@@ -249,7 +404,7 @@ impl ListenStream {
                     }));
 
                 // Wake the stream to deliver the last event.
-                if let Some(waker) = self.close_listener_waker.take() {
+                if let Some(waker) = self.waker.take() {
                     waker.wake();
                 }
             }
@@ -268,6 +423,7 @@ impl ListenStream {
                     if self.listen_addr.is_ipv4() == ip.is_ipv4()
                         || self.listen_addr.is_ipv6() == ip.is_ipv6()
                     {
+                        self.addresses.insert(ip);
                         return Poll::Ready(TransportEvent::NewAddress {
                             listener_id: self.listener_id,
                             listen_addr: self.listen_multiaddress(ip),
@@ -279,6 +435,7 @@ impl ListenStream {
                     if self.listen_addr.is_ipv4() == ip.is_ipv4()
                         || self.listen_addr.is_ipv6() == ip.is_ipv6()
                     {
+                        self.addresses.remove(&ip);
                         return Poll::Ready(TransportEvent::AddressExpired {
                             listener_id: self.listener_id,
                             listen_addr: self.listen_multiaddress(ip),
@@ -301,7 +458,7 @@ impl ListenStream {
     fn listen_multiaddress(&self, ip: IpAddr) -> Multiaddr {
         let socket_addr = SocketAddr::new(ip, self.listen_addr.port());
 
-        socketaddr_to_multiaddr(&socket_addr, Some(self.config.fingerprint))
+        socketaddr_to_multiaddr(&socket_addr, &self.config.certhashes())
     }
 }
 
@@ -310,7 +467,7 @@ impl Stream for ListenStream {
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
         loop {
-            if let Some(event) = self.pending_event.take() {
+            if let Some(event) = self.pending_events.pop_front() {
                 return Poll::Ready(Some(event));
             }
 
@@ -329,8 +486,8 @@ impl Stream for ListenStream {
             match self.udp_mux.poll(cx) {
                 Poll::Ready(UDPMuxEvent::NewAddr(new_addr)) => {
                     let local_addr =
-                        socketaddr_to_multiaddr(&self.listen_addr, Some(self.config.fingerprint));
-                    let send_back_addr = socketaddr_to_multiaddr(&new_addr.addr, None);
+                        socketaddr_to_multiaddr(&self.listen_addr, &[self.config.fingerprint]);
+                    let send_back_addr = socketaddr_to_multiaddr(&new_addr.addr, &[]);
 
                     let upgrade = upgrade::inbound(
                         new_addr.addr,
@@ -356,7 +513,7 @@ impl Stream for ListenStream {
                 Poll::Pending => {}
             }
 
-            self.close_listener_waker = Some(cx.waker().clone());
+            self.waker = Some(cx.waker().clone());
 
             return Poll::Pending;
         }
@@ -368,6 +525,8 @@ impl Stream for ListenStream {
 struct Config {
     inner: RTCConfiguration,
     fingerprint: Fingerprint,
+    /// The fingerprint of the next certificate, announced ahead of a rotation.
+    next_fingerprint: Option<Fingerprint>,
     id_keys: identity::Keypair,
 }
 
@@ -383,22 +542,28 @@ impl Config {
                 ..RTCConfiguration::default()
             },
             fingerprint,
+            next_fingerprint: None,
         }
     }
+
+    /// The certhashes of the listen addresses, the current one first.
+    fn certhashes(&self) -> Vec<Fingerprint> {
+        std::iter::once(self.fingerprint)
+            .chain(self.next_fingerprint)
+            .collect()
+    }
 }
 
 /// Turns an IP address and port into the corresponding WebRTC multiaddr.
-fn socketaddr_to_multiaddr(socket_addr: &SocketAddr, certhash: Option<Fingerprint>) -> Multiaddr {
+fn socketaddr_to_multiaddr(socket_addr: &SocketAddr, certhashes: &[Fingerprint]) -> Multiaddr {
     let addr = Multiaddr::empty()
         .with(socket_addr.ip().into())
         .with(Protocol::Udp(socket_addr.port()))
         .with(Protocol::WebRTCDirect);
 
-    if let Some(fp) = certhash {
-        return addr.with(Protocol::Certhash(fp.to_multihash()));
-    }
-
-    addr
+    certhashes.iter().fold(addr, |addr, fp| {
+        addr.with(Protocol::Certhash(fp.to_multihash()))
+    })
 }
 
 /// Parse the given [`Multiaddr`] into a [`SocketAddr`] for listening.
@@ -551,4 +716,72 @@ mod tests {
             assert!(transport.listeners.is_empty());
         }
     }
+
+    #[tokio::test]
+    async fn certificate_rotation() {
+        let id_keys = identity::Keypair::generate_ed25519();
+        let mut transport =
+            Transport::new(id_keys, Certificate::generate(&mut thread_rng()).unwrap())
+                .with_certificate_rotation(CertificateRotation::new(
+                    Duration::from_millis(300),
+                    Duration::from_millis(100),
+                ));
+        transport
+            .listen_on(
+                ListenerId::next(),
+                "/ip4/127.0.0.1/udp/0/webrtc-direct".parse().unwrap(),
+            )
+            .unwrap();
+
+        let certhashes = |addr: &Multiaddr| {
+            addr.iter()
+                .filter_map(|p| match p {
+                    Protocol::Certhash(hash) => Some(hash),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let TransportEvent::NewAddress { listen_addr, .. } =
+            poll_fn(|cx| Pin::new(&mut transport).as_mut().poll(cx)).await
+        else {
+            panic!("Expected new address");
+        };
+        let initial = certhashes(&listen_addr);
+        assert_eq!(initial.len(), 1);
+
+        // The next certificate is announced alongside the current one.
+        let TransportEvent::AddressExpired {
+            listen_addr: expired,
+            ..
+        } = poll_fn(|cx| Pin::new(&mut transport).as_mut().poll(cx)).await
+        else {
+            panic!("Expected expired address");
+        };
+        assert_eq!(expired, listen_addr);
+        let TransportEvent::NewAddress { listen_addr, .. } =
+            poll_fn(|cx| Pin::new(&mut transport).as_mut().poll(cx)).await
+        else {
+            panic!("Expected new address");
+        };
+        let announced = certhashes(&listen_addr);
+        assert_eq!(announced.len(), 2);
+        assert_eq!(announced[0], initial[0]);
+
+        // Only the next certificate remains after the rotation.
+        let TransportEvent::AddressExpired {
+            listen_addr: expired,
+            ..
+        } = poll_fn(|cx| Pin::new(&mut transport).as_mut().poll(cx)).await
+        else {
+            panic!("Expected expired address");
+        };
+        assert_eq!(expired, listen_addr);
+        let TransportEvent::NewAddress { listen_addr, .. } =
+            poll_fn(|cx| Pin::new(&mut transport).as_mut().poll(cx)).await
+        else {
+            panic!("Expected new address");
+        };
+        assert_eq!(certhashes(&listen_addr), vec![announced[1]]);
+    }
 }
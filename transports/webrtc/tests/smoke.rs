@@ -53,6 +53,42 @@ async fn smoke() {
     assert_eq!(b_connected, a_peer_id);
 }
 
+#[tokio::test]
+async fn connection_stats() {
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .try_init();
+
+    let (_, mut a_transport) = create_transport();
+    let (stats_tx, mut stats_rx) = mpsc::unbounded();
+    let keypair = generate_tls_keypair();
+    let mut b_transport = webrtc::tokio::Transport::new(
+        keypair,
+        webrtc::tokio::Certificate::generate(&mut thread_rng()).unwrap(),
+    )
+    .map(move |(p, c), _| {
+        stats_tx.unbounded_send(c.stats_handle()).unwrap();
+        (p, StreamMuxerBox::new(c))
+    })
+    .boxed();
+
+    let addr = start_listening(&mut a_transport, "/ip4/127.0.0.1/udp/0/webrtc-direct").await;
+    start_listening(&mut b_transport, "/ip4/127.0.0.1/udp/0/webrtc-direct").await;
+    let (_a, _b) = connect(&mut a_transport, &mut b_transport, addr).await;
+
+    let stats_handle = stats_rx.next().await.unwrap();
+    let stats = stats_handle.stats().await;
+
+    let pair = stats
+        .selected_candidate_pair
+        .expect("a selected candidate pair");
+    assert_eq!(pair.local.kind, webrtc::tokio::CandidateKind::Host);
+    assert_eq!(pair.remote.kind, webrtc::tokio::CandidateKind::Host);
+    // The DTLS and noise handshakes have been exchanged over the connection.
+    assert!(stats.new_bytes_sent > 0);
+    assert!(stats.new_bytes_received > 0);
+}
+
 // Note: This test should likely be ported to the muxer compliance test suite.
 #[test]
 fn concurrent_connections_and_streams_tokio() {
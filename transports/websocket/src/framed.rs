@@ -34,7 +34,7 @@ use soketto::{
     handshake,
 };
 use std::{collections::HashMap, ops::DerefMut, sync::Arc};
-use std::{fmt, io, mem, pin::Pin, task::Context, task::Poll};
+use std::{fmt, io, mem, pin::Pin, task::Context, task::Poll, time::Duration};
 use url::Url;
 
 /// Max. number of payload bytes of a single frame.
@@ -149,6 +149,10 @@ where
         self.transport.lock().remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.transport.lock().remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         self.do_dial(addr, Endpoint::Dialer)
     }
@@ -500,26 +504,40 @@ fn parse_ws_dial_addr<T>(addr: Multiaddr) -> Result<WsAddress, Error<T>> {
         }
     };
 
-    // Now consume the `Ws` / `Wss` protocol from the end of the address,
-    // preserving the trailing `P2p` protocol that identifies the remote,
-    // if any.
+    // Now consume the `Ws` / `Wss` protocol, possibly preceded by `Tls` and `Sni`,
+    // from the end of the address, preserving the trailing `P2p` protocol that
+    // identifies the remote, if any.
     let mut protocols = addr.clone();
     let mut p2p = None;
-    let (use_tls, path) = loop {
+    let (use_tls, sni, path) = loop {
         match protocols.pop() {
             p @ Some(Protocol::P2p(_)) => p2p = p,
-            Some(Protocol::Ws(path)) => break (false, path.into_owned()),
-            Some(Protocol::Wss(path)) => {
-                if dns_name.is_none() {
-                    tracing::debug!(address=%addr, "Missing DNS name in WSS address");
-                    return Err(Error::InvalidMultiaddr(addr));
+            Some(Protocol::Ws(path)) => match protocols.pop() {
+                Some(Protocol::Tls) => break (true, None, path.into_owned()),
+                Some(Protocol::Sni(name)) => match protocols.pop() {
+                    Some(Protocol::Tls) => {
+                        break (true, Some(tls::dns_name_ref(&name)?), path.into_owned())
+                    }
+                    _ => return Err(Error::InvalidMultiaddr(addr)),
+                },
+                Some(p) => {
+                    protocols.push(p);
+                    break (false, None, path.into_owned());
                 }
-                break (true, path.into_owned());
-            }
+                None => return Err(Error::InvalidMultiaddr(addr)),
+            },
+            Some(Protocol::Wss(path)) => break (true, None, path.into_owned()),
             _ => return Err(Error::InvalidMultiaddr(addr)),
         }
     };
 
+    // The server name indicated by `Sni` takes precedence over the DNS name of the host.
+    let dns_name = sni.or(dns_name);
+    if use_tls && dns_name.is_none() {
+        tracing::debug!(address=%addr, "Missing DNS name in WSS address");
+        return Err(Error::InvalidMultiaddr(addr));
+    }
+
     // The original address, stripped of the `/ws` and `/wss` protocols,
     // makes up the address for the inner TCP-based transport.
     let tcp_addr = match p2p {
@@ -767,3 +785,61 @@ where
             .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p_identity::PeerId;
+
+    fn parse(addr: &str) -> Result<WsAddress, Error<io::Error>> {
+        parse_ws_dial_addr(addr.parse().unwrap())
+    }
+
+    #[test]
+    fn tls_ws_is_equivalent_to_wss() {
+        let addr = parse("/dns4/example.com/tcp/443/tls/ws").unwrap();
+
+        assert!(addr.use_tls);
+        assert_eq!(addr.host_port, "example.com:443");
+        assert_eq!(
+            addr.dns_name,
+            Some(tls::dns_name_ref("example.com").unwrap())
+        );
+        assert_eq!(addr.tcp_addr, "/dns4/example.com/tcp/443".parse().unwrap());
+    }
+
+    #[test]
+    fn sni_overrides_server_name() {
+        let peer_id = PeerId::random();
+        let addr = parse(&format!(
+            "/ip4/192.0.2.1/tcp/443/tls/sni/relay.example.com/ws/p2p/{peer_id}"
+        ))
+        .unwrap();
+
+        assert!(addr.use_tls);
+        assert_eq!(addr.host_port, "192.0.2.1:443");
+        assert_eq!(
+            addr.dns_name,
+            Some(tls::dns_name_ref("relay.example.com").unwrap())
+        );
+        assert_eq!(
+            addr.tcp_addr,
+            format!("/ip4/192.0.2.1/tcp/443/p2p/{peer_id}")
+                .parse()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn tls_requires_server_name() {
+        assert!(matches!(
+            parse("/ip4/192.0.2.1/tcp/443/tls/ws"),
+            Err(Error::InvalidMultiaddr(_))
+        ));
+        assert!(matches!(
+            parse("/ip4/192.0.2.1/tcp/443/sni/example.com/ws"),
+            Err(Error::InvalidMultiaddr(_))
+        ));
+        assert!(!parse("/ip4/192.0.2.1/tcp/443/ws").unwrap().use_tls);
+    }
+}
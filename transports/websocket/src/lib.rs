@@ -41,6 +41,7 @@ use std::{
     io,
     pin::Pin,
     task::{Context, Poll},
+    time::Duration,
 };
 
 /// A Websocket transport.
@@ -53,6 +54,10 @@ use std::{
 ///
 /// If you don't need Secure Websocket's support, use a plain TCP transport as an inner transport.
 ///
+/// Secure Websocket addresses can be written as `/wss` or `/tls/ws`. With `/tls/sni/<name>/ws`,
+/// `<name>` is used as the server name of the TLS handshake instead of the DNS name of the
+/// address, which allows dialing IP addresses, e.g. `/ip4/192.0.2.1/tcp/443/tls/sni/example.com/ws`.
+///
 /// # Dependencies
 ///
 /// This transport requires the `zlib` shared library to be installed on the system.
@@ -202,6 +207,10 @@ where
         self.transport.remove_listener(id)
     }
 
+    fn remove_listener_graceful(&mut self, id: ListenerId, timeout: Duration) -> bool {
+        self.transport.remove_listener_graceful(id, timeout)
+    }
+
     fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
         self.transport.dial(addr)
     }
@@ -18,7 +18,16 @@
 // FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
 // DEALINGS IN THE SOFTWARE.
 
-use futures_rustls::{rustls, TlsAcceptor, TlsConnector};
+use futures_rustls::{
+    rustls::{
+        self,
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::WebPkiSupportedAlgorithms,
+        pki_types::{CertificateDer, ServerName, UnixTime},
+        DigitallySignedStruct, SignatureScheme,
+    },
+    TlsAcceptor, TlsConnector,
+};
 use std::{fmt, io, sync::Arc};
 
 /// TLS configuration.
@@ -64,6 +73,98 @@ impl Certificate {
     }
 }
 
+impl AsRef<[u8]> for Certificate {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+/// Verifies the certificates presented by a server when dialing a `/wss` or `/tls/ws` address,
+/// see [`Builder::certificate_verifier`].
+///
+/// Allows pinning certificates or validating them against private certificate authorities,
+/// e.g. to only accept a known certificate:
+///
+/// ```
+/// # use libp2p_websocket::tls::{Certificate, CertificateVerifier, Error};
+/// #[derive(Debug)]
+/// struct Pinned(Certificate);
+///
+/// impl CertificateVerifier for Pinned {
+///     fn verify(&self, end_entity: &Certificate, _: &[Certificate], _: &str) -> Result<(), Error> {
+///         if end_entity.as_ref() == self.0.as_ref() {
+///             Ok(())
+///         } else {
+///             Err(Error::Tls("certificate is not pinned".into()))
+///         }
+///     }
+/// }
+/// ```
+///
+/// The signatures of the TLS handshake are still verified with the public key of `end_entity`.
+pub trait CertificateVerifier: fmt::Debug + Send + Sync + 'static {
+    /// Verifies the end-entity certificate and its intermediates presented by the server
+    /// for the given server name.
+    fn verify(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &str,
+    ) -> Result<(), Error>;
+}
+
+/// Adapts a [`CertificateVerifier`] to the verifier of `rustls`.
+#[derive(Debug)]
+struct CustomServerCertVerifier {
+    verifier: Arc<dyn CertificateVerifier>,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl ServerCertVerifier for CustomServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer,
+        intermediates: &[CertificateDer],
+        server_name: &ServerName,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        let end_entity = Certificate(end_entity.clone().into_owned());
+        let intermediates = intermediates
+            .iter()
+            .map(|c| Certificate(c.clone().into_owned()))
+            .collect::<Vec<_>>();
+
+        self.verifier
+            .verify(&end_entity, &intermediates, &server_name.to_str())
+            .map_err(|e| rustls::Error::General(e.to_string()))?;
+
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
 impl Config {
     /// Create a new TLS configuration with the given server key and certificate chain.
     pub fn new<I>(key: PrivateKey, certs: I) -> Result<Self, Error>
@@ -93,6 +194,7 @@ impl Config {
     pub fn builder() -> Builder {
         Builder {
             client_root_store: client_root_store(),
+            verifier: None,
             server: None,
         }
     }
@@ -114,6 +216,7 @@ fn client_root_store() -> rustls::RootCertStore {
 /// TLS configuration builder.
 pub struct Builder {
     client_root_store: rustls::RootCertStore,
+    verifier: Option<Arc<dyn CertificateVerifier>>,
     server: Option<rustls::ServerConfig>,
 }
 
@@ -143,14 +246,32 @@ impl Builder {
         Ok(self)
     }
 
+    /// Verify the certificates of servers with the given [`CertificateVerifier`] instead of
+    /// the trust anchors, including those added via [`Builder::add_trust`].
+    pub fn certificate_verifier(&mut self, verifier: impl CertificateVerifier) -> &mut Self {
+        self.verifier = Some(Arc::new(verifier));
+        self
+    }
+
     /// Finish configuration.
     pub fn finish(self) -> Config {
         let provider = rustls::crypto::ring::default_provider();
-        let client = rustls::ClientConfig::builder_with_provider(provider.into())
+        let algorithms = provider.signature_verification_algorithms;
+        let builder = rustls::ClientConfig::builder_with_provider(provider.into())
             .with_safe_default_protocol_versions()
-            .unwrap()
-            .with_root_certificates(self.client_root_store)
-            .with_no_client_auth();
+            .unwrap();
+        let client = match self.verifier {
+            Some(verifier) => builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(CustomServerCertVerifier {
+                    verifier,
+                    algorithms,
+                }))
+                .with_no_client_auth(),
+            None => builder
+                .with_root_certificates(self.client_root_store)
+                .with_no_client_auth(),
+        };
 
         Config {
             client: Arc::new(client).into(),
@@ -0,0 +1,195 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use libp2p_core::muxing::{StreamMuxer, StreamMuxerEvent};
+use libp2p_core::upgrade::OutboundConnectionUpgrade;
+use libp2p_core::UpgradeInfo;
+use libp2p_identity::{Keypair, PeerId};
+use multihash::Multihash;
+use std::collections::HashSet;
+use std::fmt;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use wtransport::endpoint::endpoint_side::Client;
+use wtransport::VarInt;
+
+use crate::{Error, Stream};
+
+/// An opened WebTransport connection.
+pub struct Connection {
+    session: Arc<wtransport::Connection>,
+    /// The endpoint the session was established on, kept alive for the lifetime of the session.
+    _endpoint: wtransport::Endpoint<Client>,
+    outgoing: Option<BoxFuture<'static, Result<Stream, Error>>>,
+    incoming: Option<BoxFuture<'static, Result<Stream, Error>>>,
+    closed: bool,
+}
+
+impl Connection {
+    pub(crate) fn new(
+        session: wtransport::Connection,
+        endpoint: wtransport::Endpoint<Client>,
+    ) -> Self {
+        Connection {
+            session: Arc::new(session),
+            _endpoint: endpoint,
+            outgoing: None,
+            incoming: None,
+            closed: false,
+        }
+    }
+
+    /// Authenticates with the server
+    ///
+    /// This methods runs the security handshake as described
+    /// in the [spec][1]. It validates the certhashes and peer ID
+    /// of the server.
+    ///
+    /// [1]: https://github.com/libp2p/specs/tree/master/webtransport#security-handshake
+    pub(crate) async fn authenticate(
+        &mut self,
+        keypair: &Keypair,
+        remote_peer: Option<PeerId>,
+        certhashes: HashSet<Multihash<64>>,
+    ) -> Result<PeerId, Error> {
+        let stream = poll_fn(|cx| self.poll_create_bidirectional_stream(cx)).await?;
+        let mut noise = libp2p_noise::Config::new(keypair)?;
+
+        if !certhashes.is_empty() {
+            noise = noise.with_webtransport_certhashes(certhashes);
+        }
+
+        // We do not use `upgrade::apply_outbound` function because it uses
+        // `multistream_select` protocol, which is not used by WebTransport spec.
+        let info = noise.protocol_info().next().unwrap_or_default();
+        let (peer_id, _io) = noise.upgrade_outbound(stream, info).await?;
+
+        if let Some(expected_peer_id) = remote_peer {
+            if peer_id != expected_peer_id {
+                return Err(Error::UnknownRemotePeerId);
+            }
+        }
+
+        Ok(peer_id)
+    }
+
+    /// Initiates and polls the opening of an outgoing bidirectional stream.
+    fn poll_create_bidirectional_stream(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<Stream, Error>> {
+        let session = self.session.clone();
+        let fut = self.outgoing.get_or_insert_with(|| {
+            async move {
+                let (send, recv) = session.open_bi().await?.await?;
+                Ok(Stream::new(send, recv))
+            }
+            .boxed()
+        });
+        let result = ready!(fut.poll_unpin(cx));
+        self.outgoing = None;
+
+        Poll::Ready(result)
+    }
+
+    /// Polls for the next incoming bidirectional stream.
+    fn poll_incoming_bidirectional_streams(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<Stream, Error>> {
+        let session = self.session.clone();
+        let fut = self.incoming.get_or_insert_with(|| {
+            async move {
+                let (send, recv) = session.accept_bi().await?;
+                Ok(Stream::new(send, recv))
+            }
+            .boxed()
+        });
+        let result = ready!(fut.poll_unpin(cx));
+        self.incoming = None;
+
+        Poll::Ready(result)
+    }
+
+    /// Closes the session.
+    ///
+    /// This closes the streams also and they will return an error
+    /// when they will be used.
+    fn close_session(&mut self) {
+        if !self.closed {
+            self.session.close(VarInt::from_u32(0), b"");
+            self.closed = true;
+        }
+    }
+}
+
+impl fmt::Debug for Connection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connection")
+            .field("remote_address", &self.session.remote_address())
+            .field("closed", &self.closed)
+            .finish()
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.close_session();
+    }
+}
+
+/// WebTransport native multiplexing
+impl StreamMuxer for Connection {
+    type Substream = Stream;
+    type Error = Error;
+
+    fn poll_inbound(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        self.poll_incoming_bidirectional_streams(cx)
+    }
+
+    fn poll_outbound(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Self::Substream, Self::Error>> {
+        self.poll_create_bidirectional_stream(cx)
+    }
+
+    fn poll_close(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<(), Self::Error>> {
+        self.close_session();
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<StreamMuxerEvent, Self::Error>> {
+        Poll::Pending
+    }
+}
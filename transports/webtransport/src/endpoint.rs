@@ -0,0 +1,211 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use libp2p_identity::PeerId;
+use multiaddr::{Multiaddr, Protocol};
+use multihash::Multihash;
+use std::collections::HashSet;
+use wtransport::tls::Sha256Digest;
+
+use crate::Error;
+
+/// The multihash code of SHA-256, the only hash function WebTransport supports for certificate
+/// hashes.
+const SHA_256_CODE: u64 = 0x12;
+
+pub(crate) struct Endpoint {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) is_ipv6: bool,
+    pub(crate) certhashes: HashSet<Multihash<64>>,
+    pub(crate) remote_peer: Option<PeerId>,
+}
+
+impl Endpoint {
+    pub(crate) fn from_multiaddr(addr: &Multiaddr) -> Result<Self, Error> {
+        let mut host = None;
+        let mut port = None;
+        let mut found_quic = false;
+        let mut found_webtransport = false;
+        let mut certhashes = HashSet::new();
+        let mut remote_peer = None;
+        let mut is_ipv6 = false;
+
+        for proto in addr.iter() {
+            match proto {
+                Protocol::Ip4(addr) => {
+                    if host.is_some() {
+                        return Err(Error::InvalidMultiaddr("More than one host definitions"));
+                    }
+
+                    host = Some(addr.to_string());
+                }
+                Protocol::Ip6(addr) => {
+                    if host.is_some() {
+                        return Err(Error::InvalidMultiaddr("More than one host definitions"));
+                    }
+
+                    is_ipv6 = true;
+                    host = Some(addr.to_string());
+                }
+                Protocol::Dns(domain) | Protocol::Dns4(domain) | Protocol::Dns6(domain) => {
+                    if host.is_some() {
+                        return Err(Error::InvalidMultiaddr("More than one host definitions"));
+                    }
+
+                    host = Some(domain.to_string())
+                }
+                Protocol::Dnsaddr(_) => {
+                    return Err(Error::InvalidMultiaddr("/dnsaddr must be resolved first"));
+                }
+                Protocol::Udp(p) => {
+                    if port.is_some() {
+                        return Err(Error::InvalidMultiaddr("More than one port definitions"));
+                    }
+
+                    port = Some(p);
+                }
+                Protocol::QuicV1 => {
+                    if host.is_none() || port.is_none() {
+                        return Err(Error::InvalidMultiaddr(
+                            "No host and port definition before /quic-v1/webtransport",
+                        ));
+                    }
+
+                    found_quic = true;
+                }
+                Protocol::WebTransport => {
+                    if !found_quic {
+                        return Err(Error::InvalidMultiaddr(
+                            "/quic-v1 is not found before /webtransport",
+                        ));
+                    }
+
+                    found_webtransport = true;
+                }
+                Protocol::Certhash(hash) => {
+                    if !found_webtransport {
+                        return Err(Error::InvalidMultiaddr(
+                            "/certhashes must be after /quic-v1/webtransport",
+                        ));
+                    }
+
+                    certhashes.insert(hash);
+                }
+                Protocol::P2p(peer) => {
+                    if remote_peer.is_some() {
+                        return Err(Error::InvalidMultiaddr("More than one peer definitions"));
+                    }
+
+                    remote_peer = Some(peer);
+                }
+                _ => {}
+            }
+        }
+
+        if !found_quic || !found_webtransport {
+            return Err(Error::InvalidMultiaddr(
+                "Not a /quic-v1/webtransport multiaddr",
+            ));
+        }
+
+        let host = host.ok_or_else(|| Error::InvalidMultiaddr("Host is not defined"))?;
+        let port = port.ok_or_else(|| Error::InvalidMultiaddr("Port is not defined"))?;
+
+        Ok(Endpoint {
+            host,
+            port,
+            is_ipv6,
+            certhashes,
+            remote_peer,
+        })
+    }
+
+    pub(crate) fn url(&self) -> String {
+        let host = &self.host;
+        let port = self.port;
+
+        if self.is_ipv6 {
+            format!("https://[{host}]:{port}/.well-known/libp2p-webtransport?type=noise")
+        } else {
+            format!("https://{host}:{port}/.well-known/libp2p-webtransport?type=noise")
+        }
+    }
+
+    /// The SHA-256 certificate hashes to verify the server certificate against.
+    ///
+    /// Hashes of other hash functions are ignored.
+    pub(crate) fn certificate_digests(&self) -> Vec<Sha256Digest> {
+        self.certhashes
+            .iter()
+            .filter(|hash| hash.code() == SHA_256_CODE)
+            .filter_map(|hash| <[u8; 32]>::try_from(hash.digest()).ok())
+            .map(Sha256Digest::new)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn valid_webtransport_multiaddr() {
+        let addr = Multiaddr::from_str("/ip4/127.0.0.1/udp/44874/quic-v1/webtransport/certhash/uEiCaDd1Ca1A8IVJ3hsIxIyi11cwxaDKqzVrBkGJbKZU5ng/certhash/uEiDv-VGW8oXxui_G_Kqp-87YjvET-Hr2qYAMYPePJDcsjQ/p2p/12D3KooWR7EfNv5SLtgjMRjUwR8AvNu3hP4fLrtSa9fmHHXKYWNG").unwrap();
+        let endpoint = Endpoint::from_multiaddr(&addr).unwrap();
+
+        assert_eq!(endpoint.host, "127.0.0.1");
+        assert_eq!(endpoint.port, 44874);
+        assert_eq!(endpoint.certhashes.len(), 2);
+        assert_eq!(endpoint.certificate_digests().len(), 2);
+        assert_eq!(
+            endpoint.remote_peer.unwrap(),
+            PeerId::from_str("12D3KooWR7EfNv5SLtgjMRjUwR8AvNu3hP4fLrtSa9fmHHXKYWNG").unwrap()
+        );
+        assert_eq!(
+            endpoint.url(),
+            "https://127.0.0.1:44874/.well-known/libp2p-webtransport?type=noise"
+        );
+    }
+
+    #[test]
+    fn ipv6_webtransport() {
+        let addr = Multiaddr::from_str("/ip6/::1/udp/44874/quic-v1/webtransport/p2p/12D3KooWR7EfNv5SLtgjMRjUwR8AvNu3hP4fLrtSa9fmHHXKYWNG").unwrap();
+        let endpoint = Endpoint::from_multiaddr(&addr).unwrap();
+
+        assert_eq!(endpoint.host, "::1");
+        assert!(endpoint.certificate_digests().is_empty());
+        assert_eq!(
+            endpoint.url(),
+            "https://[::1]:44874/.well-known/libp2p-webtransport?type=noise"
+        );
+    }
+
+    #[test]
+    fn non_webtransport_multiaddr() {
+        let addr = Multiaddr::from_str("/ip4/127.0.0.1/udp/44874/quic-v1").unwrap();
+
+        assert!(matches!(
+            Endpoint::from_multiaddr(&addr),
+            Err(Error::InvalidMultiaddr(_))
+        ));
+    }
+}
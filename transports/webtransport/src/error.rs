@@ -0,0 +1,45 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+/// Errors that may happen on the [`Transport`](crate::Transport) or the
+/// [`Connection`](crate::Connection).
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("Invalid multiaddr: {0}")]
+    InvalidMultiaddr(&'static str),
+
+    #[error("Noise authentication failed")]
+    Noise(#[from] libp2p_noise::Error),
+
+    #[error("Failed to create WebTransport endpoint")]
+    Endpoint(#[source] std::io::Error),
+
+    #[error("Failed to establish WebTransport session")]
+    Connecting(#[from] wtransport::error::ConnectingError),
+
+    #[error("WebTransport connection failed")]
+    Connection(#[from] wtransport::error::ConnectionError),
+
+    #[error("Failed to open WebTransport stream")]
+    StreamOpening(#[from] wtransport::error::StreamOpeningError),
+
+    #[error("Unknown remote peer ID")]
+    UnknownRemotePeerId,
+}
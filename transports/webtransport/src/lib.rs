@@ -0,0 +1,36 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+//! Libp2p WebTransport client for native platforms, built on [wtransport](https://docs.rs/wtransport).
+//!
+//! The [`Transport`] dials `/quic-v1/webtransport` addresses, e.g. those of browser-facing
+//! listeners, verifying self-signed server certificates against the `/certhash` components of the
+//! address. Listening is not supported. The transport requires a `tokio` runtime.
+
+mod connection;
+mod endpoint;
+mod error;
+mod stream;
+mod transport;
+
+pub use self::connection::Connection;
+pub use self::error::Error;
+pub use self::stream::Stream;
+pub use self::transport::{Config, Transport};
@@ -0,0 +1,250 @@
+// Copyright 2024 Protocol Labs.
+//
+// Permission is hereby granted, free of charge, to any person obtaining a
+// copy of this software and associated documentation files (the "Software"),
+// to deal in the Software without restriction, including without limitation
+// the rights to use, copy, modify, merge, publish, distribute, sublicense,
+// and/or sell copies of the Software, and to permit persons to whom the
+// Software is furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in
+// all copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS
+// OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING
+// FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER
+// DEALINGS IN THE SOFTWARE.
+
+use futures::future::FutureExt;
+use libp2p_core::muxing::StreamMuxerBox;
+use libp2p_core::transport::{Boxed, ListenerId, Transport as _, TransportError, TransportEvent};
+use libp2p_identity::{Keypair, PeerId};
+use multiaddr::Multiaddr;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use wtransport::ClientConfig;
+
+use crate::endpoint::Endpoint;
+use crate::Connection;
+use crate::Error;
+
+/// Config for the [`Transport`].
+pub struct Config {
+    keypair: Keypair,
+}
+
+/// A WebTransport [`Transport`](libp2p_core::Transport) for native platforms.
+///
+/// Only dialing is supported. Must be polled within a `tokio` runtime.
+pub struct Transport {
+    config: Config,
+}
+
+impl Config {
+    /// Constructs a new configuration for the [`Transport`].
+    pub fn new(keypair: &Keypair) -> Self {
+        Config {
+            keypair: keypair.to_owned(),
+        }
+    }
+}
+
+impl Transport {
+    /// Constructs a new `Transport` with the given [`Config`].
+    pub fn new(config: Config) -> Transport {
+        Transport { config }
+    }
+
+    /// Wraps `Transport` in [`Boxed`] and makes it ready to be consumed by
+    /// SwarmBuilder.
+    pub fn boxed(self) -> Boxed<(PeerId, StreamMuxerBox)> {
+        self.map(|(peer_id, muxer), _| (peer_id, StreamMuxerBox::new(muxer)))
+            .boxed()
+    }
+}
+
+impl libp2p_core::Transport for Transport {
+    type Output = (PeerId, Connection);
+    type Error = Error;
+    type ListenerUpgrade = Pin<Box<dyn Future<Output = Result<Self::Output, Self::Error>> + Send>>;
+    type Dial = Pin<Box<dyn Future<Output = Result<Self::Output, Self::Error>> + Send>>;
+
+    fn listen_on(
+        &mut self,
+        _id: ListenerId,
+        addr: Multiaddr,
+    ) -> Result<(), TransportError<Self::Error>> {
+        Err(TransportError::MultiaddrNotSupported(addr))
+    }
+
+    fn remove_listener(&mut self, _id: ListenerId) -> bool {
+        false
+    }
+
+    fn dial(&mut self, addr: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let endpoint = Endpoint::from_multiaddr(&addr).map_err(|e| match e {
+            e @ Error::InvalidMultiaddr(_) => {
+                tracing::debug!("{}", e);
+                TransportError::MultiaddrNotSupported(addr)
+            }
+            e => TransportError::Other(e),
+        })?;
+
+        let client_config = if endpoint.certhashes.is_empty() {
+            // Endpoint has CA-signed TLS certificate.
+            ClientConfig::builder()
+                .with_bind_default()
+                .with_native_certs()
+                .build()
+        } else {
+            // Endpoint has self-signed TLS certificates.
+            ClientConfig::builder()
+                .with_bind_default()
+                .with_server_certificate_hashes(endpoint.certificate_digests())
+                .build()
+        };
+        let keypair = self.config.keypair.clone();
+
+        Ok(async move {
+            let client = wtransport::Endpoint::client(client_config).map_err(Error::Endpoint)?;
+            let session = client.connect(endpoint.url()).await?;
+
+            let mut session = Connection::new(session, client);
+            let peer_id = session
+                .authenticate(&keypair, endpoint.remote_peer, endpoint.certhashes)
+                .await?;
+            Ok((peer_id, session))
+        }
+        .boxed())
+    }
+
+    fn dial_as_listener(
+        &mut self,
+        addr: Multiaddr,
+    ) -> Result<Self::Dial, TransportError<Self::Error>> {
+        Err(TransportError::MultiaddrNotSupported(addr))
+    }
+
+    fn poll(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<TransportEvent<Self::ListenerUpgrade, Self::Error>> {
+        Poll::Pending
+    }
+
+    fn address_translation(&self, _listen: &Multiaddr, _observed: &Multiaddr) -> Option<Multiaddr> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Stream;
+    use futures::{AsyncReadExt, AsyncWriteExt};
+    use libp2p_core::muxing::StreamMuxerExt;
+    use libp2p_core::upgrade::InboundConnectionUpgrade;
+    use libp2p_core::UpgradeInfo;
+    use multiaddr::Protocol;
+    use multihash::Multihash;
+    use std::collections::HashSet;
+    use std::future::poll_fn;
+    use std::net::{Ipv4Addr, SocketAddr};
+    use wtransport::{Identity, ServerConfig};
+
+    /// Starts a server accepting WebTransport sessions, running the responder side of the
+    /// security handshake and echoing the first stream of each session.
+    ///
+    /// Returns the address of the server, without `/p2p`.
+    fn start_server(keypair: Keypair) -> Multiaddr {
+        let identity = Identity::self_signed(["localhost", "127.0.0.1"]).unwrap();
+        let digest = identity.certificate_chain().as_slice()[0].hash();
+        let certhash = Multihash::wrap(0x12, digest.as_ref()).unwrap();
+        let config = ServerConfig::builder()
+            .with_bind_address(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+            .with_identity(&identity)
+            .build();
+        let server = wtransport::Endpoint::server(config).unwrap();
+        let port = server.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            loop {
+                let incoming = server.accept().await;
+                let keypair = keypair.clone();
+                tokio::spawn(async move {
+                    let session = incoming.await?.accept().await?;
+
+                    let (send, recv) = session.accept_bi().await?;
+                    let noise = libp2p_noise::Config::new(&keypair)?
+                        .with_webtransport_certhashes(HashSet::from([certhash]));
+                    let info = noise.protocol_info().next().unwrap_or_default();
+                    noise.upgrade_inbound(Stream::new(send, recv), info).await?;
+
+                    let (send, recv) = session.accept_bi().await?;
+                    let mut stream = Stream::new(send, recv);
+                    let mut buf = Vec::new();
+                    stream.read_to_end(&mut buf).await?;
+                    stream.write_all(&buf).await?;
+                    stream.close().await?;
+
+                    // Keep the session open until the client closes it.
+                    session.closed().await;
+                    Ok::<_, Box<dyn std::error::Error + Send + Sync>>(())
+                });
+            }
+        });
+
+        Multiaddr::empty()
+            .with(Protocol::Ip4(Ipv4Addr::LOCALHOST))
+            .with(Protocol::Udp(port))
+            .with(Protocol::QuicV1)
+            .with(Protocol::WebTransport)
+            .with(Protocol::Certhash(certhash))
+    }
+
+    #[tokio::test]
+    async fn dial_authenticates_server_and_opens_streams() {
+        let server_keypair = Keypair::generate_ed25519();
+        let server_id = server_keypair.public().to_peer_id();
+        let addr = start_server(server_keypair).with(Protocol::P2p(server_id));
+
+        let mut transport = Transport::new(Config::new(&Keypair::generate_ed25519()));
+        let (peer_id, mut connection) = transport.dial(addr).unwrap().await.unwrap();
+        assert_eq!(peer_id, server_id);
+
+        let mut stream = poll_fn(|cx| connection.poll_outbound_unpin(cx))
+            .await
+            .unwrap();
+        stream.write_all(b"ping").await.unwrap();
+        stream.close().await.unwrap();
+        let mut buf = Vec::new();
+        stream.read_to_end(&mut buf).await.unwrap();
+        assert_eq!(buf, b"ping");
+    }
+
+    #[tokio::test]
+    async fn dial_rejects_unexpected_peer_id() {
+        let addr = start_server(Keypair::generate_ed25519()).with(Protocol::P2p(PeerId::random()));
+
+        let mut transport = Transport::new(Config::new(&Keypair::generate_ed25519()));
+        let error = transport.dial(addr).unwrap().await.unwrap_err();
+        assert!(matches!(error, Error::UnknownRemotePeerId), "{error:?}");
+    }
+
+    #[tokio::test]
+    async fn dial_rejects_unknown_certificate() {
+        let addr = start_server(Keypair::generate_ed25519())
+            .into_iter()
+            .filter(|p| !matches!(p, Protocol::Certhash(_)))
+            .chain([Protocol::Certhash(Multihash::wrap(0x12, &[0; 32]).unwrap())])
+            .collect::<Multiaddr>();
+
+        let mut transport = Transport::new(Config::new(&Keypair::generate_ed25519()));
+        let error = transport.dial(addr).unwrap().await.unwrap_err();
+        assert!(matches!(error, Error::Connecting(_)), "{error:?}");
+    }
+}